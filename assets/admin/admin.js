@@ -0,0 +1,50 @@
+// Read-only views over the admin endpoints; every request carries the token
+// from this tab's sessionStorage, never a cookie
+const PANELS = [
+  ["Boot report", "/api/admin/boot-report"],
+  ["Memory", "/api/admin/memory"],
+  ["Lanes", "/api/admin/lanes"],
+  ["CPU pool", "/api/admin/cpu-pool"],
+  ["Object pools", "/api/admin/object-pools"],
+  ["Anomalies", "/api/admin/anomalies"],
+  ["Rate limits", "/api/admin/rate-limits"],
+  ["Deprecations", "/api/admin/deprecations"],
+  ["Routes", "/api/admin/routes"],
+];
+
+const status = document.getElementById("status");
+const output = document.getElementById("output");
+const tokenInput = document.getElementById("token");
+
+async function load(title, path) {
+  const token = sessionStorage.getItem("adminToken");
+  if (!token) {
+    status.textContent = "Enter the admin token first.";
+    return;
+  }
+  status.textContent = `Loading ${title}…`;
+  try {
+    const response = await fetch(path, { headers: { Authorization: `Bearer ${token}` } });
+    const body = await response.json();
+    status.textContent = response.ok ? `${title} (${path})` : `${title}: ${body.error?.code ?? response.status}`;
+    output.textContent = JSON.stringify(response.ok ? body.data : body.error, null, 2);
+  } catch (err) {
+    status.textContent = `${title}: ${err}`;
+    output.textContent = "";
+  }
+}
+
+const nav = document.getElementById("panels");
+for (const [title, path] of PANELS) {
+  const button = document.createElement("button");
+  button.textContent = title;
+  button.addEventListener("click", () => load(title, path));
+  nav.appendChild(button);
+}
+
+tokenInput.value = sessionStorage.getItem("adminToken") ?? "";
+document.getElementById("token-form").addEventListener("submit", (event) => {
+  event.preventDefault();
+  sessionStorage.setItem("adminToken", tokenInput.value.trim());
+  load(...PANELS[0]);
+});
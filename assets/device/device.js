@@ -0,0 +1,88 @@
+// Looks up what a device asks for, then approves or denies it with the
+// signed-in user's `session` cookie; writes authenticated by the cookie need
+// the CSRF token, fetched first
+const lookupForm = document.getElementById("lookup-form");
+const decisionForm = document.getElementById("decision-form");
+const codeInput = document.getElementById("user-code");
+const clientId = document.getElementById("client-id");
+const scopes = document.getElementById("scopes");
+const status = document.getElementById("status");
+
+async function csrfToken() {
+  const response = await fetch("/api/auth/csrf", { credentials: "same-origin" });
+  if (response.status === 401) {
+    throw new Error("Sign in to the app first, then reload this page.");
+  }
+  const body = await response.json();
+  if (!response.ok) {
+    throw new Error(body.error?.message ?? `Error ${response.status}`);
+  }
+  return body.data;
+}
+
+async function lookUp() {
+  status.textContent = "Looking up…";
+  try {
+    const response = await fetch(`/api/oidc/device/${encodeURIComponent(codeInput.value)}`, { credentials: "same-origin" });
+    if (response.status === 401) {
+      throw new Error("Sign in to the app first, then reload this page.");
+    }
+    const body = await response.json();
+    if (!response.ok) {
+      throw new Error(body.error?.message ?? `Error ${response.status}`);
+    }
+    clientId.textContent = body.data.client_id;
+    scopes.textContent = body.data.scope.split(" ").join(", ");
+    status.textContent = "Only approve if you started this client and expect it to ask for this access.";
+    lookupForm.hidden = true;
+    decisionForm.hidden = false;
+  } catch (err) {
+    status.textContent = err.message;
+  }
+}
+
+lookupForm.addEventListener("submit", (event) => {
+  event.preventDefault();
+  lookUp();
+});
+
+document.getElementById("back").addEventListener("click", () => {
+  decisionForm.hidden = true;
+  lookupForm.hidden = false;
+  status.textContent = "";
+  codeInput.focus();
+});
+
+decisionForm.addEventListener("submit", async (event) => {
+  event.preventDefault();
+  const approve = event.submitter?.value !== "deny";
+  status.textContent = "Sending…";
+  try {
+    const csrf = await csrfToken();
+    const response = await fetch("/api/oidc/device", {
+      method: "POST",
+      credentials: "same-origin",
+      headers: { "Content-Type": "application/json", [csrf.header]: csrf.token },
+      body: JSON.stringify({ user_code: codeInput.value, approve }),
+    });
+    const body = await response.json();
+    if (!response.ok) {
+      status.textContent = body.error?.message ?? `Error ${response.status}`;
+      return;
+    }
+    const { client_id, approved } = body.data;
+    status.textContent = approved
+      ? `${client_id} is connected. You can return to your terminal.`
+      : `${client_id} was denied.`;
+    decisionForm.hidden = true;
+  } catch (err) {
+    status.textContent = err.message;
+  }
+});
+
+// Opened from `verification_uri_complete`: look the code up straight away,
+// but still wait for the user to approve
+codeInput.value = new URLSearchParams(location.search).get("user_code") ?? "";
+if (codeInput.value) {
+  lookUp();
+}
@@ -0,0 +1,10 @@
+// Swagger UI 5.17.14 (see LICENSE and NOTICE) over this server's own spec
+window.onload = function () {
+  window.ui = SwaggerUIBundle({
+    url: "/api/docs/openapi.json",
+    dom_id: "#swagger-ui",
+    deepLinking: true,
+    presets: [SwaggerUIBundle.presets.apis],
+    layout: "BaseLayout",
+  });
+};
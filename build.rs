@@ -0,0 +1,47 @@
+//! Brotli-compresses `assets/` into `$OUT_DIR/assets`, so the server embeds a
+//! precompressed copy of each file next to the original.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Formats that are compressed already
+const SKIP: &[&str] = &["png", "ico", "woff2"];
+
+fn main() -> io::Result<()> {
+    println!("cargo:rerun-if-changed=assets");
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").expect("cargo sets OUT_DIR")).join("assets");
+    // rust-embed wants the folder to exist even if nothing was compressed
+    fs::create_dir_all(&out_dir)?;
+    compress_dir(Path::new("assets"), &out_dir)
+}
+
+fn compress_dir(dir: &Path, out_dir: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let target = out_dir.join(path.file_name().expect("read_dir entries have a name"));
+        if path.is_dir() {
+            fs::create_dir_all(&target)?;
+            compress_dir(&path, &target)?;
+            continue;
+        }
+        let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+        if SKIP.contains(&extension) {
+            continue;
+        }
+
+        let original = fs::read(&path)?;
+        let mut compressed = Vec::new();
+        {
+            let mut writer = brotli::CompressorWriter::new(&mut compressed, 64 * 1024, 11, 22);
+            writer.write_all(&original)?;
+        }
+        // Only worth serving when it saves something
+        if compressed.len() < original.len() {
+            let mut name = target.into_os_string();
+            name.push(".br");
+            fs::write(name, compressed)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,1164 @@
+//! Typed client for the rust-boilerplate API.
+//!
+//! Generated by `cargo run -- generate-clients` from the OpenAPI spec. Do not edit.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug)]
+pub enum ClientError {
+    Http(reqwest::Error),
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Http(err) => write!(f, "HTTP error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(err: reqwest::Error) -> Self {
+        ClientError::Http(err)
+    }
+}
+
+/// Standard response envelope
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiResponse<T> {
+    pub success: bool,
+    pub data: Option<T>,
+    pub error: Option<ApiError>,
+    pub meta: Option<Meta>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnomaliesResponse {
+    pub anomalies: Vec<Anomaly>,
+    pub routes_tracked: i64,
+    pub window_secs: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Anomaly {
+    pub baseline: f64,
+    pub kind: String,
+    pub observed: f64,
+    pub route: String,
+    pub since: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiError {
+    pub code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditPage {
+    pub archived: bool,
+    pub next_before_seq: i64,
+    pub records: Vec<AuditRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub event: String,
+    pub fields: serde_json::Value,
+    pub level: String,
+    pub message: String,
+    pub recorded_at: String,
+    pub seq: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BootReport {
+    pub boot_duration_ms: i64,
+    pub booted_at: String,
+    pub components: Vec<ComponentTiming>,
+    pub config: serde_json::Value,
+    pub deployment_color: String,
+    pub deployment_id: String,
+    pub features: Vec<String>,
+    pub listeners: Vec<Listener>,
+    pub migrations: MigrationStatus,
+    pub pid: i64,
+    pub region: String,
+    pub routes: Vec<MountedRoute>,
+    pub runtime: RuntimeReport,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worker: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheInvalidated {
+    pub cache: String,
+    pub removed: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentTiming {
+    pub component: String,
+    pub duration_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsumerReport {
+    pub buckets: Vec<LatencyBucket>,
+    pub connected: bool,
+    pub dead_letter_depth: i64,
+    pub dead_lettered: i64,
+    pub failed: i64,
+    pub in_flight: i64,
+    pub lag: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_processed_at: Option<String>,
+    pub mean_ms: f64,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p50_ms: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p95_ms: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p99_ms: Option<i64>,
+    pub processed: i64,
+    pub retries: i64,
+    pub transport: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsumersResponse {
+    pub consumers: Vec<ConsumerReport>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpuPoolStats {
+    pub active: i64,
+    pub batch_capacity: i64,
+    pub batch_queued: i64,
+    pub completed: i64,
+    pub interactive_capacity: i64,
+    pub interactive_queued: i64,
+    pub panicked: i64,
+    pub rejected_batch: i64,
+    pub rejected_interactive: i64,
+    pub threads: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateProductRequest {
+    pub name: String,
+    pub price: Money,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateReportRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+    pub format: String,
+    pub kind: String,
+    pub requested_by: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateUserRequest {
+    pub email: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsrfTokenResponse {
+    pub header: String,
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DegradationState {
+    pub active: bool,
+    pub dependency: String,
+    pub fallback: String,
+    pub mode: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependenciesResponse {
+    pub dependencies: Vec<DependencyStatus>,
+    pub status: String,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyStatus {
+    pub consecutive_failures: i64,
+    pub critical: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub last_checked: String,
+    pub last_success: String,
+    pub latency_ms: f64,
+    pub name: String,
+    pub stale: bool,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeprecationUsage {
+    pub deprecated_on: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<String>,
+    pub name: String,
+    pub requests: i64,
+    pub sunset_on: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeprecationsResponse {
+    pub deprecations: Vec<DeprecationUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceVerificationRequest {
+    pub approve: bool,
+    pub user_code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceVerificationResponse {
+    pub approved: bool,
+    pub client_id: String,
+    pub scope: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrainResponse {
+    pub deployment_id: String,
+    pub draining: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventPoll {
+    pub cursor: String,
+    pub events: Vec<StreamEvent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reset: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub critical: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub latency_ms: f64,
+    pub name: String,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthResponse {
+    pub checks: Vec<HealthCheck>,
+    pub degradations: Vec<DegradationState>,
+    pub region: String,
+    pub service: String,
+    pub status: String,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImpersonateRequest {
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImpersonationEntry {
+    pub actor: String,
+    pub expires_at: String,
+    pub started_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImpersonationResponse {
+    pub expires_at: String,
+    pub session_id: String,
+    pub token: String,
+    pub user_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InfoResponse {
+    pub deployment_color: String,
+    pub deployment_id: String,
+    pub draining: bool,
+    pub region: String,
+    pub service: String,
+    pub started_at: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvalidateCacheRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keys: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefixes: Option<Vec<String>>,
+    pub reason: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvalidationReport {
+    pub caches: Vec<CacheInvalidated>,
+    pub cdn: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cdn_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaneReport {
+    pub capacity: i64,
+    pub in_flight: i64,
+    pub lanes: Vec<LaneStats>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaneStats {
+    pub admitted: i64,
+    pub in_flight: i64,
+    pub lane: String,
+    pub limit: i64,
+    pub rejected: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyBucket {
+    pub count: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub le_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimitedClient {
+    pub bucket: String,
+    pub client: String,
+    pub last_limited_at: String,
+    pub limited: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListUsersResponse {
+    /// Deprecated since 2026-10-18, removed after 2027-04-30. Read pagination from the response `meta` instead.
+    pub limit: i64,
+    /// Deprecated since 2026-10-18, removed after 2027-04-30. Read pagination from the response `meta` instead.
+    pub page: i64,
+    /// Deprecated since 2026-10-18, removed after 2027-04-30. Read pagination from the response `meta` instead.
+    pub total: i64,
+    /// Deprecated since 2026-10-18, removed after 2027-04-30. Read pagination from the response `meta` instead.
+    pub total_exact: bool,
+    pub users: Vec<User>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Listener {
+    pub address: String,
+    pub protocol: String,
+    pub shared: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveResponse {
+    pub status: String,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryReport {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hard_limit_bytes: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<ProcessMemory>,
+    pub pressure: String,
+    pub reclaimed_entries: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sampled_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soft_limit_bytes: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Meta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub links: Option<PageLinks>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_exact: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_pages: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MethodMetricsResponse {
+    pub methods: Vec<MethodReport>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MethodReport {
+    pub buckets: Vec<LatencyBucket>,
+    pub calls: i64,
+    pub error_rate: f64,
+    pub faults: i64,
+    pub mean_ms: f64,
+    pub method: String,
+    pub over_budget: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p50_ms: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p95_ms: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p99_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationStatus {
+    pub sql_applied: bool,
+    pub sql_migrations: Vec<String>,
+    pub user_record_version: i64,
+    pub user_store: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissingPart {
+    pub reason: String,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Money {
+    pub amount: String,
+    pub currency: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MountedRoute {
+    pub admin: bool,
+    pub handler: String,
+    pub method: String,
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectPoolStats {
+    pub discarded: i64,
+    pub hits: i64,
+    pub max_pooled: i64,
+    pub misses: i64,
+    pub name: String,
+    pub pooled: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectPoolsResponse {
+    pub pools: Vec<ObjectPoolStats>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageLinks {
+    pub first: String,
+    pub last: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatchUserRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingDeviceResponse {
+    pub client_id: String,
+    pub expires_at: String,
+    pub scope: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProblemDetails {
+    pub code: String,
+    pub detail: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<ProblemViolation>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    pub status: i64,
+    pub title: String,
+    pub r#type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProblemViolation {
+    pub code: String,
+    pub detail: String,
+    pub field: String,
+    pub pointer: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessMemory {
+    pub peak_rss_bytes: i64,
+    pub rss_bytes: i64,
+    pub virtual_bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Product {
+    pub created_at: String,
+    pub id: String,
+    pub name: String,
+    pub price: Money,
+    pub price_display: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitReport {
+    pub clients: Vec<LimitedClient>,
+    pub mode: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadyResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checked_at: Option<String>,
+    pub checks: Vec<HealthCheck>,
+    pub deployment_color: String,
+    pub deployment_id: String,
+    pub region: String,
+    pub status: String,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rebalance {
+    pub added: Vec<String>,
+    pub at: String,
+    pub moved_fraction: f64,
+    pub removed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<String>,
+    pub created_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub format: String,
+    pub id: String,
+    pub kind: String,
+    pub requested_by: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rows: Option<i64>,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseCacheReport {
+    pub entries: i64,
+    pub max_entries: i64,
+    pub routes: Vec<RouteCacheReport>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevokeSessionsResponse {
+    pub revoked: i64,
+    pub user_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteCacheReport {
+    pub entries: i64,
+    pub fresh_secs: i64,
+    pub hits: i64,
+    pub misses: i64,
+    pub refresh_failures: i64,
+    pub refreshes: i64,
+    pub route: String,
+    pub stale_secs: i64,
+    pub stale_serves: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutesResponse {
+    pub routes: Vec<MountedRoute>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeReport {
+    pub event_interval: i64,
+    pub max_blocking_threads: i64,
+    pub worker_threads: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub created_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    pub expires_at: String,
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonated_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip_address: Option<String>,
+    pub kind: String,
+    pub last_seen_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub roles: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    pub user_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionsOverview {
+    pub active: i64,
+    pub impersonations: Vec<ImpersonationEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionsResponse {
+    pub sessions: Vec<Session>,
+    pub user_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardNodeStats {
+    pub node: String,
+    pub picks: i64,
+    pub share: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardRingStats {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_rebalance: Option<Rebalance>,
+    pub name: String,
+    pub nodes: Vec<ShardNodeStats>,
+    pub rebalances: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardsResponse {
+    pub rings: Vec<ShardRingStats>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignInRequest {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignInResponse {
+    pub expires_at: String,
+    pub session_id: String,
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamEvent {
+    pub data: serde_json::Value,
+    pub published_at: String,
+    pub seq: i64,
+    pub topics: Vec<String>,
+    pub r#type: String,
+}
+
+pub type UpdateUserRequest = serde_json::Value;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    pub created_at: String,
+    pub email: String,
+    pub id: String,
+    pub metadata: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presence: Option<UserPresence>,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserOverviewResponse {
+    pub missing: Vec<MissingPart>,
+    pub partial: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sessions: Option<SessionsOverview>,
+    pub user: User,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserPresence {
+    pub last_seen_at: String,
+    pub status: String,
+}
+
+pub struct Client {
+    base_url: String,
+    http: reqwest::Client,
+    bearer_token: Option<String>,
+}
+
+impl Client {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+            bearer_token: None,
+        }
+    }
+
+    pub fn with_bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.bearer_token = Some(token.into());
+        self
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<ApiResponse<T>, ClientError> {
+        let request = match &self.bearer_token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        };
+        Ok(request.send().await?.json().await?)
+    }
+
+    /// Currently active request anomalies
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn list_anomalies(&self) -> Result<ApiResponse<AnomaliesResponse>, ClientError> {
+        let url = format!("{}/api/admin/anomalies", self.base_url);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Audit events, newest first, from the journal and its archive
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn list_audit_events(&self, event: Option<String>, since: Option<String>, until: Option<String>, before_seq: Option<i64>, limit: Option<i64>) -> Result<ApiResponse<AuditPage>, ClientError> {
+        let url = format!("{}/api/admin/audit-events", self.base_url);
+        let request = self.http.get(url);
+        let mut query: Vec<(&str, String)> = Vec::new();
+        if let Some(value) = event {
+            query.push(("event", value.to_string()));
+        }
+        if let Some(value) = since {
+            query.push(("since", value.to_string()));
+        }
+        if let Some(value) = until {
+            query.push(("until", value.to_string()));
+        }
+        if let Some(value) = before_seq {
+            query.push(("before_seq", value.to_string()));
+        }
+        if let Some(value) = limit {
+            query.push(("limit", value.to_string()));
+        }
+        let request = request.query(&query);
+        self.send(request).await
+    }
+
+    /// Config, features, listeners, migrations and startup times this instance booted with
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn get_boot_report(&self) -> Result<ApiResponse<BootReport>, ClientError> {
+        let url = format!("{}/api/admin/boot-report", self.base_url);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Drop cache entries by key, prefix or tag and purge the tags at the CDN
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn invalidate_cache(&self, body: &InvalidateCacheRequest) -> Result<ApiResponse<InvalidationReport>, ClientError> {
+        let url = format!("{}/api/admin/cache/invalidate", self.base_url);
+        let request = self.http.post(url).json(body);
+        self.send(request).await
+    }
+
+    /// Lag, processing latency, retries and dead letters of each message consumer
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn list_consumers(&self) -> Result<ApiResponse<ConsumersResponse>, ClientError> {
+        let url = format!("{}/api/admin/consumers", self.base_url);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Queue depths and counters of the CPU work pool
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn cpu_pool_stats(&self) -> Result<ApiResponse<CpuPoolStats>, ClientError> {
+        let url = format!("{}/api/admin/cpu-pool", self.base_url);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Deprecated routes and fields, with their sunset dates and recent use
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn list_deprecations(&self) -> Result<ApiResponse<DeprecationsResponse>, ClientError> {
+        let url = format!("{}/api/admin/deprecations", self.base_url);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Stop draining
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn stop_draining(&self) -> Result<ApiResponse<DrainResponse>, ClientError> {
+        let url = format!("{}/api/admin/drain", self.base_url);
+        let request = self.http.delete(url);
+        self.send(request).await
+    }
+
+    /// Mark instance as draining
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn start_draining(&self) -> Result<ApiResponse<DrainResponse>, ClientError> {
+        let url = format!("{}/api/admin/drain", self.base_url);
+        let request = self.http.post(url);
+        self.send(request).await
+    }
+
+    /// End every impersonation session for the user
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn stop_impersonation(&self, id: &str) -> Result<ApiResponse<RevokeSessionsResponse>, ClientError> {
+        let url = format!("{}/api/admin/impersonate/{}", self.base_url, id);
+        let request = self.http.delete(url);
+        self.send(request).await
+    }
+
+    /// Issue a short-lived token acting as the user
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn start_impersonation(&self, id: &str, body: &ImpersonateRequest) -> Result<ApiResponse<ImpersonationResponse>, ClientError> {
+        let url = format!("{}/api/admin/impersonate/{}", self.base_url, id);
+        let request = self.http.post(url).json(body);
+        self.send(request).await
+    }
+
+    /// Request slots in use and shed per priority lane
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn list_lanes(&self) -> Result<ApiResponse<LaneReport>, ClientError> {
+        let url = format!("{}/api/admin/lanes", self.base_url);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Process memory, limits and pressure
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn memory_report(&self) -> Result<ApiResponse<MemoryReport>, ClientError> {
+        let url = format!("{}/api/admin/memory", self.base_url);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Calls, latency and error rate of each service method
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn list_method_metrics(&self) -> Result<ApiResponse<MethodMetricsResponse>, ClientError> {
+        let url = format!("{}/api/admin/method-metrics", self.base_url);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Reuse counters of the buffer pools
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn list_object_pools(&self) -> Result<ApiResponse<ObjectPoolsResponse>, ClientError> {
+        let url = format!("{}/api/admin/object-pools", self.base_url);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Clients that went over a rate limit most often
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn list_rate_limited_clients(&self) -> Result<ApiResponse<RateLimitReport>, ClientError> {
+        let url = format!("{}/api/admin/rate-limits", self.base_url);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Queue a CSV or XLSX report; the requester is sent a download link once it is built
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn create_report(&self, body: &CreateReportRequest) -> Result<ApiResponse<Report>, ClientError> {
+        let url = format!("{}/api/admin/reports", self.base_url);
+        let request = self.http.post(url).json(body);
+        self.send(request).await
+    }
+
+    /// Status of a report, with a fresh download link once it is ready
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn get_report(&self, id: &str) -> Result<ApiResponse<Report>, ClientError> {
+        let url = format!("{}/api/admin/reports/{}", self.base_url, id);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Hits, stale serves, misses and background refreshes of each cached route
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn get_response_cache_stats(&self) -> Result<ApiResponse<ResponseCacheReport>, ClientError> {
+        let url = format!("{}/api/admin/response-cache", self.base_url);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Routes served by this instance, with their handlers
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn list_routes(&self) -> Result<ApiResponse<RoutesResponse>, ClientError> {
+        let url = format!("{}/api/admin/routes", self.base_url);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Nodes, key shares and rebalances of each shard ring
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn list_shards(&self) -> Result<ApiResponse<ShardsResponse>, ClientError> {
+        let url = format!("{}/api/admin/shards", self.base_url);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Revoke every session of a user
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn revoke_user_sessions(&self, id: &str) -> Result<ApiResponse<RevokeSessionsResponse>, ClientError> {
+        let url = format!("{}/api/admin/users/{}/sessions", self.base_url, id);
+        let request = self.http.delete(url);
+        self.send(request).await
+    }
+
+    /// Active sessions and refresh tokens of a user
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn list_user_sessions(&self, id: &str) -> Result<ApiResponse<SessionsResponse>, ClientError> {
+        let url = format!("{}/api/admin/users/{}/sessions", self.base_url, id);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Revoke one session of a user
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn revoke_user_session(&self, id: &str, session_id: &str) -> Result<ApiResponse<RevokeSessionsResponse>, ClientError> {
+        let url = format!("{}/api/admin/users/{}/sessions/{}", self.base_url, id, session_id);
+        let request = self.http.delete(url);
+        self.send(request).await
+    }
+
+    /// CSRF token for writes authenticated by the session cookie
+    pub async fn issue_csrf_token(&self) -> Result<ApiResponse<CsrfTokenResponse>, ClientError> {
+        let url = format!("{}/api/auth/csrf", self.base_url);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Sign in with an email and password
+    pub async fn sign_in(&self, body: &SignInRequest) -> Result<ApiResponse<SignInResponse>, ClientError> {
+        let url = format!("{}/api/auth/login", self.base_url);
+        let request = self.http.post(url).json(body);
+        self.send(request).await
+    }
+
+    /// Events past a cursor, waiting up to 25s for the next one
+    pub async fn poll_events(&self, cursor: Option<String>, topics: Option<String>, wait: Option<i64>) -> Result<ApiResponse<EventPoll>, ClientError> {
+        let url = format!("{}/api/events/poll", self.base_url);
+        let request = self.http.get(url);
+        let mut query: Vec<(&str, String)> = Vec::new();
+        if let Some(value) = cursor {
+            query.push(("cursor", value.to_string()));
+        }
+        if let Some(value) = topics {
+            query.push(("topics", value.to_string()));
+        }
+        if let Some(value) = wait {
+            query.push(("wait", value.to_string()));
+        }
+        let request = request.query(&query);
+        self.send(request).await
+    }
+
+    /// Health check
+    pub async fn health_check(&self) -> Result<ApiResponse<HealthResponse>, ClientError> {
+        let url = format!("{}/api/health", self.base_url);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Last background probe result for each dependency
+    pub async fn get_dependencies(&self) -> Result<ApiResponse<DependenciesResponse>, ClientError> {
+        let url = format!("{}/api/health/dependencies", self.base_url);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Service version and deployment metadata
+    pub async fn get_info(&self) -> Result<ApiResponse<InfoResponse>, ClientError> {
+        let url = format!("{}/api/info", self.base_url);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Liveness check
+    pub async fn liveness_check(&self) -> Result<ApiResponse<LiveResponse>, ClientError> {
+        let url = format!("{}/api/live", self.base_url);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Approve or deny the device showing a user code
+    pub async fn verify_device(&self, body: &DeviceVerificationRequest) -> Result<ApiResponse<DeviceVerificationResponse>, ClientError> {
+        let url = format!("{}/api/oidc/device", self.base_url);
+        let request = self.http.post(url).json(body);
+        self.send(request).await
+    }
+
+    /// Client and scopes of the device showing a user code
+    pub async fn describe_device(&self, user_code: &str) -> Result<ApiResponse<PendingDeviceResponse>, ClientError> {
+        let url = format!("{}/api/oidc/device/{}", self.base_url, user_code);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Create product
+    pub async fn create_product(&self, body: &CreateProductRequest) -> Result<ApiResponse<Product>, ClientError> {
+        let url = format!("{}/api/products", self.base_url);
+        let request = self.http.post(url).json(body);
+        self.send(request).await
+    }
+
+    /// Get product by ID
+    pub async fn get_product(&self, id: &str) -> Result<ApiResponse<Product>, ClientError> {
+        let url = format!("{}/api/products/{}", self.base_url, id);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Readiness check
+    pub async fn readiness_check(&self) -> Result<ApiResponse<ReadyResponse>, ClientError> {
+        let url = format!("{}/api/ready", self.base_url);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// List users (with pagination)
+    pub async fn list_users(&self, page: Option<i64>, limit: Option<i64>, exact: Option<bool>, include: Option<String>) -> Result<ApiResponse<ListUsersResponse>, ClientError> {
+        let url = format!("{}/api/users", self.base_url);
+        let request = self.http.get(url);
+        let mut query: Vec<(&str, String)> = Vec::new();
+        if let Some(value) = page {
+            query.push(("page", value.to_string()));
+        }
+        if let Some(value) = limit {
+            query.push(("limit", value.to_string()));
+        }
+        if let Some(value) = exact {
+            query.push(("exact", value.to_string()));
+        }
+        if let Some(value) = include {
+            query.push(("include", value.to_string()));
+        }
+        let request = request.query(&query);
+        self.send(request).await
+    }
+
+    /// Create user
+    pub async fn create_user(&self, body: &CreateUserRequest) -> Result<ApiResponse<User>, ClientError> {
+        let url = format!("{}/api/users", self.base_url);
+        let request = self.http.post(url).json(body);
+        self.send(request).await
+    }
+
+    /// Delete user (not implemented)
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn delete_user(&self, id: &str) -> Result<ApiResponse<serde_json::Value>, ClientError> {
+        let url = format!("{}/api/users/{}", self.base_url, id);
+        let request = self.http.delete(url);
+        self.send(request).await
+    }
+
+    /// Get user by ID
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn get_user(&self, id: &str) -> Result<ApiResponse<User>, ClientError> {
+        let url = format!("{}/api/users/{}", self.base_url, id);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Update the fields present; metadata is merge-patched
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn patch_user(&self, id: &str, body: &PatchUserRequest) -> Result<ApiResponse<User>, ClientError> {
+        let url = format!("{}/api/users/{}", self.base_url, id);
+        let request = self.http.patch(url).json(body);
+        self.send(request).await
+    }
+
+    /// Replace user (not implemented)
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn update_user(&self, id: &str, body: &UpdateUserRequest) -> Result<ApiResponse<User>, ClientError> {
+        let url = format!("{}/api/users/{}", self.base_url, id);
+        let request = self.http.put(url).json(body);
+        self.send(request).await
+    }
+
+    /// The user's profile with their active sessions
+    ///
+    /// Requires a bearer token, see `Client::with_bearer_token`.
+    pub async fn get_user_overview(&self, id: &str) -> Result<ApiResponse<UserOverviewResponse>, ClientError> {
+        let url = format!("{}/api/users/{}/overview", self.base_url, id);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+
+    /// Whether the user is online, and when they were last seen
+    pub async fn get_user_presence(&self, id: &str) -> Result<ApiResponse<UserPresence>, ClientError> {
+        let url = format!("{}/api/users/{}/presence", self.base_url, id);
+        let request = self.http.get(url);
+        self.send(request).await
+    }
+}
@@ -0,0 +1,823 @@
+// Typed client for the rust-boilerplate API.
+//
+// Generated by `cargo run -- generate-clients` from the OpenAPI spec. Do not edit.
+
+export interface ApiResponse<T> {
+  success: boolean;
+  data: T | null;
+  error: ApiError | null;
+  meta: Meta | null;
+}
+
+export interface AnomaliesResponse {
+  anomalies: Anomaly[];
+  routes_tracked: number;
+  window_secs: number;
+}
+
+export interface Anomaly {
+  baseline: number;
+  kind: string;
+  observed: number;
+  route: string;
+  since: string;
+}
+
+export interface ApiError {
+  code: string;
+  details?: unknown;
+  message: string;
+}
+
+export interface AuditPage {
+  archived: boolean;
+  next_before_seq: number;
+  records: AuditRecord[];
+}
+
+export interface AuditRecord {
+  event: string;
+  fields: unknown;
+  level: string;
+  message: string;
+  recorded_at: string;
+  seq: number;
+}
+
+export interface BootReport {
+  boot_duration_ms: number;
+  booted_at: string;
+  components: ComponentTiming[];
+  config: unknown;
+  deployment_color: string;
+  deployment_id: string;
+  features: string[];
+  listeners: Listener[];
+  migrations: MigrationStatus;
+  pid: number;
+  region: string;
+  routes: MountedRoute[];
+  runtime: RuntimeReport;
+  version: string;
+  worker?: number;
+}
+
+export interface CacheInvalidated {
+  cache: string;
+  removed: number;
+}
+
+export interface ComponentTiming {
+  component: string;
+  duration_ms: number;
+}
+
+export interface ConsumerReport {
+  buckets: LatencyBucket[];
+  connected: boolean;
+  dead_letter_depth: number;
+  dead_lettered: number;
+  failed: number;
+  in_flight: number;
+  lag: number;
+  last_processed_at?: string;
+  mean_ms: number;
+  name: string;
+  p50_ms?: number;
+  p95_ms?: number;
+  p99_ms?: number;
+  processed: number;
+  retries: number;
+  transport: string;
+}
+
+export interface ConsumersResponse {
+  consumers: ConsumerReport[];
+}
+
+export interface CpuPoolStats {
+  active: number;
+  batch_capacity: number;
+  batch_queued: number;
+  completed: number;
+  interactive_capacity: number;
+  interactive_queued: number;
+  panicked: number;
+  rejected_batch: number;
+  rejected_interactive: number;
+  threads: number;
+}
+
+export interface CreateProductRequest {
+  name: string;
+  price: Money;
+}
+
+export interface CreateReportRequest {
+  callback_url?: string;
+  format: string;
+  kind: string;
+  requested_by: string;
+}
+
+export interface CreateUserRequest {
+  email: string;
+  metadata?: unknown;
+  password: string;
+}
+
+export interface CsrfTokenResponse {
+  header: string;
+  token: string;
+}
+
+export interface DegradationState {
+  active: boolean;
+  dependency: string;
+  fallback: string;
+  mode: string;
+  since?: string;
+}
+
+export interface DependenciesResponse {
+  dependencies: DependencyStatus[];
+  status: string;
+  timestamp: string;
+}
+
+export interface DependencyStatus {
+  consecutive_failures: number;
+  critical: boolean;
+  error?: string;
+  last_checked: string;
+  last_success: string;
+  latency_ms: number;
+  name: string;
+  stale: boolean;
+  status: string;
+}
+
+export interface DeprecationUsage {
+  deprecated_on: string;
+  last_used_at?: string;
+  name: string;
+  requests: number;
+  sunset_on: string;
+}
+
+export interface DeprecationsResponse {
+  deprecations: DeprecationUsage[];
+}
+
+export interface DeviceVerificationRequest {
+  approve: boolean;
+  user_code: string;
+}
+
+export interface DeviceVerificationResponse {
+  approved: boolean;
+  client_id: string;
+  scope: string;
+}
+
+export interface DrainResponse {
+  deployment_id: string;
+  draining: boolean;
+}
+
+export interface EventPoll {
+  cursor: string;
+  events: StreamEvent[];
+  reset?: string;
+}
+
+export interface HealthCheck {
+  critical: boolean;
+  error?: string;
+  latency_ms: number;
+  name: string;
+  status: string;
+}
+
+export interface HealthResponse {
+  checks: HealthCheck[];
+  degradations: DegradationState[];
+  region: string;
+  service: string;
+  status: string;
+  timestamp: string;
+}
+
+export interface ImpersonateRequest {
+  reason: string;
+}
+
+export interface ImpersonationEntry {
+  actor: string;
+  expires_at: string;
+  started_at: string;
+}
+
+export interface ImpersonationResponse {
+  expires_at: string;
+  session_id: string;
+  token: string;
+  user_id: string;
+}
+
+export interface InfoResponse {
+  deployment_color: string;
+  deployment_id: string;
+  draining: boolean;
+  region: string;
+  service: string;
+  started_at: string;
+  version: string;
+}
+
+export interface InvalidateCacheRequest {
+  keys?: string[];
+  prefixes?: string[];
+  reason: string;
+  tags?: string[];
+}
+
+export interface InvalidationReport {
+  caches: CacheInvalidated[];
+  cdn: string;
+  cdn_error?: string;
+}
+
+export interface LaneReport {
+  capacity: number;
+  in_flight: number;
+  lanes: LaneStats[];
+}
+
+export interface LaneStats {
+  admitted: number;
+  in_flight: number;
+  lane: string;
+  limit: number;
+  rejected: number;
+}
+
+export interface LatencyBucket {
+  count: number;
+  le_ms?: number;
+}
+
+export interface LimitedClient {
+  bucket: string;
+  client: string;
+  last_limited_at: string;
+  limited: number;
+}
+
+export interface ListUsersResponse {
+  /** @deprecated Deprecated since 2026-10-18, removed after 2027-04-30. Read pagination from the response `meta` instead. */
+  limit: number;
+  /** @deprecated Deprecated since 2026-10-18, removed after 2027-04-30. Read pagination from the response `meta` instead. */
+  page: number;
+  /** @deprecated Deprecated since 2026-10-18, removed after 2027-04-30. Read pagination from the response `meta` instead. */
+  total: number;
+  /** @deprecated Deprecated since 2026-10-18, removed after 2027-04-30. Read pagination from the response `meta` instead. */
+  total_exact: boolean;
+  users: User[];
+}
+
+export interface Listener {
+  address: string;
+  protocol: string;
+  shared: boolean;
+}
+
+export interface LiveResponse {
+  status: string;
+  timestamp: string;
+}
+
+export interface MemoryReport {
+  hard_limit_bytes?: number;
+  memory?: ProcessMemory;
+  pressure: string;
+  reclaimed_entries: number;
+  sampled_at?: string;
+  soft_limit_bytes?: number;
+}
+
+export interface Meta {
+  limit?: number;
+  links?: PageLinks;
+  page?: number;
+  total?: number;
+  total_exact?: boolean;
+  total_pages?: number;
+}
+
+export interface MethodMetricsResponse {
+  methods: MethodReport[];
+}
+
+export interface MethodReport {
+  buckets: LatencyBucket[];
+  calls: number;
+  error_rate: number;
+  faults: number;
+  mean_ms: number;
+  method: string;
+  over_budget: number;
+  p50_ms?: number;
+  p95_ms?: number;
+  p99_ms?: number;
+}
+
+export interface MigrationStatus {
+  sql_applied: boolean;
+  sql_migrations: string[];
+  user_record_version: number;
+  user_store: string;
+}
+
+export interface MissingPart {
+  reason: string;
+  source: string;
+}
+
+export interface Money {
+  amount: string;
+  currency: string;
+}
+
+export interface MountedRoute {
+  admin: boolean;
+  handler: string;
+  method: string;
+  name: string;
+  path: string;
+}
+
+export interface ObjectPoolStats {
+  discarded: number;
+  hits: number;
+  max_pooled: number;
+  misses: number;
+  name: string;
+  pooled: number;
+}
+
+export interface ObjectPoolsResponse {
+  pools: ObjectPoolStats[];
+}
+
+export interface PageLinks {
+  first: string;
+  last: string;
+  next?: string;
+  prev?: string;
+}
+
+export interface PatchUserRequest {
+  metadata?: unknown;
+}
+
+export interface PendingDeviceResponse {
+  client_id: string;
+  expires_at: string;
+  scope: string;
+}
+
+export interface ProblemDetails {
+  code: string;
+  detail: string;
+  errors?: ProblemViolation[];
+  instance?: string;
+  status: number;
+  title: string;
+  type: string;
+}
+
+export interface ProblemViolation {
+  code: string;
+  detail: string;
+  field: string;
+  pointer: string;
+}
+
+export interface ProcessMemory {
+  peak_rss_bytes: number;
+  rss_bytes: number;
+  virtual_bytes: number;
+}
+
+export interface Product {
+  created_at: string;
+  id: string;
+  name: string;
+  price: Money;
+  price_display: string;
+}
+
+export interface RateLimitReport {
+  clients: LimitedClient[];
+  mode: string;
+}
+
+export interface ReadyResponse {
+  checked_at?: string;
+  checks: HealthCheck[];
+  deployment_color: string;
+  deployment_id: string;
+  region: string;
+  status: string;
+  timestamp: string;
+}
+
+export interface Rebalance {
+  added: string[];
+  at: string;
+  moved_fraction: number;
+  removed: string[];
+}
+
+export interface Report {
+  completed_at?: string;
+  created_at: string;
+  download_url?: string;
+  error?: string;
+  format: string;
+  id: string;
+  kind: string;
+  requested_by: string;
+  rows?: number;
+  status: string;
+}
+
+export interface ResponseCacheReport {
+  entries: number;
+  max_entries: number;
+  routes: RouteCacheReport[];
+}
+
+export interface RevokeSessionsResponse {
+  revoked: number;
+  user_id: string;
+}
+
+export interface RouteCacheReport {
+  entries: number;
+  fresh_secs: number;
+  hits: number;
+  misses: number;
+  refresh_failures: number;
+  refreshes: number;
+  route: string;
+  stale_secs: number;
+  stale_serves: number;
+}
+
+export interface RoutesResponse {
+  routes: MountedRoute[];
+}
+
+export interface RuntimeReport {
+  event_interval: number;
+  max_blocking_threads: number;
+  worker_threads: number;
+}
+
+export interface Session {
+  created_at: string;
+  device?: string;
+  expires_at: string;
+  id: string;
+  impersonated_by?: string;
+  ip_address?: string;
+  kind: string;
+  last_seen_at: string;
+  roles?: string[];
+  tenant?: string;
+  user_id: string;
+}
+
+export interface SessionsOverview {
+  active: number;
+  impersonations: ImpersonationEntry[];
+  last_seen_at?: string;
+}
+
+export interface SessionsResponse {
+  sessions: Session[];
+  user_id: string;
+}
+
+export interface ShardNodeStats {
+  node: string;
+  picks: number;
+  share: number;
+}
+
+export interface ShardRingStats {
+  last_rebalance?: Rebalance;
+  name: string;
+  nodes: ShardNodeStats[];
+  rebalances: number;
+}
+
+export interface ShardsResponse {
+  rings: ShardRingStats[];
+}
+
+export interface SignInRequest {
+  email: string;
+  password: string;
+}
+
+export interface SignInResponse {
+  expires_at: string;
+  session_id: string;
+  token: string;
+}
+
+export interface StreamEvent {
+  data: unknown;
+  published_at: string;
+  seq: number;
+  topics: string[];
+  type: string;
+}
+
+export type UpdateUserRequest = Record<string, unknown>;
+
+export interface User {
+  created_at: string;
+  email: string;
+  id: string;
+  metadata: unknown;
+  presence?: UserPresence;
+  updated_at: string;
+}
+
+export interface UserOverviewResponse {
+  missing: MissingPart[];
+  partial: boolean;
+  sessions?: SessionsOverview;
+  user: User;
+}
+
+export interface UserPresence {
+  last_seen_at: string;
+  status: string;
+}
+
+export class ApiClient {
+  private readonly baseUrl: string;
+
+  constructor(baseUrl: string, private readonly bearerToken?: string) {
+    this.baseUrl = baseUrl.replace(/\/+$/, "");
+  }
+
+  private async send<T>(method: string, path: string, query?: Record<string, unknown>, body?: unknown): Promise<ApiResponse<T>> {
+    const url = new URL(this.baseUrl + path);
+    for (const [key, value] of Object.entries(query ?? {})) {
+      if (value !== undefined && value !== null) {
+        url.searchParams.set(key, String(value));
+      }
+    }
+
+    const headers: Record<string, string> = {};
+    if (body !== undefined) {
+      headers["Content-Type"] = "application/json";
+    }
+    if (this.bearerToken) {
+      headers["Authorization"] = `Bearer ${this.bearerToken}`;
+    }
+
+    const response = await fetch(url, {
+      method,
+      headers,
+      body: body === undefined ? undefined : JSON.stringify(body),
+    });
+    return (await response.json()) as ApiResponse<T>;
+  }
+
+  /** Currently active request anomalies (requires bearer token) */
+  listAnomalies(): Promise<ApiResponse<AnomaliesResponse>> {
+    return this.send("GET", `/api/admin/anomalies`, undefined);
+  }
+
+  /** Audit events, newest first, from the journal and its archive (requires bearer token) */
+  listAuditEvents(query: { event?: string; since?: string; until?: string; before_seq?: number; limit?: number } = {}): Promise<ApiResponse<AuditPage>> {
+    return this.send("GET", `/api/admin/audit-events`, query);
+  }
+
+  /** Config, features, listeners, migrations and startup times this instance booted with (requires bearer token) */
+  getBootReport(): Promise<ApiResponse<BootReport>> {
+    return this.send("GET", `/api/admin/boot-report`, undefined);
+  }
+
+  /** Drop cache entries by key, prefix or tag and purge the tags at the CDN (requires bearer token) */
+  invalidateCache(body: InvalidateCacheRequest): Promise<ApiResponse<InvalidationReport>> {
+    return this.send("POST", `/api/admin/cache/invalidate`, undefined, body);
+  }
+
+  /** Lag, processing latency, retries and dead letters of each message consumer (requires bearer token) */
+  listConsumers(): Promise<ApiResponse<ConsumersResponse>> {
+    return this.send("GET", `/api/admin/consumers`, undefined);
+  }
+
+  /** Queue depths and counters of the CPU work pool (requires bearer token) */
+  cpuPoolStats(): Promise<ApiResponse<CpuPoolStats>> {
+    return this.send("GET", `/api/admin/cpu-pool`, undefined);
+  }
+
+  /** Deprecated routes and fields, with their sunset dates and recent use (requires bearer token) */
+  listDeprecations(): Promise<ApiResponse<DeprecationsResponse>> {
+    return this.send("GET", `/api/admin/deprecations`, undefined);
+  }
+
+  /** Stop draining (requires bearer token) */
+  stopDraining(): Promise<ApiResponse<DrainResponse>> {
+    return this.send("DELETE", `/api/admin/drain`, undefined);
+  }
+
+  /** Mark instance as draining (requires bearer token) */
+  startDraining(): Promise<ApiResponse<DrainResponse>> {
+    return this.send("POST", `/api/admin/drain`, undefined);
+  }
+
+  /** End every impersonation session for the user (requires bearer token) */
+  stopImpersonation(id: string): Promise<ApiResponse<RevokeSessionsResponse>> {
+    return this.send("DELETE", `/api/admin/impersonate/${encodeURIComponent(id)}`, undefined);
+  }
+
+  /** Issue a short-lived token acting as the user (requires bearer token) */
+  startImpersonation(id: string, body: ImpersonateRequest): Promise<ApiResponse<ImpersonationResponse>> {
+    return this.send("POST", `/api/admin/impersonate/${encodeURIComponent(id)}`, undefined, body);
+  }
+
+  /** Request slots in use and shed per priority lane (requires bearer token) */
+  listLanes(): Promise<ApiResponse<LaneReport>> {
+    return this.send("GET", `/api/admin/lanes`, undefined);
+  }
+
+  /** Process memory, limits and pressure (requires bearer token) */
+  memoryReport(): Promise<ApiResponse<MemoryReport>> {
+    return this.send("GET", `/api/admin/memory`, undefined);
+  }
+
+  /** Calls, latency and error rate of each service method (requires bearer token) */
+  listMethodMetrics(): Promise<ApiResponse<MethodMetricsResponse>> {
+    return this.send("GET", `/api/admin/method-metrics`, undefined);
+  }
+
+  /** Reuse counters of the buffer pools (requires bearer token) */
+  listObjectPools(): Promise<ApiResponse<ObjectPoolsResponse>> {
+    return this.send("GET", `/api/admin/object-pools`, undefined);
+  }
+
+  /** Clients that went over a rate limit most often (requires bearer token) */
+  listRateLimitedClients(): Promise<ApiResponse<RateLimitReport>> {
+    return this.send("GET", `/api/admin/rate-limits`, undefined);
+  }
+
+  /** Queue a CSV or XLSX report; the requester is sent a download link once it is built (requires bearer token) */
+  createReport(body: CreateReportRequest): Promise<ApiResponse<Report>> {
+    return this.send("POST", `/api/admin/reports`, undefined, body);
+  }
+
+  /** Status of a report, with a fresh download link once it is ready (requires bearer token) */
+  getReport(id: string): Promise<ApiResponse<Report>> {
+    return this.send("GET", `/api/admin/reports/${encodeURIComponent(id)}`, undefined);
+  }
+
+  /** Hits, stale serves, misses and background refreshes of each cached route (requires bearer token) */
+  getResponseCacheStats(): Promise<ApiResponse<ResponseCacheReport>> {
+    return this.send("GET", `/api/admin/response-cache`, undefined);
+  }
+
+  /** Routes served by this instance, with their handlers (requires bearer token) */
+  listRoutes(): Promise<ApiResponse<RoutesResponse>> {
+    return this.send("GET", `/api/admin/routes`, undefined);
+  }
+
+  /** Nodes, key shares and rebalances of each shard ring (requires bearer token) */
+  listShards(): Promise<ApiResponse<ShardsResponse>> {
+    return this.send("GET", `/api/admin/shards`, undefined);
+  }
+
+  /** Revoke every session of a user (requires bearer token) */
+  revokeUserSessions(id: string): Promise<ApiResponse<RevokeSessionsResponse>> {
+    return this.send("DELETE", `/api/admin/users/${encodeURIComponent(id)}/sessions`, undefined);
+  }
+
+  /** Active sessions and refresh tokens of a user (requires bearer token) */
+  listUserSessions(id: string): Promise<ApiResponse<SessionsResponse>> {
+    return this.send("GET", `/api/admin/users/${encodeURIComponent(id)}/sessions`, undefined);
+  }
+
+  /** Revoke one session of a user (requires bearer token) */
+  revokeUserSession(id: string, sessionId: string): Promise<ApiResponse<RevokeSessionsResponse>> {
+    return this.send("DELETE", `/api/admin/users/${encodeURIComponent(id)}/sessions/${encodeURIComponent(sessionId)}`, undefined);
+  }
+
+  /** CSRF token for writes authenticated by the session cookie */
+  issueCsrfToken(): Promise<ApiResponse<CsrfTokenResponse>> {
+    return this.send("GET", `/api/auth/csrf`, undefined);
+  }
+
+  /** Sign in with an email and password */
+  signIn(body: SignInRequest): Promise<ApiResponse<SignInResponse>> {
+    return this.send("POST", `/api/auth/login`, undefined, body);
+  }
+
+  /** Events past a cursor, waiting up to 25s for the next one */
+  pollEvents(query: { cursor?: string; topics?: string; wait?: number } = {}): Promise<ApiResponse<EventPoll>> {
+    return this.send("GET", `/api/events/poll`, query);
+  }
+
+  /** Health check */
+  healthCheck(): Promise<ApiResponse<HealthResponse>> {
+    return this.send("GET", `/api/health`, undefined);
+  }
+
+  /** Last background probe result for each dependency */
+  getDependencies(): Promise<ApiResponse<DependenciesResponse>> {
+    return this.send("GET", `/api/health/dependencies`, undefined);
+  }
+
+  /** Service version and deployment metadata */
+  getInfo(): Promise<ApiResponse<InfoResponse>> {
+    return this.send("GET", `/api/info`, undefined);
+  }
+
+  /** Liveness check */
+  livenessCheck(): Promise<ApiResponse<LiveResponse>> {
+    return this.send("GET", `/api/live`, undefined);
+  }
+
+  /** Approve or deny the device showing a user code */
+  verifyDevice(body: DeviceVerificationRequest): Promise<ApiResponse<DeviceVerificationResponse>> {
+    return this.send("POST", `/api/oidc/device`, undefined, body);
+  }
+
+  /** Client and scopes of the device showing a user code */
+  describeDevice(userCode: string): Promise<ApiResponse<PendingDeviceResponse>> {
+    return this.send("GET", `/api/oidc/device/${encodeURIComponent(userCode)}`, undefined);
+  }
+
+  /** Create product */
+  createProduct(body: CreateProductRequest): Promise<ApiResponse<Product>> {
+    return this.send("POST", `/api/products`, undefined, body);
+  }
+
+  /** Get product by ID */
+  getProduct(id: string): Promise<ApiResponse<Product>> {
+    return this.send("GET", `/api/products/${encodeURIComponent(id)}`, undefined);
+  }
+
+  /** Readiness check */
+  readinessCheck(): Promise<ApiResponse<ReadyResponse>> {
+    return this.send("GET", `/api/ready`, undefined);
+  }
+
+  /** List users (with pagination) */
+  listUsers(query: { page?: number; limit?: number; exact?: boolean; include?: string } = {}): Promise<ApiResponse<ListUsersResponse>> {
+    return this.send("GET", `/api/users`, query);
+  }
+
+  /** Create user */
+  createUser(body: CreateUserRequest): Promise<ApiResponse<User>> {
+    return this.send("POST", `/api/users`, undefined, body);
+  }
+
+  /** Delete user (not implemented) (requires bearer token) */
+  deleteUser(id: string): Promise<ApiResponse<unknown>> {
+    return this.send("DELETE", `/api/users/${encodeURIComponent(id)}`, undefined);
+  }
+
+  /** Get user by ID (requires bearer token) */
+  getUser(id: string): Promise<ApiResponse<User>> {
+    return this.send("GET", `/api/users/${encodeURIComponent(id)}`, undefined);
+  }
+
+  /** Update the fields present; metadata is merge-patched (requires bearer token) */
+  patchUser(id: string, body: PatchUserRequest): Promise<ApiResponse<User>> {
+    return this.send("PATCH", `/api/users/${encodeURIComponent(id)}`, undefined, body);
+  }
+
+  /** Replace user (not implemented) (requires bearer token) */
+  updateUser(id: string, body: UpdateUserRequest): Promise<ApiResponse<User>> {
+    return this.send("PUT", `/api/users/${encodeURIComponent(id)}`, undefined, body);
+  }
+
+  /** The user's profile with their active sessions (requires bearer token) */
+  getUserOverview(id: string): Promise<ApiResponse<UserOverviewResponse>> {
+    return this.send("GET", `/api/users/${encodeURIComponent(id)}/overview`, undefined);
+  }
+
+  /** Whether the user is online, and when they were last seen */
+  getUserPresence(id: string): Promise<ApiResponse<UserPresence>> {
+    return this.send("GET", `/api/users/${encodeURIComponent(id)}/presence`, undefined);
+  }
+}
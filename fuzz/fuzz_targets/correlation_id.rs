@@ -0,0 +1,29 @@
+#![no_main]
+
+use axum::http::{HeaderMap, HeaderValue};
+use libfuzzer_sys::fuzz_target;
+use rust_boilerplate::middleware::extract_or_generate_correlation_id;
+
+const HEADERS: [&str; 5] = [
+    "x-correlation-id",
+    "x-request-id",
+    "x-trace-id",
+    "request-id",
+    "correlation-id",
+];
+
+// First byte picks the header, the rest is its raw value
+fuzz_target!(|data: &[u8]| {
+    let Some((selector, value)) = data.split_first() else { return };
+    let Ok(value) = HeaderValue::from_bytes(value) else { return };
+
+    let mut headers = HeaderMap::new();
+    headers.insert(HEADERS[*selector as usize % HEADERS.len()], value.clone());
+
+    let correlation_id = extract_or_generate_correlation_id(&headers);
+    match value.to_str() {
+        Ok(expected) => assert_eq!(correlation_id, expected),
+        // Non-ASCII values are ignored and a fresh UUID is generated
+        Err(_) => assert_eq!(correlation_id.len(), 36),
+    }
+});
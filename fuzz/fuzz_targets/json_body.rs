@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_boilerplate::delivery::parse_json;
+
+fuzz_target!(|data: &[u8]| {
+    // Errors are fine, panics are not; anything accepted must re-serialize to parseable JSON
+    if let Ok(value) = parse_json::<serde_json::Value>(data) {
+        let bytes = serde_json::to_vec(&value).unwrap();
+        parse_json::<serde_json::Value>(&bytes).unwrap();
+    }
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use axum::http::Uri;
+use libfuzzer_sys::fuzz_target;
+use rust_boilerplate::middleware::{suspicious_uri_patterns, suspicious_user_agent_patterns};
+
+fuzz_target!(|data: &[u8]| {
+    let input = String::from_utf8_lossy(data);
+    suspicious_user_agent_patterns(&input);
+    suspicious_uri_patterns(&input);
+
+    // The middleware scans the parsed request URI, not the raw bytes
+    if let Ok(uri) = Uri::try_from(data) {
+        suspicious_uri_patterns(&uri.to_string());
+    }
+});
@@ -0,0 +1,12 @@
+-- Exact amount plus ISO 4217 code; decoded into `Money` by sqlx
+CREATE TYPE money_amount AS (
+    amount NUMERIC,
+    currency TEXT
+);
+
+CREATE TABLE products (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(200) NOT NULL,
+    price money_amount NOT NULL CHECK ((price).amount IS NOT NULL AND (price).currency IS NOT NULL),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,4 @@
+ALTER TABLE users ADD COLUMN metadata JSONB NOT NULL DEFAULT '{}';
+
+-- `metadata.key=value` list filters become `metadata @> '{"key": "value"}'`
+CREATE INDEX idx_users_metadata ON users USING GIN (metadata jsonb_path_ops);
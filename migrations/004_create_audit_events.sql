@@ -0,0 +1,14 @@
+-- Audit events exported from each instance's audit journal; `seq` counts within one `source`
+CREATE TABLE audit_events (
+    source TEXT NOT NULL,
+    seq BIGINT NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL,
+    level TEXT NOT NULL,
+    event TEXT NOT NULL,
+    message TEXT NOT NULL,
+    fields JSONB NOT NULL DEFAULT '{}',
+    PRIMARY KEY (source, seq)
+);
+
+CREATE INDEX idx_audit_events_recorded_at ON audit_events (recorded_at);
+CREATE INDEX idx_audit_events_event ON audit_events (event, recorded_at);
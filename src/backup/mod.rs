@@ -0,0 +1,330 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::config::Config;
+use crate::container::startup::StartupComponent;
+use crate::container::user_repository;
+use crate::domain::user::repository::UserRepository;
+use crate::infrastructure::{run_blocking, ALERT_TARGET};
+use crate::transfer::{self, ImportSummary};
+
+/// Archive header: magic, format version, key salt, AES-GCM nonce
+const MAGIC: &[u8; 8] = b"RBBACKUP";
+const FORMAT_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + 1 + SALT_LEN + NONCE_LEN;
+
+const FILE_PREFIX: &str = "backup-";
+const FILE_SUFFIX: &str = ".rbk";
+
+/// Key derivation, compression and writing of a large store take a while
+const SEAL_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Error)]
+pub enum BackupError {
+    #[error("BACKUP_PASSPHRASE is not set")]
+    MissingPassphrase,
+    #[error("not a backup archive")]
+    NotAnArchive,
+    #[error("archive format {0} is not supported")]
+    UnsupportedFormat(u8),
+    #[error("cannot decrypt the archive: wrong passphrase or corrupted file")]
+    Decrypt,
+    #[error("backup I/O failed: {0}")]
+    Io(#[from] io::Error),
+}
+
+impl From<BackupError> for io::Error {
+    fn from(err: BackupError) -> Self {
+        match err {
+            BackupError::Io(err) => err,
+            err => io::Error::other(err.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BackupSettings {
+    pub dir: PathBuf,
+    pub passphrase: String,
+    /// Newest archives kept in `dir` after each backup; 0 keeps all
+    pub retention: usize,
+}
+
+impl BackupSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            dir: PathBuf::from(&config.backup_dir),
+            passphrase: config.backup_passphrase.clone(),
+            retention: config.backup_retention,
+        }
+    }
+}
+
+/// Outcome of `backup`
+#[derive(Debug, Serialize)]
+pub struct BackupSummary {
+    pub path: String,
+    pub users: u64,
+    /// Archive size on disk
+    pub bytes: u64,
+    /// Older archives removed by the retention policy
+    pub pruned: usize,
+}
+
+/// First line of an archive's plaintext; the users follow as JSON lines in
+/// the `users export` format
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    created_at: DateTime<Utc>,
+    users: u64,
+}
+
+/// `backup`: write an archive of the configured repository to `BACKUP_DIR`
+pub async fn backup_users(config: &Config) -> Result<BackupSummary, BackupError> {
+    backup(user_repository(config).as_ref(), &BackupSettings::from_config(config)).await
+}
+
+/// `restore <file>`: save the users of an archive into the configured
+/// repository, matched by id as in `users import`
+pub async fn restore_users(config: &Config, path: &Path) -> Result<ImportSummary, BackupError> {
+    restore(user_repository(config).as_ref(), path, &config.backup_passphrase).await
+}
+
+/// Dump every user into a gzip-compressed archive encrypted with AES-256-GCM
+/// under a key derived from the passphrase with Argon2, then prune archives
+/// beyond the retention count.
+///
+/// Sessions are not included: they live only in process memory and expire
+/// on their own.
+pub async fn backup(repository: &dyn UserRepository, settings: &BackupSettings) -> Result<BackupSummary, BackupError> {
+    if settings.passphrase.is_empty() {
+        return Err(BackupError::MissingPassphrase);
+    }
+    let created_at = Utc::now();
+    let mut users = Vec::new();
+    let count = transfer::export(repository, &mut users).await?;
+    let mut plaintext = serde_json::to_vec(&Manifest { created_at, users: count }).map_err(io::Error::other)?;
+    plaintext.push(b'\n');
+    plaintext.extend_from_slice(&users);
+
+    let name = format!("{FILE_PREFIX}{}{FILE_SUFFIX}", created_at.format("%Y%m%dT%H%M%S%.3fZ"));
+    let settings = settings.clone();
+    let (path, bytes, pruned) = run_blocking("backup_seal", SEAL_TIMEOUT, move || {
+        let archive = seal(&plaintext, &settings.passphrase)?;
+        let path = store(&settings.dir, &name, &archive)?;
+        let pruned = prune(&settings.dir, settings.retention)?;
+        Ok::<_, BackupError>((path, archive.len() as u64, pruned))
+    })
+    .await
+    .map_err(io::Error::other)??;
+    Ok(BackupSummary { path: path.display().to_string(), users: count, bytes, pruned })
+}
+
+pub async fn restore(repository: &dyn UserRepository, path: &Path, passphrase: &str) -> Result<ImportSummary, BackupError> {
+    if passphrase.is_empty() {
+        return Err(BackupError::MissingPassphrase);
+    }
+    let archive = tokio::fs::read(path).await?;
+    let passphrase = passphrase.to_string();
+    let plaintext = run_blocking("backup_open", SEAL_TIMEOUT, move || open(&archive, &passphrase))
+        .await
+        .map_err(io::Error::other)??;
+
+    let manifest_end = plaintext.iter().position(|byte| *byte == b'\n').unwrap_or(plaintext.len());
+    let (manifest, users) = plaintext.split_at(manifest_end);
+    let manifest: Manifest = serde_json::from_slice(manifest).map_err(|_| BackupError::NotAnArchive)?;
+    tracing::info!(created_at = %manifest.created_at, users = manifest.users, "Restoring backup");
+    Ok(transfer::import(repository, users).await?)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key<Aes256Gcm>, BackupError> {
+    let mut key = Key::<Aes256Gcm>::default();
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| io::Error::other(err.to_string()))?;
+    Ok(key)
+}
+
+fn seal(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>, BackupError> {
+    let mut compressor = GzEncoder::new(Vec::new(), Compression::default());
+    compressor.write_all(plaintext)?;
+    let compressed = compressor.finish()?;
+
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let mut archive = Vec::with_capacity(HEADER_LEN + compressed.len() + 16);
+    archive.extend_from_slice(MAGIC);
+    archive.push(FORMAT_VERSION);
+    archive.extend_from_slice(&salt);
+    archive.extend_from_slice(&nonce);
+
+    // The header is authenticated too, so it can't be swapped between archives
+    let cipher = Aes256Gcm::new(&derive_key(passphrase, &salt)?);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: &compressed, aad: &archive })
+        .map_err(|_| io::Error::other("encryption failed"))?;
+    archive.extend_from_slice(&ciphertext);
+    Ok(archive)
+}
+
+fn open(archive: &[u8], passphrase: &str) -> Result<Vec<u8>, BackupError> {
+    if archive.len() < HEADER_LEN || &archive[..MAGIC.len()] != MAGIC {
+        return Err(BackupError::NotAnArchive);
+    }
+    let version = archive[MAGIC.len()];
+    if version != FORMAT_VERSION {
+        return Err(BackupError::UnsupportedFormat(version));
+    }
+    let (header, ciphertext) = archive.split_at(HEADER_LEN);
+    let salt = &header[MAGIC.len() + 1..MAGIC.len() + 1 + SALT_LEN];
+    let nonce = Nonce::from_slice(&header[HEADER_LEN - NONCE_LEN..]);
+
+    let cipher = Aes256Gcm::new(&derive_key(passphrase, salt)?);
+    let compressed = cipher
+        .decrypt(nonce, Payload { msg: ciphertext, aad: header })
+        .map_err(|_| BackupError::Decrypt)?;
+    let mut plaintext = Vec::new();
+    GzDecoder::new(compressed.as_slice()).read_to_end(&mut plaintext)?;
+    Ok(plaintext)
+}
+
+/// Write `archive` to `dir` as `name`. It goes beside the archive first and is
+/// renamed, so a crash never leaves a truncated backup.
+fn store(dir: &Path, name: &str, archive: &[u8]) -> io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(name);
+    let partial = path.with_extension("partial");
+    let mut file = std::fs::File::create(&partial)?;
+    file.write_all(archive)?;
+    file.sync_all()?;
+    std::fs::rename(&partial, &path)?;
+    Ok(path)
+}
+
+/// Remove all but the newest `retention` archives. Names embed the creation
+/// time, so they sort chronologically.
+fn prune(dir: &Path, retention: usize) -> io::Result<usize> {
+    if retention == 0 {
+        return Ok(0);
+    }
+    let mut archives: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(FILE_PREFIX) && name.ends_with(FILE_SUFFIX))
+        })
+        .collect();
+    archives.sort();
+    let excess = archives.len().saturating_sub(retention);
+    for path in &archives[..excess] {
+        std::fs::remove_file(path)?;
+    }
+    Ok(excess)
+}
+
+/// Takes a backup every `interval` while the server runs, through the
+/// server's own repository, so it also works with a redb file the server
+/// holds open. Failures are alerted and retried at the next interval.
+pub struct BackupScheduler {
+    repository: Arc<dyn UserRepository>,
+    settings: BackupSettings,
+    interval: Duration,
+}
+
+impl BackupScheduler {
+    pub fn new(repository: Arc<dyn UserRepository>, settings: BackupSettings, interval: Duration) -> Self {
+        Self { repository, settings, interval }
+    }
+}
+
+#[async_trait]
+impl StartupComponent for BackupScheduler {
+    fn name(&self) -> &'static str {
+        "scheduled_backups"
+    }
+
+    fn depends_on(&self) -> &'static [&'static str] {
+        &["user_repository"]
+    }
+
+    async fn start(&self) -> Result<(), String> {
+        if self.settings.passphrase.is_empty() {
+            return Err(BackupError::MissingPassphrase.to_string());
+        }
+        let repository = self.repository.clone();
+        let settings = self.settings.clone();
+        let interval = self.interval;
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+            loop {
+                ticker.tick().await;
+                match backup(repository.as_ref(), &settings).await {
+                    Ok(summary) => tracing::info!(
+                        path = %summary.path,
+                        users = summary.users,
+                        bytes = summary.bytes,
+                        pruned = summary.pruned,
+                        "Scheduled backup written"
+                    ),
+                    Err(err) => tracing::error!(
+                        target: ALERT_TARGET,
+                        alert = "backup_failed",
+                        error = %err,
+                        "Scheduled backup failed"
+                    ),
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user::entities::User;
+    use crate::domain::user::repository::InMemoryUserRepository;
+
+    #[tokio::test]
+    async fn backups_round_trip_and_are_pruned_to_the_retention() {
+        let dir = std::env::temp_dir().join(format!("backups-{}", uuid::Uuid::new_v4()));
+        let settings = BackupSettings { dir: dir.clone(), passphrase: "secret".to_string(), retention: 2 };
+        let source = InMemoryUserRepository::new_with_users(
+            (0..3).map(|i| User::new(format!("user{i}@example.com"), "hash".to_string())).collect(),
+        );
+
+        let mut summaries = Vec::new();
+        for _ in 0..3 {
+            summaries.push(backup(&source, &settings).await.unwrap());
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+        assert_eq!(summaries[2].users, 3);
+        assert_eq!(summaries[2].pruned, 1);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+        let latest = Path::new(&summaries[2].path);
+        let target = InMemoryUserRepository::new();
+        assert!(matches!(restore(&target, latest, "wrong").await, Err(BackupError::Decrypt)));
+        let restored = restore(&target, latest, "secret").await.unwrap();
+        assert_eq!(restored, ImportSummary { imported: 3, conflicts: 0 });
+        assert!(target.exists_by_email("user2@example.com").await.unwrap());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
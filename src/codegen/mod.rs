@@ -0,0 +1,310 @@
+pub mod rust;
+pub mod typescript;
+
+// The committed Rust client, compiled into the test build so it is exercised against the real router
+#[cfg(test)]
+#[allow(dead_code)]
+#[path = "../../clients/rust/src/lib.rs"]
+mod generated_client;
+
+use serde_json::Value;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A generated file, relative to the output directory
+pub struct GeneratedFile {
+    pub path: &'static str,
+    pub contents: String,
+}
+
+/// Generate every client SDK from an OpenAPI document
+pub fn generate_clients(spec: &Value) -> Vec<GeneratedFile> {
+    let api = ApiDescription::from_spec(spec);
+    let mut files = rust::generate(&api);
+    files.extend(typescript::generate(&api));
+    files
+}
+
+/// `generate-clients [out_dir]` subcommand
+pub fn write_clients(spec: &Value, out_dir: &Path) -> io::Result<()> {
+    for file in generate_clients(spec) {
+        let path = out_dir.join(file.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, file.contents)?;
+        println!("generated {}", path.display());
+    }
+    Ok(())
+}
+
+/// The subset of OpenAPI the generators understand
+pub struct ApiDescription {
+    pub schemas: Vec<Schema>,
+    pub operations: Vec<Operation>,
+}
+
+pub struct Schema {
+    pub name: String,
+    /// None for free-form objects
+    pub fields: Option<Vec<Field>>,
+}
+
+pub struct Field {
+    pub name: String,
+    pub field_type: FieldType,
+    pub required: bool,
+    /// Description of a deprecated field
+    pub deprecated: Option<String>,
+}
+
+pub enum FieldType {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Any,
+    Ref(String),
+    Array(Box<FieldType>),
+}
+
+pub struct Operation {
+    pub operation_id: String,
+    pub method: String,
+    pub path: String,
+    pub summary: String,
+    pub path_params: Vec<String>,
+    pub query_params: Vec<Field>,
+    pub body: Option<String>,
+    pub data: Option<String>,
+    pub requires_auth: bool,
+    /// Description of a deprecated operation
+    pub deprecated: Option<String>,
+}
+
+impl ApiDescription {
+    pub fn from_spec(spec: &Value) -> Self {
+        let schemas = spec["components"]["schemas"]
+            .as_object()
+            .map(|schemas| {
+                schemas
+                    .iter()
+                    .map(|(name, schema)| Schema {
+                        name: name.clone(),
+                        fields: schema["properties"].as_object().map(|properties| {
+                            let required: Vec<&str> = schema["required"]
+                                .as_array()
+                                .map(|required| required.iter().filter_map(Value::as_str).collect())
+                                .unwrap_or_default();
+                            properties
+                                .iter()
+                                .map(|(field, property)| Field {
+                                    name: field.clone(),
+                                    field_type: FieldType::from_schema(property),
+                                    required: required.contains(&field.as_str()),
+                                    deprecated: deprecation(property),
+                                })
+                                .collect()
+                        }),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut operations = Vec::new();
+        if let Some(paths) = spec["paths"].as_object() {
+            for (path, methods) in paths {
+                let Some(methods) = methods.as_object() else { continue };
+                for (method, operation) in methods {
+                    operations.push(Operation::from_spec(path, method, operation));
+                }
+            }
+        }
+
+        Self { schemas, operations }
+    }
+}
+
+impl Operation {
+    fn from_spec(path: &str, method: &str, operation: &Value) -> Self {
+        let parameters = operation["parameters"].as_array().cloned().unwrap_or_default();
+        let path_params = parameters
+            .iter()
+            .filter(|parameter| parameter["in"] == "path")
+            .filter_map(|parameter| parameter["name"].as_str().map(str::to_string))
+            .collect();
+        let query_params = parameters
+            .iter()
+            .filter(|parameter| parameter["in"] == "query")
+            .map(|parameter| Field {
+                name: parameter["name"].as_str().unwrap_or_default().to_string(),
+                field_type: FieldType::from_schema(&parameter["schema"]),
+                required: parameter["required"].as_bool().unwrap_or(false),
+                deprecated: deprecation(parameter),
+            })
+            .collect();
+
+        Self {
+            operation_id: operation["operationId"].as_str().unwrap_or_default().to_string(),
+            method: method.to_uppercase(),
+            path: path.to_string(),
+            summary: operation["summary"].as_str().unwrap_or_default().to_string(),
+            path_params,
+            query_params,
+            body: ref_name(&operation["requestBody"]["content"]["application/json"]["schema"]),
+            data: ref_name(
+                &success_response(operation)["content"]["application/json"]["schema"]["properties"]["data"],
+            ),
+            requires_auth: requires_auth(operation),
+            deprecated: deprecation(operation),
+        }
+    }
+}
+
+impl FieldType {
+    fn from_schema(schema: &Value) -> Self {
+        if let Some(name) = ref_name(schema) {
+            return FieldType::Ref(name);
+        }
+        match schema["type"].as_str() {
+            Some("string") => FieldType::String,
+            Some("integer") => FieldType::Integer,
+            Some("number") => FieldType::Number,
+            Some("boolean") => FieldType::Boolean,
+            Some("array") => FieldType::Array(Box::new(FieldType::from_schema(&schema["items"]))),
+            _ => FieldType::Any,
+        }
+    }
+}
+
+/// The description of a `deprecated` operation, parameter or property
+fn deprecation(item: &Value) -> Option<String> {
+    (item["deprecated"] == true).then(|| item["description"].as_str().unwrap_or("Deprecated.").to_string())
+}
+
+/// Whether the operation has security requirements and none of them is
+/// empty; an empty one means anonymous callers are let in too
+pub fn requires_auth(operation: &Value) -> bool {
+    operation["security"]
+        .as_array()
+        .is_some_and(|requirements| !requirements.iter().any(|requirement| requirement.as_object().is_some_and(|schemes| schemes.is_empty())))
+}
+
+/// The 200 response, or the 201 or 202 of operations that create or queue something
+fn success_response(operation: &Value) -> &Value {
+    ["200", "201", "202"]
+        .iter()
+        .find_map(|status| operation["responses"].get(*status))
+        .unwrap_or(&Value::Null)
+}
+
+fn ref_name(schema: &Value) -> Option<String> {
+    schema["$ref"]
+        .as_str()
+        .and_then(|reference| reference.rsplit('/').next())
+        .map(str::to_string)
+}
+
+/// listUsers -> list_users
+pub fn snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, ch) in name.chars().enumerate() {
+        if ch.is_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.extend(ch.to_lowercase());
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+/// total_exact -> totalExact
+pub fn camel_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut upper = false;
+    for ch in name.chars() {
+        if ch == '_' {
+            upper = true;
+        } else if upper {
+            out.extend(ch.to_uppercase());
+            upper = false;
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delivery::openapi_spec;
+    use std::path::PathBuf;
+
+    /// The committed clients/ directory must match what the generator produces
+    #[test]
+    fn committed_clients_are_up_to_date() {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("clients");
+        for file in generate_clients(&openapi_spec()) {
+            let committed = fs::read_to_string(root.join(file.path)).unwrap_or_default();
+            assert!(
+                committed == file.contents,
+                "clients/{} is stale; run `cargo run -- generate-clients`",
+                file.path
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn generated_rust_client_round_trips_against_app() {
+        let mut config = crate::config::Config::from_env();
+        config.admin_api_token = "client-admin".to_string();
+        // Started as `main` does, so the queue workers are attached and the checks pass
+        let container = crate::container::AppContainer::new(&config);
+        container.startup.start_all().await.unwrap();
+        let app = crate::delivery::create_app(&container);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = generated_client::Client::new(format!("http://{}", addr)).with_bearer_token("client-admin");
+
+        let health = client.health_check().await.unwrap();
+        assert!(health.success);
+        assert_eq!(health.data.unwrap().status, "healthy");
+
+        let created = client
+            .create_user(&generated_client::CreateUserRequest {
+                email: "client@example.com".to_string(),
+                password: "password123".to_string(),
+                metadata: None,
+            })
+            .await
+            .unwrap()
+            .data
+            .unwrap();
+        assert_eq!(created.email, "client@example.com");
+
+        let fetched = client.get_user(&created.id).await.unwrap().data.unwrap();
+        assert_eq!(fetched.id, created.id);
+
+        let listed = client.list_users(Some(1), Some(10), Some(true), None).await.unwrap();
+        assert_eq!(listed.data.unwrap().total, 1);
+        assert_eq!(listed.meta.unwrap().total_exact, Some(true));
+
+        let missing = client.get_user(&uuid::Uuid::new_v4().to_string()).await.unwrap();
+        assert!(!missing.success);
+        assert_eq!(missing.error.unwrap().code, "NOT_FOUND");
+    }
+
+    #[test]
+    fn every_operation_has_an_id() {
+        let api = ApiDescription::from_spec(&openapi_spec());
+        assert!(!api.operations.is_empty());
+        assert!(api.operations.iter().all(|operation| !operation.operation_id.is_empty()));
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt::Write;
+
+use super::{snake_case, ApiDescription, FieldType, GeneratedFile, Operation};
+
+const CARGO_TOML: &str = r#"[package]
+name = "rust-boilerplate-client"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+reqwest = { version = "0.12", default-features = false, features = ["rustls-tls", "json"] }
+serde = { version = "1.0", features = ["derive"] }
+serde_json = "1.0"
+"#;
+
+const PRELUDE: &str = r#"//! Typed client for the rust-boilerplate API.
+//!
+//! Generated by `cargo run -- generate-clients` from the OpenAPI spec. Do not edit.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug)]
+pub enum ClientError {
+    Http(reqwest::Error),
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Http(err) => write!(f, "HTTP error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(err: reqwest::Error) -> Self {
+        ClientError::Http(err)
+    }
+}
+
+/// Standard response envelope
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiResponse<T> {
+    pub success: bool,
+    pub data: Option<T>,
+    pub error: Option<ApiError>,
+    pub meta: Option<Meta>,
+}
+"#;
+
+const CLIENT: &str = r#"
+pub struct Client {
+    base_url: String,
+    http: reqwest::Client,
+    bearer_token: Option<String>,
+}
+
+impl Client {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+            bearer_token: None,
+        }
+    }
+
+    pub fn with_bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.bearer_token = Some(token.into());
+        self
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<ApiResponse<T>, ClientError> {
+        let request = match &self.bearer_token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        };
+        Ok(request.send().await?.json().await?)
+    }
+"#;
+
+pub fn generate(api: &ApiDescription) -> Vec<GeneratedFile> {
+    let mut lib = String::from(PRELUDE);
+
+    for schema in &api.schemas {
+        lib.push('\n');
+        match &schema.fields {
+            None => {
+                writeln!(lib, "pub type {} = serde_json::Value;", schema.name).unwrap();
+            }
+            Some(fields) => {
+                lib.push_str("#[derive(Debug, Clone, Serialize, Deserialize)]\n");
+                writeln!(lib, "pub struct {} {{", schema.name).unwrap();
+                for field in fields {
+                    let field_type = rust_type(&field.field_type);
+                    if let Some(deprecated) = &field.deprecated {
+                        writeln!(lib, "    /// {}", deprecated).unwrap();
+                    }
+                    let field_name = field_ident(&field.name);
+                    if field.required {
+                        writeln!(lib, "    pub {}: {},", field_name, field_type).unwrap();
+                    } else {
+                        lib.push_str("    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n");
+                        writeln!(lib, "    pub {}: Option<{}>,", field_name, field_type).unwrap();
+                    }
+                }
+                lib.push_str("}\n");
+            }
+        }
+    }
+
+    lib.push_str(CLIENT);
+    for operation in &api.operations {
+        lib.push('\n');
+        write_operation(&mut lib, operation);
+    }
+    lib.push_str("}\n");
+
+    vec![
+        GeneratedFile { path: "rust/Cargo.toml", contents: CARGO_TOML.to_string() },
+        GeneratedFile { path: "rust/src/lib.rs", contents: lib },
+    ]
+}
+
+fn write_operation(out: &mut String, operation: &Operation) {
+    let mut args = vec!["&self".to_string()];
+    for param in &operation.path_params {
+        args.push(format!("{}: &str", param));
+    }
+    for param in &operation.query_params {
+        args.push(format!("{}: Option<{}>", param.name, rust_type(&param.field_type)));
+    }
+    if let Some(body) = &operation.body {
+        args.push(format!("body: &{}", body));
+    }
+    let data = operation.data.as_deref().unwrap_or("serde_json::Value");
+
+    writeln!(out, "    /// {}", operation.summary).unwrap();
+    if operation.requires_auth {
+        out.push_str("    ///\n    /// Requires a bearer token, see `Client::with_bearer_token`.\n");
+    }
+    if let Some(deprecated) = &operation.deprecated {
+        writeln!(out, "    ///\n    /// {}", deprecated).unwrap();
+    }
+    writeln!(
+        out,
+        "    pub async fn {}({}) -> Result<ApiResponse<{}>, ClientError> {{",
+        snake_case(&operation.operation_id),
+        args.join(", "),
+        data
+    )
+    .unwrap();
+
+    let mut path = operation.path.clone();
+    for param in &operation.path_params {
+        path = path.replace(&format!("{{{}}}", param), "{}");
+    }
+    let mut url_args = vec!["self.base_url".to_string()];
+    url_args.extend(operation.path_params.iter().cloned());
+    writeln!(
+        out,
+        "        let url = format!(\"{{}}{}\", {});",
+        path,
+        url_args.join(", ")
+    )
+    .unwrap();
+
+    write!(out, "        let request = self.http.{}(url)", operation.method.to_lowercase()).unwrap();
+    if operation.body.is_some() {
+        out.push_str(".json(body)");
+    }
+    out.push_str(";\n");
+
+    if !operation.query_params.is_empty() {
+        out.push_str("        let mut query: Vec<(&str, String)> = Vec::new();\n");
+        for param in &operation.query_params {
+            writeln!(
+                out,
+                "        if let Some(value) = {} {{\n            query.push((\"{}\", value.to_string()));\n        }}",
+                param.name, param.name
+            )
+            .unwrap();
+        }
+        out.push_str("        let request = request.query(&query);\n");
+    }
+
+    out.push_str("        self.send(request).await\n    }\n");
+}
+
+/// Fields named after Rust keywords become raw identifiers; serde still uses the bare name
+fn field_ident(name: &str) -> String {
+    match name {
+        "type" | "ref" | "match" | "move" | "self" | "use" | "where" | "impl" | "fn" | "mod" | "loop" | "in" => {
+            format!("r#{name}")
+        }
+        _ => name.to_string(),
+    }
+}
+
+fn rust_type(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::String => "String".to_string(),
+        FieldType::Integer => "i64".to_string(),
+        FieldType::Number => "f64".to_string(),
+        FieldType::Boolean => "bool".to_string(),
+        FieldType::Any => "serde_json::Value".to_string(),
+        FieldType::Ref(name) => name.clone(),
+        FieldType::Array(items) => format!("Vec<{}>", rust_type(items)),
+    }
+}
@@ -0,0 +1,145 @@
+use std::fmt::Write;
+
+use super::{camel_case, ApiDescription, FieldType, GeneratedFile, Operation};
+
+const PRELUDE: &str = r#"// Typed client for the rust-boilerplate API.
+//
+// Generated by `cargo run -- generate-clients` from the OpenAPI spec. Do not edit.
+
+export interface ApiResponse<T> {
+  success: boolean;
+  data: T | null;
+  error: ApiError | null;
+  meta: Meta | null;
+}
+"#;
+
+const CLIENT: &str = r#"
+export class ApiClient {
+  private readonly baseUrl: string;
+
+  constructor(baseUrl: string, private readonly bearerToken?: string) {
+    this.baseUrl = baseUrl.replace(/\/+$/, "");
+  }
+
+  private async send<T>(method: string, path: string, query?: Record<string, unknown>, body?: unknown): Promise<ApiResponse<T>> {
+    const url = new URL(this.baseUrl + path);
+    for (const [key, value] of Object.entries(query ?? {})) {
+      if (value !== undefined && value !== null) {
+        url.searchParams.set(key, String(value));
+      }
+    }
+
+    const headers: Record<string, string> = {};
+    if (body !== undefined) {
+      headers["Content-Type"] = "application/json";
+    }
+    if (this.bearerToken) {
+      headers["Authorization"] = `Bearer ${this.bearerToken}`;
+    }
+
+    const response = await fetch(url, {
+      method,
+      headers,
+      body: body === undefined ? undefined : JSON.stringify(body),
+    });
+    return (await response.json()) as ApiResponse<T>;
+  }
+"#;
+
+pub fn generate(api: &ApiDescription) -> Vec<GeneratedFile> {
+    let mut out = String::from(PRELUDE);
+
+    for schema in &api.schemas {
+        out.push('\n');
+        match &schema.fields {
+            None => {
+                writeln!(out, "export type {} = Record<string, unknown>;", schema.name).unwrap();
+            }
+            Some(fields) => {
+                writeln!(out, "export interface {} {{", schema.name).unwrap();
+                for field in fields {
+                    let optional = if field.required { "" } else { "?" };
+                    if let Some(deprecated) = &field.deprecated {
+                        writeln!(out, "  /** @deprecated {} */", deprecated).unwrap();
+                    }
+                    writeln!(out, "  {}{}: {};", field.name, optional, ts_type(&field.field_type)).unwrap();
+                }
+                out.push_str("}\n");
+            }
+        }
+    }
+
+    out.push_str(CLIENT);
+    for operation in &api.operations {
+        out.push('\n');
+        write_operation(&mut out, operation);
+    }
+    out.push_str("}\n");
+
+    vec![GeneratedFile { path: "typescript/index.ts", contents: out }]
+}
+
+fn write_operation(out: &mut String, operation: &Operation) {
+    let mut args: Vec<String> = operation
+        .path_params
+        .iter()
+        .map(|param| format!("{}: string", camel_case(param)))
+        .collect();
+    if let Some(body) = &operation.body {
+        args.push(format!("body: {}", body));
+    }
+    if !operation.query_params.is_empty() {
+        let fields: Vec<String> = operation
+            .query_params
+            .iter()
+            .map(|param| format!("{}?: {}", param.name, ts_type(&param.field_type)))
+            .collect();
+        args.push(format!("query: {{ {} }} = {{}}", fields.join("; ")));
+    }
+    let data = operation.data.as_deref().unwrap_or("unknown");
+
+    let mut path = operation.path.clone();
+    for param in &operation.path_params {
+        path = path.replace(
+            &format!("{{{}}}", param),
+            &format!("${{encodeURIComponent({})}}", camel_case(param)),
+        );
+    }
+
+    let auth_note = if operation.requires_auth { " (requires bearer token)" } else { "" };
+    match &operation.deprecated {
+        Some(deprecated) => {
+            writeln!(out, "  /** {}{}\n   * @deprecated {} */", operation.summary, auth_note, deprecated).unwrap()
+        }
+        None => writeln!(out, "  /** {}{} */", operation.summary, auth_note).unwrap(),
+    }
+    writeln!(
+        out,
+        "  {}({}): Promise<ApiResponse<{}>> {{",
+        camel_case(&operation.operation_id),
+        args.join(", "),
+        data
+    )
+    .unwrap();
+    let query = if operation.query_params.is_empty() { "undefined" } else { "query" };
+    let body = if operation.body.is_some() { ", body" } else { "" };
+    writeln!(
+        out,
+        "    return this.send(\"{}\", `{}`, {}{});",
+        operation.method, path, query, body
+    )
+    .unwrap();
+    out.push_str("  }\n");
+}
+
+fn ts_type(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::String => "string".to_string(),
+        FieldType::Integer | FieldType::Number => "number".to_string(),
+        FieldType::Boolean => "boolean".to_string(),
+        FieldType::Any => "unknown".to_string(),
+        FieldType::Ref(name) => name.clone(),
+        FieldType::Array(items) => format!("{}[]", ts_type(items)),
+    }
+}
@@ -0,0 +1,217 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::net::SocketAddr;
+use std::time::Instant;
+
+use super::lint::{lint_config, ConfigFinding};
+use super::startup::ComponentTiming;
+use crate::config::Config;
+use crate::delivery::MountedRoute;
+use crate::domain::user::entities::User;
+use crate::infrastructure::Versioned;
+
+/// Files under `migrations/`, in the order they apply
+pub const SQL_MIGRATIONS: &[&str] = &[
+    "001_create_users.sql",
+    "002_create_products.sql",
+    "003_add_user_metadata.sql",
+    "004_create_audit_events.sql",
+];
+
+/// A socket the server accepts connections on
+#[derive(Debug, Clone, Serialize)]
+pub struct Listener {
+    pub protocol: &'static str,
+    pub address: SocketAddr,
+    /// Bound with `SO_REUSEPORT` alongside the other supervised server processes
+    pub shared: bool,
+}
+
+/// Tokio runtime as built in `main`
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeReport {
+    pub worker_threads: usize,
+    pub max_blocking_threads: usize,
+    pub event_interval: u32,
+}
+
+/// Schema state of the stores this build uses
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationStatus {
+    /// `USER_REPOSITORY`
+    pub user_store: String,
+    /// Version of the user records this build writes; older records are
+    /// upcast when read
+    pub user_record_version: u32,
+    pub sql_migrations: Vec<&'static str>,
+    /// Whether the server applied `sql_migrations` itself. No store in this
+    /// build is Postgres, so they are applied out of band.
+    pub sql_applied: bool,
+}
+
+/// What this process started with, built once the server is listening.
+/// Logged once as `Boot report` and served at `/api/admin/boot-report`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BootReport {
+    pub version: &'static str,
+    pub deployment_id: String,
+    pub deployment_color: String,
+    pub region: String,
+    pub pid: u32,
+    /// Index of this process under the supervisor
+    pub worker: Option<usize>,
+    pub booted_at: DateTime<Utc>,
+    /// From `main` until the listeners were bound
+    pub boot_duration_ms: u64,
+    pub runtime: RuntimeReport,
+    /// Every setting, with secrets redacted; see `Config::redacted`
+    pub config: Map<String, Value>,
+    /// Optional features switched on by the config
+    pub features: Vec<&'static str>,
+    /// Insecure defaults the config still has; see `lint::lint_config`
+    pub config_findings: Vec<ConfigFinding>,
+    pub listeners: Vec<Listener>,
+    pub migrations: MigrationStatus,
+    /// Startup components in the order they started
+    pub components: Vec<ComponentTiming>,
+    pub routes: Vec<MountedRoute>,
+}
+
+impl BootReport {
+    pub fn new(config: &Config, components: Vec<ComponentTiming>) -> Self {
+        let worker_threads = tokio::runtime::Handle::try_current().map_or(0, |handle| handle.metrics().num_workers());
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            deployment_id: config.deployment_id.clone(),
+            deployment_color: config.deployment_color.clone(),
+            region: config.region.clone(),
+            pid: std::process::id(),
+            worker: None,
+            booted_at: Utc::now(),
+            boot_duration_ms: 0,
+            runtime: RuntimeReport {
+                worker_threads,
+                max_blocking_threads: config.runtime_max_blocking_threads,
+                event_interval: config.runtime_event_interval,
+            },
+            config: config.redacted(),
+            features: enabled_features(config),
+            config_findings: lint_config(config),
+            listeners: Vec::new(),
+            migrations: MigrationStatus {
+                user_store: config.user_repository.clone(),
+                user_record_version: User::VERSION,
+                sql_migrations: SQL_MIGRATIONS.to_vec(),
+                sql_applied: false,
+            },
+            components,
+            routes: Vec::new(),
+        }
+    }
+
+    pub fn with_worker(mut self, worker: Option<usize>) -> Self {
+        self.worker = worker;
+        self
+    }
+
+    pub fn with_listener(mut self, listener: Listener) -> Self {
+        self.listeners.push(listener);
+        self
+    }
+
+    pub fn with_routes(mut self, routes: Vec<MountedRoute>) -> Self {
+        self.routes = routes;
+        self
+    }
+
+    /// Stamp the boot time and duration, measured from `started`
+    pub fn finish(mut self, started: Instant) -> Self {
+        self.booted_at = Utc::now();
+        self.boot_duration_ms = started.elapsed().as_millis() as u64;
+        self
+    }
+
+    /// The one startup log line, with the whole report as JSON in `boot_report`
+    pub fn log(&self) {
+        let report = serde_json::to_string(self).unwrap_or_default();
+        tracing::info!(
+            deployment_id = %self.deployment_id,
+            region = %self.region,
+            boot_duration_ms = self.boot_duration_ms,
+            boot_report = %report,
+            "Boot report"
+        );
+    }
+}
+
+/// Names of the optional features `config` turns on
+pub fn enabled_features(config: &Config) -> Vec<&'static str> {
+    [
+        ("scheduled_backups", config.backup_interval_secs > 0),
+        ("email_bloom_filter", config.email_bloom_enabled),
+        ("request_dedup", config.request_dedup_window_ms > 0),
+        ("anomaly_detection", config.anomaly_window_secs > 0),
+        ("geoip", !config.geoip_city_db_path.is_empty() || !config.geoip_asn_db_path.is_empty()),
+        ("canary", config.canary_percentage > 0),
+        ("cdn_purge", matches!(config.cdn_purge_provider.as_str(), "fastly" | "cloudflare")),
+        ("ops_alerts", !config.ops_alert_webhook_url.is_empty()),
+        ("audit_export", !config.audit_journal_path.is_empty() && matches!(config.audit_export_sink.as_str(), "postgres" | "files")),
+        ("server_error_alerts", config.ops_alert_5xx_threshold > 0),
+        ("user_concurrency_limit", config.user_max_concurrent_requests > 0),
+        ("lane_admission", config.lane_capacity > 0),
+        ("impersonation_writes", config.impersonation_allow_writes),
+        ("stripe_webhooks", !config.stripe_webhook_secret.is_empty()),
+        ("github_webhooks", !config.github_webhook_secret.is_empty()),
+        ("slack_webhooks", !config.slack_signing_secret.is_empty()),
+        ("sql_explain", config.sql_explain_top > 0),
+        ("shard_client", !config.shard_nodes.is_empty()),
+        ("region_pinning", !config.region_peers.is_empty()),
+        ("event_schema_registry", !config.schema_registry_url.is_empty() && matches!(config.event_serializer.as_str(), "avro" | "protobuf")),
+        ("audit_schema_registry", !config.schema_registry_url.is_empty() && matches!(config.audit_export_format.as_str(), "avro" | "protobuf")),
+        ("wasm_plugins", cfg!(feature = "wasm-plugins") && !config.plugins_dir.is_empty()),
+        ("script_policies", cfg!(feature = "script-policies") && !config.scripts_dir.is_empty()),
+    ]
+    .into_iter()
+    .filter_map(|(feature, enabled)| enabled.then_some(feature))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_lists_features_and_redacts_secrets() {
+        let mut config = Config::from_env();
+        config.request_dedup_window_ms = 500;
+        config.email_bloom_enabled = false;
+        config.github_webhook_secret = "whsec".to_string();
+        let components = vec![ComponentTiming { component: "user_repository", duration_ms: 3 }];
+
+        let report = BootReport::new(&config, components)
+            .with_listener(Listener { protocol: "http", address: "127.0.0.1:3000".parse().unwrap(), shared: false })
+            .finish(Instant::now());
+        assert!(report.features.contains(&"request_dedup"));
+        assert!(report.features.contains(&"github_webhooks"));
+        assert!(!report.features.contains(&"email_bloom_filter"));
+        assert_eq!(report.config["github_webhook_secret"], crate::config::REDACTED);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["listeners"][0]["address"], "127.0.0.1:3000");
+        assert_eq!(json["components"][0]["component"], "user_repository");
+        assert!(!json.to_string().contains("whsec"));
+    }
+
+    #[test]
+    fn sql_migrations_match_the_migrations_directory() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("migrations");
+        let mut files: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(".sql"))
+            .collect();
+        files.sort();
+        assert_eq!(files, SQL_MIGRATIONS);
+    }
+}
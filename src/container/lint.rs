@@ -0,0 +1,196 @@
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::middleware::BODY_LOG_TARGET;
+
+/// `APP_PROFILE` values treated as production, where findings stop startup
+const PRODUCTION_PROFILES: &[&str] = &["prod", "production"];
+
+/// Admin tokens shorter than this are flagged as guessable
+const MIN_ADMIN_TOKEN_LEN: usize = 32;
+
+/// A setting left at a default that is fine locally but unsafe or lossy in
+/// production
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigFinding {
+    /// Stable name, listed in `CONFIG_LINT_ALLOW` to accept the finding
+    pub check: &'static str,
+    /// The variable to change
+    pub variable: &'static str,
+    pub message: &'static str,
+}
+
+/// Whether `APP_PROFILE` names a production profile
+pub fn is_production(config: &Config) -> bool {
+    PRODUCTION_PROFILES.contains(&config.profile.as_str())
+}
+
+/// Insecure or instance-local defaults in `config`, minus the checks named
+/// in `CONFIG_LINT_ALLOW`
+pub fn lint_config(config: &Config) -> Vec<ConfigFinding> {
+    let secret_unset = |value: &str, variable, message| (value.is_empty(), ConfigFinding { check: "secret_unset", variable, message });
+    [
+        (
+            config.user_repository == "memory",
+            ConfigFinding {
+                check: "in_memory_repository",
+                variable: "USER_REPOSITORY",
+                message: "users are kept in memory and lost on restart, and each instance sees its own",
+            },
+        ),
+        (
+            !config.admin_api_token.is_empty() && config.admin_api_token.len() < MIN_ADMIN_TOKEN_LEN,
+            ConfigFinding {
+                check: "weak_admin_token",
+                variable: "ADMIN_API_TOKEN",
+                message: "the admin token is shorter than 32 characters",
+            },
+        ),
+        secret_unset(&config.csrf_secret, "CSRF_SECRET", "a random per-process secret is used, so CSRF tokens break on restart and across instances"),
+        secret_unset(&config.report_link_secret, "REPORT_LINK_SECRET", "a random per-process secret is used, so report links break on restart and across instances"),
+        secret_unset(&config.events_resume_secret, "EVENTS_RESUME_SECRET", "a random per-process secret is used, so event streams cannot resume on another instance"),
+        (
+            (!config.oidc_clients.is_empty() || !config.oidc_device_clients.is_empty()) && config.oidc_signing_key_path.is_empty(),
+            ConfigFinding {
+                check: "ephemeral_signing_key",
+                variable: "OIDC_SIGNING_KEY_PATH",
+                message: "ID tokens are signed with a per-process key, so they stop verifying on restart and across instances",
+            },
+        ),
+        (
+            body_logging_enabled(&config.log_level),
+            ConfigFinding {
+                check: "body_logging",
+                variable: "LOG_LEVEL",
+                message: "request and response bodies, including personal data, are written to the logs",
+            },
+        ),
+        (
+            config.impersonation_allow_writes,
+            ConfigFinding {
+                check: "impersonation_writes",
+                variable: "IMPERSONATION_ALLOW_WRITES",
+                message: "impersonation tokens can change data on the user's behalf",
+            },
+        ),
+        (
+            config.plugins_fail_open,
+            ConfigFinding {
+                check: "plugins_fail_open",
+                variable: "PLUGINS_FAIL_OPEN",
+                message: "requests pass through when a plugin traps or times out",
+            },
+        ),
+        (
+            config.rate_limit_mode == "shadow",
+            ConfigFinding {
+                check: "rate_limit_shadow",
+                variable: "RATE_LIMIT_MODE",
+                message: "over-budget requests are only logged, not rejected",
+            },
+        ),
+    ]
+    .into_iter()
+    .filter(|(found, finding)| *found && !allowed(config, finding.check))
+    .map(|(_, finding)| finding)
+    .collect()
+}
+
+/// Log every finding at warn. In a production profile, return them as an
+/// error instead, so the process does not start.
+pub fn enforce(config: &Config) -> Result<(), String> {
+    let findings = lint_config(config);
+    for finding in &findings {
+        tracing::warn!(check = finding.check, variable = finding.variable, "Insecure configuration: {}", finding.message);
+    }
+    if findings.is_empty() || !is_production(config) {
+        return Ok(());
+    }
+    let mut checks: Vec<&str> = findings.iter().map(|finding| finding.check).collect();
+    checks.dedup();
+    Err(format!(
+        "APP_PROFILE={} refuses insecure settings ({}); fix them or list the checks in CONFIG_LINT_ALLOW",
+        config.profile,
+        checks.join(", ")
+    ))
+}
+
+fn allowed(config: &Config, check: &str) -> bool {
+    config.config_lint_allow.split(',').any(|allowed| allowed.trim() == check)
+}
+
+/// Whether the `LOG_LEVEL` directives turn on debug for the body log target,
+/// directly or through a global `debug`/`trace` level
+fn body_logging_enabled(log_level: &str) -> bool {
+    let verbose = |level: &str| matches!(level.trim().to_ascii_lowercase().as_str(), "debug" | "trace");
+    log_level.split(',').any(|directive| match directive.split_once('=') {
+        Some((target, level)) => target.trim() == BODY_LOG_TARGET && verbose(level),
+        None => verbose(directive),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secured() -> Config {
+        let mut config = Config::from_env();
+        config.profile = "production".to_string();
+        config.user_repository = "redb".to_string();
+        config.admin_api_token = "a".repeat(MIN_ADMIN_TOKEN_LEN);
+        config.csrf_secret = "csrf".to_string();
+        config.report_link_secret = "links".to_string();
+        config.events_resume_secret = "resume".to_string();
+        config.oidc_clients = String::new();
+        config.oidc_device_clients = String::new();
+        config.log_level = "info".to_string();
+        config.impersonation_allow_writes = false;
+        config.plugins_fail_open = false;
+        config.rate_limit_mode = "enforce".to_string();
+        config.config_lint_allow = String::new();
+        config
+    }
+
+    #[test]
+    fn flags_insecure_defaults_and_refuses_them_in_production() {
+        let mut config = secured();
+        assert!(lint_config(&config).is_empty());
+        assert!(enforce(&config).is_ok());
+
+        config.user_repository = "memory".to_string();
+        config.csrf_secret = String::new();
+        config.report_link_secret = String::new();
+        config.oidc_clients = "web=https://app.example.com/callback".to_string();
+        config.oidc_signing_key_path = String::new();
+        config.log_level = "info,http_body=debug".to_string();
+        let checks: Vec<_> = lint_config(&config).into_iter().map(|finding| (finding.check, finding.variable)).collect();
+        assert_eq!(
+            checks,
+            [
+                ("in_memory_repository", "USER_REPOSITORY"),
+                ("secret_unset", "CSRF_SECRET"),
+                ("secret_unset", "REPORT_LINK_SECRET"),
+                ("ephemeral_signing_key", "OIDC_SIGNING_KEY_PATH"),
+                ("body_logging", "LOG_LEVEL"),
+            ]
+        );
+        let err = enforce(&config).unwrap_err();
+        assert!(err.contains("in_memory_repository, secret_unset, ephemeral_signing_key, body_logging"), "{err}");
+
+        config.profile = "staging".to_string();
+        assert!(enforce(&config).is_ok());
+    }
+
+    #[test]
+    fn allow_list_accepts_findings_and_log_levels_are_parsed() {
+        let mut config = secured();
+        config.user_repository = "memory".to_string();
+        config.config_lint_allow = "in_memory_repository, rate_limit_shadow".to_string();
+        assert!(lint_config(&config).is_empty());
+
+        assert!(body_logging_enabled("debug"));
+        assert!(body_logging_enabled("warn,http_body=TRACE"));
+        assert!(!body_logging_enabled("info,sql=debug"));
+        assert!(!body_logging_enabled("http_body=info"));
+    }
+}
@@ -1,28 +1,640 @@
-use std::sync::Arc;
-use crate::domain::user::feature::UserService;
-use crate::domain::user::feature::UserServiceImpl;
-use crate::domain::user::repository::InMemoryUserRepository;
+pub mod boot;
+pub mod lint;
+pub mod startup;
+
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use crate::backup::{BackupScheduler, BackupSettings};
+use crate::config::Config;
+use crate::delivery::{DeprecationTracker, ResponseCache, RouteTable, DEPRECATIONS};
+use boot::BootReport;
+use startup::StartupGraph;
+use crate::infrastructure::{
+    AnomalyDetector, AnomalyEvaluator, AuditArchiver, AuditExporter, AuditJournal, AuditLog, CacheInvalidator, FileSink, PostgresCopySink, WarehouseSink, AuditRecord, ConfluentSchemaRegistry, RegistrySerializer, SchemaRegistration, SchemaRegistry, ConsumerMetrics, CdnPurgeClient, CloudflarePurgeClient, CpuPool, DeploymentInfo,
+    FastlyPurgeClient, GeoIp, ImpossibleTravelDetector, JsonBudget, LaneLimiter, MemoryGuard, MemorySampler,
+    NoopPurgeClient, PluginChain, RateLimitMode, RegionPinMode, RegionRouter, MethodMetrics, QueryLog, ShardRegistry, ShardedHttpClient, RateLimiter,
+};
+use crate::middleware::{Disclosure, Ownership, RateLimitBucket, ResourceKind};
+use crate::domain::health::feature::{Criticality, Degradations, DegradeMode, DependencyMonitor, DependencyMonitorStartup, HealthRegistry, MemoryProbe, ConsumerProbe};
+use crate::domain::session::feature::{CsrfTokens, ImpersonationPolicy, ImpersonationService, SignIn};
+use crate::domain::oidc::feature::{OidcProvider, SigningKey};
+use crate::domain::session::repository::{InMemorySessionStore, SessionStore};
+use crate::domain::product::repository::{InMemoryProductStore, ProductStore};
+use crate::domain::realtime::feature::{EventHub, EventRelay, EventSerializer, ResumeTokens, TopicAccess};
+use crate::domain::report::feature::{DownloadLinks, ReportService, ReportWorker};
+use crate::domain::report::repository::{ArtifactStore, InMemoryArtifactStore, LocalArtifactStore};
+use crate::domain::user::feature::{PresenceSweeper, PresenceTracker, UserOverviewService, UserOwnership, UserService};
+use crate::domain::webhook::feature::{
+    GitHubProvider, SlackProvider, StripeProvider, WebhookInbox, WebhookWorker,
+};
+use crate::domain::user::feature::{
+    EmailBloomProbe, EmailBloomWarmer, InstrumentedUserService, UserRepositoryProbe, UserRepositoryStartup, UserServiceImpl,
+};
+use crate::domain::user::repository::{
+    BloomUserRepository, CachedUserRepository, InMemoryPresenceStore, InMemoryUserRepository, MetricsUserRepository,
+    RedbUserRepository, RetryingUserRepository, TracingUserRepository, UserRepository,
+};
+
+/// `AUDIT_JOURNAL_PATH`, or `<path>.<n>` for server process `n` under the
+/// supervisor, so processes never share a journal
+pub fn audit_journal_path(config: &Config, worker: Option<usize>) -> Option<std::path::PathBuf> {
+    match (config.audit_journal_path.as_str(), worker) {
+        ("", _) => None,
+        (path, None) => Some(path.into()),
+        (path, Some(worker)) => Some(format!("{path}.{worker}").into()),
+    }
+}
+
+/// Exporter of the journal of server process `worker` to the sink picked by
+/// `AUDIT_EXPORT_SINK`; `None` without a journal or a sink
+pub fn audit_exporter(config: &Config, worker: Option<usize>) -> Option<AuditExporter> {
+    let journal = audit_journal_path(config, worker)?;
+    let sink: Arc<dyn WarehouseSink> = match config.audit_export_sink.as_str() {
+        "files" => Arc::new(FileSink::new(std::path::Path::new(&config.audit_export_dir)).with_serializer(Arc::new(audit_serializer(config)))),
+        // Lazy, so a warehouse that is down does not hold up startup
+        "postgres" => match sqlx::postgres::PgPoolOptions::new().max_connections(2).connect_lazy(&config.database_url) {
+            Ok(pool) => Arc::new(PostgresCopySink::new(pool)),
+            Err(err) => {
+                tracing::warn!(error = %err, "AUDIT_EXPORT_SINK=postgres but DATABASE_URL is invalid; audit events are not exported");
+                return None;
+            }
+        },
+        _ => return None,
+    };
+    let mut source = match config.audit_source.as_str() {
+        "" => std::env::var("HOSTNAME").unwrap_or_else(|_| "local".to_string()),
+        source => source.to_string(),
+    };
+    if let Some(worker) = worker {
+        source = format!("{source}.{worker}");
+    }
+    Some(
+        AuditExporter::new(&journal, sink, &source, config.audit_export_batch_size)
+            .with_interval(Duration::from_secs(config.audit_export_interval_secs)),
+    )
+}
+
+/// Framing of audit export files picked by `AUDIT_EXPORT_FORMAT`; JSON lines
+/// without a schema registry
+fn audit_serializer(config: &Config) -> RegistrySerializer<AuditRecord> {
+    match schema_registry(config) {
+        Some(registry) => RegistrySerializer::from_name(&config.audit_export_format, &config.audit_schema_subject).with_registry(registry),
+        None => RegistrySerializer::json(),
+    }
+}
+
+/// The registry at `SCHEMA_REGISTRY_URL`, if set
+fn schema_registry(config: &Config) -> Option<Arc<dyn SchemaRegistry>> {
+    match config.schema_registry_url.as_str() {
+        "" => None,
+        url => Some(Arc::new(ConfluentSchemaRegistry::new(url).with_credentials(&config.schema_registry_credentials))),
+    }
+}
+
+/// `AUDIT_ARCHIVE_DIR`, with a subdirectory per server process under the
+/// supervisor, since each process numbers its own journal
+fn audit_archive_dir(config: &Config) -> std::path::PathBuf {
+    let dir = std::path::PathBuf::from(&config.audit_archive_dir);
+    match crate::supervisor::worker_index() {
+        Some(worker) => dir.join(worker.to_string()),
+        None => dir,
+    }
+}
+
+/// Reader over the audit journal and its archived segments; `None` without a journal
+pub fn audit_log(config: &Config) -> Option<Arc<AuditLog>> {
+    Some(Arc::new(AuditLog::new(&audit_journal_path(config, crate::supervisor::worker_index())?, &audit_archive_dir(config))))
+}
+
+/// Archiver of aged journal records, once `AUDIT_ARCHIVE_AFTER_DAYS` is set.
+/// Takes the journal `main` opened, since only its writer may rewrite it.
+pub fn audit_archiver(config: &Config, journal: Arc<AuditJournal>) -> Option<AuditArchiver> {
+    if config.audit_archive_after_days == 0 {
+        return None;
+    }
+    let after = Duration::from_secs(config.audit_archive_after_days * 24 * 60 * 60);
+    let mut archiver = AuditArchiver::new(journal, &audit_archive_dir(config), after)
+        .with_interval(Duration::from_secs(config.audit_archive_interval_secs.max(1)));
+    // Unshipped records wait in the journal for the exporter
+    if let Some(exporter) = audit_exporter(config, crate::supervisor::worker_index()) {
+        archiver = archiver.with_exporter(exporter);
+    }
+    Some(archiver)
+}
+
+/// The user store selected by `USER_REPOSITORY`, without the caching and
+/// bloom filter layers the container puts in front of it
+pub(crate) fn user_repository(config: &Config) -> Arc<dyn UserRepository> {
+    match config.user_repository.as_str() {
+        "redb" => Arc::new(RedbUserRepository::new(&config.redb_path)),
+        _ => Arc::new(InMemoryUserRepository::new()),
+    }
+}
+
+/// The key at `OIDC_SIGNING_KEY_PATH`, or a per-process one when it is
+/// empty, unreadable, or no OpenID clients are configured
+fn oidc_signing_key(config: &Config) -> SigningKey {
+    if (config.oidc_clients.is_empty() && config.oidc_device_clients.is_empty()) || config.oidc_signing_key_path.is_empty() {
+        return SigningKey::ephemeral();
+    }
+    SigningKey::load_or_create(std::path::Path::new(&config.oidc_signing_key_path)).unwrap_or_else(|err| {
+        tracing::error!(error = %err, "Falling back to a per-process OIDC signing key");
+        SigningKey::ephemeral()
+    })
+}
 
 pub struct AppContainer {
     pub user_service: Arc<dyn UserService>,
+    pub deployment: Arc<DeploymentInfo>,
+    /// This instance's region and its peers, for `X-Region-Pin`
+    pub region: Arc<RegionRouter>,
+    pub health: Arc<HealthRegistry>,
+    /// Background-probed dependency statuses for /api/health/dependencies
+    pub dependencies: Arc<DependencyMonitor>,
+    /// Fallbacks in use while dependencies are down, shown in /api/health
+    pub degradations: Arc<Degradations>,
+    pub admin_token: Arc<str>,
+    /// Owner lookups for routes only the resource's owner may call
+    pub ownership: Arc<Ownership>,
+    pub geoip: Arc<GeoIp>,
+    /// Sign-ins report each successful login here
+    pub impossible_travel: Arc<ImpossibleTravelDetector>,
+    pub anomalies: Arc<AnomalyDetector>,
+    /// Login sessions and refresh tokens; admins can list and revoke them
+    pub sessions: Arc<dyn SessionStore>,
+    pub impersonation: Arc<ImpersonationService>,
+    /// Email and password sign-in for `/api/auth/login`
+    pub sign_in: Arc<SignIn>,
+    /// Fans out to the user service and session store for `/api/users/:id/overview`
+    pub user_overview: Arc<UserOverviewService>,
+    /// Online / last-seen per user; subscribe for changes
+    pub presence: Arc<PresenceTracker>,
+    /// Signs and checks the CSRF tokens of session-cookie requests
+    pub csrf: Arc<CsrfTokens>,
+    /// Authorization codes and tokens for the apps in `OIDC_CLIENTS`
+    pub oidc: Arc<OidcProvider>,
+    /// The example priced resource
+    pub products: Arc<dyn ProductStore>,
+    /// Inbound webhooks; domains subscribe with `webhooks.on(provider, event_type, handler)`
+    pub webhooks: Arc<WebhookInbox>,
+    /// Admin reports, queued and built in the background
+    pub reports: Arc<ReportService>,
+    /// Events pushed to `/api/events`; closes its streams on shutdown
+    pub events: Arc<EventHub>,
+    /// Who may open `/api/events` and subscribe to which topics
+    pub event_access: Arc<TopicAccess>,
+    /// Avro or Protobuf framing of events for `events.v1.registry` clients
+    pub event_serializer: Arc<EventSerializer>,
+    /// Per-client budgets for the rate-limit buckets in route policies
+    pub rate_limiter: Arc<RateLimiter>,
+    /// In-process caches and the CDN, for invalidation from the admin API
+    pub caches: Arc<CacheInvalidator>,
+    /// Shard rings, reported at `/api/admin/shards`
+    pub shards: Arc<ShardRegistry>,
+    /// Per-method metrics of the domain services
+    pub method_metrics: Arc<MethodMetrics>,
+    /// Lag, processing latency and dead letters of the message consumers
+    pub consumers: Arc<ConsumerMetrics>,
+    /// Query logging for repositories that talk to Postgres
+    pub sql_log: Arc<QueryLog>,
+    /// Client for the backends in `SHARD_NODES`, when set
+    pub shard_client: Option<Arc<ShardedHttpClient>>,
+    /// Request plugins from `PLUGINS_DIR`, filled in at startup
+    pub plugins: Arc<PluginChain>,
+    /// Shared request capacity split across the lanes in route policies
+    pub lanes: Arc<LaneLimiter>,
+    /// Dedicated threads for CPU-heavy work, with bounded per-priority queues
+    pub cpu_pool: Arc<CpuPool>,
+    /// Process memory sampling, cache shrinking and the memory readiness check
+    pub memory: Arc<MemoryGuard>,
+    /// Usage of the deprecated routes and fields in `DEPRECATIONS`
+    pub deprecations: Arc<DeprecationTracker>,
+    /// Responses of routes with a stale-while-revalidate window
+    pub response_cache: Arc<ResponseCache>,
+    /// The audit journal and its archive, when `AUDIT_JOURNAL_PATH` is set
+    pub audit_log: Option<Arc<AuditLog>>,
+    /// Stub routes are left out of the router and the served docs
+    pub hide_unimplemented_routes: bool,
+    /// `PUBLIC_BASE_URL`; absolute links start with it
+    pub public_base_url: String,
+    /// Filled by `create_app` with every route it mounts
+    pub routes: Arc<RouteTable>,
+    /// Set by `main` once the server is listening
+    pub boot_report: Arc<OnceLock<BootReport>>,
+    /// Must be started before serving; see `StartupGraph::start_all`
+    pub startup: StartupGraph,
 }
 
 impl AppContainer {
-    pub fn new() -> Self {
+    pub fn new(config: &Config) -> Self {
         // Create repository instances
-        let user_repository = Arc::new(InMemoryUserRepository::new());
+        let mut user_repository = user_repository(config);
+
+        // Components initialized in dependency order before the server accepts traffic
+        let mut startup = StartupGraph::new();
+        startup.add(Arc::new(UserRepositoryStartup::new(user_repository.clone())));
+
+        // Scheduled backups read the store directly, below the caching layers.
+        // Under the supervisor only worker 0 takes them, not one per process.
+        if config.backup_interval_secs > 0 && crate::supervisor::worker_index().unwrap_or(0) == 0 {
+            startup.add(Arc::new(BackupScheduler::new(
+                user_repository.clone(),
+                BackupSettings::from_config(config),
+                Duration::from_secs(config.backup_interval_secs),
+            )));
+        }
+
+        // Dependency checks reported by /api/health and /api/ready; domains register their own
+        let health = Arc::new(
+            HealthRegistry::new().with_timeout(Duration::from_millis(config.health_check_timeout_ms)),
+        );
+
+        // Optionally guard the signup duplicate check with a bloom filter
+        if config.email_bloom_enabled {
+            let bloom_repository = Arc::new(BloomUserRepository::new(
+                user_repository,
+                config.email_bloom_expected_items,
+                config.email_bloom_false_positive_rate,
+            ));
+            startup.add(Arc::new(EmailBloomWarmer::new(
+                bloom_repository.clone(),
+                Duration::from_secs(config.email_bloom_rebuild_interval_secs.max(1)),
+            )));
+            health.register(
+                "email_bloom_filter",
+                Criticality::NonCritical,
+                Arc::new(EmailBloomProbe::new(bloom_repository.clone())),
+            );
+            user_repository = bloom_repository;
+        }
+
+        health.register(
+            "user_repository",
+            Criticality::Critical,
+            Arc::new(UserRepositoryProbe::new(user_repository.clone())),
+        );
+
+        // Shrinks caches past the soft limit and fails readiness past the hard limit
+        let memory = Arc::new(MemoryGuard::new(
+            config.memory_soft_limit_mb,
+            config.memory_hard_limit_mb,
+            Duration::from_secs(config.memory_check_interval_secs.max(1)),
+        ));
+        health.register("memory", Criticality::Critical, Arc::new(MemoryProbe::new(memory.clone())));
+        startup.add(Arc::new(MemorySampler::new(memory.clone())));
+
+        // Lag, latency and dead letters of every message consumer; readiness fails while one is detached
+        let consumers = Arc::new(ConsumerMetrics::new());
+        health.register("message_consumers", Criticality::Critical, Arc::new(ConsumerProbe::new(consumers.clone())));
+
+        // Fallbacks switched on and off by the probe results below
+        let degradations = Arc::new(Degradations::new(
+            DegradeMode::from_name(&config.degradation_default_mode),
+            &config.degradation_modes,
+        ));
+
+        // Probe the same checks in the background so readiness and dependency status reads are instant
+        let dependencies = Arc::new(
+            DependencyMonitor::new(health.clone(), Duration::from_secs(config.dependency_probe_interval_secs.max(1)))
+                .with_jitter(Duration::from_millis(config.dependency_probe_jitter_ms))
+                .with_degradations(degradations.clone()),
+        );
+        startup.add(Arc::new(DependencyMonitorStartup::new(dependencies.clone())));
+
+        // Calls, latency and faults per service and repository method, at /api/admin/method-metrics
+        let method_metrics = Arc::new(MethodMetrics::new(Duration::from_millis(config.method_latency_budget_ms)));
+
+        // Admins can invalidate these caches, and the CDN once it is known
+        let mut caches = CacheInvalidator::new();
+        // Shard rings, from the user cache and SHARD_NODES
+        let shards = Arc::new(ShardRegistry::new());
+
+        // Stack the USER_REPOSITORY_LAYERS decorators, innermost first
+        for layer in config.user_repository_layers.split(',').map(str::trim).filter(|layer| !layer.is_empty()).rev() {
+            user_repository = match layer {
+                "cache" => {
+                    let cached_repository = Arc::new(
+                        CachedUserRepository::new(
+                            user_repository,
+                            Duration::from_secs(config.user_cache_ttl_secs),
+                            Duration::from_secs(config.user_cache_negative_ttl_secs),
+                            config.user_cache_max_entries,
+                        )
+                        .with_shards(config.user_cache_shards)
+                        .with_degradation(degradations.register("user_repository", "stale cached users by id")),
+                    );
+                    shards.register(cached_repository.ring());
+                    memory.register_reclaimer(cached_repository.clone());
+                    caches.register(cached_repository.clone());
+                    cached_repository
+                }
+                "metrics" => Arc::new(MetricsUserRepository::new(user_repository, method_metrics.clone())),
+                "retry" => Arc::new(RetryingUserRepository::new(
+                    user_repository,
+                    config.user_repository_retry_attempts,
+                    Duration::from_millis(config.user_repository_retry_backoff_ms),
+                )),
+                "tracing" => Arc::new(TracingUserRepository::new(user_repository)),
+                unknown => {
+                    tracing::warn!(layer = unknown, "Unknown USER_REPOSITORY_LAYERS entry skipped");
+                    user_repository
+                }
+            };
+        }
+
+        // CDN purge client used to invalidate edge caches on writes
+        let cdn: Arc<dyn CdnPurgeClient> = match config.cdn_purge_provider.as_str() {
+            "fastly" => Arc::new(FastlyPurgeClient::new(
+                config.cdn_service_id.clone(),
+                config.cdn_api_token.clone(),
+            )),
+            "cloudflare" => Arc::new(CloudflarePurgeClient::new(
+                config.cdn_service_id.clone(),
+                config.cdn_api_token.clone(),
+            )),
+            _ => Arc::new(NoopPurgeClient),
+        };
+        if matches!(config.cdn_purge_provider.as_str(), "fastly" | "cloudflare") {
+            caches = caches.with_cdn_purge_client(cdn.clone());
+        }
+
+        // Hashing and other CPU-heavy work runs here instead of on the runtime
+        let cpu_pool = Arc::new(CpuPool::new(
+            config.cpu_pool_threads,
+            config.cpu_pool_interactive_queue,
+            config.cpu_pool_batch_queue,
+        ));
+
+        // Reports read users straight from the repository, page by page
+        let report_users = user_repository.clone();
 
         // Create service instances with their dependencies
-        let user_service: Arc<dyn UserService> = Arc::new(UserServiceImpl::new(user_repository));
+        let user_service = Arc::new(
+            UserServiceImpl::new(user_repository, cpu_pool.clone())
+                .with_total_count_ttl(Duration::from_secs(config.user_count_cache_ttl_secs))
+                .with_cdn_purge_client(cdn)
+                .with_metadata_budget(JsonBudget::new(
+                    config.user_metadata_max_bytes,
+                    config.user_metadata_max_depth,
+                    config.user_metadata_max_keys,
+                )),
+        );
+        caches.register(user_service.clone());
+        let response_cache = Arc::new(
+            ResponseCache::new(config.response_cache_max_entries).with_windows(&config.response_cache_windows),
+        );
+        caches.register(response_cache.clone());
+        let user_service: Arc<dyn UserService> = Arc::new(InstrumentedUserService::new(user_service, method_metrics.clone()));
+
+        let ownership = Arc::new(
+            Ownership::new(Disclosure::from_name(&config.ownership_disclosure))
+                .with_resource(ResourceKind::User, Arc::new(UserOwnership::new(user_service.clone()))),
+        );
+
+        // GeoIP databases are opened at startup so a bad path fails fast
+        let geoip = Arc::new(GeoIp::new(config.geoip_city_db_path.clone(), config.geoip_asn_db_path.clone()));
+        if geoip.is_enabled() {
+            startup.add(geoip.clone());
+        }
+
+        // Per-route request baselines; windows are only closed when enabled
+        let anomalies = Arc::new(
+            AnomalyDetector::new(Duration::from_secs(config.anomaly_window_secs))
+                .with_threshold(config.anomaly_threshold),
+        );
+        if config.anomaly_window_secs > 0 {
+            startup.add(Arc::new(AnomalyEvaluator::new(anomalies.clone())));
+        }
+
+        // Login sessions, and support staff acting as a user on top of them
+        let sessions: Arc<dyn SessionStore> = Arc::new(InMemorySessionStore::new());
+        let impersonation = Arc::new(ImpersonationService::new(
+            sessions.clone(),
+            user_service.clone(),
+            chrono::Duration::seconds(config.impersonation_ttl_secs),
+            ImpersonationPolicy { allow_writes: config.impersonation_allow_writes },
+        ));
+        let user_overview = Arc::new(UserOverviewService::new(
+            user_service.clone(),
+            sessions.clone(),
+            Duration::from_millis(config.user_overview_branch_timeout_ms),
+        ));
+        let oidc = Arc::new(
+            OidcProvider::new(&config.oidc_issuer, &config.oidc_clients, oidc_signing_key(config), sessions.clone(), user_service.clone())
+                .with_code_ttl(chrono::Duration::seconds(config.oidc_code_ttl_secs))
+                .with_token_ttl(chrono::Duration::seconds(config.oidc_token_ttl_secs))
+                .with_device_clients(
+                    &config.oidc_device_clients,
+                    chrono::Duration::seconds(config.oidc_device_code_ttl_secs),
+                    config.oidc_device_poll_interval_secs,
+                ),
+        );
+        let presence_ttl = Duration::from_secs(config.presence_ttl_secs.max(1));
+        let presence = Arc::new(PresenceTracker::new(Arc::new(InMemoryPresenceStore::new(presence_ttl)), presence_ttl));
+        startup.add(Arc::new(PresenceSweeper::new(presence.clone())));
+
+        // Webhook providers are enabled by configuring their secret
+        let webhooks = Arc::new(
+            WebhookInbox::new(config.webhook_queue_capacity, Duration::from_secs(24 * 60 * 60))
+                .with_consumer_stats(consumers.consumer("webhooks", "in_process")),
+        );
+        if !config.stripe_webhook_secret.is_empty() {
+            webhooks.register_provider(Arc::new(StripeProvider::new(
+                config.stripe_webhook_secret.clone(),
+                config.webhook_tolerance_secs,
+            )));
+        }
+        if !config.github_webhook_secret.is_empty() {
+            webhooks.register_provider(Arc::new(GitHubProvider::new(config.github_webhook_secret.clone())));
+        }
+        if !config.slack_signing_secret.is_empty() {
+            webhooks.register_provider(Arc::new(SlackProvider::new(
+                config.slack_signing_secret.clone(),
+                config.webhook_tolerance_secs,
+            )));
+        }
+        startup.add(Arc::new(WebhookWorker::new(webhooks.clone())));
+
+        // Report files stay on disk unless REPORT_DIR is empty
+        let artifacts: Arc<dyn ArtifactStore> = if config.report_dir.is_empty() {
+            Arc::new(InMemoryArtifactStore::new())
+        } else {
+            Arc::new(LocalArtifactStore::new(&config.report_dir))
+        };
+        let reports = Arc::new(
+            ReportService::new(
+                report_users,
+                sessions.clone(),
+                presence.clone(),
+                artifacts,
+                DownloadLinks::new(&config.report_link_secret, Duration::from_secs(config.report_link_ttl_secs)),
+                config.report_queue_capacity,
+            )
+            .with_consumer_stats(consumers.consumer("reports", "in_process"))
+            .with_callback_hosts(&config.report_callback_hosts),
+        );
+        startup.add(Arc::new(ReportWorker::new(reports.clone())));
+        if let Some(exporter) = audit_exporter(config, crate::supervisor::worker_index()) {
+            startup.add(Arc::new(exporter.with_consumer_stats(consumers.consumer("audit_export", "warehouse"))));
+        }
+
+        // Live events, resumable across restarts through the replay snapshot
+        let mut events = EventHub::new(
+            config.events_replay_capacity,
+            ResumeTokens::new(&config.events_resume_secret, Duration::from_secs(config.events_resume_window_secs)),
+        );
+        if !config.events_replay_snapshot.is_empty() {
+            events = events.with_snapshot(&config.events_replay_snapshot);
+        }
+        let events = Arc::new(events);
+        startup.add(Arc::new(EventRelay::new(events.clone(), presence.clone())));
+        let event_access = Arc::new(TopicAccess::new(
+            ownership.clone(),
+            Arc::from(config.admin_api_token.as_str()),
+            config.events_max_subscriptions,
+        )
+        .with_allowed_origins(&config.events_allowed_origins));
+        // The schema is checked and registered at startup; without a registry events stay JSON
+        let event_serializer = Arc::new(match schema_registry(config) {
+            Some(registry) => EventSerializer::from_name(&config.event_serializer, &config.event_schema_subject).with_registry(registry),
+            None => EventSerializer::json(),
+        });
+        if event_serializer.uses_registry() {
+            startup.add(Arc::new(SchemaRegistration::new("event_schemas", event_serializer.clone())));
+        }
+
+        // Request plugins are compiled at startup so a broken one fails fast.
+        // The chain gets at least a script's own time limit on the CPU pool,
+        // so a runaway script is reported as such rather than as a pool timeout.
+        let plugin_timeout = config.plugin_timeout_ms.max(config.script_timeout_ms);
+        let plugins = Arc::new(
+            PluginChain::new(config.plugins_fail_open)
+                .with_cpu_pool(cpu_pool.clone(), Duration::from_millis(plugin_timeout)),
+        );
+        if !config.plugins_dir.is_empty() {
+            #[cfg(feature = "wasm-plugins")]
+            startup.add(Arc::new(crate::infrastructure::WasmPluginLoader::new(
+                &config.plugins_dir,
+                crate::infrastructure::WasmLimits {
+                    fuel: config.plugin_fuel,
+                    memory_bytes: config.plugin_memory_limit_mb * 1024 * 1024,
+                },
+                plugins.clone(),
+            )));
+            #[cfg(not(feature = "wasm-plugins"))]
+            tracing::warn!(dir = %config.plugins_dir, "PLUGINS_DIR is set but this build has no `wasm-plugins` feature; no plugins run");
+        }
+        if !config.scripts_dir.is_empty() {
+            #[cfg(feature = "script-policies")]
+            {
+                let limits = crate::infrastructure::ScriptLimits {
+                    max_operations: config.script_max_operations,
+                    timeout: Duration::from_millis(config.script_timeout_ms),
+                };
+                startup.add(Arc::new(crate::infrastructure::ScriptPolicyLoader::new(
+                    Arc::new(crate::infrastructure::ScriptPolicies::new(&config.scripts_dir, limits)),
+                    plugins.clone(),
+                    Duration::from_secs(config.scripts_reload_secs),
+                )));
+            }
+            #[cfg(not(feature = "script-policies"))]
+            tracing::warn!(dir = %config.scripts_dir, "SCRIPTS_DIR is set but this build has no `script-policies` feature; no scripts run");
+        }
+
+        let mut sql_log = QueryLog::new(Duration::from_millis(config.sql_slow_query_ms));
+        if config.sql_explain_top > 0 {
+            // Lazy, so the pool only connects once a slow query needs a plan
+            match sqlx::postgres::PgPoolOptions::new().max_connections(1).connect_lazy(&config.database_url) {
+                Ok(pool) => sql_log = sql_log.with_explain(pool, config.sql_explain_top),
+                Err(err) => tracing::warn!(error = %err, "SQL_EXPLAIN_TOP is set but DATABASE_URL is invalid; no plans captured"),
+            }
+        }
+        let sql_log = Arc::new(sql_log);
+
+        let shard_client = (!config.shard_nodes.is_empty()).then(|| {
+            let client = Arc::new(ShardedHttpClient::new("shard_nodes", config.shard_nodes.split(',')));
+            shards.register(client.ring());
+            client
+        });
+
+        let impossible_travel = Arc::new(ImpossibleTravelDetector::new(config.impossible_travel_max_kmh));
+        let sign_in = Arc::new(SignIn::new(
+            user_service.clone(),
+            sessions.clone(),
+            impossible_travel.clone(),
+            chrono::Duration::seconds(config.session_ttl_secs),
+        ));
+
+        let deployment = Arc::new(DeploymentInfo::new(
+            config.deployment_id.clone(),
+            config.deployment_color.clone(),
+            config.region.clone(),
+        ));
+        let region = Arc::new(
+            RegionRouter::new(&config.region, RegionPinMode::from_name(&config.region_pin_mode))
+                .with_peers(&config.region_peers),
+        );
 
         Self {
             user_service,
+            deployment,
+            region,
+            health,
+            dependencies,
+            degradations,
+            admin_token: Arc::from(config.admin_api_token.as_str()),
+            ownership,
+            geoip,
+            impossible_travel,
+            anomalies,
+            sessions,
+            impersonation,
+            sign_in,
+            user_overview,
+            presence,
+            csrf: Arc::new(CsrfTokens::new(&config.csrf_secret)),
+            oidc,
+            products: Arc::new(InMemoryProductStore::new()),
+            webhooks,
+            reports,
+            events,
+            event_access,
+            event_serializer,
+            rate_limiter: Arc::new(
+                RateLimiter::new()
+                    .with_limit(RateLimitBucket::Read.name(), config.rate_limit_read_per_minute)
+                    .with_limit(RateLimitBucket::Write.name(), config.rate_limit_write_per_minute)
+                    .with_limit(RateLimitBucket::Webhook.name(), config.rate_limit_webhook_per_minute)
+                    .with_mode(RateLimitMode::parse(&config.rate_limit_mode)),
+            ),
+            caches: Arc::new(caches),
+            shards,
+            method_metrics,
+            consumers,
+            sql_log,
+            shard_client,
+            plugins,
+            lanes: Arc::new(LaneLimiter::new(
+                config.lane_capacity,
+                config.lane_interactive_share,
+                config.lane_bulk_share,
+            )),
+            cpu_pool,
+            memory,
+            deprecations: Arc::new(DeprecationTracker::new(DEPRECATIONS)),
+            response_cache,
+            audit_log: audit_log(config),
+            hide_unimplemented_routes: config.hide_unimplemented_routes,
+            public_base_url: config.public_base_url.clone(),
+            routes: Arc::new(RouteTable::new()),
+            boot_report: Arc::default(),
+            startup,
         }
     }
 }
 
 impl Default for AppContainer {
     fn default() -> Self {
-        Self::new()
+        Self::new(&Config::from_env())
     }
-}
\ No newline at end of file
+}
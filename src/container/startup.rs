@@ -0,0 +1,233 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// A piece of the application that needs asynchronous initialization before
+/// the server accepts traffic (connections, migrations, cache warmers, ...)
+#[async_trait]
+pub trait StartupComponent: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Components that must have started successfully before this one
+    fn depends_on(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// `Err` carries a short, operator-facing reason
+    async fn start(&self) -> Result<(), String>;
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum StartupError {
+    #[error("component `{0}` is registered twice")]
+    DuplicateComponent(String),
+    #[error("component `{component}` depends on unknown component `{dependency}`")]
+    UnknownDependency { component: String, dependency: String },
+    #[error("dependency cycle between components: {}", .0.join(", "))]
+    Cycle(Vec<String>),
+    #[error("component `{component}` failed to start: {reason} (not started: {})", format_blocked(.blocked))]
+    ComponentFailed {
+        component: String,
+        reason: String,
+        blocked: Vec<String>,
+    },
+}
+
+fn format_blocked(blocked: &[String]) -> String {
+    if blocked.is_empty() {
+        "none".to_string()
+    } else {
+        blocked.join(", ")
+    }
+}
+
+/// How long one component took to start
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentTiming {
+    pub component: &'static str,
+    pub duration_ms: u64,
+}
+
+/// Startup components whose initialization order is derived from their
+/// declared dependencies. Nothing runs until `start_all`.
+#[derive(Default)]
+pub struct StartupGraph {
+    components: Vec<Arc<dyn StartupComponent>>,
+}
+
+impl StartupGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, component: Arc<dyn StartupComponent>) {
+        self.components.push(component);
+    }
+
+    /// Dependency order; ties keep registration order so startup is deterministic
+    pub fn order(&self) -> Result<Vec<&'static str>, StartupError> {
+        let mut index = HashMap::new();
+        for (position, component) in self.components.iter().enumerate() {
+            if index.insert(component.name(), position).is_some() {
+                return Err(StartupError::DuplicateComponent(component.name().to_string()));
+            }
+        }
+
+        let mut remaining_deps = Vec::with_capacity(self.components.len());
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); self.components.len()];
+        for (position, component) in self.components.iter().enumerate() {
+            for dependency in component.depends_on() {
+                let Some(&dep) = index.get(dependency) else {
+                    return Err(StartupError::UnknownDependency {
+                        component: component.name().to_string(),
+                        dependency: dependency.to_string(),
+                    });
+                };
+                dependents[dep].push(position);
+            }
+            remaining_deps.push(component.depends_on().len());
+        }
+
+        let mut order = Vec::with_capacity(self.components.len());
+        let mut started = vec![false; self.components.len()];
+        while order.len() < self.components.len() {
+            let Some(next) = (0..self.components.len()).find(|&i| !started[i] && remaining_deps[i] == 0) else {
+                let cycle = (0..self.components.len())
+                    .filter(|&i| !started[i])
+                    .map(|i| self.components[i].name().to_string())
+                    .collect();
+                return Err(StartupError::Cycle(cycle));
+            };
+            started[next] = true;
+            order.push(self.components[next].name());
+            for &dependent in &dependents[next] {
+                remaining_deps[dependent] -= 1;
+            }
+        }
+        Ok(order)
+    }
+
+    /// Start every component in dependency order, stopping at the first
+    /// failure. Returns each component's start time, in start order.
+    pub async fn start_all(&self) -> Result<Vec<ComponentTiming>, StartupError> {
+        let order = self.order()?;
+        let by_name: HashMap<&str, &Arc<dyn StartupComponent>> =
+            self.components.iter().map(|component| (component.name(), component)).collect();
+
+        let startup = Instant::now();
+        let mut timings = Vec::with_capacity(order.len());
+        for (position, name) in order.iter().enumerate() {
+            let started = Instant::now();
+            if let Err(reason) = by_name[name].start().await {
+                let blocked: Vec<String> = order[position + 1..].iter().map(|name| name.to_string()).collect();
+                tracing::error!(
+                    component = name,
+                    error = %reason,
+                    not_started = ?blocked,
+                    "Startup component failed"
+                );
+                return Err(StartupError::ComponentFailed {
+                    component: name.to_string(),
+                    reason,
+                    blocked,
+                });
+            }
+            let duration_ms = started.elapsed().as_millis() as u64;
+            tracing::info!(component = name, duration_ms, "Startup component ready");
+            timings.push(ComponentTiming { component: name, duration_ms });
+        }
+
+        tracing::info!(
+            components = order.len(),
+            duration_ms = startup.elapsed().as_millis(),
+            "Startup complete"
+        );
+        Ok(timings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    struct Recording {
+        name: &'static str,
+        deps: &'static [&'static str],
+        fail: bool,
+        log: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    #[async_trait]
+    impl StartupComponent for Recording {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn depends_on(&self) -> &'static [&'static str] {
+            self.deps
+        }
+
+        async fn start(&self) -> Result<(), String> {
+            self.log.lock().unwrap().push(self.name);
+            if self.fail {
+                Err("connection refused".to_string())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    fn graph(components: &[(&'static str, &'static [&'static str], bool)]) -> (StartupGraph, Arc<Mutex<Vec<&'static str>>>) {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut graph = StartupGraph::new();
+        for &(name, deps, fail) in components {
+            graph.add(Arc::new(Recording { name, deps, fail, log: log.clone() }));
+        }
+        (graph, log)
+    }
+
+    #[tokio::test]
+    async fn components_start_in_dependency_order() {
+        let (graph, log) = graph(&[
+            ("cache_warmer", &["migrations"], false),
+            ("migrations", &["database"], false),
+            ("database", &[], false),
+        ]);
+
+        let timings = graph.start_all().await.unwrap();
+        assert_eq!(*log.lock().unwrap(), vec!["database", "migrations", "cache_warmer"]);
+        let started: Vec<_> = timings.iter().map(|timing| timing.component).collect();
+        assert_eq!(started, vec!["database", "migrations", "cache_warmer"]);
+    }
+
+    #[tokio::test]
+    async fn failure_names_the_component_and_what_it_blocked() {
+        let (graph, log) = graph(&[
+            ("database", &[], false),
+            ("migrations", &["database"], true),
+            ("cache_warmer", &["migrations"], false),
+        ]);
+
+        let err = graph.start_all().await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "component `migrations` failed to start: connection refused (not started: cache_warmer)"
+        );
+        assert_eq!(*log.lock().unwrap(), vec!["database", "migrations"]);
+    }
+
+    #[test]
+    fn invalid_graphs_are_rejected_before_anything_starts() {
+        let (unknown, _) = graph(&[("migrations", &["database"], false)]);
+        assert_eq!(
+            unknown.order().unwrap_err(),
+            StartupError::UnknownDependency { component: "migrations".into(), dependency: "database".into() }
+        );
+
+        let (cycle, _) = graph(&[("a", &["b"], false), ("b", &["a"], false), ("c", &[], false)]);
+        assert_eq!(cycle.order().unwrap_err(), StartupError::Cycle(vec!["a".into(), "b".into()]));
+    }
+}
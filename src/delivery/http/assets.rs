@@ -0,0 +1,237 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use rust_embed::RustEmbed;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::io::Write;
+use std::sync::Arc;
+
+use crate::domain::oidc::feature::OidcProvider;
+use crate::response::not_found_response;
+
+/// `assets/`, compiled into the binary in every build profile
+#[derive(RustEmbed)]
+#[folder = "assets"]
+struct Files;
+
+/// Brotli copies of `Files` made by `build.rs`, as `<path>.br`; files that
+/// don't shrink have none
+#[derive(RustEmbed)]
+#[folder = "$OUT_DIR/assets"]
+struct Brotli;
+
+/// A file as served: its bytes, a brotli copy when that is smaller, and a
+/// strong `ETag` per encoding
+pub struct Asset {
+    content_type: &'static str,
+    /// Hex SHA-256 of `body`
+    hash: String,
+    body: Cow<'static, [u8]>,
+    brotli: Option<Cow<'static, [u8]>>,
+}
+
+impl Asset {
+    /// A file under `assets/`, e.g. `swagger-ui/index.html`
+    pub fn embedded(path: &str) -> Option<Self> {
+        let file = Files::get(path)?;
+        Some(Self {
+            content_type: content_type(path),
+            hash: hex::encode(file.metadata.sha256_hash()),
+            body: file.data,
+            brotli: Brotli::get(&format!("{path}.br")).map(|compressed| compressed.data),
+        })
+    }
+
+    /// A document built at runtime, compressed once here. Quality 9 keeps
+    /// this to milliseconds while staying close to the build-time ratio.
+    pub fn generated(content_type: &'static str, body: Vec<u8>) -> Self {
+        let mut compressed = Vec::new();
+        {
+            let mut writer = brotli::CompressorWriter::new(&mut compressed, 64 * 1024, 9, 22);
+            writer.write_all(&body).expect("writing to a Vec does not fail");
+        }
+        Self {
+            content_type,
+            hash: hex::encode(Sha256::digest(&body)),
+            brotli: (compressed.len() < body.len()).then_some(Cow::Owned(compressed)),
+            body: Cow::Owned(body),
+        }
+    }
+
+    /// The brotli copy when the client accepts it, else the original.
+    /// Answers 304 when `If-None-Match` already names that representation;
+    /// `Cache-Control` comes from the route policy.
+    pub fn respond(&self, request_headers: &HeaderMap) -> Response {
+        let brotli = self.brotli.as_ref().filter(|_| accepts_brotli(request_headers));
+        let etag = match brotli {
+            Some(_) => format!("\"{}-br\"", self.hash),
+            None => format!("\"{}\"", self.hash),
+        };
+
+        let mut response = if if_none_match(request_headers, &etag) {
+            StatusCode::NOT_MODIFIED.into_response()
+        } else {
+            let body = brotli.unwrap_or(&self.body).clone();
+            let mut response = (StatusCode::OK, body).into_response();
+            response.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_static(self.content_type));
+            if brotli.is_some() {
+                response.headers_mut().insert(header::CONTENT_ENCODING, HeaderValue::from_static("br"));
+            }
+            response
+        };
+        let headers = response.headers_mut();
+        if let Ok(value) = HeaderValue::from_str(&etag) {
+            headers.insert(header::ETAG, value);
+        }
+        if self.brotli.is_some() {
+            headers.insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+        }
+        response
+    }
+}
+
+/// Any embedded file, under `/api/assets/`
+pub async fn asset(Path(path): Path<String>, headers: HeaderMap) -> Response {
+    match Asset::embedded(&path) {
+        Some(asset) => asset.respond(&headers),
+        None => not_found_response("Asset").into_response(),
+    }
+}
+
+/// Swagger UI over `/api/docs/openapi.json`
+pub async fn swagger_ui(headers: HeaderMap) -> Response {
+    embedded_page("swagger-ui/index.html", &headers)
+}
+
+/// Admin dashboard; the page is public, and asks for the admin token before
+/// calling the admin endpoints
+pub async fn admin_dashboard(headers: HeaderMap) -> Response {
+    embedded_page("admin/index.html", &headers)
+}
+
+/// Where users approve a command-line client by its device code; the page
+/// is public, and the approval needs the `session` cookie. Answers 404, like
+/// the rest of the provider, when no OpenID clients are configured.
+pub async fn device_verification_page(State(provider): State<Arc<OidcProvider>>, headers: HeaderMap) -> Response {
+    if !provider.enabled() {
+        return not_found_response("OpenID provider").into_response();
+    }
+    embedded_page("device/index.html", &headers)
+}
+
+fn embedded_page(path: &str, headers: &HeaderMap) -> Response {
+    Asset::embedded(path).expect("pages are part of assets/").respond(headers)
+}
+
+fn content_type(path: &str) -> &'static str {
+    match path.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("json") => "application/json",
+        Some("png") => "image/png",
+        Some("svg") => "image/svg+xml",
+        _ => "text/plain; charset=utf-8",
+    }
+}
+
+/// `br` is listed in `Accept-Encoding` (or `*` is) without `q=0`
+fn accepts_brotli(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|coding| {
+            let mut parts = coding.split(';').map(str::trim);
+            let name = parts.next().unwrap_or_default();
+            let refused = parts
+                .filter_map(|param| param.strip_prefix("q="))
+                .any(|q| q.parse::<f32>().is_ok_and(|q| q == 0.0));
+            (name.eq_ignore_ascii_case("br") || name == "*") && !refused
+        })
+}
+
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value.split(',').map(str::trim).any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(accept_encoding: Option<&str>, if_none_match: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = accept_encoding {
+            headers.insert(header::ACCEPT_ENCODING, HeaderValue::from_str(value).unwrap());
+        }
+        if let Some(value) = if_none_match {
+            headers.insert(header::IF_NONE_MATCH, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[tokio::test]
+    async fn serves_the_precompressed_copy_to_clients_that_accept_brotli() {
+        let asset = Asset::embedded("swagger-ui/swagger-ui-bundle.js").unwrap();
+        let original = asset.body.len();
+
+        let response = asset.respond(&request(Some("gzip, deflate, br"), None));
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "br");
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/javascript; charset=utf-8");
+        assert_eq!(response.headers()[header::VARY], "accept-encoding");
+        let etag = response.headers()[header::ETAG].to_str().unwrap().to_string();
+        let compressed = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(compressed.len() < original / 3);
+        let mut decompressed = Vec::new();
+        brotli::BrotliDecompress(&mut compressed.as_ref(), &mut decompressed).unwrap();
+        assert_eq!(decompressed.len(), original);
+
+        let response = asset.respond(&request(Some("br"), Some(&etag)));
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        let response = asset.respond(&request(Some("br;q=0, gzip"), Some(&etag)));
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        assert_ne!(response.headers()[header::ETAG], etag.as_str());
+    }
+
+    #[tokio::test]
+    async fn the_device_page_is_missing_without_openid_clients() {
+        use crate::domain::oidc::feature::SigningKey;
+        use crate::domain::session::repository::InMemorySessionStore;
+        use crate::domain::user::feature::UserServiceImpl;
+        use crate::domain::user::repository::InMemoryUserRepository;
+        use crate::infrastructure::CpuPool;
+
+        let provider = || {
+            let users = Arc::new(UserServiceImpl::new(Arc::new(InMemoryUserRepository::new()), CpuPool::shared_for_tests()));
+            OidcProvider::new("https://id.example.com/api/oidc", "", SigningKey::ephemeral(), Arc::new(InMemorySessionStore::new()), users)
+        };
+        let disabled = device_verification_page(State(Arc::new(provider())), HeaderMap::new()).await;
+        assert_eq!(disabled.status(), StatusCode::NOT_FOUND);
+        let enabled = provider().with_device_clients("cli", chrono::Duration::minutes(10), 5);
+        let page = device_verification_page(State(Arc::new(enabled)), HeaderMap::new()).await;
+        assert_eq!(page.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn every_page_and_the_files_it_links_are_embedded() {
+        for page in ["swagger-ui/index.html", "admin/index.html", "device/index.html"] {
+            let html = String::from_utf8(Asset::embedded(page).unwrap().body.into_owned()).unwrap();
+            for link in html.split('"').filter_map(|part| part.strip_prefix("/api/assets/")) {
+                assert!(Asset::embedded(link).is_some(), "{page} links missing asset {link}");
+            }
+        }
+        let spec = Asset::generated("application/json", vec![b' '; 4096]);
+        assert!(spec.brotli.is_some());
+        assert!(Asset::generated("application/json", b"{}".to_vec()).brotli.is_none());
+    }
+}
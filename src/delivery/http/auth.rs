@@ -0,0 +1,121 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::request::{Builder, Parts},
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use std::ops::Deref;
+use uuid::Uuid;
+
+use crate::domain::session::entities::{Claims, Principal, SessionKind};
+use crate::response::unauthorized_response;
+
+/// The authenticated caller. Rejects the request with 401 when the auth
+/// middleware found no active session token on it.
+#[derive(Debug, Clone)]
+pub struct AuthUser(pub Principal);
+
+/// The caller when the request is authenticated, for routes that also serve
+/// anonymous clients
+#[derive(Debug, Clone)]
+pub struct MaybeAuthUser(pub Option<Principal>);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AuthUser {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Principal>()
+            .cloned()
+            .map(AuthUser)
+            .ok_or_else(|| unauthorized_response("Authentication required").into_response())
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for MaybeAuthUser {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(MaybeAuthUser(parts.extensions.get::<Principal>().cloned()))
+    }
+}
+
+/// Who is calling an admin route, for the audit trail: the user id of an
+/// admin session, or `admin-token` for the shared `ADMIN_API_TOKEN`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminActor(pub String);
+
+/// Actor recorded for calls made with `ADMIN_API_TOKEN`
+pub const ADMIN_TOKEN_ACTOR: &str = "admin-token";
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AdminActor {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let actor = parts.extensions.get::<Principal>().map(|principal| principal.user_id.to_string());
+        Ok(AdminActor(actor.unwrap_or_else(|| ADMIN_TOKEN_ACTOR.to_string())))
+    }
+}
+
+impl Deref for AuthUser {
+    type Target = Principal;
+
+    fn deref(&self) -> &Principal {
+        &self.0
+    }
+}
+
+/// A principal for tests, as if from a regular session valid for an hour
+pub fn fake_principal(user_id: Uuid, roles: &[&str]) -> Principal {
+    let now = Utc::now();
+    Principal {
+        user_id,
+        session_id: Uuid::new_v4(),
+        roles: roles.iter().map(|role| role.to_string()).collect(),
+        tenant: None,
+        scopes: None,
+        claims: Claims {
+            kind: SessionKind::Session,
+            issued_at: now,
+            expires_at: now + chrono::Duration::hours(1),
+            impersonated_by: None,
+        },
+    }
+}
+
+/// Attach a principal to a test request, bypassing token issuing. The auth
+/// middleware leaves it alone as long as the request has no bearer token.
+pub trait WithPrincipal {
+    fn with_principal(self, principal: Principal) -> Self;
+}
+
+impl WithPrincipal for Builder {
+    fn with_principal(self, principal: Principal) -> Self {
+        self.extension(principal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+
+    async fn actor(request: Request<()>) -> String {
+        let (mut parts, _) = request.into_parts();
+        let Ok(AdminActor(actor)) = AdminActor::from_request_parts(&mut parts, &()).await;
+        actor
+    }
+
+    #[tokio::test]
+    async fn admin_actor_is_the_session_user_or_the_admin_token() {
+        let user_id = Uuid::new_v4();
+        let request = Request::builder().with_principal(fake_principal(user_id, &["admin"])).body(()).unwrap();
+        assert_eq!(actor(request).await, user_id.to_string());
+        assert_eq!(actor(Request::new(())).await, ADMIN_TOKEN_ACTOR);
+    }
+}
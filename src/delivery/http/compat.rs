@@ -0,0 +1,345 @@
+use axum::{
+    body::Body,
+    extract::{MatchedPath, Request, State},
+    http::{header, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::any,
+    Router,
+};
+use serde_json::{Map, Value};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+use super::{RouteName, API_PREFIX};
+use crate::infrastructure::BodyReader;
+use crate::response::internal_error_response;
+
+/// Older API versions still served, each under `/api/v{n}`
+pub const LEGACY_API_VERSIONS: &[u32] = &[1];
+
+const REQUEST_READER: BodyReader = BodyReader::new(1024 * 1024);
+const RESPONSE_READER: BodyReader = BodyReader::new(8 * 1024 * 1024);
+
+/// A difference between a legacy version's shape of an object and the next
+/// version's. `default` and `value` are JSON literals.
+#[derive(Debug, Clone, Copy)]
+pub enum FieldChange {
+    /// Called `old` in the legacy version and `new` since
+    Renamed { old: &'static str, new: &'static str },
+    /// New since the legacy version: its requests get `default` when they
+    /// leave the field out, and its responses don't show the field
+    Added { field: &'static str, default: &'static str },
+    /// Gone since the legacy version: its requests have the field dropped,
+    /// and its responses get it back as `value`
+    Removed { field: &'static str, value: &'static str },
+}
+
+/// How one route's bodies differ in a legacy version from the version after it
+pub struct Shim {
+    pub version: u32,
+    pub route: RouteName,
+    /// Where the changed object sits in the request body, `None` when the
+    /// request is unchanged. `""` is the body itself, `a.b` a nested object
+    /// and `items[]` each element of an array; `[]` alone is each element of
+    /// a body that is a list.
+    pub request_at: Option<&'static str>,
+    /// Same, within the `data` of the response envelope
+    pub response_at: Option<&'static str>,
+    pub changes: &'static [FieldChange],
+}
+
+/// Every shape change since v1. When a model changes, list what a legacy
+/// client still sends and expects here instead of keeping the old struct:
+///
+/// ```ignore
+/// Shim {
+///     version: 1,
+///     route: RouteName::CreateUser,
+///     request_at: Some(""),
+///     response_at: Some(""),
+///     changes: &[FieldChange::Renamed { old: "mail", new: "email" }],
+/// }
+/// ```
+pub static SHIMS: &[Shim] = &[];
+
+/// Set on requests forwarded from `/api/v{n}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApiVersion(pub u32);
+
+/// Shims of a route, oldest version first; `None` when it has none
+pub fn route_shims(name: RouteName) -> Option<Arc<Vec<&'static Shim>>> {
+    let mut shims: Vec<&'static Shim> = SHIMS.iter().filter(|shim| shim.route == name).collect();
+    shims.sort_by_key(|shim| shim.version);
+    (!shims.is_empty()).then(|| Arc::new(shims))
+}
+
+/// Serve every legacy version by forwarding to the current routes, so each
+/// route's policy still applies once and its shims see the `ApiVersion`
+pub fn with_legacy_versions(current: Router) -> Router {
+    let mut router = current.clone();
+    for &version in LEGACY_API_VERSIONS {
+        let forward_to = current.clone();
+        router = router.route(
+            &format!("{API_PREFIX}/v{version}/*path"),
+            any(move |request: Request| forward_legacy(forward_to.clone(), version, request)),
+        );
+    }
+    router
+}
+
+async fn forward_legacy(current: Router, version: u32, mut request: Request) -> Response {
+    let prefix = format!("{API_PREFIX}/v{version}");
+    let path_and_query = request.uri().path_and_query().map_or("/", |path| path.as_str());
+    let rest = path_and_query.strip_prefix(prefix.as_str()).unwrap_or(path_and_query);
+    let Ok(uri) = format!("{API_PREFIX}{rest}").parse::<Uri>() else {
+        return internal_error_response("Failed to route legacy request").into_response();
+    };
+    *request.uri_mut() = uri;
+    // The current router records its own match; a leftover one would be prefixed to it
+    request.extensions_mut().remove::<MatchedPath>();
+    request.extensions_mut().insert(ApiVersion(version));
+    match current.oneshot(request).await {
+        Ok(response) => response,
+        Err(never) => match never {},
+    }
+}
+
+/// Rewrites the bodies of requests forwarded from a legacy version: the
+/// request up to the current shape, the response back down to the legacy one
+pub async fn version_shim_middleware(
+    State(shims): State<Arc<Vec<&'static Shim>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(ApiVersion(version)) = request.extensions().get::<ApiVersion>().copied() else {
+        return next.run(request).await;
+    };
+    let shims: Vec<&Shim> = shims.iter().copied().filter(|shim| shim.version >= version).collect();
+    if shims.is_empty() {
+        return next.run(request).await;
+    }
+
+    let request = if shims.iter().any(|shim| shim.request_at.is_some()) {
+        let (mut parts, body) = request.into_parts();
+        let bytes = match REQUEST_READER.read(body).await {
+            Ok(bytes) => bytes,
+            Err(err) => return err.into_response(),
+        };
+        let body = rewrite_json(&bytes, |body| {
+            for shim in &shims {
+                if let Some(at) = shim.request_at {
+                    each_object(body, at, &mut |object| upgrade(object, shim.changes));
+                }
+            }
+        });
+        parts.headers.remove(header::CONTENT_LENGTH);
+        Request::from_parts(parts, body)
+    } else {
+        request
+    };
+
+    let response = next.run(request).await;
+    if !shims.iter().any(|shim| shim.response_at.is_some()) {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let bytes = match RESPONSE_READER.read(body).await {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            tracing::error!(error = %err, "Failed to read response to downgrade");
+            return internal_error_response("Failed to adapt response").into_response();
+        }
+    };
+    // Only envelopes with data are rewritten; error bodies and bare values pass through as they are
+    let body = rewrite_json(&bytes, |envelope| {
+        let Some(data) = envelope.as_object_mut().and_then(|envelope| envelope.get_mut("data")) else {
+            return;
+        };
+        for shim in shims.iter().rev() {
+            if let Some(at) = shim.response_at {
+                each_object(data, at, &mut |object| downgrade(object, shim.changes));
+            }
+        }
+    });
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, body)
+}
+
+/// Bodies that aren't JSON are passed on as they are, for the handler to reject
+fn rewrite_json(bytes: &[u8], rewrite: impl FnOnce(&mut Value)) -> Body {
+    match serde_json::from_slice::<Value>(bytes) {
+        Ok(mut value) => {
+            rewrite(&mut value);
+            Body::from(serde_json::to_vec(&value).unwrap_or_else(|_| bytes.to_vec()))
+        }
+        Err(_) => Body::from(bytes.to_vec()),
+    }
+}
+
+fn each_object(value: &mut Value, at: &str, apply: &mut dyn FnMut(&mut Map<String, Value>)) {
+    if at.is_empty() {
+        if let Value::Object(object) = value {
+            apply(object);
+        }
+        return;
+    }
+    let (segment, rest) = at.split_once('.').unwrap_or((at, ""));
+    match segment.strip_suffix("[]") {
+        Some(field) => {
+            // A bare `[]` is the items of the value itself, as in a `data` that is a list
+            let items = if field.is_empty() { Some(value) } else { value.get_mut(field) };
+            if let Some(Value::Array(items)) = items {
+                for item in items {
+                    each_object(item, rest, apply);
+                }
+            }
+        }
+        None => {
+            if let Some(inner) = value.get_mut(segment) {
+                each_object(inner, rest, apply);
+            }
+        }
+    }
+}
+
+fn literal(json: &str) -> Value {
+    serde_json::from_str(json).unwrap_or(Value::Null)
+}
+
+/// Legacy shape to the next version's
+fn upgrade(object: &mut Map<String, Value>, changes: &[FieldChange]) {
+    for change in changes {
+        match *change {
+            FieldChange::Renamed { old, new } => {
+                if let Some(value) = object.remove(old) {
+                    object.insert(new.to_string(), value);
+                }
+            }
+            FieldChange::Added { field, default } => {
+                object.entry(field).or_insert_with(|| literal(default));
+            }
+            FieldChange::Removed { field, .. } => {
+                object.remove(field);
+            }
+        }
+    }
+}
+
+/// Next version's shape to the legacy one
+fn downgrade(object: &mut Map<String, Value>, changes: &[FieldChange]) {
+    for change in changes.iter().rev() {
+        match *change {
+            FieldChange::Renamed { old, new } => {
+                if let Some(value) = object.remove(new) {
+                    object.insert(old.to_string(), value);
+                }
+            }
+            FieldChange::Added { field, .. } => {
+                object.remove(field);
+            }
+            FieldChange::Removed { field, value } => {
+                object.entry(field).or_insert_with(|| literal(value));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Json};
+    use serde_json::json;
+
+    static PROFILE_SHIMS: &[Shim] = &[Shim {
+        version: 1,
+        route: RouteName::CreateUser,
+        request_at: Some(""),
+        response_at: Some("users[]"),
+        changes: &[
+            FieldChange::Renamed { old: "mail", new: "email" },
+            FieldChange::Added { field: "locale", default: "\"en\"" },
+            FieldChange::Removed { field: "nickname", value: "null" },
+        ],
+    }];
+
+    #[tokio::test]
+    async fn legacy_requests_are_upgraded_and_their_responses_downgraded() {
+        let shims = Arc::new(PROFILE_SHIMS.iter().collect::<Vec<_>>());
+        let current = Router::new().nest(
+            API_PREFIX,
+            Router::new().route(
+                "/profiles",
+                post(|Json(profile): Json<Value>| async move { Json(json!({ "data": { "users": [profile] } })) })
+                    .layer(axum::middleware::from_fn_with_state(shims, version_shim_middleware)),
+            ),
+        );
+        let app = with_legacy_versions(current);
+        let send = |path: &str, body: Value| {
+            let request = Request::post(path)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let bytes = axum::body::to_bytes(response.into_body(), 4096).await.unwrap();
+                serde_json::from_slice::<Value>(&bytes).unwrap()["data"]["users"][0].clone()
+            }
+        };
+
+        let legacy = send("/api/v1/profiles", json!({ "mail": "a@example.com", "nickname": "al" })).await;
+        assert_eq!(legacy, json!({ "mail": "a@example.com", "nickname": null }));
+
+        // The current version sees its own shape, untouched
+        let current = send("/api/profiles", json!({ "email": "a@example.com" })).await;
+        assert_eq!(current, json!({ "email": "a@example.com" }));
+    }
+
+    #[tokio::test]
+    async fn lists_and_error_bodies_pass_through_shimmed_routes() {
+        static LIST_SHIMS: &[Shim] = &[Shim {
+            version: 1,
+            route: RouteName::ListUsers,
+            request_at: None,
+            response_at: Some("[]"),
+            changes: &[FieldChange::Renamed { old: "mail", new: "email" }],
+        }];
+        let shims = Arc::new(LIST_SHIMS.iter().collect::<Vec<_>>());
+        let shimmed = |body: Value| {
+            axum::routing::get(move || async move { Json(body) })
+                .layer(axum::middleware::from_fn_with_state(shims.clone(), version_shim_middleware))
+        };
+        let current = Router::new().nest(
+            API_PREFIX,
+            Router::new()
+                .route("/listed", shimmed(json!({ "data": [{ "email": "a@example.com" }] })))
+                .route("/bare", shimmed(json!([{ "email": "a@example.com" }])))
+                .route("/failed", shimmed(json!({ "success": false, "error": { "code": "NOT_FOUND" } }))),
+        );
+        let app = with_legacy_versions(current);
+        let get = |path: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(Request::get(path).body(Body::empty()).unwrap()).await.unwrap();
+                let bytes = axum::body::to_bytes(response.into_body(), 4096).await.unwrap();
+                serde_json::from_slice::<Value>(&bytes).unwrap()
+            }
+        };
+
+        assert_eq!(get("/api/v1/listed").await, json!({ "data": [{ "mail": "a@example.com" }] }));
+        assert_eq!(get("/api/v1/bare").await, json!([{ "email": "a@example.com" }]));
+        assert_eq!(get("/api/v1/failed").await, json!({ "success": false, "error": { "code": "NOT_FOUND" } }));
+    }
+
+    #[test]
+    fn shim_literals_are_json() {
+        for shim in SHIMS {
+            for change in shim.changes {
+                if let FieldChange::Added { default: json, .. } | FieldChange::Removed { value: json, .. } = change {
+                    assert!(serde_json::from_str::<Value>(json).is_ok(), "{:?}: `{json}` is not JSON", shim.route);
+                }
+            }
+        }
+    }
+}
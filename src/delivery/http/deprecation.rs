@@ -0,0 +1,269 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+use super::RouteName;
+
+/// `Deprecation` response header (RFC 9745)
+pub const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+/// `Sunset` response header (RFC 8594)
+pub const SUNSET: HeaderName = HeaderName::from_static("sunset");
+
+/// What is being retired
+#[derive(Debug, Clone, Copy)]
+pub enum DeprecatedSurface {
+    /// The whole route; its responses carry `Deprecation`, `Sunset` and `Link`
+    Route(RouteName),
+    /// Fields of a response schema, and the route that returns them. The
+    /// route itself stays, so its responses get no headers.
+    Fields { schema: &'static str, fields: &'static [&'static str], route: RouteName },
+}
+
+/// One deprecated route or set of fields
+#[derive(Debug)]
+pub struct Deprecation {
+    pub surface: DeprecatedSurface,
+    /// `YYYY-MM-DD`
+    pub deprecated_on: &'static str,
+    /// `YYYY-MM-DD`; the surface may be removed from this day on
+    pub sunset_on: &'static str,
+    /// Migration notes, sent as the `rel="deprecation"` link
+    pub link: &'static str,
+    pub note: &'static str,
+}
+
+/// Deprecated routes and response fields. An entry stays until its surface is
+/// removed, some time after the sunset date.
+pub const DEPRECATIONS: &[Deprecation] = &[Deprecation {
+    surface: DeprecatedSurface::Fields {
+        schema: "ListUsersResponse",
+        fields: &["total", "total_exact", "page", "limit"],
+        route: RouteName::ListUsers,
+    },
+    deprecated_on: "2026-10-18",
+    sunset_on: "2027-04-30",
+    link: "/api/docs/openapi.json",
+    note: "Read pagination from the response `meta` instead.",
+}];
+
+impl Deprecation {
+    /// The route whose requests count as usage
+    pub fn route(&self) -> RouteName {
+        match self.surface {
+            DeprecatedSurface::Route(route) | DeprecatedSurface::Fields { route, .. } => route,
+        }
+    }
+
+    /// Operation id, or `Schema.field` list, for reports
+    pub fn name(&self) -> String {
+        match self.surface {
+            DeprecatedSurface::Route(route) => route.operation_id(),
+            DeprecatedSurface::Fields { schema, fields, .. } => {
+                fields.iter().map(|field| format!("{schema}.{field}")).collect::<Vec<_>>().join(", ")
+            }
+        }
+    }
+
+    /// Shown in the OpenAPI spec and generated clients
+    pub fn description(&self) -> String {
+        format!("Deprecated since {}, removed after {}. {}", self.deprecated_on, self.sunset_on, self.note)
+    }
+
+    /// `Deprecation`, `Sunset` and `Link` headers for a deprecated route;
+    /// empty for deprecated fields
+    pub fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let DeprecatedSurface::Route(_) = self.surface {
+            let deprecated = HeaderValue::from_str(&format!("@{}", start_of(self.deprecated_on).timestamp()));
+            let sunset = HeaderValue::from_str(
+                &start_of(self.sunset_on).format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+            );
+            let link = HeaderValue::from_str(&format!("<{}>; rel=\"deprecation\"", self.link));
+            for (name, value) in [(DEPRECATION, deprecated), (SUNSET, sunset), (header::LINK, link)] {
+                headers.insert(name, value.expect("deprecation header values are ASCII"));
+            }
+        }
+        headers
+    }
+}
+
+fn start_of(day: &str) -> DateTime<Utc> {
+    NaiveDate::parse_from_str(day, "%Y-%m-%d")
+        .expect("deprecation dates are YYYY-MM-DD")
+        .and_time(chrono::NaiveTime::MIN)
+        .and_utc()
+}
+
+/// How often a deprecated surface is still used
+#[derive(Debug, Clone, Serialize)]
+pub struct DeprecationUsage {
+    pub name: String,
+    pub deprecated_on: &'static str,
+    pub sunset_on: &'static str,
+    pub requests: u64,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// Counts requests to deprecated routes, and to routes serving deprecated
+/// fields, so it is clear when a surface can be removed
+pub struct DeprecationTracker {
+    deprecations: &'static [Deprecation],
+    usage: Mutex<Vec<(u64, Option<DateTime<Utc>>)>>,
+}
+
+impl DeprecationTracker {
+    pub fn new(deprecations: &'static [Deprecation]) -> Self {
+        Self { deprecations, usage: Mutex::new(vec![(0, None); deprecations.len()]) }
+    }
+
+    /// Indexes of the deprecations that apply to `route`
+    pub fn for_route(&self, route: RouteName) -> Vec<usize> {
+        (0..self.deprecations.len()).filter(|&index| self.deprecations[index].route() == route).collect()
+    }
+
+    pub fn record(&self, indexes: &[usize]) {
+        let now = Utc::now();
+        let mut usage = self.usage.lock().unwrap();
+        for &index in indexes {
+            usage[index].0 += 1;
+            usage[index].1 = Some(now);
+        }
+    }
+
+    pub fn usage(&self) -> Vec<DeprecationUsage> {
+        let usage = self.usage.lock().unwrap();
+        self.deprecations
+            .iter()
+            .zip(usage.iter())
+            .map(|(deprecation, &(requests, last_used_at))| DeprecationUsage {
+                name: deprecation.name(),
+                deprecated_on: deprecation.deprecated_on,
+                sunset_on: deprecation.sunset_on,
+                requests,
+                last_used_at,
+            })
+            .collect()
+    }
+
+    /// State for `deprecation_middleware` on `route`; `None` when nothing
+    /// about it is deprecated
+    pub fn route_state(self: &Arc<Self>, route: RouteName) -> Option<Arc<DeprecatedRoute>> {
+        let indexes = self.for_route(route);
+        if indexes.is_empty() {
+            return None;
+        }
+        let mut headers = HeaderMap::new();
+        for &index in &indexes {
+            headers.extend(self.deprecations[index].headers());
+        }
+        Some(Arc::new(DeprecatedRoute { tracker: self.clone(), indexes, headers }))
+    }
+}
+
+/// What the deprecation middleware of one route needs
+pub struct DeprecatedRoute {
+    tracker: Arc<DeprecationTracker>,
+    indexes: Vec<usize>,
+    headers: HeaderMap,
+}
+
+/// Counts the request and adds the route's deprecation headers to the response
+pub async fn deprecation_middleware(
+    State(route): State<Arc<DeprecatedRoute>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    route.tracker.record(&route.indexes);
+    let mut response = next.run(request).await;
+    response.headers_mut().extend(route.headers.clone());
+    response
+}
+
+/// Mark deprecated operations and schema properties in an OpenAPI document,
+/// with the sunset date in the description and `x-sunset`
+pub fn apply_deprecations(spec: &mut Value, deprecations: &[Deprecation]) {
+    for deprecation in deprecations {
+        match deprecation.surface {
+            DeprecatedSurface::Route(route) => {
+                let path = route.openapi_path().replace('~', "~0").replace('/', "~1");
+                let method = route.route().method.as_str().to_lowercase();
+                mark_deprecated(spec.pointer_mut(&format!("/paths/{path}/{method}")), deprecation);
+            }
+            DeprecatedSurface::Fields { schema, fields, .. } => {
+                for field in fields {
+                    let pointer = format!("/components/schemas/{schema}/properties/{field}");
+                    mark_deprecated(spec.pointer_mut(&pointer), deprecation);
+                }
+            }
+        }
+    }
+}
+
+fn mark_deprecated(target: Option<&mut Value>, deprecation: &Deprecation) {
+    if let Some(target) = target.filter(|target| target.is_object()) {
+        target["deprecated"] = json!(true);
+        target["description"] = json!(deprecation.description());
+        target["x-sunset"] = json!(deprecation.sunset_on);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delivery::openapi_spec;
+    use axum::{body::Body, routing::get, Router};
+    use tower::ServiceExt;
+
+    static ROUTE_DEPRECATIONS: &[Deprecation] = &[Deprecation {
+        surface: DeprecatedSurface::Route(RouteName::GetInfo),
+        deprecated_on: "2026-01-01",
+        sunset_on: "2026-07-01",
+        link: "/docs/info-v2",
+        note: "Use /api/health instead.",
+    }];
+
+    #[test]
+    fn registered_deprecations_match_the_spec_and_have_valid_dates() {
+        let spec = openapi_spec();
+        for deprecation in DEPRECATIONS {
+            assert!(start_of(deprecation.deprecated_on) < start_of(deprecation.sunset_on), "{}", deprecation.name());
+            if let DeprecatedSurface::Fields { schema, fields, .. } = deprecation.surface {
+                for field in fields {
+                    let property = &spec["components"]["schemas"][schema]["properties"][field];
+                    assert_eq!(property["deprecated"], true, "{schema}.{field}");
+                    assert_eq!(property["x-sunset"], deprecation.sunset_on);
+                }
+            }
+        }
+
+        let mut spec = openapi_spec();
+        apply_deprecations(&mut spec, ROUTE_DEPRECATIONS);
+        assert_eq!(spec["paths"]["/api/info"]["get"]["deprecated"], true);
+    }
+
+    #[tokio::test]
+    async fn deprecated_routes_send_headers_and_count_usage() {
+        let tracker = Arc::new(DeprecationTracker::new(ROUTE_DEPRECATIONS));
+        assert!(tracker.route_state(RouteName::HealthCheck).is_none());
+        let state = tracker.route_state(RouteName::GetInfo).unwrap();
+        let app = Router::new()
+            .route("/info", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(state, deprecation_middleware));
+
+        let response = app.oneshot(Request::get("/info").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.headers()[DEPRECATION], "@1767225600");
+        assert_eq!(response.headers()[SUNSET], "Wed, 01 Jul 2026 00:00:00 GMT");
+        assert_eq!(response.headers()[header::LINK], "</docs/info-v2>; rel=\"deprecation\"");
+
+        let usage = tracker.usage();
+        assert_eq!((usage[0].name.as_str(), usage[0].requests), ("getInfo", 1));
+        assert!(usage[0].last_used_at.is_some());
+    }
+}
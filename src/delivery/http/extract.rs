@@ -0,0 +1,150 @@
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{FromRequest, FromRequestParts, OriginalUri, Request},
+    http::{header, request::Parts},
+    response::{IntoResponse, Response},
+};
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+
+use crate::infrastructure::RequestContext;
+use crate::response::{bad_request_response, ListEnvelope, X_ENVELOPE};
+
+/// JSON body extractor for ingest endpoints.
+///
+/// With the `simd-json` feature enabled the body is parsed with simd-json,
+/// falling back to serde_json if simd-json rejects it so error messages stay
+/// the same as the default build.
+pub struct FastJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for FastJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let is_json = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.starts_with("application/json"))
+            .unwrap_or(false);
+        if !is_json {
+            return Err(bad_request_response("Expected request with `Content-Type: application/json`").into_response());
+        }
+
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        parse_json(&bytes)
+            .map(FastJson)
+            .map_err(|err| bad_request_response(&format!("Invalid JSON body: {}", err)).into_response())
+    }
+}
+
+#[cfg(feature = "simd-json")]
+pub fn parse_json<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, serde_json::Error> {
+    // simd-json parses in place, so it needs its own mutable copy
+    let mut scratch = bytes.to_vec();
+    match simd_json::serde::from_slice(&mut scratch) {
+        Ok(value) => Ok(value),
+        Err(_) => serde_json::from_slice(bytes),
+    }
+}
+
+#[cfg(not(feature = "simd-json"))]
+pub fn parse_json<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, serde_json::Error> {
+    serde_json::from_slice(bytes)
+}
+
+/// The context resolved by `request_context_middleware`, or the built-in
+/// defaults on routers without it
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for RequestContext {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts.extensions.get::<RequestContext>().copied().unwrap_or_default())
+    }
+}
+
+/// Whether a list endpoint should drop the envelope, from `?envelope=false`
+/// or `X-Envelope: false`, and the URL its page links are built from
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ListEnvelope {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        // Nested routers strip their prefix from `parts.uri`; links need the full path
+        let uri = parts.extensions.get::<OriginalUri>().map_or(&parts.uri, |original| &original.0);
+        let envelope = ListEnvelope::from_request(uri, parts.headers.get(X_ENVELOPE));
+        Ok(match parts.extensions.get::<PublicUrl>() {
+            Some(PublicUrl(origin)) => envelope.with_origin(origin.as_ref()),
+            None => envelope,
+        })
+    }
+}
+
+/// `PUBLIC_BASE_URL`, in the extensions of every API request. Absolute links
+/// start with it, never with the `Host` a client sent, so a forged header
+/// cannot point them at another site.
+#[derive(Debug, Clone)]
+pub struct PublicUrl(pub Arc<str>);
+
+impl PublicUrl {
+    pub fn new(base_url: &str) -> Self {
+        Self(Arc::from(base_url.trim_end_matches('/')))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn sample_body() -> Vec<u8> {
+        let users: Vec<serde_json::Value> = (0..1000)
+            .map(|i| serde_json::json!({ "email": format!("user{}@example.com", i), "password": "password123" }))
+            .collect();
+        serde_json::to_vec(&users).unwrap()
+    }
+
+    #[test]
+    fn parse_json_reports_serde_errors() {
+        let err = parse_json::<serde_json::Value>(b"{\"email\": ").unwrap_err();
+        assert!(err.is_eof());
+    }
+
+    /// Run with `cargo test --release --features simd-json -- --ignored --nocapture bench_`
+    #[test]
+    #[ignore]
+    fn bench_parse_json_vs_serde_json() {
+        const ITERATIONS: u32 = 500;
+        let body = sample_body();
+
+        let started = Instant::now();
+        for _ in 0..ITERATIONS {
+            std::hint::black_box(serde_json::from_slice::<Vec<serde_json::Value>>(&body).unwrap());
+        }
+        let serde_elapsed = started.elapsed();
+
+        let started = Instant::now();
+        for _ in 0..ITERATIONS {
+            std::hint::black_box(parse_json::<Vec<serde_json::Value>>(&body).unwrap());
+        }
+        let parse_elapsed = started.elapsed();
+
+        println!(
+            "serde_json: {:?}/body, parse_json (simd-json: {}): {:?}/body",
+            serde_elapsed / ITERATIONS,
+            cfg!(feature = "simd-json"),
+            parse_elapsed / ITERATIONS
+        );
+    }
+}
+
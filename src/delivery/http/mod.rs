@@ -1,3 +1,24 @@
 pub mod router;
+pub mod extract;
+pub mod auth;
+pub mod server;
+pub mod assets;
+pub mod openapi;
+pub mod postman;
+pub mod routes;
+pub mod policy;
+pub mod deprecation;
+pub mod response_cache;
+pub mod wiring;
+pub mod compat;
 
-pub use router::*;
\ No newline at end of file
+pub use router::*;
+pub use extract::*;
+pub use auth::*;
+pub use server::*;
+pub use openapi::*;
+pub use routes::*;
+pub use deprecation::*;
+pub use response_cache::*;
+pub use wiring::*;
+pub use compat::*;
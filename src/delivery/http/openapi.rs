@@ -0,0 +1,1274 @@
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::response::Response;
+use serde_json::{json, Value};
+use std::sync::OnceLock;
+
+use super::assets::Asset;
+use super::deprecation::{apply_deprecations, DEPRECATIONS};
+use super::{RouteName, ROUTES};
+use crate::domain::session::entities::Scope;
+
+/// OpenAPI 3.0 description of the HTTP API.
+///
+/// Kept next to the router so new routes are documented in the same change;
+/// client SDKs and other exports are generated from this document.
+/// Entries in `DEPRECATIONS` are marked `deprecated`, and operations list the
+/// scope their route policy declares.
+pub fn openapi_spec() -> Value {
+    let mut spec = json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "rust-boilerplate",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "servers": [{ "url": "/" }],
+        "paths": {
+            "/api/health": {
+                "get": operation("healthCheck", "Health", "Health check", Some("HealthResponse")),
+            },
+            "/api/health/dependencies": {
+                "get": operation("getDependencies", "Health", "Last background probe result for each dependency", Some("DependenciesResponse")),
+            },
+            "/api/ready": {
+                "get": operation("readinessCheck", "Health", "Readiness check", Some("ReadyResponse")),
+            },
+            "/api/live": {
+                "get": operation("livenessCheck", "Health", "Liveness check", Some("LiveResponse")),
+            },
+            "/api/info": {
+                "get": operation("getInfo", "Health", "Service version and deployment metadata", Some("InfoResponse")),
+            },
+            "/api/users": {
+                "get": bare_list(with_description(
+                    with_parameters(
+                        operation("listUsers", "Users", "List users (with pagination)", Some("ListUsersResponse")),
+                        vec![
+                            query_parameter("page", json!({ "type": "integer", "format": "int32", "minimum": 1 })),
+                            query_parameter("limit", json!({ "type": "integer", "format": "int32", "minimum": 1, "maximum": 100 })),
+                            query_parameter("exact", json!({ "type": "boolean" })),
+                            query_parameter("include", json!({ "type": "string", "enum": ["presence"] })),
+                        ],
+                    ),
+                    "Filter by metadata with up to 5 `metadata.<key>[.<key>...]=<value>` parameters, e.g. \
+                     `?metadata.plan=pro&metadata.org.region=eu`. A user matches when the value at each \
+                     path is that string, or a number or boolean written that way. `include=presence` adds each \
+                     user's `presence`; such responses are not cached.",
+                )),
+                "post": with_body(
+                    conflict(created(operation("createUser", "Users", "Create user", Some("User"))), "USER_ALREADY_EXISTS"),
+                    "CreateUserRequest",
+                ),
+            },
+            "/api/users/{id}": {
+                "get": owned(with_parameters(
+                    operation("getUser", "Users", "Get user by ID", Some("User")),
+                    vec![id_parameter()],
+                )),
+                "put": owned(with_body(
+                    with_parameters(
+                        not_implemented(operation("updateUser", "Users", "Replace user (not implemented)", Some("User"))),
+                        vec![id_parameter()],
+                    ),
+                    "UpdateUserRequest",
+                )),
+                "patch": owned(with_body(
+                    with_parameters(
+                        operation("patchUser", "Users", "Update the fields present; metadata is merge-patched", Some("User")),
+                        vec![id_parameter()],
+                    ),
+                    "PatchUserRequest",
+                )),
+                "delete": owned(with_parameters(
+                    not_implemented(operation("deleteUser", "Users", "Delete user (not implemented)", None)),
+                    vec![id_parameter()],
+                )),
+            },
+            "/api/users/{id}/overview": {
+                "get": owned(with_description(
+                    with_parameters(
+                        operation("getUserOverview", "Users", "The user's profile with their active sessions", Some("UserOverviewResponse")),
+                        vec![id_parameter()],
+                    ),
+                    "Sources are queried concurrently, each for up to `USER_OVERVIEW_BRANCH_TIMEOUT_MS`. \
+                     A source that times out or fails is `null`, listed in `missing`, and sets `partial`; only the user \
+                     record is required, and answers 504 `TIMEOUT` when it is slow.",
+                )),
+            },
+            "/api/users/{id}/presence": {
+                "get": with_parameters(
+                    operation("getUserPresence", "Users", "Whether the user is online, and when they were last seen", Some("UserPresence")),
+                    vec![id_parameter()],
+                ),
+            },
+            "/api/products": {
+                "post": with_body(
+                    created(operation("createProduct", "Products", "Create product", Some("Product"))),
+                    "CreateProductRequest",
+                ),
+            },
+            "/api/products/{id}": {
+                "get": with_parameters(
+                    operation("getProduct", "Products", "Get product by ID", Some("Product")),
+                    vec![id_parameter()],
+                ),
+            },
+            "/api/auth/login": {
+                "post": with_description(
+                    with_body(
+                        operation("signIn", "Auth", "Sign in with an email and password", Some("SignInResponse")),
+                        "SignInRequest",
+                    ),
+                    "Opens a login session. The token is returned once and also set as the `session` cookie \
+                     (`HttpOnly`, `SameSite=Lax`) for `SESSION_TTL_SECS`. A wrong email or password answers 401 \
+                     `INVALID_CREDENTIALS`.",
+                ),
+            },
+            "/api/auth/csrf": {
+                "get": with_description(
+                    operation("issueCsrfToken", "Auth", "CSRF token for writes authenticated by the session cookie", Some("CsrfTokenResponse")),
+                    "Requires a session, as a bearer token or the `session` cookie. The token is also set as the \
+                     `csrf_token` cookie; POST, PUT, PATCH and DELETE requests authenticated by the cookie must \
+                     repeat it in `X-CSRF-Token` or are refused with 403 `CSRF_TOKEN_MISSING` or `CSRF_TOKEN_INVALID`.",
+                ),
+            },
+            "/api/oidc/device": {
+                "post": with_description(
+                    with_body(
+                        operation("verifyDevice", "Auth", "Approve or deny the device showing a user code", Some("DeviceVerificationResponse")),
+                        "DeviceVerificationRequest",
+                    ),
+                    "Requires a session, as a bearer token or the `session` cookie. Used by the page at \
+                     `GET /api/oidc/device` in the OAuth device authorization grant. Unknown or expired codes answer \
+                     404 `USER_CODE_NOT_FOUND`, and codes already decided answer 409 `USER_CODE_USED`.",
+                ),
+            },
+            "/api/oidc/device/{user_code}": {
+                "get": with_description(
+                    with_parameters(
+                        operation("describeDevice", "Auth", "Client and scopes of the device showing a user code", Some("PendingDeviceResponse")),
+                        vec![json!({ "name": "user_code", "in": "path", "required": true, "schema": { "type": "string", "example": "WDJB-MJHT" } })],
+                    ),
+                    "Requires a session. Shown by the verification page before the user approves, so they can check which \
+                     client is asking and for what. Answers 404 `USER_CODE_NOT_FOUND` and 409 `USER_CODE_USED` like the decision itself.",
+                ),
+            },
+            "/api/admin/drain": {
+                "post": admin(operation("startDraining", "Admin", "Mark instance as draining", Some("DrainResponse"))),
+                "delete": admin(operation("stopDraining", "Admin", "Stop draining", Some("DrainResponse"))),
+            },
+            "/api/admin/users/{id}/sessions": {
+                "get": admin(bare_list(with_parameters(
+                    operation("listUserSessions", "Admin", "Active sessions and refresh tokens of a user", Some("SessionsResponse")),
+                    vec![id_parameter()],
+                ))),
+                "delete": admin(with_parameters(
+                    operation("revokeUserSessions", "Admin", "Revoke every session of a user", Some("RevokeSessionsResponse")),
+                    vec![id_parameter()],
+                )),
+            },
+            "/api/admin/users/{id}/sessions/{session_id}": {
+                "delete": admin(with_parameters(
+                    operation("revokeUserSession", "Admin", "Revoke one session of a user", Some("RevokeSessionsResponse")),
+                    vec![id_parameter(), path_parameter("session_id")],
+                )),
+            },
+            "/api/admin/impersonate/{id}": {
+                "post": admin(with_body(
+                    with_parameters(
+                        created(operation("startImpersonation", "Admin", "Issue a short-lived token acting as the user", Some("ImpersonationResponse"))),
+                        vec![id_parameter()],
+                    ),
+                    "ImpersonateRequest",
+                )),
+                "delete": admin(with_parameters(
+                    operation("stopImpersonation", "Admin", "End every impersonation session for the user", Some("RevokeSessionsResponse")),
+                    vec![id_parameter()],
+                )),
+            },
+            "/api/admin/anomalies": {
+                "get": admin(bare_list(operation("listAnomalies", "Admin", "Currently active request anomalies", Some("AnomaliesResponse")))),
+            },
+            "/api/admin/routes": {
+                "get": admin(bare_list(operation("listRoutes", "Admin", "Routes served by this instance, with their handlers", Some("RoutesResponse")))),
+            },
+            "/api/admin/cpu-pool": {
+                "get": admin(operation("cpuPoolStats", "Admin", "Queue depths and counters of the CPU work pool", Some("CpuPoolStats"))),
+            },
+            "/api/admin/memory": {
+                "get": admin(operation("memoryReport", "Admin", "Process memory, limits and pressure", Some("MemoryReport"))),
+            },
+            "/api/admin/object-pools": {
+                "get": admin(bare_list(operation("listObjectPools", "Admin", "Reuse counters of the buffer pools", Some("ObjectPoolsResponse")))),
+            },
+            "/api/admin/shards": {
+                "get": admin(bare_list(operation("listShards", "Admin", "Nodes, key shares and rebalances of each shard ring", Some("ShardsResponse")))),
+            },
+            "/api/admin/method-metrics": {
+                "get": admin(bare_list(operation("listMethodMetrics", "Admin", "Calls, latency and error rate of each service method", Some("MethodMetricsResponse")))),
+            },
+            "/api/admin/consumers": {
+                "get": admin(bare_list(operation("listConsumers", "Admin", "Lag, processing latency, retries and dead letters of each message consumer", Some("ConsumersResponse")))),
+            },
+            "/api/admin/response-cache": {
+                "get": admin(operation("getResponseCacheStats", "Admin", "Hits, stale serves, misses and background refreshes of each cached route", Some("ResponseCacheReport"))),
+            },
+            "/api/admin/audit-events": {
+                "get": admin(bare_list(with_description(
+                    with_parameters(
+                        operation("listAuditEvents", "Admin", "Audit events, newest first, from the journal and its archive", Some("AuditPage")),
+                        vec![
+                            query_parameter("event", json!({ "type": "string" })),
+                            query_parameter("since", json!({ "type": "string", "format": "date-time" })),
+                            query_parameter("until", json!({ "type": "string", "format": "date-time" })),
+                            query_parameter("before_seq", json!({ "type": "integer", "format": "int64" })),
+                            query_parameter("limit", json!({ "type": "integer", "minimum": 1, "maximum": 500, "default": 50 })),
+                        ],
+                    ),
+                    "Records older than `AUDIT_ARCHIVE_AFTER_DAYS` are read from the archived segments when the \
+                     journal does not fill the page; `archived` says so. Pass `next_before_seq` as `before_seq` \
+                     for the next page. Answers 404 without `AUDIT_JOURNAL_PATH`.",
+                ))),
+            },
+            "/api/admin/lanes": {
+                "get": admin(operation("listLanes", "Admin", "Request slots in use and shed per priority lane", Some("LaneReport"))),
+            },
+            "/api/admin/deprecations": {
+                "get": admin(bare_list(operation("listDeprecations", "Admin", "Deprecated routes and fields, with their sunset dates and recent use", Some("DeprecationsResponse")))),
+            },
+            "/api/admin/cache/invalidate": {
+                "post": admin(with_body(
+                    operation("invalidateCache", "Admin", "Drop cache entries by key, prefix or tag and purge the tags at the CDN", Some("InvalidationReport")),
+                    "InvalidateCacheRequest",
+                )),
+            },
+            "/api/admin/boot-report": {
+                "get": admin(operation("getBootReport", "Admin", "Config, features, listeners, migrations and startup times this instance booted with", Some("BootReport"))),
+            },
+            "/api/admin/reports": {
+                "post": admin(with_description(
+                    with_body(
+                        accepted(operation("createReport", "Admin", "Queue a CSV or XLSX report; the requester is sent a download link once it is built", Some("Report"))),
+                        "CreateReportRequest",
+                    ),
+                    "Reports are built one at a time in the background. When one is ready, or has failed, `callback_url` \
+                     is sent a POST with `report_id`, `kind`, `status`, `requested_by`, `download_url` and `error`. \
+                     The download link is signed and expires after `REPORT_LINK_TTL_SECS`; it needs no token. \
+                     `callback_url` must be http(s) on a host in `REPORT_CALLBACK_HOSTS`, or the request gets 400 `VALIDATION_ERROR`. \
+                     Answers 503 `SATURATED` when `REPORT_QUEUE_CAPACITY` reports are already waiting.",
+                )),
+            },
+            "/api/admin/reports/{id}": {
+                "get": admin(with_parameters(
+                    operation("getReport", "Admin", "Status of a report, with a fresh download link once it is ready", Some("Report")),
+                    vec![id_parameter()],
+                )),
+            },
+            "/api/admin/rate-limits": {
+                "get": admin(operation("listRateLimitedClients", "Admin", "Clients that went over a rate limit most often", Some("RateLimitReport"))),
+            },
+            "/api/events/poll": {
+                "get": with_description(
+                    with_parameters(
+                        operation("pollEvents", "Events", "Events past a cursor, waiting up to 25s for the next one", Some("EventPoll")),
+                        vec![
+                            query_parameter("cursor", json!({ "type": "string" })),
+                            query_parameter("topics", json!({ "type": "string", "default": "presence" })),
+                            query_parameter("wait", json!({ "type": "integer", "minimum": 0, "maximum": 25 })),
+                        ],
+                    ),
+                    "Long-polling fallback for `/api/events`. Requires a session or the admin token. Answers at once \
+                     when events past `cursor` are buffered, otherwise waits up to `wait` seconds for the next one; \
+                     pass the returned `cursor` to the next poll. Without a cursor, only later events are returned.",
+                ),
+            },
+        },
+        "components": {
+            "securitySchemes": {
+                "adminToken": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "`ADMIN_API_TOKEN`. Holds every scope. Admin routes also take the session token of a user with the `admin` role. Tokens issued to OpenID clients never open them.",
+                },
+                "sessionToken": { "type": "http", "scheme": "bearer", "description": "A session token from signing in. Holds every scope." },
+                "oidc": oidc_scheme(),
+            },
+            "schemas": {
+                "ApiError": object(
+                    &["code", "message"],
+                    json!({
+                        "code": { "type": "string" },
+                        "message": { "type": "string" },
+                        "details": { "type": "object", "additionalProperties": true },
+                    }),
+                ),
+                "ProblemDetails": {
+                    "type": "object",
+                    "description": "RFC 9457 problem document. Other `error.details` entries, such as `correlation_id`, \
+                                    are extension members of their own.",
+                    "required": ["type", "title", "status", "detail", "code"],
+                    "properties": {
+                        "type": {
+                            "type": "string",
+                            "format": "uri-reference",
+                            "description": "`/problems/` and the error code in kebab case, e.g. `/problems/validation-error`",
+                        },
+                        "title": { "type": "string", "description": "The same for every problem of this type" },
+                        "status": { "type": "integer" },
+                        "detail": { "type": "string" },
+                        "instance": { "type": "string", "format": "uri-reference", "description": "Path of the request" },
+                        "code": { "type": "string", "description": "The `error.code` of the envelope" },
+                        "errors": {
+                            "type": "array",
+                            "description": "Failed input rules of `validation-error` and conflict problems",
+                            "items": { "$ref": "#/components/schemas/ProblemViolation" },
+                        },
+                    },
+                    "additionalProperties": true,
+                },
+                "ProblemViolation": object(
+                    &["pointer", "field", "code", "detail"],
+                    json!({
+                        "pointer": { "type": "string", "description": "JSON Pointer fragment into the request body, e.g. `#/address/city`" },
+                        "field": { "type": "string", "description": "Dotted path, as in the envelope's `details.fields`" },
+                        "code": { "type": "string", "description": "The rule that failed, e.g. `email` or `length`; `invalid` for custom checks" },
+                        "detail": { "type": "string" },
+                    }),
+                ),
+                "Meta": object(
+                    &[],
+                    json!({
+                        "page": { "type": "integer", "format": "int32" },
+                        "limit": { "type": "integer", "format": "int32" },
+                        "total": { "type": "integer", "format": "int64" },
+                        "total_pages": { "type": "integer", "format": "int32" },
+                        "total_exact": { "type": "boolean" },
+                        "links": { "$ref": "#/components/schemas/PageLinks" },
+                    }),
+                ),
+                "PageLinks": object(
+                    &["first", "last"],
+                    json!({
+                        "first": { "type": "string", "format": "uri" },
+                        "prev": { "type": "string", "format": "uri", "nullable": true },
+                        "next": { "type": "string", "format": "uri", "nullable": true },
+                        "last": { "type": "string", "format": "uri" },
+                    }),
+                ),
+                "User": object(
+                    &["id", "email", "metadata", "created_at", "updated_at"],
+                    json!({
+                        "id": { "type": "string", "format": "uuid" },
+                        "email": { "type": "string", "format": "email" },
+                        "metadata": metadata_schema(),
+                        "created_at": { "type": "string", "format": "date-time" },
+                        "updated_at": { "type": "string", "format": "date-time" },
+                        "presence": { "$ref": "#/components/schemas/UserPresence" },
+                    }),
+                ),
+                "UserPresence": object(
+                    &["status", "last_seen_at"],
+                    json!({
+                        "status": { "type": "string", "enum": ["online", "offline"] },
+                        "last_seen_at": { "type": "string", "format": "date-time", "nullable": true },
+                    }),
+                ),
+                "CreateUserRequest": object(
+                    &["email", "password"],
+                    json!({
+                        "email": { "type": "string", "format": "email" },
+                        "password": { "type": "string", "minLength": 6 },
+                        "metadata": metadata_schema(),
+                    }),
+                ),
+                "PatchUserRequest": object(
+                    &[],
+                    json!({
+                        "metadata": {
+                            "type": "object",
+                            "additionalProperties": true,
+                            "description": "JSON merge patch (RFC 7396) for the user's metadata; null removes a key",
+                        },
+                    }),
+                ),
+                "UpdateUserRequest": { "type": "object", "additionalProperties": true },
+                "ListUsersResponse": object(
+                    &["users", "total", "total_exact", "page", "limit"],
+                    json!({
+                        "users": { "type": "array", "items": { "$ref": "#/components/schemas/User" } },
+                        "total": { "type": "integer", "format": "int64" },
+                        "total_exact": { "type": "boolean" },
+                        "page": { "type": "integer", "format": "int32" },
+                        "limit": { "type": "integer", "format": "int32" },
+                    }),
+                ),
+                "UserOverviewResponse": object(
+                    &["user", "partial", "missing"],
+                    json!({
+                        "user": { "$ref": "#/components/schemas/User" },
+                        "sessions": { "$ref": "#/components/schemas/SessionsOverview" },
+                        "partial": { "type": "boolean" },
+                        "missing": { "type": "array", "items": { "$ref": "#/components/schemas/MissingPart" } },
+                    }),
+                ),
+                "SessionsOverview": object(
+                    &["active", "impersonations"],
+                    json!({
+                        "active": { "type": "integer", "format": "int32" },
+                        "last_seen_at": { "type": "string", "format": "date-time", "nullable": true },
+                        "impersonations": { "type": "array", "items": { "$ref": "#/components/schemas/ImpersonationEntry" } },
+                    }),
+                ),
+                "ImpersonationEntry": object(
+                    &["actor", "started_at", "expires_at"],
+                    json!({
+                        "actor": { "type": "string" },
+                        "started_at": { "type": "string", "format": "date-time" },
+                        "expires_at": { "type": "string", "format": "date-time" },
+                    }),
+                ),
+                "MissingPart": object(
+                    &["source", "reason"],
+                    json!({
+                        "source": { "type": "string" },
+                        "reason": { "type": "string", "enum": ["timeout", "error"] },
+                    }),
+                ),
+                "Money": object(
+                    &["amount", "currency"],
+                    json!({
+                        "amount": { "type": "string", "format": "decimal", "pattern": "^-?[0-9]+(\\.[0-9]+)?$", "example": "12.50" },
+                        "currency": { "type": "string", "description": "ISO 4217 code", "example": "EUR" },
+                    }),
+                ),
+                "SignInRequest": object(
+                    &["email", "password"],
+                    json!({
+                        "email": { "type": "string", "format": "email" },
+                        "password": { "type": "string", "format": "password" },
+                    }),
+                ),
+                "SignInResponse": object(
+                    &["token", "session_id", "expires_at"],
+                    json!({
+                        "token": { "type": "string", "description": "Bearer token of the new session; also set as the `session` cookie" },
+                        "session_id": { "type": "string", "format": "uuid" },
+                        "expires_at": { "type": "string", "format": "date-time" },
+                    }),
+                ),
+                "CsrfTokenResponse": object(
+                    &["token", "header"],
+                    json!({
+                        "token": { "type": "string" },
+                        "header": { "type": "string" },
+                    }),
+                ),
+                "DeviceVerificationRequest": object(
+                    &["user_code", "approve"],
+                    json!({
+                        "user_code": { "type": "string", "description": "As shown on the device; case and dashes are ignored", "example": "WDJB-MJHT" },
+                        "approve": { "type": "boolean" },
+                    }),
+                ),
+                "PendingDeviceResponse": object(
+                    &["client_id", "scope", "expires_at"],
+                    json!({
+                        "client_id": { "type": "string" },
+                        "scope": { "type": "string", "description": "Space-separated, as the device asked for them" },
+                        "expires_at": { "type": "string", "format": "date-time" },
+                    }),
+                ),
+                "DeviceVerificationResponse": object(
+                    &["client_id", "scope", "approved"],
+                    json!({
+                        "client_id": { "type": "string" },
+                        "scope": { "type": "string" },
+                        "approved": { "type": "boolean" },
+                    }),
+                ),
+                "Product": object(
+                    &["id", "name", "price", "price_display", "created_at"],
+                    json!({
+                        "id": { "type": "string", "format": "uuid" },
+                        "name": { "type": "string" },
+                        "price": { "$ref": "#/components/schemas/Money" },
+                        "price_display": { "type": "string" },
+                        "created_at": { "type": "string", "format": "date-time" },
+                    }),
+                ),
+                "CreateProductRequest": object(
+                    &["name", "price"],
+                    json!({
+                        "name": { "type": "string", "minLength": 1, "maxLength": 200 },
+                        "price": { "$ref": "#/components/schemas/Money" },
+                    }),
+                ),
+                "HealthResponse": object(
+                    &["status", "timestamp", "service", "region", "checks", "degradations"],
+                    json!({
+                        "status": { "type": "string", "enum": ["healthy", "degraded", "unhealthy"] },
+                        "timestamp": { "type": "string", "format": "date-time" },
+                        "service": { "type": "string" },
+                        "region": { "type": "string" },
+                        "checks": { "type": "array", "items": { "$ref": "#/components/schemas/HealthCheck" } },
+                        "degradations": { "type": "array", "items": { "$ref": "#/components/schemas/DegradationState" } },
+                    }),
+                ),
+                "DegradationState": object(
+                    &["dependency", "fallback", "mode", "active"],
+                    json!({
+                        "dependency": { "type": "string" },
+                        "fallback": { "type": "string" },
+                        "mode": { "type": "string", "enum": ["fallback", "strict"] },
+                        "active": { "type": "boolean" },
+                        "since": { "type": "string", "format": "date-time", "nullable": true },
+                    }),
+                ),
+                "HealthCheck": object(
+                    &["name", "status", "critical", "latency_ms"],
+                    json!({
+                        "name": { "type": "string" },
+                        "status": { "type": "string", "enum": ["healthy", "unhealthy"] },
+                        "critical": { "type": "boolean" },
+                        "latency_ms": { "type": "number", "format": "double" },
+                        "error": { "type": "string" },
+                    }),
+                ),
+                "DependenciesResponse": object(
+                    &["status", "timestamp", "dependencies"],
+                    json!({
+                        "status": { "type": "string", "enum": ["healthy", "degraded", "unhealthy"] },
+                        "timestamp": { "type": "string", "format": "date-time" },
+                        "dependencies": { "type": "array", "items": { "$ref": "#/components/schemas/DependencyStatus" } },
+                    }),
+                ),
+                "DependencyStatus": object(
+                    &["name", "status", "critical", "latency_ms", "last_checked", "last_success", "consecutive_failures", "stale"],
+                    json!({
+                        "name": { "type": "string" },
+                        "status": { "type": "string", "enum": ["healthy", "unhealthy"] },
+                        "critical": { "type": "boolean" },
+                        "latency_ms": { "type": "number", "format": "double" },
+                        "last_checked": { "type": "string", "format": "date-time", "nullable": true },
+                        "last_success": { "type": "string", "format": "date-time", "nullable": true },
+                        "consecutive_failures": { "type": "integer", "format": "int32" },
+                        "stale": { "type": "boolean" },
+                        "error": { "type": "string" },
+                    }),
+                ),
+                "ReadyResponse": object(
+                    &["status", "timestamp", "deployment_id", "deployment_color", "region", "checks"],
+                    json!({
+                        "status": { "type": "string" },
+                        "timestamp": { "type": "string", "format": "date-time" },
+                        "deployment_id": { "type": "string" },
+                        "deployment_color": { "type": "string" },
+                        "region": { "type": "string" },
+                        "checked_at": { "type": "string", "format": "date-time", "nullable": true },
+                        "checks": { "type": "array", "items": { "$ref": "#/components/schemas/HealthCheck" } },
+                    }),
+                ),
+                "LiveResponse": object(
+                    &["status", "timestamp"],
+                    json!({
+                        "status": { "type": "string" },
+                        "timestamp": { "type": "string", "format": "date-time" },
+                    }),
+                ),
+                "InfoResponse": object(
+                    &["service", "version", "deployment_id", "deployment_color", "region", "draining", "started_at"],
+                    json!({
+                        "service": { "type": "string" },
+                        "version": { "type": "string" },
+                        "deployment_id": { "type": "string" },
+                        "deployment_color": { "type": "string" },
+                        "region": { "type": "string" },
+                        "draining": { "type": "boolean" },
+                        "started_at": { "type": "string", "format": "date-time" },
+                    }),
+                ),
+                "DrainResponse": object(
+                    &["deployment_id", "draining"],
+                    json!({
+                        "deployment_id": { "type": "string" },
+                        "draining": { "type": "boolean" },
+                    }),
+                ),
+                "Anomaly": object(
+                    &["route", "kind", "observed", "baseline", "since"],
+                    json!({
+                        "route": { "type": "string" },
+                        "kind": { "type": "string", "enum": ["error_rate_spike", "latency_spike", "traffic_spike", "traffic_drop"] },
+                        "observed": { "type": "number", "format": "double" },
+                        "baseline": { "type": "number", "format": "double" },
+                        "since": { "type": "string", "format": "date-time" },
+                    }),
+                ),
+                "Session": object(
+                    &["id", "user_id", "kind", "created_at", "last_seen_at", "expires_at"],
+                    json!({
+                        "id": { "type": "string", "format": "uuid" },
+                        "user_id": { "type": "string", "format": "uuid" },
+                        "kind": { "type": "string", "enum": ["session", "refresh_token", "impersonation"] },
+                        "device": { "type": "string", "nullable": true },
+                        "ip_address": { "type": "string", "nullable": true },
+                        "created_at": { "type": "string", "format": "date-time" },
+                        "last_seen_at": { "type": "string", "format": "date-time" },
+                        "expires_at": { "type": "string", "format": "date-time" },
+                        "impersonated_by": { "type": "string", "nullable": true },
+                        "roles": { "type": "array", "items": { "type": "string" } },
+                        "tenant": { "type": "string", "nullable": true },
+                    }),
+                ),
+                "SessionsResponse": object(
+                    &["user_id", "sessions"],
+                    json!({
+                        "user_id": { "type": "string", "format": "uuid" },
+                        "sessions": { "type": "array", "items": { "$ref": "#/components/schemas/Session" } },
+                    }),
+                ),
+                "ImpersonateRequest": object(
+                    &["reason"],
+                    json!({
+                        "reason": { "type": "string", "minLength": 1 },
+                    }),
+                ),
+                "ImpersonationResponse": object(
+                    &["token", "user_id", "session_id", "expires_at"],
+                    json!({
+                        "token": { "type": "string" },
+                        "user_id": { "type": "string", "format": "uuid" },
+                        "session_id": { "type": "string", "format": "uuid" },
+                        "expires_at": { "type": "string", "format": "date-time" },
+                    }),
+                ),
+                "RevokeSessionsResponse": object(
+                    &["user_id", "revoked"],
+                    json!({
+                        "user_id": { "type": "string", "format": "uuid" },
+                        "revoked": { "type": "integer", "format": "int64" },
+                    }),
+                ),
+                "AnomaliesResponse": object(
+                    &["window_secs", "routes_tracked", "anomalies"],
+                    json!({
+                        "window_secs": { "type": "integer", "format": "int64" },
+                        "routes_tracked": { "type": "integer", "format": "int64" },
+                        "anomalies": { "type": "array", "items": { "$ref": "#/components/schemas/Anomaly" } },
+                    }),
+                ),
+                "MountedRoute": object(
+                    &["name", "method", "path", "handler", "admin"],
+                    json!({
+                        "name": { "type": "string" },
+                        "method": { "type": "string" },
+                        "path": { "type": "string" },
+                        "handler": { "type": "string" },
+                        "admin": { "type": "boolean" },
+                    }),
+                ),
+                "CpuPoolStats": integer_object(&[
+                    "threads", "active", "interactive_queued", "interactive_capacity", "batch_queued",
+                    "batch_capacity", "completed", "panicked", "rejected_interactive", "rejected_batch",
+                ]),
+                "ProcessMemory": integer_object(&["rss_bytes", "peak_rss_bytes", "virtual_bytes"]),
+                "MemoryReport": object(
+                    &["pressure", "reclaimed_entries"],
+                    json!({
+                        "pressure": { "type": "string", "enum": ["normal", "soft", "hard"] },
+                        "memory": { "$ref": "#/components/schemas/ProcessMemory" },
+                        "soft_limit_bytes": { "type": "integer", "nullable": true },
+                        "hard_limit_bytes": { "type": "integer", "nullable": true },
+                        "reclaimed_entries": { "type": "integer" },
+                        "sampled_at": { "type": "string", "format": "date-time", "nullable": true },
+                    }),
+                ),
+                "ObjectPoolStats": object(
+                    &["name", "pooled", "max_pooled", "hits", "misses", "discarded"],
+                    json!({
+                        "name": { "type": "string" },
+                        "pooled": { "type": "integer" },
+                        "max_pooled": { "type": "integer" },
+                        "hits": { "type": "integer" },
+                        "misses": { "type": "integer" },
+                        "discarded": { "type": "integer" },
+                    }),
+                ),
+                "ObjectPoolsResponse": object(
+                    &["pools"],
+                    json!({
+                        "pools": { "type": "array", "items": { "$ref": "#/components/schemas/ObjectPoolStats" } },
+                    }),
+                ),
+                "ShardNodeStats": object(
+                    &["node", "picks", "share"],
+                    json!({
+                        "node": { "type": "string" },
+                        "picks": { "type": "integer" },
+                        "share": { "type": "number", "format": "double" },
+                    }),
+                ),
+                "Rebalance": object(
+                    &["at", "added", "removed", "moved_fraction"],
+                    json!({
+                        "at": { "type": "string", "format": "date-time" },
+                        "added": { "type": "array", "items": { "type": "string" } },
+                        "removed": { "type": "array", "items": { "type": "string" } },
+                        "moved_fraction": { "type": "number", "format": "double" },
+                    }),
+                ),
+                "ShardRingStats": object(
+                    &["name", "nodes", "rebalances"],
+                    json!({
+                        "name": { "type": "string" },
+                        "nodes": { "type": "array", "items": { "$ref": "#/components/schemas/ShardNodeStats" } },
+                        "rebalances": { "type": "integer" },
+                        "last_rebalance": { "$ref": "#/components/schemas/Rebalance" },
+                    }),
+                ),
+                "ShardsResponse": object(
+                    &["rings"],
+                    json!({
+                        "rings": { "type": "array", "items": { "$ref": "#/components/schemas/ShardRingStats" } },
+                    }),
+                ),
+                "LatencyBucket": object(
+                    &["count"],
+                    json!({
+                        "le_ms": { "type": "integer", "nullable": true },
+                        "count": { "type": "integer" },
+                    }),
+                ),
+                "MethodReport": object(
+                    &["method", "calls", "faults", "error_rate", "over_budget", "mean_ms", "buckets"],
+                    json!({
+                        "method": { "type": "string" },
+                        "calls": { "type": "integer" },
+                        "faults": { "type": "integer" },
+                        "error_rate": { "type": "number", "format": "double" },
+                        "over_budget": { "type": "integer" },
+                        "mean_ms": { "type": "number", "format": "double" },
+                        "p50_ms": { "type": "integer", "nullable": true },
+                        "p95_ms": { "type": "integer", "nullable": true },
+                        "p99_ms": { "type": "integer", "nullable": true },
+                        "buckets": { "type": "array", "items": { "$ref": "#/components/schemas/LatencyBucket" } },
+                    }),
+                ),
+                "MethodMetricsResponse": object(
+                    &["methods"],
+                    json!({
+                        "methods": { "type": "array", "items": { "$ref": "#/components/schemas/MethodReport" } },
+                    }),
+                ),
+                "ConsumerReport": object(
+                    &["name", "transport", "connected", "lag", "in_flight", "processed", "failed", "retries",
+                      "dead_lettered", "dead_letter_depth", "mean_ms", "buckets"],
+                    json!({
+                        "name": { "type": "string" },
+                        "transport": { "type": "string", "description": "`in_process`, or the bus the consumer reads from" },
+                        "connected": { "type": "boolean" },
+                        "lag": { "type": "integer", "description": "Messages waiting to be handled" },
+                        "in_flight": { "type": "integer" },
+                        "processed": { "type": "integer" },
+                        "failed": { "type": "integer" },
+                        "retries": { "type": "integer" },
+                        "dead_lettered": { "type": "integer" },
+                        "dead_letter_depth": { "type": "integer" },
+                        "mean_ms": { "type": "number", "format": "double" },
+                        "p50_ms": { "type": "integer", "nullable": true },
+                        "p95_ms": { "type": "integer", "nullable": true },
+                        "p99_ms": { "type": "integer", "nullable": true },
+                        "buckets": { "type": "array", "items": { "$ref": "#/components/schemas/LatencyBucket" } },
+                        "last_processed_at": { "type": "string", "format": "date-time", "nullable": true },
+                    }),
+                ),
+                "ConsumersResponse": object(
+                    &["consumers"],
+                    json!({
+                        "consumers": { "type": "array", "items": { "$ref": "#/components/schemas/ConsumerReport" } },
+                    }),
+                ),
+                "LaneStats": object(
+                    &["lane", "limit", "in_flight", "admitted", "rejected"],
+                    json!({
+                        "lane": { "type": "string", "enum": ["critical", "interactive", "bulk"] },
+                        "limit": { "type": "integer" },
+                        "in_flight": { "type": "integer" },
+                        "admitted": { "type": "integer" },
+                        "rejected": { "type": "integer" },
+                    }),
+                ),
+                "LaneReport": object(
+                    &["capacity", "in_flight", "lanes"],
+                    json!({
+                        "capacity": { "type": "integer" },
+                        "in_flight": { "type": "integer" },
+                        "lanes": { "type": "array", "items": { "$ref": "#/components/schemas/LaneStats" } },
+                    }),
+                ),
+                "RouteCacheReport": object(
+                    &["route", "fresh_secs", "stale_secs", "entries", "hits", "stale_serves", "misses", "refreshes", "refresh_failures"],
+                    json!({
+                        "route": { "type": "string" },
+                        "fresh_secs": { "type": "integer" },
+                        "stale_secs": { "type": "integer" },
+                        "entries": { "type": "integer" },
+                        "hits": { "type": "integer", "format": "int64" },
+                        "stale_serves": { "type": "integer", "format": "int64" },
+                        "misses": { "type": "integer", "format": "int64" },
+                        "refreshes": { "type": "integer", "format": "int64" },
+                        "refresh_failures": { "type": "integer", "format": "int64" },
+                    }),
+                ),
+                "ResponseCacheReport": object(
+                    &["max_entries", "entries", "routes"],
+                    json!({
+                        "max_entries": { "type": "integer" },
+                        "entries": { "type": "integer" },
+                        "routes": { "type": "array", "items": { "$ref": "#/components/schemas/RouteCacheReport" } },
+                    }),
+                ),
+                "AuditRecord": object(
+                    &["seq", "recorded_at", "level", "event", "message", "fields"],
+                    json!({
+                        "seq": { "type": "integer", "format": "int64" },
+                        "recorded_at": { "type": "string", "format": "date-time" },
+                        "level": { "type": "string" },
+                        "event": { "type": "string" },
+                        "message": { "type": "string" },
+                        "fields": { "type": "object", "additionalProperties": true },
+                    }),
+                ),
+                "AuditPage": object(
+                    &["records", "archived", "next_before_seq"],
+                    json!({
+                        "records": { "type": "array", "items": { "$ref": "#/components/schemas/AuditRecord" } },
+                        "archived": { "type": "boolean" },
+                        "next_before_seq": { "type": "integer", "format": "int64", "nullable": true },
+                    }),
+                ),
+                "LimitedClient": object(
+                    &["bucket", "client", "limited", "last_limited_at"],
+                    json!({
+                        "bucket": { "type": "string" },
+                        "client": { "type": "string" },
+                        "limited": { "type": "integer" },
+                        "last_limited_at": { "type": "string", "format": "date-time" },
+                    }),
+                ),
+                "RateLimitReport": object(
+                    &["mode", "clients"],
+                    json!({
+                        "mode": { "type": "string", "enum": ["enforce", "shadow"] },
+                        "clients": { "type": "array", "items": { "$ref": "#/components/schemas/LimitedClient" } },
+                    }),
+                ),
+                "DeprecationUsage": object(
+                    &["name", "deprecated_on", "sunset_on", "requests"],
+                    json!({
+                        "name": { "type": "string" },
+                        "deprecated_on": { "type": "string", "format": "date" },
+                        "sunset_on": { "type": "string", "format": "date" },
+                        "requests": { "type": "integer" },
+                        "last_used_at": { "type": "string", "format": "date-time", "nullable": true },
+                    }),
+                ),
+                "DeprecationsResponse": object(
+                    &["deprecations"],
+                    json!({
+                        "deprecations": { "type": "array", "items": { "$ref": "#/components/schemas/DeprecationUsage" } },
+                    }),
+                ),
+                "InvalidateCacheRequest": object(
+                    &["reason"],
+                    json!({
+                        "reason": { "type": "string", "minLength": 1 },
+                        "keys": { "type": "array", "maxItems": 100, "items": { "type": "string", "minLength": 1 } },
+                        "prefixes": { "type": "array", "maxItems": 100, "items": { "type": "string", "minLength": 1 } },
+                        "tags": {
+                            "type": "array",
+                            "maxItems": 100,
+                            "items": { "type": "string", "minLength": 1 },
+                            "description": "Surrogate keys such as `user:<id>` or `users:list`; also purged at the CDN",
+                        },
+                    }),
+                ),
+                "CacheInvalidated": object(
+                    &["cache", "removed"],
+                    json!({
+                        "cache": { "type": "string" },
+                        "removed": { "type": "integer" },
+                    }),
+                ),
+                "InvalidationReport": object(
+                    &["caches", "cdn"],
+                    json!({
+                        "caches": { "type": "array", "items": { "$ref": "#/components/schemas/CacheInvalidated" } },
+                        "cdn": { "type": "string", "enum": ["not_configured", "no_tags", "purged", "failed"] },
+                        "cdn_error": { "type": "string" },
+                    }),
+                ),
+                "RuntimeReport": integer_object(&["worker_threads", "max_blocking_threads", "event_interval"]),
+                "Listener": object(
+                    &["protocol", "address", "shared"],
+                    json!({
+                        "protocol": { "type": "string" },
+                        "address": { "type": "string" },
+                        "shared": { "type": "boolean" },
+                    }),
+                ),
+                "MigrationStatus": object(
+                    &["user_store", "user_record_version", "sql_migrations", "sql_applied"],
+                    json!({
+                        "user_store": { "type": "string" },
+                        "user_record_version": { "type": "integer" },
+                        "sql_migrations": { "type": "array", "items": { "type": "string" } },
+                        "sql_applied": { "type": "boolean" },
+                    }),
+                ),
+                "ComponentTiming": object(
+                    &["component", "duration_ms"],
+                    json!({
+                        "component": { "type": "string" },
+                        "duration_ms": { "type": "integer" },
+                    }),
+                ),
+                "BootReport": object(
+                    &["version", "deployment_id", "deployment_color", "region", "pid", "booted_at", "boot_duration_ms",
+                      "runtime", "config", "features", "listeners", "migrations", "components", "routes"],
+                    json!({
+                        "version": { "type": "string" },
+                        "deployment_id": { "type": "string" },
+                        "deployment_color": { "type": "string" },
+                        "region": { "type": "string" },
+                        "pid": { "type": "integer" },
+                        "worker": { "type": "integer", "nullable": true },
+                        "booted_at": { "type": "string", "format": "date-time" },
+                        "boot_duration_ms": { "type": "integer" },
+                        "runtime": { "$ref": "#/components/schemas/RuntimeReport" },
+                        "config": {
+                            "type": "object",
+                            "additionalProperties": true,
+                            "description": "Every setting by field name; secrets that are set read `[redacted]`",
+                        },
+                        "features": { "type": "array", "items": { "type": "string" } },
+                        "listeners": { "type": "array", "items": { "$ref": "#/components/schemas/Listener" } },
+                        "migrations": { "$ref": "#/components/schemas/MigrationStatus" },
+                        "components": { "type": "array", "items": { "$ref": "#/components/schemas/ComponentTiming" } },
+                        "routes": { "type": "array", "items": { "$ref": "#/components/schemas/MountedRoute" } },
+                    }),
+                ),
+                "CreateReportRequest": object(
+                    &["kind", "format", "requested_by"],
+                    json!({
+                        "kind": { "type": "string", "enum": ["users_by_signup_date", "activity_summary"] },
+                        "format": { "type": "string", "enum": ["csv", "xlsx"] },
+                        "requested_by": { "type": "string", "minLength": 1, "maxLength": 100 },
+                        "callback_url": { "type": "string", "format": "uri" },
+                    }),
+                ),
+                "Report": object(
+                    &["id", "kind", "format", "status", "requested_by", "created_at"],
+                    json!({
+                        "id": { "type": "string", "format": "uuid" },
+                        "kind": { "type": "string", "enum": ["users_by_signup_date", "activity_summary"] },
+                        "format": { "type": "string", "enum": ["csv", "xlsx"] },
+                        "status": { "type": "string", "enum": ["queued", "running", "ready", "failed"] },
+                        "requested_by": { "type": "string" },
+                        "created_at": { "type": "string", "format": "date-time" },
+                        "completed_at": { "type": "string", "format": "date-time", "nullable": true },
+                        "rows": { "type": "integer", "nullable": true },
+                        "error": { "type": "string", "nullable": true },
+                        "download_url": { "type": "string", "nullable": true },
+                    }),
+                ),
+                "StreamEvent": object(
+                    &["seq", "type", "topics", "data", "published_at"],
+                    json!({
+                        "seq": { "type": "integer", "format": "int64" },
+                        "type": { "type": "string" },
+                        "topics": { "type": "array", "items": { "type": "string" } },
+                        "data": { "type": "object", "additionalProperties": true },
+                        "published_at": { "type": "string", "format": "date-time" },
+                    }),
+                ),
+                "EventPoll": object(
+                    &["events", "cursor"],
+                    json!({
+                        "events": { "type": "array", "items": { "$ref": "#/components/schemas/StreamEvent" } },
+                        "cursor": { "type": "string" },
+                        "reset": { "type": "string", "enum": ["expired", "unknown_stream", "evicted"] },
+                    }),
+                ),
+                "RoutesResponse": object(
+                    &["routes"],
+                    json!({
+                        "routes": { "type": "array", "items": { "$ref": "#/components/schemas/MountedRoute" } },
+                    }),
+                ),
+            },
+        },
+    });
+    apply_deprecations(&mut spec, DEPRECATIONS);
+    apply_scopes(&mut spec);
+    spec
+}
+
+/// Session tokens issued by the OpenID provider, with the scopes they can be
+/// limited to
+fn oidc_scheme() -> Value {
+    let scopes: serde_json::Map<String, Value> = Scope::ALL
+        .into_iter()
+        .filter(|scope| scope.user_grantable())
+        .map(|scope| (scope.name().to_string(), json!(scope.description())))
+        .collect();
+    json!({
+        "type": "oauth2",
+        "description": "Session tokens from the OpenID provider. Requesting API scopes, e.g. `openid users:read` \
+                        or `openid users:*`, limits the token to them; without any it holds every scope. A token \
+                        without the scope an operation lists gets 403 `INSUFFICIENT_SCOPE`.",
+        "flows": {
+            "authorizationCode": {
+                "authorizationUrl": RouteName::OidcAuthorize.template(),
+                "tokenUrl": RouteName::OidcToken.template(),
+                "scopes": scopes,
+            },
+        },
+    })
+}
+
+/// Adds the scope of each documented route's policy to its security
+/// requirements. Public routes get an empty requirement too, since they
+/// still take anonymous callers; admin scopes are left to the admin token.
+fn apply_scopes(spec: &mut Value) {
+    for route in ROUTES.iter().filter(|route| route.documented) {
+        let Some(scope) = route.name.policy().scope.filter(|scope| scope.user_grantable()) else { continue };
+        let operation = &mut spec["paths"][route.name.openapi_path()][route.method.as_str().to_ascii_lowercase()];
+        let mut security = match operation["security"].take() {
+            Value::Array(requirements) => requirements,
+            _ => vec![json!({})],
+        };
+        security.push(json!({ "oidc": [scope.name()] }));
+        operation["security"] = Value::Array(security);
+
+        let lacking = format!("The token lacks the `{}` scope", scope.name());
+        match operation["responses"]["403"]["description"].as_str() {
+            Some(existing) => operation["responses"]["403"]["description"] = json!(format!("{existing}, or {}", lacking.to_lowercase())),
+            None => {
+                operation["responses"]["403"] = json!({
+                    "description": lacking,
+                    "content": { "application/json": { "schema": envelope(json!({ "nullable": true })) } },
+                })
+            }
+        }
+    }
+}
+
+/// The spec as served: without the stub routes when they are hidden
+pub fn served_spec(hide_unimplemented: bool) -> Value {
+    let mut spec = openapi_spec();
+    if hide_unimplemented {
+        for route in ROUTES.iter().filter(|route| route.stub) {
+            let path = route.name.openapi_path();
+            if let Some(methods) = spec["paths"][&path].as_object_mut() {
+                methods.remove(&route.method.as_str().to_ascii_lowercase());
+                if methods.is_empty() {
+                    spec["paths"].as_object_mut().map(|paths| paths.remove(&path));
+                }
+            }
+        }
+    }
+    spec
+}
+
+/// GET /api/docs/openapi.json
+/// The spec is fixed for a build and setting, so it is rendered and compressed once
+pub async fn openapi_json(State(hide_unimplemented): State<bool>, headers: HeaderMap) -> Response {
+    static SPECS: [OnceLock<Asset>; 2] = [OnceLock::new(), OnceLock::new()];
+    SPECS[hide_unimplemented as usize]
+        .get_or_init(|| {
+            let body = serde_json::to_vec(&served_spec(hide_unimplemented)).expect("the spec is plain JSON");
+            Asset::generated("application/json", body)
+        })
+        .respond(&headers)
+}
+
+fn operation(operation_id: &str, tag: &str, summary: &str, data_schema: Option<&str>) -> Value {
+    let data = match data_schema {
+        Some(schema) => json!({ "$ref": format!("#/components/schemas/{}", schema) }),
+        None => json!({ "nullable": true }),
+    };
+
+    json!({
+        "operationId": operation_id,
+        "tags": [tag],
+        "summary": summary,
+        "responses": {
+            "200": {
+                "description": "Standard success envelope",
+                "content": { "application/json": { "schema": envelope(data) } },
+            },
+            "default": {
+                "description": "Standard error envelope, or a problem document with `RESPONSE_ERROR_FORMAT=problem`",
+                "content": {
+                    "application/json": { "schema": envelope(json!({ "nullable": true })) },
+                    "application/problem+json": { "schema": { "$ref": "#/components/schemas/ProblemDetails" } },
+                },
+            },
+        },
+    })
+}
+
+fn envelope(data: Value) -> Value {
+    json!({
+        "type": "object",
+        "required": ["success"],
+        "properties": {
+            "success": { "type": "boolean" },
+            "data": data,
+            "error": { "$ref": "#/components/schemas/ApiError" },
+            "meta": { "$ref": "#/components/schemas/Meta" },
+        },
+    })
+}
+
+fn with_parameters(mut operation: Value, parameters: Vec<Value>) -> Value {
+    operation["parameters"] = Value::Array(parameters);
+    operation
+}
+
+fn with_description(mut operation: Value, description: &str) -> Value {
+    operation["description"] = json!(description);
+    operation
+}
+
+/// List endpoints also answer without the envelope; the generated clients
+/// always ask for it
+fn bare_list(mut operation: Value) -> Value {
+    let note = "With `?envelope=false` or `X-Envelope: false` the body is only the list, with the total in \
+                `X-Total-Count` and, for paged lists, page links in `Link`.";
+    operation["description"] = match operation["description"].as_str() {
+        Some(description) => json!(format!("{description}\n\n{note}")),
+        None => json!(note),
+    };
+    operation
+}
+
+/// Limits are the `USER_METADATA_*` defaults; deployments may configure others
+fn metadata_schema() -> Value {
+    json!({
+        "type": "object",
+        "additionalProperties": true,
+        "maxProperties": 64,
+        "description": "Free-form JSON, at most 4096 bytes, 4 levels deep and 64 keys in total",
+    })
+}
+
+fn with_body(mut operation: Value, schema: &str) -> Value {
+    operation["requestBody"] = json!({
+        "required": true,
+        "content": {
+            "application/json": {
+                "schema": { "$ref": format!("#/components/schemas/{}", schema) },
+            },
+        },
+    });
+    operation
+}
+
+/// Stub routes document the 501 they answer with
+fn not_implemented(mut operation: Value) -> Value {
+    operation["responses"]["501"] = json!({
+        "description": "Not implemented yet (`NOT_IMPLEMENTED`)",
+        "content": { "application/json": { "schema": envelope(json!({ "nullable": true })) } },
+    });
+    operation
+}
+
+/// Clashes with existing data answer 409; `details.fields` maps each
+/// clashing field to its messages, as validation errors do
+fn conflict(mut operation: Value, code: &str) -> Value {
+    operation["responses"]["409"] = json!({
+        "description": format!("Conflicts with existing data (`{code}`); `error.details.fields` names the fields"),
+        "content": { "application/json": { "schema": envelope(json!({ "nullable": true })) } },
+    });
+    operation
+}
+
+/// Answers `201 Created` with the new resource's URL in `Location`
+fn created(operation: Value) -> Value {
+    with_success_status(operation, "201", "Created; the envelope holds the new resource", "URL of the new resource")
+}
+
+/// Answers `202 Accepted` with the queued resource's URL in `Location`
+fn accepted(operation: Value) -> Value {
+    with_success_status(operation, "202", "Accepted; the envelope holds the queued resource", "URL to poll for the outcome")
+}
+
+fn with_success_status(mut operation: Value, status: &str, description: &str, location: &str) -> Value {
+    let mut success = operation["responses"]["200"].take();
+    success["description"] = json!(description);
+    success["headers"] = json!({
+        "Location": { "description": location, "schema": { "type": "string" } },
+    });
+    if let Some(responses) = operation["responses"].as_object_mut() {
+        responses.remove("200");
+        responses.insert(status.to_string(), success);
+    }
+    operation
+}
+
+fn admin(mut operation: Value) -> Value {
+    operation["security"] = json!([{ "adminToken": [] }]);
+    operation
+}
+
+/// Only the owner's session or the admin token; anyone else gets 404 (or
+/// 403 with `OWNERSHIP_DISCLOSURE=forbidden`)
+fn owned(mut operation: Value) -> Value {
+    operation["security"] = json!([{ "sessionToken": [] }, { "adminToken": [] }]);
+    operation["responses"]["403"] = json!({
+        "description": "Belongs to another user, with `OWNERSHIP_DISCLOSURE=forbidden` (otherwise 404)",
+        "content": { "application/json": { "schema": envelope(json!({ "nullable": true })) } },
+    });
+    operation
+}
+
+fn id_parameter() -> Value {
+    path_parameter("id")
+}
+
+fn path_parameter(name: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "path",
+        "required": true,
+        "schema": { "type": "string", "format": "uuid" },
+    })
+}
+
+fn query_parameter(name: &str, schema: Value) -> Value {
+    json!({
+        "name": name,
+        "in": "query",
+        "required": false,
+        "schema": schema,
+    })
+}
+
+fn object(required: &[&str], properties: Value) -> Value {
+    json!({
+        "type": "object",
+        "required": required,
+        "properties": properties,
+    })
+}
+
+/// Object whose fields are all required integers
+fn integer_object(fields: &[&str]) -> Value {
+    let properties = fields
+        .iter()
+        .map(|field| (field.to_string(), json!({ "type": "integer" })))
+        .collect();
+    object(fields, Value::Object(properties))
+}
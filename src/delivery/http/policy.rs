@@ -0,0 +1,79 @@
+use super::RouteName;
+use crate::domain::session::entities::Scope;
+use crate::infrastructure::Lane;
+use crate::middleware::{Cacheability, RateLimitBucket, ResourceKind, RoutePolicy};
+
+/// Orchestrator probes: never limited, answered quickly or not at all
+const PROBE: RoutePolicy =
+    RoutePolicy::public().limit(RateLimitBucket::Unlimited).timeout_secs(5).lane(Lane::Critical);
+/// Purged by surrogate key on writes, so the CDN can keep them a while
+const CDN_CACHED: Cacheability = Cacheability::Public { max_age_secs: 60 };
+const WRITE: RoutePolicy = RoutePolicy::public().limit(RateLimitBucket::Write);
+const ADMIN_READ: RoutePolicy = RoutePolicy::public().admin().scope(Scope::AdminRead).lane(Lane::Critical);
+const ADMIN_WRITE: RoutePolicy = WRITE.admin().scope(Scope::AdminWrite).lane(Lane::Critical);
+const USERS_READ: RoutePolicy = RoutePolicy::public().scope(Scope::UsersRead);
+const USERS_WRITE: RoutePolicy = WRITE.scope(Scope::UsersWrite);
+/// The user's own record; not cached, since the answer depends on the caller
+const OWN_USER: RoutePolicy = USERS_READ.owned(ResourceKind::User);
+
+impl RouteName {
+    /// Auth, scope, rate-limit bucket, timeout, cacheability and lane, applied
+    /// by the router when the route is mounted. Every route must be listed here.
+    pub const fn policy(self) -> RoutePolicy {
+        use RouteName::*;
+        match self {
+            HealthCheck | GetDependencies | ReadinessCheck | LivenessCheck => PROBE,
+            GetInfo => RoutePolicy::public().timeout_secs(5).lane(Lane::Critical),
+
+            ListUsers => USERS_READ.cache(CDN_CACHED),
+            GetUser => OWN_USER,
+            // Fans out to several stores; a minute-old answer is fine for dashboards
+            GetUserOverview => OWN_USER.stale_while_revalidate(5, 60),
+            // Visible to everyone, like the list; changes too often to cache
+            GetUserPresence => USERS_READ,
+            CreateUser => USERS_WRITE,
+            UpdateUser | PatchUser | DeleteUser => USERS_WRITE.owned(ResourceKind::User),
+
+            // Not cached: `price_display` follows the caller's locale
+            GetProduct => RoutePolicy::public().scope(Scope::ProductsRead),
+            CreateProduct => WRITE.scope(Scope::ProductsWrite),
+
+            // In the write bucket, so passwords cannot be guessed at read rates
+            SignIn => WRITE,
+            // A fresh token every time; must never be shared between sessions
+            IssueCsrfToken => RoutePolicy::public(),
+
+            // Fixed for the life of the signing key
+            OpenIdConfiguration | GetJwks => RoutePolicy::public().cache(Cacheability::Public { max_age_secs: 300 }),
+            // Depends on the caller's session
+            OidcAuthorize => RoutePolicy::public(),
+            // In the write bucket, so codes cannot be guessed at read rates
+            OidcToken | DeviceAuthorization | DescribeDevice | VerifyDevice => WRITE,
+
+            // Providers give up after about 10s and retry
+            ReceiveWebhook => RoutePolicy::public().limit(RateLimitBucket::Webhook),
+
+            ListAnomalies | ListUserSessions | ListRoutes | ListRateLimitedClients | ListDeprecations => ADMIN_READ,
+            CpuPoolStats | MemoryReport | ListObjectPools | ListShards | ListLanes | GetBootReport => ADMIN_READ,
+            // Percentiles over every recorded call; polled by the admin dashboard
+            ListMethodMetrics => ADMIN_READ.stale_while_revalidate(5, 60),
+            ListConsumers | GetResponseCacheStats | ListAuditEvents => ADMIN_READ,
+            GetReport => ADMIN_READ,
+            StartDraining | StopDraining | RevokeUserSessions | RevokeUserSession => ADMIN_WRITE,
+            StartImpersonation | StopImpersonation | InvalidateCache | CreateReport => ADMIN_WRITE,
+            // The signed link is the credential; files can be large, so off the interactive lane
+            DownloadReport => RoutePolicy::public().lane(Lane::Bulk),
+            // The handler authenticates, since the admin token counts too; the stream itself runs outside the policy
+            EventStream => RoutePolicy::public().scope(Scope::EventsRead),
+            // Off the interactive lane and given time past the longest wait; the slot is released while parked
+            PollEvents => RoutePolicy::public().scope(Scope::EventsRead).timeout_secs(30).lane(Lane::Bulk),
+
+            // Fixed for a build; revalidated by `ETag` once stale
+            OpenApiSpec | SwaggerUi | AdminDashboard | DeviceVerificationPage | StaticAsset => {
+                RoutePolicy::public().cache(Cacheability::Public { max_age_secs: 300 }).lane(Lane::Bulk)
+            }
+            // Generated from the whole route table on every request
+            PostmanCollection => RoutePolicy::public().cache(Cacheability::Public { max_age_secs: 300 }).lane(Lane::Bulk),
+        }
+    }
+}
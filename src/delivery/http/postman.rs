@@ -0,0 +1,224 @@
+use axum::extract::State;
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use serde_json::{json, Map, Value};
+
+use super::openapi::served_spec;
+use super::PublicUrl;
+use crate::codegen::requires_auth;
+
+const POSTMAN_SCHEMA: &str = "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+const DEFAULT_BASE_URL: &str = "http://localhost:3000";
+
+/// Convert an OpenAPI document into a Postman v2.1 collection.
+///
+/// Requests are grouped into folders by tag. The collection defines a
+/// `baseUrl` and an `authToken` variable; operations that require auth
+/// send `Authorization: Bearer {{authToken}}`.
+pub fn postman_collection(spec: &Value, base_url: &str) -> Value {
+    let mut folders: Vec<(String, Vec<Value>)> = Vec::new();
+
+    if let Some(paths) = spec["paths"].as_object() {
+        for (path, methods) in paths {
+            let Some(methods) = methods.as_object() else { continue };
+            for (method, operation) in methods {
+                let tag = operation["tags"][0].as_str().unwrap_or("Default").to_string();
+                let item = request_item(spec, path, method, operation);
+                match folders.iter_mut().find(|(name, _)| *name == tag) {
+                    Some((_, items)) => items.push(item),
+                    None => folders.push((tag, vec![item])),
+                }
+            }
+        }
+    }
+
+    json!({
+        "info": {
+            "name": spec["info"]["title"],
+            "description": format!("Generated from the OpenAPI spec, version {}", spec["info"]["version"].as_str().unwrap_or_default()),
+            "schema": POSTMAN_SCHEMA,
+        },
+        "variable": [
+            { "key": "baseUrl", "value": base_url, "type": "string" },
+            { "key": "authToken", "value": "", "type": "string" },
+        ],
+        "item": folders
+            .into_iter()
+            .map(|(name, items)| json!({ "name": name, "item": items }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// GET /api/docs/postman
+pub async fn postman_json(State(hide_unimplemented): State<bool>, public_url: Option<Extension<PublicUrl>>) -> Response {
+    // `PUBLIC_BASE_URL`, never the `Host` the collection was downloaded with, since the response is cached publicly
+    let base_url = public_url.map_or_else(|| DEFAULT_BASE_URL.to_string(), |Extension(PublicUrl(url))| url.to_string());
+
+    let mut response = Json(postman_collection(&served_spec(hide_unimplemented), &base_url)).into_response();
+    response.headers_mut().insert(
+        header::CONTENT_DISPOSITION,
+        header::HeaderValue::from_static("attachment; filename=\"rust-boilerplate.postman_collection.json\""),
+    );
+    response
+}
+
+fn request_item(spec: &Value, path: &str, method: &str, operation: &Value) -> Value {
+    let parameters = operation["parameters"].as_array().cloned().unwrap_or_default();
+
+    // Postman uses `:id` for path variables instead of `{id}`
+    let segments: Vec<String> = path
+        .trim_start_matches('/')
+        .split('/')
+        .map(|segment| match segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            Some(name) => format!(":{}", name),
+            None => segment.to_string(),
+        })
+        .collect();
+
+    let variables: Vec<Value> = parameters
+        .iter()
+        .filter(|parameter| parameter["in"] == "path")
+        .map(|parameter| json!({ "key": parameter["name"], "value": "" }))
+        .collect();
+    let query: Vec<Value> = parameters
+        .iter()
+        .filter(|parameter| parameter["in"] == "query")
+        .map(|parameter| json!({ "key": parameter["name"], "value": "", "disabled": true }))
+        .collect();
+
+    let mut request = json!({
+        "method": method.to_uppercase(),
+        "header": [],
+        "url": {
+            "raw": format!("{{{{baseUrl}}}}/{}", segments.join("/")),
+            "host": ["{{baseUrl}}"],
+            "path": segments,
+            "query": query,
+            "variable": variables,
+        },
+        "description": operation["summary"],
+    });
+
+    if requires_auth(operation) {
+        request["auth"] = json!({
+            "type": "bearer",
+            "bearer": [{ "key": "token", "value": "{{authToken}}", "type": "string" }],
+        });
+    }
+
+    let body_schema = &operation["requestBody"]["content"]["application/json"]["schema"];
+    if !body_schema.is_null() {
+        request["header"] = json!([{ "key": "Content-Type", "value": "application/json" }]);
+        request["body"] = json!({
+            "mode": "raw",
+            "raw": serde_json::to_string_pretty(&example(spec, body_schema)).unwrap_or_default(),
+            "options": { "raw": { "language": "json" } },
+        });
+    }
+
+    json!({
+        "name": operation["summary"],
+        "request": request,
+    })
+}
+
+/// Placeholder value for a schema, used to pre-fill request bodies
+fn example(spec: &Value, schema: &Value) -> Value {
+    if let Some(name) = schema["$ref"].as_str().and_then(|r| r.rsplit('/').next()) {
+        return example(spec, &spec["components"]["schemas"][name]);
+    }
+    match schema["type"].as_str() {
+        Some("object") => {
+            let mut object = Map::new();
+            if let Some(properties) = schema["properties"].as_object() {
+                for (name, property) in properties {
+                    object.insert(name.clone(), example(spec, property));
+                }
+            }
+            Value::Object(object)
+        }
+        Some("array") => json!([example(spec, &schema["items"])]),
+        Some("integer") | Some("number") => json!(0),
+        Some("boolean") => json!(false),
+        Some("string") => match schema["format"].as_str() {
+            Some("email") => json!("user@example.com"),
+            _ => json!(""),
+        },
+        _ => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delivery::openapi_spec;
+
+    fn find_request<'a>(collection: &'a Value, name: &str) -> &'a Value {
+        collection["item"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|folder| folder["item"].as_array().unwrap())
+            .find(|item| item["name"] == name)
+            .map(|item| &item["request"])
+            .unwrap()
+    }
+
+    #[test]
+    fn collection_covers_every_operation() {
+        let spec = openapi_spec();
+        let collection = postman_collection(&spec, DEFAULT_BASE_URL);
+
+        let operations: usize = spec["paths"]
+            .as_object()
+            .unwrap()
+            .values()
+            .map(|methods| methods.as_object().unwrap().len())
+            .sum();
+        let requests: usize = collection["item"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|folder| folder["item"].as_array().unwrap().len())
+            .sum();
+        assert_eq!(requests, operations);
+        assert_eq!(collection["variable"][0]["key"], "baseUrl");
+        assert_eq!(collection["variable"][1]["key"], "authToken");
+    }
+
+    #[test]
+    fn requests_use_variables_and_bearer_auth() {
+        let collection = postman_collection(&openapi_spec(), DEFAULT_BASE_URL);
+
+        let get_product = find_request(&collection, "Get product by ID");
+        assert_eq!(get_product["url"]["raw"], "{{baseUrl}}/api/products/:id");
+        assert_eq!(get_product["url"]["variable"][0]["key"], "id");
+        assert!(get_product.get("auth").is_none());
+
+        let get_user = find_request(&collection, "Get user by ID");
+        assert_eq!(get_user["auth"]["bearer"][0]["value"], "{{authToken}}");
+
+        let drain = find_request(&collection, "Mark instance as draining");
+        assert_eq!(drain["auth"]["bearer"][0]["value"], "{{authToken}}");
+
+        let create = find_request(&collection, "Create user");
+        let body: Value = serde_json::from_str(create["body"]["raw"].as_str().unwrap()).unwrap();
+        assert_eq!(body["email"], "user@example.com");
+    }
+
+    #[tokio::test]
+    async fn the_base_url_is_the_public_url_not_the_host() {
+        use axum::{body::Body, http::Request, routing::get, Router};
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route("/postman", get(postman_json))
+            .with_state(false)
+            .layer(Extension(PublicUrl::new("https://api.example.com")));
+        let request = Request::get("/postman").header(header::HOST, "evil.example").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let collection: Value = serde_json::from_slice(&axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(collection["variable"][0]["value"], "https://api.example.com");
+    }
+}
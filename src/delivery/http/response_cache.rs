@@ -0,0 +1,396 @@
+use async_trait::async_trait;
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+// tokio's clock so tests can drive freshness with paused time
+use tokio::time::Instant;
+
+use super::RouteName;
+use crate::infrastructure::{CacheSelector, InvalidatableCache};
+use crate::middleware::{StaleWhileRevalidate, CURRENCY_HEADER, UNITS_HEADER};
+use crate::response::X_ENVELOPE;
+
+/// `HIT`, `STALE` or `MISS` on responses of cached routes
+pub const X_CACHE: HeaderName = HeaderName::from_static("x-cache");
+/// Larger responses are served but not kept
+const MAX_CACHED_BODY: usize = 1024 * 1024;
+/// Surrogate keys the response was tagged with, for invalidation by tag
+const SURROGATE_KEY: &str = "surrogate-key";
+
+#[derive(Clone)]
+struct Entry {
+    route: RouteName,
+    /// Path and query, matched against invalidation keys and prefixes
+    path: String,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    tags: Vec<String>,
+    stored_at: Instant,
+}
+
+impl Entry {
+    fn response(&self, outcome: &'static str) -> Response {
+        let mut response = Response::new(Body::from(self.body.clone()));
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers.clone();
+        let headers = response.headers_mut();
+        headers.insert(X_CACHE, HeaderValue::from_static(outcome));
+        headers.insert(header::AGE, HeaderValue::from(self.stored_at.elapsed().as_secs()));
+        response
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct RouteStats {
+    hits: u64,
+    stale_serves: u64,
+    misses: u64,
+    refreshes: u64,
+    refresh_failures: u64,
+}
+
+/// How one cached route has been served since startup
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteCacheReport {
+    pub route: String,
+    pub fresh_secs: u32,
+    pub stale_secs: u32,
+    pub entries: usize,
+    pub hits: u64,
+    /// Answered from an expired entry while it was being refreshed
+    pub stale_serves: u64,
+    pub misses: u64,
+    pub refreshes: u64,
+    pub refresh_failures: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponseCacheReport {
+    pub max_entries: usize,
+    pub entries: usize,
+    pub routes: Vec<RouteCacheReport>,
+}
+
+/// Server-side cache of GET responses for routes with a stale-while-revalidate
+/// window. Fresh entries are served as they are; stale ones are served at
+/// once while one request per entry recomputes it in the background, so slow
+/// aggregates only cost the caller on a cold miss.
+pub struct ResponseCache {
+    entries: Mutex<HashMap<String, Entry>>,
+    refreshing: Mutex<HashSet<String>>,
+    stats: Mutex<HashMap<RouteName, RouteStats>>,
+    /// Set from config, taking precedence over the route policies
+    windows: HashMap<RouteName, StaleWhileRevalidate>,
+    max_entries: usize,
+}
+
+impl ResponseCache {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            refreshing: Mutex::new(HashSet::new()),
+            stats: Mutex::new(HashMap::new()),
+            windows: HashMap::new(),
+            max_entries,
+        }
+    }
+
+    /// Override route windows with `operationId=fresh:stale` pairs, e.g.
+    /// `getUserOverview=10:120,listMethodMetrics=0:0`; `0:0` turns caching off
+    pub fn with_windows(mut self, windows: &str) -> Self {
+        for entry in windows.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let parsed = entry.split_once('=').and_then(|(route, window)| {
+                let route = RouteName::from_operation_id(route.trim())?;
+                let (fresh, stale) = window.split_once(':')?;
+                Some((route, StaleWhileRevalidate { fresh_secs: fresh.trim().parse().ok()?, stale_secs: stale.trim().parse().ok()? }))
+            });
+            match parsed {
+                Some((route, window)) => {
+                    self.windows.insert(route, window);
+                }
+                None => tracing::warn!(entry, "Unknown or malformed RESPONSE_CACHE_WINDOWS entry skipped"),
+            }
+        }
+        self
+    }
+
+    /// The window `route` is cached with, if any
+    pub fn window(&self, route: RouteName) -> Option<StaleWhileRevalidate> {
+        self.windows
+            .get(&route)
+            .copied()
+            .or(route.policy().revalidate)
+            .filter(|window| window.fresh_secs > 0 || window.stale_secs > 0)
+    }
+
+    /// State for `response_cache_middleware` on `route`; `None` when it is not cached
+    pub fn route_state(self: &Arc<Self>, route: RouteName) -> Option<Arc<CachedRoute>> {
+        let window = self.window(route)?;
+        Some(Arc::new(CachedRoute { cache: self.clone(), route, window, timeout: route.policy().timeout }))
+    }
+
+    fn count(&self, route: RouteName, update: impl FnOnce(&mut RouteStats)) {
+        update(self.stats.lock().unwrap().entry(route).or_default());
+    }
+
+    fn lookup(&self, key: &str, window: StaleWhileRevalidate) -> Lookup {
+        let entries = self.entries.lock().unwrap();
+        let Some(entry) = entries.get(key) else {
+            return Lookup::Miss;
+        };
+        let age = entry.stored_at.elapsed();
+        if age < Duration::from_secs(window.fresh_secs.into()) {
+            Lookup::Fresh(entry.clone())
+        } else if age < Duration::from_secs(u64::from(window.fresh_secs) + u64::from(window.stale_secs)) {
+            Lookup::Stale(entry.clone())
+        } else {
+            Lookup::Miss
+        }
+    }
+
+    /// Keep a response if it can be shared; hands back what is sent instead
+    async fn store(&self, key: String, route: RouteName, path: String, response: Response) -> Response {
+        let cacheable = response.status() == StatusCode::OK
+            && !response.headers().contains_key(header::SET_COOKIE)
+            && response
+                .headers()
+                .get(header::CACHE_CONTROL)
+                .and_then(|value| value.to_str().ok())
+                .is_none_or(|value| !value.contains("no-store") && !value.contains("private"));
+        if !cacheable {
+            return response;
+        }
+        let (parts, body) = response.into_parts();
+        let body = match axum::body::to_bytes(body, usize::MAX).await {
+            Ok(body) => body,
+            Err(err) => {
+                tracing::warn!(error = %err, "Failed to read response for caching");
+                return Response::from_parts(parts, Body::empty());
+            }
+        };
+        let mut response = Response::from_parts(parts, Body::from(body.clone()));
+        if body.len() > MAX_CACHED_BODY {
+            return response;
+        }
+        let tags = response
+            .headers()
+            .get(SURROGATE_KEY)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default();
+        let entry = Entry {
+            route,
+            path,
+            status: response.status(),
+            headers: response.headers().clone(),
+            body,
+            tags,
+            stored_at: Instant::now(),
+        };
+
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            // Drop what is past its stale window before giving up on the new entry
+            entries.retain(|_, entry| match self.window(entry.route) {
+                Some(window) => {
+                    entry.stored_at.elapsed() < Duration::from_secs(u64::from(window.fresh_secs) + u64::from(window.stale_secs))
+                }
+                None => false,
+            });
+        }
+        if entries.len() < self.max_entries || entries.contains_key(&key) {
+            entries.insert(key, entry);
+        }
+        drop(entries);
+        response.headers_mut().insert(X_CACHE, HeaderValue::from_static("MISS"));
+        response
+    }
+
+    pub fn report(&self) -> ResponseCacheReport {
+        let entries = self.entries.lock().unwrap();
+        let stats = self.stats.lock().unwrap();
+        let mut routes: Vec<RouteCacheReport> = stats
+            .iter()
+            .filter_map(|(&route, stats)| {
+                let window = self.window(route)?;
+                Some(RouteCacheReport {
+                    route: route.operation_id(),
+                    fresh_secs: window.fresh_secs,
+                    stale_secs: window.stale_secs,
+                    entries: entries.values().filter(|entry| entry.route == route).count(),
+                    hits: stats.hits,
+                    stale_serves: stats.stale_serves,
+                    misses: stats.misses,
+                    refreshes: stats.refreshes,
+                    refresh_failures: stats.refresh_failures,
+                })
+            })
+            .collect();
+        routes.sort_by(|a, b| a.route.cmp(&b.route));
+        ResponseCacheReport { max_entries: self.max_entries, entries: entries.len(), routes }
+    }
+}
+
+/// Entries match on their path and query and on their surrogate keys
+#[async_trait]
+impl InvalidatableCache for ResponseCache {
+    fn name(&self) -> &'static str {
+        "response_cache"
+    }
+
+    async fn invalidate(&self, selector: &CacheSelector) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+        entries.retain(|_, entry| {
+            let tags: Vec<&str> = entry.tags.iter().map(String::as_str).collect();
+            !selector.matches(&entry.path, &tags)
+        });
+        before - entries.len()
+    }
+}
+
+enum Lookup {
+    Fresh(Entry),
+    Stale(Entry),
+    Miss,
+}
+
+/// What the response cache middleware of one route needs
+pub struct CachedRoute {
+    cache: Arc<ResponseCache>,
+    route: RouteName,
+    window: StaleWhileRevalidate,
+    /// The route's timeout, also applied to background refreshes
+    timeout: Duration,
+}
+
+/// Answers GET requests from the cache, refreshing stale entries in the
+/// background. Runs inside the route policy, so only callers it lets in are
+/// served from the cache.
+pub async fn response_cache_middleware(State(route): State<Arc<CachedRoute>>, request: Request, next: Next) -> Response {
+    if request.method() != Method::GET {
+        return next.run(request).await;
+    }
+    let path = request.uri().path_and_query().map(|path| path.as_str()).unwrap_or("/").to_string();
+    // Answers are negotiated by these headers, so entries are kept per combination
+    let mut key = path.clone();
+    for name in [header::ACCEPT.as_str(), header::ACCEPT_LANGUAGE.as_str(), CURRENCY_HEADER, UNITS_HEADER, X_ENVELOPE.as_str()] {
+        key.push('\n');
+        key.push_str(request.headers().get(name).and_then(|value| value.to_str().ok()).unwrap_or_default());
+    }
+
+    let cache = &route.cache;
+    match cache.lookup(&key, route.window) {
+        Lookup::Fresh(entry) => {
+            cache.count(route.route, |stats| stats.hits += 1);
+            entry.response("HIT")
+        }
+        Lookup::Stale(entry) => {
+            cache.count(route.route, |stats| stats.stale_serves += 1);
+            if cache.refreshing.lock().unwrap().insert(key.clone()) {
+                tokio::spawn(refresh(route.clone(), key, path, request, next));
+            }
+            entry.response("STALE")
+        }
+        Lookup::Miss => {
+            cache.count(route.route, |stats| stats.misses += 1);
+            let response = next.run(request).await;
+            cache.store(key, route.route, path, response).await
+        }
+    }
+}
+
+async fn refresh(route: Arc<CachedRoute>, key: String, path: String, request: Request, next: Next) {
+    let cache = &route.cache;
+    match tokio::time::timeout(route.timeout, next.run(request)).await {
+        Ok(response) if response.status() == StatusCode::OK => {
+            cache.store(key.clone(), route.route, path, response).await;
+            cache.count(route.route, |stats| stats.refreshes += 1);
+        }
+        outcome => {
+            let status = outcome.map(|response| response.status().as_u16()).ok();
+            tracing::warn!(route = %route.route.operation_id(), status, "Background refresh of a cached response failed");
+            cache.count(route.route, |stats| stats.refresh_failures += 1);
+        }
+    }
+    cache.refreshing.lock().unwrap().remove(&key);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use tower::ServiceExt;
+
+    fn app(cache: &Arc<ResponseCache>, calls: Arc<AtomicU64>) -> Router {
+        let state = cache.route_state(RouteName::ListMethodMetrics).unwrap();
+        Router::new()
+            .route(
+                "/metrics",
+                get(move || async move { format!("computed {}", calls.fetch_add(1, Ordering::SeqCst) + 1) }),
+            )
+            .layer(axum::middleware::from_fn_with_state(state, response_cache_middleware))
+    }
+
+    async fn fetch(app: &Router) -> (String, String) {
+        let response = app.clone().oneshot(Request::get("/metrics").body(Body::empty()).unwrap()).await.unwrap();
+        let outcome = response.headers()[X_CACHE].to_str().unwrap().to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (outcome, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stale_entries_are_served_while_one_request_refreshes_them() {
+        let cache = Arc::new(ResponseCache::new(10).with_windows("listMethodMetrics=5:60"));
+        let calls = Arc::new(AtomicU64::new(0));
+        let app = app(&cache, calls.clone());
+
+        assert_eq!(fetch(&app).await, ("MISS".to_string(), "computed 1".to_string()));
+        assert_eq!(fetch(&app).await, ("HIT".to_string(), "computed 1".to_string()));
+
+        tokio::time::advance(Duration::from_secs(6)).await;
+        assert_eq!(fetch(&app).await, ("STALE".to_string(), "computed 1".to_string()));
+        assert_eq!(fetch(&app).await.0, "STALE");
+        tokio::task::yield_now().await;
+        assert_eq!(fetch(&app).await, ("HIT".to_string(), "computed 2".to_string()));
+        assert_eq!(calls.load(Ordering::SeqCst), 2, "one refresh for both stale serves");
+
+        tokio::time::advance(Duration::from_secs(70)).await;
+        assert_eq!(fetch(&app).await, ("MISS".to_string(), "computed 3".to_string()));
+
+        let report = cache.report();
+        let route = &report.routes[0];
+        assert_eq!(route.route, "listMethodMetrics");
+        assert_eq!((route.hits, route.stale_serves, route.misses, route.refreshes), (2, 2, 2, 1));
+    }
+
+    #[tokio::test]
+    async fn windows_come_from_policies_unless_config_overrides_them() {
+        let cache = ResponseCache::new(10);
+        assert_eq!(cache.window(RouteName::GetUserOverview), Some(StaleWhileRevalidate { fresh_secs: 5, stale_secs: 60 }));
+        assert_eq!(cache.window(RouteName::GetUser), None);
+
+        let overridden = ResponseCache::new(10).with_windows("getUserOverview=0:0, getUser=1:2, nope=1:1, getInfo=x");
+        assert_eq!(overridden.window(RouteName::GetUserOverview), None);
+        assert_eq!(overridden.window(RouteName::GetUser), Some(StaleWhileRevalidate { fresh_secs: 1, stale_secs: 2 }));
+        assert_eq!(overridden.window(RouteName::GetInfo), None);
+
+        // Invalidated entries are recomputed on the next request
+        let cache = Arc::new(cache);
+        let app = app(&cache, Arc::new(AtomicU64::new(0)));
+        fetch(&app).await;
+        let selector = CacheSelector { prefixes: vec!["/metrics".to_string()], ..Default::default() };
+        assert_eq!(cache.invalidate(&selector).await, 1);
+        assert_eq!(fetch(&app).await, ("MISS".to_string(), "computed 2".to_string()));
+    }
+}
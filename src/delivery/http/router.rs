@@ -1,28 +1,304 @@
-use axum::Router;
-use crate::domain::user::handler as user_handlers;
-use crate::domain::health::handler as health_handlers;
+use axum::handler::Handler;
+use axum::routing::{on, MethodFilter};
+use axum::{Extension, Router};
+use std::sync::Arc;
+use crate::domain::user::handler::{self as user_handlers, UsersState};
+use crate::domain::health::handler::{self as health_handlers, HealthState};
+use crate::domain::admin::handler as admin_handlers;
+use crate::domain::webhook::handler as webhook_handlers;
+use crate::domain::product::handler as product_handlers;
+use crate::domain::session::handler as session_handlers;
+use crate::domain::realtime::handler as realtime_handlers;
+use crate::domain::report::handler as report_handlers;
+use crate::domain::oidc::handler as oidc_handlers;
 use crate::container::AppContainer;
+use crate::config::Config;
+use crate::middleware::{route_policy_middleware, Ownership, PolicyState};
+use crate::infrastructure::{LaneLimiter, RateLimiter};
+use super::{assets, deprecation_middleware, response_cache_middleware, ResponseCache, route_shims, version_shim_middleware, with_legacy_versions, openapi, postman, verify_wiring, DeprecationTracker, PublicUrl, RouteName, RouteTable, API_PREFIX};
 
-pub fn create_routes() -> Router {
+pub fn create_routes(config: &Config) -> Router {
     // Create dependency injection container
-    let container = AppContainer::new();
+    create_app(&AppContainer::new(config))
+}
 
-    Router::new()
+/// Routes over an existing container, so callers can run its startup graph first
+pub fn create_app(container: &AppContainer) -> Router {
+    let routes = &Mounter {
+        table: &container.routes,
+        admin_token: container.admin_token.clone(),
+        limiter: container.rate_limiter.clone(),
+        lanes: container.lanes.clone(),
+        deprecations: container.deprecations.clone(),
+        response_cache: container.response_cache.clone(),
+        hide_unimplemented: container.hide_unimplemented_routes,
+        ownership: container.ownership.clone(),
+    };
+
+    // Health checks and instance metadata
+    let health_routes = Router::new()
+        .mount(routes, RouteName::HealthCheck, health_handlers::health_check)
+        .mount(routes, RouteName::GetDependencies, health_handlers::dependencies)
+        .mount(routes, RouteName::ReadinessCheck, health_handlers::readiness_check)
+        .mount(routes, RouteName::LivenessCheck, health_handlers::liveness_check)
+        .mount(routes, RouteName::GetInfo, health_handlers::info)
+        .with_state(HealthState {
+            deployment: container.deployment.clone(),
+            registry: container.health.clone(),
+            dependencies: container.dependencies.clone(),
+            degradations: container.degradations.clone(),
+        });
+
+    // User endpoints
+    let user_routes = Router::new()
+        .mount(routes, RouteName::CreateUser, user_handlers::create_user)
+        .mount(routes, RouteName::ListUsers, user_handlers::list_users)
+        .mount(routes, RouteName::GetUser, user_handlers::get_user)
+        .mount(routes, RouteName::UpdateUser, user_handlers::update_user)
+        .mount(routes, RouteName::PatchUser, user_handlers::patch_user)
+        .mount(routes, RouteName::DeleteUser, user_handlers::delete_user)
+        .mount(routes, RouteName::GetUserPresence, user_handlers::get_user_presence)
+        .with_state(UsersState { users: container.user_service.clone(), presence: container.presence.clone() })
+        .merge(
+            Router::new()
+                .mount(routes, RouteName::GetUserOverview, user_handlers::get_user_overview)
+                .with_state(container.user_overview.clone()),
+        );
+
+    // Example priced resource
+    let product_routes = Router::new()
+        .mount(routes, RouteName::CreateProduct, product_handlers::create_product)
+        .mount(routes, RouteName::GetProduct, product_handlers::get_product)
+        .with_state(container.products.clone());
+
+    // Session helpers for browser clients
+    let session_routes = Router::new()
+        .mount(routes, RouteName::IssueCsrfToken, session_handlers::issue_csrf_token)
+        .with_state(container.csrf.clone())
+        .merge(
+            Router::new()
+                .mount(routes, RouteName::SignIn, session_handlers::sign_in)
+                .with_state(container.sign_in.clone()),
+        );
+
+    // OpenID provider for first-party apps and CLIs; answers 404 without clients
+    let oidc_routes = Router::new()
+        .mount(routes, RouteName::OpenIdConfiguration, oidc_handlers::openid_configuration)
+        .mount(routes, RouteName::GetJwks, oidc_handlers::jwks)
+        .mount(routes, RouteName::OidcAuthorize, oidc_handlers::authorize)
+        .mount(routes, RouteName::OidcToken, oidc_handlers::token)
+        .mount(routes, RouteName::DeviceAuthorization, oidc_handlers::device_authorization)
+        .mount(routes, RouteName::DeviceVerificationPage, assets::device_verification_page)
+        .mount(routes, RouteName::DescribeDevice, oidc_handlers::describe_device)
+        .mount(routes, RouteName::VerifyDevice, oidc_handlers::verify_device)
+        .with_state(container.oidc.clone());
+
+    // Inbound webhooks, authenticated by each provider's signature
+    let webhook_routes = Router::new()
+        .mount(routes, RouteName::ReceiveWebhook, webhook_handlers::receive_webhook)
+        .with_state(container.webhooks.clone());
+
+    // Admin endpoints; their policies require the admin token
+    let admin_routes = Router::new()
+        .mount(routes, RouteName::StartDraining, admin_handlers::start_draining)
+        .mount(routes, RouteName::StopDraining, admin_handlers::stop_draining)
+        .with_state(container.deployment.clone())
+        .merge(
+            Router::new()
+                .mount(routes, RouteName::ListAnomalies, admin_handlers::list_anomalies)
+                .with_state(container.anomalies.clone()),
+        )
+        .merge(
+            Router::new()
+                .mount(routes, RouteName::ListUserSessions, admin_handlers::list_sessions)
+                .mount(routes, RouteName::RevokeUserSessions, admin_handlers::revoke_all_sessions)
+                .mount(routes, RouteName::RevokeUserSession, admin_handlers::revoke_session)
+                .with_state(container.sessions.clone()),
+        )
+        .merge(
+            Router::new()
+                .mount(routes, RouteName::ListRoutes, admin_handlers::list_routes)
+                .with_state(container.routes.clone()),
+        )
+        .merge(
+            Router::new()
+                .mount(routes, RouteName::CpuPoolStats, admin_handlers::cpu_pool_stats)
+                .with_state(container.cpu_pool.clone()),
+        )
+        .merge(
+            Router::new()
+                .mount(routes, RouteName::MemoryReport, admin_handlers::memory_report)
+                .with_state(container.memory.clone()),
+        )
+        .mount(routes, RouteName::ListObjectPools, admin_handlers::list_object_pools)
+        .merge(
+            Router::new()
+                .mount(routes, RouteName::ListShards, admin_handlers::list_shards)
+                .with_state(container.shards.clone()),
+        )
+        .merge(
+            Router::new()
+                .mount(routes, RouteName::ListMethodMetrics, admin_handlers::list_method_metrics)
+                .with_state(container.method_metrics.clone()),
+        )
+        .merge(
+            Router::new()
+                .mount(routes, RouteName::ListConsumers, admin_handlers::list_consumers)
+                .with_state(container.consumers.clone()),
+        )
+        .merge(
+            Router::new()
+                .mount(routes, RouteName::GetResponseCacheStats, admin_handlers::get_response_cache_stats)
+                .with_state(container.response_cache.clone()),
+        )
+        .merge(
+            Router::new()
+                .mount(routes, RouteName::ListAuditEvents, admin_handlers::list_audit_events)
+                .with_state(container.audit_log.clone()),
+        )
+        .merge(
+            Router::new()
+                .mount(routes, RouteName::ListLanes, admin_handlers::list_lanes)
+                .with_state(container.lanes.clone()),
+        )
+        .merge(
+            Router::new()
+                .mount(routes, RouteName::ListRateLimitedClients, admin_handlers::list_rate_limited_clients)
+                .with_state(container.rate_limiter.clone()),
+        )
+        .merge(
+            Router::new()
+                .mount(routes, RouteName::ListDeprecations, admin_handlers::list_deprecations)
+                .with_state(container.deprecations.clone()),
+        )
+        .merge(
+            Router::new()
+                .mount(routes, RouteName::InvalidateCache, admin_handlers::invalidate_cache)
+                .with_state(container.caches.clone()),
+        )
+        .merge(
+            Router::new()
+                .mount(routes, RouteName::GetBootReport, admin_handlers::get_boot_report)
+                .with_state(container.boot_report.clone()),
+        )
+        .merge(
+            Router::new()
+                .mount(routes, RouteName::StartImpersonation, admin_handlers::start_impersonation)
+                .mount(routes, RouteName::StopImpersonation, admin_handlers::stop_impersonation)
+                .with_state(container.impersonation.clone()),
+        );
+
+    // Admin reports, built in the background; the download is authorized by its signed link
+    let report_routes = Router::new()
+        .mount(routes, RouteName::CreateReport, report_handlers::create_report)
+        .mount(routes, RouteName::GetReport, report_handlers::get_report)
+        .mount(routes, RouteName::DownloadReport, report_handlers::download_report)
+        .with_state(container.reports.clone());
+
+    // Live events over a WebSocket
+    let event_routes = Router::new()
+        .mount(routes, RouteName::EventStream, realtime_handlers::event_stream)
+        .mount(routes, RouteName::PollEvents, realtime_handlers::poll_events)
+        .with_state(realtime_handlers::EventsState {
+            hub: container.events.clone(),
+            access: container.event_access.clone(),
+            serializer: container.event_serializer.clone(),
+        });
+
+    // API documentation
+    let docs_routes = Router::new()
+        .mount(routes, RouteName::OpenApiSpec, openapi::openapi_json)
+        .mount(routes, RouteName::PostmanCollection, postman::postman_json)
+        .mount(routes, RouteName::SwaggerUi, assets::swagger_ui)
+        .mount(routes, RouteName::AdminDashboard, assets::admin_dashboard)
+        .mount(routes, RouteName::StaticAsset, assets::asset)
+        .with_state(container.hide_unimplemented_routes);
+
+    // ROUTES, the mounts above and the OpenAPI spec are kept in step by hand
+    if cfg!(debug_assertions) {
+        if let Err(problems) = verify_wiring(&container.routes, &openapi::openapi_spec(), container.hide_unimplemented_routes) {
+            let problems: Vec<String> = problems.iter().map(ToString::to_string).collect();
+            panic!("Route wiring has drifted:\n  {}", problems.join("\n  "));
+        }
+    }
+
+    let current = Router::new()
         // API routes with /api prefix
-        .nest("/api", Router::new()
-            // Health checks
-            .route("/health", axum::routing::get(health_handlers::health_check))
-            .route("/ready", axum::routing::get(health_handlers::readiness_check))
-            .route("/live", axum::routing::get(health_handlers::liveness_check))
-
-            // User endpoints
-            .route("/users", axum::routing::post(user_handlers::create_user))
-            .route("/users", axum::routing::get(user_handlers::list_users))
-            .route("/users/:id", axum::routing::get(user_handlers::get_user))
-            .route("/users/:id", axum::routing::put(user_handlers::update_user))
-            .route("/users/:id", axum::routing::delete(user_handlers::delete_user))
-        )
-
-        // Provide user service as state from the container
-        .with_state(container.user_service)
-}
\ No newline at end of file
+        .nest(API_PREFIX, Router::new()
+            .merge(docs_routes)
+            .merge(health_routes)
+            .merge(user_routes)
+            .merge(product_routes)
+            .merge(session_routes)
+            .merge(oidc_routes)
+            .merge(webhook_routes)
+            .merge(admin_routes)
+            .merge(report_routes)
+            .merge(event_routes)
+        )
+        .layer(Extension(PublicUrl::new(&container.public_base_url)));
+    // Older versions under /api/v{n}, adapted to the routes above by their shims
+    with_legacy_versions(current)
+}
+
+/// What mounting needs besides the router: the table recording each route,
+/// what the route policies (including ownership) are enforced with,
+/// deprecation tracking, and whether stub routes are left out
+struct Mounter<'a> {
+    table: &'a RouteTable,
+    admin_token: Arc<str>,
+    limiter: Arc<RateLimiter>,
+    lanes: Arc<LaneLimiter>,
+    deprecations: Arc<DeprecationTracker>,
+    response_cache: Arc<ResponseCache>,
+    hide_unimplemented: bool,
+    ownership: Arc<Ownership>,
+}
+
+/// Mounts a named route with the method from `ROUTES` and the policy from
+/// `RouteName::policy`, and records it, with its handler, in the route table
+trait MountRoute<S> {
+    fn mount<H, T>(self, mounter: &Mounter, name: RouteName, handler: H) -> Self
+    where
+        H: Handler<T, S>,
+        T: 'static;
+}
+
+impl<S: Clone + Send + Sync + 'static> MountRoute<S> for Router<S> {
+    fn mount<H, T>(self, mounter: &Mounter, name: RouteName, handler: H) -> Self
+    where
+        H: Handler<T, S>,
+        T: 'static,
+    {
+        if mounter.hide_unimplemented && name.route().stub {
+            return self;
+        }
+        let method = MethodFilter::try_from(name.route().method.clone())
+            .expect("ROUTES only uses methods axum can route");
+        let policy = Arc::new(PolicyState {
+            policy: name.policy(),
+            admin_token: mounter.admin_token.clone(),
+            limiter: mounter.limiter.clone(),
+            lanes: mounter.lanes.clone(),
+            ownership: mounter.ownership.clone(),
+        });
+        mounter.table.record(name, std::any::type_name::<H>());
+        let mut route = on(method, handler);
+        // Innermost, so legacy shims and deprecation tracking still see every request
+        if let Some(cached) = mounter.response_cache.route_state(name) {
+            route = route.layer(axum::middleware::from_fn_with_state(cached, response_cache_middleware));
+        }
+        // Inside the policy, so only requests that are served count as usage
+        if let Some(deprecated) = mounter.deprecations.route_state(name) {
+            route = route.layer(axum::middleware::from_fn_with_state(deprecated, deprecation_middleware));
+        }
+        // Only acts on requests forwarded from a legacy version
+        if let Some(shims) = route_shims(name) {
+            route = route.layer(axum::middleware::from_fn_with_state(shims, version_shim_middleware));
+        }
+        self.route(
+            name.router_path(),
+            route.layer(axum::middleware::from_fn_with_state(policy, route_policy_middleware)),
+        )
+    }
+}
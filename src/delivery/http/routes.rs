@@ -0,0 +1,602 @@
+use axum::http::Method;
+use crate::middleware::Auth;
+use serde::Serialize;
+use std::fmt::Display;
+use std::sync::Mutex;
+
+/// Every route is served under this prefix
+pub const API_PREFIX: &str = "/api";
+
+/// Named routes; variants match the OpenAPI operation ids
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteName {
+    HealthCheck,
+    GetDependencies,
+    ReadinessCheck,
+    LivenessCheck,
+    GetInfo,
+    ListUsers,
+    CreateUser,
+    GetUser,
+    UpdateUser,
+    PatchUser,
+    DeleteUser,
+    GetUserOverview,
+    GetUserPresence,
+    CreateProduct,
+    GetProduct,
+    SignIn,
+    IssueCsrfToken,
+    OpenIdConfiguration,
+    GetJwks,
+    OidcAuthorize,
+    OidcToken,
+    DeviceAuthorization,
+    DeviceVerificationPage,
+    DescribeDevice,
+    VerifyDevice,
+    ReceiveWebhook,
+    StartDraining,
+    StopDraining,
+    ListAnomalies,
+    ListUserSessions,
+    RevokeUserSessions,
+    RevokeUserSession,
+    StartImpersonation,
+    StopImpersonation,
+    ListRoutes,
+    CpuPoolStats,
+    MemoryReport,
+    ListObjectPools,
+    ListShards,
+    ListMethodMetrics,
+    ListConsumers,
+    GetResponseCacheStats,
+    ListAuditEvents,
+    ListLanes,
+    ListRateLimitedClients,
+    ListDeprecations,
+    GetBootReport,
+    InvalidateCache,
+    CreateReport,
+    GetReport,
+    DownloadReport,
+    EventStream,
+    PollEvents,
+    OpenApiSpec,
+    PostmanCollection,
+    SwaggerUi,
+    AdminDashboard,
+    StaticAsset,
+}
+
+/// One method on one path, defined once for the router, URL generation and
+/// the startup listing
+#[derive(Debug)]
+pub struct Route {
+    pub name: RouteName,
+    pub method: Method,
+    /// Full path with `:param` segments
+    pub template: &'static str,
+    pub summary: &'static str,
+    /// Part of the OpenAPI spec and generated clients
+    pub documented: bool,
+    /// Answers 501 until implemented; left out entirely with `HIDE_UNIMPLEMENTED_ROUTES`
+    pub stub: bool,
+}
+
+impl Route {
+    /// Requires the admin token, per the route's policy
+    pub fn admin(&self) -> bool {
+        self.name.policy().auth == Auth::Admin
+    }
+
+    /// Requires a session or the admin token; documented with a `security` requirement
+    pub fn authenticated(&self) -> bool {
+        self.name.policy().auth != Auth::Public
+    }
+}
+
+const fn route(name: RouteName, method: Method, template: &'static str, summary: &'static str) -> Route {
+    Route { name, method, template, summary, documented: true, stub: false }
+}
+
+const fn undocumented(mut route: Route) -> Route {
+    route.documented = false;
+    route
+}
+
+const fn stub(mut route: Route) -> Route {
+    route.stub = true;
+    route
+}
+
+pub static ROUTES: &[Route] = &[
+    route(RouteName::HealthCheck, Method::GET, "/api/health", "Health check"),
+    route(RouteName::GetDependencies, Method::GET, "/api/health/dependencies", "Last background probe result for each dependency"),
+    route(RouteName::ReadinessCheck, Method::GET, "/api/ready", "Readiness check"),
+    route(RouteName::LivenessCheck, Method::GET, "/api/live", "Liveness check"),
+    route(RouteName::GetInfo, Method::GET, "/api/info", "Service version and deployment metadata"),
+    route(RouteName::ListUsers, Method::GET, "/api/users", "List users (with pagination)"),
+    route(RouteName::CreateUser, Method::POST, "/api/users", "Create user"),
+    route(RouteName::GetUser, Method::GET, "/api/users/:id", "Get user by ID"),
+    stub(route(RouteName::UpdateUser, Method::PUT, "/api/users/:id", "Replace user (not implemented)")),
+    route(RouteName::PatchUser, Method::PATCH, "/api/users/:id", "Update the fields present; metadata is merge-patched"),
+    stub(route(RouteName::DeleteUser, Method::DELETE, "/api/users/:id", "Delete user (not implemented)")),
+    route(RouteName::GetUserOverview, Method::GET, "/api/users/:id/overview", "The user's profile with their active sessions"),
+    route(RouteName::GetUserPresence, Method::GET, "/api/users/:id/presence", "Whether the user is online, and when they were last seen"),
+    route(RouteName::CreateProduct, Method::POST, "/api/products", "Create product"),
+    route(RouteName::GetProduct, Method::GET, "/api/products/:id", "Get product by ID"),
+    route(RouteName::SignIn, Method::POST, "/api/auth/login", "Sign in with an email and password"),
+    route(RouteName::IssueCsrfToken, Method::GET, "/api/auth/csrf", "CSRF token for writes authenticated by the session cookie"),
+    // Standard OpenID Connect endpoints; clients read the discovery document rather than the API spec
+    undocumented(route(RouteName::OpenIdConfiguration, Method::GET, "/api/oidc/.well-known/openid-configuration", "OpenID provider metadata")),
+    undocumented(route(RouteName::GetJwks, Method::GET, "/api/oidc/jwks", "Public keys ID tokens are signed with")),
+    undocumented(route(RouteName::OidcAuthorize, Method::GET, "/api/oidc/authorize", "Authorization code for the caller's session, by redirect")),
+    undocumented(route(RouteName::OidcToken, Method::POST, "/api/oidc/token", "Exchange an authorization code or a device code for tokens")),
+    undocumented(route(RouteName::DeviceAuthorization, Method::POST, "/api/oidc/device_authorization", "Start a device authorization for a command-line client")),
+    undocumented(route(RouteName::DeviceVerificationPage, Method::GET, "/api/oidc/device", "Page where signed-in users enter a device's code")),
+    route(RouteName::DescribeDevice, Method::GET, "/api/oidc/device/:user_code", "Client and scopes of the device showing a user code"),
+    route(RouteName::VerifyDevice, Method::POST, "/api/oidc/device", "Approve or deny the device showing a user code"),
+    undocumented(route(RouteName::ReceiveWebhook, Method::POST, "/api/hooks/:provider", "Signed webhook deliveries")),
+    route(RouteName::StartDraining, Method::POST, "/api/admin/drain", "Mark instance as draining"),
+    route(RouteName::StopDraining, Method::DELETE, "/api/admin/drain", "Stop draining"),
+    route(RouteName::ListAnomalies, Method::GET, "/api/admin/anomalies", "Currently active request anomalies"),
+    route(RouteName::ListUserSessions, Method::GET, "/api/admin/users/:id/sessions", "Active sessions and refresh tokens of a user"),
+    route(RouteName::RevokeUserSessions, Method::DELETE, "/api/admin/users/:id/sessions", "Revoke every session of a user"),
+    route(RouteName::RevokeUserSession, Method::DELETE, "/api/admin/users/:id/sessions/:session_id", "Revoke one session of a user"),
+    route(RouteName::StartImpersonation, Method::POST, "/api/admin/impersonate/:id", "Issue a short-lived token acting as the user"),
+    route(RouteName::StopImpersonation, Method::DELETE, "/api/admin/impersonate/:id", "End every impersonation session for the user"),
+    route(RouteName::ListRoutes, Method::GET, "/api/admin/routes", "Routes served by this instance, with their handlers"),
+    route(RouteName::CpuPoolStats, Method::GET, "/api/admin/cpu-pool", "Queue depths and counters of the CPU work pool"),
+    route(RouteName::MemoryReport, Method::GET, "/api/admin/memory", "Process memory, limits and pressure"),
+    route(RouteName::ListObjectPools, Method::GET, "/api/admin/object-pools", "Reuse counters of the buffer pools"),
+    route(RouteName::ListShards, Method::GET, "/api/admin/shards", "Nodes, key shares and rebalances of each shard ring"),
+    route(RouteName::ListMethodMetrics, Method::GET, "/api/admin/method-metrics", "Calls, latency and error rate of each service method"),
+    route(RouteName::ListConsumers, Method::GET, "/api/admin/consumers", "Lag, processing latency, retries and dead letters of each message consumer"),
+    route(RouteName::GetResponseCacheStats, Method::GET, "/api/admin/response-cache", "Hits, stale serves, misses and background refreshes of each cached route"),
+    route(RouteName::ListAuditEvents, Method::GET, "/api/admin/audit-events", "Audit events, newest first, from the journal and its archive"),
+    route(RouteName::ListLanes, Method::GET, "/api/admin/lanes", "Request slots in use and shed per priority lane"),
+    route(RouteName::ListRateLimitedClients, Method::GET, "/api/admin/rate-limits", "Clients that went over a rate limit most often"),
+    route(RouteName::ListDeprecations, Method::GET, "/api/admin/deprecations", "Deprecated routes and fields, with their sunset dates and recent use"),
+    route(RouteName::InvalidateCache, Method::POST, "/api/admin/cache/invalidate", "Drop cache entries by key, prefix or tag and purge the tags at the CDN"),
+    route(RouteName::GetBootReport, Method::GET, "/api/admin/boot-report", "Config, features, listeners, migrations and startup times this instance booted with"),
+    route(RouteName::CreateReport, Method::POST, "/api/admin/reports", "Queue a CSV or XLSX report; the requester is sent a download link once it is built"),
+    route(RouteName::GetReport, Method::GET, "/api/admin/reports/:id", "Status of a report, with a fresh download link once it is ready"),
+    undocumented(route(RouteName::DownloadReport, Method::GET, "/api/reports/:id/download", "Report files, through their signed links")),
+    undocumented(route(RouteName::EventStream, Method::GET, "/api/events", "Live events over a WebSocket, resumable with the token from the close frame")),
+    route(RouteName::PollEvents, Method::GET, "/api/events/poll", "Events past a cursor, waiting up to 25s for the next one"),
+    undocumented(route(RouteName::OpenApiSpec, Method::GET, "/api/docs/openapi.json", "OpenAPI spec")),
+    undocumented(route(RouteName::PostmanCollection, Method::GET, "/api/docs/postman", "Postman collection")),
+    undocumented(route(RouteName::SwaggerUi, Method::GET, "/api/docs", "Swagger UI")),
+    undocumented(route(RouteName::AdminDashboard, Method::GET, "/api/dashboard", "Admin dashboard")),
+    undocumented(route(RouteName::StaticAsset, Method::GET, "/api/assets/*path", "Files embedded from assets/")),
+];
+
+/// A route as mounted on the running router
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MountedRoute {
+    pub name: String,
+    pub method: String,
+    pub path: &'static str,
+    /// Handler function path within the crate, e.g. `domain::user::handler::get_user`
+    pub handler: String,
+    pub admin: bool,
+}
+
+/// What the router actually serves, recorded by `create_app` as it mounts
+/// each route
+#[derive(Default)]
+pub struct RouteTable {
+    routes: Mutex<Vec<MountedRoute>>,
+}
+
+impl RouteTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mounting the same route again replaces its entry
+    pub fn record(&self, name: RouteName, handler: &str) {
+        let route = name.route();
+        let handler = handler
+            .strip_prefix(concat!(env!("CARGO_CRATE_NAME"), "::"))
+            .unwrap_or(handler)
+            .to_string();
+        let mounted = MountedRoute {
+            name: name.operation_id(),
+            method: route.method.to_string(),
+            path: route.template,
+            handler,
+            admin: route.admin(),
+        };
+
+        let mut routes = self.routes.lock().unwrap();
+        routes.retain(|existing| existing.name != mounted.name);
+        routes.push(mounted);
+    }
+
+    /// Sorted by path, then method
+    pub fn routes(&self) -> Vec<MountedRoute> {
+        let mut routes = self.routes.lock().unwrap().clone();
+        routes.sort_by(|a, b| a.path.cmp(b.path).then_with(|| a.method.cmp(&b.method)));
+        routes
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum RouteError {
+    #[error("Missing value for path parameter `{0}`")]
+    MissingParam(&'static str),
+    #[error("Route has no path parameter `{0}`")]
+    UnknownParam(String),
+}
+
+impl RouteName {
+    pub fn route(self) -> &'static Route {
+        ROUTES
+            .iter()
+            .find(|route| route.name == self)
+            .expect("every RouteName has an entry in ROUTES")
+    }
+
+    /// Full path template, e.g. `/api/users/:id`
+    pub fn template(self) -> &'static str {
+        self.route().template
+    }
+
+    /// Template relative to `API_PREFIX`, for the nested router
+    pub fn router_path(self) -> &'static str {
+        let template = self.template();
+        template.strip_prefix(API_PREFIX).unwrap_or(template)
+    }
+
+    /// Template in OpenAPI form, e.g. `/api/users/{id}`
+    pub fn openapi_path(self) -> String {
+        self.template()
+            .split('/')
+            .map(|segment| match segment.strip_prefix(':') {
+                Some(param) => format!("{{{param}}}"),
+                None => segment.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    pub fn operation_id(self) -> String {
+        let name = format!("{self:?}");
+        let mut chars = name.chars();
+        chars
+            .next()
+            .map(|first| first.to_ascii_lowercase().to_string() + chars.as_str())
+            .unwrap_or_default()
+    }
+
+    pub fn from_operation_id(operation_id: &str) -> Option<Self> {
+        ROUTES.iter().map(|route| route.name).find(|name| name.operation_id() == operation_id)
+    }
+}
+
+/// Build the path of a named route, filling its `:param` segments by name.
+/// Values are percent-encoded, so any `Display` value is safe in a segment.
+pub fn url_for(name: RouteName, params: &[(&str, &dyn Display)]) -> Result<String, RouteError> {
+    let template = name.template();
+    let mut used = 0;
+    let mut path = String::with_capacity(template.len());
+    for (index, segment) in template.split('/').enumerate() {
+        if index > 0 {
+            path.push('/');
+        }
+        match segment.strip_prefix(':') {
+            Some(param) => {
+                let (_, value) = params
+                    .iter()
+                    .find(|(key, _)| *key == param)
+                    .ok_or(RouteError::MissingParam(param))?;
+                encode_segment(&value.to_string(), &mut path);
+                used += 1;
+            }
+            None => path.push_str(segment),
+        }
+    }
+
+    if used < params.len() {
+        let unknown = params
+            .iter()
+            .find(|(key, _)| !template.split('/').any(|segment| segment.strip_prefix(':') == Some(*key)))
+            .map(|(key, _)| key.to_string())
+            .unwrap_or_default();
+        return Err(RouteError::UnknownParam(unknown));
+    }
+    Ok(path)
+}
+
+/// Percent-encode everything outside RFC 3986 unreserved characters
+fn encode_segment(value: &str, out: &mut String) {
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::container::AppContainer;
+    use crate::delivery::{create_app, openapi_spec};
+    use std::collections::HashSet;
+    use uuid::Uuid;
+
+    #[test]
+    fn url_for_fills_and_encodes_params() {
+        let id = Uuid::nil();
+        assert_eq!(
+            url_for(RouteName::GetUser, &[("id", &id)]).unwrap(),
+            "/api/users/00000000-0000-0000-0000-000000000000"
+        );
+        assert_eq!(url_for(RouteName::ReceiveWebhook, &[("provider", &"a b/c")]).unwrap(), "/api/hooks/a%20b%2Fc");
+        assert_eq!(url_for(RouteName::ListUsers, &[]).unwrap(), "/api/users");
+
+        assert_eq!(url_for(RouteName::GetUser, &[]), Err(RouteError::MissingParam("id")));
+        assert_eq!(
+            url_for(RouteName::GetUser, &[("id", &id), ("page", &1)]),
+            Err(RouteError::UnknownParam("page".to_string()))
+        );
+    }
+
+    #[test]
+    fn names_and_method_paths_are_unique() {
+        let names: HashSet<RouteName> = ROUTES.iter().map(|route| route.name).collect();
+        assert_eq!(names.len(), ROUTES.len());
+        let endpoints: HashSet<(&Method, &str)> = ROUTES.iter().map(|route| (&route.method, route.template)).collect();
+        assert_eq!(endpoints.len(), ROUTES.len());
+    }
+
+    #[tokio::test]
+    async fn router_mounts_every_route_with_its_handler() {
+        let container = AppContainer::new(&Config::from_env());
+        let _ = create_app(&container);
+        let _ = create_app(&container);
+
+        let mounted = container.routes.routes();
+        assert_eq!(mounted.len(), ROUTES.len());
+        for route in ROUTES {
+            let entry = mounted.iter().find(|entry| entry.name == route.name.operation_id()).unwrap();
+            assert_eq!((entry.method.as_str(), entry.path), (route.method.as_str(), route.template));
+        }
+        let get_user = mounted.iter().find(|entry| entry.name == "getUser").unwrap();
+        assert_eq!(get_user.handler, "domain::user::handler::get_user");
+    }
+
+    #[test]
+    fn documented_routes_match_the_openapi_spec() {
+        let spec = openapi_spec();
+        let mut spec_operations = HashSet::new();
+        for (path, methods) in spec["paths"].as_object().unwrap() {
+            for (method, operation) in methods.as_object().unwrap() {
+                spec_operations.insert((
+                    path.clone(),
+                    method.to_uppercase(),
+                    operation["operationId"].as_str().unwrap().to_string(),
+                    crate::codegen::requires_auth(operation),
+                ));
+            }
+        }
+
+        let routes: HashSet<(String, String, String, bool)> = ROUTES
+            .iter()
+            .filter(|route| route.documented)
+            .map(|route| (route.name.openapi_path(), route.method.to_string(), route.name.operation_id(), route.authenticated()))
+            .collect();
+        assert_eq!(routes, spec_operations);
+
+        for route in ROUTES.iter().filter(|route| route.documented) {
+            let operation = &spec["paths"][route.name.openapi_path()][route.method.as_str().to_ascii_lowercase()];
+            let scopes: Vec<&str> = operation["security"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|requirement| requirement["oidc"].as_array())
+                .flatten()
+                .filter_map(serde_json::Value::as_str)
+                .collect();
+            let declared = route.name.policy().scope.filter(|scope| scope.user_grantable()).map(|scope| scope.name());
+            assert_eq!(scopes, declared.into_iter().collect::<Vec<_>>(), "{:?}", route.name);
+        }
+        let listed = spec["components"]["securitySchemes"]["oidc"]["flows"]["authorizationCode"]["scopes"].as_object().unwrap();
+        assert!(listed.contains_key("users:write") && !listed.contains_key("admin:read"));
+    }
+
+    #[tokio::test]
+    async fn each_route_refuses_tokens_without_its_declared_scope() {
+        use crate::domain::session::entities::{Scope, Session, SessionKind};
+        use crate::domain::user::model::CreateUserRequest;
+        use crate::middleware::auth_middleware;
+        use axum::{body::Body, extract::Request, http::{header, StatusCode}};
+        use tower::ServiceExt;
+
+        let mut config = Config::from_env();
+        config.admin_api_token = "test-admin".to_string();
+        let container = AppContainer::new(&config);
+        let app = create_app(&container)
+            .layer(axum::middleware::from_fn_with_state(container.sessions.clone(), auth_middleware));
+        let user = container
+            .user_service
+            .create_user(CreateUserRequest { email: "scoped@example.com".to_string(), password: "scoped-password".to_string(), metadata: None })
+            .await
+            .unwrap();
+
+        for route in ROUTES {
+            let Some(scope) = route.name.policy().scope else { continue };
+            assert_eq!(route.admin(), matches!(scope, Scope::AdminRead | Scope::AdminWrite), "{:?}", route.name);
+
+            // Every scope a user may hold but this one, so the route's own scope is what is missing
+            let mut session = Session::new(user.id(), SessionKind::Session, chrono::Duration::minutes(5));
+            session.scopes =
+                Some(Scope::ALL.into_iter().filter(|other| other.user_grantable() && *other != scope).map(|other| other.name().to_string()).collect());
+            let token = session.issue_token("ses_");
+            container.sessions.save(session).await.unwrap();
+
+            let path: Vec<String> = route
+                .template
+                .split('/')
+                .map(|segment| match segment.strip_prefix(':') {
+                    Some(_) => user.id().to_string(),
+                    None => segment.to_string(),
+                })
+                .collect();
+            let request = Request::builder()
+                .method(route.method.clone())
+                .uri(path.join("/"))
+                .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            if route.admin() {
+                // Sessions never get past the admin check, whatever their scopes
+                assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{:?}", route.name);
+                continue;
+            }
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{:?}", route.name);
+            let challenge = response.headers()[header::WWW_AUTHENTICATE].to_str().unwrap();
+            assert!(challenge.ends_with(&format!("scope=\"{}\"", scope.name())), "{challenge}");
+        }
+    }
+
+    #[tokio::test]
+    async fn page_links_start_with_the_public_url_whatever_the_host() {
+        use axum::{body::Body, extract::Request, http::header};
+        use tower::ServiceExt;
+
+        let mut config = Config::from_env();
+        config.public_base_url = "https://api.example.com/".to_string();
+        let container = AppContainer::new(&config);
+        for n in 0..3 {
+            let email = format!("linked{n}@example.com");
+            let request = crate::domain::user::model::CreateUserRequest { email, password: "linked-password".to_string(), metadata: None };
+            container.user_service.create_user(request).await.unwrap();
+        }
+        let request = Request::get("/api/users?page=1&limit=2")
+            .header(header::HOST, "evil.example")
+            .header("x-forwarded-proto", "https")
+            .body(Body::empty())
+            .unwrap();
+        let response = create_app(&container).oneshot(request).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        let next = body["meta"]["links"]["next"].as_str().unwrap();
+        assert!(next.starts_with("https://api.example.com/api/users?"), "{next}");
+    }
+
+    #[tokio::test]
+    async fn user_reads_carry_their_surrogate_keys() {
+        use axum::{body::Body, extract::Request};
+        use tower::ServiceExt;
+
+        let mut config = Config::from_env();
+        config.admin_api_token = "test-admin".to_string();
+        let container = AppContainer::new(&config);
+        let request = crate::domain::user::model::CreateUserRequest { email: "tagged@example.com".to_string(), password: "tagged-password".to_string(), metadata: None };
+        let id = container.user_service.create_user(request).await.unwrap().id();
+        let app = create_app(&container);
+        let get = |uri: String| Request::get(uri).header("authorization", "Bearer test-admin").body(Body::empty()).unwrap();
+
+        let user = app.clone().oneshot(get(url_for(RouteName::GetUser, &[("id", &id)]).unwrap())).await.unwrap();
+        assert_eq!(user.headers()["surrogate-key"], format!("user:{id}").as_str());
+        assert_eq!(user.headers()["cache-tag"], format!("user:{id}").as_str());
+
+        let list = app.oneshot(get("/api/users".to_string())).await.unwrap();
+        assert_eq!(list.headers()["surrogate-key"], format!("users:list user:{id}").as_str());
+        assert_eq!(list.headers()["cache-tag"], format!("users:list,user:{id}").as_str());
+    }
+
+    #[tokio::test]
+    async fn parked_polls_do_not_hold_lane_slots() {
+        use axum::{body::Body, extract::Request, http::StatusCode};
+        use tower::ServiceExt;
+
+        let mut config = Config::from_env();
+        config.admin_api_token = "test-admin".to_string();
+        // Interactive requests are shed from 3 in flight, bulk ones from 1
+        (config.lane_capacity, config.lane_interactive_share, config.lane_bulk_share) = (4, 75, 25);
+        let container = AppContainer::new(&config);
+        let app = create_app(&container);
+        let get = |uri: &str| Request::get(uri).header("authorization", "Bearer test-admin").body(Body::empty()).unwrap();
+
+        let polls: Vec<_> = (0..8).map(|_| tokio::spawn(app.clone().oneshot(get("/api/events/poll?topics=presence&wait=5")))).collect();
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        assert!(polls.iter().all(|poll| !poll.is_finished()), "every poll is admitted and parked");
+        assert_eq!(container.lanes.report().in_flight, 0);
+
+        let users = app.clone().oneshot(get("/api/users")).await.unwrap();
+        assert_eq!(users.status(), StatusCode::OK);
+        polls.iter().for_each(|poll| poll.abort());
+    }
+
+    #[tokio::test]
+    async fn create_user_answers_created_with_a_location_that_resolves() {
+        let mut config = Config::from_env();
+        config.admin_api_token = "test-admin".to_string();
+        let app = crate::delivery::create_routes(&config);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = reqwest::Client::new();
+        let created = client
+            .post(format!("{base}{}", RouteName::CreateUser.template()))
+            .json(&serde_json::json!({ "email": "located@example.com", "password": "located-password" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(created.status(), reqwest::StatusCode::CREATED);
+        let location = created.headers()[reqwest::header::LOCATION].to_str().unwrap().to_string();
+        let body: serde_json::Value = created.json().await.unwrap();
+        assert_eq!(location, url_for(RouteName::GetUser, &[("id", &body["data"]["id"].as_str().unwrap())]).unwrap());
+
+        let fetched = client.get(format!("{base}{location}")).bearer_auth("test-admin").send().await.unwrap();
+        let fetched: serde_json::Value = fetched.json().await.unwrap();
+        assert_eq!(fetched["data"]["email"], "located@example.com");
+
+        let duplicate = client
+            .post(format!("{base}{}", RouteName::CreateUser.template()))
+            .json(&serde_json::json!({ "email": "located@example.com", "password": "another-password" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(duplicate.status(), reqwest::StatusCode::CONFLICT);
+        let body: serde_json::Value = duplicate.json().await.unwrap();
+        assert_eq!(body["error"]["code"], "USER_ALREADY_EXISTS");
+        assert_eq!(body["error"]["details"]["fields"]["email"], serde_json::json!(["Email is already registered"]));
+    }
+
+    #[tokio::test]
+    async fn stub_routes_answer_not_implemented_unless_hidden() {
+        let mut config = Config::from_env();
+        config.hide_unimplemented_routes = true;
+        let container = AppContainer::new(&config);
+        let _ = create_app(&container);
+        let mounted = container.routes.routes();
+        assert_eq!(mounted.len(), ROUTES.iter().filter(|route| !route.stub).count());
+        assert!(!mounted.iter().any(|entry| entry.name == "deleteUser"));
+        let spec = crate::delivery::served_spec(true);
+        let user = spec["paths"]["/api/users/{id}"].as_object().unwrap();
+        assert_eq!(user.keys().map(String::as_str).collect::<Vec<_>>(), ["get", "patch"]);
+
+        config.hide_unimplemented_routes = false;
+        config.admin_api_token = "test-admin".to_string();
+        let app = crate::delivery::create_routes(&config);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let path = url_for(RouteName::DeleteUser, &[("id", &Uuid::new_v4())]).unwrap();
+        let response = reqwest::Client::new().delete(format!("{base}{path}")).bearer_auth("test-admin").send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_IMPLEMENTED);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["error"]["code"], "NOT_IMPLEMENTED");
+        assert_eq!(body["error"]["message"], "Deleting users is not implemented yet");
+    }
+}
@@ -0,0 +1,219 @@
+use axum::{extract::ConnectInfo, http::Request, Router};
+use hyper::body::Incoming;
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo, TokioTimer},
+    server::conn::auto::Builder,
+    service::TowerToHyperService,
+};
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tower::ServiceExt;
+
+use crate::middleware::TrustedProxies;
+
+/// How long to stop accepting after an error that is not the client's, such
+/// as running out of file descriptors (EMFILE/ENFILE)
+const ACCEPT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Limits enforced on raw connections before a request reaches any middleware
+#[derive(Debug, Clone)]
+pub struct ConnectionLimits {
+    pub header_read_timeout: Duration,
+    pub max_headers: usize,
+    pub max_header_bytes: usize,
+    /// 0 disables the per-IP limit
+    pub max_connections_per_ip: usize,
+    /// Peers exempt from the per-IP limit: every client behind a load
+    /// balancer shares its address
+    pub trusted_proxies: Arc<TrustedProxies>,
+}
+
+/// Tracks open connections per client IP
+#[derive(Default)]
+struct ConnectionTracker {
+    open: Mutex<HashMap<IpAddr, usize>>,
+}
+
+impl ConnectionTracker {
+    fn try_acquire(self: &Arc<Self>, ip: IpAddr, max: usize) -> Option<ConnectionGuard> {
+        let mut open = self.open.lock().unwrap();
+        let count = open.entry(ip).or_insert(0);
+        if max > 0 && *count >= max {
+            return None;
+        }
+        *count += 1;
+        Some(ConnectionGuard { tracker: Arc::clone(self), ip })
+    }
+}
+
+struct ConnectionGuard {
+    tracker: Arc<ConnectionTracker>,
+    ip: IpAddr,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        let mut open = self.tracker.open.lock().unwrap();
+        if let Some(count) = open.get_mut(&self.ip) {
+            *count -= 1;
+            if *count == 0 {
+                open.remove(&self.ip);
+            }
+        }
+    }
+}
+
+/// Accept loop replacing `axum::serve` so connection-level limits can be applied.
+/// Slow or oversized header sections are cut off by hyper itself, and clients
+/// over their concurrent connection budget are dropped at accept time.
+pub async fn serve(listener: TcpListener, app: Router, limits: ConnectionLimits) -> io::Result<()> {
+    let tracker = Arc::new(ConnectionTracker::default());
+
+    let mut builder = Builder::new(TokioExecutor::new());
+    builder
+        .http1()
+        .timer(TokioTimer::new())
+        .header_read_timeout(limits.header_read_timeout)
+        .max_headers(limits.max_headers)
+        .max_buf_size(limits.max_header_bytes.max(8192));
+    builder
+        .http2()
+        .timer(TokioTimer::new())
+        .max_header_list_size(limits.max_header_bytes as u32);
+    let builder = Arc::new(builder);
+
+    loop {
+        let (stream, remote_addr) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            // A client that gave up before being accepted; the next one may be fine
+            Err(err) if is_connection_error(&err) => continue,
+            // Out of descriptors or similar: retrying at once would spin the loop
+            Err(err) => {
+                tracing::warn!(error = %err, "Failed to accept connection, backing off");
+                tokio::time::sleep(ACCEPT_BACKOFF).await;
+                continue;
+            }
+        };
+
+        let max = if limits.trusted_proxies.contains(remote_addr.ip()) { 0 } else { limits.max_connections_per_ip };
+        let Some(guard) = tracker.try_acquire(remote_addr.ip(), max) else {
+            tracing::warn!(
+                ip_address = %remote_addr.ip(),
+                max_connections_per_ip = limits.max_connections_per_ip,
+                "Rejected connection over per-IP limit"
+            );
+            continue;
+        };
+
+        let service = app
+            .clone()
+            .map_request(move |mut request: Request<Incoming>| {
+                request.extensions_mut().insert(ConnectInfo::<SocketAddr>(remote_addr));
+                request
+            });
+        let builder = Arc::clone(&builder);
+
+        tokio::spawn(async move {
+            let _guard = guard;
+            let connection = builder.serve_connection_with_upgrades(
+                TokioIo::new(stream),
+                TowerToHyperService::new(service),
+            );
+            if let Err(err) = connection.await {
+                tracing::debug!(error = %err, ip_address = %remote_addr.ip(), "Connection closed with error");
+            }
+        });
+    }
+}
+
+fn is_connection_error(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::ConnectionRefused | io::ErrorKind::ConnectionAborted | io::ErrorKind::ConnectionReset
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    fn limits() -> ConnectionLimits {
+        ConnectionLimits {
+            header_read_timeout: Duration::from_millis(200),
+            max_headers: 10,
+            max_header_bytes: 8192,
+            max_connections_per_ip: 1,
+            trusted_proxies: Arc::default(),
+        }
+    }
+
+    async fn start(limits: ConnectionLimits) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, Router::new().route("/", get(|| async { "ok" })), limits));
+        addr
+    }
+
+    /// Everything the server writes until it closes the connection
+    async fn exchange(addr: SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = Vec::new();
+        tokio::time::timeout(Duration::from_secs(5), stream.read_to_end(&mut response))
+            .await
+            .expect("the server should close the connection")
+            .unwrap_or_default();
+        String::from_utf8_lossy(&response).into_owned()
+    }
+
+    #[tokio::test]
+    async fn connections_over_the_per_ip_limit_are_dropped() {
+        let addr = start(limits()).await;
+        let held = TcpStream::connect(addr).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(exchange(addr, "GET / HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n").await, "");
+
+        drop(held);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(exchange(addr, "GET / HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n").await.starts_with("HTTP/1.1 200"));
+    }
+
+    #[tokio::test]
+    async fn trusted_proxies_are_not_held_to_the_per_ip_limit() {
+        let addr = start(ConnectionLimits { trusted_proxies: Arc::new(TrustedProxies::parse("127.0.0.1")), ..limits() }).await;
+        let _held = TcpStream::connect(addr).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert!(exchange(addr, "GET / HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n").await.starts_with("HTTP/1.1 200"));
+    }
+
+    #[tokio::test]
+    async fn slow_and_oversized_header_sections_are_cut_off() {
+        let addr = start(ConnectionLimits { max_connections_per_ip: 0, ..limits() }).await;
+
+        // A client that never finishes its headers is disconnected
+        let started = std::time::Instant::now();
+        let slow = exchange(addr, "GET / HTTP/1.1\r\nHost: a\r\n").await;
+        assert!(!slow.starts_with("HTTP/1.1 200") && started.elapsed() < Duration::from_secs(5));
+
+        let headers: String = (0..20).map(|index| format!("X-Filler-{index}: 1\r\n")).collect();
+        let crowded = exchange(addr, &format!("GET / HTTP/1.1\r\nHost: a\r\n{headers}Connection: close\r\n\r\n")).await;
+        assert!(crowded.starts_with("HTTP/1.1 431"), "{crowded}");
+    }
+
+    #[test]
+    fn only_client_side_accept_errors_are_retried_at_once() {
+        assert!(is_connection_error(&io::Error::from(io::ErrorKind::ConnectionAborted)));
+        // EMFILE and ENFILE
+        assert!(!is_connection_error(&io::Error::from_raw_os_error(24)));
+        assert!(!is_connection_error(&io::Error::from_raw_os_error(23)));
+    }
+}
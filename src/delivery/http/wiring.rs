@@ -0,0 +1,137 @@
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+use super::{Route, RouteTable, ROUTES};
+
+/// A way `ROUTES`, the router and the OpenAPI spec have drifted apart
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum WiringError {
+    #[error("{method} {path} is listed more than once in ROUTES")]
+    DuplicateEndpoint { method: String, path: &'static str },
+    #[error("`:{first}` and `:{second}` name the same segment of {prefix}")]
+    ParamConflict { prefix: String, first: String, second: String },
+    #[error("{0} is in ROUTES but create_app never mounts it")]
+    NotMounted(String),
+    #[error("{0} is documented but has no OpenAPI operation at its path and method")]
+    MissingFromSpec(String),
+    #[error("OpenAPI operation {0} has no documented route")]
+    UnknownOperation(String),
+}
+
+/// Check the route registry against what `create_app` recorded in `table`
+/// and against `spec`. `create_app` runs this in debug builds and panics on
+/// any problem, so drift shows up when the app or its tests start rather than
+/// as a 404 or a stale client.
+pub fn verify_wiring(table: &RouteTable, spec: &Value, hide_unimplemented: bool) -> Result<(), Vec<WiringError>> {
+    let mut problems = registry_problems(ROUTES);
+
+    let mounted: HashSet<String> = table.routes().into_iter().map(|route| route.name).collect();
+    for route in ROUTES.iter().filter(|route| !(hide_unimplemented && route.stub)) {
+        let operation_id = route.name.operation_id();
+        if !mounted.contains(&operation_id) {
+            problems.push(WiringError::NotMounted(operation_id));
+        }
+    }
+
+    let documented: HashSet<String> =
+        ROUTES.iter().filter(|route| route.documented).map(|route| route.name.operation_id()).collect();
+    for route in ROUTES.iter().filter(|route| route.documented) {
+        let operation = &spec["paths"][route.name.openapi_path()][route.method.as_str().to_ascii_lowercase()];
+        if operation["operationId"].as_str() != Some(route.name.operation_id().as_str()) {
+            problems.push(WiringError::MissingFromSpec(route.name.operation_id()));
+        }
+    }
+    for methods in spec["paths"].as_object().into_iter().flat_map(|paths| paths.values()) {
+        for operation in methods.as_object().into_iter().flat_map(|methods| methods.values()) {
+            let operation_id = operation["operationId"].as_str().unwrap_or_default();
+            if !documented.contains(operation_id) {
+                problems.push(WiringError::UnknownOperation(operation_id.to_string()));
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems)
+    }
+}
+
+/// Duplicate endpoints and clashing parameter names in a route list
+fn registry_problems(routes: &[Route]) -> Vec<WiringError> {
+    let mut problems = Vec::new();
+    let mut endpoints = HashSet::new();
+    // Routers reject two names for one segment, e.g. `/users/:id` and `/users/:user_id/sessions`
+    let mut params: HashMap<String, String> = HashMap::new();
+    for route in routes {
+        if !endpoints.insert((route.method.as_str(), route.template)) {
+            problems.push(WiringError::DuplicateEndpoint { method: route.method.to_string(), path: route.template });
+        }
+        let mut prefix = String::new();
+        for segment in route.template.split('/').skip(1) {
+            if let Some(name) = segment.strip_prefix(':') {
+                match params.get(&prefix) {
+                    Some(first) if first != name => problems.push(WiringError::ParamConflict {
+                        prefix: prefix.clone(),
+                        first: first.clone(),
+                        second: name.to_string(),
+                    }),
+                    Some(_) => {}
+                    None => {
+                        params.insert(prefix.clone(), name.to_string());
+                    }
+                }
+                prefix.push_str("/:");
+            } else {
+                prefix.push('/');
+                prefix.push_str(segment);
+            }
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::container::AppContainer;
+    use crate::delivery::{create_app, openapi_spec, RouteName};
+    use axum::http::Method;
+
+    #[test]
+    fn drift_between_routes_router_and_spec_is_reported() {
+        let container = AppContainer::new(&Config::from_env());
+        let _ = create_app(&container);
+        let mut spec = openapi_spec();
+        assert_eq!(verify_wiring(&container.routes, &spec, false), Ok(()));
+
+        spec["paths"]["/api/users/{id}"].as_object_mut().unwrap().remove("get");
+        spec["paths"]["/api/legacy"] = serde_json::json!({ "get": { "operationId": "getLegacy" } });
+        let empty = RouteTable::new();
+        empty.record(RouteName::GetUser, "get_user");
+        let problems = verify_wiring(&empty, &spec, false).unwrap_err();
+        assert!(problems.contains(&WiringError::MissingFromSpec("getUser".to_string())));
+        assert!(problems.contains(&WiringError::UnknownOperation("getLegacy".to_string())));
+        assert!(problems.contains(&WiringError::NotMounted("listUsers".to_string())));
+        assert!(!problems.contains(&WiringError::NotMounted("getUser".to_string())));
+
+        let route = |name, template| Route { name, method: Method::GET, template, summary: "", documented: true, stub: false };
+        let clashing = [
+            route(RouteName::GetUser, "/api/users/:id"),
+            route(RouteName::ListUserSessions, "/api/users/:user_id/sessions"),
+            route(RouteName::GetUserOverview, "/api/users/:id"),
+        ];
+        assert_eq!(
+            registry_problems(&clashing),
+            vec![
+                WiringError::ParamConflict {
+                    prefix: "/api/users".to_string(),
+                    first: "id".to_string(),
+                    second: "user_id".to_string(),
+                },
+                WiringError::DuplicateEndpoint { method: "GET".to_string(), path: "/api/users/:id" },
+            ]
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::{Response, IntoResponse},
+};
+use std::sync::{Arc, OnceLock};
+use uuid::Uuid;
+use validator::Validate;
+
+use super::model::{
+    AnomaliesResponse, ConsumersResponse, DeprecationsResponse, DrainResponse, ImpersonateRequest, InvalidateCacheRequest, ImpersonationResponse, ObjectPoolsResponse,
+    RevokeSessionsResponse, RoutesResponse, SessionsResponse, ShardsResponse, MethodMetricsResponse,
+};
+use crate::container::boot::BootReport;
+use crate::delivery::{url_for, AdminActor, DeprecationTracker, FastJson, ResponseCache, RouteName, RouteTable};
+use crate::domain::session::feature::{ImpersonationError, ImpersonationService};
+use crate::domain::session::repository::SessionStore;
+use crate::infrastructure::{run_blocking, AnomalyDetector, AuditLog, AuditQuery, CacheInvalidator, ConsumerMetrics, CpuPool, DeploymentInfo, FieldErrors, LaneLimiter, MemoryGuard, MethodMetrics, RateLimiter, ShardRegistry};
+use crate::middleware::BODY_CAPTURE_BUFFERS;
+use crate::response::{RESPONSE_BUFFERS, ListEnvelope, created_response, internal_error_response, not_found_response, success_response, validation_error_response};
+
+/// Reading back through archived segments decompresses them; past this the request gives up
+const AUDIT_QUERY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(20);
+
+pub async fn start_draining(State(deployment): State<Arc<DeploymentInfo>>) -> Response {
+    deployment.set_draining(true);
+    tracing::warn!(deployment_id = %deployment.id, "Instance marked as draining");
+    drain_response(&deployment)
+}
+
+pub async fn stop_draining(State(deployment): State<Arc<DeploymentInfo>>) -> Response {
+    deployment.set_draining(false);
+    tracing::info!(deployment_id = %deployment.id, "Instance no longer draining");
+    drain_response(&deployment)
+}
+
+/// Anomalies flagged in the most recently evaluated window
+pub async fn list_anomalies(State(detector): State<Arc<AnomalyDetector>>, envelope: ListEnvelope) -> Response {
+    let response = AnomaliesResponse {
+        window_secs: detector.window().as_secs(),
+        routes_tracked: detector.routes().len(),
+        anomalies: detector.active(),
+    };
+    envelope.respond(response, |response| &response.anomalies, None)
+}
+
+/// Routes mounted on this instance, with the handler serving each
+pub async fn list_routes(State(routes): State<Arc<RouteTable>>, envelope: ListEnvelope) -> Response {
+    envelope.respond(RoutesResponse { routes: routes.routes() }, |response| &response.routes, None)
+}
+
+/// Queue depths and counters of the CPU work pool
+pub async fn cpu_pool_stats(State(pool): State<Arc<CpuPool>>) -> Response {
+    success_response(pool.stats()).into_response()
+}
+
+/// Latest process memory sample, the configured limits and current pressure
+pub async fn memory_report(State(memory): State<Arc<MemoryGuard>>) -> Response {
+    success_response(memory.report()).into_response()
+}
+
+/// Hit, miss and discard counts of the response and body-capture buffer pools
+pub async fn list_object_pools(envelope: ListEnvelope) -> Response {
+    let response = ObjectPoolsResponse {
+        pools: vec![RESPONSE_BUFFERS.stats(), BODY_CAPTURE_BUFFERS.stats()],
+    };
+    envelope.respond(response, |response| &response.pools, None)
+}
+
+/// Pick counts per node and the last rebalance of every shard ring
+pub async fn list_shards(State(shards): State<Arc<ShardRegistry>>, envelope: ListEnvelope) -> Response {
+    envelope.respond(ShardsResponse { rings: shards.stats() }, |response| &response.rings, None)
+}
+
+/// Call counts, latency percentiles and error rates of the service methods
+pub async fn list_method_metrics(State(metrics): State<Arc<MethodMetrics>>, envelope: ListEnvelope) -> Response {
+    envelope.respond(MethodMetricsResponse { methods: metrics.report() }, |response| &response.methods, None)
+}
+
+/// Lag, processing latency, retries and dead-letter depth of each message consumer
+pub async fn list_consumers(State(consumers): State<Arc<ConsumerMetrics>>, envelope: ListEnvelope) -> Response {
+    envelope.respond(ConsumersResponse { consumers: consumers.report() }, |response| &response.consumers, None)
+}
+
+/// Hits, stale serves, misses and background refreshes of each cached route,
+/// to check that stale serves keep dashboards fast without hiding failing refreshes
+pub async fn get_response_cache_stats(State(cache): State<Arc<ResponseCache>>) -> Response {
+    success_response(cache.report()).into_response()
+}
+
+/// Audit events, newest first. Reads past the journal into archived segments
+/// when it does not hold enough; page back with `before_seq`.
+pub async fn list_audit_events(
+    State(log): State<Option<Arc<AuditLog>>>,
+    Query(query): Query<AuditQuery>,
+    envelope: ListEnvelope,
+) -> Response {
+    let Some(log) = log else {
+        return not_found_response("Audit journal").into_response();
+    };
+    match run_blocking("audit_query", AUDIT_QUERY_TIMEOUT, move || log.query(&query)).await {
+        Ok(Ok(page)) => envelope.respond(page, |page| &page.records, None),
+        Ok(Err(err)) => {
+            tracing::error!(error = %err, "Audit trail unreadable");
+            internal_error_response("Audit trail unreadable").into_response()
+        }
+        Err(err) => {
+            tracing::error!(error = %err, "Audit query failed");
+            internal_error_response("Audit query failed").into_response()
+        }
+    }
+}
+
+/// Shared request capacity, with the share, occupancy and shed count of each lane
+pub async fn list_lanes(State(lanes): State<Arc<LaneLimiter>>) -> Response {
+    success_response(lanes.report()).into_response()
+}
+
+/// The rate limit mode and the clients most often over budget, for tuning
+/// limits in shadow mode before enforcing them
+pub async fn list_rate_limited_clients(State(limiter): State<Arc<RateLimiter>>) -> Response {
+    success_response(limiter.report()).into_response()
+}
+
+/// Deprecated routes and fields, with their sunset dates and how often they
+/// are still requested
+pub async fn list_deprecations(State(tracker): State<Arc<DeprecationTracker>>, envelope: ListEnvelope) -> Response {
+    envelope.respond(DeprecationsResponse { deprecations: tracker.usage() }, |response| &response.deprecations, None)
+}
+
+/// What this instance booted with; logged once at startup as `Boot report`
+pub async fn get_boot_report(State(report): State<Arc<OnceLock<BootReport>>>) -> Response {
+    match report.get() {
+        Some(report) => success_response(report).into_response(),
+        None => not_found_response("Boot report").into_response(),
+    }
+}
+
+/// Drop in-process cache entries by key, prefix or tag, and purge the tags at
+/// the CDN, for troubleshooting stale responses
+pub async fn invalidate_cache(
+    State(caches): State<Arc<CacheInvalidator>>,
+    AdminActor(actor): AdminActor,
+    FastJson(payload): FastJson<InvalidateCacheRequest>,
+) -> Result<Response, Response> {
+    if let Err(errors) = payload.validate() {
+        return Err(validation_error_response(&FieldErrors::from(errors)).into_response());
+    }
+    if payload.is_empty() {
+        let errors = FieldErrors::field("keys", "Give at least one key, prefix or tag");
+        return Err(validation_error_response(&errors).into_response());
+    }
+
+    let report = caches.invalidate(&payload.selector()).await;
+    tracing::warn!(
+        target: "audit",
+        audit_event = "cache_invalidated",
+        actor = %actor,
+        reason = %payload.reason,
+        keys = ?payload.keys,
+        prefixes = ?payload.prefixes,
+        tags = ?payload.tags,
+        removed = report.caches.iter().map(|cache| cache.removed).sum::<usize>(),
+        cdn = ?report.cdn,
+        "Cache invalidated"
+    );
+    Ok(success_response(report).into_response())
+}
+
+/// Active sessions and refresh tokens of a user
+pub async fn list_sessions(
+    State(sessions): State<Arc<dyn SessionStore>>,
+    Path(user_id): Path<Uuid>,
+    envelope: ListEnvelope,
+) -> Result<Response, Response> {
+    match sessions.list_active(user_id).await {
+        Ok(sessions) => Ok(envelope.respond(SessionsResponse { user_id, sessions }, |response| &response.sessions, None)),
+        Err(_) => Err(internal_error_response("Failed to list sessions").into_response()),
+    }
+}
+
+pub async fn revoke_session(
+    State(sessions): State<Arc<dyn SessionStore>>,
+    Path((user_id, session_id)): Path<(Uuid, Uuid)>,
+) -> Result<Response, Response> {
+    match sessions.revoke(user_id, session_id).await {
+        Ok(true) => {
+            tracing::warn!(user_id = %user_id, session_id = %session_id, "Session revoked by admin");
+            Ok(success_response(RevokeSessionsResponse { user_id, revoked: 1 }).into_response())
+        }
+        Ok(false) => Err(not_found_response("Session").into_response()),
+        Err(_) => Err(internal_error_response("Failed to revoke session").into_response()),
+    }
+}
+
+pub async fn revoke_all_sessions(
+    State(sessions): State<Arc<dyn SessionStore>>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Response, Response> {
+    match sessions.revoke_all(user_id).await {
+        Ok(revoked) => {
+            tracing::warn!(user_id = %user_id, revoked = revoked, "All sessions revoked by admin");
+            Ok(success_response(RevokeSessionsResponse { user_id, revoked }).into_response())
+        }
+        Err(_) => Err(internal_error_response("Failed to revoke sessions").into_response()),
+    }
+}
+
+/// Issue a short-lived token acting as the user
+pub async fn start_impersonation(
+    State(impersonation): State<Arc<ImpersonationService>>,
+    AdminActor(actor): AdminActor,
+    Path(user_id): Path<Uuid>,
+    FastJson(payload): FastJson<ImpersonateRequest>,
+) -> Result<Response, Response> {
+    if let Err(errors) = payload.validate() {
+        return Err(validation_error_response(&FieldErrors::from(errors)).into_response());
+    }
+
+    match impersonation.start(user_id, &actor, &payload.reason).await {
+        // The session is listed, and can be revoked, with the user's other sessions
+        Ok((token, session)) => Ok(created_response(
+            ImpersonationResponse { token, user_id, session_id: session.id, expires_at: session.expires_at },
+            url_for(RouteName::ListUserSessions, &[("id", &user_id)]).ok(),
+        )
+        .into_response()),
+        Err(ImpersonationError::UserNotFound) => Err(not_found_response("User").into_response()),
+        Err(_) => Err(internal_error_response("Failed to start impersonation").into_response()),
+    }
+}
+
+/// End every impersonation session for the user
+pub async fn stop_impersonation(
+    State(impersonation): State<Arc<ImpersonationService>>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Response, Response> {
+    match impersonation.stop(user_id).await {
+        Ok(revoked) => Ok(success_response(RevokeSessionsResponse { user_id, revoked }).into_response()),
+        Err(_) => Err(internal_error_response("Failed to stop impersonation").into_response()),
+    }
+}
+
+fn drain_response(deployment: &DeploymentInfo) -> Response {
+    success_response(DrainResponse {
+        deployment_id: deployment.id.clone(),
+        draining: deployment.is_draining(),
+    })
+    .into_response()
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+use crate::infrastructure::CacheSelector;
+
+/// Most keys, prefixes or tags in one invalidation request
+pub const MAX_CACHE_SELECTORS: usize = 100;
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct ImpersonateRequest {
+    #[validate(length(min = 1, message = "Reason is required"))]
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct InvalidateCacheRequest {
+    #[validate(length(min = 1, message = "Reason is required"))]
+    pub reason: String,
+
+    /// Exact cache keys, e.g. `user:<id>`
+    #[serde(default)]
+    #[validate(custom = "validate_selectors")]
+    pub keys: Vec<String>,
+
+    /// Key prefixes, e.g. `user:` for every cached user
+    #[serde(default)]
+    #[validate(custom = "validate_selectors")]
+    pub prefixes: Vec<String>,
+
+    /// Surrogate keys; also purged at the CDN when one is configured
+    #[serde(default)]
+    #[validate(custom = "validate_selectors")]
+    pub tags: Vec<String>,
+}
+
+impl InvalidateCacheRequest {
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.prefixes.is_empty() && self.tags.is_empty()
+    }
+
+    pub fn selector(&self) -> CacheSelector {
+        CacheSelector { keys: self.keys.clone(), prefixes: self.prefixes.clone(), tags: self.tags.clone() }
+    }
+}
+
+/// An empty prefix would match every entry, so blanks are rejected
+fn validate_selectors(values: &[String]) -> Result<(), ValidationError> {
+    let message = if values.len() > MAX_CACHE_SELECTORS {
+        format!("At most {MAX_CACHE_SELECTORS} entries")
+    } else if values.iter().any(|value| value.trim().is_empty()) {
+        "Entries must not be blank".to_string()
+    } else {
+        return Ok(());
+    };
+    let mut error = ValidationError::new("cache_selectors");
+    error.message = Some(message.into());
+    Err(error)
+}
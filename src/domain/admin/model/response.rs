@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::delivery::{DeprecationUsage, MountedRoute};
+use crate::domain::session::entities::Session;
+use crate::infrastructure::{Anomaly, ConsumerReport, MethodReport, ObjectPoolStats, ShardRingStats};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DrainResponse {
+    pub deployment_id: String,
+    pub draining: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnomaliesResponse {
+    pub window_secs: u64,
+    pub routes_tracked: usize,
+    pub anomalies: Vec<Anomaly>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionsResponse {
+    pub user_id: Uuid,
+    pub sessions: Vec<Session>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImpersonationResponse {
+    /// Bearer token acting as the user; shown once
+    pub token: String,
+    pub user_id: Uuid,
+    pub session_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevokeSessionsResponse {
+    pub user_id: Uuid,
+    pub revoked: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ObjectPoolsResponse {
+    pub pools: Vec<ObjectPoolStats>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShardsResponse {
+    pub rings: Vec<ShardRingStats>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MethodMetricsResponse {
+    pub methods: Vec<MethodReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConsumersResponse {
+    pub consumers: Vec<ConsumerReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeprecationsResponse {
+    pub deprecations: Vec<DeprecationUsage>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RoutesResponse {
+    pub routes: Vec<MountedRoute>,
+}
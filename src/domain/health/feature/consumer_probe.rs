@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::domain::health::feature::HealthProbe;
+use crate::infrastructure::ConsumerMetrics;
+
+/// Fails while any message consumer is detached from its queue or bus, so an
+/// instance that would accept events it can't process is taken out of rotation
+pub struct ConsumerProbe {
+    consumers: Arc<ConsumerMetrics>,
+}
+
+impl ConsumerProbe {
+    pub fn new(consumers: Arc<ConsumerMetrics>) -> Self {
+        Self { consumers }
+    }
+}
+
+#[async_trait]
+impl HealthProbe for ConsumerProbe {
+    async fn check(&self) -> Result<(), String> {
+        match self.consumers.disconnected().as_slice() {
+            [] => Ok(()),
+            names => Err(format!("not connected: {}", names.join(", "))),
+        }
+    }
+}
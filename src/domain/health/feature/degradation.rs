@@ -0,0 +1,141 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+use crate::domain::health::model::DegradationState;
+
+/// What a component does while the dependency it falls back from is down
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DegradeMode {
+    /// Switch to the local substitute until the dependency recovers
+    Fallback,
+    /// Keep using the dependency and let its errors through
+    Strict,
+}
+
+impl DegradeMode {
+    pub fn from_name(name: &str) -> Self {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "strict" | "off" => DegradeMode::Strict,
+            _ => DegradeMode::Fallback,
+        }
+    }
+}
+
+/// Shared between the registry and the component that degrades; cheap to
+/// read on every request
+pub struct DegradationSwitch {
+    dependency: String,
+    fallback: &'static str,
+    mode: DegradeMode,
+    down: AtomicBool,
+    since: RwLock<Option<DateTime<Utc>>>,
+}
+
+impl DegradationSwitch {
+    /// The dependency is down and the policy allows falling back
+    pub fn is_active(&self) -> bool {
+        self.mode == DegradeMode::Fallback && self.down.load(Ordering::Relaxed)
+    }
+}
+
+/// Per-dependency degradation policies, switched on and off by the
+/// dependency monitor's probe results.
+///
+/// A component registers the dependency it can do without and the fallback
+/// it uses, then checks its switch before each call. The mode of each
+/// dependency comes from `DEGRADATION_MODES`, e.g. `user_repository=strict`;
+/// unlisted dependencies use the default mode.
+pub struct Degradations {
+    default_mode: DegradeMode,
+    modes: HashMap<String, DegradeMode>,
+    switches: RwLock<Vec<Arc<DegradationSwitch>>>,
+}
+
+impl Degradations {
+    /// `modes` are `dependency=fallback|strict` pairs separated by commas
+    pub fn new(default_mode: DegradeMode, modes: &str) -> Self {
+        let modes = modes
+            .split(',')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(dependency, mode)| (dependency.trim().to_string(), DegradeMode::from_name(mode)))
+            .collect();
+        Self { default_mode, modes, switches: RwLock::default() }
+    }
+
+    /// `dependency` is the name its health probe is registered under
+    pub fn register(&self, dependency: &str, fallback: &'static str) -> Arc<DegradationSwitch> {
+        let switch = Arc::new(DegradationSwitch {
+            dependency: dependency.to_string(),
+            fallback,
+            mode: self.modes.get(dependency).copied().unwrap_or(self.default_mode),
+            down: AtomicBool::new(false),
+            since: RwLock::default(),
+        });
+        self.switches.write().unwrap().push(switch.clone());
+        switch
+    }
+
+    /// Record a probe result; logs when a fallback starts or stops
+    pub fn observe(&self, dependency: &str, healthy: bool) {
+        for switch in self.switches.read().unwrap().iter().filter(|switch| switch.dependency == dependency) {
+            let was_down = switch.down.swap(!healthy, Ordering::Relaxed);
+            match (was_down, healthy) {
+                (false, false) => {
+                    *switch.since.write().unwrap() = Some(Utc::now());
+                    match switch.mode {
+                        DegradeMode::Fallback => {
+                            tracing::warn!(dependency, fallback = switch.fallback, "Dependency down; degraded to fallback")
+                        }
+                        DegradeMode::Strict => {
+                            tracing::warn!(dependency, "Dependency down; fallback disabled (strict)")
+                        }
+                    }
+                }
+                (true, true) => tracing::info!(dependency, "Dependency recovered; fallback off"),
+                _ => {}
+            }
+        }
+    }
+
+    pub fn states(&self) -> Vec<DegradationState> {
+        self.switches
+            .read()
+            .unwrap()
+            .iter()
+            .map(|switch| DegradationState {
+                dependency: switch.dependency.clone(),
+                fallback: switch.fallback.to_string(),
+                mode: switch.mode,
+                active: switch.is_active(),
+                since: *switch.since.read().unwrap(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fallbacks_follow_probe_results_unless_strict() {
+        let degradations = Degradations::new(DegradeMode::Fallback, "search=strict, bogus");
+        let cache = degradations.register("user_repository", "stale cached users");
+        let search = degradations.register("search", "none");
+
+        degradations.observe("user_repository", false);
+        degradations.observe("search", false);
+        assert!(cache.is_active());
+        assert!(!search.is_active());
+        let states = degradations.states();
+        assert!(states[0].active && states[0].since.is_some());
+        assert_eq!((states[1].mode, states[1].active), (DegradeMode::Strict, false));
+
+        degradations.observe("user_repository", true);
+        assert!(!cache.is_active());
+    }
+}
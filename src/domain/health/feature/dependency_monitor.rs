@@ -0,0 +1,250 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::{Degradations, HealthRegistry, OverallStatus};
+use crate::container::startup::StartupComponent;
+use crate::domain::health::model::DependencyStatus;
+
+/// Results older than this many probe intervals are flagged as stale
+const STALE_AFTER_INTERVALS: u32 = 3;
+
+/// Runs the registry's checks in the background and keeps the latest result
+/// per dependency, so `/api/health/dependencies` and `/api/ready` answer
+/// without probing
+pub struct DependencyMonitor {
+    registry: Arc<HealthRegistry>,
+    interval: Duration,
+    jitter: Duration,
+    statuses: RwLock<Vec<DependencyStatus>>,
+    degradations: Option<Arc<Degradations>>,
+}
+
+impl DependencyMonitor {
+    pub fn new(registry: Arc<HealthRegistry>, interval: Duration) -> Self {
+        Self {
+            registry,
+            interval,
+            jitter: Duration::ZERO,
+            statuses: RwLock::new(Vec::new()),
+            degradations: None,
+        }
+    }
+
+    /// Switch fallbacks on and off with each probe result
+    pub fn with_degradations(mut self, degradations: Arc<Degradations>) -> Self {
+        self.degradations = Some(degradations);
+        self
+    }
+
+    /// Spread each wait between probes by up to `jitter` either way, so
+    /// replicas started together don't probe shared dependencies in lockstep.
+    /// Capped at half the interval.
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter.min(self.interval / 2);
+        self
+    }
+
+    /// The wait before the next probe
+    fn next_delay(&self) -> Duration {
+        let span = self.jitter.as_millis() as u64 * 2;
+        if span == 0 {
+            return self.interval;
+        }
+        let offset = (Uuid::new_v4().as_u128() % (u128::from(span) + 1)) as u64;
+        (self.interval + Duration::from_millis(offset)).saturating_sub(self.jitter)
+    }
+
+    /// Probe every dependency once and record the outcome
+    pub async fn refresh(&self) {
+        let report = self.registry.run().await;
+        let checked_at = Utc::now();
+
+        let mut statuses = self.statuses.write().unwrap();
+        let previous = std::mem::take(&mut *statuses);
+        for check in report.checks {
+            let before = previous.iter().find(|status| status.name == check.name);
+            let healthy = check.error.is_none();
+            if !healthy {
+                tracing::warn!(dependency = %check.name, error = ?check.error, "Dependency probe failed");
+            }
+            if let Some(degradations) = &self.degradations {
+                degradations.observe(&check.name, healthy);
+            }
+            statuses.push(DependencyStatus {
+                status: check.status,
+                critical: check.critical,
+                latency_ms: check.latency_ms,
+                last_checked: Some(checked_at),
+                last_success: if healthy { Some(checked_at) } else { before.and_then(|status| status.last_success) },
+                consecutive_failures: if healthy {
+                    0
+                } else {
+                    before.map_or(0, |status| status.consecutive_failures) + 1
+                },
+                stale: false,
+                error: check.error,
+                name: check.name,
+            });
+        }
+    }
+
+    /// Latest recorded statuses and their aggregate; never runs a probe
+    pub fn snapshot(&self) -> (OverallStatus, Vec<DependencyStatus>) {
+        let stale_before = Utc::now()
+            - chrono::Duration::from_std(self.interval * STALE_AFTER_INTERVALS).unwrap_or(chrono::Duration::MAX);
+        let mut dependencies = self.statuses.read().unwrap().clone();
+
+        let mut overall = OverallStatus::Healthy;
+        for dependency in &mut dependencies {
+            dependency.stale = dependency.last_checked.is_none_or(|checked| checked < stale_before);
+            if dependency.error.is_some() || dependency.stale {
+                overall = match (dependency.critical, overall) {
+                    (true, _) => OverallStatus::Unhealthy,
+                    (false, OverallStatus::Healthy) => OverallStatus::Degraded,
+                    (false, overall) => overall,
+                };
+            }
+        }
+        (overall, dependencies)
+    }
+
+    pub fn spawn(self: &Arc<Self>) {
+        let monitor = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(monitor.next_delay()).await;
+                monitor.refresh().await;
+            }
+        });
+    }
+}
+
+/// Takes the first reading before traffic arrives, then keeps probing
+pub struct DependencyMonitorStartup {
+    monitor: Arc<DependencyMonitor>,
+}
+
+impl DependencyMonitorStartup {
+    pub fn new(monitor: Arc<DependencyMonitor>) -> Self {
+        Self { monitor }
+    }
+}
+
+#[async_trait]
+impl StartupComponent for DependencyMonitorStartup {
+    fn name(&self) -> &'static str {
+        "dependency_monitor"
+    }
+
+    /// The first reading should see the message consumers attached
+    fn depends_on(&self) -> &'static [&'static str] {
+        &["user_repository", "webhook_worker", "report_worker"]
+    }
+
+    async fn start(&self) -> Result<(), String> {
+        self.monitor.refresh().await;
+        self.monitor.spawn();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::health::feature::{Criticality, HealthProbe};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    struct Flaky {
+        up: AtomicBool,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl HealthProbe for Flaky {
+        async fn check(&self) -> Result<(), String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.up.load(Ordering::SeqCst) {
+                Ok(())
+            } else {
+                Err("connection refused".to_string())
+            }
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn background_probes_track_last_success_and_failures() {
+        let probe = Arc::new(Flaky { up: AtomicBool::new(true), calls: AtomicUsize::new(0) });
+        let registry = Arc::new(HealthRegistry::new());
+        registry.register("database", Criticality::Critical, probe.clone());
+        let monitor = Arc::new(DependencyMonitor::new(registry, Duration::from_secs(15)));
+
+        assert!(monitor.snapshot().1.is_empty());
+
+        monitor.refresh().await;
+        let (status, dependencies) = monitor.snapshot();
+        assert_eq!(status, OverallStatus::Healthy);
+        let last_success = dependencies[0].last_success;
+        assert!(last_success.is_some());
+
+        probe.up.store(false, Ordering::SeqCst);
+        monitor.refresh().await;
+        monitor.refresh().await;
+        let (status, dependencies) = monitor.snapshot();
+        assert_eq!(status, OverallStatus::Unhealthy);
+        assert_eq!(dependencies[0].last_success, last_success);
+        assert_eq!(dependencies[0].consecutive_failures, 2);
+        assert_eq!(dependencies[0].error.as_deref(), Some("connection refused"));
+
+        // Reading the snapshot never probes
+        assert_eq!(probe.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn background_probes_fire_within_the_jitter_window() {
+        struct Timed(std::sync::Mutex<Vec<tokio::time::Instant>>);
+
+        #[async_trait]
+        impl HealthProbe for Timed {
+            async fn check(&self) -> Result<(), String> {
+                self.0.lock().unwrap().push(tokio::time::Instant::now());
+                Ok(())
+            }
+        }
+
+        let probe = Arc::new(Timed(std::sync::Mutex::new(Vec::new())));
+        let registry = Arc::new(HealthRegistry::new());
+        registry.register("database", Criticality::Critical, probe.clone());
+        let monitor = Arc::new(DependencyMonitor::new(registry, Duration::from_secs(10)).with_jitter(Duration::from_secs(2)));
+        let started = tokio::time::Instant::now();
+        monitor.spawn();
+
+        // The idle runtime jumps straight to each probe's timer
+        tokio::time::sleep(Duration::from_secs(300)).await;
+        let fired = probe.0.lock().unwrap().clone();
+        assert!(fired.len() >= 25, "{} probes", fired.len());
+        let gaps: Vec<Duration> =
+            std::iter::once(started).chain(fired.iter().copied()).zip(fired.iter()).map(|(before, at)| *at - before).collect();
+        assert!(gaps.iter().all(|gap| (Duration::from_secs(8)..=Duration::from_secs(12)).contains(gap)), "{gaps:?}");
+        assert!(gaps.iter().any(|gap| *gap != gaps[0]));
+    }
+
+    #[test]
+    fn jittered_delays_stay_within_the_configured_spread() {
+        let registry = Arc::new(HealthRegistry::new());
+        let monitor = DependencyMonitor::new(registry.clone(), Duration::from_secs(15));
+        assert_eq!(monitor.next_delay(), Duration::from_secs(15));
+
+        let monitor = monitor.with_jitter(Duration::from_secs(3));
+        let delays: Vec<_> = (0..200).map(|_| monitor.next_delay()).collect();
+        assert!(delays.iter().all(|delay| (Duration::from_secs(12)..=Duration::from_secs(18)).contains(delay)));
+        assert!(delays.iter().any(|delay| *delay != delays[0]));
+
+        // Capped at half the interval, so probes never run back to back
+        let monitor = DependencyMonitor::new(registry, Duration::from_secs(1)).with_jitter(Duration::from_secs(5));
+        let range = Duration::from_millis(500)..=Duration::from_millis(1500);
+        assert!((0..50).all(|_| range.contains(&monitor.next_delay())));
+    }
+}
@@ -0,0 +1,205 @@
+use async_trait::async_trait;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+use crate::domain::health::model::HealthCheck;
+
+/// A named dependency check contributed by a domain or plugin
+#[async_trait]
+pub trait HealthProbe: Send + Sync {
+    /// `Err` carries a short, operator-facing reason
+    async fn check(&self) -> Result<(), String>;
+}
+
+/// How a failing check affects the aggregate status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Criticality {
+    /// Failure makes the instance unhealthy and not ready
+    Critical,
+    /// Failure only degrades the reported status
+    NonCritical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverallStatus {
+    Healthy,
+    Degraded,
+    Unhealthy,
+}
+
+impl OverallStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OverallStatus::Healthy => "healthy",
+            OverallStatus::Degraded => "degraded",
+            OverallStatus::Unhealthy => "unhealthy",
+        }
+    }
+}
+
+pub struct HealthReport {
+    pub status: OverallStatus,
+    pub checks: Vec<HealthCheck>,
+}
+
+struct RegisteredCheck {
+    name: String,
+    criticality: Criticality,
+    probe: Arc<dyn HealthProbe>,
+}
+
+/// Registry of health checks aggregated by `/api/health` and `/api/ready`.
+///
+/// Checks run concurrently on every request and each is bounded by the
+/// registry timeout, so one hanging dependency can't stall the probe.
+pub struct HealthRegistry {
+    checks: RwLock<Vec<RegisteredCheck>>,
+    timeout: Duration,
+}
+
+impl HealthRegistry {
+    pub fn new() -> Self {
+        Self {
+            checks: RwLock::new(Vec::new()),
+            timeout: Duration::from_secs(2),
+        }
+    }
+
+    /// Upper bound for a single check; slower checks are reported as failed
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Register a check, replacing any existing check with the same name
+    pub fn register(&self, name: impl Into<String>, criticality: Criticality, probe: Arc<dyn HealthProbe>) {
+        let name = name.into();
+        let mut checks = self.checks.write().unwrap();
+        checks.retain(|check| check.name != name);
+        checks.push(RegisteredCheck { name, criticality, probe });
+    }
+
+    pub async fn run(&self) -> HealthReport {
+        let registered: Vec<(String, Criticality, Arc<dyn HealthProbe>)> = self
+            .checks
+            .read()
+            .unwrap()
+            .iter()
+            .map(|check| (check.name.clone(), check.criticality, Arc::clone(&check.probe)))
+            .collect();
+
+        let mut tasks = JoinSet::new();
+        for (index, (_, _, probe)) in registered.iter().enumerate() {
+            let probe = Arc::clone(probe);
+            let timeout = self.timeout;
+            tasks.spawn(async move {
+                let started = Instant::now();
+                let result = match tokio::time::timeout(timeout, probe.check()).await {
+                    Ok(result) => result,
+                    Err(_) => Err(format!("timed out after {}ms", timeout.as_millis())),
+                };
+                (index, result, started.elapsed())
+            });
+        }
+
+        let mut outcomes: Vec<Option<(Result<(), String>, Duration)>> = vec![None; registered.len()];
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((index, result, latency)) => outcomes[index] = Some((result, latency)),
+                Err(err) => tracing::error!(error = %err, "Health check task panicked"),
+            }
+        }
+
+        let mut status = OverallStatus::Healthy;
+        let checks = registered
+            .into_iter()
+            .zip(outcomes)
+            .map(|((name, criticality, _), outcome)| {
+                let (result, latency) =
+                    outcome.unwrap_or_else(|| (Err("check panicked".to_string()), Duration::ZERO));
+                if result.is_err() {
+                    status = match criticality {
+                        Criticality::Critical => OverallStatus::Unhealthy,
+                        Criticality::NonCritical if status == OverallStatus::Healthy => OverallStatus::Degraded,
+                        Criticality::NonCritical => status,
+                    };
+                }
+                HealthCheck {
+                    name,
+                    status: if result.is_ok() { "healthy" } else { "unhealthy" }.to_string(),
+                    critical: criticality == Criticality::Critical,
+                    latency_ms: latency.as_secs_f64() * 1000.0,
+                    error: result.err(),
+                }
+            })
+            .collect();
+
+        HealthReport { status, checks }
+    }
+}
+
+impl Default for HealthRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StaticProbe(Result<(), String>);
+
+    #[async_trait]
+    impl HealthProbe for StaticProbe {
+        async fn check(&self) -> Result<(), String> {
+            self.0.clone()
+        }
+    }
+
+    struct HangingProbe;
+
+    #[async_trait]
+    impl HealthProbe for HangingProbe {
+        async fn check(&self) -> Result<(), String> {
+            std::future::pending().await
+        }
+    }
+
+    fn probe(result: Result<(), &str>) -> Arc<dyn HealthProbe> {
+        Arc::new(StaticProbe(result.map_err(str::to_string)))
+    }
+
+    #[tokio::test]
+    async fn criticality_decides_aggregate_status() {
+        let registry = HealthRegistry::new();
+        registry.register("database", Criticality::Critical, probe(Ok(())));
+        registry.register("search", Criticality::NonCritical, probe(Err("connection refused")));
+
+        let report = registry.run().await;
+        assert_eq!(report.status, OverallStatus::Degraded);
+        assert_eq!(report.checks[1].error.as_deref(), Some("connection refused"));
+
+        registry.register("database", Criticality::Critical, probe(Err("down")));
+        let report = registry.run().await;
+        assert_eq!(report.status, OverallStatus::Unhealthy);
+        // Re-registering replaces the check instead of adding a second one
+        assert_eq!(report.checks.len(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn hanging_check_fails_at_the_timeout() {
+        let registry = HealthRegistry::new().with_timeout(Duration::from_millis(500));
+        registry.register("cache", Criticality::Critical, Arc::new(HangingProbe));
+        registry.register("queue", Criticality::NonCritical, probe(Ok(())));
+
+        let report = registry.run().await;
+        assert_eq!(report.status, OverallStatus::Unhealthy);
+        assert_eq!(report.checks[0].name, "cache");
+        assert_eq!(report.checks[0].error.as_deref(), Some("timed out after 500ms"));
+        assert_eq!(report.checks[0].latency_ms, 500.0);
+        assert_eq!(report.checks[1].status, "healthy");
+    }
+}
@@ -0,0 +1,32 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::domain::health::feature::HealthProbe;
+use crate::infrastructure::{MemoryGuard, MemoryPressure};
+
+/// Fails while process memory is over the guard's hard limit, so readiness
+/// drops the instance before the OOM killer does
+pub struct MemoryProbe {
+    guard: Arc<MemoryGuard>,
+}
+
+impl MemoryProbe {
+    pub fn new(guard: Arc<MemoryGuard>) -> Self {
+        Self { guard }
+    }
+}
+
+#[async_trait]
+impl HealthProbe for MemoryProbe {
+    async fn check(&self) -> Result<(), String> {
+        let report = self.guard.report();
+        match (report.pressure, report.memory, report.hard_limit_bytes) {
+            (MemoryPressure::Hard, Some(memory), Some(limit)) => Err(format!(
+                "RSS {} MiB is over the {} MiB hard limit",
+                memory.rss_bytes / (1024 * 1024),
+                limit / (1024 * 1024)
+            )),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,11 @@
+pub mod degradation;
+pub mod dependency_monitor;
+pub mod health_registry;
+pub mod memory_probe;
+pub mod consumer_probe;
+
+pub use degradation::*;
+pub use dependency_monitor::*;
+pub use health_registry::*;
+pub use memory_probe::*;
+pub use consumer_probe::*;
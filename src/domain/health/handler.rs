@@ -1,28 +1,84 @@
-use axum::response::{Response, IntoResponse};
-use super::model::{HealthResponse, ReadyResponse, LiveResponse, HealthCheck};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{Response, IntoResponse},
+};
+use std::sync::Arc;
+use super::feature::{Degradations, DependencyMonitor, HealthRegistry, OverallStatus};
+use super::model::{DependenciesResponse, HealthCheck, HealthResponse, ReadyResponse, LiveResponse, InfoResponse};
+use crate::infrastructure::DeploymentInfo;
 use crate::response::success_response;
 
-pub async fn health_check() -> Response {
-    let response = HealthResponse::healthy("rust-boilerplate".to_string());
-    success_response(response).into_response()
+/// State shared by the health endpoints
+#[derive(Clone)]
+pub struct HealthState {
+    pub deployment: Arc<DeploymentInfo>,
+    pub registry: Arc<HealthRegistry>,
+    pub dependencies: Arc<DependencyMonitor>,
+    pub degradations: Arc<Degradations>,
+}
+
+pub async fn health_check(State(state): State<HealthState>) -> Response {
+    let report = state.registry.run().await;
+    let code = if report.status == OverallStatus::Unhealthy {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+
+    let response = HealthResponse {
+        status: report.status.as_str().to_string(),
+        timestamp: chrono::Utc::now(),
+        service: "rust-boilerplate".to_string(),
+        region: state.deployment.region.clone(),
+        checks: report.checks,
+        degradations: state.degradations.states(),
+    };
+    (code, success_response(response)).into_response()
+}
+
+/// Served from the background monitor's last results; never probes on request
+pub async fn dependencies(State(state): State<HealthState>) -> Response {
+    let (status, dependencies) = state.dependencies.snapshot();
+    let code = if status == OverallStatus::Unhealthy {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+
+    let response = DependenciesResponse {
+        status: status.as_str().to_string(),
+        timestamp: chrono::Utc::now(),
+        dependencies,
+    };
+    (code, success_response(response)).into_response()
 }
 
-pub async fn readiness_check() -> Response {
+/// Served from the background monitor's last results, so frequent
+/// orchestrator probes never reach the dependencies themselves
+pub async fn readiness_check(State(state): State<HealthState>) -> Response {
+    let (overall, dependencies) = state.dependencies.snapshot();
+
+    // A draining instance reports 503 so the load balancer stops routing to it;
+    // so does one whose critical dependencies are failing or stale
+    let (status, code) = if state.deployment.is_draining() {
+        ("draining", StatusCode::SERVICE_UNAVAILABLE)
+    } else if overall == OverallStatus::Unhealthy {
+        ("not_ready", StatusCode::SERVICE_UNAVAILABLE)
+    } else {
+        ("ready", StatusCode::OK)
+    };
+
     let response = ReadyResponse {
-        status: "ready".to_string(),
+        status: status.to_string(),
         timestamp: chrono::Utc::now(),
-        checks: vec![
-            HealthCheck {
-                name: "database".to_string(),
-                status: "healthy".to_string(),
-            },
-            HealthCheck {
-                name: "memory".to_string(),
-                status: "healthy".to_string(),
-            },
-        ],
+        deployment_id: state.deployment.id.clone(),
+        deployment_color: state.deployment.color.clone(),
+        region: state.deployment.region.clone(),
+        checked_at: dependencies.iter().filter_map(|dependency| dependency.last_checked).min(),
+        checks: dependencies.into_iter().map(HealthCheck::from).collect(),
     };
-    success_response(response).into_response()
+    (code, success_response(response)).into_response()
 }
 
 pub async fn liveness_check() -> Response {
@@ -31,4 +87,105 @@ pub async fn liveness_check() -> Response {
         timestamp: chrono::Utc::now(),
     };
     success_response(response).into_response()
-}
\ No newline at end of file
+}
+
+pub async fn info(State(state): State<HealthState>) -> Response {
+    let deployment = &state.deployment;
+    let response = InfoResponse {
+        service: "rust-boilerplate".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        deployment_id: deployment.id.clone(),
+        deployment_color: deployment.color.clone(),
+        region: deployment.region.clone(),
+        draining: deployment.is_draining(),
+        started_at: deployment.started_at,
+    };
+    success_response(response).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::health::feature::{Criticality, DegradeMode, HealthProbe};
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    struct Switchable(AtomicBool);
+
+    #[async_trait]
+    impl HealthProbe for Switchable {
+        async fn check(&self) -> Result<(), String> {
+            if self.0.load(Ordering::SeqCst) {
+                Ok(())
+            } else {
+                Err("connection refused".to_string())
+            }
+        }
+    }
+
+    fn state(probes: &[(&str, Criticality, Arc<Switchable>)]) -> HealthState {
+        let registry = Arc::new(HealthRegistry::new());
+        for (name, criticality, probe) in probes {
+            registry.register(*name, *criticality, probe.clone());
+        }
+        HealthState {
+            deployment: Arc::new(DeploymentInfo::new("build-42".into(), "green".into(), "eu".into())),
+            dependencies: Arc::new(DependencyMonitor::new(registry.clone(), Duration::from_secs(15))),
+            degradations: Arc::new(Degradations::new(DegradeMode::Fallback, "")),
+            registry,
+        }
+    }
+
+    async fn body(response: Response) -> (StatusCode, serde_json::Value) {
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn draining_instances_report_not_ready_with_their_deployment() {
+        let state = state(&[("database", Criticality::Critical, Arc::new(Switchable(AtomicBool::new(true))))]);
+        state.dependencies.refresh().await;
+
+        let (status, ready) = body(readiness_check(State(state.clone())).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ready["data"]["status"], "ready");
+        assert_eq!((ready["data"]["deployment_id"].as_str(), ready["data"]["deployment_color"].as_str()), (Some("build-42"), Some("green")));
+
+        // As `POST /api/admin/drain` does
+        crate::domain::admin::handler::start_draining(State(state.deployment.clone())).await;
+        let (status, ready) = body(readiness_check(State(state.clone())).await).await;
+        assert_eq!((status, ready["data"]["status"].as_str()), (StatusCode::SERVICE_UNAVAILABLE, Some("draining")));
+        let (_, info) = body(info(State(state.clone())).await).await;
+        assert_eq!((info["data"]["draining"].as_bool(), info["data"]["region"].as_str()), (Some(true), Some("eu")));
+
+        crate::domain::admin::handler::stop_draining(State(state.deployment.clone())).await;
+        assert_eq!(readiness_check(State(state)).await.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn readiness_breaks_down_the_cached_result_per_dependency() {
+        let (database, cache) = (Arc::new(Switchable(AtomicBool::new(true))), Arc::new(Switchable(AtomicBool::new(false))));
+        let state = state(&[("database", Criticality::Critical, database.clone()), ("cache", Criticality::NonCritical, cache)]);
+        state.dependencies.refresh().await;
+
+        // A failing non-critical dependency is reported but leaves the instance ready
+        let (status, ready) = body(readiness_check(State(state.clone())).await).await;
+        assert_eq!((status, ready["data"]["status"].as_str()), (StatusCode::OK, Some("ready")));
+        assert!(ready["data"]["checked_at"].is_string());
+        let checks = ready["data"]["checks"].as_array().unwrap();
+        let check = |name: &str| checks.iter().find(|check| check["name"] == name).cloned().unwrap();
+        assert_eq!((check("database")["critical"].as_bool(), check("database").get("error")), (Some(true), None));
+        assert_eq!((check("cache")["critical"].as_bool(), check("cache")["error"].as_str()), (Some(false), Some("connection refused")));
+
+        // A failing critical one is not, once the monitor has seen it
+        database.0.store(false, Ordering::SeqCst);
+        assert_eq!(readiness_check(State(state.clone())).await.status(), StatusCode::OK);
+        state.dependencies.refresh().await;
+        let (status, ready) = body(readiness_check(State(state)).await).await;
+        assert_eq!((status, ready["data"]["status"].as_str()), (StatusCode::SERVICE_UNAVAILABLE, Some("not_ready")));
+        let database = ready["data"]["checks"].as_array().unwrap().iter().find(|check| check["name"] == "database").cloned().unwrap();
+        assert_eq!(database["error"], "connection refused");
+    }
+}
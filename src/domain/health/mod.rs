@@ -1,4 +1,5 @@
 pub mod model;
+pub mod feature;
 pub mod handler;
 
 pub use model::*;
@@ -1,38 +1,106 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
+use crate::domain::health::feature::DegradeMode;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HealthResponse {
     pub status: String,
     pub timestamp: DateTime<Utc>,
     pub service: String,
+    pub region: String,
+    pub checks: Vec<HealthCheck>,
+    /// Fallback policies and whether each is in use
+    pub degradations: Vec<DegradationState>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadyResponse {
+    pub status: String,
+    pub timestamp: DateTime<Utc>,
+    pub deployment_id: String,
+    pub deployment_color: String,
+    pub region: String,
+    /// When the oldest of the cached check results was probed
+    pub checked_at: Option<DateTime<Utc>>,
+    pub checks: Vec<HealthCheck>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub name: String,
+    pub status: String,
+    pub critical: bool,
+    pub latency_ms: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
-impl HealthResponse {
-    pub fn healthy(service: String) -> Self {
+impl From<DependencyStatus> for HealthCheck {
+    /// A stale result is reported with an error, since it counts as failing
+    fn from(dependency: DependencyStatus) -> Self {
+        let error = match dependency.error {
+            None if dependency.stale => Some("No recent probe result".to_string()),
+            error => error,
+        };
         Self {
-            status: "healthy".to_string(),
-            timestamp: Utc::now(),
-            service,
+            name: dependency.name,
+            status: dependency.status,
+            critical: dependency.critical,
+            latency_ms: dependency.latency_ms,
+            error,
         }
     }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct ReadyResponse {
+pub struct DependenciesResponse {
     pub status: String,
     pub timestamp: DateTime<Utc>,
-    pub checks: Vec<HealthCheck>,
+    pub dependencies: Vec<DependencyStatus>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct HealthCheck {
+/// Latest background probe result for one external dependency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyStatus {
     pub name: String,
     pub status: String,
+    pub critical: bool,
+    pub latency_ms: f64,
+    pub last_checked: Option<DateTime<Utc>>,
+    pub last_success: Option<DateTime<Utc>>,
+    pub consecutive_failures: u32,
+    /// The probe loop hasn't reported within three intervals
+    pub stale: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A degradation policy and whether its fallback is in use
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DegradationState {
+    pub dependency: String,
+    /// What takes over while the dependency is down
+    pub fallback: String,
+    pub mode: DegradeMode,
+    pub active: bool,
+    /// When the dependency was last seen going down
+    pub since: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LiveResponse {
     pub status: String,
     pub timestamp: DateTime<Utc>,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InfoResponse {
+    pub service: String,
+    pub version: String,
+    pub deployment_id: String,
+    pub deployment_color: String,
+    pub region: String,
+    pub draining: bool,
+    pub started_at: DateTime<Utc>,
+}
@@ -1,5 +1,12 @@
 pub mod user;
 pub mod health;
+pub mod admin;
+pub mod session;
+pub mod webhook;
+pub mod product;
+pub mod report;
+pub mod realtime;
+pub mod oidc;
 
 pub use user::*;
 pub use health::*;
\ No newline at end of file
@@ -0,0 +1,231 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+use super::provider::{Grant, TokenError};
+
+/// Letters of user codes: no vowels, so codes never spell words, and
+/// nothing easily confused when read aloud (RFC 8628 section 6.1)
+const USER_CODE_ALPHABET: &[u8] = b"BCDFGHJKLMNPQRSTVWXZ";
+const USER_CODE_LEN: usize = 8;
+/// Added to a client's polling interval each time it polls too fast
+const SLOW_DOWN_STEP_SECS: i64 = 5;
+
+/// Why a user code could not be approved or denied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum DeviceVerificationError {
+    #[error("Unknown or expired code; start again on the device")]
+    UnknownCode,
+    #[error("This code has already been approved or denied")]
+    AlreadyDecided,
+    #[error("Impersonated sessions cannot approve devices")]
+    Impersonated,
+}
+
+impl DeviceVerificationError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            DeviceVerificationError::UnknownCode => "USER_CODE_NOT_FOUND",
+            DeviceVerificationError::AlreadyDecided => "USER_CODE_USED",
+            DeviceVerificationError::Impersonated => "IMPERSONATION_NOT_ALLOWED",
+        }
+    }
+}
+
+/// A device authorization as handed out, before the device polls for it
+#[derive(Debug, Clone)]
+pub struct StartedDevice {
+    pub device_code: String,
+    /// Without the dash, e.g. `WDJBMJHT`
+    pub user_code: String,
+    pub expires_in: i64,
+    pub interval: i64,
+}
+
+enum Decision {
+    Pending,
+    Approved(Grant),
+    Denied,
+}
+
+struct DeviceAuthorization {
+    user_code: String,
+    client_id: String,
+    scope: String,
+    expires_at: DateTime<Utc>,
+    interval: i64,
+    last_poll: Option<DateTime<Utc>>,
+    decision: Decision,
+}
+
+/// Pending device authorizations (RFC 8628), keyed by device code. A user
+/// approves one by entering its user code while signed in; the device
+/// meanwhile polls the token endpoint with the device code. Entries live in
+/// this process only and go once redeemed, denied or expired.
+pub struct DeviceGrants {
+    pending: Mutex<HashMap<String, DeviceAuthorization>>,
+    ttl: chrono::Duration,
+    interval: i64,
+}
+
+impl DeviceGrants {
+    pub fn new(ttl: chrono::Duration, interval_secs: i64) -> Self {
+        Self { pending: Mutex::new(HashMap::new()), ttl, interval: interval_secs.max(1) }
+    }
+
+    pub fn start(&self, client_id: &str, scope: &str) -> StartedDevice {
+        let now = Utc::now();
+        let device_code = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, device| device.expires_at > now);
+        let user_code = loop {
+            let candidate = user_code();
+            if !pending.values().any(|device| device.user_code == candidate) {
+                break candidate;
+            }
+        };
+        pending.insert(
+            device_code.clone(),
+            DeviceAuthorization {
+                user_code: user_code.clone(),
+                client_id: client_id.to_string(),
+                scope: scope.to_string(),
+                expires_at: now + self.ttl,
+                interval: self.interval,
+                last_poll: None,
+                decision: Decision::Pending,
+            },
+        );
+        StartedDevice { device_code, user_code, expires_in: self.ttl.num_seconds(), interval: self.interval }
+    }
+
+    /// The client and scope waiting on `user_code`, and when the code
+    /// expires, so the user sees what they are about to approve
+    pub(crate) fn describe(&self, user_code: &str) -> Result<(String, String, DateTime<Utc>), DeviceVerificationError> {
+        let user_code = normalize_user_code(user_code);
+        let now = Utc::now();
+        let pending = self.pending.lock().unwrap();
+        let device = pending
+            .values()
+            .find(|device| device.user_code == user_code && device.expires_at > now)
+            .ok_or(DeviceVerificationError::UnknownCode)?;
+        if !matches!(device.decision, Decision::Pending) {
+            return Err(DeviceVerificationError::AlreadyDecided);
+        }
+        Ok((device.client_id.clone(), device.scope.clone(), device.expires_at))
+    }
+
+    /// Approves the device behind `user_code` with `grant`, or denies it when
+    /// `grant` is `None`. The scope is the one the device asked for, whatever
+    /// `grant` says. Returns the client and scope the user decided on.
+    pub(crate) fn decide(&self, user_code: &str, grant: Option<Grant>) -> Result<(String, String), DeviceVerificationError> {
+        let user_code = normalize_user_code(user_code);
+        let now = Utc::now();
+        let mut pending = self.pending.lock().unwrap();
+        let device = pending
+            .values_mut()
+            .find(|device| device.user_code == user_code && device.expires_at > now)
+            .ok_or(DeviceVerificationError::UnknownCode)?;
+        if !matches!(device.decision, Decision::Pending) {
+            return Err(DeviceVerificationError::AlreadyDecided);
+        }
+        device.decision = match grant {
+            Some(grant) => Decision::Approved(grant),
+            None => Decision::Denied,
+        };
+        Ok((device.client_id.clone(), device.scope.clone()))
+    }
+
+    /// The grant once the user has approved the device, or the OAuth error
+    /// telling the device to keep polling, slow down or give up
+    pub(crate) fn poll(&self, device_code: &str, client_id: &str) -> Result<Grant, TokenError> {
+        let now = Utc::now();
+        let mut pending = self.pending.lock().unwrap();
+        let device = pending.get_mut(device_code).ok_or(TokenError::InvalidGrant("Unknown device_code"))?;
+        if device.client_id != client_id {
+            return Err(TokenError::InvalidGrant("device_code was issued to another client"));
+        }
+        if device.expires_at <= now {
+            pending.remove(device_code);
+            return Err(TokenError::ExpiredToken);
+        }
+        if matches!(device.decision, Decision::Pending) {
+            let too_fast = device.last_poll.is_some_and(|last| now - last < chrono::Duration::seconds(device.interval));
+            device.last_poll = Some(now);
+            if too_fast {
+                device.interval += SLOW_DOWN_STEP_SECS;
+                return Err(TokenError::SlowDown);
+            }
+            return Err(TokenError::AuthorizationPending);
+        }
+        match pending.remove(device_code) {
+            Some(DeviceAuthorization { decision: Decision::Approved(grant), scope, .. }) => Ok(Grant { scope, ..grant }),
+            _ => Err(TokenError::AccessDenied),
+        }
+    }
+}
+
+/// `XXXX-XXXX`, as shown to users
+pub fn format_user_code(user_code: &str) -> String {
+    let (first, second) = user_code.split_at(user_code.len() / 2);
+    format!("{first}-{second}")
+}
+
+/// Upper case, without the dash or anything else outside the alphabet
+fn normalize_user_code(input: &str) -> String {
+    input.chars().map(|c| c.to_ascii_uppercase()).filter(|c| c.is_ascii() && USER_CODE_ALPHABET.contains(&(*c as u8))).collect()
+}
+
+fn user_code() -> String {
+    // 256 is not a multiple of 20, which skews the letters by under 1%
+    Uuid::new_v4().as_bytes()[..USER_CODE_LEN]
+        .iter()
+        .map(|byte| USER_CODE_ALPHABET[*byte as usize % USER_CODE_ALPHABET.len()] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grant() -> Grant {
+        Grant {
+            user_id: Uuid::new_v4(),
+            roles: Vec::new(),
+            tenant: None,
+            scope: "openid".to_string(),
+            scopes: None,
+            nonce: None,
+            auth_time: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn devices_poll_until_the_user_decides_and_are_told_to_slow_down() {
+        let devices = DeviceGrants::new(chrono::Duration::minutes(10), 5);
+        let started = devices.start("cli", "openid");
+        assert_eq!(started.user_code.len(), USER_CODE_LEN);
+        assert_eq!(normalize_user_code(&format_user_code(&started.user_code).to_lowercase()), started.user_code);
+
+        assert!(matches!(devices.poll(&started.device_code, "cli"), Err(TokenError::AuthorizationPending)));
+        assert!(matches!(devices.poll(&started.device_code, "cli"), Err(TokenError::SlowDown)));
+        assert!(matches!(devices.poll(&started.device_code, "other"), Err(TokenError::InvalidGrant(_))));
+
+        let code = format_user_code(&started.user_code).to_lowercase();
+        let (client_id, scope, expires_at) = devices.describe(&code).unwrap();
+        assert_eq!((client_id.as_str(), scope.as_str()), ("cli", "openid"));
+        assert!(expires_at > Utc::now());
+        assert_eq!(devices.decide(&code, Some(grant())), Ok(("cli".to_string(), "openid".to_string())));
+        assert_eq!(devices.decide(&code, None), Err(DeviceVerificationError::AlreadyDecided));
+        assert_eq!(devices.describe(&code), Err(DeviceVerificationError::AlreadyDecided));
+        assert!(devices.poll(&started.device_code, "cli").is_ok());
+        assert!(matches!(devices.poll(&started.device_code, "cli"), Err(TokenError::InvalidGrant(_))));
+
+        let denied = devices.start("cli", "openid");
+        devices.decide(&denied.user_code, None).unwrap();
+        assert!(matches!(devices.poll(&denied.device_code, "cli"), Err(TokenError::AccessDenied)));
+        assert_eq!(devices.decide("BCDF-GHJK", None), Err(DeviceVerificationError::UnknownCode));
+        assert_eq!(devices.describe("BCDF-GHJK"), Err(DeviceVerificationError::UnknownCode));
+    }
+}
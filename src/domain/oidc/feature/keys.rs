@@ -0,0 +1,129 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SigningKeyError {
+    #[error("Failed to read or write signing key {path}: {source}")]
+    Io { path: String, source: std::io::Error },
+    #[error("Signing key {path} is not a PKCS#8 P-256 key: {reason}")]
+    Invalid { path: String, reason: String },
+}
+
+/// The P-256 key ID tokens are signed with (ES256), published at the JWKS
+/// endpoint under `kid`, the first 16 hex digits of the SHA-256 of its
+/// public point
+pub struct SigningKey {
+    pair: EcdsaKeyPair,
+    kid: String,
+    rng: SystemRandom,
+}
+
+impl SigningKey {
+    /// A new key that lives as long as the process; tokens it signed stop
+    /// verifying after a restart
+    pub fn ephemeral() -> Self {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).expect("system RNG is available");
+        Self::from_pkcs8(pkcs8.as_ref(), rng).expect("a generated key parses")
+    }
+
+    /// The PKCS#8 key at `path`, generated and written there (mode 0600 on
+    /// Unix) when the file does not exist yet
+    pub fn load_or_create(path: &Path) -> Result<Self, SigningKeyError> {
+        let shown = path.display().to_string();
+        let io = |source| SigningKeyError::Io { path: shown.clone(), source };
+        let rng = SystemRandom::new();
+        let pkcs8 = match std::fs::read(path) {
+            Ok(pkcs8) => pkcs8,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                let generated = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+                    .map_err(|_| SigningKeyError::Invalid { path: shown.clone(), reason: "key generation failed".to_string() })?;
+                if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+                    std::fs::create_dir_all(parent).map_err(io)?;
+                }
+                write_private(path, generated.as_ref()).map_err(io)?;
+                tracing::info!(path = %shown, "Generated OIDC signing key");
+                generated.as_ref().to_vec()
+            }
+            Err(err) => return Err(io(err)),
+        };
+        Self::from_pkcs8(&pkcs8, rng).map_err(|reason| SigningKeyError::Invalid { path: shown.clone(), reason })
+    }
+
+    fn from_pkcs8(pkcs8: &[u8], rng: SystemRandom) -> Result<Self, String> {
+        let pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8, &rng).map_err(|err| err.to_string())?;
+        let kid = hex::encode(&Sha256::digest(pair.public_key().as_ref())[..8]);
+        Ok(Self { pair, kid, rng })
+    }
+
+    pub fn kid(&self) -> &str {
+        &self.kid
+    }
+
+    /// A compact JWS of `claims`
+    pub fn sign_jwt(&self, claims: &Value) -> String {
+        let header = json!({"alg": "ES256", "typ": "JWT", "kid": self.kid});
+        let signing_input = format!("{}.{}", URL_SAFE_NO_PAD.encode(header.to_string()), URL_SAFE_NO_PAD.encode(claims.to_string()));
+        let signature = self.pair.sign(&self.rng, signing_input.as_bytes()).expect("ECDSA signing does not fail with a valid key");
+        format!("{signing_input}.{}", URL_SAFE_NO_PAD.encode(signature.as_ref()))
+    }
+
+    /// The public half as a JWK
+    pub fn jwk(&self) -> Value {
+        // Uncompressed point: 0x04, then x and y
+        let point = self.pair.public_key().as_ref();
+        json!({
+            "kty": "EC",
+            "crv": "P-256",
+            "alg": "ES256",
+            "use": "sig",
+            "kid": self.kid,
+            "x": URL_SAFE_NO_PAD.encode(&point[1..33]),
+            "y": URL_SAFE_NO_PAD.encode(&point[33..65]),
+        })
+    }
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+    std::fs::OpenOptions::new().write(true).create_new(true).mode(0o600).open(path)?.write_all(bytes)
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    std::fs::write(path, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_FIXED};
+
+    #[test]
+    fn tokens_verify_against_the_published_key_which_survives_reloading() {
+        let path = std::env::temp_dir().join(format!("oidc-key-{}/signing.p8", uuid::Uuid::new_v4()));
+        let key = SigningKey::load_or_create(&path).unwrap();
+        let token = key.sign_jwt(&json!({"sub": "user"}));
+
+        let (signing_input, signature) = token.rsplit_once('.').unwrap();
+        let jwk = key.jwk();
+        let mut point = vec![4];
+        point.extend(URL_SAFE_NO_PAD.decode(jwk["x"].as_str().unwrap()).unwrap());
+        point.extend(URL_SAFE_NO_PAD.decode(jwk["y"].as_str().unwrap()).unwrap());
+        let signature = URL_SAFE_NO_PAD.decode(signature).unwrap();
+        UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, &point).verify(signing_input.as_bytes(), &signature).unwrap();
+        let header: Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(token.split('.').next().unwrap()).unwrap()).unwrap();
+        assert_eq!((header["alg"].as_str(), header["kid"].as_str()), (Some("ES256"), Some(key.kid())));
+
+        let reloaded = SigningKey::load_or_create(&path).unwrap();
+        assert_eq!(reloaded.jwk(), jwk);
+        assert_ne!(SigningKey::ephemeral().kid(), key.kid());
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,7 @@
+pub mod keys;
+pub mod provider;
+pub mod device;
+
+pub use keys::*;
+pub use provider::*;
+pub use device::*;
@@ -0,0 +1,636 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use reqwest::Url;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use super::device::{format_user_code, DeviceGrants, DeviceVerificationError};
+use super::keys::SigningKey;
+use crate::domain::oidc::model::{AuthorizeRequest, DeviceAuthorizationRequest, DeviceAuthorizationResponse, DiscoveryDocument, PendingDeviceResponse, TokenRequest, TokenResponse};
+use crate::domain::session::entities::{requested_scopes, Principal, Scope, Session, SessionKind, ADMIN_ROLE};
+use crate::domain::session::feature::SESSION_TOKEN_PREFIX;
+use crate::domain::session::repository::{SessionStore, SessionStoreError};
+use crate::domain::user::feature::{ServiceError, UserService};
+
+/// Requests the client cannot be trusted with an error redirect for: the
+/// error is shown to the user instead
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum AuthorizeError {
+    #[error("Unknown client_id")]
+    UnknownClient,
+    #[error("redirect_uri is missing or not registered for this client")]
+    UnregisteredRedirect,
+}
+
+/// `grant_type` of device code token requests (RFC 8628)
+pub const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Why a token or device authorization request was refused; `code` is the
+/// OAuth `error`
+#[derive(Debug, thiserror::Error)]
+pub enum TokenError {
+    #[error("Only the authorization_code and device_code grants are supported")]
+    UnsupportedGrantType,
+    #[error("{0} is required")]
+    MissingParameter(&'static str),
+    #[error("Unknown client_id, or not registered for this grant")]
+    InvalidClient,
+    #[error("{0}")]
+    InvalidGrant(&'static str),
+    #[error("scope must include openid")]
+    InvalidScope,
+    #[error("The user has not approved the device yet")]
+    AuthorizationPending,
+    #[error("Polling too often; wait longer between requests")]
+    SlowDown,
+    #[error("The user denied the device")]
+    AccessDenied,
+    #[error("The device code has expired; start again")]
+    ExpiredToken,
+    #[error("User lookup failed: {0}")]
+    Users(#[from] ServiceError),
+    #[error(transparent)]
+    Store(#[from] SessionStoreError),
+}
+
+impl TokenError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            TokenError::UnsupportedGrantType => "unsupported_grant_type",
+            TokenError::MissingParameter(_) => "invalid_request",
+            TokenError::InvalidClient => "invalid_client",
+            TokenError::InvalidGrant(_) => "invalid_grant",
+            TokenError::InvalidScope => "invalid_scope",
+            TokenError::AuthorizationPending => "authorization_pending",
+            TokenError::SlowDown => "slow_down",
+            TokenError::AccessDenied => "access_denied",
+            TokenError::ExpiredToken => "expired_token",
+            TokenError::Users(_) | TokenError::Store(_) => "server_error",
+        }
+    }
+}
+
+/// What a user agreed to: tokens for their account with these roles, tenant
+/// and scope, redeemed by a code or a device. The `admin` role is never
+/// passed on, so no client is handed a token that opens admin routes.
+pub(crate) struct Grant {
+    pub user_id: Uuid,
+    pub roles: Vec<String>,
+    pub tenant: Option<String>,
+    pub scope: String,
+    /// Those of the approving session, which issued tokens cannot exceed
+    pub scopes: Option<Vec<String>>,
+    pub nonce: Option<String>,
+    /// When the approving session signed in
+    pub auth_time: DateTime<Utc>,
+}
+
+impl Grant {
+    fn from_principal(principal: &Principal, scope: &str, nonce: Option<String>) -> Self {
+        Self {
+            user_id: principal.user_id,
+            roles: principal.roles.iter().filter(|role| *role != ADMIN_ROLE).cloned().collect(),
+            tenant: principal.tenant.clone(),
+            scope: scope.to_string(),
+            scopes: principal.scopes.clone(),
+            nonce,
+            auth_time: principal.claims.issued_at,
+        }
+    }
+}
+
+/// What an authorization code stands for until it is redeemed
+struct PendingCode {
+    client_id: String,
+    redirect_uri: String,
+    code_challenge: String,
+    grant: Grant,
+    expires_at: DateTime<Utc>,
+}
+
+/// A minimal OpenID provider for the service's own apps: the authorization
+/// code flow with S256 PKCE for public clients, registered in `OIDC_CLIENTS`
+/// with their exact redirect URIs, and the device authorization grant for
+/// command-line clients in `OIDC_DEVICE_CLIENTS`. Users authorize with their
+/// existing session; the access token is a new session of that user, and the
+/// ID token is an ES256 JWT verifiable with the JWKS. Codes are single-use
+/// and live in this process only.
+pub struct OidcProvider {
+    issuer: String,
+    clients: HashMap<String, Vec<String>>,
+    device_clients: Vec<String>,
+    devices: DeviceGrants,
+    key: SigningKey,
+    sessions: Arc<dyn SessionStore>,
+    users: Arc<dyn UserService>,
+    codes: Mutex<HashMap<String, PendingCode>>,
+    code_ttl: chrono::Duration,
+    token_ttl: chrono::Duration,
+}
+
+impl OidcProvider {
+    /// `clients` is `client_id=redirect_uri|redirect_uri,...`; with none, and
+    /// no device clients either, the provider is disabled
+    pub fn new(issuer: &str, clients: &str, key: SigningKey, sessions: Arc<dyn SessionStore>, users: Arc<dyn UserService>) -> Self {
+        Self {
+            issuer: issuer.trim_end_matches('/').to_string(),
+            clients: parse_clients(clients),
+            device_clients: Vec::new(),
+            devices: DeviceGrants::new(chrono::Duration::minutes(10), 5),
+            key,
+            sessions,
+            users,
+            codes: Mutex::new(HashMap::new()),
+            code_ttl: chrono::Duration::seconds(60),
+            token_ttl: chrono::Duration::hours(1),
+        }
+    }
+
+    pub fn with_code_ttl(mut self, ttl: chrono::Duration) -> Self {
+        self.code_ttl = ttl;
+        self
+    }
+
+    pub fn with_token_ttl(mut self, ttl: chrono::Duration) -> Self {
+        self.token_ttl = ttl;
+        self
+    }
+
+    /// Clients allowed the device grant, comma-separated; they need no
+    /// redirect URI. Device codes expire after `ttl` and are polled for
+    /// every `interval_secs` at most.
+    pub fn with_device_clients(mut self, clients: &str, ttl: chrono::Duration, interval_secs: i64) -> Self {
+        self.device_clients = clients.split(',').map(str::trim).filter(|client| !client.is_empty()).map(String::from).collect();
+        self.devices = DeviceGrants::new(ttl, interval_secs);
+        self
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.clients.is_empty() || !self.device_clients.is_empty()
+    }
+
+    pub fn discovery(&self) -> DiscoveryDocument {
+        DiscoveryDocument {
+            issuer: self.issuer.clone(),
+            authorization_endpoint: format!("{}/authorize", self.issuer),
+            token_endpoint: format!("{}/token", self.issuer),
+            jwks_uri: format!("{}/jwks", self.issuer),
+            device_authorization_endpoint: format!("{}/device_authorization", self.issuer),
+            response_types_supported: ["code"],
+            grant_types_supported: ["authorization_code", DEVICE_CODE_GRANT],
+            subject_types_supported: ["public"],
+            id_token_signing_alg_values_supported: ["ES256"],
+            scopes_supported: ["openid", "email"]
+                .into_iter()
+                .chain(Scope::ALL.into_iter().filter(|scope| scope.user_grantable()).map(Scope::name))
+                .collect(),
+            token_endpoint_auth_methods_supported: ["none"],
+            code_challenge_methods_supported: ["S256"],
+        }
+    }
+
+    pub fn jwks(&self) -> Value {
+        json!({ "keys": [self.key.jwk()] })
+    }
+
+    /// Where to send the user next: the client's redirect URI with a `code`,
+    /// or with an OAuth `error` when the request or the session is refused.
+    /// `principal` is `None` for callers without a session.
+    pub fn authorize(&self, request: &AuthorizeRequest, principal: Option<&Principal>) -> Result<Url, AuthorizeError> {
+        let client_id = request.client_id.as_deref().unwrap_or_default();
+        let registered = self.clients.get(client_id).ok_or(AuthorizeError::UnknownClient)?;
+        let redirect_uri = request
+            .redirect_uri
+            .as_deref()
+            .filter(|uri| registered.iter().any(|registered| registered == uri))
+            .ok_or(AuthorizeError::UnregisteredRedirect)?;
+        let mut url = Url::parse(redirect_uri).map_err(|_| AuthorizeError::UnregisteredRedirect)?;
+
+        match self.grant(request, client_id, redirect_uri, principal) {
+            Ok(code) => url.query_pairs_mut().append_pair("code", &code),
+            Err((error, description)) => url.query_pairs_mut().append_pair("error", error).append_pair("error_description", description),
+        };
+        if let Some(state) = &request.state {
+            url.query_pairs_mut().append_pair("state", state);
+        }
+        url.query_pairs_mut().append_pair("iss", &self.issuer);
+        Ok(url)
+    }
+
+    /// A new code, or the OAuth `error` and its description
+    fn grant(
+        &self,
+        request: &AuthorizeRequest,
+        client_id: &str,
+        redirect_uri: &str,
+        principal: Option<&Principal>,
+    ) -> Result<String, (&'static str, &'static str)> {
+        if request.response_type.as_deref() != Some("code") {
+            return Err(("unsupported_response_type", "Only response_type=code is supported"));
+        }
+        let scope = request.scope.as_deref().unwrap_or_default();
+        if !has_scope(scope, "openid") {
+            return Err(("invalid_scope", "scope must include openid"));
+        }
+        let code_challenge = match (request.code_challenge.as_deref(), request.code_challenge_method.as_deref()) {
+            (Some(challenge), Some("S256")) if !challenge.is_empty() => challenge,
+            _ => return Err(("invalid_request", "PKCE with code_challenge_method=S256 is required")),
+        };
+        let principal = principal.ok_or(("login_required", "Sign in first, then retry the authorization request"))?;
+        if principal.is_impersonated() {
+            return Err(("access_denied", "Impersonated sessions cannot sign in to other apps"));
+        }
+
+        let now = Utc::now();
+        let code = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let pending = PendingCode {
+            client_id: client_id.to_string(),
+            redirect_uri: redirect_uri.to_string(),
+            code_challenge: code_challenge.to_string(),
+            grant: Grant::from_principal(principal, scope, request.nonce.clone()),
+            expires_at: now + self.code_ttl,
+        };
+        let mut codes = self.codes.lock().unwrap();
+        codes.retain(|_, pending| pending.expires_at > now);
+        codes.insert(code.clone(), pending);
+        tracing::info!(client_id, user_id = %principal.user_id, "OIDC authorization code issued");
+        Ok(code)
+    }
+
+    /// Starts a device authorization: the device shows the user code and
+    /// the verification URI, then polls the token endpoint
+    pub fn start_device(&self, request: &DeviceAuthorizationRequest) -> Result<DeviceAuthorizationResponse, TokenError> {
+        let client_id = request.client_id.as_deref().ok_or(TokenError::MissingParameter("client_id"))?;
+        if !self.device_clients.iter().any(|client| client == client_id) {
+            return Err(TokenError::InvalidClient);
+        }
+        let scope = request.scope.as_deref().unwrap_or("openid");
+        if !has_scope(scope, "openid") {
+            return Err(TokenError::InvalidScope);
+        }
+        let started = self.devices.start(client_id, scope);
+        let user_code = format_user_code(&started.user_code);
+        let verification_uri = format!("{}/device", self.issuer);
+        tracing::info!(client_id, "OIDC device authorization started");
+        Ok(DeviceAuthorizationResponse {
+            device_code: started.device_code,
+            verification_uri_complete: format!("{verification_uri}?user_code={user_code}"),
+            user_code,
+            verification_uri,
+            expires_in: started.expires_in,
+            interval: started.interval,
+        })
+    }
+
+    /// The client and scopes behind `user_code`, for the verification page
+    /// to show before the user decides
+    pub fn describe_device(&self, user_code: &str) -> Result<PendingDeviceResponse, DeviceVerificationError> {
+        let (client_id, scope, expires_at) = self.devices.describe(user_code)?;
+        Ok(PendingDeviceResponse { client_id, scope, expires_at })
+    }
+
+    /// The signed-in user approves or denies the device showing `user_code`.
+    /// Returns the client and scope decided on.
+    pub fn verify_device(&self, user_code: &str, principal: &Principal, approve: bool) -> Result<(String, String), DeviceVerificationError> {
+        if principal.is_impersonated() {
+            return Err(DeviceVerificationError::Impersonated);
+        }
+        let grant = approve.then(|| Grant::from_principal(principal, "", None));
+        let (client_id, scope) = self.devices.decide(user_code, grant)?;
+        tracing::info!(target: "audit", audit_event = "oidc_device_decided", client_id = %client_id, user_id = %principal.user_id, approved = approve, "OIDC device authorization decided");
+        Ok((client_id, scope))
+    }
+
+    /// Redeems an authorization code, or polls for a device authorization.
+    /// A code is spent by the first attempt, whether or not it succeeds.
+    pub async fn exchange(&self, request: &TokenRequest) -> Result<TokenResponse, TokenError> {
+        match request.grant_type.as_deref() {
+            Some("authorization_code") => self.redeem_code(request).await,
+            Some(DEVICE_CODE_GRANT) => {
+                let device_code = request.device_code.as_deref().ok_or(TokenError::MissingParameter("device_code"))?;
+                let client_id = request.client_id.as_deref().ok_or(TokenError::MissingParameter("client_id"))?;
+                if !self.device_clients.iter().any(|client| client == client_id) {
+                    return Err(TokenError::InvalidClient);
+                }
+                let grant = self.devices.poll(device_code, client_id)?;
+                self.issue(client_id, grant).await
+            }
+            _ => Err(TokenError::UnsupportedGrantType),
+        }
+    }
+
+    async fn redeem_code(&self, request: &TokenRequest) -> Result<TokenResponse, TokenError> {
+        let code = request.code.as_deref().ok_or(TokenError::MissingParameter("code"))?;
+        let client_id = request.client_id.as_deref().ok_or(TokenError::MissingParameter("client_id"))?;
+        let verifier = request.code_verifier.as_deref().ok_or(TokenError::MissingParameter("code_verifier"))?;
+        if !self.clients.contains_key(client_id) {
+            return Err(TokenError::InvalidClient);
+        }
+        let pending = self.codes.lock().unwrap().remove(code).ok_or(TokenError::InvalidGrant("Unknown or already used code"))?;
+        if pending.expires_at <= Utc::now() {
+            return Err(TokenError::InvalidGrant("Code has expired"));
+        }
+        if pending.client_id != client_id || request.redirect_uri.as_deref() != Some(pending.redirect_uri.as_str()) {
+            return Err(TokenError::InvalidGrant("Code was issued to another client or redirect_uri"));
+        }
+        if URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())) != pending.code_challenge {
+            return Err(TokenError::InvalidGrant("code_verifier does not match code_challenge"));
+        }
+        self.issue(client_id, pending.grant).await
+    }
+
+    /// A new session for the user, and an ID token naming it. API scopes in
+    /// the request, e.g. `users:read`, limit the session to them; without
+    /// any it holds every scope the approving session did.
+    async fn issue(&self, client_id: &str, grant: Grant) -> Result<TokenResponse, TokenError> {
+        let user = self.users.get_user_by_id(grant.user_id).await?.ok_or(TokenError::InvalidGrant("User no longer exists"))?;
+
+        let mut session = Session::new(grant.user_id, SessionKind::Session, self.token_ttl);
+        session.device = Some(format!("{client_id} (OpenID Connect)"));
+        session.roles = grant.roles;
+        session.tenant = grant.tenant;
+        let requested = requested_scopes(&grant.scope);
+        session.scopes = if requested.is_empty() {
+            grant.scopes
+        } else {
+            let allowed = |scope: &Scope| grant.scopes.as_ref().is_none_or(|granted| granted.iter().any(|granted| scope.granted_by(granted)));
+            Some(requested.into_iter().filter(allowed).map(|scope| scope.name().to_string()).collect())
+        };
+        let access_token = session.issue_token(SESSION_TOKEN_PREFIX);
+        self.sessions.save(session.clone()).await?;
+
+        let mut claims = json!({
+            "iss": self.issuer,
+            "sub": grant.user_id,
+            "aud": client_id,
+            "iat": session.created_at.timestamp(),
+            "exp": session.expires_at.timestamp(),
+            "auth_time": grant.auth_time.timestamp(),
+            "sid": session.id,
+        });
+        if let Some(nonce) = grant.nonce {
+            claims["nonce"] = json!(nonce);
+        }
+        if has_scope(&grant.scope, "email") {
+            claims["email"] = json!(user.email());
+        }
+        let scope = match &session.scopes {
+            Some(scopes) => ["openid", "email"]
+                .into_iter()
+                .filter(|oidc| has_scope(&grant.scope, oidc))
+                .chain(scopes.iter().map(String::as_str))
+                .collect::<Vec<_>>()
+                .join(" "),
+            None => grant.scope,
+        };
+        tracing::info!(target: "audit", audit_event = "oidc_token_issued", client_id, user_id = %grant.user_id, session_id = %session.id, "OIDC tokens issued");
+        Ok(TokenResponse {
+            access_token,
+            token_type: "Bearer",
+            expires_in: self.token_ttl.num_seconds(),
+            id_token: self.key.sign_jwt(&claims),
+            scope,
+        })
+    }
+}
+
+fn has_scope(scope: &str, wanted: &str) -> bool {
+    scope.split(' ').any(|scope| scope == wanted)
+}
+
+/// `client=uri|uri,client=uri`; entries without a redirect URI are dropped
+fn parse_clients(spec: &str) -> HashMap<String, Vec<String>> {
+    spec.split(',')
+        .filter_map(|entry| {
+            let (client, uris) = entry.split_once('=')?;
+            let uris: Vec<String> = uris.split('|').map(str::trim).filter(|uri| !uri.is_empty()).map(String::from).collect();
+            (!client.trim().is_empty() && !uris.is_empty()).then(|| (client.trim().to_string(), uris))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delivery::fake_principal;
+    use crate::domain::session::entities::hash_token;
+    use crate::domain::session::repository::InMemorySessionStore;
+    use crate::domain::user::feature::UserServiceImpl;
+    use crate::domain::user::model::CreateUserRequest;
+    use crate::domain::user::repository::InMemoryUserRepository;
+    use crate::infrastructure::CpuPool;
+
+    const REDIRECT: &str = "https://app.example.com/callback";
+    const VERIFIER: &str = "dBjftJeZ4CVP-mJ92K9PxqFAxLW2wU5Eg1Z3QV7ghQ0";
+
+    fn authorize_request() -> AuthorizeRequest {
+        AuthorizeRequest {
+            response_type: Some("code".to_string()),
+            client_id: Some("web".to_string()),
+            redirect_uri: Some(REDIRECT.to_string()),
+            scope: Some("openid email".to_string()),
+            state: Some("xyz".to_string()),
+            nonce: Some("n-1".to_string()),
+            code_challenge: Some(URL_SAFE_NO_PAD.encode(Sha256::digest(VERIFIER))),
+            code_challenge_method: Some("S256".to_string()),
+        }
+    }
+
+    fn param(url: &Url, name: &str) -> Option<String> {
+        url.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.into_owned())
+    }
+
+    #[tokio::test]
+    async fn codes_redeem_once_for_a_session_token_and_a_signed_id_token() {
+        let sessions: Arc<dyn SessionStore> = Arc::new(InMemorySessionStore::new());
+        let users: Arc<dyn UserService> = Arc::new(UserServiceImpl::new(Arc::new(InMemoryUserRepository::new()), CpuPool::shared_for_tests()));
+        let user = users
+            .create_user(CreateUserRequest { email: "ada@example.com".to_string(), password: "correct horse battery".to_string(), metadata: None })
+            .await
+            .unwrap();
+        let provider = OidcProvider::new("https://id.example.com/api/oidc/", &format!("web={REDIRECT}"), SigningKey::ephemeral(), sessions.clone(), users);
+        let principal = fake_principal(user.id(), &["editor"]);
+
+        assert_eq!(provider.authorize(&AuthorizeRequest::default(), Some(&principal)), Err(AuthorizeError::UnknownClient));
+        let mut elsewhere = authorize_request();
+        elsewhere.redirect_uri = Some("https://evil.example.com/callback".to_string());
+        assert_eq!(provider.authorize(&elsewhere, Some(&principal)), Err(AuthorizeError::UnregisteredRedirect));
+        let anonymous = provider.authorize(&authorize_request(), None).unwrap();
+        assert_eq!((param(&anonymous, "error").as_deref(), param(&anonymous, "state").as_deref()), (Some("login_required"), Some("xyz")));
+
+        let redirect = provider.authorize(&authorize_request(), Some(&principal)).unwrap();
+        assert!(redirect.as_str().starts_with(REDIRECT));
+        let code = param(&redirect, "code").unwrap();
+        let token_request = |verifier: &str| TokenRequest {
+            grant_type: Some("authorization_code".to_string()),
+            code: Some(code.clone()),
+            redirect_uri: Some(REDIRECT.to_string()),
+            client_id: Some("web".to_string()),
+            code_verifier: Some(verifier.to_string()),
+            ..TokenRequest::default()
+        };
+
+        let tokens = provider.exchange(&token_request(VERIFIER)).await.unwrap();
+        let session = sessions.find_by_token_hash(&hash_token(&tokens.access_token)).await.unwrap().unwrap();
+        assert_eq!((session.user_id, session.roles.as_slice()), (user.id(), ["editor".to_string()].as_slice()));
+        let claims = tokens.id_token.split('.').nth(1).unwrap();
+        let claims: Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(claims).unwrap()).unwrap();
+        assert_eq!(claims["iss"], "https://id.example.com/api/oidc");
+        assert_eq!((claims["aud"].as_str(), claims["nonce"].as_str(), claims["email"].as_str()), (Some("web"), Some("n-1"), Some("ada@example.com")));
+        assert_eq!(claims["sub"], json!(user.id()));
+
+        let replayed = provider.exchange(&token_request(VERIFIER)).await.unwrap_err();
+        assert_eq!(replayed.code(), "invalid_grant");
+        let redirect = provider.authorize(&authorize_request(), Some(&principal)).unwrap();
+        let code = param(&redirect, "code").unwrap();
+        let mut wrong_verifier = token_request("not-the-verifier");
+        wrong_verifier.code = Some(code);
+        assert!(matches!(provider.exchange(&wrong_verifier).await, Err(TokenError::InvalidGrant(_))));
+    }
+
+    #[tokio::test]
+    async fn tokens_approved_by_an_admin_never_open_admin_routes() {
+        use crate::infrastructure::{LaneLimiter, RateLimiter};
+        use crate::middleware::{auth_middleware, route_policy_middleware, Ownership, PolicyState, RoutePolicy};
+        use axum::{body::Body, http::{header, Request, StatusCode}, routing::get, Router};
+        use tower::ServiceExt;
+
+        let sessions: Arc<dyn SessionStore> = Arc::new(InMemorySessionStore::new());
+        let users: Arc<dyn UserService> = Arc::new(UserServiceImpl::new(Arc::new(InMemoryUserRepository::new()), CpuPool::shared_for_tests()));
+        let user = users
+            .create_user(CreateUserRequest { email: "ada@example.com".to_string(), password: "correct horse battery".to_string(), metadata: None })
+            .await
+            .unwrap();
+        let provider = OidcProvider::new("https://id.example.com/api/oidc/", &format!("web={REDIRECT}"), SigningKey::ephemeral(), sessions.clone(), users);
+        let mut admin = Session::new(user.id(), SessionKind::Session, chrono::Duration::minutes(1));
+        admin.roles = vec![ADMIN_ROLE.to_string(), "editor".to_string()];
+        let admin_token = admin.issue_token(SESSION_TOKEN_PREFIX);
+        sessions.save(admin.clone()).await.unwrap();
+
+        let redirect = provider.authorize(&authorize_request(), Some(&Principal::from_session(&admin))).unwrap();
+        let tokens = provider
+            .exchange(&TokenRequest {
+                grant_type: Some("authorization_code".to_string()),
+                code: param(&redirect, "code"),
+                redirect_uri: Some(REDIRECT.to_string()),
+                client_id: Some("web".to_string()),
+                code_verifier: Some(VERIFIER.to_string()),
+                ..TokenRequest::default()
+            })
+            .await
+            .unwrap();
+        let session = sessions.find_by_token_hash(&hash_token(&tokens.access_token)).await.unwrap().unwrap();
+        assert_eq!(session.roles, ["editor".to_string()]);
+
+        let policy = Arc::new(PolicyState {
+            policy: RoutePolicy::public().admin(),
+            admin_token: Arc::from("secret"),
+            limiter: Arc::new(RateLimiter::new()),
+            lanes: Arc::new(LaneLimiter::new(0, 100, 100)),
+            ownership: Arc::new(Ownership::default()),
+        });
+        let app = Router::new()
+            .route("/admin", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(policy, route_policy_middleware))
+            .layer(axum::middleware::from_fn_with_state(sessions, auth_middleware));
+        let call = |token: &str| Request::get("/admin").header(header::AUTHORIZATION, format!("Bearer {token}")).body(Body::empty()).unwrap();
+        assert_eq!(app.clone().oneshot(call(&admin_token)).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(app.oneshot(call(&tokens.access_token)).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn api_scopes_limit_tokens_to_what_the_approving_session_holds() {
+        let sessions: Arc<dyn SessionStore> = Arc::new(InMemorySessionStore::new());
+        let users: Arc<dyn UserService> = Arc::new(UserServiceImpl::new(Arc::new(InMemoryUserRepository::new()), CpuPool::shared_for_tests()));
+        let user = users
+            .create_user(CreateUserRequest { email: "ada@example.com".to_string(), password: "correct horse battery".to_string(), metadata: None })
+            .await
+            .unwrap();
+        let provider = OidcProvider::new("https://id.example.com/api/oidc", &format!("web={REDIRECT}"), SigningKey::ephemeral(), sessions.clone(), users);
+        let redeem = |principal: Principal, scope: &str| {
+            let mut request = authorize_request();
+            request.scope = Some(scope.to_string());
+            let code = param(&provider.authorize(&request, Some(&principal)).unwrap(), "code");
+            let provider = &provider;
+            async move {
+                let token_request = TokenRequest {
+                    grant_type: Some("authorization_code".to_string()),
+                    code,
+                    redirect_uri: Some(REDIRECT.to_string()),
+                    client_id: Some("web".to_string()),
+                    code_verifier: Some(VERIFIER.to_string()),
+                    ..TokenRequest::default()
+                };
+                provider.exchange(&token_request).await.unwrap()
+            }
+        };
+
+        let tokens = redeem(fake_principal(user.id(), &[]), "openid users:* admin:* products:read").await;
+        assert_eq!(tokens.scope, "openid users:read users:write products:read");
+        let session = sessions.find_by_token_hash(&hash_token(&tokens.access_token)).await.unwrap().unwrap();
+        let scoped = Principal::from_session(&session);
+        assert!(scoped.allows(Scope::UsersWrite) && !scoped.allows(Scope::EventsRead) && !scoped.allows(Scope::AdminRead));
+
+        let narrowed = redeem(scoped.clone(), "openid email users:write events:read").await;
+        assert_eq!(narrowed.scope, "openid email users:write");
+        let inherited = redeem(scoped, "openid").await;
+        assert_eq!(inherited.scope, "openid users:read users:write products:read");
+        assert_eq!(redeem(fake_principal(user.id(), &[]), "openid").await.scope, "openid");
+    }
+
+    #[tokio::test]
+    async fn devices_get_tokens_once_the_user_approves_their_code() {
+        let sessions: Arc<dyn SessionStore> = Arc::new(InMemorySessionStore::new());
+        let users: Arc<dyn UserService> = Arc::new(UserServiceImpl::new(Arc::new(InMemoryUserRepository::new()), CpuPool::shared_for_tests()));
+        let user = users
+            .create_user(CreateUserRequest { email: "ada@example.com".to_string(), password: "correct horse battery".to_string(), metadata: None })
+            .await
+            .unwrap();
+        let provider = OidcProvider::new("https://id.example.com/api/oidc", "", SigningKey::ephemeral(), sessions.clone(), users)
+            .with_device_clients("cli", chrono::Duration::minutes(10), 5);
+        assert!(provider.enabled());
+
+        let unknown = DeviceAuthorizationRequest { client_id: Some("web".to_string()), scope: None };
+        assert!(matches!(provider.start_device(&unknown), Err(TokenError::InvalidClient)));
+        let started = provider
+            .start_device(&DeviceAuthorizationRequest { client_id: Some("cli".to_string()), scope: Some("openid email".to_string()) })
+            .unwrap();
+        assert_eq!(started.verification_uri, "https://id.example.com/api/oidc/device");
+        assert!(started.verification_uri_complete.ends_with(&format!("?user_code={}", started.user_code)));
+
+        let poll = TokenRequest {
+            grant_type: Some(DEVICE_CODE_GRANT.to_string()),
+            client_id: Some("cli".to_string()),
+            device_code: Some(started.device_code.clone()),
+            ..TokenRequest::default()
+        };
+        assert_eq!(provider.exchange(&poll).await.unwrap_err().code(), "authorization_pending");
+
+        let pending = provider.describe_device(&started.user_code).unwrap();
+        assert_eq!((pending.client_id.as_str(), pending.scope.as_str()), ("cli", "openid email"));
+
+        let mut impersonated = fake_principal(user.id(), &[]);
+        impersonated.claims.impersonated_by = Some("support".to_string());
+        assert_eq!(provider.verify_device(&started.user_code, &impersonated, true), Err(DeviceVerificationError::Impersonated));
+        let decided = provider.verify_device(&started.user_code, &fake_principal(user.id(), &[]), true).unwrap();
+        assert_eq!(decided, ("cli".to_string(), "openid email".to_string()));
+
+        let tokens = provider.exchange(&poll).await.unwrap();
+        assert_eq!(tokens.scope, "openid email");
+        let session = sessions.find_by_token_hash(&hash_token(&tokens.access_token)).await.unwrap().unwrap();
+        assert_eq!(session.user_id, user.id());
+        assert_eq!(provider.exchange(&poll).await.unwrap_err().code(), "invalid_grant");
+    }
+
+    #[test]
+    fn clients_list_their_exact_redirect_uris() {
+        let clients = parse_clients("web=https://a.example/cb|https://b.example/cb, cli=http://127.0.0.1:8765/cb,broken=,=x");
+        assert_eq!(clients.len(), 2);
+        assert_eq!(clients["web"], ["https://a.example/cb", "https://b.example/cb"]);
+        assert_eq!(clients["cli"], ["http://127.0.0.1:8765/cb"]);
+        assert!(parse_clients("").is_empty());
+    }
+}
@@ -0,0 +1,118 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Redirect, Response},
+    Form, Json,
+};
+use serde_json::json;
+use std::sync::Arc;
+
+use super::feature::{DeviceVerificationError, OidcProvider, TokenError};
+use super::model::{AuthorizeRequest, DeviceAuthorizationRequest, DeviceVerificationRequest, DeviceVerificationResponse, TokenRequest};
+use crate::delivery::{AuthUser, FastJson, MaybeAuthUser};
+use crate::response::{error_response, not_found_response, success_response};
+
+/// OpenID clients read these bare, so discovery, keys and tokens are not
+/// wrapped in the response envelope
+pub async fn openid_configuration(State(provider): State<Arc<OidcProvider>>) -> Response {
+    if !provider.enabled() {
+        return not_found_response("OpenID provider").into_response();
+    }
+    Json(provider.discovery()).into_response()
+}
+
+pub async fn jwks(State(provider): State<Arc<OidcProvider>>) -> Response {
+    if !provider.enabled() {
+        return not_found_response("OpenID provider").into_response();
+    }
+    Json(provider.jwks()).into_response()
+}
+
+/// Sends the browser back to the client with a code for the caller's
+/// session, or with `error=login_required` when there is none, so the app
+/// can show its sign-in page and retry
+pub async fn authorize(
+    State(provider): State<Arc<OidcProvider>>,
+    MaybeAuthUser(principal): MaybeAuthUser,
+    Query(request): Query<AuthorizeRequest>,
+) -> Response {
+    if !provider.enabled() {
+        return not_found_response("OpenID provider").into_response();
+    }
+    match provider.authorize(&request, principal.as_ref()) {
+        Ok(url) => Redirect::to(url.as_str()).into_response(),
+        Err(err) => error_response(StatusCode::BAD_REQUEST, "INVALID_AUTHORIZATION_REQUEST", err.to_string()).into_response(),
+    }
+}
+
+/// Errors are RFC 6749 error bodies, `{"error", "error_description"}`;
+/// devices polling for approval get `authorization_pending` until then
+pub async fn token(State(provider): State<Arc<OidcProvider>>, Form(request): Form<TokenRequest>) -> Response {
+    if !provider.enabled() {
+        return not_found_response("OpenID provider").into_response();
+    }
+    match provider.exchange(&request).await {
+        Ok(tokens) => Json(tokens).into_response(),
+        Err(err) => oauth_error(err),
+    }
+}
+
+/// A user code for the device to show, and the device code it polls with
+pub async fn device_authorization(State(provider): State<Arc<OidcProvider>>, Form(request): Form<DeviceAuthorizationRequest>) -> Response {
+    if !provider.enabled() {
+        return not_found_response("OpenID provider").into_response();
+    }
+    match provider.start_device(&request) {
+        Ok(started) => Json(started).into_response(),
+        Err(err) => oauth_error(err),
+    }
+}
+
+/// What the device showing `user_code` asks for; the verification page
+/// shows it before offering to approve
+pub async fn describe_device(State(provider): State<Arc<OidcProvider>>, AuthUser(_): AuthUser, Path(user_code): Path<String>) -> Response {
+    if !provider.enabled() {
+        return not_found_response("OpenID provider").into_response();
+    }
+    match provider.describe_device(&user_code) {
+        Ok(pending) => success_response(pending).into_response(),
+        Err(err) => verification_error(err),
+    }
+}
+
+/// The signed-in user approves or denies a device by its user code; called
+/// by the verification page
+pub async fn verify_device(
+    State(provider): State<Arc<OidcProvider>>,
+    AuthUser(principal): AuthUser,
+    FastJson(payload): FastJson<DeviceVerificationRequest>,
+) -> Response {
+    if !provider.enabled() {
+        return not_found_response("OpenID provider").into_response();
+    }
+    match provider.verify_device(&payload.user_code, &principal, payload.approve) {
+        Ok((client_id, scope)) => success_response(DeviceVerificationResponse { client_id, scope, approved: payload.approve }).into_response(),
+        Err(err) => verification_error(err),
+    }
+}
+
+fn verification_error(err: DeviceVerificationError) -> Response {
+    let status = match err {
+        DeviceVerificationError::UnknownCode => StatusCode::NOT_FOUND,
+        DeviceVerificationError::AlreadyDecided => StatusCode::CONFLICT,
+        DeviceVerificationError::Impersonated => StatusCode::FORBIDDEN,
+    };
+    error_response(status, err.code(), err.to_string()).into_response()
+}
+
+fn oauth_error(err: TokenError) -> Response {
+    let status = match &err {
+        TokenError::InvalidClient => StatusCode::UNAUTHORIZED,
+        TokenError::Users(_) | TokenError::Store(_) => {
+            tracing::error!(error = %err, "OIDC token request failed");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+        _ => StatusCode::BAD_REQUEST,
+    };
+    (status, Json(json!({"error": err.code(), "error_description": err.to_string()}))).into_response()
+}
@@ -0,0 +1,45 @@
+use serde::Deserialize;
+
+/// Query of `GET /api/oidc/authorize`; only the authorization code flow
+/// with S256 PKCE is supported
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuthorizeRequest {
+    pub response_type: Option<String>,
+    pub client_id: Option<String>,
+    pub redirect_uri: Option<String>,
+    /// Must include `openid`; `email` adds the email claim to the ID token
+    pub scope: Option<String>,
+    /// Returned to the client unchanged
+    pub state: Option<String>,
+    pub nonce: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+}
+
+/// Form body of `POST /api/oidc/token`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TokenRequest {
+    pub grant_type: Option<String>,
+    pub code: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub client_id: Option<String>,
+    pub code_verifier: Option<String>,
+    /// For the device code grant
+    pub device_code: Option<String>,
+}
+
+/// Form body of `POST /api/oidc/device_authorization`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DeviceAuthorizationRequest {
+    pub client_id: Option<String>,
+    /// `openid` when left out
+    pub scope: Option<String>,
+}
+
+/// The signed-in user's decision on the device showing `user_code`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceVerificationRequest {
+    /// As shown on the device; case and dashes are ignored
+    pub user_code: String,
+    pub approve: bool,
+}
@@ -0,0 +1,63 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// OpenID Provider metadata, served at `/.well-known/openid-configuration`
+/// under the issuer
+#[derive(Debug, Serialize)]
+pub struct DiscoveryDocument {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+    pub device_authorization_endpoint: String,
+    pub response_types_supported: [&'static str; 1],
+    pub grant_types_supported: [&'static str; 2],
+    pub subject_types_supported: [&'static str; 1],
+    pub id_token_signing_alg_values_supported: [&'static str; 1],
+    /// `openid`, `email` and the API scopes users may grant
+    pub scopes_supported: Vec<&'static str>,
+    pub token_endpoint_auth_methods_supported: [&'static str; 1],
+    pub code_challenge_methods_supported: [&'static str; 1],
+}
+
+/// Successful token response (RFC 6749 section 5.1)
+#[derive(Debug, Serialize)]
+pub struct TokenResponse {
+    /// A session token, accepted as a bearer token by the rest of the API
+    pub access_token: String,
+    pub token_type: &'static str,
+    pub expires_in: i64,
+    pub id_token: String,
+    pub scope: String,
+}
+
+/// Device authorization response (RFC 8628 section 3.2)
+#[derive(Debug, Serialize)]
+pub struct DeviceAuthorizationResponse {
+    /// Sent by the device when polling the token endpoint
+    pub device_code: String,
+    /// Shown to the user, e.g. `WDJB-MJHT`
+    pub user_code: String,
+    /// Where the user enters the code while signed in
+    pub verification_uri: String,
+    /// `verification_uri` with the code filled in, e.g. for a QR code
+    pub verification_uri_complete: String,
+    pub expires_in: i64,
+    /// Seconds the device waits between polls
+    pub interval: i64,
+}
+
+/// What a device is asking for, shown before the user approves it
+#[derive(Debug, Serialize)]
+pub struct PendingDeviceResponse {
+    pub client_id: String,
+    pub scope: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeviceVerificationResponse {
+    pub client_id: String,
+    pub scope: String,
+    pub approved: bool,
+}
@@ -0,0 +1,3 @@
+pub mod product;
+
+pub use product::*;
@@ -0,0 +1,21 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::infrastructure::Money;
+
+/// Something with a price; the example of a resource with financial fields
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Product {
+    pub id: Uuid,
+    pub name: String,
+    /// Stored as the `money_amount` composite in Postgres, never as a float
+    pub price: Money,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Product {
+    pub fn new(name: String, price: Money) -> Self {
+        Self { id: Uuid::new_v4(), name, price, created_at: Utc::now() }
+    }
+}
@@ -0,0 +1,94 @@
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use super::entities::Product;
+use super::model::{CreateProductRequest, ProductResponse};
+use super::repository::ProductStore;
+use crate::delivery::{url_for, FastJson, RouteName};
+use crate::infrastructure::{FieldErrors, RequestContext};
+use crate::response::{created_response, internal_error_response, not_found_response, success_response, validation_error_response};
+
+pub async fn create_product(
+    State(products): State<Arc<dyn ProductStore>>,
+    context: RequestContext,
+    FastJson(payload): FastJson<CreateProductRequest>,
+) -> Result<Response, Response> {
+    if let Err(errors) = payload.validate() {
+        return Err(validation_error_response(&FieldErrors::from(errors)).into_response());
+    }
+
+    let product = Product::new(payload.name, payload.price);
+    products
+        .save(product.clone())
+        .await
+        .map_err(|_| internal_error_response("Failed to create product").into_response())?;
+
+    let location = url_for(RouteName::GetProduct, &[("id", &product.id)]).ok();
+    Ok(created_response(ProductResponse::new(product, &context), location).into_response())
+}
+
+pub async fn get_product(
+    State(products): State<Arc<dyn ProductStore>>,
+    context: RequestContext,
+    Path(product_id): Path<Uuid>,
+) -> Result<Response, Response> {
+    match products.find_by_id(product_id).await {
+        Ok(Some(product)) => Ok(success_response(ProductResponse::new(product, &context)).into_response()),
+        Ok(None) => Err(not_found_response("Product").into_response()),
+        Err(_) => Err(internal_error_response("Failed to retrieve product").into_response()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::product::repository::InMemoryProductStore;
+    use axum::{body::Body, http::{header, HeaderMap, Request, StatusCode}, routing::{get, post}, Router};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    async fn call(app: &Router, request: Request<Body>) -> (StatusCode, HeaderMap, Value) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let (status, headers) = (response.status(), response.headers().clone());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, headers, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn create(body: Value) -> Request<Body> {
+        Request::post("/products")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn prices_round_trip_exactly_and_display_per_locale() {
+        let store: Arc<dyn ProductStore> = Arc::new(InMemoryProductStore::new());
+        let app = Router::new()
+            .route("/products", post(create_product))
+            .route("/products/:id", get(get_product))
+            .with_state(store);
+
+        let (status, headers, body) = call(&app, create(json!({ "name": "Lamp", "price": { "amount": "1234.5", "currency": "EUR" } }))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["data"]["price"], json!({ "amount": "1234.5", "currency": "EUR" }));
+
+        let id = body["data"]["id"].as_str().unwrap();
+        assert_eq!(headers[header::LOCATION], format!("/api/products/{id}"));
+        let mut request = Request::get(format!("/products/{id}")).body(Body::empty()).unwrap();
+        request.extensions_mut().insert(RequestContext::from_settings("de-DE", "EUR", "metric"));
+        let (_, _, body) = call(&app, request).await;
+        assert_eq!(body["data"]["price_display"], "1.234,50\u{a0}€");
+
+        let (status, _, body) = call(&app, create(json!({ "name": "Pen", "price": { "amount": "0.999", "currency": "USD" } }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["details"]["fields"]["price"], json!(["At most 2 decimals for USD"]));
+        let (status, _, _) = call(&app, create(json!({ "name": "Pen", "price": { "amount": 1.5, "currency": "USD" } }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
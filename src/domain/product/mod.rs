@@ -0,0 +1,4 @@
+pub mod entities;
+pub mod repository;
+pub mod model;
+pub mod handler;
@@ -0,0 +1,16 @@
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::infrastructure::Money;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateProductRequest {
+    #[validate(length(min = 1, max = 200, message = "Name must be 1 to 200 characters"))]
+    pub name: String,
+
+    #[validate(
+        custom = "crate::infrastructure::validate_money_precision",
+        custom = "crate::infrastructure::validate_money_non_negative"
+    )]
+    pub price: Money,
+}
@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::domain::product::entities::Product;
+use crate::infrastructure::{RequestContext, Rounding};
+
+#[derive(Debug, Serialize)]
+pub struct ProductResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub price: crate::infrastructure::Money,
+    /// `price` formatted for the request's locale, e.g. `1.234,50 €`; display only
+    pub price_display: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ProductResponse {
+    /// Prices are validated to the currency's minor units on the way in, so
+    /// rounding here only matters for rows written by other means
+    pub fn new(product: Product, context: &RequestContext) -> Self {
+        let price_display = product
+            .price
+            .format(context.locale, Rounding::HalfEven)
+            .unwrap_or_else(|_| product.price.to_string());
+        Self {
+            id: product.id,
+            name: product.name,
+            price: product.price,
+            price_display,
+            created_at: product.created_at,
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::domain::product::entities::Product;
+use crate::domain::product::repository::{ProductStore, ProductStoreError};
+
+#[derive(Default)]
+pub struct InMemoryProductStore {
+    products: RwLock<HashMap<Uuid, Product>>,
+}
+
+impl InMemoryProductStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ProductStore for InMemoryProductStore {
+    async fn save(&self, product: Product) -> Result<(), ProductStoreError> {
+        self.products.write().await.insert(product.id, product);
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Product>, ProductStoreError> {
+        Ok(self.products.read().await.get(&id).cloned())
+    }
+}
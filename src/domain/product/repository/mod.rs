@@ -0,0 +1,5 @@
+pub mod store;
+pub mod in_memory_impl;
+
+pub use store::*;
+pub use in_memory_impl::*;
@@ -0,0 +1,15 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+use crate::domain::product::entities::Product;
+
+#[async_trait]
+pub trait ProductStore: Send + Sync {
+    async fn save(&self, product: Product) -> Result<(), ProductStoreError>;
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Product>, ProductStoreError>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProductStoreError {
+    #[error("Product store error: {0}")]
+    Internal(String),
+}
@@ -0,0 +1,186 @@
+use serde_json::{json, Value};
+
+use super::StreamError;
+use axum::extract::ws::Message;
+use crate::domain::realtime::model::{ClientMessage, ControlMessage, Envelope, StreamEvent};
+
+/// Version of the envelope, also named in the subprotocols
+pub const ENVELOPE_VERSION: u8 = 1;
+pub const JSON_PROTOCOL: &str = "events.v1.json";
+pub const MSGPACK_PROTOCOL: &str = "events.v1.msgpack";
+pub const REGISTRY_PROTOCOL: &str = "events.v1.registry";
+
+#[derive(Debug, thiserror::Error)]
+pub enum CodecError {
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    MessagePack(#[from] rmp_serde::encode::Error),
+}
+
+/// How messages on one connection are written, picked from the subprotocols
+/// the client offers on the upgrade
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    /// No subprotocol: bare JSON text messages, as before envelopes existed
+    Plain,
+    /// `events.v1.json`: envelopes as JSON text messages
+    Json,
+    /// `events.v1.msgpack`: envelopes as MessagePack binary messages
+    MessagePack,
+    /// `events.v1.registry`: events as binary messages from the
+    /// `EventSerializer`, in the schema registry's wire format; everything
+    /// else, and events while the serializer falls back, as `events.v1.json`
+    Registry,
+}
+
+impl Codec {
+    /// The first offered subprotocol this server speaks. Clients offering
+    /// none of them get `Plain` and no subprotocol in the answer, which per
+    /// RFC 6455 leaves it to them to go on or close.
+    pub fn negotiate(offered: &[String]) -> Self {
+        offered
+            .iter()
+            .find_map(|protocol| match protocol.as_str() {
+                JSON_PROTOCOL => Some(Self::Json),
+                MSGPACK_PROTOCOL => Some(Self::MessagePack),
+                REGISTRY_PROTOCOL => Some(Self::Registry),
+                _ => None,
+            })
+            .unwrap_or(Self::Plain)
+    }
+
+    pub fn protocol(&self) -> Option<&'static str> {
+        match self {
+            Self::Plain => None,
+            Self::Json => Some(JSON_PROTOCOL),
+            Self::MessagePack => Some(MSGPACK_PROTOCOL),
+            Self::Registry => Some(REGISTRY_PROTOCOL),
+        }
+    }
+
+    pub fn encode_event(&self, event: &StreamEvent) -> Result<Message, CodecError> {
+        if *self == Self::Plain {
+            return Ok(Message::Text(serde_json::to_string(event)?));
+        }
+        self.encode(&Envelope {
+            v: ENVELOPE_VERSION,
+            kind: event.kind.clone(),
+            id: Some(event.seq),
+            payload: json!({ "topics": event.topics, "data": event.data, "published_at": event.published_at }),
+        })
+    }
+
+    /// `id` is the request being answered, if any
+    pub fn encode_control(&self, control: &ControlMessage, id: Option<u64>) -> Result<Message, CodecError> {
+        if *self == Self::Plain {
+            return Ok(Message::Text(serde_json::to_string(control)?));
+        }
+        // The tag becomes the envelope type and the remaining fields its payload
+        let mut payload = serde_json::to_value(control)?;
+        let kind = match payload.as_object_mut().and_then(|fields| fields.remove("type")) {
+            Some(Value::String(kind)) => kind,
+            _ => String::new(),
+        };
+        self.encode(&Envelope { v: ENVELOPE_VERSION, kind, id, payload })
+    }
+
+    fn encode(&self, envelope: &Envelope) -> Result<Message, CodecError> {
+        Ok(match self {
+            Self::MessagePack => Message::Binary(rmp_serde::to_vec_named(envelope)?),
+            _ => Message::Text(serde_json::to_string(envelope)?),
+        })
+    }
+
+    /// A client message and the id to answer it with. Each codec takes only
+    /// its own message kind: text for the JSON ones, binary for MessagePack.
+    pub fn decode(&self, message: &Message) -> Result<(ClientMessage, Option<u64>), StreamError> {
+        let bad = |err: &dyn std::fmt::Display| StreamError::BadMessage(err.to_string());
+        let envelope: Envelope = match (self, message) {
+            (Self::Plain, Message::Text(text)) => return Ok((serde_json::from_str(text).map_err(|err| bad(&err))?, None)),
+            (Self::Json | Self::Registry, Message::Text(text)) => serde_json::from_str(text).map_err(|err| bad(&err))?,
+            (Self::MessagePack, Message::Binary(bytes)) => rmp_serde::from_slice(bytes).map_err(|err| bad(&err))?,
+            (Self::MessagePack, _) => return Err(bad(&"expected a MessagePack binary message")),
+            _ => return Err(bad(&"expected a JSON text message")),
+        };
+        if envelope.v != ENVELOPE_VERSION {
+            return Err(bad(&format!("unsupported envelope version {}", envelope.v)));
+        }
+        let mut fields = match envelope.payload {
+            Value::Object(fields) => fields,
+            Value::Null => Default::default(),
+            _ => return Err(bad(&"payload must be a map")),
+        };
+        fields.insert("action".to_string(), Value::String(envelope.kind));
+        let request = serde_json::from_value(Value::Object(fields)).map_err(|err| bad(&err))?;
+        Ok((request, envelope.id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn event() -> StreamEvent {
+        StreamEvent {
+            seq: 7,
+            kind: "presence".to_string(),
+            topics: vec!["presence".to_string()],
+            data: json!({"user_id": "u1", "online": true}),
+            published_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn envelopes_carry_the_same_fields_in_json_and_messagepack() {
+        let offered = ["chat".to_string(), MSGPACK_PROTOCOL.to_string(), JSON_PROTOCOL.to_string()];
+        assert_eq!(Codec::negotiate(&offered), Codec::MessagePack);
+        assert_eq!(Codec::negotiate(&["chat".to_string()]), Codec::Plain);
+        assert_eq!(Codec::negotiate(&[REGISTRY_PROTOCOL.to_string()]), Codec::Registry);
+
+        let event = event();
+        let Message::Binary(bytes) = Codec::MessagePack.encode_event(&event).unwrap() else {
+            panic!("MessagePack frames are binary");
+        };
+        let packed: Envelope = rmp_serde::from_slice(&bytes).unwrap();
+        let Message::Text(text) = Codec::Json.encode_event(&event).unwrap() else {
+            panic!("JSON frames are text");
+        };
+        assert_eq!(packed, serde_json::from_str::<Envelope>(&text).unwrap());
+        assert_eq!((packed.v, packed.kind.as_str(), packed.id), (1, "presence", Some(7)));
+        assert_eq!(packed.payload["data"]["online"], json!(true));
+
+        let ack = ControlMessage::Subscribed { topic: "presence".to_string() };
+        let Message::Binary(bytes) = Codec::MessagePack.encode_control(&ack, Some(3)).unwrap() else {
+            panic!("MessagePack frames are binary");
+        };
+        let packed: Envelope = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!((packed.kind.as_str(), packed.id, packed.payload), ("subscribed", Some(3), json!({"topic": "presence"})));
+
+        // Without a subprotocol, messages are as they were before envelopes
+        let Message::Text(plain) = Codec::Plain.encode_control(&ack, Some(3)).unwrap() else {
+            panic!("plain frames are text");
+        };
+        assert_eq!(plain, r#"{"type":"subscribed","topic":"presence"}"#);
+    }
+
+    #[test]
+    fn client_requests_decode_from_their_codecs_message_kind() {
+        let request = Envelope { v: 1, kind: "subscribe".to_string(), id: Some(9), payload: json!({"topic": "presence"}) };
+        let expected = (ClientMessage::Subscribe { topic: "presence".to_string() }, Some(9));
+
+        let packed = Message::Binary(rmp_serde::to_vec_named(&request).unwrap());
+        assert_eq!(Codec::MessagePack.decode(&packed).unwrap(), expected);
+        let text = Message::Text(serde_json::to_string(&request).unwrap());
+        assert_eq!(Codec::Json.decode(&text).unwrap(), expected);
+        assert_eq!(Codec::Registry.decode(&text).unwrap(), expected);
+        assert!(matches!(Codec::MessagePack.decode(&text), Err(StreamError::BadMessage(_))));
+
+        let future = Envelope { v: 2, ..request };
+        let packed = Message::Binary(rmp_serde::to_vec_named(&future).unwrap());
+        assert!(matches!(Codec::MessagePack.decode(&packed), Err(StreamError::BadMessage(_))));
+        let plain = Message::Text(r#"{"action": "unsubscribe", "topic": "presence"}"#.to_string());
+        assert_eq!(Codec::Plain.decode(&plain).unwrap(), (ClientMessage::Unsubscribe { topic: "presence".to_string() }, None));
+    }
+}
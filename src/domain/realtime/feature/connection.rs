@@ -0,0 +1,349 @@
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+
+use super::{Attachment, Codec, CodecError, EventHub, EventSerializer, StreamError, Subscriber, TopicAccess};
+use crate::domain::realtime::model::{ClientMessage, ControlMessage, StreamEvent};
+
+/// Clients only send subscriptions, pings and close frames, so anything
+/// bigger is refused with close code 1009
+pub const MAX_CLIENT_MESSAGE: usize = 4096;
+/// A close frame has room for 123 bytes of reason after the code
+const MAX_CLOSE_REASON: usize = 123;
+
+/// Who is connected and which topics they are sent
+pub struct StreamSession {
+    pub subscriber: Subscriber,
+    pub access: Arc<TopicAccess>,
+    /// Topic names, already authorized
+    pub topics: HashSet<String>,
+    pub codec: Codec,
+    /// Writes events under `Codec::Registry`
+    pub serializer: Arc<EventSerializer>,
+}
+
+impl StreamSession {
+    fn wants(&self, event: &StreamEvent) -> bool {
+        event.concerns(&self.topics)
+    }
+
+    fn encode_event(&self, event: &StreamEvent) -> Result<Message, CodecError> {
+        match self.codec {
+            Codec::Registry => match self.serializer.encode(event) {
+                Some(bytes) => Ok(Message::Binary(bytes)),
+                None => self.codec.encode_event(event),
+            },
+            _ => self.codec.encode_event(event),
+        }
+    }
+
+    /// Apply a client request, returning the acknowledgement to send
+    async fn handle(&mut self, request: ClientMessage) -> Result<ControlMessage, StreamError> {
+        match request {
+            ClientMessage::Subscribe { topic } => {
+                if !self.topics.contains(&topic) {
+                    self.access.authorize(&self.subscriber, &topic, self.topics.len()).await?;
+                    self.topics.insert(topic.clone());
+                }
+                Ok(ControlMessage::Subscribed { topic })
+            }
+            ClientMessage::Unsubscribe { topic } => {
+                self.topics.remove(&topic);
+                Ok(ControlMessage::Unsubscribed { topic })
+            }
+        }
+    }
+}
+
+/// How a connection ended
+enum Ending {
+    /// Send a close frame with this code and reason
+    Close(u16, String),
+    /// Tell the client what it did wrong, in reply to request `id` if known, then close
+    Refused(StreamError, Option<u64>),
+    /// The peer is gone; nothing left to send
+    Gone,
+}
+
+/// Serve one `/api/events` connection: the backlog, then live events of the
+/// subscribed topics until either side closes. Server-side closes carry a
+/// resume token in the close reason, for the last event this connection has
+/// seen; closes over client errors carry the error code instead.
+pub async fn stream_events(hub: Arc<EventHub>, mut socket: WebSocket, mut attachment: Attachment, mut session: StreamSession) {
+    let session_end = session
+        .subscriber
+        .expires_at()
+        .map(|expires_at| tokio::time::Instant::now() + (expires_at - chrono::Utc::now()).to_std().unwrap_or_default());
+
+    let mut last_seq = attachment.last_seq;
+    let ending = 'stream: {
+        let mut replayed = 0;
+        for event in attachment.backlog.iter().filter(|event| session.wants(event)) {
+            if send(&mut socket, session.encode_event(event)).await.is_err() {
+                break 'stream Ending::Gone;
+            }
+            replayed += 1;
+        }
+        let control = match attachment.resumed {
+            Some(Err(reason)) => Some(ControlMessage::Reset { reason }),
+            Some(Ok(_)) => Some(ControlMessage::Resumed { replayed }),
+            None => None,
+        };
+        if let Some(control) = control {
+            if send(&mut socket, session.codec.encode_control(&control, None)).await.is_err() {
+                break 'stream Ending::Gone;
+            }
+        }
+
+        loop {
+            tokio::select! {
+                _ = attachment.shutdown.changed() => {
+                    break Ending::Close(close_code::RESTART, hub.resume_token(last_seq));
+                }
+                _ = sleep_until(session_end) => break Ending::Refused(StreamError::SessionExpired, None),
+                event = attachment.live.recv() => match event {
+                    // Already sent in the backlog
+                    Ok(event) if event.seq <= last_seq => {}
+                    Ok(event) => {
+                        if session.wants(&event) && send(&mut socket, session.encode_event(&event)).await.is_err() {
+                            break Ending::Gone;
+                        }
+                        last_seq = event.seq;
+                    }
+                    // Too slow for the live feed; the replay buffer can catch it up
+                    Err(RecvError::Lagged(_)) => break Ending::Close(close_code::AGAIN, hub.resume_token(last_seq)),
+                    Err(RecvError::Closed) => break Ending::Close(close_code::AWAY, hub.resume_token(last_seq)),
+                },
+                // Receiving is cancel-safe: a partly read frame stays buffered in the socket
+                message = socket.recv() => match message {
+                    Some(Ok(message @ (Message::Text(_) | Message::Binary(_)))) => {
+                        let (request, id) = match session.codec.decode(&message) {
+                            Ok(decoded) => decoded,
+                            Err(err) => break Ending::Refused(err, None),
+                        };
+                        match session.handle(request).await {
+                            Ok(ack) => {
+                                if send(&mut socket, session.codec.encode_control(&ack, id)).await.is_err() {
+                                    break Ending::Gone;
+                                }
+                            }
+                            Err(err) => break Ending::Refused(err, id),
+                        }
+                    }
+                    // The socket answers pings itself
+                    Some(Ok(Message::Ping(_) | Message::Pong(_))) => {}
+                    Some(Ok(Message::Close(_))) => break Ending::Close(close_code::NORMAL, String::new()),
+                    Some(Err(err)) => {
+                        let reason = err.to_string();
+                        match read_error_code(err) {
+                            Some(code) => break Ending::Close(code, reason),
+                            None => break Ending::Gone,
+                        }
+                    }
+                    None => break Ending::Gone,
+                },
+            }
+        }
+    };
+
+    match ending {
+        Ending::Close(code, reason) => close(&mut socket, code, &reason).await,
+        Ending::Refused(err, id) => {
+            let error = ControlMessage::Error { code: err.code(), message: err.to_string() };
+            let _ = send(&mut socket, session.codec.encode_control(&error, id)).await;
+            close(&mut socket, err.close_code(), err.code()).await;
+        }
+        Ending::Gone => {}
+    }
+}
+
+/// Wait for `deadline`, or forever without one
+async fn sleep_until(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn send(socket: &mut WebSocket, message: Result<Message, CodecError>) -> Result<(), axum::Error> {
+    socket.send(message.map_err(axum::Error::new)?).await
+}
+
+/// `reason` is cut to the room a close frame has for it
+async fn close(socket: &mut WebSocket, code: u16, reason: &str) {
+    let mut end = reason.len().min(MAX_CLOSE_REASON);
+    while !reason.is_char_boundary(end) {
+        end -= 1;
+    }
+    let frame = CloseFrame { code, reason: reason[..end].to_string().into() };
+    let _ = socket.send(Message::Close(Some(frame))).await;
+}
+
+/// The close code to send the peer after a read error, unless the
+/// connection itself failed
+fn read_error_code(err: axum::Error) -> Option<u16> {
+    let err = err.into_inner().downcast::<tungstenite::Error>().ok()?;
+    match *err {
+        tungstenite::Error::Capacity(_) => Some(close_code::SIZE),
+        tungstenite::Error::Protocol(_) => Some(close_code::PROTOCOL),
+        tungstenite::Error::Utf8 => Some(close_code::INVALID),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::realtime::feature::ResumeTokens;
+    use crate::middleware::Ownership;
+    use axum::{extract::WebSocketUpgrade, routing::get, Router};
+    use serde_json::json;
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::sync::oneshot;
+
+    /// Unmasked server frames: (opcode, payload)
+    fn frames(mut bytes: &[u8]) -> Vec<(u8, Vec<u8>)> {
+        let mut frames = Vec::new();
+        while bytes.len() >= 2 {
+            let len = bytes[1] as usize;
+            frames.push((bytes[0] & 0x0F, bytes[2..2 + len].to_vec()));
+            bytes = &bytes[2 + len..];
+        }
+        frames
+    }
+
+    /// A masked client text frame, with an all-zero mask
+    fn text_frame(text: &str) -> Vec<u8> {
+        let mut frame = vec![0x81];
+        match text.len() {
+            len if len < 126 => frame.push(0x80 | len as u8),
+            len => {
+                frame.push(0x80 | 126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+        }
+        frame.extend_from_slice(&[0, 0, 0, 0]);
+        frame.extend_from_slice(text.as_bytes());
+        frame
+    }
+
+    fn hub() -> Arc<EventHub> {
+        Arc::new(EventHub::new(16, ResumeTokens::new("secret", Duration::from_secs(60))))
+    }
+
+    fn admin_session(topics: &[&str]) -> StreamSession {
+        StreamSession {
+            subscriber: Subscriber::Admin,
+            access: Arc::new(TopicAccess::new(Arc::new(Ownership::default()), Arc::from("secret"), 2)),
+            topics: topics.iter().map(|topic| topic.to_string()).collect(),
+            codec: Codec::Plain,
+            serializer: Arc::new(EventSerializer::json()),
+        }
+    }
+
+    /// Serve one upgraded connection with `stream_events`. Returns the client
+    /// end, past the 101, and a receiver told when the server side is done.
+    async fn connect(hub: Arc<EventHub>, session: StreamSession) -> (TcpStream, oneshot::Receiver<()>) {
+        let (done_tx, done) = oneshot::channel();
+        let pending = Arc::new(Mutex::new(Some((hub.clone(), hub.attach(None).unwrap(), session, done_tx))));
+        let app = Router::new().route(
+            "/",
+            get(move |upgrade: WebSocketUpgrade| {
+                let (hub, attachment, session, done_tx) = pending.lock().unwrap().take().unwrap();
+                async move {
+                    upgrade.max_message_size(MAX_CLIENT_MESSAGE).on_upgrade(move |socket| async move {
+                        stream_events(hub, socket, attachment, session).await;
+                        let _ = done_tx.send(());
+                    })
+                }
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = "GET / HTTP/1.1\r\nHost: localhost\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\
+                       Sec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n";
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            head.push(stream.read_u8().await.unwrap());
+        }
+        assert!(head.starts_with(b"HTTP/1.1 101"), "{}", String::from_utf8_lossy(&head));
+        (stream, done)
+    }
+
+    /// Everything the server sent until it hung up
+    async fn read_all(stream: &mut TcpStream) -> Vec<(u8, Vec<u8>)> {
+        let mut output = Vec::new();
+        tokio::time::timeout(Duration::from_secs(5), stream.read_to_end(&mut output)).await.unwrap().unwrap();
+        frames(&output)
+    }
+
+    #[tokio::test]
+    async fn shutdown_closes_with_a_token_for_the_last_event_seen() {
+        let hub = hub();
+        let (mut client, done) = connect(hub.clone(), admin_session(&["presence"])).await;
+
+        hub.publish("presence", vec!["presence".to_string()], json!({"n": 1}));
+        hub.publish("profile", vec!["users/1".to_string()], json!({"n": 2}));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        hub.shutdown(Duration::from_secs(1)).await;
+        done.await.unwrap();
+        assert_eq!(hub.connections(), 0);
+
+        let frames = read_all(&mut client).await;
+        assert_eq!(frames.len(), 2, "events of other topics are not sent");
+        let event: serde_json::Value = serde_json::from_slice(&frames[0].1).unwrap();
+        assert_eq!((event["seq"].clone(), event["type"].clone()), (json!(1), json!("presence")));
+        let (opcode, close) = &frames[1];
+        assert_eq!((*opcode, u16::from_be_bytes([close[0], close[1]])), (0x8, close_code::RESTART));
+
+        let token = std::str::from_utf8(&close[2..]).unwrap();
+        let point = ResumeTokens::new("secret", Duration::from_secs(60)).verify(token).unwrap();
+        assert_eq!(point.seq, 2);
+    }
+
+    #[tokio::test]
+    async fn subscribing_past_the_limit_closes_with_a_structured_error() {
+        let hub = hub();
+        let (mut client, done) = connect(hub.clone(), admin_session(&["presence"])).await;
+
+        let user = format!("users/{}", uuid::Uuid::new_v4());
+        let other = format!("users/{}", uuid::Uuid::new_v4());
+        for topic in [&user, "presence", &other] {
+            let message = json!({"action": "subscribe", "topic": topic}).to_string();
+            client.write_all(&text_frame(&message)).await.unwrap();
+        }
+        done.await.unwrap();
+
+        let frames = read_all(&mut client).await;
+        assert_eq!(frames.len(), 4);
+        let replies: Vec<serde_json::Value> =
+            frames[..3].iter().map(|(_, payload)| serde_json::from_slice(payload).unwrap()).collect();
+        assert_eq!(replies[0], json!({"type": "subscribed", "topic": user}));
+        assert_eq!(replies[1], json!({"type": "subscribed", "topic": "presence"}));
+        assert_eq!((replies[2]["type"].clone(), replies[2]["code"].clone()), (json!("error"), json!("TOO_MANY_SUBSCRIPTIONS")));
+        let close = &frames[3].1;
+        assert_eq!(u16::from_be_bytes([close[0], close[1]]), 4429);
+        assert_eq!(&close[2..], b"TOO_MANY_SUBSCRIPTIONS");
+    }
+
+    #[tokio::test]
+    async fn oversized_messages_close_with_1009() {
+        let hub = hub();
+        let (mut client, done) = connect(hub.clone(), admin_session(&["presence"])).await;
+
+        client.write_all(&text_frame(&"x".repeat(MAX_CLIENT_MESSAGE + 1))).await.unwrap();
+        done.await.unwrap();
+
+        let frames = read_all(&mut client).await;
+        let (opcode, close) = frames.last().unwrap();
+        assert_eq!((*opcode, u16::from_be_bytes([close[0], close[1]])), (0x8, close_code::SIZE));
+    }
+}
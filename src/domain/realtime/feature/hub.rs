@@ -0,0 +1,351 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, watch};
+use uuid::Uuid;
+
+use super::{ResumeTokens, TokenError};
+use crate::domain::realtime::model::{ResetReason, StreamEvent};
+
+/// Live events a connection may fall behind by before it is closed and
+/// left to resume from the replay buffer
+const LIVE_BUFFER: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum AttachError {
+    #[error("Resume token is invalid")]
+    InvalidToken,
+    #[error("Server is shutting down; reconnect shortly")]
+    ShuttingDown,
+}
+
+impl AttachError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AttachError::InvalidToken => "RESUME_TOKEN_INVALID",
+            AttachError::ShuttingDown => "SHUTTING_DOWN",
+        }
+    }
+}
+
+/// Numbered events of one stream and the latest of them. A stream is
+/// identified by its epoch, so sequence numbers are only compared within it.
+#[derive(Debug, Serialize, Deserialize)]
+struct ReplayBuffer {
+    epoch: String,
+    last_seq: u64,
+    events: VecDeque<StreamEvent>,
+}
+
+/// What the replay buffer is written to on shutdown and read from on startup
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    saved_at: DateTime<Utc>,
+    #[serde(flatten)]
+    buffer: ReplayBuffer,
+}
+
+/// A client joining the stream: what it missed, and the live events after that
+pub struct Attachment {
+    /// Replayed before any live event
+    pub backlog: Vec<Arc<StreamEvent>>,
+    /// Set when a resume was asked for; `Err` when the missed events could not be replayed
+    pub resumed: Option<Result<usize, ResetReason>>,
+    /// The last event the client has, counting the backlog
+    pub last_seq: u64,
+    pub live: broadcast::Receiver<Arc<StreamEvent>>,
+    pub shutdown: watch::Receiver<bool>,
+    _connection: ConnectionGuard,
+}
+
+struct ConnectionGuard(Arc<AtomicUsize>);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Fan-out of events to `/api/events` connections.
+///
+/// Every published event is numbered and kept in a bounded replay buffer.
+/// When the server shuts down each connection is closed with a resume token
+/// for the last event it was sent; a client reconnecting with the token
+/// within the resume window is sent what it missed before live events
+/// continue. The buffer is in memory, and written to the snapshot file on
+/// shutdown so the next process picks up the same stream.
+pub struct EventHub {
+    buffer: Mutex<ReplayBuffer>,
+    capacity: usize,
+    live: broadcast::Sender<Arc<StreamEvent>>,
+    tokens: ResumeTokens,
+    shutdown: watch::Sender<bool>,
+    connections: Arc<AtomicUsize>,
+    snapshot: Option<PathBuf>,
+}
+
+impl EventHub {
+    /// Keeps the last `capacity` events for replay
+    pub fn new(capacity: usize, tokens: ResumeTokens) -> Self {
+        Self {
+            buffer: Mutex::new(ReplayBuffer {
+                epoch: Uuid::new_v4().simple().to_string()[..12].to_string(),
+                last_seq: 0,
+                events: VecDeque::with_capacity(capacity),
+            }),
+            capacity,
+            live: broadcast::channel(LIVE_BUFFER).0,
+            tokens,
+            shutdown: watch::channel(false).0,
+            connections: Arc::new(AtomicUsize::new(0)),
+            snapshot: None,
+        }
+    }
+
+    /// Persist the replay buffer to `path` on shutdown, and continue from it on `restore`
+    pub fn with_snapshot(mut self, path: impl Into<PathBuf>) -> Self {
+        self.snapshot = Some(path.into());
+        self
+    }
+
+    /// Number, keep and send an event to the subscribers of any of `topics`;
+    /// returns its sequence number
+    pub fn publish(&self, kind: &str, topics: Vec<String>, data: serde_json::Value) -> u64 {
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.last_seq += 1;
+        let event = StreamEvent { seq: buffer.last_seq, kind: kind.to_string(), topics, data, published_at: Utc::now() };
+        if self.capacity > 0 {
+            if buffer.events.len() == self.capacity {
+                buffer.events.pop_front();
+            }
+            buffer.events.push_back(event.clone());
+        }
+        // Sent under the lock, so `attach` sees every event in either the backlog or `live`
+        let _ = self.live.send(Arc::new(event));
+        buffer.last_seq
+    }
+
+    /// Join the stream, replaying what the client missed when it brings a resume token
+    pub fn attach(&self, resume: Option<&str>) -> Result<Attachment, AttachError> {
+        if *self.shutdown.borrow() {
+            return Err(AttachError::ShuttingDown);
+        }
+        let point = match resume.map(|token| self.tokens.verify(token)) {
+            None => None,
+            Some(Ok(point)) => Some(Ok(point)),
+            Some(Err(TokenError::Expired)) => Some(Err(ResetReason::Expired)),
+            Some(Err(TokenError::Invalid)) => return Err(AttachError::InvalidToken),
+        };
+
+        let buffer = self.buffer.lock().unwrap();
+        let live = self.live.subscribe();
+        let (backlog, resumed) = match point {
+            None => (Vec::new(), None),
+            Some(Err(reason)) => (Vec::new(), Some(Err(reason))),
+            Some(Ok(point)) if point.epoch != buffer.epoch || point.seq > buffer.last_seq => {
+                (Vec::new(), Some(Err(ResetReason::UnknownStream)))
+            }
+            Some(Ok(point)) => {
+                let missed: Vec<Arc<StreamEvent>> =
+                    buffer.events.iter().filter(|event| event.seq > point.seq).cloned().map(Arc::new).collect();
+                if missed.len() as u64 == buffer.last_seq - point.seq {
+                    let replayed = missed.len();
+                    (missed, Some(Ok(replayed)))
+                } else {
+                    (Vec::new(), Some(Err(ResetReason::Evicted)))
+                }
+            }
+        };
+        let last_seq = buffer.last_seq;
+        drop(buffer);
+
+        self.connections.fetch_add(1, Ordering::Relaxed);
+        Ok(Attachment {
+            backlog,
+            resumed,
+            last_seq,
+            live,
+            shutdown: self.shutdown.subscribe(),
+            _connection: ConnectionGuard(Arc::clone(&self.connections)),
+        })
+    }
+
+    /// Token a client can resume from after receiving event `seq`
+    pub fn resume_token(&self, seq: u64) -> String {
+        let epoch = self.buffer.lock().unwrap().epoch.clone();
+        self.tokens.issue(&epoch, seq)
+    }
+
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::Relaxed)
+    }
+
+    /// Close every connection with a resume token, wait up to `grace` for
+    /// them to finish, then write the snapshot
+    pub async fn shutdown(&self, grace: Duration) {
+        self.shutdown.send_replace(true);
+        let deadline = tokio::time::Instant::now() + grace;
+        while self.connections() > 0 && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let open = self.connections();
+        if open > 0 {
+            tracing::warn!(open, "Event streams still open after the shutdown grace period");
+        }
+        if let Err(err) = self.save().await {
+            tracing::warn!(error = %err, "Failed to write event replay snapshot");
+        }
+    }
+
+    async fn save(&self) -> std::io::Result<()> {
+        let Some(path) = &self.snapshot else {
+            return Ok(());
+        };
+        let json = {
+            let buffer = self.buffer.lock().unwrap();
+            let snapshot = Snapshot {
+                saved_at: Utc::now(),
+                buffer: ReplayBuffer {
+                    epoch: buffer.epoch.clone(),
+                    last_seq: buffer.last_seq,
+                    events: buffer.events.clone(),
+                },
+            };
+            serde_json::to_vec(&snapshot)?
+        };
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(path, json).await?;
+        tracing::info!(path = %path.display(), "Event replay snapshot written");
+        Ok(())
+    }
+
+    /// Continue the stream a previous process saved, if its tokens can still be
+    /// used. Run before anything is published; the snapshot is removed once
+    /// read, so it is never picked up twice.
+    pub async fn restore(&self) {
+        let Some(path) = &self.snapshot else {
+            return;
+        };
+        let contents = match tokio::fs::read(path).await {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return,
+            Err(err) => {
+                tracing::warn!(path = %path.display(), error = %err, "Failed to read event replay snapshot");
+                return;
+            }
+        };
+        let _ = tokio::fs::remove_file(path).await;
+        let snapshot: Snapshot = match serde_json::from_slice(&contents) {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                tracing::warn!(path = %path.display(), error = %err, "Ignoring unreadable event replay snapshot");
+                return;
+            }
+        };
+        let age = (Utc::now() - snapshot.saved_at).to_std().unwrap_or_default();
+        if age > self.tokens.window() {
+            return;
+        }
+
+        let mut restored = snapshot.buffer;
+        while restored.events.len() > self.capacity {
+            restored.events.pop_front();
+        }
+        tracing::info!(epoch = %restored.epoch, last_seq = restored.last_seq, "Event stream restored from snapshot");
+        *self.buffer.lock().unwrap() = restored;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn topics() -> Vec<String> {
+        vec!["presence".to_string()]
+    }
+
+    fn hub(capacity: usize) -> EventHub {
+        EventHub::new(capacity, ResumeTokens::new("secret", Duration::from_secs(60)))
+    }
+
+    #[test]
+    fn resuming_replays_missed_events_while_they_are_buffered() {
+        let hub = hub(3);
+        hub.publish("presence", topics(), json!({"n": 1}));
+        let token = hub.resume_token(1);
+        for n in 2..=4 {
+            hub.publish("presence", topics(), json!({ "n": n }));
+        }
+
+        let attachment = hub.attach(Some(&token)).unwrap();
+        let seqs: Vec<u64> = attachment.backlog.iter().map(|event| event.seq).collect();
+        assert_eq!((seqs, attachment.resumed, attachment.last_seq), (vec![2, 3, 4], Some(Ok(3)), 4));
+        assert_eq!(hub.connections(), 1);
+        drop(attachment);
+        assert_eq!(hub.connections(), 0);
+
+        hub.publish("presence", topics(), json!({"n": 5}));
+        let evicted = hub.attach(Some(&token)).unwrap();
+        assert!(evicted.backlog.is_empty());
+        assert_eq!(evicted.resumed, Some(Err(ResetReason::Evicted)));
+
+        assert_eq!(hub.attach(Some("1.2.3.4")).err(), Some(AttachError::InvalidToken));
+        let foreign = ResumeTokens::new("secret", Duration::from_secs(60)).issue("elsewhere", 1);
+        assert_eq!(hub.attach(Some(&foreign)).unwrap().resumed, Some(Err(ResetReason::UnknownStream)));
+    }
+
+    #[tokio::test]
+    async fn the_stream_survives_a_restart_through_the_snapshot() {
+        let path = std::env::temp_dir().join(format!("events-{}.json", Uuid::new_v4()));
+        let before = hub(8).with_snapshot(&path);
+        before.publish("presence", topics(), json!({"n": 1}));
+        before.publish("presence", topics(), json!({"n": 2}));
+        let token = before.resume_token(1);
+        before.shutdown(Duration::from_millis(50)).await;
+        assert_eq!(before.attach(None).err(), Some(AttachError::ShuttingDown));
+
+        let after = hub(8).with_snapshot(&path);
+        after.restore().await;
+        assert!(!path.exists());
+        assert_eq!(after.publish("presence", topics(), json!({"n": 3})), 3);
+        let attachment = after.attach(Some(&token)).unwrap();
+        let seqs: Vec<u64> = attachment.backlog.iter().map(|event| event.seq).collect();
+        assert_eq!((seqs, attachment.resumed), (vec![2, 3], Some(Ok(2))));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shutdown_waits_for_streams_to_close_but_not_past_the_grace() {
+        let draining = hub(8);
+        // A stream that takes 300ms to say goodbye once told to close
+        let closing = {
+            let mut attachment = draining.attach(None).unwrap();
+            tokio::spawn(async move {
+                attachment.shutdown.changed().await.unwrap();
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                drop(attachment);
+            })
+        };
+        let started = tokio::time::Instant::now();
+        draining.shutdown(Duration::from_secs(5)).await;
+        let waited = started.elapsed();
+        assert!((Duration::from_millis(300)..Duration::from_millis(320)).contains(&waited), "{waited:?}");
+        assert_eq!(draining.connections(), 0);
+        closing.await.unwrap();
+
+        // One that never closes is given up on at the deadline
+        let stuck = hub(8);
+        let _open = stuck.attach(None).unwrap();
+        let started = tokio::time::Instant::now();
+        stuck.shutdown(Duration::from_secs(2)).await;
+        let waited = started.elapsed();
+        assert!((Duration::from_secs(2)..Duration::from_millis(2020)).contains(&waited), "{waited:?}");
+        assert_eq!(stuck.connections(), 1);
+    }
+}
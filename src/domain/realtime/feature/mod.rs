@@ -0,0 +1,17 @@
+pub mod tokens;
+pub mod topics;
+pub mod codec;
+pub mod serializer;
+pub mod hub;
+pub mod connection;
+pub mod poll;
+pub mod relay;
+
+pub use tokens::*;
+pub use topics::*;
+pub use codec::*;
+pub use serializer::*;
+pub use hub::*;
+pub use connection::*;
+pub use poll::*;
+pub use relay::*;
@@ -0,0 +1,118 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+
+use super::Attachment;
+use crate::domain::realtime::model::{ResetReason, StreamEvent};
+
+/// Most events one poll answers with; the rest are left for the next poll
+pub const MAX_POLL_BATCH: usize = 100;
+
+/// What one long poll found
+#[derive(Debug)]
+pub struct Polled {
+    pub events: Vec<Arc<StreamEvent>>,
+    pub reset: Option<ResetReason>,
+    /// The last event the poll has looked at, for the next cursor
+    pub last_seq: u64,
+}
+
+/// Answer a long poll from an attachment resumed at its cursor: the missed
+/// events of `topics` if there are any, otherwise the first ones published
+/// within `wait`. Returns early, possibly empty, when the server shuts down.
+pub async fn wait_for_events(mut attachment: Attachment, topics: &HashSet<String>, wait: Duration) -> Polled {
+    let reset = match attachment.resumed {
+        Some(Err(reason)) => Some(reason),
+        _ => None,
+    };
+    let mut events = Vec::new();
+    let mut last_seq = attachment.last_seq;
+    for event in std::mem::take(&mut attachment.backlog) {
+        if events.len() == MAX_POLL_BATCH {
+            last_seq = event.seq - 1;
+            break;
+        }
+        if event.concerns(topics) {
+            events.push(event);
+        }
+    }
+    if !events.is_empty() || reset.is_some() {
+        return Polled { events, reset, last_seq };
+    }
+
+    let deadline = tokio::time::sleep(wait);
+    tokio::pin!(deadline);
+    loop {
+        tokio::select! {
+            _ = &mut deadline => break,
+            _ = attachment.shutdown.changed() => break,
+            event = attachment.live.recv() => match event {
+                Ok(event) if event.seq <= last_seq => {}
+                Ok(event) => {
+                    last_seq = event.seq;
+                    if event.concerns(topics) {
+                        events.push(event);
+                        break;
+                    }
+                }
+                // The next poll picks up from the replay buffer
+                Err(RecvError::Lagged(_) | RecvError::Closed) => break,
+            },
+        }
+    }
+    // Whatever else is already queued goes out with the first event
+    while !events.is_empty() && events.len() < MAX_POLL_BATCH {
+        match attachment.live.try_recv() {
+            Ok(event) => {
+                last_seq = event.seq;
+                if event.concerns(topics) {
+                    events.push(event);
+                }
+            }
+            Err(TryRecvError::Empty | TryRecvError::Closed | TryRecvError::Lagged(_)) => break,
+        }
+    }
+    Polled { events, reset: None, last_seq }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::realtime::feature::{EventHub, ResumeTokens};
+    use serde_json::json;
+
+    fn presence() -> HashSet<String> {
+        HashSet::from(["presence".to_string()])
+    }
+
+    #[tokio::test]
+    async fn polls_answer_with_missed_events_or_wait_for_the_next_one() {
+        let hub = Arc::new(EventHub::new(16, ResumeTokens::new("secret", Duration::from_secs(60))));
+        hub.publish("presence", vec!["presence".to_string()], json!({"n": 1}));
+        let cursor = hub.resume_token(0);
+
+        let polled = wait_for_events(hub.attach(Some(&cursor)).unwrap(), &presence(), Duration::from_secs(5)).await;
+        assert_eq!((polled.events.len(), polled.last_seq), (1, 1));
+
+        // Nothing new: waits, skipping events of other topics
+        let cursor = hub.resume_token(polled.last_seq);
+        let attachment = hub.attach(Some(&cursor)).unwrap();
+        let publisher = {
+            let hub = hub.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                hub.publish("profile", vec!["users/1".to_string()], json!({"n": 2}));
+                hub.publish("presence", vec!["presence".to_string()], json!({"n": 3}));
+            })
+        };
+        let polled = wait_for_events(attachment, &presence(), Duration::from_secs(5)).await;
+        publisher.await.unwrap();
+        let seqs: Vec<u64> = polled.events.iter().map(|event| event.seq).collect();
+        assert_eq!((seqs, polled.last_seq), (vec![3], 3));
+
+        let timed_out = wait_for_events(hub.attach(None).unwrap(), &presence(), Duration::from_millis(20)).await;
+        assert!(timed_out.events.is_empty());
+        assert_eq!((timed_out.reset, timed_out.last_seq), (None, 3));
+    }
+}
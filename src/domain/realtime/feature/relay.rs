@@ -0,0 +1,51 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+
+use super::{EventHub, Topic};
+use crate::container::startup::StartupComponent;
+use crate::domain::user::feature::PresenceTracker;
+
+/// Restores the event stream from its snapshot, then forwards presence
+/// changes to it for as long as the process runs
+pub struct EventRelay {
+    hub: Arc<EventHub>,
+    presence: Arc<PresenceTracker>,
+}
+
+impl EventRelay {
+    pub fn new(hub: Arc<EventHub>, presence: Arc<PresenceTracker>) -> Self {
+        Self { hub, presence }
+    }
+}
+
+#[async_trait]
+impl StartupComponent for EventRelay {
+    fn name(&self) -> &'static str {
+        "event_relay"
+    }
+
+    async fn start(&self) -> Result<(), String> {
+        self.hub.restore().await;
+        let hub = self.hub.clone();
+        let mut presence = self.presence.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match presence.recv().await {
+                    Ok(event) => match serde_json::to_value(&event) {
+                        Ok(data) => {
+                            let topics = vec![Topic::Presence.name(), Topic::User(event.user_id).name()];
+                            hub.publish("presence", topics, data);
+                        }
+                        Err(err) => tracing::warn!(error = %err, "Failed to serialize presence event"),
+                    },
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "Event relay fell behind; presence events were dropped")
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+        Ok(())
+    }
+}
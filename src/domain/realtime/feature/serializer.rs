@@ -0,0 +1,155 @@
+use crate::domain::realtime::model::StreamEvent;
+use crate::infrastructure::{avro_long, avro_string, protobuf_string, protobuf_varint, RegistryPayload, RegistrySerializer};
+
+/// Avro or Protobuf framing of events for `events.v1.registry` clients,
+/// in the format picked by `EVENT_SERIALIZER`
+pub type EventSerializer = RegistrySerializer<StreamEvent>;
+
+impl RegistryPayload for StreamEvent {
+    /// `data` is JSON text, since its shape depends on `type`
+    const AVRO_SCHEMA: &'static str = r#"{"type":"record","name":"StreamEvent","namespace":"rust_boilerplate.events","fields":[{"name":"seq","type":"long"},{"name":"type","type":"string"},{"name":"topics","type":{"type":"array","items":"string"}},{"name":"data","type":"string","doc":"JSON text"},{"name":"published_at","type":{"type":"long","logicalType":"timestamp-millis"}}]}"#;
+
+    /// The same fields as the Avro record
+    const PROTOBUF_SCHEMA: &'static str = r#"syntax = "proto3";
+package rust_boilerplate.events;
+
+message StreamEvent {
+  uint64 seq = 1;
+  string type = 2;
+  repeated string topics = 3;
+  // JSON text
+  string data = 4;
+  // Unix milliseconds
+  int64 published_at = 5;
+}
+"#;
+
+    fn encode_avro(&self, out: &mut Vec<u8>) {
+        avro_long(self.seq as i64, out);
+        avro_string(&self.kind, out);
+        if !self.topics.is_empty() {
+            avro_long(self.topics.len() as i64, out);
+            for topic in &self.topics {
+                avro_string(topic, out);
+            }
+        }
+        // Arrays end with an empty block
+        avro_long(0, out);
+        avro_string(&self.data.to_string(), out);
+        avro_long(self.published_at.timestamp_millis(), out);
+    }
+
+    fn encode_protobuf(&self, out: &mut Vec<u8>) {
+        protobuf_varint(1, self.seq, out);
+        protobuf_string(2, &self.kind, out);
+        for topic in &self.topics {
+            protobuf_string(3, topic, out);
+        }
+        protobuf_string(4, &self.data.to_string(), out);
+        protobuf_varint(5, self.published_at.timestamp_millis() as u64, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::decode_avro;
+    use chrono::TimeZone;
+    use prost::Message;
+    use serde_json::json;
+
+    fn event() -> StreamEvent {
+        StreamEvent {
+            seq: 3,
+            kind: "presence".to_string(),
+            topics: vec!["presence".to_string()],
+            data: json!({"n": 1}),
+            published_at: chrono::Utc.timestamp_millis_opt(1).unwrap(),
+        }
+    }
+
+    /// `PROTOBUF_SCHEMA`, as prost would generate it
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct DecodedEvent {
+        #[prost(uint64, tag = "1")]
+        seq: u64,
+        #[prost(string, tag = "2")]
+        r#type: String,
+        #[prost(string, repeated, tag = "3")]
+        topics: Vec<String>,
+        #[prost(string, tag = "4")]
+        data: String,
+        #[prost(int64, tag = "5")]
+        published_at: i64,
+    }
+
+    #[test]
+    fn events_are_framed_with_the_schema_id_in_avro_and_protobuf() {
+        let avro = EventSerializer::from_name("avro", "events-value");
+        assert_eq!(avro.encode(&event()), None);
+        let avro = avro.with_schema_id(42);
+        let mut expected = vec![0, 0, 0, 0, 42, 6, 16];
+        expected.extend_from_slice(b"presence");
+        expected.extend_from_slice(&[2, 16]);
+        expected.extend_from_slice(b"presence");
+        expected.extend_from_slice(&[0, 14]);
+        expected.extend_from_slice(br#"{"n":1}"#);
+        expected.push(2);
+        assert_eq!(avro.encode(&event()).unwrap(), expected);
+
+        let protobuf = EventSerializer::from_name("protobuf", "events-value").with_schema_id(7);
+        let mut expected = vec![0, 0, 0, 0, 7, 0, 0x08, 3, 0x12, 8];
+        expected.extend_from_slice(b"presence");
+        expected.extend_from_slice(&[0x1a, 8]);
+        expected.extend_from_slice(b"presence");
+        expected.extend_from_slice(&[0x22, 7]);
+        expected.extend_from_slice(br#"{"n":1}"#);
+        expected.extend_from_slice(&[0x28, 1]);
+        assert_eq!(protobuf.encode(&event()).unwrap(), expected);
+        assert_eq!(EventSerializer::from_name("json", "events-value").encode(&event()), None);
+    }
+
+    #[test]
+    fn events_decode_as_their_schemas_describe() {
+        let several = StreamEvent {
+            seq: 300,
+            topics: vec!["presence".to_string(), "users/7".to_string()],
+            // Before 1970, so negative
+            published_at: chrono::Utc.timestamp_millis_opt(-86_400_000).unwrap(),
+            ..event()
+        };
+        let none = StreamEvent { seq: 0, kind: String::new(), topics: Vec::new(), ..event() };
+
+        let avro = EventSerializer::from_name("avro", "events-value").with_schema_id(1);
+        for event in [event(), several.clone(), none.clone()] {
+            let decoded = decode_avro::<StreamEvent>(&avro.encode(&event).unwrap());
+            assert_eq!(
+                decoded,
+                json!({
+                    "seq": event.seq,
+                    "type": event.kind,
+                    "topics": event.topics,
+                    "data": event.data.to_string(),
+                    "published_at": event.published_at.timestamp_millis(),
+                })
+            );
+        }
+
+        let protobuf = EventSerializer::from_name("protobuf", "events-value").with_schema_id(1);
+        for event in [event(), several, none] {
+            let framed = protobuf.encode(&event).unwrap();
+            // Magic byte, schema id and the message index
+            let decoded = DecodedEvent::decode(&framed[6..]).unwrap();
+            assert_eq!(
+                decoded,
+                DecodedEvent {
+                    seq: event.seq,
+                    r#type: event.kind.clone(),
+                    topics: event.topics.clone(),
+                    data: event.data.to_string(),
+                    published_at: event.published_at.timestamp_millis(),
+                }
+            );
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::Duration;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Signature bytes kept in a token, so it fits a close frame's reason
+const SIGNATURE_BYTES: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum TokenError {
+    #[error("Resume token has expired")]
+    Expired,
+    #[error("Resume token is invalid")]
+    Invalid,
+}
+
+/// Where a client left a stream: the stream's epoch and the last event it received
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResumePoint {
+    pub epoch: String,
+    pub seq: u64,
+}
+
+/// Resume tokens handed to clients when the server closes their event
+/// stream: `epoch.seq.expires.signature`, with a truncated HMAC-SHA256
+/// over the rest. Every instance must share the secret for tokens to
+/// survive a restart or a reconnect to another instance.
+pub struct ResumeTokens {
+    secret: Vec<u8>,
+    window: Duration,
+}
+
+impl ResumeTokens {
+    /// An empty secret is replaced by a random one, so tokens stop working
+    /// when the process restarts
+    pub fn new(secret: &str, window: Duration) -> Self {
+        let secret = if secret.is_empty() {
+            format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()).into_bytes()
+        } else {
+            secret.as_bytes().to_vec()
+        };
+        Self { secret, window }
+    }
+
+    /// How long after being issued a token can be used
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub fn issue(&self, epoch: &str, seq: u64) -> String {
+        let expires = Utc::now().timestamp() + self.window.as_secs() as i64;
+        let signature = self.mac(epoch, seq, expires).finalize().into_bytes();
+        format!("{epoch}.{seq}.{expires}.{}", hex::encode(&signature[..SIGNATURE_BYTES]))
+    }
+
+    pub fn verify(&self, token: &str) -> Result<ResumePoint, TokenError> {
+        let mut parts = token.split('.');
+        let (Some(epoch), Some(seq), Some(expires), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(TokenError::Invalid);
+        };
+        let seq: u64 = seq.parse().map_err(|_| TokenError::Invalid)?;
+        let expires: i64 = expires.parse().map_err(|_| TokenError::Invalid)?;
+        let signature = hex::decode(signature).map_err(|_| TokenError::Invalid)?;
+        if signature.len() != SIGNATURE_BYTES {
+            return Err(TokenError::Invalid);
+        }
+        self.mac(epoch, seq, expires).verify_truncated_left(&signature).map_err(|_| TokenError::Invalid)?;
+        if expires < Utc::now().timestamp() {
+            return Err(TokenError::Expired);
+        }
+        Ok(ResumePoint { epoch: epoch.to_string(), seq })
+    }
+
+    fn mac(&self, epoch: &str, seq: u64, expires: i64) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(format!("{epoch}.{seq}.{expires}").as_bytes());
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_round_trip_until_they_expire() {
+        let tokens = ResumeTokens::new("secret", Duration::from_secs(60));
+        let token = tokens.issue("a1b2c3d4", 42);
+        assert!(token.len() <= 123, "must fit a close frame reason");
+        assert_eq!(tokens.verify(&token), Ok(ResumePoint { epoch: "a1b2c3d4".into(), seq: 42 }));
+
+        let forged = token.replacen(".42.", ".41.", 1);
+        assert_eq!(tokens.verify(&forged), Err(TokenError::Invalid));
+        assert_eq!(ResumeTokens::new("other", Duration::from_secs(60)).verify(&token), Err(TokenError::Invalid));
+        assert_eq!(tokens.verify("a1b2c3d4.42"), Err(TokenError::Invalid));
+
+        let expired = ResumeTokens::new("secret", Duration::ZERO);
+        let past = Utc::now().timestamp() - 1;
+        let signature = expired.mac("a1b2c3d4", 42, past).finalize().into_bytes();
+        let token = format!("a1b2c3d4.42.{past}.{}", hex::encode(&signature[..SIGNATURE_BYTES]));
+        assert_eq!(expired.verify(&token), Err(TokenError::Expired));
+    }
+}
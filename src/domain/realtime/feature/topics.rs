@@ -0,0 +1,240 @@
+use axum::extract::ws::close_code;
+use axum::http::{header, HeaderMap, StatusCode};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::domain::session::entities::Principal;
+use crate::middleware::{admin_token_matches, Auth, Disclosure, Ownership, ResourceKind};
+
+/// What connections subscribe to; events name the topics they belong to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Topic {
+    /// Every user's presence changes
+    Presence,
+    /// Events about one user, `users/<id>`
+    User(Uuid),
+}
+
+impl Topic {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.split_once('/') {
+            None if name == "presence" => Some(Self::Presence),
+            Some(("users", id)) => Uuid::parse_str(id).ok().map(Self::User),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Presence => "presence".to_string(),
+            Self::User(id) => format!("users/{id}"),
+        }
+    }
+
+    /// Who may subscribe, in the terms of route policies. Like on owned
+    /// routes, the admin token may subscribe to any owned topic.
+    pub fn auth(&self) -> Auth {
+        match self {
+            // Presence is public over HTTP too
+            Self::Presence => Auth::Public,
+            Self::User(_) => Auth::Owner(ResourceKind::User),
+        }
+    }
+
+    /// The `:id` an owned topic is checked against
+    fn resource_id(&self) -> Option<String> {
+        match self {
+            Self::Presence => None,
+            Self::User(id) => Some(id.to_string()),
+        }
+    }
+}
+
+/// Who opened a connection, established on the upgrade
+#[derive(Debug, Clone)]
+pub enum Subscriber {
+    /// Bearer `ADMIN_API_TOKEN`
+    Admin,
+    User(Principal),
+}
+
+impl Subscriber {
+    /// When the session behind the connection ends; the connection is closed then
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Admin => None,
+            Self::User(principal) => Some(principal.claims.expires_at),
+        }
+    }
+}
+
+/// Why a subscription or client message was refused. Before the upgrade it
+/// is answered with `status`; afterwards the connection is closed with
+/// `close_code` and `code` as the reason.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum StreamError {
+    #[error("Malformed message: {0}")]
+    BadMessage(String),
+    #[error("Session has expired")]
+    SessionExpired,
+    #[error("Not allowed to subscribe to {0}")]
+    Forbidden(String),
+    #[error("No such topic: {0}")]
+    UnknownTopic(String),
+    #[error("At most {0} subscriptions per connection")]
+    TooManySubscriptions(usize),
+    #[error("Failed to authorize subscription")]
+    Internal,
+}
+
+impl StreamError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::BadMessage(_) => "BAD_MESSAGE",
+            Self::SessionExpired => "SESSION_EXPIRED",
+            Self::Forbidden(_) => "TOPIC_FORBIDDEN",
+            Self::UnknownTopic(_) => "UNKNOWN_TOPIC",
+            Self::TooManySubscriptions(_) => "TOO_MANY_SUBSCRIPTIONS",
+            Self::Internal => "INTERNAL_ERROR",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::BadMessage(_) | Self::TooManySubscriptions(_) => StatusCode::BAD_REQUEST,
+            Self::SessionExpired => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::UnknownTopic(_) => StatusCode::NOT_FOUND,
+            Self::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Application close codes are 4000 plus the matching HTTP status
+    pub fn close_code(&self) -> u16 {
+        match self {
+            Self::Internal => close_code::ERROR,
+            Self::TooManySubscriptions(_) => 4429,
+            err => 4000 + err.status().as_u16(),
+        }
+    }
+}
+
+/// Who may open `/api/events` and subscribe to what. Topics are authorized
+/// with the route policy rules and the owner lookups owned routes use.
+pub struct TopicAccess {
+    ownership: Arc<Ownership>,
+    admin_token: Arc<str>,
+    max_subscriptions: usize,
+    allowed_origins: Vec<String>,
+}
+
+impl TopicAccess {
+    pub fn new(ownership: Arc<Ownership>, admin_token: Arc<str>, max_subscriptions: usize) -> Self {
+        Self { ownership, admin_token, max_subscriptions, allowed_origins: Vec::new() }
+    }
+
+    /// Comma-separated origins, e.g. `https://app.example.com`, whose pages
+    /// may open a stream with the session cookie besides the server's own
+    pub fn with_allowed_origins(mut self, spec: &str) -> Self {
+        self.allowed_origins = spec
+            .split(',')
+            .map(|origin| origin.trim().trim_end_matches('/').to_ascii_lowercase())
+            .filter(|origin| !origin.is_empty())
+            .collect();
+        self
+    }
+
+    /// Whether the page an upgrade came from may use the session cookie
+    /// browsers attach to it: its `Origin` must be the server's own host or
+    /// an allowed origin. Browsers always send `Origin` on upgrades, so
+    /// cookie upgrades without one are refused too.
+    pub fn origin_allowed(&self, headers: &HeaderMap) -> bool {
+        let Some(origin) = headers.get(header::ORIGIN).and_then(|value| value.to_str().ok()) else {
+            return false;
+        };
+        let origin = origin.trim_end_matches('/').to_ascii_lowercase();
+        let host = headers.get(header::HOST).and_then(|value| value.to_str().ok()).map(str::to_ascii_lowercase);
+        let same_host = host.is_some_and(|host| origin.split_once("://").is_some_and(|(_, authority)| authority == host));
+        same_host || self.allowed_origins.contains(&origin)
+    }
+
+    /// The caller of an upgrade request; `None` for anonymous callers, who are refused
+    pub fn subscriber(&self, principal: Option<Principal>, headers: &HeaderMap) -> Option<Subscriber> {
+        match principal {
+            Some(principal) => Some(Subscriber::User(principal)),
+            None if admin_token_matches(headers, &self.admin_token) => Some(Subscriber::Admin),
+            None => None,
+        }
+    }
+
+    /// Check one more subscription for a connection holding `held`
+    pub async fn authorize(&self, subscriber: &Subscriber, name: &str, held: usize) -> Result<Topic, StreamError> {
+        let topic = Topic::parse(name).ok_or_else(|| StreamError::UnknownTopic(name.to_string()))?;
+        if held >= self.max_subscriptions {
+            return Err(StreamError::TooManySubscriptions(self.max_subscriptions));
+        }
+        let Subscriber::User(principal) = subscriber else {
+            // The admin token passes every route policy
+            return Ok(topic);
+        };
+        let kind = match topic.auth() {
+            Auth::Public => return Ok(topic),
+            Auth::Admin => return Err(StreamError::Forbidden(name.to_string())),
+            Auth::Owner(kind) => kind,
+        };
+        let user_id = principal.user_id;
+        let id = topic.resource_id().unwrap_or_default();
+        match self.ownership.is_owner(kind, user_id, &id).await {
+            Ok(Some(true)) => Ok(topic),
+            Ok(Some(false)) => {
+                tracing::warn!(target: "security", topic = %name, user_id = %user_id, "Subscription to another user's topic denied");
+                Err(match self.ownership.disclosure() {
+                    Disclosure::NotFound => StreamError::UnknownTopic(name.to_string()),
+                    Disclosure::Forbidden => StreamError::Forbidden(name.to_string()),
+                })
+            }
+            Ok(None) => Err(StreamError::UnknownTopic(name.to_string())),
+            Err(err) => {
+                tracing::error!(topic = %name, error = %err, "Owner lookup failed");
+                Err(StreamError::Internal)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delivery::fake_principal;
+    use crate::middleware::{OwnedResource, OwnershipError};
+    use async_trait::async_trait;
+
+    struct OwnedBySelf;
+
+    #[async_trait]
+    impl OwnedResource for OwnedBySelf {
+        async fn owner_of(&self, id: &str) -> Result<Option<Uuid>, OwnershipError> {
+            Ok(Uuid::parse_str(id).ok())
+        }
+    }
+
+    #[tokio::test]
+    async fn owned_topics_need_the_owner_or_the_admin_token() {
+        let ownership = Ownership::new(Disclosure::Forbidden).with_resource(ResourceKind::User, Arc::new(OwnedBySelf));
+        let access = TopicAccess::new(Arc::new(ownership), Arc::from("secret"), 2);
+        let (me, someone) = (Uuid::new_v4(), Uuid::new_v4());
+        let user = Subscriber::User(fake_principal(me, &[]));
+
+        assert_eq!(access.authorize(&user, "presence", 0).await, Ok(Topic::Presence));
+        assert_eq!(access.authorize(&user, &format!("users/{me}"), 1).await, Ok(Topic::User(me)));
+        let theirs = format!("users/{someone}");
+        assert_eq!(access.authorize(&user, &theirs, 1).await, Err(StreamError::Forbidden(theirs.clone())));
+        assert_eq!(access.authorize(&Subscriber::Admin, &theirs, 1).await, Ok(Topic::User(someone)));
+        assert_eq!(access.authorize(&user, "presence", 2).await, Err(StreamError::TooManySubscriptions(2)));
+        assert_eq!(access.authorize(&user, "users/me", 0).await, Err(StreamError::UnknownTopic("users/me".into())));
+
+        assert_eq!(StreamError::Forbidden(theirs).close_code(), 4403);
+        assert!(access.subscriber(None, &HeaderMap::new()).is_none());
+    }
+}
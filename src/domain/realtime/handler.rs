@@ -0,0 +1,246 @@
+use axum::{
+    extract::{
+        ws::{rejection::WebSocketUpgradeRejection, WebSocketUpgrade},
+        FromRef, Query, State,
+    },
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Extension,
+};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::feature::{
+    stream_events, wait_for_events, AttachError, Codec, EventHub, EventSerializer, StreamSession, Subscriber, TopicAccess,
+    MAX_CLIENT_MESSAGE,
+};
+use super::model::EventPollResponse;
+use crate::delivery::http::auth::MaybeAuthUser;
+use crate::domain::session::entities::Principal;
+use crate::infrastructure::LaneSlot;
+use crate::middleware::CookieSession;
+use crate::response::{error_response, success_response, unauthorized_response};
+
+/// Topics a connection or poll starts with when it names none
+const DEFAULT_TOPICS: &str = "presence";
+/// Longest a poll is held open; below the route's timeout
+pub const MAX_POLL_WAIT_SECS: u64 = 25;
+
+#[derive(Clone)]
+pub struct EventsState {
+    pub hub: Arc<EventHub>,
+    pub access: Arc<TopicAccess>,
+    pub serializer: Arc<EventSerializer>,
+}
+
+impl FromRef<EventsState> for Arc<EventHub> {
+    fn from_ref(state: &EventsState) -> Self {
+        state.hub.clone()
+    }
+}
+
+impl FromRef<EventsState> for Arc<TopicAccess> {
+    fn from_ref(state: &EventsState) -> Self {
+        state.access.clone()
+    }
+}
+
+impl FromRef<EventsState> for Arc<EventSerializer> {
+    fn from_ref(state: &EventsState) -> Self {
+        state.serializer.clone()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventStreamQuery {
+    /// Token from the close frame of an earlier connection
+    pub resume: Option<String>,
+    /// Comma-separated topics to start with
+    pub topics: Option<String>,
+}
+
+/// Live events over a WebSocket, resumed from `?resume=<token>` when given and
+/// framed as the negotiated subprotocol asks.
+/// Needs a session or the admin token; the starting topics are authorized
+/// before the upgrade, so a refused one fails the handshake with its status.
+/// Sessions from the cookie also need an allowed `Origin`, since browsers
+/// attach the cookie to upgrades started by any site. Requests that are not
+/// WebSocket handshakes get 426.
+pub async fn event_stream(
+    State(EventsState { hub, access, serializer }): State<EventsState>,
+    MaybeAuthUser(principal): MaybeAuthUser,
+    cookie: Option<Extension<CookieSession>>,
+    headers: HeaderMap,
+    Query(query): Query<EventStreamQuery>,
+    upgrade: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+) -> Response {
+    let upgrade = match upgrade {
+        Ok(upgrade) => upgrade,
+        Err(rejection) => return upgrade_required(rejection),
+    };
+    if cookie.is_some() && !access.origin_allowed(&headers) {
+        let origin = headers.get(header::ORIGIN).and_then(|value| value.to_str().ok());
+        tracing::warn!(target: "security", origin, "Cross-origin event stream with the session cookie refused");
+        return error_response(StatusCode::FORBIDDEN, "ORIGIN_NOT_ALLOWED", "Origin not allowed").into_response();
+    }
+    let (subscriber, topics) = match subscribe(&access, principal, &headers, query.topics.as_deref()).await {
+        Ok(subscribed) => subscribed,
+        Err(response) => return response,
+    };
+    // Attached before the upgrade completes, so nothing published in between is missed
+    let attachment = match hub.attach(query.resume.as_deref()) {
+        Ok(attachment) => attachment,
+        Err(err) => return attach_error(err),
+    };
+    // The client's order of preference decides, so only the pick is offered back
+    let codec = Codec::negotiate(&offered_protocols(&headers));
+    let session = StreamSession { subscriber, access, topics, codec, serializer };
+    upgrade
+        .protocols(codec.protocol())
+        .max_message_size(MAX_CLIENT_MESSAGE)
+        .max_frame_size(MAX_CLIENT_MESSAGE)
+        .on_failed_upgrade(|err| tracing::debug!(error = %err, "WebSocket upgrade failed"))
+        .on_upgrade(move |socket| stream_events(hub, socket, attachment, session))
+}
+
+/// Subprotocols the client offered, in its order of preference
+fn offered_protocols(headers: &HeaderMap) -> Vec<String> {
+    headers
+        .get_all(header::SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|protocol| protocol.trim().to_string())
+        .filter(|protocol| !protocol.is_empty())
+        .collect()
+}
+
+/// 426 with the WebSocket version spoken, for requests that can't be upgraded
+fn upgrade_required(rejection: WebSocketUpgradeRejection) -> Response {
+    let mut response = error_response(StatusCode::UPGRADE_REQUIRED, "UPGRADE_REQUIRED", rejection.body_text()).into_response();
+    let headers = response.headers_mut();
+    headers.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+    headers.insert(header::SEC_WEBSOCKET_VERSION, HeaderValue::from_static("13"));
+    response
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventPollQuery {
+    /// `cursor` of the previous poll; without one, only events published from now on are returned
+    pub cursor: Option<String>,
+    /// Comma-separated topics, as on `/api/events`
+    pub topics: Option<String>,
+    /// Seconds to wait for an event, up to `MAX_POLL_WAIT_SECS`
+    pub wait: Option<u64>,
+}
+
+/// Long-polling fallback for clients whose proxies break WebSockets: answers
+/// with the events past `cursor` right away, or waits for the next one
+pub async fn poll_events(
+    State(hub): State<Arc<EventHub>>,
+    State(access): State<Arc<TopicAccess>>,
+    MaybeAuthUser(principal): MaybeAuthUser,
+    slot: Option<Extension<LaneSlot>>,
+    headers: HeaderMap,
+    Query(query): Query<EventPollQuery>,
+) -> Response {
+    let (_, topics) = match subscribe(&access, principal, &headers, query.topics.as_deref()).await {
+        Ok(subscribed) => subscribed,
+        Err(response) => return response,
+    };
+    let attachment = match hub.attach(query.cursor.as_deref()) {
+        Ok(attachment) => attachment,
+        Err(err) => return attach_error(err),
+    };
+    let wait = Duration::from_secs(query.wait.unwrap_or(MAX_POLL_WAIT_SECS).min(MAX_POLL_WAIT_SECS));
+    // Parked polls only wait on the hub, so they give their lane slot back
+    if let Some(Extension(slot)) = slot {
+        slot.release();
+    }
+    let polled = wait_for_events(attachment, &topics, wait).await;
+    success_response(EventPollResponse {
+        events: polled.events.iter().map(|event| (**event).clone()).collect(),
+        cursor: hub.resume_token(polled.last_seq),
+        reset: polled.reset,
+    })
+    .into_response()
+}
+
+/// The caller and the topics they asked for, each authorized, or the response refusing them
+async fn subscribe(
+    access: &TopicAccess,
+    principal: Option<Principal>,
+    headers: &HeaderMap,
+    requested: Option<&str>,
+) -> Result<(Subscriber, HashSet<String>), Response> {
+    let Some(subscriber) = access.subscriber(principal, headers) else {
+        return Err(unauthorized_response("Authentication required").into_response());
+    };
+    let mut topics = HashSet::new();
+    for name in requested.unwrap_or(DEFAULT_TOPICS).split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        if topics.contains(name) {
+            continue;
+        }
+        if let Err(err) = access.authorize(&subscriber, name, topics.len()).await {
+            return Err(error_response(err.status(), err.code(), err.to_string()).into_response());
+        }
+        topics.insert(name.to_string());
+    }
+    Ok((subscriber, topics))
+}
+
+fn attach_error(err: AttachError) -> Response {
+    let status = match err {
+        AttachError::InvalidToken => StatusCode::BAD_REQUEST,
+        AttachError::ShuttingDown => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    error_response(status, err.code(), err.to_string()).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::container::AppContainer;
+    use crate::delivery::create_app;
+    use crate::domain::session::entities::{Session, SessionKind};
+    use crate::middleware::auth_middleware;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+    use uuid::Uuid;
+
+    /// The status line of a raw handshake, since only a served connection can be upgraded
+    async fn handshake(addr: std::net::SocketAddr, credential: &str, origin: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = format!(
+            "GET /api/events?topics=presence HTTP/1.1\r\nHost: {addr}\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\
+             Sec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nOrigin: {origin}\r\n{credential}\r\n\r\n"
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = vec![0; 64];
+        let read = stream.read(&mut response).await.unwrap();
+        String::from_utf8_lossy(&response[..read]).lines().next().unwrap_or_default().to_string()
+    }
+
+    #[tokio::test]
+    async fn cookie_upgrades_need_an_allowed_origin() {
+        let mut config = Config::from_env();
+        config.events_allowed_origins = "https://app.example.com".to_string();
+        let container = AppContainer::new(&config);
+        let app = create_app(&container).layer(axum::middleware::from_fn_with_state(container.sessions.clone(), auth_middleware));
+        let mut session = Session::new(Uuid::new_v4(), SessionKind::Session, chrono::Duration::minutes(5));
+        let token = session.issue_token("ses_");
+        container.sessions.save(session).await.unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let cookie = format!("Cookie: session={token}");
+        assert!(handshake(addr, &cookie, "https://evil.example").await.contains("403"));
+        assert!(handshake(addr, &cookie, "https://app.example.com").await.contains("101"));
+        assert!(handshake(addr, &cookie, &format!("http://{addr}")).await.contains("101"));
+        // Not sent by browsers on their own, so not checked
+        assert!(handshake(addr, &format!("Authorization: Bearer {token}"), "https://evil.example").await.contains("101"));
+    }
+}
@@ -0,0 +1,85 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// One event on `/api/events`, numbered in publish order. Sent as is to
+/// connections subscribed to any of its topics:
+/// `{"seq": 12, "type": "presence", "topics": [...], "data": {...}, "published_at": ...}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamEvent {
+    pub seq: u64,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub topics: Vec<String>,
+    pub data: serde_json::Value,
+    pub published_at: DateTime<Utc>,
+}
+
+impl StreamEvent {
+    /// Whether a client subscribed to `topics` is sent this event
+    pub fn concerns(&self, topics: &HashSet<String>) -> bool {
+        self.topics.iter().any(|topic| topics.contains(topic))
+    }
+}
+
+/// Why a resuming client cannot be sent what it missed and has to reload its state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResetReason {
+    /// The token is older than the resume window
+    Expired,
+    /// The token belongs to a stream this server does not have, e.g. after a
+    /// restart without a replay snapshot
+    UnknownStream,
+    /// Some of the missed events have already left the replay buffer
+    Evicted,
+}
+
+/// Messages the server sends besides events, told apart by `type`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ControlMessage {
+    /// Sent after the missed events were replayed; live events follow
+    Resumed { replayed: usize },
+    /// Sent first when a resume failed; live events follow
+    Reset { reason: ResetReason },
+    Subscribed { topic: String },
+    Unsubscribed { topic: String },
+    /// Sent just before the server closes the connection over a client error;
+    /// `code` is also the close frame's reason
+    Error { code: &'static str, message: String },
+}
+
+/// What clients send, told apart by `action`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe { topic: String },
+    Unsubscribe { topic: String },
+}
+
+/// Framing under a negotiated subprotocol, the same in JSON and MessagePack:
+/// `{"v": 1, "type", "id", "payload"}`. Events carry their `seq` as `id`;
+/// client requests may carry an `id`, which the reply echoes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Envelope {
+    pub v: u8,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    #[serde(default)]
+    pub payload: serde_json::Value,
+}
+
+/// Answer to `GET /api/events/poll`
+#[derive(Debug, Serialize)]
+pub struct EventPollResponse {
+    pub events: Vec<StreamEvent>,
+    /// Pass as `cursor` to the next poll; also a resume token for `/api/events`
+    pub cursor: String,
+    /// Set when the events after the given cursor are gone
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reset: Option<ResetReason>,
+}
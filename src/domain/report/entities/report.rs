@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportKind {
+    /// Signups per UTC day
+    UsersBySignupDate,
+    /// One row per user: signup, last activity and active sessions
+    ActivitySummary,
+}
+
+impl ReportKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::UsersBySignupDate => "users_by_signup_date",
+            Self::ActivitySummary => "activity_summary",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Csv,
+    Xlsx,
+}
+
+impl ReportFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Xlsx => "xlsx",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportStatus {
+    Queued,
+    Running,
+    Ready,
+    Failed,
+}
+
+/// A requested report and, once built, where its artifact is stored
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub id: Uuid,
+    pub kind: ReportKind,
+    pub format: ReportFormat,
+    pub status: ReportStatus,
+    /// Who asked for it, as given in the request
+    pub requested_by: String,
+    /// Posted to when the report is ready or has failed
+    pub callback_url: Option<String>,
+    /// Scheme and host the request came in on, for absolute download links
+    pub origin: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Data rows, without the header
+    pub rows: Option<u64>,
+    /// Key of the artifact in the `ArtifactStore`
+    pub artifact: Option<String>,
+    pub error: Option<String>,
+}
+
+impl Report {
+    pub fn new(kind: ReportKind, format: ReportFormat, requested_by: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            kind,
+            format,
+            status: ReportStatus::Queued,
+            requested_by,
+            callback_url: None,
+            origin: None,
+            created_at: Utc::now(),
+            completed_at: None,
+            rows: None,
+            artifact: None,
+            error: None,
+        }
+    }
+
+    /// File name offered to the browser
+    pub fn file_name(&self) -> String {
+        format!("{}-{}.{}", self.kind.name(), self.created_at.format("%Y%m%d"), self.format.extension())
+    }
+}
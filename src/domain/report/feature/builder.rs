@@ -0,0 +1,119 @@
+use super::xlsx;
+use crate::domain::report::entities::ReportFormat;
+
+/// One cell of a report; numbers stay numbers in spreadsheets
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Text(String),
+    Number(i64),
+    Empty,
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        Cell::Text(text.to_string())
+    }
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Self {
+        Cell::Text(text)
+    }
+}
+
+impl From<i64> for Cell {
+    fn from(number: i64) -> Self {
+        Cell::Number(number)
+    }
+}
+
+impl<T: Into<Cell>> From<Option<T>> for Cell {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Cell::Empty, Into::into)
+    }
+}
+
+/// Rows under a header, rendered into any `ReportFormat`
+#[derive(Debug, Clone, Default)]
+pub struct ReportTable {
+    columns: Vec<&'static str>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl ReportTable {
+    pub fn new(columns: &[&'static str]) -> Self {
+        Self { columns: columns.to_vec(), rows: Vec::new() }
+    }
+
+    /// Missing trailing cells are left empty
+    pub fn push(&mut self, row: Vec<Cell>) {
+        self.rows.push(row);
+    }
+
+    /// Data rows, without the header
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn render(&self, format: ReportFormat) -> Vec<u8> {
+        match format {
+            ReportFormat::Csv => self.to_csv().into_bytes(),
+            ReportFormat::Xlsx => xlsx::workbook(&self.header(), &self.rows),
+        }
+    }
+
+    fn header(&self) -> Vec<Cell> {
+        self.columns.iter().map(|column| Cell::from(*column)).collect()
+    }
+
+    /// RFC 4180, CRLF line endings
+    fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        for row in std::iter::once(&self.header()).chain(&self.rows) {
+            let line: Vec<String> = row.iter().map(csv_field).collect();
+            csv.push_str(&line.join(","));
+            csv.push_str("\r\n");
+        }
+        csv
+    }
+}
+
+fn csv_field(cell: &Cell) -> String {
+    match cell {
+        Cell::Number(number) => number.to_string(),
+        Cell::Empty => String::new(),
+        Cell::Text(text) => {
+            // A leading formula character would be evaluated by spreadsheet apps
+            let text = if text.starts_with(['=', '+', '-', '@']) { format!("'{text}") } else { text.clone() };
+            if text.contains([',', '"', '\r', '\n']) {
+                format!("\"{}\"", text.replace('"', "\"\""))
+            } else {
+                text
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_quotes_and_defuses_formulas_and_xlsx_is_a_zip() {
+        let mut table = ReportTable::new(&["name", "count"]);
+        table.push(vec![Cell::from("Doe, \"Jane\""), Cell::from(3)]);
+        table.push(vec![Cell::from("=HYPERLINK(\"x\")"), Cell::Empty]);
+
+        let csv = String::from_utf8(table.render(ReportFormat::Csv)).unwrap();
+        assert_eq!(csv, "name,count\r\n\"Doe, \"\"Jane\"\"\",3\r\n\"'=HYPERLINK(\"\"x\"\")\",\r\n");
+
+        // Read back with a real zip reader, checksums and all
+        let mut xlsx = zip::ZipArchive::new(std::io::Cursor::new(table.render(ReportFormat::Xlsx))).unwrap();
+        let mut names: Vec<&str> = xlsx.file_names().collect();
+        names.sort_unstable();
+        assert_eq!(names, ["[Content_Types].xml", "_rels/.rels", "xl/_rels/workbook.xml.rels", "xl/workbook.xml", "xl/worksheets/sheet1.xml"]);
+        let mut sheet = String::new();
+        std::io::Read::read_to_string(&mut xlsx.by_name("xl/worksheets/sheet1.xml").unwrap(), &mut sheet).unwrap();
+        assert!(sheet.contains(r#"<row r="2"><c t="inlineStr"><is><t xml:space="preserve">Doe, &quot;Jane&quot;</t></is></c><c><v>3</v></c></row>"#), "{sheet}");
+    }
+}
@@ -0,0 +1,99 @@
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::Duration;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Why a download link was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum LinkError {
+    #[error("Download link has expired; request the report again")]
+    Expired,
+    #[error("Download link signature is invalid")]
+    Invalid,
+}
+
+impl LinkError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            LinkError::Expired => "LINK_EXPIRED",
+            LinkError::Invalid => "LINK_INVALID",
+        }
+    }
+}
+
+/// Expiring download links for report artifacts. The signature is an
+/// HMAC-SHA256 of the report id and the expiry, so a link can be handed to
+/// whoever asked for the report without an admin token.
+pub struct DownloadLinks {
+    secret: Vec<u8>,
+    ttl: Duration,
+}
+
+impl DownloadLinks {
+    /// An empty secret is replaced by a random one, so links stop working
+    /// when the process restarts
+    pub fn new(secret: &str, ttl: Duration) -> Self {
+        let secret = if secret.is_empty() {
+            format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()).into_bytes()
+        } else {
+            secret.as_bytes().to_vec()
+        };
+        Self { secret, ttl }
+    }
+
+    /// Expiry as a Unix timestamp, and the signature
+    pub fn sign(&self, report_id: Uuid) -> (i64, String) {
+        let expires = Utc::now().timestamp() + self.ttl.as_secs() as i64;
+        (expires, hex::encode(self.mac(report_id, expires).finalize().into_bytes()))
+    }
+
+    /// Path and query of a signed download link
+    pub fn path(&self, report_id: Uuid) -> String {
+        let (expires, signature) = self.sign(report_id);
+        format!("/api/reports/{report_id}/download?expires={expires}&signature={signature}")
+    }
+
+    pub fn verify(&self, report_id: Uuid, expires: i64, signature: &str) -> Result<(), LinkError> {
+        let signature = hex::decode(signature).map_err(|_| LinkError::Invalid)?;
+        self.mac(report_id, expires).verify_slice(&signature).map_err(|_| LinkError::Invalid)?;
+        if expires < Utc::now().timestamp() {
+            return Err(LinkError::Expired);
+        }
+        Ok(())
+    }
+
+    fn mac(&self, report_id: Uuid, expires: i64) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(report_id.as_bytes());
+        mac.update(b".");
+        mac.update(expires.to_string().as_bytes());
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_verify_only_for_their_report_until_they_expire() {
+        let links = DownloadLinks::new("secret", Duration::from_secs(60));
+        let report_id = Uuid::new_v4();
+        let (expires, signature) = links.sign(report_id);
+
+        assert_eq!(links.verify(report_id, expires, &signature), Ok(()));
+        assert_eq!(links.verify(Uuid::new_v4(), expires, &signature), Err(LinkError::Invalid));
+        assert_eq!(links.verify(report_id, expires + 60, &signature), Err(LinkError::Invalid));
+        assert_eq!(links.verify(report_id, expires, "zz"), Err(LinkError::Invalid));
+
+        let expired = DownloadLinks::new("secret", Duration::ZERO);
+        let (expires, signature) = expired.sign(report_id);
+        assert_eq!(expired.verify(report_id, expires - 1, &signature), Err(LinkError::Invalid));
+        let past = expires - 1;
+        let signature = hex::encode(expired.mac(report_id, past).finalize().into_bytes());
+        assert_eq!(expired.verify(report_id, past, &signature), Err(LinkError::Expired));
+    }
+}
@@ -0,0 +1,8 @@
+pub mod builder;
+pub mod xlsx;
+pub mod links;
+pub mod service;
+
+pub use builder::*;
+pub use links::*;
+pub use service::*;
@@ -0,0 +1,426 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{NaiveDate, Utc};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use super::{Cell, DownloadLinks, LinkError, ReportTable};
+use crate::container::startup::StartupComponent;
+use crate::domain::report::entities::{Report, ReportFormat, ReportKind, ReportStatus};
+use crate::domain::report::repository::ArtifactStore;
+use crate::domain::session::repository::SessionStore;
+use crate::domain::user::feature::PresenceTracker;
+use crate::domain::user::repository::UserRepository;
+use crate::infrastructure::ConsumerStats;
+use crate::pagination::PageRequest;
+
+/// Users read per repository call while building a report
+const PAGE_SIZE: u32 = 500;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReportError {
+    #[error("Report queue is full")]
+    QueueFull,
+    #[error("Report not found")]
+    NotFound,
+    #[error("Report is not ready")]
+    NotReady,
+    #[error(transparent)]
+    Link(#[from] LinkError),
+    #[error("Report artifact unavailable: {0}")]
+    Storage(String),
+    #[error("callback_url must be an http(s) URL on an allowed host")]
+    CallbackNotAllowed,
+}
+
+/// What an admin asked for in `POST /api/admin/reports`
+#[derive(Debug, Clone)]
+pub struct ReportRequest {
+    pub kind: ReportKind,
+    pub format: ReportFormat,
+    pub requested_by: String,
+    pub callback_url: Option<String>,
+    pub origin: Option<String>,
+}
+
+/// Builds admin reports off the request path.
+///
+/// Requests are put on a bounded in-process queue drained by a single
+/// worker, which reads users page by page, renders the table, stores the
+/// artifact and posts a signed download link to the request's callback URL.
+/// Report records live in memory, so a restart forgets them; their artifacts
+/// stay in the `ArtifactStore`.
+pub struct ReportService {
+    users: Arc<dyn UserRepository>,
+    sessions: Arc<dyn SessionStore>,
+    presence: Arc<PresenceTracker>,
+    artifacts: Arc<dyn ArtifactStore>,
+    links: DownloadLinks,
+    http: reqwest::Client,
+    /// Hosts `callback_url` may point at; none allows no callbacks
+    callback_hosts: Vec<String>,
+    reports: RwLock<HashMap<Uuid, Report>>,
+    queue: mpsc::Sender<Uuid>,
+    receiver: Mutex<Option<mpsc::Receiver<Uuid>>>,
+    stats: Arc<ConsumerStats>,
+}
+
+impl ReportService {
+    pub fn new(
+        users: Arc<dyn UserRepository>,
+        sessions: Arc<dyn SessionStore>,
+        presence: Arc<PresenceTracker>,
+        artifacts: Arc<dyn ArtifactStore>,
+        links: DownloadLinks,
+        queue_capacity: usize,
+    ) -> Self {
+        let (queue, receiver) = mpsc::channel(queue_capacity.max(1));
+        // Redirects could lead a callback off its allowed host
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap_or_default();
+        Self {
+            users,
+            sessions,
+            presence,
+            artifacts,
+            links,
+            http,
+            callback_hosts: Vec::new(),
+            reports: RwLock::new(HashMap::new()),
+            queue,
+            receiver: Mutex::new(Some(receiver)),
+            stats: Arc::new(ConsumerStats::new("in_process")),
+        }
+    }
+
+    /// Report queue lag and build times to `stats`
+    pub fn with_consumer_stats(mut self, stats: Arc<ConsumerStats>) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// Hosts callbacks may be posted to, comma-separated, e.g.
+    /// `hooks.example.com,ops.example.com`
+    pub fn with_callback_hosts(mut self, spec: &str) -> Self {
+        self.callback_hosts = spec
+            .split(',')
+            .map(|host| host.trim().to_ascii_lowercase())
+            .filter(|host| !host.is_empty())
+            .collect();
+        self
+    }
+
+    /// Whether the worker may post to `url`: http or https, on a listed host.
+    /// Anything else would let a caller make the server request internal addresses.
+    pub fn callback_allowed(&self, url: &str) -> bool {
+        let Ok(url) = reqwest::Url::parse(url) else {
+            return false;
+        };
+        matches!(url.scheme(), "http" | "https")
+            && url.host_str().is_some_and(|host| self.callback_hosts.iter().any(|allowed| host.eq_ignore_ascii_case(allowed)))
+    }
+
+    /// Queue a report; it is `Queued` until the worker picks it up
+    pub fn request(&self, request: ReportRequest) -> Result<Report, ReportError> {
+        if request.callback_url.as_deref().is_some_and(|url| !self.callback_allowed(url)) {
+            return Err(ReportError::CallbackNotAllowed);
+        }
+        let mut report = Report::new(request.kind, request.format, request.requested_by);
+        report.callback_url = request.callback_url;
+        report.origin = request.origin;
+        self.reports.write().unwrap().insert(report.id, report.clone());
+        if self.queue.try_send(report.id).is_err() {
+            self.reports.write().unwrap().remove(&report.id);
+            return Err(ReportError::QueueFull);
+        }
+        self.stats.enqueued();
+        Ok(report)
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<Report> {
+        self.reports.read().unwrap().get(&id).cloned()
+    }
+
+    /// A fresh signed link to a ready report, absolute when the request's origin is known
+    pub fn download_url(&self, report: &Report) -> Option<String> {
+        (report.status == ReportStatus::Ready)
+            .then(|| format!("{}{}", report.origin.as_deref().unwrap_or(""), self.links.path(report.id)))
+    }
+
+    /// The artifact behind a signed link
+    pub async fn download(&self, id: Uuid, expires: i64, signature: &str) -> Result<(Report, Bytes), ReportError> {
+        self.links.verify(id, expires, signature)?;
+        let report = self.get(id).ok_or(ReportError::NotFound)?;
+        let Some(key) = report.artifact.as_deref().filter(|_| report.status == ReportStatus::Ready) else {
+            return Err(ReportError::NotReady);
+        };
+        match self.artifacts.get(key).await {
+            Ok(Some(contents)) => Ok((report, contents)),
+            Ok(None) => Err(ReportError::Storage(format!("artifact `{key}` is missing"))),
+            Err(err) => Err(ReportError::Storage(err.to_string())),
+        }
+    }
+
+    /// Build, store and announce one queued report; `false` when it failed
+    pub async fn run(&self, id: Uuid) -> bool {
+        let Some(report) = self.update(id, |report| report.status = ReportStatus::Running) else {
+            return false;
+        };
+        let started = std::time::Instant::now();
+        let outcome = self.build(&report).await;
+        let report = self
+            .update(id, |report| {
+                report.completed_at = Some(Utc::now());
+                match &outcome {
+                    Ok((key, rows)) => {
+                        report.status = ReportStatus::Ready;
+                        report.artifact = Some(key.clone());
+                        report.rows = Some(*rows);
+                    }
+                    Err(reason) => {
+                        report.status = ReportStatus::Failed;
+                        report.error = Some(reason.clone());
+                    }
+                }
+            })
+            .unwrap_or(report);
+        match &outcome {
+            Ok((_, rows)) => tracing::info!(
+                report_id = %id,
+                kind = report.kind.name(),
+                rows,
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                "Report ready"
+            ),
+            Err(reason) => tracing::error!(report_id = %id, kind = report.kind.name(), error = %reason, "Report failed"),
+        }
+        self.notify(&report).await;
+        outcome.is_ok()
+    }
+
+    fn update(&self, id: Uuid, apply: impl FnOnce(&mut Report)) -> Option<Report> {
+        let mut reports = self.reports.write().unwrap();
+        let report = reports.get_mut(&id)?;
+        apply(report);
+        Some(report.clone())
+    }
+
+    /// Artifact key and row count; `Err` carries a short reason for the report
+    async fn build(&self, report: &Report) -> Result<(String, u64), String> {
+        let table = match report.kind {
+            ReportKind::UsersBySignupDate => self.users_by_signup_date().await?,
+            ReportKind::ActivitySummary => self.activity_summary().await?,
+        };
+        let rows = table.row_count() as u64;
+        let format = report.format;
+        let contents = tokio::task::spawn_blocking(move || table.render(format))
+            .await
+            .map_err(|err| format!("rendering failed: {err}"))?;
+        let key = format!("{}.{}", report.id, format.extension());
+        self.artifacts
+            .put(&key, Bytes::from(contents))
+            .await
+            .map_err(|err| err.to_string())?;
+        Ok((key, rows))
+    }
+
+    async fn users_by_signup_date(&self) -> Result<ReportTable, String> {
+        let mut signups: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+        let mut request = PageRequest::new(1, PAGE_SIZE);
+        loop {
+            let users = self.users.list(request.page, request.limit).await.map_err(|err| err.to_string())?;
+            for user in &users {
+                *signups.entry(user.created_at.date_naive()).or_default() += 1;
+            }
+            if request.is_last(users.len()) {
+                break;
+            }
+            request = request.next();
+        }
+
+        let mut table = ReportTable::new(&["date", "signups"]);
+        for (date, count) in signups {
+            table.push(vec![Cell::from(date.to_string()), Cell::from(count)]);
+        }
+        Ok(table)
+    }
+
+    async fn activity_summary(&self) -> Result<ReportTable, String> {
+        let mut table = ReportTable::new(&["user_id", "email", "signed_up_at", "last_seen_at", "active_sessions"]);
+        let mut request = PageRequest::new(1, PAGE_SIZE);
+        loop {
+            let users = self.users.list(request.page, request.limit).await.map_err(|err| err.to_string())?;
+            let ids: Vec<Uuid> = users.iter().map(|user| user.id).collect();
+            let presence = self.presence.presence(&ids).await.map_err(|err| err.to_string())?;
+            for user in &users {
+                let sessions = self.sessions.list_active(user.id).await.map_err(|err| err.to_string())?;
+                let last_seen_at = presence.get(&user.id).and_then(|presence| presence.last_seen_at);
+                table.push(vec![
+                    Cell::from(user.id.to_string()),
+                    Cell::from(user.email.as_str()),
+                    Cell::from(user.created_at.to_rfc3339()),
+                    Cell::from(last_seen_at.map(|at| at.to_rfc3339())),
+                    Cell::from(sessions.len() as i64),
+                ]);
+            }
+            if request.is_last(users.len()) {
+                break;
+            }
+            request = request.next();
+        }
+        Ok(table)
+    }
+
+    /// Post the outcome to the request's callback URL; failures are logged, not retried
+    async fn notify(&self, report: &Report) {
+        let Some(callback_url) = report.callback_url.as_deref() else {
+            return;
+        };
+        let payload = json!({
+            "report_id": report.id,
+            "kind": report.kind,
+            "status": report.status,
+            "requested_by": report.requested_by,
+            "download_url": self.download_url(report),
+            "error": report.error,
+        });
+        match self.http.post(callback_url).json(&payload).send().await {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => {
+                tracing::warn!(report_id = %report.id, status = %response.status(), "Report callback was refused")
+            }
+            Err(err) => tracing::warn!(report_id = %report.id, error = %err, "Report callback failed"),
+        }
+    }
+
+    /// Start draining the queue; only the first call has an effect
+    pub fn spawn_worker(self: &Arc<Self>) {
+        let Some(mut receiver) = self.receiver.lock().unwrap().take() else {
+            return;
+        };
+        let service = Arc::clone(self);
+        service.stats.set_connected(true);
+        tokio::spawn(async move {
+            while let Some(id) = receiver.recv().await {
+                service.stats.started();
+                let started = std::time::Instant::now();
+                let ok = service.run(id).await;
+                service.stats.finished(started.elapsed(), ok);
+            }
+            service.stats.set_connected(false);
+        });
+    }
+}
+
+/// Builds queued reports once the server is about to take traffic
+pub struct ReportWorker {
+    service: Arc<ReportService>,
+}
+
+impl ReportWorker {
+    pub fn new(service: Arc<ReportService>) -> Self {
+        Self { service }
+    }
+}
+
+#[async_trait]
+impl StartupComponent for ReportWorker {
+    fn name(&self) -> &'static str {
+        "report_worker"
+    }
+
+    async fn start(&self) -> Result<(), String> {
+        self.service.spawn_worker();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::report::repository::InMemoryArtifactStore;
+    use crate::domain::session::repository::InMemorySessionStore;
+    use crate::domain::user::entities::User;
+    use crate::domain::user::repository::{InMemoryPresenceStore, InMemoryUserRepository};
+
+    fn service(queue_capacity: usize) -> (ReportService, Arc<InMemoryUserRepository>) {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let ttl = Duration::from_secs(60);
+        let service = ReportService::new(
+            users.clone(),
+            Arc::new(InMemorySessionStore::new()),
+            Arc::new(PresenceTracker::new(Arc::new(InMemoryPresenceStore::new(ttl)), ttl)),
+            Arc::new(InMemoryArtifactStore::new()),
+            DownloadLinks::new("secret", Duration::from_secs(60)),
+            queue_capacity,
+        );
+        (service, users)
+    }
+
+    fn request(kind: ReportKind) -> ReportRequest {
+        ReportRequest {
+            kind,
+            format: ReportFormat::Csv,
+            requested_by: "sam".into(),
+            callback_url: None,
+            origin: Some("http://localhost".into()),
+        }
+    }
+
+    #[tokio::test]
+    async fn queued_reports_are_built_and_downloadable_through_their_signed_link() {
+        let (service, users) = service(1);
+        for email in ["a@example.com", "b@example.com"] {
+            users.save(Arc::new(User::new(email.into(), "hash".into()))).await.unwrap();
+        }
+
+        let report = service.request(request(ReportKind::UsersBySignupDate)).unwrap();
+        assert!(matches!(service.request(request(ReportKind::ActivitySummary)), Err(ReportError::QueueFull)));
+        assert_eq!(report.status, ReportStatus::Queued);
+        assert!(service.download_url(&report).is_none());
+
+        service.run(report.id).await;
+        let report = service.get(report.id).unwrap();
+        assert_eq!((report.status, report.rows), (ReportStatus::Ready, Some(1)));
+
+        let url = service.download_url(&report).unwrap();
+        let query = url.split_once('?').unwrap().1;
+        let (expires, signature) = query.split_once('&').unwrap();
+        let expires: i64 = expires.trim_start_matches("expires=").parse().unwrap();
+        let signature = signature.trim_start_matches("signature=");
+        let (_, contents) = service.download(report.id, expires, signature).await.unwrap();
+        let today = Utc::now().date_naive();
+        assert_eq!(contents, Bytes::from(format!("date,signups\r\n{today},2\r\n")));
+
+        assert!(matches!(service.download(report.id, expires, "00").await, Err(ReportError::Link(LinkError::Invalid))));
+    }
+
+    #[test]
+    fn callbacks_only_go_to_allowed_hosts() {
+        let (closed, _) = service(4);
+        let (open, _) = service(4);
+        let open = open.with_callback_hosts("hooks.example.com, Ops.Example.com");
+        let with_callback = |url: &str| ReportRequest { callback_url: Some(url.to_string()), ..request(ReportKind::ActivitySummary) };
+
+        assert!(open.request(with_callback("https://hooks.example.com/reports")).is_ok());
+        assert!(open.request(with_callback("http://ops.example.com:8080/done")).is_ok());
+        for url in [
+            "http://169.254.169.254/latest/meta-data",
+            "http://localhost:3000/api/admin/users",
+            "https://hooks.example.com.evil.example/reports",
+            "https://user@evil.example/?hooks.example.com",
+            "file:///etc/passwd",
+            "not a url",
+        ] {
+            assert!(matches!(open.request(with_callback(url)), Err(ReportError::CallbackNotAllowed)), "{url}");
+        }
+        assert!(matches!(closed.request(with_callback("https://hooks.example.com/")), Err(ReportError::CallbackNotAllowed)));
+    }
+}
@@ -0,0 +1,114 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Extension,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use super::feature::{LinkError, ReportError, ReportRequest, ReportService};
+use super::model::{CreateReportRequest, ReportResponse};
+use crate::delivery::{url_for, FastJson, PublicUrl, RouteName};
+use crate::infrastructure::FieldErrors;
+use crate::response::{error_response, internal_error_response, not_found_response, respond, success_response, validation_error_response};
+
+/// Queue a report; it is built in the background and the requester is
+/// notified at `callback_url` once it is ready. Callbacks only go to the
+/// hosts in `REPORT_CALLBACK_HOSTS`.
+pub async fn create_report(
+    State(reports): State<Arc<ReportService>>,
+    public_url: Option<Extension<PublicUrl>>,
+    FastJson(payload): FastJson<CreateReportRequest>,
+) -> Result<Response, Response> {
+    if let Err(errors) = payload.validate() {
+        return Err(validation_error_response(&FieldErrors::from(errors)).into_response());
+    }
+
+    let report = reports
+        .request(ReportRequest {
+            kind: payload.kind,
+            format: payload.format,
+            requested_by: payload.requested_by,
+            callback_url: payload.callback_url,
+            origin: public_url.map(|Extension(PublicUrl(origin))| origin.to_string()),
+        })
+        .map_err(|err| match err {
+            ReportError::CallbackNotAllowed => {
+                validation_error_response(&FieldErrors::field("callback_url", err.to_string())).into_response()
+            }
+            err => {
+                let mut response =
+                    error_response(StatusCode::SERVICE_UNAVAILABLE, "SATURATED", err.to_string()).into_response();
+                response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from_static("30"));
+                response
+            }
+        })?;
+
+    tracing::info!(report_id = %report.id, kind = report.kind.name(), requested_by = %report.requested_by, "Report queued");
+    let location = url_for(RouteName::GetReport, &[("id", &report.id)]).unwrap_or_default();
+    Ok(respond(ReportResponse::new(report, None))
+        .status(StatusCode::ACCEPTED)
+        .location(&location)
+        .into_response())
+}
+
+pub async fn get_report(State(reports): State<Arc<ReportService>>, Path(report_id): Path<Uuid>) -> Response {
+    match reports.get(report_id) {
+        Some(report) => {
+            let download_url = reports.download_url(&report);
+            success_response(ReportResponse::new(report, download_url)).into_response()
+        }
+        None => not_found_response("Report").into_response(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DownloadQuery {
+    pub expires: Option<i64>,
+    pub signature: Option<String>,
+}
+
+/// Serve a report's file to whoever holds a valid signed link
+pub async fn download_report(
+    State(reports): State<Arc<ReportService>>,
+    Path(report_id): Path<Uuid>,
+    Query(query): Query<DownloadQuery>,
+) -> Result<Response, Response> {
+    let (Some(expires), Some(signature)) = (query.expires, query.signature) else {
+        return Err(link_error(LinkError::Invalid));
+    };
+    let (report, contents) = reports.download(report_id, expires, &signature).await.map_err(|err| match err {
+        ReportError::Link(err) => link_error(err),
+        ReportError::NotFound => not_found_response("Report").into_response(),
+        ReportError::NotReady => {
+            error_response(StatusCode::CONFLICT, "REPORT_NOT_READY", err.to_string()).into_response()
+        }
+        ReportError::Storage(reason) => {
+            tracing::error!(report_id = %report_id, error = %reason, "Failed to read report artifact");
+            internal_error_response("Failed to read report").into_response()
+        }
+        ReportError::QueueFull | ReportError::CallbackNotAllowed => internal_error_response("Failed to read report").into_response(),
+    })?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, report.format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", report.file_name())),
+            (header::CACHE_CONTROL, "private, no-store".to_string()),
+        ],
+        Body::from(contents),
+    )
+        .into_response())
+}
+
+fn link_error(err: LinkError) -> Response {
+    let status = match err {
+        LinkError::Expired => StatusCode::GONE,
+        LinkError::Invalid => StatusCode::FORBIDDEN,
+    };
+    error_response(status, err.code(), err.to_string()).into_response()
+}
@@ -0,0 +1,5 @@
+pub mod entities;
+pub mod repository;
+pub mod feature;
+pub mod model;
+pub mod handler;
@@ -0,0 +1,19 @@
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::domain::report::entities::{ReportFormat, ReportKind};
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateReportRequest {
+    pub kind: ReportKind,
+
+    pub format: ReportFormat,
+
+    /// Staff member the report is for, recorded with it
+    #[validate(length(min = 1, max = 100, message = "requested_by must be 1 to 100 characters"))]
+    pub requested_by: String,
+
+    /// Posted to with the outcome and a download link once the report is built
+    #[validate(url(message = "callback_url must be an absolute URL"))]
+    pub callback_url: Option<String>,
+}
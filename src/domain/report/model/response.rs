@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::domain::report::entities::{Report, ReportFormat, ReportKind, ReportStatus};
+
+#[derive(Debug, Serialize)]
+pub struct ReportResponse {
+    pub id: Uuid,
+    pub kind: ReportKind,
+    pub format: ReportFormat,
+    pub status: ReportStatus,
+    pub requested_by: String,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub rows: Option<u64>,
+    pub error: Option<String>,
+    /// Signed, expiring link to the file; only once the report is ready
+    pub download_url: Option<String>,
+}
+
+impl ReportResponse {
+    pub fn new(report: Report, download_url: Option<String>) -> Self {
+        Self {
+            id: report.id,
+            kind: report.kind,
+            format: report.format,
+            status: report.status,
+            requested_by: report.requested_by,
+            created_at: report.created_at,
+            completed_at: report.completed_at,
+            rows: report.rows,
+            error: report.error,
+            download_url,
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+
+/// Where built artifacts (report files) are kept until downloaded. Keys are
+/// generated by the caller and safe as file names.
+#[async_trait]
+pub trait ArtifactStore: Send + Sync {
+    async fn put(&self, key: &str, contents: Bytes) -> Result<(), ArtifactStoreError>;
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, ArtifactStoreError>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArtifactStoreError {
+    #[error("Artifact store error: {0}")]
+    Internal(String),
+}
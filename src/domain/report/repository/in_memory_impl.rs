@@ -0,0 +1,29 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+use crate::domain::report::repository::{ArtifactStore, ArtifactStoreError};
+
+#[derive(Default)]
+pub struct InMemoryArtifactStore {
+    artifacts: RwLock<HashMap<String, Bytes>>,
+}
+
+impl InMemoryArtifactStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ArtifactStore for InMemoryArtifactStore {
+    async fn put(&self, key: &str, contents: Bytes) -> Result<(), ArtifactStoreError> {
+        self.artifacts.write().await.insert(key.to_string(), contents);
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, ArtifactStoreError> {
+        Ok(self.artifacts.read().await.get(key).cloned())
+    }
+}
@@ -0,0 +1,41 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::io;
+use std::path::PathBuf;
+
+use crate::domain::report::repository::{ArtifactStore, ArtifactStoreError};
+
+/// Artifacts as files in a local directory, created on first write
+pub struct LocalArtifactStore {
+    dir: PathBuf,
+}
+
+impl LocalArtifactStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl ArtifactStore for LocalArtifactStore {
+    async fn put(&self, key: &str, contents: Bytes) -> Result<(), ArtifactStoreError> {
+        tokio::fs::create_dir_all(&self.dir).await.map_err(internal)?;
+        // Write beside the file and rename, so a crash never leaves a truncated artifact
+        let path = self.dir.join(key);
+        let partial = self.dir.join(format!("{key}.partial"));
+        tokio::fs::write(&partial, &contents).await.map_err(internal)?;
+        tokio::fs::rename(&partial, &path).await.map_err(internal)
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, ArtifactStoreError> {
+        match tokio::fs::read(self.dir.join(key)).await {
+            Ok(contents) => Ok(Some(Bytes::from(contents))),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(internal(err)),
+        }
+    }
+}
+
+fn internal(err: io::Error) -> ArtifactStoreError {
+    ArtifactStoreError::Internal(err.to_string())
+}
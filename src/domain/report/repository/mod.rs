@@ -0,0 +1,7 @@
+pub mod artifact_store;
+pub mod local_impl;
+pub mod in_memory_impl;
+
+pub use artifact_store::*;
+pub use local_impl::*;
+pub use in_memory_impl::*;
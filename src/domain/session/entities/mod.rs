@@ -0,0 +1,7 @@
+pub mod session;
+pub mod principal;
+pub mod scope;
+
+pub use session::*;
+pub use principal::*;
+pub use scope::*;
@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::scope::Scope;
+use super::session::{Session, SessionKind};
+
+/// Facts about the token a request was authenticated with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Claims {
+    pub kind: SessionKind,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Staff member acting as the user, for impersonation tokens
+    pub impersonated_by: Option<String>,
+}
+
+/// Role that opens admin routes to a session, like the admin token does
+pub const ADMIN_ROLE: &str = "admin";
+
+/// The caller behind an authenticated request, resolved from its bearer
+/// token by the auth middleware
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub user_id: Uuid,
+    pub session_id: Uuid,
+    pub roles: Vec<String>,
+    pub tenant: Option<String>,
+    /// `None` unless the token is limited to some scopes
+    pub scopes: Option<Vec<String>>,
+    pub claims: Claims,
+}
+
+impl Principal {
+    pub fn from_session(session: &Session) -> Self {
+        Self {
+            user_id: session.user_id,
+            session_id: session.id,
+            roles: session.roles.clone(),
+            tenant: session.tenant.clone(),
+            scopes: session.scopes.clone(),
+            claims: Claims {
+                kind: session.kind,
+                issued_at: session.created_at,
+                expires_at: session.expires_at,
+                impersonated_by: session.impersonated_by.clone(),
+            },
+        }
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|granted| granted == role)
+    }
+
+    /// Whether the caller may use admin routes; never through impersonation,
+    /// even of an admin
+    pub fn is_admin(&self) -> bool {
+        self.has_role(ADMIN_ROLE) && !self.is_impersonated()
+    }
+
+    /// Whether the token may be used on routes declaring `scope`
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.scopes.as_ref().is_none_or(|granted| granted.iter().any(|granted| scope.granted_by(granted)))
+    }
+
+    /// Whether support staff is acting as this user
+    pub fn is_impersonated(&self) -> bool {
+        self.claims.impersonated_by.is_some()
+    }
+}
@@ -0,0 +1,97 @@
+/// What a route needs of a scoped token, declared in its `RoutePolicy`.
+/// Tokens without scopes (from signing in, impersonation, the admin token)
+/// hold them all; those issued to OpenID clients hold only what was granted.
+/// Admin routes also need the admin token or an `admin` session, which
+/// OpenID clients are never issued.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    UsersRead,
+    UsersWrite,
+    ProductsRead,
+    ProductsWrite,
+    EventsRead,
+    /// Never granted to OpenID clients; held by the admin token and, with
+    /// no scopes of their own, by `admin` sessions
+    AdminRead,
+    AdminWrite,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 7] = [
+        Scope::UsersRead,
+        Scope::UsersWrite,
+        Scope::ProductsRead,
+        Scope::ProductsWrite,
+        Scope::EventsRead,
+        Scope::AdminRead,
+        Scope::AdminWrite,
+    ];
+
+    /// `resource:action`, as in tokens and the OpenAPI spec
+    pub const fn name(self) -> &'static str {
+        match self {
+            Scope::UsersRead => "users:read",
+            Scope::UsersWrite => "users:write",
+            Scope::ProductsRead => "products:read",
+            Scope::ProductsWrite => "products:write",
+            Scope::EventsRead => "events:read",
+            Scope::AdminRead => "admin:read",
+            Scope::AdminWrite => "admin:write",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Scope::UsersRead => "Read users, their overview and presence",
+            Scope::UsersWrite => "Create, update and delete users",
+            Scope::ProductsRead => "Read products",
+            Scope::ProductsWrite => "Create products",
+            Scope::EventsRead => "Subscribe to and poll realtime events",
+            Scope::AdminRead => "Admin reads; held by the admin token and admin sessions, never granted to clients",
+            Scope::AdminWrite => "Admin actions; held by the admin token and admin sessions, never granted to clients",
+        }
+    }
+
+    /// Whether a granted scope covers this one: the same name, or a
+    /// wildcard over its resource such as `users:*`
+    pub fn granted_by(self, granted: &str) -> bool {
+        let name = self.name();
+        granted == name
+            || granted
+                .strip_suffix('*')
+                .is_some_and(|prefix| prefix.ends_with(':') && name.starts_with(prefix))
+    }
+
+    /// Whether tokens issued to users may carry it
+    pub fn user_grantable(self) -> bool {
+        !matches!(self, Scope::AdminRead | Scope::AdminWrite)
+    }
+}
+
+/// The scopes a user may grant that a space-separated `scope` parameter
+/// names, with wildcards expanded. Anything else, e.g. `openid` or
+/// `admin:*`, is left out.
+pub fn requested_scopes(scope: &str) -> Vec<Scope> {
+    Scope::ALL
+        .into_iter()
+        .filter(|wanted| wanted.user_grantable() && scope.split(' ').any(|requested| wanted.granted_by(requested)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards_cover_their_resource_and_admin_scopes_are_never_requested() {
+        assert!(Scope::UsersRead.granted_by("users:read"));
+        assert!(Scope::UsersWrite.granted_by("users:*"));
+        assert!(!Scope::UsersWrite.granted_by("users:read"));
+        assert!(!Scope::UsersRead.granted_by("*"));
+        assert!(!Scope::UsersRead.granted_by("user*"));
+        assert!(Scope::AdminWrite.granted_by("admin:*"));
+
+        assert_eq!(requested_scopes("openid users:* products:read"), [Scope::UsersRead, Scope::UsersWrite, Scope::ProductsRead]);
+        assert!(requested_scopes("openid email admin:* admin:read bogus").is_empty());
+    }
+}
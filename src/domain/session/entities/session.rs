@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionKind {
+    Session,
+    RefreshToken,
+    /// Issued to support staff acting as the user
+    Impersonation,
+}
+
+/// A login session or refresh token issued to a user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub kind: SessionKind,
+    /// Parsed client description, e.g. "Chrome on Windows"
+    pub device: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Staff member acting as the user, for impersonation sessions
+    pub impersonated_by: Option<String>,
+    /// Roles granted to requests made with this session's token
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// Tenant the session was issued for, in multi-tenant deployments
+    #[serde(default)]
+    pub tenant: Option<String>,
+    /// Names of the scopes the token is limited to; `None` for every scope
+    #[serde(default)]
+    pub scopes: Option<Vec<String>>,
+    /// SHA-256 of the bearer token; the token itself is never stored
+    #[serde(skip)]
+    pub token_hash: Option<String>,
+}
+
+impl Session {
+    pub fn new(user_id: Uuid, kind: SessionKind, ttl: chrono::Duration) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            kind,
+            device: None,
+            ip_address: None,
+            created_at: now,
+            last_seen_at: now,
+            expires_at: now + ttl,
+            impersonated_by: None,
+            roles: Vec::new(),
+            tenant: None,
+            scopes: None,
+            token_hash: None,
+        }
+    }
+
+    /// Random opaque bearer token with the given prefix; only its hash is kept on the session
+    pub fn issue_token(&mut self, prefix: &str) -> String {
+        let token = format!("{}{}{}", prefix, Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        self.token_hash = Some(hash_token(&token));
+        token
+    }
+
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at > now
+    }
+}
+
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
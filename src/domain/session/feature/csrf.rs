@@ -0,0 +1,104 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Cookie the session token is read from when there is no bearer token
+pub const SESSION_COOKIE: &str = "session";
+/// Cookie holding the CSRF token; readable by scripts so they can echo it
+pub const CSRF_COOKIE: &str = "csrf_token";
+/// Header unsafe cookie-authenticated requests repeat the CSRF token in
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// Why an unsafe cookie-authenticated request was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum CsrfError {
+    #[error("CSRF token is missing; send the csrf_token cookie value in the X-CSRF-Token header")]
+    Missing,
+    #[error("CSRF token does not match this session")]
+    Invalid,
+}
+
+impl CsrfError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            CsrfError::Missing => "CSRF_TOKEN_MISSING",
+            CsrfError::Invalid => "CSRF_TOKEN_INVALID",
+        }
+    }
+}
+
+/// Signed double-submit CSRF tokens. A token is a random nonce and an
+/// HMAC-SHA256 of the nonce and the session id, so one leaked for another
+/// session, or minted by a script on a sibling domain, does not verify.
+pub struct CsrfTokens {
+    secret: Vec<u8>,
+}
+
+impl CsrfTokens {
+    /// An empty secret is replaced by a random one, which only this process
+    /// can verify against
+    pub fn new(secret: &str) -> Self {
+        let secret = if secret.is_empty() {
+            format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()).into_bytes()
+        } else {
+            secret.as_bytes().to_vec()
+        };
+        Self { secret }
+    }
+
+    pub fn issue(&self, session_id: Uuid) -> String {
+        let nonce = Uuid::new_v4().simple().to_string();
+        let signature = hex::encode(self.mac(&nonce, session_id).finalize().into_bytes());
+        format!("{nonce}.{signature}")
+    }
+
+    /// The cookie and header must carry the same token, signed for `session_id`
+    pub fn verify(&self, session_id: Uuid, cookie: Option<&str>, header: Option<&str>) -> Result<(), CsrfError> {
+        let (Some(cookie), Some(header)) = (cookie, header) else {
+            return Err(CsrfError::Missing);
+        };
+        if !constant_time_eq(cookie.as_bytes(), header.as_bytes()) {
+            return Err(CsrfError::Invalid);
+        }
+        let (nonce, signature) = header.split_once('.').ok_or(CsrfError::Invalid)?;
+        let signature = hex::decode(signature).map_err(|_| CsrfError::Invalid)?;
+        self.mac(nonce, session_id).verify_slice(&signature).map_err(|_| CsrfError::Invalid)
+    }
+
+    fn mac(&self, nonce: &str, session_id: Uuid) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(nonce.as_bytes());
+        mac.update(b".");
+        mac.update(session_id.as_bytes());
+        mac
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_verify_only_for_their_session_when_both_copies_match() {
+        let tokens = CsrfTokens::new("secret");
+        let session_id = Uuid::new_v4();
+        let token = tokens.issue(session_id);
+
+        assert_eq!(tokens.verify(session_id, Some(&token), Some(&token)), Ok(()));
+        assert_eq!(tokens.verify(session_id, Some(&token), None), Err(CsrfError::Missing));
+        assert_eq!(tokens.verify(session_id, None, Some(&token)), Err(CsrfError::Missing));
+        assert_eq!(tokens.verify(Uuid::new_v4(), Some(&token), Some(&token)), Err(CsrfError::Invalid));
+
+        let other = tokens.issue(session_id);
+        assert_eq!(tokens.verify(session_id, Some(&token), Some(&other)), Err(CsrfError::Invalid));
+        let forged = format!("{}.{}", Uuid::new_v4().simple(), "00".repeat(32));
+        assert_eq!(tokens.verify(session_id, Some(&forged), Some(&forged)), Err(CsrfError::Invalid));
+        assert_eq!(CsrfTokens::new("other").verify(session_id, Some(&token), Some(&token)), Err(CsrfError::Invalid));
+    }
+}
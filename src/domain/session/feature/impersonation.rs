@@ -0,0 +1,198 @@
+use axum::http::Method;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::domain::session::entities::{hash_token, Session, SessionKind};
+use crate::domain::session::repository::{SessionStore, SessionStoreError};
+use crate::domain::user::feature::{ServiceError, UserService};
+
+/// Bearer tokens with this prefix are impersonation tokens
+pub const IMPERSONATION_TOKEN_PREFIX: &str = "imp_";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImpersonationError {
+    #[error("User not found")]
+    UserNotFound,
+    #[error("User lookup failed: {0}")]
+    Users(#[from] ServiceError),
+    #[error(transparent)]
+    Store(#[from] SessionStoreError),
+}
+
+/// Identity attached to requests made with an impersonation token, available
+/// to handlers as `Extension<Impersonation>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Impersonation {
+    pub user_id: Uuid,
+    pub session_id: Uuid,
+    pub actor: String,
+}
+
+/// Limits on what support staff can do while acting as a user
+#[derive(Debug, Clone, Copy)]
+pub struct ImpersonationPolicy {
+    /// Non-GET requests other than deletes
+    pub allow_writes: bool,
+}
+
+impl ImpersonationPolicy {
+    /// `Err` carries the reason shown to the caller
+    pub fn check(&self, method: &Method, path: &str) -> Result<(), &'static str> {
+        if path == "/api/admin" || path.starts_with("/api/admin/") {
+            return Err("Admin endpoints are not available while impersonating");
+        }
+        if method == Method::DELETE {
+            return Err("Deletes are not allowed while impersonating");
+        }
+        if !self.allow_writes && !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+            return Err("Impersonated sessions are read-only");
+        }
+        Ok(())
+    }
+}
+
+/// Issues, resolves and ends short-lived impersonation sessions. Every step
+/// is written to the `audit` tracing target.
+pub struct ImpersonationService {
+    sessions: Arc<dyn SessionStore>,
+    users: Arc<dyn UserService>,
+    ttl: chrono::Duration,
+    policy: ImpersonationPolicy,
+}
+
+impl ImpersonationService {
+    pub fn new(
+        sessions: Arc<dyn SessionStore>,
+        users: Arc<dyn UserService>,
+        ttl: chrono::Duration,
+        policy: ImpersonationPolicy,
+    ) -> Self {
+        Self { sessions, users, ttl, policy }
+    }
+
+    pub fn policy(&self) -> ImpersonationPolicy {
+        self.policy
+    }
+
+    /// Returns the bearer token, which is not stored and cannot be shown again
+    pub async fn start(&self, user_id: Uuid, actor: &str, reason: &str) -> Result<(String, Session), ImpersonationError> {
+        if self.users.get_user_by_id(user_id).await?.is_none() {
+            return Err(ImpersonationError::UserNotFound);
+        }
+
+        let mut session = Session::new(user_id, SessionKind::Impersonation, self.ttl);
+        session.impersonated_by = Some(actor.to_string());
+        let token = session.issue_token(IMPERSONATION_TOKEN_PREFIX);
+        self.sessions.save(session.clone()).await?;
+
+        tracing::warn!(
+            target: "audit",
+            audit_event = "impersonation_started",
+            user_id = %user_id,
+            session_id = %session.id,
+            actor = actor,
+            reason = reason,
+            expires_at = %session.expires_at,
+            "Impersonation started"
+        );
+        Ok((token, session))
+    }
+
+    /// `None` for unknown, expired or revoked tokens
+    pub async fn authenticate(&self, token: &str) -> Result<Option<Impersonation>, SessionStoreError> {
+        let session = self.sessions.find_by_token_hash(&hash_token(token)).await?;
+        Ok(session
+            .filter(|session| session.kind == SessionKind::Impersonation)
+            .map(|session| Impersonation {
+                user_id: session.user_id,
+                session_id: session.id,
+                actor: session.impersonated_by.unwrap_or_default(),
+            }))
+    }
+
+    /// Ends every impersonation session for the user; regular sessions are untouched
+    pub async fn stop(&self, user_id: Uuid) -> Result<usize, SessionStoreError> {
+        let mut stopped = 0;
+        for session in self.sessions.list_active(user_id).await? {
+            if session.kind == SessionKind::Impersonation && self.sessions.revoke(user_id, session.id).await? {
+                stopped += 1;
+            }
+        }
+
+        tracing::warn!(
+            target: "audit",
+            audit_event = "impersonation_stopped",
+            user_id = %user_id,
+            sessions = stopped,
+            "Impersonation stopped"
+        );
+        Ok(stopped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::session::repository::InMemorySessionStore;
+    use crate::domain::user::feature::UserServiceImpl;
+    use crate::domain::user::model::CreateUserRequest;
+    use crate::domain::user::repository::InMemoryUserRepository;
+    use crate::infrastructure::CpuPool;
+
+    async fn service() -> (ImpersonationService, Arc<InMemorySessionStore>, Uuid) {
+        let users = Arc::new(UserServiceImpl::new(Arc::new(InMemoryUserRepository::new()), CpuPool::shared_for_tests()));
+        let user = users
+            .create_user(CreateUserRequest { email: "jane@example.com".into(), password: "secret123".into(), metadata: None })
+            .await
+            .unwrap();
+        let sessions = Arc::new(InMemorySessionStore::new());
+        let service = ImpersonationService::new(
+            sessions.clone(),
+            users,
+            chrono::Duration::minutes(15),
+            ImpersonationPolicy { allow_writes: false },
+        );
+        (service, sessions, user.id())
+    }
+
+    #[tokio::test]
+    async fn token_acts_as_the_user_until_stopped() {
+        let (service, sessions, user_id) = service().await;
+        let regular = Session::new(user_id, SessionKind::Session, chrono::Duration::hours(1));
+        sessions.save(regular.clone()).await.unwrap();
+
+        let (token, session) = service.start(user_id, "support@example.com", "ticket 42").await.unwrap();
+        assert!(token.starts_with(IMPERSONATION_TOKEN_PREFIX));
+        assert_eq!(session.token_hash.as_deref(), Some(hash_token(&token).as_str()));
+
+        let impersonation = service.authenticate(&token).await.unwrap().unwrap();
+        assert_eq!(impersonation.user_id, user_id);
+        assert_eq!(impersonation.actor, "support@example.com");
+        assert_eq!(service.authenticate("imp_forged").await.unwrap(), None);
+
+        assert_eq!(service.stop(user_id).await.unwrap(), 1);
+        assert_eq!(service.authenticate(&token).await.unwrap(), None);
+        let remaining: Vec<Uuid> = sessions.list_active(user_id).await.unwrap().iter().map(|session| session.id).collect();
+        assert_eq!(remaining, vec![regular.id]);
+    }
+
+    #[tokio::test]
+    async fn unknown_users_cannot_be_impersonated() {
+        let (service, _, _) = service().await;
+        let err = service.start(Uuid::new_v4(), "support@example.com", "ticket 42").await.unwrap_err();
+        assert!(matches!(err, ImpersonationError::UserNotFound));
+    }
+
+    #[test]
+    fn policy_blocks_admin_deletes_and_optionally_writes() {
+        let read_only = ImpersonationPolicy { allow_writes: false };
+        assert!(read_only.check(&Method::GET, "/api/users/1").is_ok());
+        assert!(read_only.check(&Method::POST, "/api/users").is_err());
+        assert!(read_only.check(&Method::GET, "/api/admin/anomalies").is_err());
+
+        let writable = ImpersonationPolicy { allow_writes: true };
+        assert!(writable.check(&Method::PUT, "/api/users/1").is_ok());
+        assert!(writable.check(&Method::DELETE, "/api/users/1").is_err());
+        assert!(writable.check(&Method::GET, "/api/administrators").is_ok());
+    }
+}
@@ -0,0 +1,7 @@
+pub mod impersonation;
+pub mod csrf;
+pub mod sign_in;
+
+pub use impersonation::*;
+pub use csrf::*;
+pub use sign_in::*;
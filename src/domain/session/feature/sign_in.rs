@@ -0,0 +1,119 @@
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::domain::session::entities::{Session, SessionKind};
+use crate::domain::session::repository::{SessionStore, SessionStoreError};
+use crate::domain::user::feature::{ServiceError, UserService};
+use crate::infrastructure::{GeoLocation, ImpossibleTravel, ImpossibleTravelDetector};
+
+/// Bearer tokens with this prefix are login sessions
+pub const SESSION_TOKEN_PREFIX: &str = "ses_";
+
+#[derive(Debug, thiserror::Error)]
+pub enum SignInError {
+    #[error("Email or password is incorrect")]
+    InvalidCredentials,
+    #[error("User lookup failed: {0}")]
+    Users(#[from] ServiceError),
+    #[error(transparent)]
+    Store(#[from] SessionStoreError),
+}
+
+/// Where a sign-in came from: the client address as resolved by
+/// `client_ip_middleware`, its location and the parsed User-Agent
+#[derive(Debug, Clone, Default)]
+pub struct SignInContext {
+    pub ip: Option<IpAddr>,
+    pub location: Option<GeoLocation>,
+    pub device: Option<String>,
+}
+
+/// A new session and the token shown to the client once
+pub struct SignedIn {
+    pub session: Session,
+    pub token: String,
+    /// Set when this login is implausibly far from the user's previous one
+    pub travel: Option<ImpossibleTravel>,
+}
+
+/// Exchanges an email and password for a login session, and feeds the
+/// login's location to impossible-travel detection
+pub struct SignIn {
+    users: Arc<dyn UserService>,
+    sessions: Arc<dyn SessionStore>,
+    travel: Arc<ImpossibleTravelDetector>,
+    ttl: chrono::Duration,
+}
+
+impl SignIn {
+    pub fn new(
+        users: Arc<dyn UserService>,
+        sessions: Arc<dyn SessionStore>,
+        travel: Arc<ImpossibleTravelDetector>,
+        ttl: chrono::Duration,
+    ) -> Self {
+        Self { users, sessions, travel, ttl }
+    }
+
+    pub fn ttl(&self) -> chrono::Duration {
+        self.ttl
+    }
+
+    pub async fn sign_in(&self, email: &str, password: String, context: SignInContext) -> Result<SignedIn, SignInError> {
+        let user = self.users.verify_credentials(email, password).await?.ok_or(SignInError::InvalidCredentials)?;
+
+        let mut session = Session::new(user.id(), SessionKind::Session, self.ttl);
+        session.device = context.device;
+        session.ip_address = context.ip.map(|ip| ip.to_string());
+        let token = session.issue_token(SESSION_TOKEN_PREFIX);
+        self.sessions.save(session.clone()).await?;
+
+        let travel = context
+            .location
+            .and_then(|location| self.travel.record_login(&user.id().to_string(), &location, Instant::now()));
+        tracing::info!(target: "audit", audit_event = "signed_in", user_id = %user.id(), session_id = %session.id, ip = session.ip_address.as_deref(), "User signed in");
+        Ok(SignedIn { session, token, travel })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::session::entities::hash_token;
+    use crate::domain::session::repository::InMemorySessionStore;
+    use crate::domain::user::feature::UserServiceImpl;
+    use crate::domain::user::model::CreateUserRequest;
+    use crate::domain::user::repository::InMemoryUserRepository;
+    use crate::infrastructure::CpuPool;
+
+    fn at(country: &str, latitude: f64, longitude: f64) -> GeoLocation {
+        GeoLocation { country: Some(country.to_string()), latitude: Some(latitude), longitude: Some(longitude), ..GeoLocation::default() }
+    }
+
+    #[tokio::test]
+    async fn sign_ins_open_sessions_from_the_client_address_and_track_travel() {
+        let sessions: Arc<dyn SessionStore> = Arc::new(InMemorySessionStore::new());
+        let users: Arc<dyn UserService> = Arc::new(UserServiceImpl::new(Arc::new(InMemoryUserRepository::new()), CpuPool::shared_for_tests()));
+        users
+            .create_user(CreateUserRequest { email: "ada@example.com".to_string(), password: "correct horse battery".to_string(), metadata: None })
+            .await
+            .unwrap();
+        let sign_in = SignIn::new(users, sessions.clone(), Arc::new(ImpossibleTravelDetector::new(900.0)), chrono::Duration::days(7));
+
+        let wrong = sign_in.sign_in("ada@example.com", "wrong".to_string(), SignInContext::default()).await;
+        assert!(matches!(wrong, Err(SignInError::InvalidCredentials)));
+        let unknown = sign_in.sign_in("bob@example.com", "correct horse battery".to_string(), SignInContext::default()).await;
+        assert!(matches!(unknown, Err(SignInError::InvalidCredentials)));
+
+        let jakarta = SignInContext { ip: "203.0.113.9".parse().ok(), location: Some(at("ID", -6.2, 106.8)), device: Some("Firefox on Linux".to_string()) };
+        let signed_in = sign_in.sign_in("ada@example.com", "correct horse battery".to_string(), jakarta).await.unwrap();
+        assert!(signed_in.token.starts_with(SESSION_TOKEN_PREFIX) && signed_in.travel.is_none());
+        let stored = sessions.find_by_token_hash(&hash_token(&signed_in.token)).await.unwrap().unwrap();
+        assert_eq!((stored.ip_address.as_deref(), stored.device.as_deref()), (Some("203.0.113.9"), Some("Firefox on Linux")));
+
+        let london = SignInContext { location: Some(at("GB", 51.5, -0.1)), ..SignInContext::default() };
+        let travel = sign_in.sign_in("ada@example.com", "correct horse battery".to_string(), london).await.unwrap().travel.unwrap();
+        assert_eq!((travel.from_country.as_deref(), travel.to_country.as_deref()), (Some("ID"), Some("GB")));
+    }
+}
@@ -0,0 +1,121 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Extension,
+};
+use std::sync::Arc;
+
+use super::feature::{CsrfTokens, SignIn, SignInContext, SignInError, CSRF_COOKIE, SESSION_COOKIE};
+use super::model::{CsrfTokenResponse, SignInRequest, SignInResponse};
+use crate::delivery::{AuthUser, FastJson};
+use crate::infrastructure::GeoLocation;
+use crate::middleware::{ClientInfo, ClientIp};
+use crate::response::{error_response, internal_error_response, success_response};
+
+/// Sign in with an email and password. The token is returned once, and set
+/// as the `session` cookie for browser clients.
+pub async fn sign_in(
+    State(sign_in): State<Arc<SignIn>>,
+    client_ip: Option<Extension<ClientIp>>,
+    location: Option<Extension<GeoLocation>>,
+    client: Option<Extension<ClientInfo>>,
+    FastJson(payload): FastJson<SignInRequest>,
+) -> Response {
+    let context = SignInContext {
+        ip: client_ip.map(|Extension(ClientIp(ip))| ip),
+        location: location.map(|Extension(location)| location),
+        device: client.map(|Extension(client)| format!("{} on {}", client.browser, client.os)),
+    };
+    match sign_in.sign_in(&payload.email, payload.password, context).await {
+        Ok(signed_in) => {
+            let cookie = format!(
+                "{SESSION_COOKIE}={}; Path=/; Max-Age={}; HttpOnly; Secure; SameSite=Lax",
+                signed_in.token,
+                sign_in.ttl().num_seconds()
+            );
+            let body = SignInResponse {
+                token: signed_in.token,
+                session_id: signed_in.session.id,
+                expires_at: signed_in.session.expires_at,
+            };
+            let mut response = success_response(body).into_response();
+            if let Ok(value) = HeaderValue::from_str(&cookie) {
+                response.headers_mut().append(header::SET_COOKIE, value);
+            }
+            response
+        }
+        Err(SignInError::InvalidCredentials) => {
+            error_response(StatusCode::UNAUTHORIZED, "INVALID_CREDENTIALS", "Email or password is incorrect").into_response()
+        }
+        Err(err) => {
+            tracing::error!(error = %err, "Sign-in failed");
+            internal_error_response("Failed to sign in").into_response()
+        }
+    }
+}
+
+/// Issue a CSRF token for the caller's session, as a cookie scripts can read
+/// and in the body. Tokens stay valid for the life of the session, so a page
+/// can fetch one once and send it with every write.
+pub async fn issue_csrf_token(State(tokens): State<Arc<CsrfTokens>>, AuthUser(principal): AuthUser) -> Response {
+    let token = tokens.issue(principal.session_id);
+    let cookie = format!("{CSRF_COOKIE}={token}; Path=/; Secure; SameSite=Strict");
+    let mut response = success_response(CsrfTokenResponse { token, header: "X-CSRF-Token" }).into_response();
+    if let Ok(value) = HeaderValue::from_str(&cookie) {
+        response.headers_mut().append(header::SET_COOKIE, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::session::entities::hash_token;
+    use crate::domain::session::repository::{InMemorySessionStore, SessionStore};
+    use crate::domain::user::feature::{UserService, UserServiceImpl};
+    use crate::domain::user::model::CreateUserRequest;
+    use crate::domain::user::repository::InMemoryUserRepository;
+    use crate::infrastructure::{CpuPool, ImpossibleTravelDetector};
+    use crate::middleware::{client_ip_middleware, TrustedProxies};
+    use axum::{body::Body, extract::ConnectInfo, http::Request, routing::post, Router};
+    use std::net::SocketAddr;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn sign_in_sets_the_session_cookie_and_ignores_spoofed_forwarding_headers() {
+        let sessions: Arc<dyn SessionStore> = Arc::new(InMemorySessionStore::new());
+        let users: Arc<dyn UserService> = Arc::new(UserServiceImpl::new(Arc::new(InMemoryUserRepository::new()), CpuPool::shared_for_tests()));
+        users
+            .create_user(CreateUserRequest { email: "ada@example.com".to_string(), password: "correct horse battery".to_string(), metadata: None })
+            .await
+            .unwrap();
+        let service = SignIn::new(users, sessions.clone(), Arc::new(ImpossibleTravelDetector::new(900.0)), chrono::Duration::hours(1));
+        let app = Router::new()
+            .route("/login", post(sign_in))
+            .with_state(Arc::new(service))
+            .layer(axum::middleware::from_fn_with_state(Arc::new(TrustedProxies::parse("10.0.0.0/8")), client_ip_middleware));
+        let login = |password: &str| {
+            let mut request = Request::post("/login")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header("x-forwarded-for", "198.51.100.1")
+                .body(Body::from(format!(r#"{{"email":"ada@example.com","password":"{password}"}}"#)))
+                .unwrap();
+            request.extensions_mut().insert(ConnectInfo(SocketAddr::from(([203, 0, 113, 9], 40000))));
+            app.clone().oneshot(request)
+        };
+
+        assert_eq!(login("wrong").await.unwrap().status(), StatusCode::UNAUTHORIZED);
+
+        let response = login("correct horse battery").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let cookie = response.headers()[header::SET_COOKIE].to_str().unwrap().to_string();
+        assert!(cookie.contains("HttpOnly") && cookie.contains("Max-Age=3600"));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let token = serde_json::from_slice::<serde_json::Value>(&body).unwrap()["data"]["token"].as_str().unwrap().to_string();
+        assert!(cookie.starts_with(&format!("{SESSION_COOKIE}={token};")));
+
+        let session = sessions.find_by_token_hash(&hash_token(&token)).await.unwrap().unwrap();
+        assert_eq!(session.ip_address.as_deref(), Some("203.0.113.9"));
+    }
+}
@@ -0,0 +1,5 @@
+pub mod request;
+pub mod response;
+
+pub use request::*;
+pub use response::*;
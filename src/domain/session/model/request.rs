@@ -0,0 +1,7 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SignInRequest {
+    pub email: String,
+    pub password: String,
+}
@@ -0,0 +1,19 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize)]
+pub struct CsrfTokenResponse {
+    /// Also set as the `csrf_token` cookie
+    pub token: String,
+    /// Header unsafe requests repeat the token in
+    pub header: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SignInResponse {
+    /// Bearer token of the new session; also set as the `session` cookie
+    pub token: String,
+    pub session_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+}
@@ -0,0 +1,160 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::domain::session::entities::Session;
+use crate::domain::session::repository::{SessionStore, SessionStoreError};
+
+/// Sessions grouped by user so per-user listing and revocation stay cheap,
+/// with an index from token hash so authenticating a request does not scan
+/// every session
+#[derive(Default)]
+pub struct InMemorySessionStore {
+    sessions: RwLock<Sessions>,
+}
+
+#[derive(Default)]
+struct Sessions {
+    by_user: HashMap<Uuid, HashMap<Uuid, Session>>,
+    /// `token_hash -> (user_id, session_id)`, kept in step with `by_user`
+    by_token: HashMap<String, (Uuid, Uuid)>,
+}
+
+impl Sessions {
+    fn unindex<'a>(&mut self, removed: impl IntoIterator<Item = &'a Session>) {
+        for session in removed {
+            if let Some(token_hash) = &session.token_hash {
+                self.by_token.remove(token_hash);
+            }
+        }
+    }
+}
+
+impl InMemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SessionStore for InMemorySessionStore {
+    async fn save(&self, session: Session) -> Result<(), SessionStoreError> {
+        let mut sessions = self.sessions.write().await;
+        let now = Utc::now();
+        let user_sessions = sessions.by_user.entry(session.user_id).or_default();
+        // Drop expired entries for this user while we hold the lock anyway,
+        // and the previous version of this session
+        let stale: Vec<Uuid> = user_sessions
+            .values()
+            .filter(|existing| !existing.is_active(now) || existing.id == session.id)
+            .map(|existing| existing.id)
+            .collect();
+        let removed: Vec<Session> = stale.iter().filter_map(|id| user_sessions.remove(id)).collect();
+        sessions.unindex(&removed);
+        if let Some(token_hash) = &session.token_hash {
+            sessions.by_token.insert(token_hash.clone(), (session.user_id, session.id));
+        }
+        sessions.by_user.entry(session.user_id).or_default().insert(session.id, session);
+        Ok(())
+    }
+
+    async fn list_active(&self, user_id: Uuid) -> Result<Vec<Session>, SessionStoreError> {
+        let now = Utc::now();
+        let mut active: Vec<Session> = self
+            .sessions
+            .read()
+            .await
+            .by_user
+            .get(&user_id)
+            .map(|sessions| sessions.values().filter(|session| session.is_active(now)).cloned().collect())
+            .unwrap_or_default();
+        active.sort_by_key(|session| std::cmp::Reverse(session.last_seen_at));
+        Ok(active)
+    }
+
+    async fn find_by_token_hash(&self, token_hash: &str) -> Result<Option<Session>, SessionStoreError> {
+        let sessions = self.sessions.read().await;
+        let session = sessions
+            .by_token
+            .get(token_hash)
+            .and_then(|(user_id, session_id)| sessions.by_user.get(user_id)?.get(session_id))
+            .filter(|session| session.is_active(Utc::now()))
+            .cloned();
+        Ok(session)
+    }
+
+    async fn revoke(&self, user_id: Uuid, session_id: Uuid) -> Result<bool, SessionStoreError> {
+        let mut sessions = self.sessions.write().await;
+        let Some(session) = sessions.by_user.get_mut(&user_id).and_then(|sessions| sessions.remove(&session_id)) else {
+            return Ok(false);
+        };
+        sessions.unindex([&session]);
+        Ok(session.is_active(Utc::now()))
+    }
+
+    async fn revoke_all(&self, user_id: Uuid) -> Result<usize, SessionStoreError> {
+        let mut sessions = self.sessions.write().await;
+        let Some(removed) = sessions.by_user.remove(&user_id) else {
+            return Ok(0);
+        };
+        sessions.unindex(removed.values());
+        let now = Utc::now();
+        Ok(removed.values().filter(|session| session.is_active(now)).count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::session::entities::{hash_token, SessionKind};
+
+    #[tokio::test]
+    async fn revocation_removes_only_the_targeted_user_sessions() {
+        let store = InMemorySessionStore::new();
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let browser = Session::new(alice, SessionKind::Session, chrono::Duration::hours(1));
+        let refresh = Session::new(alice, SessionKind::RefreshToken, chrono::Duration::days(30));
+        let expired = Session::new(alice, SessionKind::Session, chrono::Duration::seconds(-1));
+        for session in [browser.clone(), refresh.clone(), expired, Session::new(bob, SessionKind::Session, chrono::Duration::hours(1))] {
+            store.save(session).await.unwrap();
+        }
+
+        assert_eq!(store.list_active(alice).await.unwrap().len(), 2);
+        assert!(store.revoke(alice, browser.id).await.unwrap());
+        assert!(!store.revoke(bob, refresh.id).await.unwrap());
+
+        let remaining: Vec<Uuid> = store.list_active(alice).await.unwrap().iter().map(|session| session.id).collect();
+        assert_eq!(remaining, vec![refresh.id]);
+
+        assert_eq!(store.revoke_all(alice).await.unwrap(), 1);
+        assert!(store.list_active(alice).await.unwrap().is_empty());
+        assert_eq!(store.list_active(bob).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn tokens_resolve_through_the_index_until_revoked() {
+        let store = InMemorySessionStore::new();
+        let alice = Uuid::new_v4();
+        let mut browser = Session::new(alice, SessionKind::Session, chrono::Duration::hours(1));
+        let first = hash_token(&browser.issue_token("ses_"));
+        store.save(browser.clone()).await.unwrap();
+        assert_eq!(store.find_by_token_hash(&first).await.unwrap().map(|session| session.id), Some(browser.id));
+
+        // Saving the session again under a new token retires the old one
+        let second = hash_token(&browser.issue_token("ses_"));
+        store.save(browser.clone()).await.unwrap();
+        assert!(store.find_by_token_hash(&first).await.unwrap().is_none());
+        assert!(store.find_by_token_hash(&second).await.unwrap().is_some());
+
+        let mut phone = Session::new(alice, SessionKind::Session, chrono::Duration::hours(1));
+        let third = hash_token(&phone.issue_token("ses_"));
+        store.save(phone.clone()).await.unwrap();
+        assert!(store.revoke(alice, browser.id).await.unwrap());
+        assert!(store.find_by_token_hash(&second).await.unwrap().is_none());
+        assert_eq!(store.revoke_all(alice).await.unwrap(), 1);
+        assert!(store.find_by_token_hash(&third).await.unwrap().is_none());
+        assert!(store.sessions.read().await.by_token.is_empty());
+    }
+}
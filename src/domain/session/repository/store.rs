@@ -0,0 +1,23 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+use crate::domain::session::entities::Session;
+
+/// Sessions and refresh tokens, looked up per user for support and incident response
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    async fn save(&self, session: Session) -> Result<(), SessionStoreError>;
+    /// Unexpired sessions, most recently used first
+    async fn list_active(&self, user_id: Uuid) -> Result<Vec<Session>, SessionStoreError>;
+    /// Active session whose bearer token hashes to `token_hash`
+    async fn find_by_token_hash(&self, token_hash: &str) -> Result<Option<Session>, SessionStoreError>;
+    /// `false` if the user has no such session
+    async fn revoke(&self, user_id: Uuid, session_id: Uuid) -> Result<bool, SessionStoreError>;
+    /// Number of sessions revoked
+    async fn revoke_all(&self, user_id: Uuid) -> Result<usize, SessionStoreError>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SessionStoreError {
+    #[error("Session store error: {0}")]
+    Internal(String),
+}
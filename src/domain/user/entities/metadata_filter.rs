@@ -0,0 +1,106 @@
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+/// Query parameters that filter users by metadata start with this
+pub const METADATA_PREFIX: &str = "metadata.";
+/// Conditions one list request may combine
+pub const MAX_METADATA_FILTERS: usize = 5;
+const MAX_FILTER_VALUE_BYTES: usize = 256;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MetadataFilterError {
+    #[error("At most {MAX_METADATA_FILTERS} metadata filters per request")]
+    TooMany,
+    #[error("`{0}` names no metadata key")]
+    EmptyPath(String),
+    #[error("`{0}` is nested deeper than metadata may be")]
+    TooDeep(String),
+    #[error("Filter values must be at most {MAX_FILTER_VALUE_BYTES} bytes")]
+    ValueTooLong,
+}
+
+/// `metadata.<key>[.<key>...]=<value>` conditions of a user list query; a
+/// user matches when all of them hold.
+///
+/// A condition holds when the value at the path is the given string, or a
+/// number or boolean whose JSON text is the given string. Objects, arrays and
+/// missing keys never match.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetadataFilter {
+    conditions: Vec<(Vec<String>, String)>,
+}
+
+impl MetadataFilter {
+    /// From query parameters; those without the `metadata.` prefix are
+    /// ignored. Paths may not be deeper than `max_depth` keys.
+    pub fn from_query(params: &[(String, String)], max_depth: usize) -> Result<Self, MetadataFilterError> {
+        let mut conditions = Vec::new();
+        for (name, value) in params {
+            let Some(path) = name.strip_prefix(METADATA_PREFIX) else { continue };
+            if conditions.len() == MAX_METADATA_FILTERS {
+                return Err(MetadataFilterError::TooMany);
+            }
+            let path: Vec<String> = path.split('.').map(str::to_string).collect();
+            if path.iter().any(String::is_empty) {
+                return Err(MetadataFilterError::EmptyPath(name.clone()));
+            }
+            if path.len() > max_depth {
+                return Err(MetadataFilterError::TooDeep(name.clone()));
+            }
+            if value.len() > MAX_FILTER_VALUE_BYTES {
+                return Err(MetadataFilterError::ValueTooLong);
+            }
+            conditions.push((path, value.clone()));
+        }
+        Ok(Self { conditions })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    pub fn matches(&self, metadata: &Map<String, Value>) -> bool {
+        self.conditions.iter().all(|(path, expected)| {
+            let (last, parents) = path.split_last().expect("paths have at least one key");
+            let value = parents
+                .iter()
+                .try_fold(metadata, |map, key| map.get(key)?.as_object())
+                .and_then(|map| map.get(last));
+            match value {
+                Some(Value::String(actual)) => actual == expected,
+                Some(Value::Number(actual)) => expected.parse::<serde_json::Number>().is_ok_and(|number| number == *actual),
+                Some(Value::Bool(actual)) => expected.parse::<bool>() == Ok(*actual),
+                _ => false,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn query(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn matches_nested_scalars_and_enforces_limits() {
+        let metadata = json!({ "plan": "pro", "seats": 5, "org": { "beta": true }, "tags": ["a"] });
+        let metadata = metadata.as_object().unwrap();
+        let filter = |pairs| MetadataFilter::from_query(&query(pairs), 3).unwrap();
+
+        assert!(filter(&[("metadata.plan", "pro"), ("metadata.seats", "5"), ("page", "2")]).matches(metadata));
+        assert!(filter(&[("metadata.org.beta", "true")]).matches(metadata));
+        assert!(!filter(&[("metadata.plan", "free")]).matches(metadata));
+        assert!(!filter(&[("metadata.tags", "a")]).matches(metadata));
+        assert!(!filter(&[("metadata.plan.tier", "pro")]).matches(metadata));
+        assert!(filter(&[("page", "1")]).is_empty());
+
+        assert_eq!(MetadataFilter::from_query(&query(&[("metadata.a.b.c.d", "1")]), 3), Err(MetadataFilterError::TooDeep("metadata.a.b.c.d".into())));
+        assert_eq!(MetadataFilter::from_query(&query(&[("metadata.", "1")]), 3), Err(MetadataFilterError::EmptyPath("metadata.".into())));
+        let many: Vec<(String, String)> = (0..=MAX_METADATA_FILTERS).map(|i| (format!("metadata.k{i}"), "v".to_string())).collect();
+        assert_eq!(MetadataFilter::from_query(&many, 3), Err(MetadataFilterError::TooMany));
+    }
+}
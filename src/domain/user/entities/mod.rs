@@ -1,3 +1,5 @@
 pub mod user;
+pub mod metadata_filter;
 
-pub use user::*;
\ No newline at end of file
+pub use user::*;
+pub use metadata_filter::*;
@@ -1,12 +1,17 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+use crate::infrastructure::{Upcaster, Versioned};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     pub id: Uuid,
     pub email: String,
     pub password_hash: String,
+    /// Free-form client data, kept within the `USER_METADATA_*` budget
+    pub metadata: Map<String, Value>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -18,8 +23,80 @@ impl User {
             id: Uuid::new_v4(),
             email,
             password_hash,
+            metadata: Map::new(),
             created_at: now,
             updated_at: now,
         }
     }
-}
\ No newline at end of file
+
+    /// Apply a JSON merge patch (RFC 7396) to `metadata`: `null` removes a
+    /// key, objects merge recursively and anything else replaces the value
+    pub fn patch_metadata(&mut self, patch: &Map<String, Value>) {
+        merge_patch(&mut self.metadata, patch);
+        self.updated_at = Utc::now();
+    }
+}
+
+fn merge_patch(target: &mut Map<String, Value>, patch: &Map<String, Value>) {
+    for (key, value) in patch {
+        match value {
+            Value::Null => {
+                target.remove(key);
+            }
+            Value::Object(nested) => {
+                let entry = target.entry(key.clone()).or_insert_with(|| Value::Object(Map::new()));
+                if !entry.is_object() {
+                    *entry = Value::Object(Map::new());
+                }
+                if let Value::Object(entry) = entry {
+                    merge_patch(entry, nested);
+                }
+            }
+            value => {
+                target.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// Stored users (redb values, exports, snapshots and backups) carry this
+/// version. Changing the serialized fields means bumping it, appending an
+/// upcaster and adding a fixture of the previous version under `fixtures/`.
+impl Versioned for User {
+    const VERSION: u32 = 2;
+    const UPCASTERS: &'static [Upcaster] = &[add_metadata];
+}
+
+/// v1 -> v2: users gained `metadata`
+fn add_metadata(mut record: Map<String, Value>) -> Result<Map<String, Value>, String> {
+    record.entry("metadata").or_insert_with(|| Value::Object(Map::new()));
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::{from_versioned_slice, from_versioned_str, to_versioned_vec};
+    use serde_json::json;
+
+    #[test]
+    fn loads_records_of_every_stored_version() {
+        // Written before records were tagged, by redb and `users export`
+        let v1: User = from_versioned_str(include_str!("fixtures/user_v1.json")).unwrap();
+        assert_eq!(v1.email, "legacy@example.com");
+        assert_eq!(v1.id, Uuid::parse_str("6f1c2d3e-4b5a-4c6d-8e7f-901234567890").unwrap());
+        assert!(v1.metadata.is_empty());
+
+        let current: User = from_versioned_slice(&to_versioned_vec(&v1).unwrap()).unwrap();
+        assert_eq!((current.id, current.email), (v1.id, v1.email));
+    }
+
+    #[test]
+    fn metadata_patches_merge_and_remove_keys() {
+        let object = |value: Value| value.as_object().cloned().unwrap();
+        let mut user = User::new("jane@example.com".to_string(), "hash".to_string());
+        user.patch_metadata(&object(json!({ "plan": "pro", "prefs": { "theme": "dark", "beta": true }, "tags": ["a"] })));
+        user.patch_metadata(&object(json!({ "plan": null, "prefs": { "beta": null, "lang": "de" }, "tags": ["b"] })));
+        assert_eq!(Value::Object(user.metadata), json!({ "prefs": { "theme": "dark", "lang": "de" }, "tags": ["b"] }));
+    }
+}
@@ -0,0 +1,45 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::domain::health::feature::HealthProbe;
+use crate::domain::user::repository::{BloomUserRepository, UserRepository};
+
+/// Health check that the user store answers a cheap query
+pub struct UserRepositoryProbe {
+    repository: Arc<dyn UserRepository>,
+}
+
+impl UserRepositoryProbe {
+    pub fn new(repository: Arc<dyn UserRepository>) -> Self {
+        Self { repository }
+    }
+}
+
+#[async_trait]
+impl HealthProbe for UserRepositoryProbe {
+    async fn check(&self) -> Result<(), String> {
+        self.repository.count().await.map(|_| ()).map_err(|err| err.to_string())
+    }
+}
+
+/// Health check that the signup email bloom filter has been built
+pub struct EmailBloomProbe {
+    repository: Arc<BloomUserRepository>,
+}
+
+impl EmailBloomProbe {
+    pub fn new(repository: Arc<BloomUserRepository>) -> Self {
+        Self { repository }
+    }
+}
+
+#[async_trait]
+impl HealthProbe for EmailBloomProbe {
+    async fn check(&self) -> Result<(), String> {
+        if self.repository.is_ready() {
+            Ok(())
+        } else {
+            Err("filter not built yet; duplicate checks fall through to the repository".to_string())
+        }
+    }
+}
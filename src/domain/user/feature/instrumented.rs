@@ -0,0 +1,49 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use super::{ServiceError, UserService};
+use crate::domain::user::model::{CreateUserRequest, ListUsersRequest, ListUsersResponse, PatchUserRequest, UserResponse};
+use crate::infrastructure::{MethodMetrics, MethodOutcome};
+
+/// Records calls, latency and faults of every `UserService` method under
+/// `user_service.<method>`
+pub struct InstrumentedUserService {
+    inner: Arc<dyn UserService>,
+    metrics: Arc<MethodMetrics>,
+}
+
+impl InstrumentedUserService {
+    pub fn new(inner: Arc<dyn UserService>, metrics: Arc<MethodMetrics>) -> Self {
+        Self { inner, metrics }
+    }
+}
+
+/// Not-found, conflicts and validation errors are answers to the caller
+impl MethodOutcome for ServiceError {
+    fn is_fault(&self) -> bool {
+        matches!(self, ServiceError::Repository(_) | ServiceError::Blocking(_) | ServiceError::PasswordHash(_))
+    }
+}
+
+#[async_trait]
+impl UserService for InstrumentedUserService {
+    async fn create_user(&self, request: CreateUserRequest) -> Result<UserResponse, ServiceError> {
+        self.metrics.measure("user_service.create_user", self.inner.create_user(request)).await
+    }
+
+    async fn get_user_by_id(&self, id: uuid::Uuid) -> Result<Option<UserResponse>, ServiceError> {
+        self.metrics.measure("user_service.get_user_by_id", self.inner.get_user_by_id(id)).await
+    }
+
+    async fn list_users(&self, request: ListUsersRequest) -> Result<ListUsersResponse, ServiceError> {
+        self.metrics.measure("user_service.list_users", self.inner.list_users(request)).await
+    }
+
+    async fn patch_user(&self, id: uuid::Uuid, request: PatchUserRequest) -> Result<UserResponse, ServiceError> {
+        self.metrics.measure("user_service.patch_user", self.inner.patch_user(id, request)).await
+    }
+
+    async fn verify_credentials(&self, email: &str, password: String) -> Result<Option<UserResponse>, ServiceError> {
+        self.metrics.measure("user_service.verify_credentials", self.inner.verify_credentials(email, password)).await
+    }
+}
@@ -1,3 +1,17 @@
 pub mod user_service;
+pub mod instrumented;
+pub mod health_probe;
+pub mod startup;
+pub mod password;
+pub mod overview;
+pub mod ownership;
+pub mod presence;
 
-pub use user_service::*;
\ No newline at end of file
+pub use user_service::*;
+pub use instrumented::*;
+pub use health_probe::*;
+pub use startup::*;
+pub use password::*;
+pub use overview::*;
+pub use ownership::*;
+pub use presence::*;
\ No newline at end of file
@@ -0,0 +1,123 @@
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::UserService;
+use crate::domain::session::entities::{Session, SessionKind};
+use crate::domain::session::repository::SessionStore;
+use crate::domain::user::model::{ImpersonationEntry, SessionsOverview, UserOverviewResponse};
+use crate::infrastructure::{branch, Branch};
+
+#[derive(Debug, thiserror::Error)]
+pub enum OverviewError {
+    #[error("User not found")]
+    NotFound,
+    #[error("User lookup timed out")]
+    TimedOut,
+    #[error("User lookup failed: {0}")]
+    Users(String),
+}
+
+/// Builds `GET /api/users/:id/overview` from several sources queried at
+/// once, each with its own timeout. Only the user record is required; the
+/// other parts are left out when their source is slow or failing.
+pub struct UserOverviewService {
+    users: Arc<dyn UserService>,
+    sessions: Arc<dyn SessionStore>,
+    branch_timeout: Duration,
+}
+
+impl UserOverviewService {
+    pub fn new(users: Arc<dyn UserService>, sessions: Arc<dyn SessionStore>, branch_timeout: Duration) -> Self {
+        Self { users, sessions, branch_timeout }
+    }
+
+    pub async fn overview(&self, user_id: Uuid) -> Result<UserOverviewResponse, OverviewError> {
+        let (user, sessions) = tokio::join!(
+            branch(self.branch_timeout, self.users.get_user_by_id(user_id)),
+            branch(self.branch_timeout, self.sessions.list_active(user_id)),
+        );
+
+        let user = match user {
+            Branch::Ready(Some(user)) => user,
+            Branch::Ready(None) => return Err(OverviewError::NotFound),
+            Branch::TimedOut => return Err(OverviewError::TimedOut),
+            Branch::Failed(error) => return Err(OverviewError::Users(error)),
+        };
+        let mut missing = Vec::new();
+        let sessions = sessions.take("sessions", &mut missing).map(summarize_sessions);
+
+        Ok(UserOverviewResponse { user, sessions, partial: !missing.is_empty(), missing })
+    }
+}
+
+/// `sessions` are most recently used first, as `list_active` returns them
+fn summarize_sessions(sessions: Vec<Session>) -> SessionsOverview {
+    let mut impersonations: Vec<ImpersonationEntry> = sessions
+        .iter()
+        .filter(|session| session.kind == SessionKind::Impersonation)
+        .map(|session| ImpersonationEntry {
+            actor: session.impersonated_by.clone().unwrap_or_default(),
+            started_at: session.created_at,
+            expires_at: session.expires_at,
+        })
+        .collect();
+    impersonations.sort_by_key(|entry| std::cmp::Reverse(entry.started_at));
+
+    SessionsOverview {
+        active: sessions.len(),
+        last_seen_at: sessions.first().map(|session| session.last_seen_at),
+        impersonations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::session::repository::SessionStoreError;
+    use crate::domain::user::feature::UserServiceImpl;
+    use crate::domain::user::model::CreateUserRequest;
+    use crate::domain::user::repository::InMemoryUserRepository;
+    use crate::infrastructure::CpuPool;
+    use async_trait::async_trait;
+
+    /// Hangs on every lookup
+    struct StuckStore;
+
+    #[async_trait]
+    impl SessionStore for StuckStore {
+        async fn save(&self, _session: Session) -> Result<(), SessionStoreError> {
+            Ok(())
+        }
+        async fn list_active(&self, _user_id: Uuid) -> Result<Vec<Session>, SessionStoreError> {
+            std::future::pending().await
+        }
+        async fn find_by_token_hash(&self, _token_hash: &str) -> Result<Option<Session>, SessionStoreError> {
+            std::future::pending().await
+        }
+        async fn revoke(&self, _user_id: Uuid, _session_id: Uuid) -> Result<bool, SessionStoreError> {
+            Ok(false)
+        }
+        async fn revoke_all(&self, _user_id: Uuid) -> Result<usize, SessionStoreError> {
+            Ok(0)
+        }
+    }
+
+    #[tokio::test]
+    async fn slow_sources_leave_a_partial_overview() {
+        let users = Arc::new(UserServiceImpl::new(Arc::new(InMemoryUserRepository::new()), CpuPool::shared_for_tests()));
+        let user = users
+            .create_user(CreateUserRequest { email: "jane@example.com".into(), password: "secret123".into(), metadata: None })
+            .await
+            .unwrap();
+        let service = UserOverviewService::new(users, Arc::new(StuckStore), Duration::from_millis(20));
+
+        let overview = service.overview(user.id()).await.unwrap();
+        assert_eq!(overview.user.id(), user.id());
+        assert!(overview.sessions.is_none() && overview.partial);
+        assert_eq!(overview.missing[0].source, "sessions");
+        assert_eq!(overview.missing[0].reason, "timeout");
+
+        assert!(matches!(service.overview(Uuid::new_v4()).await, Err(OverviewError::NotFound)));
+    }
+}
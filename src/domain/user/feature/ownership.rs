@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::UserService;
+use crate::middleware::{OwnedResource, OwnershipError};
+
+/// Users own their own record
+pub struct UserOwnership {
+    users: Arc<dyn UserService>,
+}
+
+impl UserOwnership {
+    pub fn new(users: Arc<dyn UserService>) -> Self {
+        Self { users }
+    }
+}
+
+#[async_trait]
+impl OwnedResource for UserOwnership {
+    /// Ids that aren't UUIDs are left to the handler to reject
+    async fn owner_of(&self, id: &str) -> Result<Option<Uuid>, OwnershipError> {
+        let Ok(id) = Uuid::parse_str(id) else { return Ok(None) };
+        self.users
+            .get_user_by_id(id)
+            .await
+            .map(|user| user.map(|user| user.id()))
+            .map_err(|err| OwnershipError::Lookup(err.to_string()))
+    }
+}
@@ -0,0 +1,51 @@
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use std::time::Duration;
+
+use super::ServiceError;
+use crate::infrastructure::{CpuPool, Priority};
+
+const HASH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Argon2id PHC string for `password`, computed on the CPU pool. Each hash
+/// holds about 19 MiB, so the pool's thread count also bounds memory use
+/// during a signup burst.
+pub async fn hash_password(pool: &CpuPool, password: String) -> Result<String, ServiceError> {
+    pool.run(Priority::Interactive, "password_hash", HASH_TIMEOUT, move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+    })
+    .await?
+    .map_err(|err| ServiceError::PasswordHash(err.to_string()))
+}
+
+/// Whether `password` matches the PHC string `hash`, checked on the CPU pool.
+/// Hashes that don't parse, such as those of users imported without a
+/// password, never match.
+pub async fn verify_password(pool: &CpuPool, password: String, hash: String) -> Result<bool, ServiceError> {
+    pool.run(Priority::Interactive, "password_verify", HASH_TIMEOUT, move || {
+        PasswordHash::new(&hash).is_ok_and(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+    })
+    .await
+    .map_err(ServiceError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn hashes_are_salted_and_verify() {
+        let pool = CpuPool::new(1, 2, 0);
+        let first = hash_password(&pool, "correct horse".to_string()).await.unwrap();
+        let second = hash_password(&pool, "correct horse".to_string()).await.unwrap();
+        assert_ne!(first, second);
+        assert!(first.starts_with("$argon2id$"));
+
+        assert!(verify_password(&pool, "correct horse".to_string(), first.clone()).await.unwrap());
+        assert!(!verify_password(&pool, "wrong".to_string(), first).await.unwrap());
+        assert!(!verify_password(&pool, "correct horse".to_string(), "hashed_correct horse".to_string()).await.unwrap());
+    }
+}
@@ -0,0 +1,147 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::container::startup::StartupComponent;
+use crate::domain::user::model::{PresenceEvent, PresenceStatus, UserPresence};
+use crate::domain::user::repository::{PresenceStore, PresenceStoreError};
+
+/// Events a subscriber may fall behind by before it starts missing some
+const EVENT_BUFFER: usize = 256;
+
+/// Online / last-seen state of users, fed by `presence_middleware` on every
+/// authenticated request. Changes are broadcast as `PresenceEvent`s for
+/// push channels to fan out; `subscribe` to receive them.
+pub struct PresenceTracker {
+    store: Arc<dyn PresenceStore>,
+    ttl: Duration,
+    events: broadcast::Sender<PresenceEvent>,
+}
+
+impl PresenceTracker {
+    /// Users stay online for `ttl` after their last request
+    pub fn new(store: Arc<dyn PresenceStore>, ttl: Duration) -> Self {
+        Self { store, ttl, events: broadcast::channel(EVENT_BUFFER).0 }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PresenceEvent> {
+        self.events.subscribe()
+    }
+
+    /// Record activity now; failures are logged, since presence must never fail a request
+    pub async fn touch(&self, user_id: Uuid) {
+        let now = Utc::now();
+        match self.store.touch(user_id, now).await {
+            Ok(true) => self.publish(user_id, PresenceStatus::Online, now),
+            Ok(false) => {}
+            Err(err) => tracing::warn!(user_id = %user_id, error = %err, "Failed to record presence"),
+        }
+    }
+
+    /// Presence of each of `user_ids`, offline with no `last_seen_at` for users never seen
+    pub async fn presence(&self, user_ids: &[Uuid]) -> Result<HashMap<Uuid, UserPresence>, PresenceStoreError> {
+        let now = Utc::now();
+        let last_seen = self.store.last_seen(user_ids).await?;
+        Ok(user_ids.iter().map(|id| (*id, self.presence_at(now, last_seen.get(id).copied()))).collect())
+    }
+
+    pub async fn presence_of(&self, user_id: Uuid) -> Result<UserPresence, PresenceStoreError> {
+        let last_seen = self.store.last_seen(&[user_id]).await?;
+        Ok(self.presence_at(Utc::now(), last_seen.get(&user_id).copied()))
+    }
+
+    fn presence_at(&self, now: DateTime<Utc>, last_seen_at: Option<DateTime<Utc>>) -> UserPresence {
+        let online = last_seen_at.is_some_and(|at| (now - at).to_std().map_or(true, |idle| idle < self.ttl));
+        let status = if online { PresenceStatus::Online } else { PresenceStatus::Offline };
+        UserPresence { status, last_seen_at }
+    }
+
+    /// Announce users whose presence lapsed
+    pub async fn sweep(&self) {
+        match self.store.expire(Utc::now()).await {
+            Ok(lapsed) => {
+                for (user_id, last_seen_at) in lapsed {
+                    self.publish(user_id, PresenceStatus::Offline, last_seen_at);
+                }
+            }
+            Err(err) => tracing::warn!(error = %err, "Failed to expire presence"),
+        }
+    }
+
+    pub fn spawn_sweeper(self: &Arc<Self>) {
+        let tracker = Arc::clone(self);
+        tokio::spawn(async move {
+            // Offline events are at most half a TTL late
+            let mut ticker = tokio::time::interval((tracker.ttl / 2).max(Duration::from_secs(1)));
+            loop {
+                ticker.tick().await;
+                tracker.sweep().await;
+            }
+        });
+    }
+
+    fn publish(&self, user_id: Uuid, status: PresenceStatus, last_seen_at: DateTime<Utc>) {
+        // No subscribers is fine
+        let _ = self.events.send(PresenceEvent { user_id, status, last_seen_at });
+    }
+}
+
+/// Starts announcing lapsed presence once the server is about to take traffic
+pub struct PresenceSweeper {
+    tracker: Arc<PresenceTracker>,
+}
+
+impl PresenceSweeper {
+    pub fn new(tracker: Arc<PresenceTracker>) -> Self {
+        Self { tracker }
+    }
+}
+
+#[async_trait]
+impl StartupComponent for PresenceSweeper {
+    fn name(&self) -> &'static str {
+        "presence_sweeper"
+    }
+
+    async fn start(&self) -> Result<(), String> {
+        self.tracker.spawn_sweeper();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user::repository::InMemoryPresenceStore;
+
+    #[tokio::test]
+    async fn coming_online_and_lapsing_are_broadcast_once() {
+        let ttl = Duration::from_millis(50);
+        let tracker = PresenceTracker::new(Arc::new(InMemoryPresenceStore::new(ttl)), ttl);
+        let mut events = tracker.subscribe();
+        let (user, stranger) = (Uuid::new_v4(), Uuid::new_v4());
+
+        tracker.touch(user).await;
+        tracker.touch(user).await;
+        let online = events.try_recv().unwrap();
+        assert_eq!((online.user_id, online.status), (user, PresenceStatus::Online));
+        assert!(events.try_recv().is_err());
+
+        let presence = tracker.presence(&[user, stranger]).await.unwrap();
+        assert_eq!(presence[&user].status, PresenceStatus::Online);
+        assert_eq!(presence[&stranger], UserPresence { status: PresenceStatus::Offline, last_seen_at: None });
+
+        tokio::time::sleep(ttl * 2).await;
+        tracker.sweep().await;
+        tracker.sweep().await;
+        let offline = events.try_recv().unwrap();
+        assert_eq!((offline.user_id, offline.status), (user, PresenceStatus::Offline));
+        assert_eq!(Some(offline.last_seen_at), presence[&user].last_seen_at);
+        assert!(events.try_recv().is_err());
+        assert_eq!(tracker.presence(&[user]).await.unwrap()[&user].status, PresenceStatus::Offline);
+    }
+}
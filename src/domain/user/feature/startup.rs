@@ -0,0 +1,57 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::container::startup::StartupComponent;
+use crate::domain::user::repository::{BloomUserRepository, UserRepository};
+
+/// Verifies the user store is reachable before anything reads from it
+pub struct UserRepositoryStartup {
+    repository: Arc<dyn UserRepository>,
+}
+
+impl UserRepositoryStartup {
+    pub fn new(repository: Arc<dyn UserRepository>) -> Self {
+        Self { repository }
+    }
+}
+
+#[async_trait]
+impl StartupComponent for UserRepositoryStartup {
+    fn name(&self) -> &'static str {
+        "user_repository"
+    }
+
+    async fn start(&self) -> Result<(), String> {
+        self.repository.count().await.map(|_| ()).map_err(|err| err.to_string())
+    }
+}
+
+/// Builds the signup email bloom filter before traffic arrives, then keeps it fresh
+pub struct EmailBloomWarmer {
+    repository: Arc<BloomUserRepository>,
+    rebuild_interval: Duration,
+}
+
+impl EmailBloomWarmer {
+    pub fn new(repository: Arc<BloomUserRepository>, rebuild_interval: Duration) -> Self {
+        Self { repository, rebuild_interval }
+    }
+}
+
+#[async_trait]
+impl StartupComponent for EmailBloomWarmer {
+    fn name(&self) -> &'static str {
+        "email_bloom_filter"
+    }
+
+    fn depends_on(&self) -> &'static [&'static str] {
+        &["user_repository"]
+    }
+
+    async fn start(&self) -> Result<(), String> {
+        self.repository.rebuild().await.map_err(|err| err.to_string())?;
+        self.repository.spawn_rebuilder(self.rebuild_interval);
+        Ok(())
+    }
+}
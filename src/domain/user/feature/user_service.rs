@@ -1,24 +1,114 @@
 use async_trait::async_trait;
 use std::sync::Arc;
+use std::time::Duration;
 use validator::Validate;
-use crate::domain::user::entities::User;
+use serde_json::{Map, Value};
+use crate::domain::user::entities::{MetadataFilter, User};
 use crate::domain::user::repository::UserRepository;
-use crate::domain::user::model::{CreateUserRequest, UserResponse, ListUsersRequest, ListUsersResponse};
+use crate::domain::user::model::{CreateUserRequest, PatchUserRequest, UserResponse, ListUsersRequest, ListUsersResponse};
+use crate::infrastructure::{
+    surrogate_keys, CacheSelector, CdnPurgeClient, CpuPool, FieldErrors, InvalidatableCache, JsonBudget, NoopPurgeClient,
+    TtlCache,
+};
+use crate::pagination::PageRequest;
 
 #[async_trait]
 pub trait UserService: Send + Sync {
     async fn create_user(&self, request: CreateUserRequest) -> Result<UserResponse, ServiceError>;
     async fn get_user_by_id(&self, id: uuid::Uuid) -> Result<Option<UserResponse>, ServiceError>;
     async fn list_users(&self, request: ListUsersRequest) -> Result<ListUsersResponse, ServiceError>;
+    async fn patch_user(&self, id: uuid::Uuid, request: PatchUserRequest) -> Result<UserResponse, ServiceError>;
+    /// The user with this email and password; `None` when either is wrong
+    async fn verify_credentials(&self, email: &str, password: String) -> Result<Option<UserResponse>, ServiceError>;
 }
 
+/// Default limits for user metadata: 4 KiB, 4 levels, 64 keys
+pub const DEFAULT_METADATA_BUDGET: JsonBudget = JsonBudget::new(4096, 4, 64);
+
 pub struct UserServiceImpl {
     repository: Arc<dyn UserRepository>,
+    total_count: TtlCache<(), u64>,
+    total_count_ttl: Duration,
+    cdn: Arc<dyn CdnPurgeClient>,
+    cpu_pool: Arc<CpuPool>,
+    metadata_budget: JsonBudget,
 }
 
 impl UserServiceImpl {
-    pub fn new(repository: Arc<dyn UserRepository>) -> Self {
-        Self { repository }
+    /// Passwords are hashed on `cpu_pool`, the container's shared pool
+    pub fn new(repository: Arc<dyn UserRepository>, cpu_pool: Arc<CpuPool>) -> Self {
+        Self {
+            repository,
+            total_count: TtlCache::new(1),
+            total_count_ttl: Duration::ZERO,
+            cdn: Arc::new(NoopPurgeClient),
+            cpu_pool,
+            metadata_budget: DEFAULT_METADATA_BUDGET,
+        }
+    }
+
+    /// Size, depth and key limits for user metadata; also bounds how deep
+    /// list filters may reach
+    pub fn with_metadata_budget(mut self, budget: JsonBudget) -> Self {
+        self.metadata_budget = budget;
+        self
+    }
+
+    fn check_metadata(&self, metadata: &Map<String, Value>) -> Result<(), ServiceError> {
+        self.metadata_budget
+            .check_object(metadata)
+            .map_err(|err| ServiceError::Validation(FieldErrors::field("metadata", err.to_string())))
+    }
+
+    /// Purge edge-cached responses whenever users change
+    pub fn with_cdn_purge_client(mut self, cdn: Arc<dyn CdnPurgeClient>) -> Self {
+        self.cdn = cdn;
+        self
+    }
+
+    /// Fire-and-forget purge so CDN latency never delays the response
+    fn purge_cdn(&self, keys: Vec<String>) {
+        let cdn = Arc::clone(&self.cdn);
+        tokio::spawn(async move {
+            if let Err(err) = cdn.purge(&keys).await {
+                tracing::warn!(error = %err, keys = ?keys, "Failed to purge CDN cache");
+            }
+        });
+    }
+
+    /// Serve list totals from a cached count refreshed at most once per `ttl`
+    pub fn with_total_count_ttl(mut self, ttl: Duration) -> Self {
+        self.total_count_ttl = ttl;
+        self
+    }
+
+    /// Returns the user total and whether it was freshly counted
+    async fn total_users(&self, exact: bool) -> Result<(u64, bool), ServiceError> {
+        if !exact && !self.total_count_ttl.is_zero() {
+            if let Some(total) = self.total_count.get(&()).await {
+                return Ok((total, false));
+            }
+        }
+
+        let total = self.repository.count().await?;
+        if !self.total_count_ttl.is_zero() {
+            self.total_count.insert((), total, self.total_count_ttl).await;
+        }
+        Ok((total, true))
+    }
+}
+
+/// The cached list total, keyed `users:count` and tagged `users:list`
+#[async_trait]
+impl InvalidatableCache for UserServiceImpl {
+    fn name(&self) -> &'static str {
+        "user_count_cache"
+    }
+
+    async fn invalidate(&self, selector: &CacheSelector) -> usize {
+        self.total_count
+            .remove_matching(|_| selector.matches("users:count", &[surrogate_keys::USERS_LIST]))
+            .await
     }
 }
 
@@ -27,29 +117,25 @@ impl UserService for UserServiceImpl {
     async fn create_user(&self, request: CreateUserRequest) -> Result<UserResponse, ServiceError> {
         // Validate request
         if let Err(validation_errors) = request.validate() {
-            let errors: Vec<String> = validation_errors
-                .field_errors()
-                .iter()
-                .flat_map(|(field, errors)| {
-                    errors.iter().map(move |error| {
-                        format!("{}: {}", field, error.message.as_ref().unwrap_or(&"Invalid value".into()))
-                    })
-                })
-                .collect();
-            return Err(ServiceError::Validation(errors.join(", ")));
+            return Err(ServiceError::Validation(FieldErrors::from(validation_errors)));
         }
 
+        let metadata = request.metadata.unwrap_or_default();
+        self.check_metadata(&metadata)?;
+
         // Check if user already exists
         if self.repository.exists_by_email(&request.email).await? {
             return Err(ServiceError::AlreadyExists);
         }
 
-        // Create new user with password hashing
-        let password_hash = format!("hashed_{}", request.password); // Simplified hashing
-        let user = User::new(request.email, password_hash);
+        let password_hash = super::hash_password(&self.cpu_pool, request.password).await?;
+        let mut user = User::new(request.email, password_hash);
+        user.metadata = metadata;
+        let user = Arc::new(user);
 
         // Save user
-        self.repository.save(&user).await?;
+        self.repository.save(Arc::clone(&user)).await?;
+        self.purge_cdn(vec![surrogate_keys::USERS_LIST.to_string(), surrogate_keys::user(user.id)]);
 
         Ok(UserResponse::from(user))
     }
@@ -62,19 +148,52 @@ impl UserService for UserServiceImpl {
     }
 
     async fn list_users(&self, request: ListUsersRequest) -> Result<ListUsersResponse, ServiceError> {
-        let page = request.page.unwrap_or(1).max(1);
-        let limit = request.limit.unwrap_or(10).min(100).max(1);
+        let PageRequest { page, limit } = PageRequest::from_query(request.page, request.limit);
+        let filter = MetadataFilter::from_query(&request.filters, self.metadata_budget.max_depth)
+            .map_err(|err| ServiceError::Validation(FieldErrors::field("metadata", err.to_string())))?;
 
-        let (users, total) = self.repository.list(page, limit).await?;
+        // Filtered totals are counted along with the page, so always exact
+        let (users, total, total_exact) = if filter.is_empty() {
+            let users = self.repository.list(page, limit).await?;
+            let (total, total_exact) = self.total_users(request.exact.unwrap_or(false)).await?;
+            (users, total, total_exact)
+        } else {
+            let (users, total) = self.repository.list_matching(&filter, page, limit).await?;
+            (users, total, true)
+        };
         let user_responses: Vec<UserResponse> = users.into_iter().map(UserResponse::from).collect();
 
         Ok(ListUsersResponse {
             users: user_responses,
             total,
+            total_exact,
             page,
             limit,
         })
     }
+
+    async fn patch_user(&self, id: uuid::Uuid, request: PatchUserRequest) -> Result<UserResponse, ServiceError> {
+        let existing = self.repository.find_by_id(id).await?.ok_or(ServiceError::NotFound)?;
+        let mut user = User::clone(&existing);
+        if let Some(patch) = &request.metadata {
+            user.patch_metadata(patch);
+            self.check_metadata(&user.metadata)?;
+        }
+
+        let user = Arc::new(user);
+        self.repository.save(Arc::clone(&user)).await?;
+        self.purge_cdn(vec![surrogate_keys::USERS_LIST.to_string(), surrogate_keys::user(user.id)]);
+
+        Ok(UserResponse::from(user))
+    }
+
+    async fn verify_credentials(&self, email: &str, password: String) -> Result<Option<UserResponse>, ServiceError> {
+        let Some(user) = self.repository.find_by_email(email).await? else {
+            return Ok(None);
+        };
+        let matches = super::verify_password(&self.cpu_pool, password, user.password_hash.clone()).await?;
+        Ok(matches.then(|| UserResponse::from(user)))
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -84,7 +203,72 @@ pub enum ServiceError {
     #[error("User already exists")]
     AlreadyExists,
     #[error("Validation error: {0}")]
-    Validation(String),
+    Validation(FieldErrors),
     #[error("Repository error: {0}")]
     Repository(#[from] crate::domain::user::repository::RepositoryError),
-}
\ No newline at end of file
+    #[error(transparent)]
+    Blocking(#[from] crate::infrastructure::BlockingError),
+    #[error("Password hashing failed: {0}")]
+    PasswordHash(String),
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user::repository::InMemoryUserRepository;
+    use crate::infrastructure::CdnPurgeError;
+    use std::sync::Mutex;
+
+    /// Records the keys of every purge
+    #[derive(Default)]
+    struct RecordingPurgeClient(Mutex<Vec<Vec<String>>>);
+
+    #[async_trait]
+    impl CdnPurgeClient for RecordingPurgeClient {
+        async fn purge(&self, keys: &[String]) -> Result<(), CdnPurgeError> {
+            self.0.lock().unwrap().push(keys.to_vec());
+            Ok(())
+        }
+    }
+
+    fn list(exact: Option<bool>) -> ListUsersRequest {
+        ListUsersRequest { page: None, limit: None, exact, filters: Vec::new() }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn list_totals_are_cached_until_the_ttl_unless_exact_is_asked_for() {
+        let repository = Arc::new(InMemoryUserRepository::new());
+        let service = UserServiceImpl::new(repository.clone(), CpuPool::shared_for_tests()).with_total_count_ttl(Duration::from_secs(60));
+        repository.save(Arc::new(User::new("ada@example.com".into(), "hash".into()))).await.unwrap();
+
+        let first = service.list_users(list(None)).await.unwrap();
+        assert_eq!((first.total, first.total_exact), (1, true));
+
+        repository.save(Arc::new(User::new("bob@example.com".into(), "hash".into()))).await.unwrap();
+        let cached = service.list_users(list(None)).await.unwrap();
+        assert_eq!((cached.total, cached.total_exact), (1, false));
+
+        // exact=true counts afresh and refreshes the cache
+        let exact = service.list_users(list(Some(true))).await.unwrap();
+        assert_eq!((exact.total, exact.total_exact), (2, true));
+        repository.save(Arc::new(User::new("cy@example.com".into(), "hash".into()))).await.unwrap();
+        assert_eq!(service.list_users(list(None)).await.unwrap().total, 2);
+
+        tokio::time::advance(Duration::from_secs(61)).await;
+        let expired = service.list_users(list(Some(false))).await.unwrap();
+        assert_eq!((expired.total, expired.total_exact), (3, true));
+    }
+
+    #[tokio::test]
+    async fn user_writes_purge_the_list_and_the_user_from_the_cdn() {
+        let cdn = Arc::new(RecordingPurgeClient::default());
+        let service = UserServiceImpl::new(Arc::new(InMemoryUserRepository::new()), CpuPool::shared_for_tests()).with_cdn_purge_client(cdn.clone());
+
+        let request = CreateUserRequest { email: "ada@example.com".into(), password: "correct horse battery".into(), metadata: None };
+        let id = service.create_user(request).await.unwrap().id();
+        service.patch_user(id, PatchUserRequest { metadata: Some(Map::new()) }).await.unwrap();
+        tokio::task::yield_now().await;
+
+        let keys = vec![surrogate_keys::USERS_LIST.to_string(), surrogate_keys::user(id)];
+        assert_eq!(*cdn.0.lock().unwrap(), [keys.clone(), keys]);
+    }
+}
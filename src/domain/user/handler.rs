@@ -1,5 +1,6 @@
 use axum::{
-    extract::{Path, State, Query},
+    extract::{FromRef, Path, State, Query},
+    http::{header, HeaderValue, StatusCode},
     response::{Response, IntoResponse},
     Json,
 };
@@ -7,27 +8,55 @@ use std::sync::Arc;
 use uuid::Uuid;
 use validator::Validate;
 
-use super::feature::UserService;
-use super::model::{CreateUserRequest, ListUsersRequest};
-use crate::response::{success_response, not_found_response, bad_request_response};
+use super::feature::{OverviewError, PresenceTracker, UserOverviewService, UserService};
+use super::model::{CreateUserRequest, ListUsersRequest, ListUsersResponse, PatchUserRequest};
+use crate::delivery::{url_for, FastJson, RouteName};
+use crate::error::AppError;
+use crate::infrastructure::{surrogate_keys, BlockingError, FieldErrors};
+use crate::response::{created_response, success_response, pooled_success_response, not_found_response, error_response, validation_error_response, with_surrogate_keys, ListEnvelope, Meta};
+
+/// State of the user routes; handlers extract the parts they need
+#[derive(Clone)]
+pub struct UsersState {
+    pub users: Arc<dyn UserService>,
+    pub presence: Arc<PresenceTracker>,
+}
+
+impl FromRef<UsersState> for Arc<dyn UserService> {
+    fn from_ref(state: &UsersState) -> Self {
+        state.users.clone()
+    }
+}
+
+impl FromRef<UsersState> for Arc<PresenceTracker> {
+    fn from_ref(state: &UsersState) -> Self {
+        state.presence.clone()
+    }
+}
 
 pub async fn create_user(
     State(user_service): State<Arc<dyn UserService>>,
-    Json(payload): Json<CreateUserRequest>,
+    FastJson(payload): FastJson<CreateUserRequest>,
 ) -> Result<Response, Response> {
-    // Log request body in debug mode
-    let correlation_id = uuid::Uuid::new_v4().to_string();
-    if let Ok(body_str) = serde_json::to_string(&payload) {
-        crate::middleware::log_request_body(&correlation_id, "create_user", &body_str);
-    }
-
     match user_service.create_user(payload).await {
-        Ok(user_response) => Ok(success_response(user_response).into_response()),
-        Err(super::feature::ServiceError::AlreadyExists) => {
-            Err(bad_request_response("User with this email already exists").into_response())
+        Ok(user_response) => {
+            let location = url_for(RouteName::GetUser, &[("id", &user_response.id())]).ok();
+            Ok(created_response(&user_response, location).into_response())
         }
-        Err(super::feature::ServiceError::Validation(msg)) => {
-            Err(bad_request_response(&msg).into_response())
+        Err(err @ super::feature::ServiceError::AlreadyExists) => Err(AppError::from(err).into_response()),
+        Err(super::feature::ServiceError::Validation(errors)) => {
+            Err(validation_error_response(&errors).into_response())
+        }
+        // Password hashing is queued on the CPU pool; a full queue sheds load
+        Err(super::feature::ServiceError::Blocking(BlockingError::Saturated { .. })) => {
+            let mut response = error_response(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "SATURATED",
+                "Server is busy, please retry shortly",
+            )
+            .into_response();
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from_static("1"));
+            Err(response)
         }
         Err(_) => {
             Err(crate::response::internal_error_response("Failed to create user").into_response())
@@ -40,7 +69,10 @@ pub async fn get_user(
     Path(user_id): Path<Uuid>,
 ) -> Result<Response, Response> {
     match user_service.get_user_by_id(user_id).await {
-        Ok(Some(user_response)) => Ok(success_response(user_response).into_response()),
+        Ok(Some(user_response)) => Ok(with_surrogate_keys(
+            pooled_success_response(user_response),
+            &[surrogate_keys::user(user_id)],
+        )),
         Ok(None) => Err(not_found_response("User").into_response()),
         Err(_) => Err(crate::response::internal_error_response("Failed to retrieve user").into_response()),
     }
@@ -48,50 +80,140 @@ pub async fn get_user(
 
 pub async fn list_users(
     State(user_service): State<Arc<dyn UserService>>,
+    State(presence): State<Arc<PresenceTracker>>,
     Query(params): Query<ListUsersParams>,
+    Query(filters): Query<Vec<(String, String)>>,
+    envelope: ListEnvelope,
 ) -> Result<Response, Response> {
+    let include_presence = match params.include.as_deref() {
+        None | Some("") => false,
+        Some("presence") => true,
+        Some(other) => {
+            let errors = FieldErrors::field("include", format!("Unknown include `{other}`; expected `presence`"));
+            return Err(validation_error_response(&errors).into_response());
+        }
+    };
     let request = ListUsersRequest {
         page: params.page,
         limit: params.limit,
+        exact: params.exact,
+        filters,
     };
 
     match user_service.list_users(request).await {
-        Ok(response) => Ok(success_response(response).into_response()),
+        Ok(mut response) => {
+            if include_presence {
+                response = with_presence(response, &presence).await?;
+            }
+            let meta = Meta::new(response.page, response.limit, response.total)
+                .with_total_exact(response.total_exact);
+            let keys: Vec<String> = std::iter::once(surrogate_keys::USERS_LIST.to_string())
+                .chain(response.users.iter().map(|user| surrogate_keys::user(user.id())))
+                .collect();
+            let mut response =
+                with_surrogate_keys(envelope.respond(response, |response| &response.users, Some(meta)), &keys);
+            if include_presence {
+                // Presence changes aren't purged from the CDN like user writes are
+                response.headers_mut().insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+            }
+            Ok(response)
+        }
+        Err(super::feature::ServiceError::Validation(errors)) => {
+            Err(validation_error_response(&errors).into_response())
+        }
         Err(_) => Err(crate::response::internal_error_response("Failed to list users").into_response()),
     }
 }
 
-pub async fn update_user(
+async fn with_presence(mut response: ListUsersResponse, presence: &PresenceTracker) -> Result<ListUsersResponse, Response> {
+    let ids: Vec<Uuid> = response.users.iter().map(|user| user.id()).collect();
+    let mut presence = presence.presence(&ids).await.map_err(|err| {
+        tracing::error!(error = %err, "Failed to read presence");
+        crate::response::internal_error_response("Failed to read presence").into_response()
+    })?;
+    response.users = response
+        .users
+        .into_iter()
+        .map(|user| match presence.remove(&user.id()) {
+            Some(presence) => user.with_presence(presence),
+            None => user,
+        })
+        .collect();
+    Ok(response)
+}
+
+/// Whether the user is online and when they were last seen
+pub async fn get_user_presence(
     State(user_service): State<Arc<dyn UserService>>,
+    State(presence): State<Arc<PresenceTracker>>,
     Path(user_id): Path<Uuid>,
-    Json(_payload): Json<serde_json::Value>,
 ) -> Result<Response, Response> {
-    // Check if user exists first
     match user_service.get_user_by_id(user_id).await {
-        Ok(Some(_user)) => {
-            Err(bad_request_response("Update functionality not implemented yet").into_response())
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(not_found_response("User").into_response()),
+        Err(_) => return Err(crate::response::internal_error_response("Failed to retrieve user").into_response()),
+    }
+    match presence.presence_of(user_id).await {
+        Ok(presence) => Ok(success_response(presence).into_response()),
+        Err(err) => {
+            tracing::error!(user_id = %user_id, error = %err, "Failed to read presence");
+            Err(crate::response::internal_error_response("Failed to read presence").into_response())
+        }
+    }
+}
+
+/// The user's profile with what other sources know about them, gathered
+/// concurrently; sources that time out or fail are listed in `missing`
+pub async fn get_user_overview(
+    State(overview): State<Arc<UserOverviewService>>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Response, Response> {
+    match overview.overview(user_id).await {
+        Ok(response) => Ok(success_response(response).into_response()),
+        Err(OverviewError::NotFound) => Err(not_found_response("User").into_response()),
+        Err(OverviewError::TimedOut) => {
+            Err(error_response(StatusCode::GATEWAY_TIMEOUT, "TIMEOUT", "User lookup timed out").into_response())
+        }
+        Err(OverviewError::Users(_)) => {
+            Err(crate::response::internal_error_response("Failed to retrieve user").into_response())
         }
-        Ok(None) => Err(not_found_response("User").into_response()),
-        Err(_) => Err(crate::response::internal_error_response("Failed to update user").into_response()),
     }
 }
 
-pub async fn delete_user(
+/// Change the fields present in the body; `metadata` is a JSON merge patch
+pub async fn patch_user(
     State(user_service): State<Arc<dyn UserService>>,
     Path(user_id): Path<Uuid>,
+    FastJson(payload): FastJson<PatchUserRequest>,
 ) -> Result<Response, Response> {
-    // Check if user exists first
-    match user_service.get_user_by_id(user_id).await {
-        Ok(Some(_user)) => {
-            Err(bad_request_response("Delete functionality not implemented yet").into_response())
+    match user_service.patch_user(user_id, payload).await {
+        Ok(user_response) => Ok(success_response(user_response).into_response()),
+        Err(super::feature::ServiceError::NotFound) => Err(not_found_response("User").into_response()),
+        Err(super::feature::ServiceError::Validation(errors)) => {
+            Err(validation_error_response(&errors).into_response())
         }
-        Ok(None) => Err(not_found_response("User").into_response()),
-        Err(_) => Err(crate::response::internal_error_response("Failed to delete user").into_response()),
+        Err(_) => Err(crate::response::internal_error_response("Failed to update user").into_response()),
     }
 }
 
+/// Stub; use `patch_user` for partial updates
+pub async fn update_user(
+    Path(_user_id): Path<Uuid>,
+    Json(_payload): Json<serde_json::Value>,
+) -> AppError {
+    AppError::NotImplemented("Replacing users".to_string())
+}
+
+/// Stub
+pub async fn delete_user(Path(_user_id): Path<Uuid>) -> AppError {
+    AppError::NotImplemented("Deleting users".to_string())
+}
+
 #[derive(serde::Deserialize)]
 pub struct ListUsersParams {
     pub page: Option<u32>,
     pub limit: Option<u32>,
+    pub exact: Option<bool>,
+    /// `presence` adds each user's presence
+    pub include: Option<String>,
 }
\ No newline at end of file
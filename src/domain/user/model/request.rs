@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use validator::Validate;
 
 #[derive(Debug, Deserialize, Serialize, Validate)]
@@ -8,10 +9,24 @@ pub struct CreateUserRequest {
 
     #[validate(length(min = 6, message = "Password must be at least 6 characters"))]
     pub password: String,
+
+    /// Checked against the metadata budget by the service
+    #[serde(default)]
+    pub metadata: Option<Map<String, Value>>,
+}
+
+/// Only the fields present change
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct PatchUserRequest {
+    /// JSON merge patch for the user's metadata; `null` values remove keys
+    pub metadata: Option<Map<String, Value>>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
 pub struct ListUsersRequest {
     pub page: Option<u32>,
     pub limit: Option<u32>,
+    pub exact: Option<bool>,
+    /// Query parameters; the `metadata.*` ones filter by metadata
+    pub filters: Vec<(String, String)>,
 }
\ No newline at end of file
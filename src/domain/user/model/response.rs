@@ -1,30 +1,138 @@
-use serde::{Deserialize, Serialize};
-use uuid::Uuid;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
 use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use crate::domain::user::entities::User;
+use crate::infrastructure::MissingPart;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Public view of a user.
+///
+/// Shares the stored entity instead of copying its fields and serializes
+/// straight from it, leaving out `password_hash`.
+#[derive(Debug, Clone)]
 pub struct UserResponse {
-    pub id: Uuid,
-    pub email: String,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
-}
-
-impl From<crate::domain::user::entities::User> for UserResponse {
-    fn from(user: crate::domain::user::entities::User) -> Self {
-        Self {
-            id: user.id,
-            email: user.email,
-            created_at: user.created_at,
-            updated_at: user.updated_at,
+    user: Arc<User>,
+    /// Only when asked for, e.g. `?include=presence` on the list
+    presence: Option<UserPresence>,
+}
+
+impl UserResponse {
+    pub fn id(&self) -> uuid::Uuid {
+        self.user.id
+    }
+
+    pub fn email(&self) -> &str {
+        &self.user.email
+    }
+
+    pub fn with_presence(mut self, presence: UserPresence) -> Self {
+        self.presence = Some(presence);
+        self
+    }
+}
+
+impl Serialize for UserResponse {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("UserResponse", 5 + usize::from(self.presence.is_some()))?;
+        state.serialize_field("id", &self.user.id)?;
+        state.serialize_field("email", &self.user.email)?;
+        state.serialize_field("metadata", &self.user.metadata)?;
+        state.serialize_field("created_at", &self.user.created_at)?;
+        state.serialize_field("updated_at", &self.user.updated_at)?;
+        if let Some(presence) = &self.presence {
+            state.serialize_field("presence", presence)?;
         }
+        state.end()
+    }
+}
+
+impl From<Arc<User>> for UserResponse {
+    fn from(user: Arc<User>) -> Self {
+        Self { user, presence: None }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PresenceStatus {
+    Online,
+    Offline,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct UserPresence {
+    pub status: PresenceStatus,
+    /// `null` when the user was never seen by this deployment
+    pub last_seen_at: Option<DateTime<Utc>>,
+}
+
+/// Broadcast when a user comes online or their presence lapses
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct PresenceEvent {
+    pub user_id: uuid::Uuid,
+    pub status: PresenceStatus,
+    pub last_seen_at: DateTime<Utc>,
+}
+
+#[derive(Debug, serde::Serialize)]
 pub struct ListUsersResponse {
     pub users: Vec<UserResponse>,
     pub total: u64,
+    pub total_exact: bool,
     pub page: u32,
     pub limit: u32,
-}
\ No newline at end of file
+}
+
+/// A user together with what other sources know about them. Parts whose
+/// source timed out or failed are `null` and listed in `missing`.
+#[derive(Debug, serde::Serialize)]
+pub struct UserOverviewResponse {
+    pub user: UserResponse,
+    pub sessions: Option<SessionsOverview>,
+    /// Some parts are missing
+    pub partial: bool,
+    pub missing: Vec<MissingPart>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct SessionsOverview {
+    /// Sessions and refresh tokens, including impersonations
+    pub active: usize,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    /// Support staff currently acting as the user, most recent first
+    pub impersonations: Vec<ImpersonationEntry>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ImpersonationEntry {
+    pub actor: String,
+    pub started_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user::repository::{InMemoryUserRepository, UserRepository};
+
+    #[tokio::test]
+    async fn listed_users_share_the_stored_entity_and_serialize_without_the_hash() {
+        let repository = InMemoryUserRepository::new();
+        let mut user = User::new("ada@example.com".into(), "argon2-hash".into());
+        user.metadata.insert("plan".into(), serde_json::json!("pro"));
+        let stored = Arc::new(user);
+        repository.save(Arc::clone(&stored)).await.unwrap();
+
+        let listed = repository.list(1, 10).await.unwrap();
+        assert!(Arc::ptr_eq(&listed[0], &stored));
+        let response = UserResponse::from(listed.into_iter().next().unwrap());
+        assert!(Arc::ptr_eq(&response.user, &stored));
+
+        let body = serde_json::to_value(&response).unwrap();
+        assert_eq!(body["id"], serde_json::json!(stored.id));
+        assert_eq!(body["email"], "ada@example.com");
+        assert_eq!(body["metadata"], serde_json::json!({ "plan": "pro" }));
+        assert_eq!(body["created_at"], serde_json::json!(stored.created_at));
+        assert!(body.get("password_hash").is_none() && body.get("presence").is_none());
+        assert_eq!(body.as_object().unwrap().len(), 5);
+    }
+}
@@ -0,0 +1,422 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::domain::user::entities::{MetadataFilter, User};
+use crate::domain::user::repository::UserRepository;
+use crate::domain::user::repository::RepositoryError;
+use crate::infrastructure::sync::{AtomicBool, Mutex, Ordering, RwLock};
+use crate::infrastructure::BloomFilter;
+use crate::pagination::PageRequest;
+
+const REBUILD_PAGE_SIZE: u32 = 1000;
+
+/// Bloom filter guard in front of `exists_by_email`.
+///
+/// An email the filter has never seen is definitely absent, so the signup
+/// duplicate check can answer `false` without asking the repository. Until
+/// the first build completes every call is passed through.
+pub struct BloomUserRepository {
+    inner: Arc<dyn UserRepository>,
+    emails: EmailFilter,
+    // Only one rebuild may own the pending buffer at a time
+    rebuild_lock: tokio::sync::Mutex<()>,
+    expected_items: usize,
+    false_positive_rate: f64,
+}
+
+impl BloomUserRepository {
+    pub fn new(inner: Arc<dyn UserRepository>, expected_items: usize, false_positive_rate: f64) -> Self {
+        Self {
+            inner,
+            emails: EmailFilter::new(BloomFilter::new(expected_items, false_positive_rate)),
+            rebuild_lock: tokio::sync::Mutex::new(()),
+            expected_items,
+            false_positive_rate,
+        }
+    }
+
+    /// Rebuild the filter from every user in the underlying repository
+    pub async fn rebuild(&self) -> Result<(), RepositoryError> {
+        let _rebuilding = self.rebuild_lock.lock().await;
+        self.emails.begin_rebuild();
+
+        let mut filter = BloomFilter::new(self.expected_items, self.false_positive_rate);
+        let mut request = PageRequest::new(1, REBUILD_PAGE_SIZE);
+        let result = loop {
+            match self.inner.list(request.page, request.limit).await {
+                Ok(users) => {
+                    for user in &users {
+                        filter.insert(user.email.as_str());
+                    }
+                    if request.is_last(users.len()) {
+                        break Ok(filter);
+                    }
+                    request = request.next();
+                }
+                Err(err) => break Err(err),
+            }
+        };
+
+        let replayed = self.emails.finish_rebuild(result)?;
+        tracing::debug!(replayed, "Rebuilt email bloom filter");
+        Ok(())
+    }
+
+    /// Whether the first build has completed and lookups are being filtered
+    pub fn is_ready(&self) -> bool {
+        self.emails.ready.load(Ordering::Acquire)
+    }
+
+    /// Spawn a task that rebuilds the filter once every interval; the initial
+    /// build is left to the startup graph
+    pub fn spawn_rebuilder(self: &Arc<Self>, interval: Duration) {
+        let repository = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+            loop {
+                ticker.tick().await;
+                if let Err(err) = repository.rebuild().await {
+                    tracing::warn!(error = %err, "Failed to rebuild email bloom filter");
+                }
+            }
+        });
+    }
+}
+
+#[async_trait]
+impl UserRepository for BloomUserRepository {
+    async fn save(&self, user: Arc<User>) -> Result<(), RepositoryError> {
+        // Record the email before it becomes visible so the filter never lags behind
+        let email = user.email.clone();
+        self.emails.begin_save(&email);
+        let result = self.inner.save(user).await;
+
+        // Record it again in case a rebuild swapped the filter in the meantime
+        self.emails.finish_save(email);
+        result
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Arc<User>>, RepositoryError> {
+        self.inner.find_by_id(id).await
+    }
+
+    async fn find_by_email(&self, email: &str) -> Result<Option<Arc<User>>, RepositoryError> {
+        self.inner.find_by_email(email).await
+    }
+
+    async fn exists_by_email(&self, email: &str) -> Result<bool, RepositoryError> {
+        if !self.emails.might_contain(email) {
+            return Ok(false);
+        }
+        self.inner.exists_by_email(email).await
+    }
+
+    async fn list(&self, page: u32, limit: u32) -> Result<Vec<Arc<User>>, RepositoryError> {
+        self.inner.list(page, limit).await
+    }
+
+    async fn count(&self) -> Result<u64, RepositoryError> {
+        self.inner.count().await
+    }
+
+    async fn list_matching(
+        &self,
+        filter: &MetadataFilter,
+        page: u32,
+        limit: u32,
+    ) -> Result<(Vec<Arc<User>>, u64), RepositoryError> {
+        self.inner.list_matching(filter, page, limit).await
+    }
+}
+
+/// The filter plus the bookkeeping that keeps it consistent with concurrent
+/// saves while a rebuild is scanning the repository. Kept free of async so the
+/// protocol can be model-checked with loom.
+struct EmailFilter {
+    filter: RwLock<BloomFilter>,
+    // Emails whose save is between `begin_save` and `finish_save`
+    in_flight: Mutex<Vec<String>>,
+    // Emails saved while a rebuild is in progress, replayed into the new filter
+    pending: Mutex<Option<Vec<String>>>,
+    ready: AtomicBool,
+}
+
+impl EmailFilter {
+    fn new(filter: BloomFilter) -> Self {
+        Self {
+            filter: RwLock::new(filter),
+            in_flight: Mutex::new(Vec::new()),
+            pending: Mutex::new(None),
+            ready: AtomicBool::new(false),
+        }
+    }
+
+    fn begin_save(&self, email: &str) {
+        let mut filter = self.filter.write().unwrap();
+        filter.insert(email);
+        self.in_flight.lock().unwrap().push(email.to_string());
+    }
+
+    /// Insert an email whose save has completed, buffering it for an
+    /// in-progress rebuild whose scan may have missed it
+    fn finish_save(&self, email: String) {
+        let mut filter = self.filter.write().unwrap();
+        filter.insert(email.as_str());
+
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(index) = in_flight.iter().position(|saving| *saving == email) {
+            in_flight.swap_remove(index);
+        }
+        if let Some(pending) = self.pending.lock().unwrap().as_mut() {
+            pending.push(email);
+        }
+    }
+
+    fn begin_rebuild(&self) {
+        *self.pending.lock().unwrap() = Some(Vec::new());
+    }
+
+    /// Swap in the rebuilt filter, returning how many emails were replayed into it.
+    ///
+    /// An email missing from the scan was either saved during the rebuild
+    /// (pending) or is still being saved (in flight); both are replayed.
+    fn finish_rebuild<E>(&self, rebuilt: Result<BloomFilter, E>) -> Result<usize, E> {
+        // Swap under the filter lock so no concurrent save can slip between the two
+        let mut current = self.filter.write().unwrap();
+        let mut replay = self.in_flight.lock().unwrap().clone();
+        replay.extend(self.pending.lock().unwrap().take().unwrap_or_default());
+        let mut filter = rebuilt?;
+
+        for email in &replay {
+            filter.insert(email.as_str());
+        }
+        *current = filter;
+        drop(current);
+        self.ready.store(true, Ordering::Release);
+        Ok(replay.len())
+    }
+
+    /// False only when the email is definitely absent
+    fn might_contain(&self, email: &str) -> bool {
+        !self.ready.load(Ordering::Acquire) || self.filter.read().unwrap().contains(email)
+    }
+}
+
+#[cfg(all(test, not(loom_model)))]
+mod tests {
+    use super::*;
+    use crate::domain::user::repository::InMemoryUserRepository;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::time::advance;
+
+    /// Counts full-table scans so tests can see when a rebuild ran
+    struct CountingRepository {
+        inner: InMemoryUserRepository,
+        rebuilds: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl UserRepository for CountingRepository {
+        async fn save(&self, user: Arc<User>) -> Result<(), RepositoryError> {
+            self.inner.save(user).await
+        }
+
+        async fn find_by_id(&self, id: Uuid) -> Result<Option<Arc<User>>, RepositoryError> {
+            self.inner.find_by_id(id).await
+        }
+
+        async fn find_by_email(&self, email: &str) -> Result<Option<Arc<User>>, RepositoryError> {
+            self.inner.find_by_email(email).await
+        }
+
+        async fn exists_by_email(&self, email: &str) -> Result<bool, RepositoryError> {
+            self.inner.exists_by_email(email).await
+        }
+
+        async fn list(&self, page: u32, limit: u32) -> Result<Vec<Arc<User>>, RepositoryError> {
+            if page == 1 {
+                self.rebuilds.fetch_add(1, Ordering::SeqCst);
+            }
+            self.inner.list(page, limit).await
+        }
+
+        async fn count(&self) -> Result<u64, RepositoryError> {
+            self.inner.count().await
+        }
+
+        async fn list_matching(
+            &self,
+            filter: &MetadataFilter,
+            page: u32,
+            limit: u32,
+        ) -> Result<(Vec<Arc<User>>, u64), RepositoryError> {
+            self.inner.list_matching(filter, page, limit).await
+        }
+    }
+
+    /// Let spawned tasks run to their next await point without moving the clock
+    async fn settle() {
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rebuilder_fires_on_every_interval() {
+        let counting = Arc::new(CountingRepository {
+            inner: InMemoryUserRepository::new(),
+            rebuilds: AtomicUsize::new(0),
+        });
+        let repository = Arc::new(BloomUserRepository::new(counting.clone(), 100, 0.01));
+
+        repository.spawn_rebuilder(Duration::from_secs(300));
+        settle().await;
+        assert_eq!(counting.rebuilds.load(Ordering::SeqCst), 0);
+
+        advance(Duration::from_secs(299)).await;
+        settle().await;
+        assert_eq!(counting.rebuilds.load(Ordering::SeqCst), 0);
+
+        advance(Duration::from_secs(1)).await;
+        settle().await;
+        assert_eq!(counting.rebuilds.load(Ordering::SeqCst), 1);
+
+        advance(Duration::from_secs(600)).await;
+        settle().await;
+        // Missed ticks are caught up in a burst, one rebuild each
+        assert_eq!(counting.rebuilds.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn filter_is_not_trusted_until_first_rebuild_completes() {
+        let inner = Arc::new(InMemoryUserRepository::new());
+        inner.save(Arc::new(User::new("existing@example.com".into(), "hash".into()))).await.unwrap();
+        let repository = Arc::new(BloomUserRepository::new(inner, 100, 0.01));
+
+        // Before the first build every lookup falls through to the repository
+        assert!(repository.exists_by_email("existing@example.com").await.unwrap());
+
+        repository.rebuild().await.unwrap();
+        assert!(repository.is_ready());
+        assert!(repository.exists_by_email("existing@example.com").await.unwrap());
+        assert!(!repository.exists_by_email("new@example.com").await.unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stored_emails_are_never_filtered_out() {
+        let inner = Arc::new(InMemoryUserRepository::new());
+        // More than one rebuild page, written behind the filter's back
+        for index in 0..(REBUILD_PAGE_SIZE as usize * 2 + 500) {
+            inner.save(Arc::new(User::new(format!("bulk{index}@example.com"), "hash".into()))).await.unwrap();
+        }
+        let repository = Arc::new(BloomUserRepository::new(inner.clone(), 5000, 0.01));
+        repository.rebuild().await.unwrap();
+
+        // Saves through the filter are seen at once
+        for index in 0..100 {
+            repository.save(Arc::new(User::new(format!("signup{index}@example.com"), "hash".into()))).await.unwrap();
+        }
+        for index in 0..(REBUILD_PAGE_SIZE as usize * 2 + 500) {
+            assert!(repository.exists_by_email(&format!("bulk{index}@example.com")).await.unwrap(), "bulk{index}");
+        }
+        for index in 0..100 {
+            assert!(repository.exists_by_email(&format!("signup{index}@example.com")).await.unwrap(), "signup{index}");
+        }
+
+        // Writes that bypass the filter are picked up by the next periodic rebuild
+        inner.save(Arc::new(User::new("imported@example.com".into(), "hash".into()))).await.unwrap();
+        assert!(!repository.exists_by_email("imported@example.com").await.unwrap());
+        repository.spawn_rebuilder(Duration::from_secs(300));
+        settle().await;
+        advance(Duration::from_secs(300)).await;
+        settle().await;
+        assert!(repository.exists_by_email("imported@example.com").await.unwrap());
+    }
+}
+
+/// Run with `RUSTFLAGS="--cfg loom_model" cargo test --release bloom_impl::loom_tests`
+#[cfg(all(test, loom_model))]
+mod loom_tests {
+    use super::*;
+    use crate::infrastructure::sync::model;
+    use loom::sync::Arc;
+    use loom::thread;
+
+    fn empty_filter() -> BloomFilter {
+        BloomFilter::new(16, 0.01)
+    }
+
+    /// Mirrors `BloomUserRepository::save` against a store
+    fn save(emails: &EmailFilter, store: &Mutex<Vec<String>>, email: &str) {
+        emails.begin_save(email);
+        store.lock().unwrap().push(email.to_string());
+        emails.finish_save(email.to_string());
+    }
+
+    /// Mirrors `BloomUserRepository::rebuild` against a store
+    fn rebuild(emails: &EmailFilter, store: &Mutex<Vec<String>>) {
+        emails.begin_rebuild();
+        let mut filter = empty_filter();
+        for email in store.lock().unwrap().iter() {
+            filter.insert(email.as_str());
+        }
+        emails.finish_rebuild::<()>(Ok(filter)).unwrap();
+    }
+
+    #[test]
+    fn stored_email_is_never_reported_absent() {
+        model(|| {
+            let emails = Arc::new(EmailFilter::new(empty_filter()));
+            let store = Arc::new(Mutex::new(vec!["existing@example.com".to_string()]));
+            rebuild(&emails, &store);
+
+            let saver = {
+                let (emails, store) = (emails.clone(), store.clone());
+                thread::spawn(move || save(&emails, &store, "new@example.com"))
+            };
+            let checker = {
+                let (emails, store) = (emails.clone(), store.clone());
+                thread::spawn(move || {
+                    // Whatever the store already holds must pass the filter
+                    let stored = store.lock().unwrap().clone();
+                    for email in &stored {
+                        assert!(emails.might_contain(email), "{} stored but filtered out", email);
+                    }
+                })
+            };
+            rebuild(&emails, &store);
+
+            saver.join().unwrap();
+            checker.join().unwrap();
+            for email in store.lock().unwrap().iter() {
+                assert!(emails.might_contain(email));
+            }
+        });
+    }
+
+    #[test]
+    fn concurrent_saves_survive_a_rebuild() {
+        model(|| {
+            let emails = Arc::new(EmailFilter::new(empty_filter()));
+            let store = Arc::new(Mutex::new(Vec::new()));
+
+            let savers: Vec<_> = ["a@example.com", "b@example.com"]
+                .into_iter()
+                .map(|email| {
+                    let (emails, store) = (emails.clone(), store.clone());
+                    thread::spawn(move || save(&emails, &store, email))
+                })
+                .collect();
+            rebuild(&emails, &store);
+
+            for saver in savers {
+                saver.join().unwrap();
+            }
+            assert!(emails.might_contain("a@example.com"));
+            assert!(emails.might_contain("b@example.com"));
+            assert!(emails.in_flight.lock().unwrap().is_empty());
+        });
+    }
+}
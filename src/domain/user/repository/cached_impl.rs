@@ -0,0 +1,334 @@
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::domain::health::feature::DegradationSwitch;
+use crate::domain::user::entities::{MetadataFilter, User};
+use crate::domain::user::repository::UserRepository;
+use crate::domain::user::repository::RepositoryError;
+use crate::infrastructure::{surrogate_keys, CacheSelector, InvalidatableCache, MemoryReclaimer, ShardRing, Sharded, TtlCache};
+
+type UserCache = TtlCache<Uuid, Option<Arc<User>>>;
+
+/// Write generations are kept per stripe of ids rather than per id, so they
+/// take fixed memory
+const GENERATION_STRIPES: usize = 64;
+
+/// Caching layer in front of another UserRepository.
+///
+/// Lookups by id are cached, including misses: a "not found" result is
+/// remembered for a short TTL so repeated requests for the same unknown id
+/// don't reach the underlying repository. Saves invalidate the entry, and
+/// bump a write generation so a lookup that read the repository before the
+/// save drops what it cached instead of keeping the old value for its TTL.
+/// Entries are spread over shards by a `ShardRing`, each with its own lock,
+/// so concurrent lookups of different users rarely wait on each other.
+pub struct CachedUserRepository {
+    inner: Arc<dyn UserRepository>,
+    by_id: Sharded<UserCache>,
+    max_entries: usize,
+    ttl: Duration,
+    negative_ttl: Duration,
+    /// Saves seen per stripe of ids; see `generation`
+    generations: Box<[AtomicU64]>,
+    /// On while the repository is down and may be stood in for
+    degradation: Option<Arc<DegradationSwitch>>,
+}
+
+impl CachedUserRepository {
+    pub fn new(
+        inner: Arc<dyn UserRepository>,
+        ttl: Duration,
+        negative_ttl: Duration,
+        max_entries: usize,
+    ) -> Self {
+        Self {
+            inner,
+            by_id: shards(1, max_entries),
+            max_entries,
+            ttl,
+            negative_ttl,
+            generations: (0..GENERATION_STRIPES).map(|_| AtomicU64::new(0)).collect(),
+            degradation: None,
+        }
+    }
+
+    /// While the switch is on, lookups by id are answered from expired
+    /// entries that are still cached instead of the repository
+    pub fn with_degradation(mut self, switch: Arc<DegradationSwitch>) -> Self {
+        self.degradation = Some(switch);
+        self
+    }
+
+    /// Split the cache into `count` shards sharing `max_entries`
+    pub fn with_shards(mut self, count: usize) -> Self {
+        self.by_id = shards(count, self.max_entries);
+        self
+    }
+
+    /// For `ShardRegistry::register`
+    pub fn ring(&self) -> Arc<ShardRing> {
+        self.by_id.ring()
+    }
+
+    fn degraded(&self) -> bool {
+        self.degradation.as_ref().is_some_and(|switch| switch.is_active())
+    }
+
+    fn generation(&self, id: &Uuid) -> &AtomicU64 {
+        &self.generations[(id.as_u128() % GENERATION_STRIPES as u128) as usize]
+    }
+
+    fn shard(&self, id: &Uuid) -> Arc<UserCache> {
+        self.by_id.get(id.as_bytes()).expect("the user cache has at least one shard")
+    }
+}
+
+fn shards(count: usize, max_entries: usize) -> Sharded<UserCache> {
+    let count = count.max(1);
+    let per_shard = max_entries.div_ceil(count);
+    Sharded::new("user_cache", (0..count).map(|index| (format!("shard-{index}"), TtlCache::new(per_shard))))
+}
+
+/// Cached lookups are dropped under memory pressure and refilled on demand
+#[async_trait]
+impl MemoryReclaimer for CachedUserRepository {
+    fn name(&self) -> &'static str {
+        "user_cache"
+    }
+
+    async fn shrink(&self) -> usize {
+        let mut dropped = 0;
+        for shard in self.by_id.backends() {
+            dropped += shard.clear().await;
+        }
+        dropped
+    }
+}
+
+/// Entries are keyed and tagged by the user's surrogate key, `user:<id>`
+#[async_trait]
+impl InvalidatableCache for CachedUserRepository {
+    fn name(&self) -> &'static str {
+        "user_cache"
+    }
+
+    async fn invalidate(&self, selector: &CacheSelector) -> usize {
+        let mut removed = 0;
+        for shard in self.by_id.backends() {
+            removed += shard
+                .remove_matching(|id| {
+                    let key = surrogate_keys::user(*id);
+                    selector.matches(&key, &[&key])
+                })
+                .await;
+        }
+        removed
+    }
+}
+
+#[async_trait]
+impl UserRepository for CachedUserRepository {
+    async fn save(&self, user: Arc<User>) -> Result<(), RepositoryError> {
+        let id = user.id;
+        self.inner.save(user).await?;
+        // Bumped before the removal: a lookup that inserts after it sees the
+        // new generation and takes its entry back out
+        self.generation(&id).fetch_add(1, Ordering::SeqCst);
+        self.shard(&id).remove(&id).await;
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Arc<User>>, RepositoryError> {
+        let shard = self.shard(&id);
+        if let Some(cached) = shard.get(&id).await {
+            return Ok(cached);
+        }
+
+        if self.degraded() {
+            if let Some(stale) = shard.get_stale(&id).await {
+                return Ok(stale);
+            }
+        }
+
+        let generation = self.generation(&id).load(Ordering::SeqCst);
+        let user = self.inner.find_by_id(id).await?;
+        let ttl = if user.is_some() { self.ttl } else { self.negative_ttl };
+        if !ttl.is_zero() {
+            shard.insert(id, user.clone(), ttl).await;
+            // A save landed while the repository was read; what was read may predate it
+            if self.generation(&id).load(Ordering::SeqCst) != generation {
+                shard.remove(&id).await;
+            }
+        }
+
+        Ok(user)
+    }
+
+    async fn find_by_email(&self, email: &str) -> Result<Option<Arc<User>>, RepositoryError> {
+        self.inner.find_by_email(email).await
+    }
+
+    async fn exists_by_email(&self, email: &str) -> Result<bool, RepositoryError> {
+        self.inner.exists_by_email(email).await
+    }
+
+    async fn list(&self, page: u32, limit: u32) -> Result<Vec<Arc<User>>, RepositoryError> {
+        self.inner.list(page, limit).await
+    }
+
+    async fn count(&self) -> Result<u64, RepositoryError> {
+        self.inner.count().await
+    }
+
+    async fn list_matching(
+        &self,
+        filter: &MetadataFilter,
+        page: u32,
+        limit: u32,
+    ) -> Result<(Vec<Arc<User>>, u64), RepositoryError> {
+        self.inner.list_matching(filter, page, limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user::repository::InMemoryUserRepository;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Counts the lookups by id that reach the repository. A lookup returns
+    /// only once `hold` is free, so tests can stall it after the read.
+    struct CountingRepository {
+        inner: InMemoryUserRepository,
+        lookups: AtomicUsize,
+        hold: tokio::sync::Mutex<()>,
+    }
+
+    fn counting() -> Arc<CountingRepository> {
+        Arc::new(CountingRepository {
+            inner: InMemoryUserRepository::new(),
+            lookups: AtomicUsize::new(0),
+            hold: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    #[async_trait]
+    impl UserRepository for CountingRepository {
+        async fn save(&self, user: Arc<User>) -> Result<(), RepositoryError> {
+            self.inner.save(user).await
+        }
+
+        async fn find_by_id(&self, id: Uuid) -> Result<Option<Arc<User>>, RepositoryError> {
+            let user = self.inner.find_by_id(id).await;
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            let _held = self.hold.lock().await;
+            user
+        }
+
+        async fn find_by_email(&self, email: &str) -> Result<Option<Arc<User>>, RepositoryError> {
+            self.inner.find_by_email(email).await
+        }
+
+        async fn exists_by_email(&self, email: &str) -> Result<bool, RepositoryError> {
+            self.inner.exists_by_email(email).await
+        }
+
+        async fn list(&self, page: u32, limit: u32) -> Result<Vec<Arc<User>>, RepositoryError> {
+            self.inner.list(page, limit).await
+        }
+
+        async fn count(&self) -> Result<u64, RepositoryError> {
+            self.inner.count().await
+        }
+
+        async fn list_matching(
+            &self,
+            filter: &MetadataFilter,
+            page: u32,
+            limit: u32,
+        ) -> Result<(Vec<Arc<User>>, u64), RepositoryError> {
+            self.inner.list_matching(filter, page, limit).await
+        }
+    }
+
+    #[tokio::test]
+    async fn misses_are_remembered_until_the_user_is_created() {
+        let inner = counting();
+        let cache = CachedUserRepository::new(inner.clone(), Duration::from_secs(60), Duration::from_secs(5), 100);
+        let user = User::new("ada@example.com".to_string(), "hash".to_string());
+        let id = user.id;
+
+        // Repeated misses on the same id reach the repository once
+        assert!(cache.find_by_id(id).await.unwrap().is_none());
+        assert!(cache.find_by_id(id).await.unwrap().is_none());
+        assert_eq!(inner.lookups.load(Ordering::SeqCst), 1);
+
+        // Creating the user drops the remembered miss
+        cache.save(Arc::new(user)).await.unwrap();
+        assert_eq!(cache.find_by_id(id).await.unwrap().unwrap().email, "ada@example.com");
+        assert_eq!(inner.lookups.load(Ordering::SeqCst), 2);
+
+        // and the hit is cached in turn
+        assert!(cache.find_by_id(id).await.unwrap().is_some());
+        assert_eq!(inner.lookups.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn a_miss_that_read_before_a_create_is_not_remembered() {
+        let inner = counting();
+        let cache = Arc::new(CachedUserRepository::new(inner.clone(), Duration::from_secs(60), Duration::from_secs(5), 100));
+        let user = User::new("ada@example.com".to_string(), "hash".to_string());
+        let id = user.id;
+
+        // The lookup reads "not found", then stalls before caching it
+        let held = inner.hold.lock().await;
+        let lookup = tokio::spawn({
+            let cache = cache.clone();
+            async move { cache.find_by_id(id).await }
+        });
+        while inner.lookups.load(Ordering::SeqCst) == 0 {
+            tokio::task::yield_now().await;
+        }
+        cache.save(Arc::new(user)).await.unwrap();
+        drop(held);
+        assert!(lookup.await.unwrap().unwrap().is_none());
+
+        // The stale miss was not kept for the negative TTL
+        assert_eq!(cache.find_by_id(id).await.unwrap().unwrap().email, "ada@example.com");
+        assert_eq!(inner.lookups.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn remembered_misses_expire_after_the_negative_ttl() {
+        let inner = counting();
+        let cache = CachedUserRepository::new(inner.clone(), Duration::from_secs(60), Duration::from_secs(5), 100);
+        let id = Uuid::new_v4();
+
+        assert!(cache.find_by_id(id).await.unwrap().is_none());
+        tokio::time::advance(Duration::from_secs(6)).await;
+        assert!(cache.find_by_id(id).await.unwrap().is_none());
+        assert_eq!(inner.lookups.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn lookups_spread_over_the_shards_and_invalidation_reaches_them_all() {
+        let inner = Arc::new(InMemoryUserRepository::new());
+        let cache = CachedUserRepository::new(inner, Duration::from_secs(60), Duration::from_secs(5), 100).with_shards(4);
+        let ids: Vec<Uuid> = (0..40).map(|_| Uuid::new_v4()).collect();
+        for id in &ids {
+            assert!(cache.find_by_id(*id).await.unwrap().is_none());
+        }
+
+        let stats = cache.ring().stats();
+        assert_eq!(stats.name, "user_cache");
+        assert_eq!(stats.nodes.len(), 4);
+        assert!(stats.nodes.iter().filter(|node| node.picks > 0).count() > 1, "{stats:?}");
+
+        let selector = CacheSelector { prefixes: vec!["user:".to_string()], ..CacheSelector::default() };
+        assert_eq!(cache.invalidate(&selector).await, ids.len());
+        assert_eq!(cache.shrink().await, 0);
+    }
+}
@@ -0,0 +1,12 @@
+use crate::domain::user::entities::User;
+use crate::domain::user::repository::RepositoryError;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub async fn count_users(
+    users: Arc<RwLock<HashMap<uuid::Uuid, Arc<User>>>>,
+) -> Result<u64, RepositoryError> {
+    let user_map = users.read().await;
+    Ok(user_map.len() as u64)
+}
@@ -4,7 +4,7 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 
 pub async fn user_exists_by_email(
-    users: Arc<RwLock<HashMap<uuid::Uuid, crate::domain::user::entities::User>>>,
+    users: Arc<RwLock<HashMap<uuid::Uuid, Arc<crate::domain::user::entities::User>>>>,
     email: &str,
 ) -> Result<bool, RepositoryError> {
     let user_map = users.read().await;
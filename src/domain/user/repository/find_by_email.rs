@@ -5,9 +5,9 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 
 pub async fn find_user_by_email(
-    users: Arc<RwLock<HashMap<uuid::Uuid, User>>>,
+    users: Arc<RwLock<HashMap<uuid::Uuid, Arc<User>>>>,
     email: &str,
-) -> Result<Option<User>, RepositoryError> {
+) -> Result<Option<Arc<User>>, RepositoryError> {
     let user_map = users.read().await;
     Ok(user_map
         .values()
@@ -5,9 +5,9 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 
 pub async fn find_user_by_id(
-    users: Arc<RwLock<HashMap<uuid::Uuid, User>>>,
+    users: Arc<RwLock<HashMap<uuid::Uuid, Arc<User>>>>,
     id: uuid::Uuid,
-) -> Result<Option<User>, RepositoryError> {
+) -> Result<Option<Arc<User>>, RepositoryError> {
     let user_map = users.read().await;
     Ok(user_map.get(&id).cloned())
 }
\ No newline at end of file
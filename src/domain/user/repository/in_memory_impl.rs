@@ -4,7 +4,7 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
-use crate::domain::user::entities::User;
+use crate::domain::user::entities::{MetadataFilter, User};
 use crate::domain::user::repository::UserRepository;
 use crate::domain::user::repository::RepositoryError;
 use super::save;
@@ -12,9 +12,11 @@ use super::find_by_id;
 use super::find_by_email;
 use super::exists_by_email;
 use super::list;
+use super::list_matching;
+use super::count;
 
 pub struct InMemoryUserRepository {
-    users: Arc<RwLock<HashMap<Uuid, User>>>,
+    users: Arc<RwLock<HashMap<Uuid, Arc<User>>>>,
 }
 
 impl InMemoryUserRepository {
@@ -25,7 +27,7 @@ impl InMemoryUserRepository {
     }
 
     pub fn new_with_users(users: Vec<User>) -> Self {
-        let user_map = users.into_iter().map(|user| (user.id, user)).collect();
+        let user_map = users.into_iter().map(|user| (user.id, Arc::new(user))).collect();
         Self {
             users: Arc::new(RwLock::new(user_map)),
         }
@@ -34,15 +36,15 @@ impl InMemoryUserRepository {
 
 #[async_trait]
 impl UserRepository for InMemoryUserRepository {
-    async fn save(&self, user: &User) -> Result<(), RepositoryError> {
+    async fn save(&self, user: Arc<User>) -> Result<(), RepositoryError> {
         save::save_user(self.users.clone(), user).await
     }
 
-    async fn find_by_id(&self, id: Uuid) -> Result<Option<User>, RepositoryError> {
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Arc<User>>, RepositoryError> {
         find_by_id::find_user_by_id(self.users.clone(), id).await
     }
 
-    async fn find_by_email(&self, email: &str) -> Result<Option<User>, RepositoryError> {
+    async fn find_by_email(&self, email: &str) -> Result<Option<Arc<User>>, RepositoryError> {
         find_by_email::find_user_by_email(self.users.clone(), email).await
     }
 
@@ -50,7 +52,20 @@ impl UserRepository for InMemoryUserRepository {
         exists_by_email::user_exists_by_email(self.users.clone(), email).await
     }
 
-    async fn list(&self, page: u32, limit: u32) -> Result<(Vec<User>, u64), RepositoryError> {
+    async fn list(&self, page: u32, limit: u32) -> Result<Vec<Arc<User>>, RepositoryError> {
         list::list_users(self.users.clone(), page, limit).await
     }
+
+    async fn count(&self) -> Result<u64, RepositoryError> {
+        count::count_users(self.users.clone()).await
+    }
+
+    async fn list_matching(
+        &self,
+        filter: &MetadataFilter,
+        page: u32,
+        limit: u32,
+    ) -> Result<(Vec<Arc<User>>, u64), RepositoryError> {
+        list_matching::list_matching_users(self.users.clone(), filter, page, limit).await
+    }
 }
\ No newline at end of file
@@ -0,0 +1,20 @@
+use crate::domain::user::entities::{MetadataFilter, User};
+use crate::domain::user::repository::RepositoryError;
+use crate::pagination::PageRequest;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub async fn list_matching_users(
+    users: Arc<RwLock<HashMap<uuid::Uuid, Arc<User>>>>,
+    filter: &MetadataFilter,
+    page: u32,
+    limit: u32,
+) -> Result<(Vec<Arc<User>>, u64), RepositoryError> {
+    let user_map = users.read().await;
+
+    let matching: Vec<&Arc<User>> = user_map.values().filter(|user| filter.matches(&user.metadata)).collect();
+    let total = matching.len() as u64;
+    let page = PageRequest::new(page, limit).window(matching.into_iter()).cloned().collect();
+    Ok((page, total))
+}
@@ -0,0 +1,64 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::domain::user::entities::{MetadataFilter, User};
+use crate::domain::user::repository::UserRepository;
+use crate::domain::user::repository::RepositoryError;
+use crate::infrastructure::{MethodMetrics, MethodOutcome};
+
+/// Records calls, latency and faults of every `UserRepository` method under
+/// `user_repository.<method>`
+pub struct MetricsUserRepository {
+    inner: Arc<dyn UserRepository>,
+    metrics: Arc<MethodMetrics>,
+}
+
+impl MetricsUserRepository {
+    pub fn new(inner: Arc<dyn UserRepository>, metrics: Arc<MethodMetrics>) -> Self {
+        Self { inner, metrics }
+    }
+}
+
+/// Missing users and duplicate emails are answers to the caller
+impl MethodOutcome for RepositoryError {
+    fn is_fault(&self) -> bool {
+        matches!(self, RepositoryError::Database(_) | RepositoryError::Internal(_))
+    }
+}
+
+#[async_trait]
+impl UserRepository for MetricsUserRepository {
+    async fn save(&self, user: Arc<User>) -> Result<(), RepositoryError> {
+        self.metrics.measure("user_repository.save", self.inner.save(user)).await
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Arc<User>>, RepositoryError> {
+        self.metrics.measure("user_repository.find_by_id", self.inner.find_by_id(id)).await
+    }
+
+    async fn find_by_email(&self, email: &str) -> Result<Option<Arc<User>>, RepositoryError> {
+        self.metrics.measure("user_repository.find_by_email", self.inner.find_by_email(email)).await
+    }
+
+    async fn exists_by_email(&self, email: &str) -> Result<bool, RepositoryError> {
+        self.metrics.measure("user_repository.exists_by_email", self.inner.exists_by_email(email)).await
+    }
+
+    async fn list(&self, page: u32, limit: u32) -> Result<Vec<Arc<User>>, RepositoryError> {
+        self.metrics.measure("user_repository.list", self.inner.list(page, limit)).await
+    }
+
+    async fn count(&self) -> Result<u64, RepositoryError> {
+        self.metrics.measure("user_repository.count", self.inner.count()).await
+    }
+
+    async fn list_matching(
+        &self,
+        filter: &MetadataFilter,
+        page: u32,
+        limit: u32,
+    ) -> Result<(Vec<Arc<User>>, u64), RepositoryError> {
+        self.metrics.measure("user_repository.list_matching", self.inner.list_matching(filter, page, limit)).await
+    }
+}
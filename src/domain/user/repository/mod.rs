@@ -4,7 +4,23 @@ pub mod find_by_id;
 pub mod find_by_email;
 pub mod exists_by_email;
 pub mod list;
+pub mod list_matching;
+pub mod count;
 pub mod in_memory_impl;
+pub mod cached_impl;
+pub mod bloom_impl;
+pub mod redb_impl;
+pub mod metrics_impl;
+pub mod retrying_impl;
+pub mod tracing_impl;
+pub mod presence;
 
 pub use repository::*;
-pub use in_memory_impl::*;
\ No newline at end of file
+pub use in_memory_impl::*;
+pub use cached_impl::*;
+pub use bloom_impl::*;
+pub use redb_impl::*;
+pub use metrics_impl::*;
+pub use retrying_impl::*;
+pub use tracing_impl::*;
+pub use presence::*;
\ No newline at end of file
@@ -0,0 +1,69 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PresenceStoreError {
+    #[error("Presence store unavailable: {0}")]
+    Unavailable(String),
+}
+
+/// When each user was last active. A user is online while their last
+/// activity is younger than the store's TTL, which maps onto a Redis key
+/// with an expiry; the in-memory store keeps the same contract.
+#[async_trait]
+pub trait PresenceStore: Send + Sync {
+    /// Record activity; `true` when the user was offline until now
+    async fn touch(&self, user_id: Uuid, at: DateTime<Utc>) -> Result<bool, PresenceStoreError>;
+
+    /// Last activity of those of `user_ids` that were ever seen
+    async fn last_seen(&self, user_ids: &[Uuid]) -> Result<HashMap<Uuid, DateTime<Utc>>, PresenceStoreError>;
+
+    /// Mark users whose TTL ran out offline, returning them with their last activity
+    async fn expire(&self, now: DateTime<Utc>) -> Result<Vec<(Uuid, DateTime<Utc>)>, PresenceStoreError>;
+}
+
+struct Seen {
+    at: DateTime<Utc>,
+    online: bool,
+}
+
+pub struct InMemoryPresenceStore {
+    ttl: Duration,
+    seen: RwLock<HashMap<Uuid, Seen>>,
+}
+
+impl InMemoryPresenceStore {
+    pub fn new(ttl: std::time::Duration) -> Self {
+        Self { ttl: Duration::from_std(ttl).unwrap_or(Duration::MAX), seen: RwLock::new(HashMap::new()) }
+    }
+}
+
+#[async_trait]
+impl PresenceStore for InMemoryPresenceStore {
+    async fn touch(&self, user_id: Uuid, at: DateTime<Utc>) -> Result<bool, PresenceStoreError> {
+        let mut seen = self.seen.write().await;
+        let was_online = seen.get(&user_id).is_some_and(|seen| seen.online && at - seen.at < self.ttl);
+        seen.insert(user_id, Seen { at, online: true });
+        Ok(!was_online)
+    }
+
+    async fn last_seen(&self, user_ids: &[Uuid]) -> Result<HashMap<Uuid, DateTime<Utc>>, PresenceStoreError> {
+        let seen = self.seen.read().await;
+        Ok(user_ids.iter().filter_map(|id| seen.get(id).map(|seen| (*id, seen.at))).collect())
+    }
+
+    async fn expire(&self, now: DateTime<Utc>) -> Result<Vec<(Uuid, DateTime<Utc>)>, PresenceStoreError> {
+        let mut seen = self.seen.write().await;
+        Ok(seen
+            .iter_mut()
+            .filter(|(_, seen)| seen.online && now - seen.at >= self.ttl)
+            .map(|(id, seen)| {
+                seen.online = false;
+                (*id, seen.at)
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,232 @@
+use async_trait::async_trait;
+use redb::{Database, ReadableTable, ReadableTableMetadata, TableDefinition};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+use crate::domain::user::entities::{MetadataFilter, User};
+use crate::domain::user::repository::{RepositoryError, UserRepository};
+use crate::infrastructure::{from_versioned_slice, run_blocking, to_versioned_vec};
+use crate::pagination::PageRequest;
+
+/// User id -> versioned JSON `User`
+const USERS: TableDefinition<u128, &[u8]> = TableDefinition::new("users");
+/// Email -> user id
+const USERS_BY_EMAIL: TableDefinition<&str, u128> = TableDefinition::new("users_by_email");
+
+/// Writes wait for fsync; anything slower than this is a stuck disk
+const OPERATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Durable user store in an embedded redb file, so the app runs as a single
+/// binary without external services.
+///
+/// The file is opened on first use, which `UserRepositoryStartup` triggers,
+/// so a bad path or a file held by another process fails startup. redb is
+/// synchronous; every operation runs through `run_blocking`. Users are
+/// listed in id order.
+pub struct RedbUserRepository {
+    path: PathBuf,
+    db: OnceCell<Arc<Database>>,
+}
+
+impl RedbUserRepository {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), db: OnceCell::new() }
+    }
+
+    async fn db(&self) -> Result<Arc<Database>, RepositoryError> {
+        self.db
+            .get_or_try_init(|| {
+                let path = self.path.clone();
+                async move { run_blocking("redb_open", OPERATION_TIMEOUT, move || open(&path)).await.map_err(internal)? }
+            })
+            .await
+            .cloned()
+    }
+
+    async fn with_db<T, F>(&self, task: &'static str, work: F) -> Result<T, RepositoryError>
+    where
+        F: FnOnce(&Database) -> Result<T, RepositoryError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let db = self.db().await?;
+        run_blocking(task, OPERATION_TIMEOUT, move || work(&db)).await.map_err(internal)?
+    }
+}
+
+fn open(path: &Path) -> Result<Arc<Database>, RepositoryError> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|err| RepositoryError::Database(err.to_string()))?;
+    }
+    let db = Database::create(path).map_err(database)?;
+    // Create the tables up front so read transactions never miss them
+    let txn = db.begin_write().map_err(database)?;
+    txn.open_table(USERS).map_err(database)?;
+    txn.open_table(USERS_BY_EMAIL).map_err(database)?;
+    txn.commit().map_err(database)?;
+    Ok(Arc::new(db))
+}
+
+fn database(err: impl Into<redb::Error>) -> RepositoryError {
+    RepositoryError::Database(err.into().to_string())
+}
+
+fn internal(err: impl std::fmt::Display) -> RepositoryError {
+    RepositoryError::Internal(err.to_string())
+}
+
+fn decode(bytes: &[u8]) -> Result<Arc<User>, RepositoryError> {
+    from_versioned_slice(bytes).map(Arc::new).map_err(internal)
+}
+
+fn find_by_id(db: &Database, id: Uuid) -> Result<Option<Arc<User>>, RepositoryError> {
+    let txn = db.begin_read().map_err(database)?;
+    let users = txn.open_table(USERS).map_err(database)?;
+    let user = users.get(id.as_u128()).map_err(database)?;
+    user.map(|user| decode(user.value())).transpose()
+}
+
+#[async_trait]
+impl UserRepository for RedbUserRepository {
+    async fn save(&self, user: Arc<User>) -> Result<(), RepositoryError> {
+        let encoded = to_versioned_vec(&*user).map_err(internal)?;
+        self.with_db("redb_save_user", move |db| {
+            let txn = db.begin_write().map_err(database)?;
+            {
+                let mut users = txn.open_table(USERS).map_err(database)?;
+                let mut by_email = txn.open_table(USERS_BY_EMAIL).map_err(database)?;
+                let previous = users.insert(user.id.as_u128(), encoded.as_slice()).map_err(database)?;
+                // An email change moves the index entry
+                if let Some(previous) = previous.map(|previous| decode(previous.value())).transpose()? {
+                    if previous.email != user.email {
+                        by_email.remove(previous.email.as_str()).map_err(database)?;
+                    }
+                }
+                by_email.insert(user.email.as_str(), user.id.as_u128()).map_err(database)?;
+            }
+            txn.commit().map_err(database)
+        })
+        .await
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Arc<User>>, RepositoryError> {
+        self.with_db("redb_find_user", move |db| find_by_id(db, id)).await
+    }
+
+    async fn find_by_email(&self, email: &str) -> Result<Option<Arc<User>>, RepositoryError> {
+        let email = email.to_string();
+        self.with_db("redb_find_user_by_email", move |db| {
+            let id = {
+                let txn = db.begin_read().map_err(database)?;
+                let by_email = txn.open_table(USERS_BY_EMAIL).map_err(database)?;
+                let id = by_email.get(email.as_str()).map_err(database)?;
+                id.map(|id| id.value())
+            };
+            match id {
+                Some(id) => find_by_id(db, Uuid::from_u128(id)),
+                None => Ok(None),
+            }
+        })
+        .await
+    }
+
+    async fn exists_by_email(&self, email: &str) -> Result<bool, RepositoryError> {
+        let email = email.to_string();
+        self.with_db("redb_user_exists", move |db| {
+            let txn = db.begin_read().map_err(database)?;
+            let by_email = txn.open_table(USERS_BY_EMAIL).map_err(database)?;
+            Ok(by_email.get(email.as_str()).map_err(database)?.is_some())
+        })
+        .await
+    }
+
+    async fn list(&self, page: u32, limit: u32) -> Result<Vec<Arc<User>>, RepositoryError> {
+        let request = PageRequest::new(page, limit);
+        self.with_db("redb_list_users", move |db| {
+            let txn = db.begin_read().map_err(database)?;
+            let users = txn.open_table(USERS).map_err(database)?;
+            request
+                .window(users.iter().map_err(database)?)
+                .map(|entry| {
+                    let (_, user) = entry.map_err(database)?;
+                    decode(user.value())
+                })
+                .collect()
+        })
+        .await
+    }
+
+    async fn count(&self) -> Result<u64, RepositoryError> {
+        self.with_db("redb_count_users", |db| {
+            let txn = db.begin_read().map_err(database)?;
+            let users = txn.open_table(USERS).map_err(database)?;
+            users.len().map_err(database)
+        })
+        .await
+    }
+
+    /// Decodes every user; redb has no index over metadata
+    async fn list_matching(
+        &self,
+        filter: &MetadataFilter,
+        page: u32,
+        limit: u32,
+    ) -> Result<(Vec<Arc<User>>, u64), RepositoryError> {
+        let request = PageRequest::new(page, limit);
+        let filter = filter.clone();
+        self.with_db("redb_list_matching_users", move |db| {
+            let txn = db.begin_read().map_err(database)?;
+            let users = txn.open_table(USERS).map_err(database)?;
+            let (mut matching, mut total) = (Vec::new(), 0u64);
+            for entry in users.iter().map_err(database)? {
+                let (_, user) = entry.map_err(database)?;
+                let user = decode(user.value())?;
+                if filter.matches(&user.metadata) {
+                    if total as usize >= request.offset() && matching.len() < request.limit as usize {
+                        matching.push(user);
+                    }
+                    total += 1;
+                }
+            }
+            Ok((matching, total))
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn users_survive_reopening_and_email_changes_move_the_index() {
+        let dir = std::env::temp_dir().join(format!("redb-users-{}", Uuid::new_v4()));
+        let path = dir.join("users.redb");
+
+        let mut user = User::new("old@example.com".to_string(), "hash".to_string());
+        {
+            let repository = RedbUserRepository::new(&path);
+            repository.save(Arc::new(user.clone())).await.unwrap();
+            user.email = "new@example.com".to_string();
+            repository.save(Arc::new(user.clone())).await.unwrap();
+            repository.save(Arc::new(User::new("other@example.com".to_string(), "hash".to_string()))).await.unwrap();
+        }
+
+        let reopened = RedbUserRepository::new(&path);
+        assert_eq!(reopened.count().await.unwrap(), 2);
+        assert!(!reopened.exists_by_email("old@example.com").await.unwrap());
+        let found = reopened.find_by_email("new@example.com").await.unwrap().unwrap();
+        assert_eq!(found.id, user.id);
+        assert_eq!(reopened.find_by_id(user.id).await.unwrap().unwrap().email, "new@example.com");
+
+        let first_page = reopened.list(1, 1).await.unwrap();
+        let second_page = reopened.list(2, 1).await.unwrap();
+        assert_eq!((first_page.len(), second_page.len()), (1, 1));
+        assert_ne!(first_page[0].id, second_page[0].id);
+
+        drop(reopened);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,14 +1,23 @@
 use async_trait::async_trait;
+use std::sync::Arc;
 use uuid::Uuid;
-use crate::domain::user::entities::User;
+use crate::domain::user::entities::{MetadataFilter, User};
 
 #[async_trait]
 pub trait UserRepository: Send + Sync {
-    async fn save(&self, user: &User) -> Result<(), RepositoryError>;
-    async fn find_by_id(&self, id: Uuid) -> Result<Option<User>, RepositoryError>;
-    async fn find_by_email(&self, email: &str) -> Result<Option<User>, RepositoryError>;
+    async fn save(&self, user: Arc<User>) -> Result<(), RepositoryError>;
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Arc<User>>, RepositoryError>;
+    async fn find_by_email(&self, email: &str) -> Result<Option<Arc<User>>, RepositoryError>;
     async fn exists_by_email(&self, email: &str) -> Result<bool, RepositoryError>;
-    async fn list(&self, page: u32, limit: u32) -> Result<(Vec<User>, u64), RepositoryError>;
+    async fn list(&self, page: u32, limit: u32) -> Result<Vec<Arc<User>>, RepositoryError>;
+    async fn count(&self) -> Result<u64, RepositoryError>;
+    /// One page of the users whose metadata matches, and how many match in all
+    async fn list_matching(
+        &self,
+        filter: &MetadataFilter,
+        page: u32,
+        limit: u32,
+    ) -> Result<(Vec<Arc<User>>, u64), RepositoryError>;
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -0,0 +1,172 @@
+use async_trait::async_trait;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::domain::user::entities::{MetadataFilter, User};
+use crate::domain::user::repository::UserRepository;
+use crate::domain::user::repository::RepositoryError;
+
+/// Retries reads that fail with a database error, doubling the backoff
+/// after each attempt.
+///
+/// Saves are passed through once: a save that timed out may still have
+/// been applied, and retrying it would race with whoever wrote next.
+pub struct RetryingUserRepository {
+    inner: Arc<dyn UserRepository>,
+    /// Total tries per read, including the first
+    attempts: u32,
+    backoff: Duration,
+}
+
+impl RetryingUserRepository {
+    pub fn new(inner: Arc<dyn UserRepository>, attempts: u32, backoff: Duration) -> Self {
+        Self { inner, attempts: attempts.max(1), backoff }
+    }
+
+    async fn retry<T, F, Fut>(&self, method: &'static str, mut call: F) -> Result<T, RepositoryError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, RepositoryError>>,
+    {
+        let mut backoff = self.backoff;
+        let mut attempt = 1;
+        loop {
+            match call().await {
+                Err(RepositoryError::Database(err)) if attempt < self.attempts => {
+                    tracing::debug!(method, attempt, error = %err, "Retrying user repository read");
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl UserRepository for RetryingUserRepository {
+    async fn save(&self, user: Arc<User>) -> Result<(), RepositoryError> {
+        self.inner.save(user).await
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Arc<User>>, RepositoryError> {
+        self.retry("find_by_id", || self.inner.find_by_id(id)).await
+    }
+
+    async fn find_by_email(&self, email: &str) -> Result<Option<Arc<User>>, RepositoryError> {
+        self.retry("find_by_email", || self.inner.find_by_email(email)).await
+    }
+
+    async fn exists_by_email(&self, email: &str) -> Result<bool, RepositoryError> {
+        self.retry("exists_by_email", || self.inner.exists_by_email(email)).await
+    }
+
+    async fn list(&self, page: u32, limit: u32) -> Result<Vec<Arc<User>>, RepositoryError> {
+        self.retry("list", || self.inner.list(page, limit)).await
+    }
+
+    async fn count(&self) -> Result<u64, RepositoryError> {
+        self.retry("count", || self.inner.count()).await
+    }
+
+    async fn list_matching(
+        &self,
+        filter: &MetadataFilter,
+        page: u32,
+        limit: u32,
+    ) -> Result<(Vec<Arc<User>>, u64), RepositoryError> {
+        self.retry("list_matching", || self.inner.list_matching(filter, page, limit)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user::repository::InMemoryUserRepository;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Fails `count` with a database error until `failures` runs out
+    struct Flaky {
+        inner: InMemoryUserRepository,
+        failures: AtomicU32,
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl UserRepository for Flaky {
+        async fn save(&self, user: Arc<User>) -> Result<(), RepositoryError> {
+            self.inner.save(user).await
+        }
+        async fn find_by_id(&self, id: Uuid) -> Result<Option<Arc<User>>, RepositoryError> {
+            self.inner.find_by_id(id).await
+        }
+        async fn find_by_email(&self, email: &str) -> Result<Option<Arc<User>>, RepositoryError> {
+            self.inner.find_by_email(email).await
+        }
+        async fn exists_by_email(&self, email: &str) -> Result<bool, RepositoryError> {
+            self.inner.exists_by_email(email).await
+        }
+        async fn list(&self, page: u32, limit: u32) -> Result<Vec<Arc<User>>, RepositoryError> {
+            self.inner.list(page, limit).await
+        }
+        async fn count(&self) -> Result<u64, RepositoryError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.failures.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| left.checked_sub(1)).is_ok() {
+                return Err(RepositoryError::Database("connection reset".into()));
+            }
+            self.inner.count().await
+        }
+        async fn list_matching(
+            &self,
+            filter: &MetadataFilter,
+            page: u32,
+            limit: u32,
+        ) -> Result<(Vec<Arc<User>>, u64), RepositoryError> {
+            self.inner.list_matching(filter, page, limit).await
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_are_retried_until_attempts_run_out() {
+        let flaky = Arc::new(Flaky {
+            inner: InMemoryUserRepository::new(),
+            failures: AtomicU32::new(2),
+            calls: AtomicU32::new(0),
+        });
+        let repository = RetryingUserRepository::new(flaky.clone(), 3, Duration::from_millis(1));
+        assert_eq!(repository.count().await.unwrap(), 0);
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+
+        flaky.failures.store(5, Ordering::SeqCst);
+        assert!(matches!(repository.count().await, Err(RepositoryError::Database(_))));
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 6);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_wait_out_a_doubling_backoff() {
+        let flaky = Arc::new(Flaky {
+            inner: InMemoryUserRepository::new(),
+            failures: AtomicU32::new(3),
+            calls: AtomicU32::new(0),
+        });
+        let repository = Arc::new(RetryingUserRepository::new(flaky.clone(), 4, Duration::from_millis(100)));
+        let started = tokio::time::Instant::now();
+        let read = tokio::spawn({
+            let repository = repository.clone();
+            async move { repository.count().await }
+        });
+
+        // Tries at 0, 100, 300 and 700ms
+        for (at, calls) in [(0, 1), (99, 1), (100, 2), (299, 2), (300, 3), (699, 3), (700, 4)] {
+            tokio::time::sleep_until(started + Duration::from_millis(at)).await;
+            for _ in 0..5 {
+                tokio::task::yield_now().await;
+            }
+            assert_eq!(flaky.calls.load(Ordering::SeqCst), calls, "at {at}ms");
+        }
+        assert_eq!(read.await.unwrap().unwrap(), 0);
+    }
+}
@@ -5,10 +5,10 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 
 pub async fn save_user(
-    users: Arc<RwLock<HashMap<uuid::Uuid, User>>>,
-    user: &User,
+    users: Arc<RwLock<HashMap<uuid::Uuid, Arc<User>>>>,
+    user: Arc<User>,
 ) -> Result<(), RepositoryError> {
     let mut user_map = users.write().await;
-    user_map.insert(user.id, user.clone());
+    user_map.insert(user.id, user);
     Ok(())
 }
\ No newline at end of file
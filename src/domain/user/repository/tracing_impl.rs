@@ -0,0 +1,77 @@
+use async_trait::async_trait;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::domain::user::entities::{MetadataFilter, User};
+use crate::domain::user::repository::UserRepository;
+use crate::domain::user::repository::RepositoryError;
+
+/// Runs every `UserRepository` call in a debug span and logs its duration
+/// and outcome, under the `user_repository` target
+pub struct TracingUserRepository {
+    inner: Arc<dyn UserRepository>,
+}
+
+impl TracingUserRepository {
+    pub fn new(inner: Arc<dyn UserRepository>) -> Self {
+        Self { inner }
+    }
+}
+
+async fn traced<T>(
+    method: &'static str,
+    call: impl Future<Output = Result<T, RepositoryError>>,
+) -> Result<T, RepositoryError> {
+    let span = tracing::debug_span!(target: "user_repository", "user_repository", method);
+    async move {
+        let started = Instant::now();
+        let result = call.await;
+        let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+        match &result {
+            Ok(_) => tracing::debug!(target: "user_repository", elapsed_ms, "Repository call"),
+            Err(err) => tracing::debug!(target: "user_repository", elapsed_ms, error = %err, "Repository call failed"),
+        }
+        result
+    }
+    .instrument(span)
+    .await
+}
+
+#[async_trait]
+impl UserRepository for TracingUserRepository {
+    async fn save(&self, user: Arc<User>) -> Result<(), RepositoryError> {
+        traced("save", self.inner.save(user)).await
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Arc<User>>, RepositoryError> {
+        traced("find_by_id", self.inner.find_by_id(id)).await
+    }
+
+    async fn find_by_email(&self, email: &str) -> Result<Option<Arc<User>>, RepositoryError> {
+        traced("find_by_email", self.inner.find_by_email(email)).await
+    }
+
+    async fn exists_by_email(&self, email: &str) -> Result<bool, RepositoryError> {
+        traced("exists_by_email", self.inner.exists_by_email(email)).await
+    }
+
+    async fn list(&self, page: u32, limit: u32) -> Result<Vec<Arc<User>>, RepositoryError> {
+        traced("list", self.inner.list(page, limit)).await
+    }
+
+    async fn count(&self) -> Result<u64, RepositoryError> {
+        traced("count", self.inner.count()).await
+    }
+
+    async fn list_matching(
+        &self,
+        filter: &MetadataFilter,
+        page: u32,
+        limit: u32,
+    ) -> Result<(Vec<Arc<User>>, u64), RepositoryError> {
+        traced("list_matching", self.inner.list_matching(filter, page, limit)).await
+    }
+}
@@ -0,0 +1,327 @@
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use super::{SignatureError, WebhookProvider};
+use crate::container::startup::StartupComponent;
+use crate::infrastructure::ConsumerStats;
+
+/// Deliveries remembered for replay protection
+const MAX_SEEN: usize = 100_000;
+/// Handlers registered under this event type receive every event of the provider
+pub const ANY_EVENT: &str = "*";
+
+/// Handlers keyed by provider and event type
+type HandlerMap = HashMap<(String, String), Vec<Arc<dyn WebhookHandler>>>;
+
+/// A verified delivery waiting to be processed
+#[derive(Debug, Clone)]
+pub struct WebhookEvent {
+    pub provider: String,
+    pub event_type: String,
+    pub delivery_id: String,
+    pub payload: Value,
+    pub received_at: DateTime<Utc>,
+}
+
+impl WebhookEvent {
+    /// Deserialize the payload into the handler's own schema
+    pub fn parse<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        T::deserialize(&self.payload)
+    }
+}
+
+/// Processes events of one provider and event type off the request path
+#[async_trait]
+pub trait WebhookHandler: Send + Sync {
+    /// `Err` carries a short reason for the logs
+    async fn handle(&self, event: &WebhookEvent) -> Result<(), String>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WebhookError {
+    #[error("Unknown webhook provider `{0}`")]
+    UnknownProvider(String),
+    #[error(transparent)]
+    Signature(#[from] SignatureError),
+    #[error("Invalid payload: {0}")]
+    InvalidPayload(String),
+    #[error("Webhook queue is full")]
+    QueueFull,
+}
+
+/// How an accepted request was handled
+#[derive(Debug, Clone, PartialEq)]
+pub enum Receipt {
+    Queued { delivery_id: String },
+    /// Already received within the replay window; acknowledged, not re-queued
+    Duplicate { delivery_id: String },
+    /// Provider handshake answered synchronously
+    Handshake(Value),
+}
+
+/// Receives webhook deliveries for registered providers.
+///
+/// Each request is verified with its provider's signature scheme, checked
+/// against recently seen deliveries and put on a bounded in-process queue.
+/// A single worker drains the queue and dispatches each event to the handlers
+/// registered for its provider and event type.
+pub struct WebhookInbox {
+    providers: RwLock<HashMap<&'static str, Arc<dyn WebhookProvider>>>,
+    handlers: RwLock<HandlerMap>,
+    replay_window: Duration,
+    seen: Mutex<HashMap<String, Instant>>,
+    queue: mpsc::Sender<WebhookEvent>,
+    receiver: Mutex<Option<mpsc::Receiver<WebhookEvent>>>,
+    stats: Arc<ConsumerStats>,
+}
+
+impl WebhookInbox {
+    pub fn new(queue_capacity: usize, replay_window: Duration) -> Self {
+        let (queue, receiver) = mpsc::channel(queue_capacity.max(1));
+        Self {
+            providers: RwLock::new(HashMap::new()),
+            handlers: RwLock::new(HashMap::new()),
+            replay_window,
+            seen: Mutex::new(HashMap::new()),
+            queue,
+            receiver: Mutex::new(Some(receiver)),
+            stats: Arc::new(ConsumerStats::new("in_process")),
+        }
+    }
+
+    /// Report queue lag and handling times to `stats`
+    pub fn with_consumer_stats(mut self, stats: Arc<ConsumerStats>) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    pub fn register_provider(&self, provider: Arc<dyn WebhookProvider>) {
+        self.providers.write().unwrap().insert(provider.name(), provider);
+    }
+
+    /// Subscribe a handler to a provider's event type, or to all of its events with `ANY_EVENT`
+    pub fn on(&self, provider: &str, event_type: &str, handler: Arc<dyn WebhookHandler>) {
+        self.handlers
+            .write()
+            .unwrap()
+            .entry((provider.to_string(), event_type.to_string()))
+            .or_default()
+            .push(handler);
+    }
+
+    pub fn receive(&self, provider_name: &str, headers: &HeaderMap, body: &[u8]) -> Result<Receipt, WebhookError> {
+        let provider = self
+            .providers
+            .read()
+            .unwrap()
+            .get(provider_name)
+            .cloned()
+            .ok_or_else(|| WebhookError::UnknownProvider(provider_name.to_string()))?;
+
+        provider.verify(headers, body, Utc::now())?;
+        let payload: Value =
+            serde_json::from_slice(body).map_err(|err| WebhookError::InvalidPayload(err.to_string()))?;
+        if let Some(answer) = provider.handshake(&payload) {
+            return Ok(Receipt::Handshake(answer));
+        }
+
+        let event_type = provider
+            .event_type(headers, &payload)
+            .ok_or_else(|| WebhookError::InvalidPayload("missing event type".to_string()))?;
+        let delivery_id = provider
+            .delivery_id(headers, &payload)
+            .unwrap_or_else(|| hex::encode(Sha256::digest(body)));
+
+        let replay_key = format!("{provider_name}:{delivery_id}");
+        if !self.mark_seen(&replay_key) {
+            tracing::info!(provider = provider_name, delivery_id = %delivery_id, "Duplicate webhook delivery ignored");
+            return Ok(Receipt::Duplicate { delivery_id });
+        }
+
+        let event = WebhookEvent {
+            provider: provider_name.to_string(),
+            event_type,
+            delivery_id: delivery_id.clone(),
+            payload,
+            received_at: Utc::now(),
+        };
+        if self.queue.try_send(event).is_ok() {
+            self.stats.enqueued();
+        } else {
+            // Let the provider's retry reach us again
+            self.seen.lock().unwrap().remove(&replay_key);
+            return Err(WebhookError::QueueFull);
+        }
+        Ok(Receipt::Queued { delivery_id })
+    }
+
+    /// `false` if the delivery was already seen within the replay window
+    fn mark_seen(&self, key: &str) -> bool {
+        let now = Instant::now();
+        let mut seen = self.seen.lock().unwrap();
+        if seen.get(key).is_some_and(|at| now.duration_since(*at) < self.replay_window) {
+            return false;
+        }
+        if seen.len() >= MAX_SEEN {
+            seen.retain(|_, at| now.duration_since(*at) < self.replay_window);
+        }
+        seen.insert(key.to_string(), now);
+        true
+    }
+
+    /// Run every handler subscribed to the event; failures are logged, not
+    /// retried. `false` when any handler failed.
+    pub async fn dispatch(&self, event: &WebhookEvent) -> bool {
+        let handlers: Vec<Arc<dyn WebhookHandler>> = {
+            let handlers = self.handlers.read().unwrap();
+            [event.event_type.as_str(), ANY_EVENT]
+                .iter()
+                .filter_map(|event_type| handlers.get(&(event.provider.clone(), event_type.to_string())))
+                .flatten()
+                .cloned()
+                .collect()
+        };
+        if handlers.is_empty() {
+            tracing::debug!(provider = %event.provider, event_type = %event.event_type, "No handler for webhook event");
+            return true;
+        }
+
+        let mut ok = true;
+        for handler in handlers {
+            if let Err(reason) = handler.handle(event).await {
+                ok = false;
+                tracing::error!(
+                    provider = %event.provider,
+                    event_type = %event.event_type,
+                    delivery_id = %event.delivery_id,
+                    error = %reason,
+                    "Webhook handler failed"
+                );
+            }
+        }
+        ok
+    }
+
+    /// Start draining the queue; only the first call has an effect
+    pub fn spawn_worker(self: &Arc<Self>) {
+        let Some(mut receiver) = self.receiver.lock().unwrap().take() else {
+            return;
+        };
+        let inbox = Arc::clone(self);
+        inbox.stats.set_connected(true);
+        tokio::spawn(async move {
+            while let Some(event) = receiver.recv().await {
+                inbox.stats.started();
+                let started = Instant::now();
+                let ok = inbox.dispatch(&event).await;
+                inbox.stats.finished(started.elapsed(), ok);
+            }
+            inbox.stats.set_connected(false);
+        });
+    }
+}
+
+/// Processes queued webhook events once the server is about to take traffic
+pub struct WebhookWorker {
+    inbox: Arc<WebhookInbox>,
+}
+
+impl WebhookWorker {
+    pub fn new(inbox: Arc<WebhookInbox>) -> Self {
+        Self { inbox }
+    }
+}
+
+#[async_trait]
+impl StartupComponent for WebhookWorker {
+    fn name(&self) -> &'static str {
+        "webhook_worker"
+    }
+
+    async fn start(&self) -> Result<(), String> {
+        self.inbox.spawn_worker();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::webhook::feature::provider::tests::{headers, sign};
+    use crate::domain::webhook::feature::GitHubProvider;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct PullRequest {
+        action: String,
+    }
+
+    struct Recorder(mpsc::UnboundedSender<String>);
+
+    #[async_trait]
+    impl WebhookHandler for Recorder {
+        async fn handle(&self, event: &WebhookEvent) -> Result<(), String> {
+            let pull_request: PullRequest = event.parse().map_err(|err| err.to_string())?;
+            self.0.send(pull_request.action).unwrap();
+            Ok(())
+        }
+    }
+
+    fn delivery(id: &str) -> (HeaderMap, &'static [u8]) {
+        let body: &[u8] = br#"{"action":"opened"}"#;
+        let headers = headers(&[
+            ("x-hub-signature-256", format!("sha256={}", sign("secret", &[body]))),
+            ("x-github-event", "pull_request".to_string()),
+            ("x-github-delivery", id.to_string()),
+        ]);
+        (headers, body)
+    }
+
+    fn inbox(capacity: usize) -> Arc<WebhookInbox> {
+        let inbox = Arc::new(WebhookInbox::new(capacity, Duration::from_secs(3600)));
+        inbox.register_provider(Arc::new(GitHubProvider::new("secret".into())));
+        inbox
+    }
+
+    #[tokio::test]
+    async fn verified_events_reach_their_handler_once() {
+        let inbox = inbox(8);
+        let (sender, mut received) = mpsc::unbounded_channel();
+        inbox.on("github", "pull_request", Arc::new(Recorder(sender)));
+        inbox.spawn_worker();
+
+        let (headers, body) = delivery("d-1");
+        assert_eq!(inbox.receive("github", &headers, body).unwrap(), Receipt::Queued { delivery_id: "d-1".into() });
+        assert_eq!(inbox.receive("github", &headers, body).unwrap(), Receipt::Duplicate { delivery_id: "d-1".into() });
+        assert_eq!(received.recv().await.as_deref(), Some("opened"));
+
+        assert!(matches!(inbox.receive("stripe", &headers, body), Err(WebhookError::UnknownProvider(_))));
+        assert!(matches!(
+            inbox.receive("github", &headers, br#"{"action":"closed"}"#),
+            Err(WebhookError::Signature(SignatureError::Mismatch))
+        ));
+    }
+
+    #[tokio::test]
+    async fn full_queue_rejects_without_marking_the_delivery_seen() {
+        let inbox = inbox(1);
+        let (first, body) = delivery("d-1");
+        let (second, _) = delivery("d-2");
+        inbox.receive("github", &first, body).unwrap();
+        assert!(matches!(inbox.receive("github", &second, body), Err(WebhookError::QueueFull)));
+
+        // The provider's retry is accepted once the worker catches up
+        inbox.spawn_worker();
+        tokio::task::yield_now().await;
+        assert_eq!(inbox.receive("github", &second, body).unwrap(), Receipt::Queued { delivery_id: "d-2".into() });
+    }
+}
@@ -0,0 +1,5 @@
+pub mod provider;
+pub mod inbox;
+
+pub use provider::*;
+pub use inbox::*;
@@ -0,0 +1,295 @@
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SignatureError {
+    #[error("Missing {0} header")]
+    MissingHeader(&'static str),
+    #[error("Malformed {0} header")]
+    Malformed(&'static str),
+    #[error("Signature does not match")]
+    Mismatch,
+    #[error("Timestamp is outside the {0}s tolerance")]
+    Expired(i64),
+}
+
+/// A webhook sender: how its requests are signed and how its payloads name
+/// their event type and delivery
+pub trait WebhookProvider: Send + Sync {
+    /// Path segment under `/api/hooks/`
+    fn name(&self) -> &'static str;
+
+    fn verify(&self, headers: &HeaderMap, body: &[u8], now: DateTime<Utc>) -> Result<(), SignatureError>;
+
+    fn event_type(&self, headers: &HeaderMap, payload: &Value) -> Option<String>;
+
+    /// Provider-assigned id used for replay protection; `None` falls back to a
+    /// hash of the body
+    fn delivery_id(&self, headers: &HeaderMap, payload: &Value) -> Option<String>;
+
+    /// Synchronous answer to a setup handshake, returned as-is instead of
+    /// queueing the payload
+    fn handshake(&self, _payload: &Value) -> Option<Value> {
+        None
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &'static str) -> Result<&'a str, SignatureError> {
+    headers
+        .get(name)
+        .ok_or(SignatureError::MissingHeader(name))?
+        .to_str()
+        .map_err(|_| SignatureError::Malformed(name))
+}
+
+/// Constant-time check of a hex HMAC-SHA256 over the concatenated parts
+fn hmac_matches(secret: &str, parts: &[&[u8]], signature_hex: &str) -> bool {
+    let Ok(signature) = hex::decode(signature_hex) else {
+        return false;
+    };
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    for part in parts {
+        mac.update(part);
+    }
+    mac.verify_slice(&signature).is_ok()
+}
+
+fn check_timestamp(timestamp: i64, now: DateTime<Utc>, tolerance_secs: i64) -> Result<(), SignatureError> {
+    if (now.timestamp() - timestamp).abs() > tolerance_secs {
+        return Err(SignatureError::Expired(tolerance_secs));
+    }
+    Ok(())
+}
+
+fn string_field(payload: &Value, field: &str) -> Option<String> {
+    payload.get(field).and_then(Value::as_str).map(str::to_string)
+}
+
+/// `Stripe-Signature: t=<unix>,v1=<hex>[,v1=<hex>]` over `"{t}.{body}"`
+pub struct StripeProvider {
+    secret: String,
+    tolerance_secs: i64,
+}
+
+impl StripeProvider {
+    pub fn new(secret: String, tolerance_secs: i64) -> Self {
+        Self { secret, tolerance_secs }
+    }
+}
+
+impl WebhookProvider for StripeProvider {
+    fn name(&self) -> &'static str {
+        "stripe"
+    }
+
+    fn verify(&self, headers: &HeaderMap, body: &[u8], now: DateTime<Utc>) -> Result<(), SignatureError> {
+        const HEADER: &str = "stripe-signature";
+        let value = header(headers, HEADER)?;
+
+        let mut timestamp = None;
+        let mut signatures = Vec::new();
+        for item in value.split(',') {
+            match item.trim().split_once('=') {
+                Some(("t", t)) => timestamp = t.parse::<i64>().ok(),
+                Some(("v1", signature)) => signatures.push(signature),
+                _ => {}
+            }
+        }
+        let timestamp = timestamp.ok_or(SignatureError::Malformed(HEADER))?;
+        if signatures.is_empty() {
+            return Err(SignatureError::Malformed(HEADER));
+        }
+
+        // Secrets rotate with an overlap, so any listed v1 signature may match
+        let signed_timestamp = timestamp.to_string();
+        if !signatures
+            .iter()
+            .any(|signature| hmac_matches(&self.secret, &[signed_timestamp.as_bytes(), b".", body], signature))
+        {
+            return Err(SignatureError::Mismatch);
+        }
+        check_timestamp(timestamp, now, self.tolerance_secs)
+    }
+
+    fn event_type(&self, _headers: &HeaderMap, payload: &Value) -> Option<String> {
+        string_field(payload, "type")
+    }
+
+    fn delivery_id(&self, _headers: &HeaderMap, payload: &Value) -> Option<String> {
+        string_field(payload, "id")
+    }
+}
+
+/// `X-Hub-Signature-256: sha256=<hex>` over the body. GitHub sends no
+/// timestamp, so replays are only caught by delivery id.
+pub struct GitHubProvider {
+    secret: String,
+}
+
+impl GitHubProvider {
+    pub fn new(secret: String) -> Self {
+        Self { secret }
+    }
+}
+
+impl WebhookProvider for GitHubProvider {
+    fn name(&self) -> &'static str {
+        "github"
+    }
+
+    fn verify(&self, headers: &HeaderMap, body: &[u8], _now: DateTime<Utc>) -> Result<(), SignatureError> {
+        const HEADER: &str = "x-hub-signature-256";
+        let signature = header(headers, HEADER)?
+            .strip_prefix("sha256=")
+            .ok_or(SignatureError::Malformed(HEADER))?;
+        if !hmac_matches(&self.secret, &[body], signature) {
+            return Err(SignatureError::Mismatch);
+        }
+        Ok(())
+    }
+
+    fn event_type(&self, headers: &HeaderMap, _payload: &Value) -> Option<String> {
+        header(headers, "x-github-event").ok().map(str::to_string)
+    }
+
+    fn delivery_id(&self, headers: &HeaderMap, _payload: &Value) -> Option<String> {
+        header(headers, "x-github-delivery").ok().map(str::to_string)
+    }
+}
+
+/// `X-Slack-Signature: v0=<hex>` over `"v0:{X-Slack-Request-Timestamp}:{body}"`
+pub struct SlackProvider {
+    signing_secret: String,
+    tolerance_secs: i64,
+}
+
+impl SlackProvider {
+    pub fn new(signing_secret: String, tolerance_secs: i64) -> Self {
+        Self { signing_secret, tolerance_secs }
+    }
+}
+
+impl WebhookProvider for SlackProvider {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    fn verify(&self, headers: &HeaderMap, body: &[u8], now: DateTime<Utc>) -> Result<(), SignatureError> {
+        const TIMESTAMP: &str = "x-slack-request-timestamp";
+        const SIGNATURE: &str = "x-slack-signature";
+        let timestamp = header(headers, TIMESTAMP)?;
+        let unix: i64 = timestamp.parse().map_err(|_| SignatureError::Malformed(TIMESTAMP))?;
+        let signature = header(headers, SIGNATURE)?
+            .strip_prefix("v0=")
+            .ok_or(SignatureError::Malformed(SIGNATURE))?;
+
+        if !hmac_matches(&self.signing_secret, &[b"v0:", timestamp.as_bytes(), b":", body], signature) {
+            return Err(SignatureError::Mismatch);
+        }
+        check_timestamp(unix, now, self.tolerance_secs)
+    }
+
+    /// Events API callbacks carry the real type in `event.type`
+    fn event_type(&self, _headers: &HeaderMap, payload: &Value) -> Option<String> {
+        match payload.get("type").and_then(Value::as_str)? {
+            "event_callback" => payload.get("event").and_then(|event| string_field(event, "type")),
+            other => Some(other.to_string()),
+        }
+    }
+
+    fn delivery_id(&self, _headers: &HeaderMap, payload: &Value) -> Option<String> {
+        string_field(payload, "event_id")
+    }
+
+    fn handshake(&self, payload: &Value) -> Option<Value> {
+        if payload.get("type").and_then(Value::as_str) != Some("url_verification") {
+            return None;
+        }
+        Some(json!({ "challenge": payload.get("challenge")? }))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    pub(crate) fn sign(secret: &str, parts: &[&[u8]]) -> String {
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+        for part in parts {
+            mac.update(part);
+        }
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    pub(crate) fn headers(pairs: &[(&'static str, String)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn stripe_checks_signature_and_timestamp() {
+        let provider = StripeProvider::new("whsec_test".into(), 300);
+        let body = br#"{"id":"evt_1","type":"invoice.paid"}"#;
+        let now = Utc::now();
+        let t = now.timestamp().to_string();
+        let valid = sign("whsec_test", &[t.as_bytes(), b".", body]);
+
+        let signed = headers(&[("stripe-signature", format!("t={t},v1=deadbeef,v1={valid}"))]);
+        assert_eq!(provider.verify(&signed, body, now), Ok(()));
+        assert_eq!(provider.verify(&signed, b"{}", now), Err(SignatureError::Mismatch));
+        assert_eq!(
+            provider.verify(&signed, body, now + chrono::Duration::minutes(10)),
+            Err(SignatureError::Expired(300))
+        );
+        assert_eq!(
+            provider.verify(&HeaderMap::new(), body, now),
+            Err(SignatureError::MissingHeader("stripe-signature"))
+        );
+    }
+
+    #[test]
+    fn github_uses_headers_for_event_and_delivery() {
+        let provider = GitHubProvider::new("gh_secret".into());
+        let body = br#"{"action":"opened"}"#;
+        let signed = headers(&[
+            ("x-hub-signature-256", format!("sha256={}", sign("gh_secret", &[body]))),
+            ("x-github-event", "pull_request".to_string()),
+            ("x-github-delivery", "d-1".to_string()),
+        ]);
+        assert_eq!(provider.verify(&signed, body, Utc::now()), Ok(()));
+        assert_eq!(provider.event_type(&signed, &Value::Null).as_deref(), Some("pull_request"));
+        assert_eq!(provider.delivery_id(&signed, &Value::Null).as_deref(), Some("d-1"));
+
+        let forged = headers(&[("x-hub-signature-256", format!("sha256={}", sign("other", &[body])))]);
+        assert_eq!(provider.verify(&forged, body, Utc::now()), Err(SignatureError::Mismatch));
+    }
+
+    #[test]
+    fn slack_answers_url_verification_and_unwraps_event_callbacks() {
+        let provider = SlackProvider::new("slack_secret".into(), 300);
+        let body = br#"{"type":"url_verification","challenge":"abc"}"#;
+        let now = Utc::now();
+        let t = now.timestamp().to_string();
+        let signed = headers(&[
+            ("x-slack-request-timestamp", t.clone()),
+            ("x-slack-signature", format!("v0={}", sign("slack_secret", &[b"v0:", t.as_bytes(), b":", body]))),
+        ]);
+        assert_eq!(provider.verify(&signed, body, now), Ok(()));
+
+        let payload: Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(provider.handshake(&payload), Some(json!({ "challenge": "abc" })));
+
+        let callback = json!({ "type": "event_callback", "event_id": "Ev1", "event": { "type": "app_mention" } });
+        assert_eq!(provider.event_type(&signed, &callback).as_deref(), Some("app_mention"));
+        assert_eq!(provider.handshake(&callback), None);
+    }
+}
@@ -0,0 +1,52 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::Arc;
+
+use super::feature::{Receipt, WebhookError, WebhookInbox};
+use super::model::WebhookReceiptResponse;
+use crate::infrastructure::BodyReader;
+use crate::response::{error_response, success_response};
+
+/// Provider payloads are small; anything larger is not a webhook we expect
+const BODY_READER: BodyReader = BodyReader::new(1024 * 1024);
+
+/// Receive a webhook delivery. Verified events are queued and acknowledged
+/// with 202 before any handler runs; repeats are acknowledged with 200.
+pub async fn receive_webhook(
+    State(inbox): State<Arc<WebhookInbox>>,
+    Path(provider): Path<String>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Response, Response> {
+    let body = BODY_READER.read(body).await.map_err(IntoResponse::into_response)?;
+
+    let receipt = inbox.receive(&provider, &headers, &body).map_err(|err| {
+        let (status, code) = match &err {
+            WebhookError::UnknownProvider(_) => (StatusCode::NOT_FOUND, "NOT_FOUND"),
+            WebhookError::Signature(_) => (StatusCode::UNAUTHORIZED, "INVALID_SIGNATURE"),
+            WebhookError::InvalidPayload(_) => (StatusCode::BAD_REQUEST, "INVALID_PAYLOAD"),
+            WebhookError::QueueFull => (StatusCode::SERVICE_UNAVAILABLE, "QUEUE_FULL"),
+        };
+        if let WebhookError::Signature(reason) = &err {
+            tracing::warn!(target: "security", provider = %provider, reason = %reason, "Webhook signature rejected");
+        }
+        error_response(status, code, err.to_string()).into_response()
+    })?;
+
+    let (code, status, delivery_id) = match receipt {
+        Receipt::Handshake(answer) => return Ok(Json(answer).into_response()),
+        Receipt::Queued { delivery_id } => (StatusCode::ACCEPTED, "queued", delivery_id),
+        Receipt::Duplicate { delivery_id } => (StatusCode::OK, "duplicate", delivery_id),
+    };
+    let response = WebhookReceiptResponse {
+        status: status.to_string(),
+        provider,
+        delivery_id,
+    };
+    Ok((code, success_response(response)).into_response())
+}
@@ -0,0 +1,3 @@
+pub mod feature;
+pub mod model;
+pub mod handler;
@@ -0,0 +1,3 @@
+pub mod response;
+
+pub use response::*;
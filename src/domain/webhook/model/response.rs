@@ -0,0 +1,9 @@
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct WebhookReceiptResponse {
+    /// `queued`, or `duplicate` for a delivery already received
+    pub status: String,
+    pub provider: String,
+    pub delivery_id: String,
+}
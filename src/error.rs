@@ -4,23 +4,55 @@ use axum::{
     Json,
 };
 use serde_json::json;
+use std::collections::HashMap;
 use thiserror::Error;
 
+use crate::domain::user::feature::ServiceError;
+use crate::infrastructure::FieldErrors;
+use crate::response::{error_response, ApiError, ApiResponse, ResponseError};
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Not found")]
     NotFound,
     #[error("Internal server error: {0}")]
     Internal(String),
+    #[allow(dead_code)]
     #[error("Bad request: {0}")]
     BadRequest(String),
     #[error("Validation error: {0}")]
     ValidationError(String),
+    /// Clashes with data that already exists; `fields` says which input
+    /// clashed, in the same shape as validation errors
+    #[error("{message}")]
+    Conflict { code: &'static str, message: String, fields: FieldErrors },
+    /// A stub route; names what is missing, e.g. "Deleting users"
+    #[error("{0} is not implemented yet")]
+    NotImplemented(String),
+}
+
+impl AppError {
+    /// 409 `USER_ALREADY_EXISTS`, pointing at the email
+    pub fn user_already_exists() -> Self {
+        AppError::Conflict {
+            code: "USER_ALREADY_EXISTS",
+            message: "User with this email already exists".to_string(),
+            fields: FieldErrors::field("email", "Email is already registered"),
+        }
+    }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, error_message) = match self {
+            AppError::Conflict { code, message, fields } => {
+                let details = HashMap::from([("fields".to_string(), json!(fields.fields()))]);
+                let error = ApiError::with_details(code, message, details).with_violations(&fields);
+                return (StatusCode::CONFLICT, axum::Json(ApiResponse::error(error))).into_response();
+            }
+            AppError::NotImplemented(_) => {
+                return error_response(StatusCode::NOT_IMPLEMENTED, "NOT_IMPLEMENTED", self.to_string()).into_response();
+            }
             AppError::NotFound => (StatusCode::NOT_FOUND, self.to_string()),
             AppError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
@@ -40,9 +72,43 @@ impl From<crate::domain::user::repository::RepositoryError> for AppError {
     fn from(err: crate::domain::user::repository::RepositoryError) -> Self {
         match err {
             crate::domain::user::repository::RepositoryError::NotFound => AppError::NotFound,
-            crate::domain::user::repository::RepositoryError::AlreadyExists => AppError::BadRequest(err.to_string()),
+            crate::domain::user::repository::RepositoryError::AlreadyExists => AppError::user_already_exists(),
             crate::domain::user::repository::RepositoryError::Database(msg) => AppError::Internal(msg),
             crate::domain::user::repository::RepositoryError::Internal(msg) => AppError::Internal(msg),
         }
     }
-}
\ No newline at end of file
+}
+impl From<ServiceError> for AppError {
+    fn from(err: ServiceError) -> Self {
+        match err {
+            ServiceError::NotFound => AppError::NotFound,
+            ServiceError::AlreadyExists => AppError::user_already_exists(),
+            ServiceError::Validation(errors) => AppError::ValidationError(errors.to_string()),
+            ServiceError::Repository(err) => err.into(),
+            ServiceError::Blocking(err) => err.into(),
+            ServiceError::PasswordHash(_) => AppError::Internal(err.to_string()),
+        }
+    }
+}
+
+/// A panicked or timed-out blocking task is a server fault; the details stay in the logs
+impl From<crate::infrastructure::BlockingError> for AppError {
+    fn from(err: crate::infrastructure::BlockingError) -> Self {
+        AppError::Internal(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn taken_email_is_a_conflict_on_the_email_field() {
+        let response = AppError::from(ServiceError::AlreadyExists).into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body: serde_json::Value =
+            serde_json::from_slice(&axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(body["error"]["code"], "USER_ALREADY_EXISTS");
+        assert_eq!(body["error"]["details"]["fields"]["email"], json!(["Email is already registered"]));
+    }
+}
@@ -0,0 +1,393 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Tracing target for events that should page someone
+pub const ALERT_TARGET: &str = "alerts";
+/// Alerts waiting to be posted; beyond this they are dropped
+const QUEUE_CAPACITY: usize = 256;
+/// Fingerprints remembered for deduplication
+const MAX_FINGERPRINTS: usize = 1000;
+/// Chat webhooks reject longer messages (Discord caps content at 2000)
+const MAX_TEXT_CHARS: usize = 1900;
+
+/// Chat webhook payload flavour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertFormat {
+    Slack,
+    Discord,
+}
+
+impl AlertFormat {
+    /// Unknown names fall back to Slack
+    pub fn from_name(name: &str) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "discord" => AlertFormat::Discord,
+            _ => AlertFormat::Slack,
+        }
+    }
+
+    fn payload(&self, text: &str) -> Value {
+        match self {
+            AlertFormat::Slack => json!({ "text": text }),
+            AlertFormat::Discord => json!({ "content": text }),
+        }
+    }
+}
+
+/// A tracing event on the `alerts` target
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpsAlert {
+    pub level: Level,
+    pub message: String,
+    pub fields: Vec<(String, String)>,
+    /// Message plus non-numeric fields, so changing counters don't defeat dedup
+    pub fingerprint: String,
+}
+
+#[derive(Default)]
+struct AlertVisitor {
+    message: String,
+    fields: Vec<(String, String)>,
+    fingerprint: String,
+}
+
+impl AlertVisitor {
+    fn push(&mut self, field: &Field, value: String, identifying: bool) {
+        if field.name() == "message" {
+            self.message = value;
+            return;
+        }
+        if identifying {
+            let _ = write!(self.fingerprint, "|{}={}", field.name(), value);
+        }
+        self.fields.push((field.name().to_string(), value));
+    }
+}
+
+impl Visit for AlertVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.push(field, value.to_string(), true);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.push(field, format!("{value:?}"), true);
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.push(field, value.to_string(), false);
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.push(field, value.to_string(), false);
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.push(field, value.to_string(), false);
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.push(field, value.to_string(), false);
+    }
+}
+
+/// What the gate lets through, with what it held back since
+#[derive(Debug, PartialEq, Eq)]
+struct Admitted {
+    /// Identical alerts suppressed since this one was last sent
+    repeats: u32,
+    /// Alerts of any kind dropped by the rate limit since the last send
+    rate_limited: u32,
+}
+
+/// Deduplication by fingerprint and a fixed-window rate limit
+struct AlertGate {
+    dedup_window: Duration,
+    max_per_minute: u32,
+    recent: HashMap<String, (Instant, u32)>,
+    window_start: Option<Instant>,
+    sent_in_window: u32,
+    rate_limited: u32,
+}
+
+impl AlertGate {
+    fn new(dedup_window: Duration, max_per_minute: u32) -> Self {
+        Self {
+            dedup_window,
+            max_per_minute,
+            recent: HashMap::new(),
+            window_start: None,
+            sent_in_window: 0,
+            rate_limited: 0,
+        }
+    }
+
+    fn admit(&mut self, fingerprint: &str, now: Instant) -> Option<Admitted> {
+        let mut repeats = 0;
+        if let Some((sent_at, suppressed)) = self.recent.get_mut(fingerprint) {
+            if now.duration_since(*sent_at) < self.dedup_window {
+                *suppressed += 1;
+                return None;
+            }
+            repeats = *suppressed;
+        }
+
+        if self.window_start.is_none_or(|start| now.duration_since(start) >= Duration::from_secs(60)) {
+            self.window_start = Some(now);
+            self.sent_in_window = 0;
+        }
+        if self.sent_in_window >= self.max_per_minute {
+            self.rate_limited += 1;
+            return None;
+        }
+        self.sent_in_window += 1;
+
+        if self.recent.len() >= MAX_FINGERPRINTS {
+            let window = self.dedup_window;
+            self.recent.retain(|_, (sent_at, _)| now.duration_since(*sent_at) < window);
+        }
+        self.recent.insert(fingerprint.to_string(), (now, 0));
+        Some(Admitted {
+            repeats,
+            rate_limited: std::mem::take(&mut self.rate_limited),
+        })
+    }
+}
+
+enum Message {
+    Post(String),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Posts operational alerts to a Slack or Discord incoming webhook.
+///
+/// Alerts with the same fingerprint are sent once per dedup window, with a
+/// count of the repeats on the next send, and at most `max_per_minute` alerts
+/// are posted per minute. Posting happens on a background task so logging
+/// never waits on the network.
+pub struct OpsAlerter {
+    format: AlertFormat,
+    source: String,
+    gate: Mutex<AlertGate>,
+    sender: mpsc::Sender<Message>,
+}
+
+impl OpsAlerter {
+    /// Must be called inside the Tokio runtime; spawns the posting task
+    pub fn spawn(
+        webhook_url: String,
+        format: AlertFormat,
+        source: String,
+        max_per_minute: u32,
+        dedup_window: Duration,
+    ) -> Arc<Self> {
+        let (sender, mut receiver) = mpsc::channel(QUEUE_CAPACITY);
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        tokio::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                match message {
+                    Message::Post(payload) => {
+                        let result = http
+                            .post(&webhook_url)
+                            .header("content-type", "application/json")
+                            .body(payload)
+                            .send()
+                            .await
+                            .and_then(|response| response.error_for_status());
+                        if let Err(err) = result {
+                            // Not on the alerts target, or a failing webhook would feed itself
+                            tracing::warn!(error = %err, "Failed to post ops alert");
+                        }
+                    }
+                    Message::Flush(done) => {
+                        let _ = done.send(());
+                    }
+                }
+            }
+        });
+
+        Arc::new(Self {
+            format,
+            source,
+            gate: Mutex::new(AlertGate::new(dedup_window, max_per_minute)),
+            sender,
+        })
+    }
+
+    pub fn submit(&self, alert: &OpsAlert) {
+        let Some(admitted) = self.gate.lock().unwrap().admit(&alert.fingerprint, Instant::now()) else {
+            return;
+        };
+        let text = self.render(alert, &admitted);
+        let _ = self.sender.try_send(Message::Post(self.format.payload(&text).to_string()));
+    }
+
+    /// Wait until every alert submitted so far has been posted
+    pub async fn flush(&self) {
+        let (done, wait) = oneshot::channel();
+        if self.sender.send(Message::Flush(done)).await.is_ok() {
+            let _ = wait.await;
+        }
+    }
+
+    fn render(&self, alert: &OpsAlert, admitted: &Admitted) -> String {
+        let mut text = format!("[{}] {}: {}", self.source, alert.level, alert.message);
+        if admitted.repeats > 0 {
+            let _ = write!(text, " (repeated {} times since the last alert)", admitted.repeats);
+        }
+        for (name, value) in &alert.fields {
+            let _ = write!(text, "\n{name}: {value}");
+        }
+        if admitted.rate_limited > 0 {
+            let _ = write!(text, "\n{} other alerts were dropped by the rate limit", admitted.rate_limited);
+        }
+        if text.chars().count() > MAX_TEXT_CHARS {
+            text = text.chars().take(MAX_TEXT_CHARS).collect::<String>() + "…";
+        }
+        text
+    }
+}
+
+/// Tracing layer forwarding `alerts`-target events at WARN or above to an `OpsAlerter`
+pub struct OpsAlertLayer {
+    alerter: Arc<OpsAlerter>,
+}
+
+impl OpsAlertLayer {
+    pub fn new(alerter: Arc<OpsAlerter>) -> Self {
+        Self { alerter }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for OpsAlertLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if metadata.target() != ALERT_TARGET || *metadata.level() > Level::WARN {
+            return;
+        }
+
+        let mut visitor = AlertVisitor::default();
+        event.record(&mut visitor);
+        let fingerprint = format!("{}{}", visitor.message, visitor.fingerprint);
+        self.alerter.submit(&OpsAlert {
+            level: *metadata.level(),
+            message: visitor.message,
+            fields: visitor.fields,
+            fingerprint,
+        });
+    }
+}
+
+/// Report panics on the `alerts` target, then run the previous hook
+pub fn install_panic_alert_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "non-string panic payload".to_string());
+        let location = info
+            .location()
+            .map(|location| format!("{}:{}", location.file(), location.line()))
+            .unwrap_or_default();
+        tracing::error!(target: ALERT_TARGET, alert = "panic", panic = %payload, location = %location, "Panic");
+        previous(info);
+    }));
+}
+
+/// Counts server errors in fixed windows and reports when a window reaches
+/// the threshold, once per window
+pub struct ErrorBurstDetector {
+    threshold: u32,
+    window: Duration,
+    state: Mutex<(Option<Instant>, u32)>,
+}
+
+impl ErrorBurstDetector {
+    pub fn new(threshold: u32, window: Duration) -> Self {
+        Self {
+            threshold,
+            window,
+            state: Mutex::new((None, 0)),
+        }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// `Some(count)` for the error that reaches the threshold
+    pub fn record(&self, now: Instant) -> Option<u32> {
+        let mut state = self.state.lock().unwrap();
+        let (start, count) = &mut *state;
+        if start.is_none_or(|start| now.duration_since(start) >= self.window) {
+            *start = Some(now);
+            *count = 0;
+        }
+        *count += 1;
+        (*count == self.threshold).then_some(*count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_alerts_are_deduplicated_and_counted() {
+        let mut gate = AlertGate::new(Duration::from_secs(300), 10);
+        let start = Instant::now();
+
+        assert_eq!(gate.admit("panic|a.rs:1", start), Some(Admitted { repeats: 0, rate_limited: 0 }));
+        assert_eq!(gate.admit("panic|a.rs:1", start + Duration::from_secs(10)), None);
+        assert_eq!(gate.admit("panic|a.rs:1", start + Duration::from_secs(20)), None);
+        assert!(gate.admit("panic|b.rs:2", start + Duration::from_secs(30)).is_some());
+
+        let later = gate.admit("panic|a.rs:1", start + Duration::from_secs(301));
+        assert_eq!(later, Some(Admitted { repeats: 2, rate_limited: 0 }));
+    }
+
+    #[test]
+    fn rate_limit_drops_and_reports_the_overflow() {
+        let mut gate = AlertGate::new(Duration::from_secs(300), 2);
+        let start = Instant::now();
+
+        assert!(gate.admit("a", start).is_some());
+        assert!(gate.admit("b", start).is_some());
+        assert_eq!(gate.admit("c", start), None);
+        assert_eq!(gate.admit("d", start), None);
+
+        let next = gate.admit("c", start + Duration::from_secs(60));
+        assert_eq!(next, Some(Admitted { repeats: 0, rate_limited: 2 }));
+    }
+
+    #[test]
+    fn bursts_fire_once_per_window() {
+        let detector = ErrorBurstDetector::new(3, Duration::from_secs(60));
+        let start = Instant::now();
+
+        assert_eq!(detector.record(start), None);
+        assert_eq!(detector.record(start), None);
+        assert_eq!(detector.record(start), Some(3));
+        assert_eq!(detector.record(start), None);
+        // A new window starts counting again
+        assert_eq!(detector.record(start + Duration::from_secs(60)), None);
+    }
+
+    #[test]
+    fn payload_matches_the_chat_service() {
+        assert_eq!(AlertFormat::from_name("Discord").payload("hi"), json!({ "content": "hi" }));
+        assert_eq!(AlertFormat::from_name("slack").payload("hi"), json!({ "text": "hi" }));
+    }
+}
@@ -0,0 +1,336 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::container::startup::StartupComponent;
+
+/// Weight of the newest window in the EWMA baselines
+const ALPHA: f64 = 0.1;
+/// Anomalous windows still move the baseline, just slowly, so a lasting
+/// shift eventually becomes the new normal instead of alerting forever
+const ANOMALOUS_ALPHA: f64 = ALPHA / 4.0;
+/// Windows a route needs before its baseline is trusted
+const WARMUP_WINDOWS: u32 = 10;
+/// Error-rate and latency checks need this many requests in a window
+const MIN_REQUESTS: u64 = 10;
+/// Latency samples kept per route and window
+const MAX_SAMPLES: usize = 2048;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    ErrorRateSpike,
+    LatencySpike,
+    TrafficSpike,
+    TrafficDrop,
+}
+
+impl AnomalyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnomalyKind::ErrorRateSpike => "error_rate_spike",
+            AnomalyKind::LatencySpike => "latency_spike",
+            AnomalyKind::TrafficSpike => "traffic_spike",
+            AnomalyKind::TrafficDrop => "traffic_drop",
+        }
+    }
+}
+
+/// A route metric that left its baseline in the last evaluated window
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Anomaly {
+    pub route: String,
+    pub kind: AnomalyKind,
+    /// Requests per window, error ratio, or p95 latency in milliseconds
+    pub observed: f64,
+    pub baseline: f64,
+    pub since: DateTime<Utc>,
+}
+
+/// Exponentially weighted mean and variance of one metric
+#[derive(Debug, Default)]
+struct Baseline {
+    mean: f64,
+    variance: f64,
+    windows: u32,
+}
+
+impl Baseline {
+    /// Standard score against the baseline; `floor` keeps a perfectly flat
+    /// history from turning the smallest wobble into an alert
+    fn score(&self, value: f64, floor: f64) -> Option<f64> {
+        if self.windows < WARMUP_WINDOWS {
+            return None;
+        }
+        let spread = self.variance.sqrt().max(self.mean.abs() * 0.1).max(floor);
+        Some((value - self.mean) / spread)
+    }
+
+    fn update(&mut self, value: f64, alpha: f64) {
+        if self.windows == 0 {
+            self.mean = value;
+        } else {
+            let diff = value - self.mean;
+            let increment = alpha * diff;
+            self.mean += increment;
+            self.variance = (1.0 - alpha) * (self.variance + diff * increment);
+        }
+        self.windows += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct RouteStats {
+    requests: u64,
+    errors: u64,
+    latencies_ms: Vec<f64>,
+    traffic: Baseline,
+    error_rate: Baseline,
+    p95_latency: Baseline,
+    active: HashMap<AnomalyKind, Anomaly>,
+}
+
+/// Per-route request baselines (EWMA mean and variance per window) used to
+/// flag error-rate, latency and traffic anomalies.
+///
+/// Requests are recorded as they finish; `evaluate` closes the current window
+/// for every route, compares it against the baselines and logs an alert on the
+/// `alerts` tracing target when an anomaly starts or clears.
+pub struct AnomalyDetector {
+    window: Duration,
+    threshold: f64,
+    routes: Mutex<HashMap<String, RouteStats>>,
+}
+
+impl AnomalyDetector {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            threshold: 4.0,
+            routes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Standard deviations from the baseline that count as anomalous
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub fn record(&self, route: &str, server_error: bool, latency: Duration) {
+        let mut routes = self.routes.lock().unwrap();
+        if !routes.contains_key(route) {
+            routes.insert(route.to_string(), RouteStats::default());
+        }
+        let stats = routes.get_mut(route).unwrap();
+
+        stats.requests += 1;
+        if server_error {
+            stats.errors += 1;
+        }
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        if stats.latencies_ms.len() < MAX_SAMPLES {
+            stats.latencies_ms.push(latency_ms);
+        } else {
+            // Overwrite in arrival order so a busy window still reflects its tail end
+            stats.latencies_ms[(stats.requests as usize) % MAX_SAMPLES] = latency_ms;
+        }
+    }
+
+    /// Close the current window and return the anomalies active after it
+    pub fn evaluate(&self) -> Vec<Anomaly> {
+        let now = Utc::now();
+        let mut routes = self.routes.lock().unwrap();
+        for (route, stats) in routes.iter_mut() {
+            self.evaluate_route(route, stats, now);
+        }
+        Self::collect(&routes)
+    }
+
+    /// Routes with a baseline, sorted
+    pub fn routes(&self) -> Vec<String> {
+        let mut routes: Vec<String> = self.routes.lock().unwrap().keys().cloned().collect();
+        routes.sort();
+        routes
+    }
+
+    /// Anomalies flagged by the most recent evaluation
+    pub fn active(&self) -> Vec<Anomaly> {
+        Self::collect(&self.routes.lock().unwrap())
+    }
+
+    fn collect(routes: &HashMap<String, RouteStats>) -> Vec<Anomaly> {
+        let mut anomalies: Vec<Anomaly> = routes.values().flat_map(|stats| stats.active.values().cloned()).collect();
+        anomalies.sort_by(|a, b| a.route.cmp(&b.route).then(a.kind.as_str().cmp(b.kind.as_str())));
+        anomalies
+    }
+
+    fn evaluate_route(&self, route: &str, stats: &mut RouteStats, now: DateTime<Utc>) {
+        let requests = std::mem::take(&mut stats.requests);
+        let errors = std::mem::take(&mut stats.errors);
+        let mut latencies = std::mem::take(&mut stats.latencies_ms);
+
+        let traffic = requests as f64;
+        let mut detected = Vec::new();
+        match stats.traffic.score(traffic, 1.0) {
+            Some(score) if score > self.threshold => detected.push((AnomalyKind::TrafficSpike, traffic, stats.traffic.mean)),
+            Some(score) if score < -self.threshold => detected.push((AnomalyKind::TrafficDrop, traffic, stats.traffic.mean)),
+            _ => {}
+        }
+
+        let mut error_rate = None;
+        let mut p95 = None;
+        if requests >= MIN_REQUESTS {
+            let rate = errors as f64 / traffic;
+            if stats.error_rate.score(rate, 0.01).is_some_and(|score| score > self.threshold) {
+                detected.push((AnomalyKind::ErrorRateSpike, rate, stats.error_rate.mean));
+            }
+            error_rate = Some(rate);
+
+            latencies.sort_by(f64::total_cmp);
+            let latency = latencies[((latencies.len() as f64 * 0.95).ceil() as usize).saturating_sub(1)];
+            if stats.p95_latency.score(latency, 5.0).is_some_and(|score| score > self.threshold) {
+                detected.push((AnomalyKind::LatencySpike, latency, stats.p95_latency.mean));
+            }
+            p95 = Some(latency);
+        }
+
+        let alpha = |kinds: &[AnomalyKind]| {
+            if detected.iter().any(|(kind, _, _)| kinds.contains(kind)) {
+                ANOMALOUS_ALPHA
+            } else {
+                ALPHA
+            }
+        };
+        let traffic_alpha = alpha(&[AnomalyKind::TrafficSpike, AnomalyKind::TrafficDrop]);
+        let error_alpha = alpha(&[AnomalyKind::ErrorRateSpike]);
+        let latency_alpha = alpha(&[AnomalyKind::LatencySpike]);
+        stats.traffic.update(traffic, traffic_alpha);
+        if let Some(rate) = error_rate {
+            stats.error_rate.update(rate, error_alpha);
+        }
+        if let Some(latency) = p95 {
+            stats.p95_latency.update(latency, latency_alpha);
+        }
+
+        let previous = std::mem::take(&mut stats.active);
+        for (kind, observed, baseline) in detected {
+            let since = match previous.get(&kind) {
+                Some(ongoing) => ongoing.since,
+                None => {
+                    tracing::warn!(
+                        target: "alerts",
+                        route = route,
+                        anomaly = kind.as_str(),
+                        observed = observed,
+                        baseline = baseline,
+                        "Anomaly detected"
+                    );
+                    now
+                }
+            };
+            stats.active.insert(kind, Anomaly { route: route.to_string(), kind, observed, baseline, since });
+        }
+        for kind in previous.keys().filter(|kind| !stats.active.contains_key(kind)) {
+            tracing::info!(target: "alerts", route = route, anomaly = kind.as_str(), "Anomaly resolved");
+        }
+    }
+
+    pub fn spawn_evaluator(self: &Arc<Self>) {
+        let detector = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + detector.window, detector.window);
+            loop {
+                ticker.tick().await;
+                detector.evaluate();
+            }
+        });
+    }
+}
+
+/// Starts closing anomaly windows once the server is about to take traffic
+pub struct AnomalyEvaluator {
+    detector: Arc<AnomalyDetector>,
+}
+
+impl AnomalyEvaluator {
+    pub fn new(detector: Arc<AnomalyDetector>) -> Self {
+        Self { detector }
+    }
+}
+
+#[async_trait]
+impl StartupComponent for AnomalyEvaluator {
+    fn name(&self) -> &'static str {
+        "anomaly_detector"
+    }
+
+    async fn start(&self) -> Result<(), String> {
+        self.detector.spawn_evaluator();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steady(detector: &AnomalyDetector, windows: u32) {
+        for window in 0..windows {
+            // 100 ± 5 requests, 1% errors, ~20ms
+            for request in 0..(100 + window % 5 * 2) {
+                detector.record("GET /api/users", request % 100 == 0, Duration::from_millis(18 + (request % 5) as u64));
+            }
+            assert!(detector.evaluate().is_empty());
+        }
+    }
+
+    #[test]
+    fn error_and_latency_spikes_are_flagged_after_warmup() {
+        let detector = AnomalyDetector::new(Duration::from_secs(60));
+        steady(&detector, 20);
+
+        for request in 0..100 {
+            detector.record("GET /api/users", request % 4 == 0, Duration::from_millis(400));
+        }
+        let anomalies = detector.evaluate();
+        let kinds: Vec<AnomalyKind> = anomalies.iter().map(|anomaly| anomaly.kind).collect();
+        assert_eq!(kinds, vec![AnomalyKind::ErrorRateSpike, AnomalyKind::LatencySpike]);
+        assert_eq!(anomalies[0].observed, 0.25);
+        assert_eq!(detector.active(), anomalies);
+    }
+
+    #[test]
+    fn silent_route_is_a_traffic_drop_until_it_recovers() {
+        let detector = AnomalyDetector::new(Duration::from_secs(60));
+        steady(&detector, 20);
+
+        let anomalies = detector.evaluate();
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].kind, AnomalyKind::TrafficDrop);
+        let since = anomalies[0].since;
+
+        // Still silent: same anomaly, same start time
+        assert_eq!(detector.evaluate()[0].since, since);
+
+        steady(&detector, 1);
+        assert!(detector.active().is_empty());
+    }
+
+    #[test]
+    fn nothing_is_flagged_during_warmup() {
+        let detector = AnomalyDetector::new(Duration::from_secs(60));
+        steady(&detector, 3);
+        for _ in 0..1000 {
+            detector.record("GET /api/users", true, Duration::from_secs(2));
+        }
+        assert!(detector.evaluate().is_empty());
+    }
+}
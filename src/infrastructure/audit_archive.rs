@@ -0,0 +1,356 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{run_blocking, AuditExporter, AuditJournal, AuditRecord};
+use crate::container::startup::StartupComponent;
+
+/// Archive passes rewrite the journal; past this the caller stops waiting
+const ARCHIVE_TIMEOUT: Duration = Duration::from_secs(300);
+/// Records per page of `AuditLog::query` when the caller asks for none or too many
+const MAX_PAGE: usize = 500;
+
+/// What one archive pass moved out of the journal
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct ArchiveOutcome {
+    pub archived: usize,
+    /// The segment written, when any records were archived
+    pub segment: Option<PathBuf>,
+}
+
+/// Move the leading records of the journal at `journal` recorded before
+/// `cutoff`, and numbered up to `through_seq`, into
+/// `<dir>/audit-<first seq>-<last seq>.ndjson.gz`, then rewrite the journal
+/// without them. The newest record always stays, so numbering continues
+/// after a restart. Only the journal's writer may call this.
+///
+/// The segment and the rewritten journal are each renamed into place, so
+/// neither is ever seen half written. A crash between the two renames leaves
+/// the records in both; `AuditLog::query` reads each seq once, and the next
+/// pass writes a segment starting at the same seq that supersedes this one.
+pub(crate) fn archive_journal(journal: &Path, cutoff: DateTime<Utc>, through_seq: u64, dir: &Path) -> io::Result<ArchiveOutcome> {
+    let mut lines: Vec<String> = match File::open(journal) {
+        Ok(file) => BufReader::new(file).lines().collect::<io::Result<_>>()?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(ArchiveOutcome::default()),
+        Err(err) => return Err(err),
+    };
+    let mut aged = Vec::new();
+    for line in lines.iter().take(lines.len().saturating_sub(1)) {
+        match serde_json::from_str::<AuditRecord>(line) {
+            Ok(record) if record.recorded_at < cutoff && record.seq <= through_seq => aged.push(record.seq),
+            _ => break,
+        }
+    }
+    let (Some(&first), Some(&last)) = (aged.first(), aged.last()) else {
+        return Ok(ArchiveOutcome::default());
+    };
+    let kept = lines.split_off(aged.len());
+
+    std::fs::create_dir_all(dir)?;
+    let name = format!("audit-{first:012}-{last:012}.ndjson.gz");
+    let partial = dir.join(format!(".{name}.partial"));
+    let mut segment = GzEncoder::new(File::create(&partial)?, Compression::default());
+    for line in &lines {
+        segment.write_all(line.as_bytes())?;
+        segment.write_all(b"\n")?;
+    }
+    segment.finish()?.sync_all()?;
+    std::fs::rename(&partial, dir.join(&name))?;
+
+    let mut rest = journal.to_path_buf().into_os_string();
+    rest.push(".partial");
+    let rest = PathBuf::from(rest);
+    let mut file = File::create(&rest)?;
+    for line in &kept {
+        file.write_all(line.as_bytes())?;
+        file.write_all(b"\n")?;
+    }
+    file.sync_all()?;
+    std::fs::rename(&rest, journal)?;
+    Ok(ArchiveOutcome { archived: aged.len(), segment: Some(dir.join(name)) })
+}
+
+/// Filters of an audit trail read; every field is optional
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditQuery {
+    /// Only records of this `audit_event`
+    pub event: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// Only records numbered below this, to page back from `next_before_seq`
+    pub before_seq: Option<u64>,
+    /// Records per page, at most 500; 50 when unset
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, record: &AuditRecord) -> bool {
+        self.event.as_ref().is_none_or(|event| *event == record.event)
+            && self.since.is_none_or(|since| record.recorded_at >= since)
+            && self.until.is_none_or(|until| record.recorded_at < until)
+            && self.before_seq.is_none_or(|before| record.seq < before)
+    }
+}
+
+/// One page of the audit trail, newest first
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditPage {
+    pub records: Vec<AuditRecord>,
+    /// Whether any of the records came from archived segments
+    pub archived: bool,
+    /// Pass as `before_seq` for the next page; absent on the last one
+    pub next_before_seq: Option<u64>,
+}
+
+/// Reads the audit trail across the live journal and its archived segments,
+/// so callers see one history wherever the records are kept
+pub struct AuditLog {
+    journal: PathBuf,
+    archive_dir: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(journal: &Path, archive_dir: &Path) -> Self {
+        Self { journal: journal.to_path_buf(), archive_dir: archive_dir.to_path_buf() }
+    }
+
+    /// Newest records matching `query`. Segments are only opened when the
+    /// journal alone does not fill the page.
+    pub fn query(&self, query: &AuditQuery) -> io::Result<AuditPage> {
+        let limit = query.limit.unwrap_or(50).clamp(1, MAX_PAGE);
+        // One extra tells whether another page follows
+        let wanted = limit + 1;
+        let journal = read_lines(&self.journal, false)?;
+        // Everything below this is read from segments; a crash mid-archive may
+        // leave records in both, and only the newer copy counts
+        let journal_first = journal.first().map_or(u64::MAX, |record| record.seq);
+        let mut floor = journal_first;
+        let mut records = newest_matching(journal, query, wanted);
+        for (first, _, path) in self.segments()? {
+            if records.len() >= wanted {
+                break;
+            }
+            if query.before_seq.is_some_and(|before| first >= before) || first >= floor {
+                continue;
+            }
+            let mut segment = read_lines(&path, true)?;
+            segment.retain(|record| record.seq < floor);
+            floor = first;
+            // Segments hold consecutive records, so older ones are all before `since` too
+            let reaches_since = segment.first().zip(query.since).is_some_and(|(oldest, since)| oldest.recorded_at < since);
+            records.extend(newest_matching(segment, query, wanted - records.len()));
+            if reaches_since {
+                break;
+            }
+        }
+        let next_before_seq = (records.len() > limit).then(|| records[limit - 1].seq);
+        records.truncate(limit);
+        let archived = records.iter().any(|record| record.seq < journal_first);
+        Ok(AuditPage { records, archived, next_before_seq })
+    }
+
+    /// `(first seq, last seq, path)` of each segment, newest first. Of two
+    /// starting at the same seq, left by a crash mid-archive, the longer one
+    /// comes first, and the other is skipped as already read.
+    fn segments(&self) -> io::Result<Vec<(u64, u64, PathBuf)>> {
+        let entries = match std::fs::read_dir(&self.archive_dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        let mut segments = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+            let range = name.strip_prefix("audit-").and_then(|rest| rest.strip_suffix(".ndjson.gz"));
+            if let Some((Ok(first), Ok(last))) = range.and_then(|range| range.split_once('-')).map(|(first, last)| (first.parse::<u64>(), last.parse::<u64>())) {
+                segments.push((first, last, path));
+            }
+        }
+        segments.sort_by_key(|&(first, last, _)| std::cmp::Reverse((first, last)));
+        Ok(segments)
+    }
+}
+
+fn read_lines(path: &Path, compressed: bool) -> io::Result<Vec<AuditRecord>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let reader: Box<dyn BufRead> = if compressed {
+        Box::new(BufReader::new(GzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+    let mut records = Vec::new();
+    for line in reader.lines() {
+        if let Ok(record) = serde_json::from_str::<AuditRecord>(&line?) {
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+fn newest_matching(records: Vec<AuditRecord>, query: &AuditQuery, wanted: usize) -> Vec<AuditRecord> {
+    records.into_iter().rev().filter(|record| query.matches(record)).take(wanted).collect()
+}
+
+/// Moves audit records older than `after` from the journal into compressed
+/// segments on a schedule. With an exporter, records it has not shipped yet
+/// stay in the journal until it has.
+pub struct AuditArchiver {
+    journal: Arc<AuditJournal>,
+    dir: PathBuf,
+    after: Duration,
+    interval: Duration,
+    exporter: Option<AuditExporter>,
+}
+
+impl AuditArchiver {
+    pub fn new(journal: Arc<AuditJournal>, dir: &Path, after: Duration) -> Self {
+        Self { journal, dir: dir.to_path_buf(), after, interval: Duration::from_secs(3600), exporter: None }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Hold back records past the exporter's watermark
+    pub fn with_exporter(mut self, exporter: AuditExporter) -> Self {
+        self.exporter = Some(exporter);
+        self
+    }
+
+    pub async fn archive_now(&self) -> io::Result<ArchiveOutcome> {
+        let cutoff = Utc::now() - chrono::Duration::from_std(self.after).unwrap_or(chrono::Duration::MAX);
+        let through_seq = match &self.exporter {
+            Some(exporter) => exporter.watermark().await.seq,
+            None => u64::MAX,
+        };
+        let (journal, dir) = (self.journal.clone(), self.dir.clone());
+        run_blocking("audit_archive", ARCHIVE_TIMEOUT, move || journal.archive(cutoff, through_seq, &dir))
+            .await
+            .map_err(io::Error::other)?
+    }
+}
+
+#[async_trait]
+impl StartupComponent for AuditArchiver {
+    fn name(&self) -> &'static str {
+        "audit_archiver"
+    }
+
+    async fn start(&self) -> Result<(), String> {
+        let archiver = Self {
+            journal: self.journal.clone(),
+            dir: self.dir.clone(),
+            after: self.after,
+            interval: self.interval,
+            exporter: self.exporter.clone(),
+        };
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(archiver.interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                match archiver.archive_now().await {
+                    Ok(outcome) if outcome.archived > 0 => {
+                        tracing::info!(archived = outcome.archived, segment = ?outcome.segment, "Audit records archived")
+                    }
+                    Ok(_) => {}
+                    Err(err) => tracing::warn!(error = %err, "Audit archive failed; retrying next interval"),
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_journal(path: &Path, records: &[(u64, i64, &str)]) {
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path).unwrap();
+        for &(seq, days_ago, event) in records {
+            let record = AuditRecord {
+                seq,
+                recorded_at: Utc::now() - chrono::Duration::days(days_ago),
+                level: "INFO".to_string(),
+                event: event.to_string(),
+                message: String::new(),
+                fields: Default::default(),
+            };
+            writeln!(file, "{}", serde_json::to_string(&record).unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn aged_records_move_to_segments_and_reads_fall_through_to_them() {
+        let dir = std::env::temp_dir().join(format!("audit-archive-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (journal, archive) = (dir.join("audit.ndjson"), dir.join("archive"));
+        write_journal(&journal, &[(1, 40, "login"), (2, 35, "cache_invalidated"), (3, 31, "login"), (4, 2, "login"), (5, 1, "login")]);
+
+        let cutoff = Utc::now() - chrono::Duration::days(30);
+        // Not past what the exporter has shipped
+        let outcome = archive_journal(&journal, cutoff, 2, &archive).unwrap();
+        assert_eq!(outcome.archived, 2);
+        assert!(archive.join("audit-000000000001-000000000002.ndjson.gz").exists());
+        assert_eq!(archive_journal(&journal, cutoff, u64::MAX, &archive).unwrap().archived, 1);
+        assert_eq!(read_lines(&journal, false).unwrap().iter().map(|record| record.seq).collect::<Vec<_>>(), [4, 5]);
+
+        let log = AuditLog::new(&journal, &archive);
+        let page = log.query(&AuditQuery { event: Some("login".to_string()), limit: Some(2), ..Default::default() }).unwrap();
+        assert_eq!((page.records.iter().map(|record| record.seq).collect::<Vec<_>>(), page.archived), (vec![5, 4], false));
+        let page = log.query(&AuditQuery { event: Some("login".to_string()), before_seq: page.next_before_seq, ..Default::default() }).unwrap();
+        assert_eq!(page.records.iter().map(|record| record.seq).collect::<Vec<_>>(), [3, 1]);
+        assert_eq!((page.archived, page.next_before_seq), (true, None));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_crash_between_the_renames_never_duplicates_records() {
+        let dir = std::env::temp_dir().join(format!("audit-archive-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (journal, archive) = (dir.join("audit.ndjson"), dir.join("archive"));
+        write_journal(&journal, &[(1, 40, "login"), (2, 35, "login"), (3, 31, "login"), (4, 1, "login")]);
+        let cutoff = Utc::now() - chrono::Duration::days(30);
+        let seqs = |log: &AuditLog| log.query(&AuditQuery::default()).unwrap().records.iter().map(|record| record.seq).collect::<Vec<_>>();
+
+        // The segment was renamed into place, the rewritten journal never was
+        let before = std::fs::read(&journal).unwrap();
+        archive_journal(&journal, cutoff, 2, &archive).unwrap();
+        std::fs::write(&journal, before).unwrap();
+        let log = AuditLog::new(&journal, &archive);
+        assert_eq!(seqs(&log), [4, 3, 2, 1]);
+
+        // The next pass starts at the same seq; its longer segment wins
+        assert_eq!(archive_journal(&journal, cutoff, u64::MAX, &archive).unwrap().archived, 3);
+        assert_eq!(std::fs::read_dir(&archive).unwrap().count(), 2);
+        assert_eq!(seqs(&log), [4, 3, 2, 1]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn the_newest_record_stays_in_the_journal() {
+        let dir = std::env::temp_dir().join(format!("audit-archive-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let journal = dir.join("audit.ndjson");
+        write_journal(&journal, &[(1, 10, "login"), (2, 9, "login")]);
+        let outcome = archive_journal(&journal, Utc::now(), u64::MAX, &dir.join("archive")).unwrap();
+        assert_eq!(outcome.archived, 1);
+        assert_eq!(read_lines(&journal, false).unwrap()[0].seq, 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
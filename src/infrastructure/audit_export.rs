@@ -0,0 +1,519 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::{
+    avro_long, avro_string, protobuf_string, protobuf_varint, read_journal, run_blocking, AuditRecord, ConsumerStats, RegistrationError,
+    RegistryPayload, RegistrySerializer, Watermark,
+};
+use crate::container::startup::StartupComponent;
+
+/// A rescan of a large journal takes a while; past this the caller stops waiting
+const JOURNAL_READ_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, thiserror::Error)]
+pub enum AuditExportError {
+    #[error("Audit journal unreadable: {0}")]
+    Journal(#[from] std::io::Error),
+    #[error("Warehouse write failed: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Audit schema not registered: {0}")]
+    Schema(#[from] RegistrationError),
+}
+
+impl RegistryPayload for AuditRecord {
+    /// `fields` is JSON text, since each event has its own
+    const AVRO_SCHEMA: &'static str = r#"{"type":"record","name":"AuditRecord","namespace":"rust_boilerplate.audit","fields":[{"name":"seq","type":"long"},{"name":"recorded_at","type":{"type":"long","logicalType":"timestamp-micros"}},{"name":"level","type":"string"},{"name":"event","type":"string"},{"name":"message","type":"string"},{"name":"fields","type":"string","doc":"JSON object text"}]}"#;
+
+    /// The same fields as the Avro record
+    const PROTOBUF_SCHEMA: &'static str = r#"syntax = "proto3";
+package rust_boilerplate.audit;
+
+message AuditRecord {
+  uint64 seq = 1;
+  // Unix microseconds
+  int64 recorded_at = 2;
+  string level = 3;
+  string event = 4;
+  string message = 5;
+  // JSON object text
+  string fields = 6;
+}
+"#;
+
+    fn encode_avro(&self, out: &mut Vec<u8>) {
+        avro_long(self.seq as i64, out);
+        avro_long(self.recorded_at.timestamp_micros(), out);
+        avro_string(&self.level, out);
+        avro_string(&self.event, out);
+        avro_string(&self.message, out);
+        avro_string(&serde_json::to_string(&self.fields).unwrap_or_default(), out);
+    }
+
+    fn encode_protobuf(&self, out: &mut Vec<u8>) {
+        protobuf_varint(1, self.seq, out);
+        protobuf_varint(2, self.recorded_at.timestamp_micros() as u64, out);
+        protobuf_string(3, &self.level, out);
+        protobuf_string(4, &self.event, out);
+        protobuf_string(5, &self.message, out);
+        protobuf_string(6, &serde_json::to_string(&self.fields).unwrap_or_default(), out);
+    }
+}
+
+/// Where exported audit records land. Writes may be repeated for the same
+/// records after a crash or a replay, so sinks should keep one copy per
+/// `(source, seq)`.
+#[async_trait]
+pub trait WarehouseSink: Send + Sync {
+    /// Names the watermark file, so each sink keeps its own position
+    fn name(&self) -> &'static str;
+
+    async fn write(&self, source: &str, records: &[AuditRecord]) -> Result<(), AuditExportError>;
+}
+
+/// `COPY` into the `audit_events` table (`migrations/004_create_audit_events.sql`)
+/// through a staging table, so records already there are skipped
+pub struct PostgresCopySink {
+    pool: PgPool,
+}
+
+impl PostgresCopySink {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl WarehouseSink for PostgresCopySink {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn write(&self, source: &str, records: &[AuditRecord]) -> Result<(), AuditExportError> {
+        let mut csv = String::new();
+        for record in records {
+            let fields = serde_json::to_string(&record.fields).unwrap_or_default();
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{}",
+                csv_field(source),
+                record.seq,
+                csv_field(&record.recorded_at.to_rfc3339()),
+                csv_field(&record.level),
+                csv_field(&record.event),
+                csv_field(&record.message),
+                csv_field(&fields)
+            );
+        }
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("CREATE TEMP TABLE audit_events_batch (LIKE audit_events) ON COMMIT DROP")
+            .execute(&mut *tx)
+            .await?;
+        let mut copy = tx
+            .copy_in_raw("COPY audit_events_batch (source, seq, recorded_at, level, event, message, fields) FROM STDIN WITH (FORMAT csv)")
+            .await?;
+        copy.send(csv.into_bytes()).await?;
+        copy.finish().await?;
+        sqlx::query("INSERT INTO audit_events SELECT * FROM audit_events_batch ON CONFLICT (source, seq) DO NOTHING")
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+/// Batch files, `audit-<source>-<first seq>-<last seq>.ndjson`, for object
+/// storage sync or a warehouse load job to pick up. With an Avro or Protobuf
+/// serializer they are `.avro.bin` or `.protobuf.bin` instead: each record in
+/// the schema registry's wire format, after its length as a big-endian `u32`.
+pub struct FileSink {
+    dir: PathBuf,
+    serializer: Arc<RegistrySerializer<AuditRecord>>,
+}
+
+impl FileSink {
+    pub fn new(dir: &Path) -> Self {
+        Self { dir: dir.to_path_buf(), serializer: Arc::new(RegistrySerializer::json()) }
+    }
+
+    /// Registers the audit schema before the first batch; until that works,
+    /// batches fail and are retried, so a load job never sees mixed formats
+    pub fn with_serializer(mut self, serializer: Arc<RegistrySerializer<AuditRecord>>) -> Self {
+        self.serializer = serializer;
+        self
+    }
+}
+
+#[async_trait]
+impl WarehouseSink for FileSink {
+    fn name(&self) -> &'static str {
+        "files"
+    }
+
+    async fn write(&self, source: &str, records: &[AuditRecord]) -> Result<(), AuditExportError> {
+        let (Some(first), Some(last)) = (records.first(), records.last()) else {
+            return Ok(());
+        };
+        if self.serializer.uses_registry() {
+            self.serializer.register().await?;
+        }
+        let mut body = Vec::new();
+        for record in records {
+            match self.serializer.encode(record) {
+                Some(framed) => {
+                    body.extend_from_slice(&(framed.len() as u32).to_be_bytes());
+                    body.extend_from_slice(&framed);
+                }
+                None => {
+                    let mut line = serde_json::to_value(record).unwrap_or_default();
+                    line["source"] = source.into();
+                    body.extend_from_slice(line.to_string().as_bytes());
+                    body.push(b'\n');
+                }
+            }
+        }
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let extension = match self.serializer.format_name() {
+            "json" => "ndjson".to_string(),
+            format => format!("{format}.bin"),
+        };
+        let name = format!("audit-{source}-{:012}-{:012}.{extension}", first.seq, last.seq);
+        // Renamed into place, so a reader never picks up half a file
+        let partial = self.dir.join(format!(".{name}.partial"));
+        tokio::fs::write(&partial, body).await?;
+        tokio::fs::rename(&partial, self.dir.join(name)).await?;
+        Ok(())
+    }
+}
+
+/// What one export pass shipped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct ExportSummary {
+    pub exported: usize,
+    pub batches: usize,
+    /// The last record shipped, or the previous watermark when none were
+    pub watermark: u64,
+}
+
+/// Ships the audit journal to a warehouse sink in batches, after a
+/// watermark kept next to the journal (`<journal>.<sink>.watermark`). The
+/// watermark moves only after a batch is written, so a failed or
+/// interrupted export is retried from the same record.
+#[derive(Clone)]
+pub struct AuditExporter {
+    journal: PathBuf,
+    sink: Arc<dyn WarehouseSink>,
+    source: String,
+    batch_size: usize,
+    interval: Duration,
+    stats: Arc<ConsumerStats>,
+}
+
+impl AuditExporter {
+    /// `source` tells this journal's records apart from other instances' in the warehouse
+    pub fn new(journal: &Path, sink: Arc<dyn WarehouseSink>, source: &str, batch_size: usize) -> Self {
+        Self {
+            journal: journal.to_path_buf(),
+            sink,
+            source: source.to_string(),
+            batch_size: batch_size.max(1),
+            interval: Duration::ZERO,
+            stats: Arc::new(ConsumerStats::new("warehouse")),
+        }
+    }
+
+    /// Export on this schedule once started; zero leaves exports to the CLI
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_consumer_stats(mut self, stats: Arc<ConsumerStats>) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    fn watermark_path(&self) -> PathBuf {
+        let mut path = self.journal.clone().into_os_string();
+        path.push(format!(".{}.watermark", self.sink.name()));
+        PathBuf::from(path)
+    }
+
+    pub async fn watermark(&self) -> Watermark {
+        tokio::fs::read(self.watermark_path())
+            .await
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    /// Up to a batch of records after `after`, read on the blocking pool:
+    /// after an archive pass the offset is stale and the whole journal is
+    /// scanned again
+    async fn read_batch(&self, after: Watermark) -> Result<(Vec<AuditRecord>, Watermark), AuditExportError> {
+        let (journal, limit) = (self.journal.clone(), self.batch_size);
+        Ok(run_blocking("audit_export_read", JOURNAL_READ_TIMEOUT, move || read_journal(&journal, after, limit))
+            .await
+            .map_err(std::io::Error::other)??)
+    }
+
+    /// Everything recorded since the watermark, moving it after each batch
+    pub async fn export_pending(&self) -> Result<ExportSummary, AuditExportError> {
+        let mut watermark = self.watermark().await;
+        let mut summary = ExportSummary { exported: 0, batches: 0, watermark: watermark.seq };
+        loop {
+            let (records, next) = self.read_batch(watermark).await?;
+            if records.is_empty() {
+                // Skipped lines still move the offset
+                if next != watermark {
+                    self.store_watermark(next).await?;
+                }
+                return Ok(summary);
+            }
+            let started = Instant::now();
+            self.stats.started();
+            let written = self.sink.write(&self.source, &records).await;
+            self.stats.finished(started.elapsed(), written.is_ok());
+            written?;
+            self.store_watermark(next).await?;
+            watermark = next;
+            summary.exported += records.len();
+            summary.batches += 1;
+            summary.watermark = next.seq;
+        }
+    }
+
+    /// Ship every record from `from_seq` on again, e.g. into a new warehouse
+    /// table, without moving the watermark
+    pub async fn replay(&self, from_seq: u64) -> Result<ExportSummary, AuditExportError> {
+        let mut position = Watermark { seq: from_seq.saturating_sub(1), offset: 0 };
+        let mut summary = ExportSummary { exported: 0, batches: 0, watermark: position.seq };
+        loop {
+            let (records, next) = self.read_batch(position).await?;
+            if records.is_empty() {
+                return Ok(summary);
+            }
+            self.sink.write(&self.source, &records).await?;
+            position = next;
+            summary.exported += records.len();
+            summary.batches += 1;
+            summary.watermark = next.seq;
+        }
+    }
+
+    async fn store_watermark(&self, watermark: Watermark) -> Result<(), AuditExportError> {
+        let path = self.watermark_path();
+        let partial = path.with_extension("watermark.partial");
+        tokio::fs::write(&partial, serde_json::to_vec(&watermark).unwrap_or_default()).await?;
+        tokio::fs::rename(&partial, &path).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StartupComponent for AuditExporter {
+    fn name(&self) -> &'static str {
+        "audit_exporter"
+    }
+
+    async fn start(&self) -> Result<(), String> {
+        self.stats.set_connected(true);
+        if self.interval.is_zero() {
+            return Ok(());
+        }
+        let exporter = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(exporter.interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                match exporter.export_pending().await {
+                    Ok(summary) if summary.exported > 0 => {
+                        tracing::info!(sink = exporter.sink.name(), exported = summary.exported, watermark = summary.watermark, "Audit events exported")
+                    }
+                    Ok(_) => {}
+                    Err(err) => {
+                        exporter.stats.retried();
+                        tracing::warn!(sink = exporter.sink.name(), error = %err, "Audit export failed; retrying next interval")
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Keeps what it was sent; fails every write while `failing` is set
+    #[derive(Default)]
+    struct MemorySink {
+        written: Mutex<Vec<u64>>,
+        failing: std::sync::atomic::AtomicBool,
+    }
+
+    #[async_trait]
+    impl WarehouseSink for MemorySink {
+        fn name(&self) -> &'static str {
+            "memory"
+        }
+
+        async fn write(&self, _source: &str, records: &[AuditRecord]) -> Result<(), AuditExportError> {
+            if self.failing.load(std::sync::atomic::Ordering::Relaxed) {
+                return Err(AuditExportError::Journal(std::io::Error::other("warehouse down")));
+            }
+            self.written.lock().unwrap().extend(records.iter().map(|record| record.seq));
+            Ok(())
+        }
+    }
+
+    fn write_journal(path: &Path, seqs: std::ops::RangeInclusive<u64>) {
+        use std::io::Write;
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path).unwrap();
+        for seq in seqs {
+            let record = AuditRecord {
+                seq,
+                recorded_at: chrono::Utc::now(),
+                level: "INFO".to_string(),
+                event: "cache_invalidated".to_string(),
+                message: String::new(),
+                fields: Default::default(),
+            };
+            writeln!(file, "{}", serde_json::to_string(&record).unwrap()).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn exports_resume_from_the_watermark_and_failed_batches_are_retried() {
+        let dir = std::env::temp_dir().join(format!("audit-export-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let journal = dir.join("audit.ndjson");
+        let sink = Arc::new(MemorySink::default());
+        let exporter = AuditExporter::new(&journal, sink.clone(), "api-1", 2);
+
+        write_journal(&journal, 1..=3);
+        let summary = exporter.export_pending().await.unwrap();
+        assert_eq!(summary, ExportSummary { exported: 3, batches: 2, watermark: 3 });
+
+        write_journal(&journal, 4..=5);
+        sink.failing.store(true, std::sync::atomic::Ordering::Relaxed);
+        assert!(exporter.export_pending().await.is_err());
+        assert_eq!(exporter.watermark().await.seq, 3);
+        sink.failing.store(false, std::sync::atomic::Ordering::Relaxed);
+        assert_eq!(exporter.export_pending().await.unwrap().exported, 2);
+        assert_eq!(*sink.written.lock().unwrap(), [1, 2, 3, 4, 5]);
+
+        // A replay sends records again without touching the watermark
+        assert_eq!(exporter.replay(4).await.unwrap().exported, 2);
+        assert_eq!(*sink.written.lock().unwrap(), [1, 2, 3, 4, 5, 4, 5]);
+        assert_eq!(exporter.watermark().await.seq, 5);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn file_sink_writes_one_named_batch_file() {
+        let dir = std::env::temp_dir().join(format!("audit-files-{}", uuid::Uuid::new_v4()));
+        let journal = dir.join("audit.ndjson");
+        std::fs::create_dir_all(&dir).unwrap();
+        write_journal(&journal, 1..=2);
+        let exporter = AuditExporter::new(&journal, Arc::new(FileSink::new(&dir.join("out"))), "api-1", 10);
+        exporter.export_pending().await.unwrap();
+
+        let body = std::fs::read_to_string(dir.join("out/audit-api-1-000000000001-000000000002.ndjson")).unwrap();
+        let lines: Vec<serde_json::Value> = body.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!((lines.len(), lines[1]["seq"].as_u64(), lines[1]["source"].as_str()), (2, Some(2), Some("api-1")));
+        assert_eq!(csv_field(r#"say "hi", bye"#), r#""say ""hi"", bye""#);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// The Protobuf schema, as prost would generate it
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct DecodedRecord {
+        #[prost(uint64, tag = "1")]
+        seq: u64,
+        #[prost(int64, tag = "2")]
+        recorded_at: i64,
+        #[prost(string, tag = "3")]
+        level: String,
+        #[prost(string, tag = "4")]
+        event: String,
+        #[prost(string, tag = "5")]
+        message: String,
+        #[prost(string, tag = "6")]
+        fields: String,
+    }
+
+    /// The wire-format frames of a `.bin` batch file
+    fn frames(body: &[u8]) -> Vec<&[u8]> {
+        let mut frames = Vec::new();
+        let mut rest = body;
+        while let Some((length, tail)) = rest.split_first_chunk::<4>() {
+            let (frame, tail) = tail.split_at(u32::from_be_bytes(*length) as usize);
+            frames.push(frame);
+            rest = tail;
+        }
+        frames
+    }
+
+    #[tokio::test]
+    async fn file_sink_frames_records_for_the_schema_registry() {
+        use crate::infrastructure::decode_avro;
+        use prost::Message;
+
+        let dir = std::env::temp_dir().join(format!("audit-registry-{}", uuid::Uuid::new_v4()));
+        let mut record = AuditRecord {
+            seq: 7,
+            recorded_at: chrono::Utc::now(),
+            level: "INFO".to_string(),
+            event: "impersonation_started".to_string(),
+            message: "Impersonation started".to_string(),
+            fields: Default::default(),
+        };
+        record.fields.insert("actor".to_string(), "admin".into());
+
+        let protobuf = Arc::new(RegistrySerializer::from_name("protobuf", "audit-value").with_schema_id(5));
+        FileSink::new(&dir).with_serializer(protobuf).write("api-1", &[record.clone()]).await.unwrap();
+        let body = std::fs::read(dir.join("audit-api-1-000000000007-000000000007.protobuf.bin")).unwrap();
+        let [frame] = frames(&body)[..] else { panic!("one record, one frame") };
+        assert_eq!(frame[..6], [0, 0, 0, 0, 5, 0]);
+        assert_eq!(
+            DecodedRecord::decode(&frame[6..]).unwrap(),
+            DecodedRecord {
+                seq: 7,
+                recorded_at: record.recorded_at.timestamp_micros(),
+                level: "INFO".to_string(),
+                event: "impersonation_started".to_string(),
+                message: "Impersonation started".to_string(),
+                fields: r#"{"actor":"admin"}"#.to_string(),
+            }
+        );
+
+        let avro = Arc::new(RegistrySerializer::from_name("avro", "audit-value").with_schema_id(6));
+        FileSink::new(&dir).with_serializer(avro).write("api-1", &[record.clone(), record.clone()]).await.unwrap();
+        let body = std::fs::read(dir.join("audit-api-1-000000000007-000000000007.avro.bin")).unwrap();
+        for frame in frames(&body) {
+            assert_eq!(
+                decode_avro::<AuditRecord>(frame),
+                serde_json::json!({
+                    "seq": 7,
+                    "recorded_at": record.recorded_at.timestamp_micros(),
+                    "level": "INFO",
+                    "event": "impersonation_started",
+                    "message": "Impersonation started",
+                    "fields": r#"{"actor":"admin"}"#,
+                })
+            );
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
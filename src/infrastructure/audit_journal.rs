@@ -0,0 +1,442 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, sync_channel, Sender, SyncSender, TrySendError};
+use std::sync::Arc;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+use super::{archive_journal, ArchiveOutcome};
+
+/// Tracing target of events that are kept for the audit trail
+pub const AUDIT_TARGET: &str = "audit";
+/// Entries waiting to be written; beyond this they are dropped and counted
+const QUEUE_CAPACITY: usize = 4096;
+
+/// One `audit`-target event as written to the journal, one JSON line each
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// Numbered in the order written, across restarts of the same journal
+    pub seq: u64,
+    pub recorded_at: DateTime<Utc>,
+    pub level: String,
+    /// The `audit_event` field, e.g. `cache_invalidated`
+    pub event: String,
+    pub message: String,
+    /// Every other field of the event
+    pub fields: Map<String, Value>,
+}
+
+/// Where to continue reading the journal: the last record handled and the
+/// byte offset just after it. The offset is only a hint, since archiving
+/// rewrites the file; the record found there must be the next `seq`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Watermark {
+    pub seq: u64,
+    pub offset: u64,
+}
+
+/// Append-only file of audit events, fed by `AuditJournalLayer`. A
+/// background thread writes the entries, so logging never waits on the disk.
+/// The journal belongs to one process; give each process its own path.
+pub struct AuditJournal {
+    path: PathBuf,
+    sender: SyncSender<Entry>,
+    dropped: AtomicU64,
+}
+
+enum Entry {
+    Record(AuditRecord),
+    /// Move old records to the archive; done on the writer thread, so no
+    /// append lands in between
+    Archive {
+        cutoff: DateTime<Utc>,
+        through_seq: u64,
+        dir: PathBuf,
+        done: Sender<io::Result<ArchiveOutcome>>,
+    },
+}
+
+impl AuditJournal {
+    /// Open or create the journal and continue its numbering
+    pub fn open(path: &Path) -> io::Result<Arc<Self>> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let open = |path: &Path| OpenOptions::new().create(true).append(true).read(true).open(path);
+        let mut file = open(path)?;
+        let mut next_seq = last_seq(&mut file)? + 1;
+        // Set when a failed write left a partial line that could not be cut off
+        let mut torn = false;
+        let (sender, receiver) = sync_channel::<Entry>(QUEUE_CAPACITY);
+        let journal = path.to_path_buf();
+        std::thread::Builder::new().name("audit-journal".to_string()).spawn(move || {
+            for entry in receiver {
+                match entry {
+                    Entry::Record(mut record) => {
+                        record.seq = next_seq;
+                        let mut line = serde_json::to_vec(&record).unwrap_or_default();
+                        line.push(b'\n');
+                        match append_line(&mut file, &line, &mut torn) {
+                            Ok(()) => next_seq += 1,
+                            // Not on the audit target, or a failing disk would feed itself
+                            Err(err) => tracing::warn!(error = %err, "Failed to write audit journal entry"),
+                        }
+                    }
+                    Entry::Archive { cutoff, through_seq, dir, done } => {
+                        let archived = archive_journal(&journal, cutoff, through_seq, &dir);
+                        // The journal was replaced; append to the new file
+                        if let Ok(reopened) = open(&journal) {
+                            file = reopened;
+                        }
+                        let _ = done.send(archived);
+                    }
+                }
+            }
+        })?;
+        Ok(Arc::new(Self { path: path.to_path_buf(), sender, dropped: AtomicU64::new(0) }))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Entries lost because the writer fell behind
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Move the leading records written before `cutoff`, up to `through_seq`,
+    /// into a compressed segment in `dir`. Blocks until the writer has done it.
+    pub fn archive(&self, cutoff: DateTime<Utc>, through_seq: u64, dir: &Path) -> io::Result<ArchiveOutcome> {
+        let (done, outcome) = channel();
+        let entry = Entry::Archive { cutoff, through_seq, dir: dir.to_path_buf(), done };
+        self.sender.send(entry).map_err(|_| io::Error::other("audit journal writer stopped"))?;
+        outcome.recv().map_err(|_| io::Error::other("audit journal writer stopped"))?
+    }
+
+    fn append(&self, record: AuditRecord) {
+        if let Err(TrySendError::Full(_)) = self.sender.try_send(Entry::Record(record)) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// The journal as the writer thread appends to it
+trait JournalFile: Write {
+    fn len(&self) -> io::Result<u64>;
+    fn truncate(&self, len: u64) -> io::Result<()>;
+}
+
+impl JournalFile for File {
+    fn len(&self) -> io::Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+
+    fn truncate(&self, len: u64) -> io::Result<()> {
+        self.set_len(len)
+    }
+}
+
+/// Appends `line` whole or not at all, so a failed write never glues the
+/// next record onto a partial line, where both would be unreadable. The
+/// partial line is cut off; if even that fails, `torn` is set and the next
+/// record starts on a new line, leaving only the partial one unreadable.
+fn append_line(file: &mut impl JournalFile, line: &[u8], torn: &mut bool) -> io::Result<()> {
+    if *torn {
+        file.write_all(b"\n")?;
+        *torn = false;
+    }
+    let start = file.len()?;
+    file.write_all(line).inspect_err(|_| *torn = file.truncate(start).is_err())
+}
+
+/// Up to `limit` records of the journal at `path` after `after`, and the
+/// watermark after the last of them. A partly written last line is left for
+/// the next read.
+pub fn read_journal(path: &Path, after: Watermark, limit: usize) -> io::Result<(Vec<AuditRecord>, Watermark)> {
+    let mut reader = match File::open(path) {
+        Ok(file) => BufReader::new(file),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok((Vec::new(), after)),
+        Err(err) => return Err(err),
+    };
+    // Since archiving, the offset may point elsewhere; then scan by seq from the start
+    let after = if offset_holds(&mut reader, after)? { after } else { Watermark { seq: after.seq, offset: 0 } };
+    reader.seek(SeekFrom::Start(after.offset))?;
+    let (mut records, mut watermark, mut line) = (Vec::new(), after, String::new());
+    while records.len() < limit {
+        line.clear();
+        let read = reader.read_line(&mut line)?;
+        if read == 0 || !line.ends_with('\n') {
+            break;
+        }
+        watermark.offset += read as u64;
+        match serde_json::from_str::<AuditRecord>(&line) {
+            Ok(record) if record.seq > after.seq => {
+                watermark.seq = record.seq;
+                records.push(record);
+            }
+            Ok(_) => {}
+            Err(err) => tracing::warn!(offset = watermark.offset, error = %err, "Skipping unreadable audit journal line"),
+        }
+    }
+    Ok((records, watermark))
+}
+
+/// Whether the line ending at `watermark.offset` is record `watermark.seq`.
+/// Lines longer than the look-back window count as not found, which only
+/// costs a scan from the start.
+fn offset_holds(reader: &mut BufReader<File>, watermark: Watermark) -> io::Result<bool> {
+    const LOOK_BACK: u64 = 64 * 1024;
+    if watermark.offset == 0 {
+        return Ok(true);
+    }
+    if watermark.offset > reader.get_ref().metadata()?.len() {
+        return Ok(false);
+    }
+    let start = watermark.offset.saturating_sub(LOOK_BACK);
+    reader.seek(SeekFrom::Start(start))?;
+    let mut window = vec![0; (watermark.offset - start) as usize];
+    reader.read_exact(&mut window)?;
+    let Some((b'\n', before)) = window.split_last() else {
+        return Ok(false);
+    };
+    let line = match before.iter().rposition(|&byte| byte == b'\n') {
+        Some(newline) => &before[newline + 1..],
+        None if start == 0 => before,
+        None => return Ok(false),
+    };
+    Ok(serde_json::from_slice::<AuditRecord>(line).is_ok_and(|record| record.seq == watermark.seq))
+}
+
+/// Sequence number of the last complete record in the journal, 0 when empty
+fn last_seq(file: &mut File) -> io::Result<u64> {
+    file.seek(SeekFrom::Start(0))?;
+    let mut last = 0;
+    for line in BufReader::new(&mut *file).lines() {
+        if let Ok(record) = serde_json::from_str::<AuditRecord>(&line?) {
+            last = record.seq;
+        }
+    }
+    Ok(last)
+}
+
+#[derive(Default)]
+struct AuditVisitor {
+    event: String,
+    message: String,
+    fields: Map<String, Value>,
+}
+
+impl AuditVisitor {
+    fn push(&mut self, field: &Field, value: Value) {
+        match (field.name(), value) {
+            ("message", Value::String(message)) => self.message = message,
+            ("audit_event", Value::String(event)) => self.event = event,
+            (name, value) => {
+                self.fields.insert(name.to_string(), value);
+            }
+        }
+    }
+}
+
+impl Visit for AuditVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.push(field, Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.push(field, Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.push(field, Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.push(field, Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.push(field, Value::from(format!("{value:?}")));
+    }
+}
+
+/// Tracing layer appending `audit`-target events to an `AuditJournal`
+pub struct AuditJournalLayer {
+    journal: Arc<AuditJournal>,
+}
+
+impl AuditJournalLayer {
+    pub fn new(journal: Arc<AuditJournal>) -> Self {
+        Self { journal }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for AuditJournalLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if metadata.target() != AUDIT_TARGET {
+            return;
+        }
+        let mut visitor = AuditVisitor::default();
+        event.record(&mut visitor);
+        self.journal.append(AuditRecord {
+            seq: 0,
+            recorded_at: Utc::now(),
+            level: metadata.level().to_string(),
+            event: visitor.event,
+            message: visitor.message,
+            fields: visitor.fields,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn audit_events_are_numbered_across_reopens_and_read_incrementally() {
+        let path = std::env::temp_dir().join(format!("audit-{}.ndjson", uuid::Uuid::new_v4()));
+        let record = |event: &str| {
+            let journal = AuditJournal::open(&path).unwrap();
+            let subscriber = tracing_subscriber::registry().with(AuditJournalLayer::new(journal.clone()));
+            tracing::subscriber::with_default(subscriber, || {
+                tracing::info!(target: "audit", audit_event = event, actor = "alice", keys = 2u64, "Audited");
+                tracing::info!(target: "other", audit_event = "ignored", "Not audited");
+            });
+            // Dropping the journal closes the queue; wait for the writer to drain it
+            drop(journal);
+            for _ in 0..100 {
+                if std::fs::read_to_string(&path).unwrap_or_default().contains(event) {
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+        };
+        record("first");
+        record("second");
+
+        let (records, watermark) = read_journal(&path, Watermark::default(), 1).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!((records[0].seq, records[0].event.as_str()), (1, "first"));
+        assert_eq!((records[0].fields["actor"].as_str(), records[0].fields["keys"].as_u64()), (Some("alice"), Some(2)));
+
+        let (records, rest) = read_journal(&path, watermark, 10).unwrap();
+        assert_eq!(records.iter().map(|record| (record.seq, record.event.as_str())).collect::<Vec<_>>(), [(2, "second")]);
+        assert!(read_journal(&path, rest, 10).unwrap().0.is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn archiving_keeps_numbering_and_stale_offsets_find_their_record() {
+        let dir = std::env::temp_dir().join(format!("audit-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("audit.ndjson");
+        let journal = AuditJournal::open(&path).unwrap();
+        let dispatch = tracing::Dispatch::new(tracing_subscriber::registry().with(AuditJournalLayer::new(journal.clone())));
+        let emit = |count: usize| {
+            tracing::dispatcher::with_default(&dispatch, || {
+                for _ in 0..count {
+                    tracing::info!(target: "audit", audit_event = "login", "Audited");
+                }
+            })
+        };
+        let read_all = || read_journal(&path, Watermark::default(), 100).unwrap();
+        emit(3);
+        while read_all().0.len() < 3 {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let (_, after_two) = read_journal(&path, Watermark::default(), 2).unwrap();
+
+        // Runs behind the queued appends, on the writer thread
+        let outcome = journal.archive(Utc::now(), u64::MAX, &dir.join("archive")).unwrap();
+        assert_eq!(outcome.archived, 2);
+        emit(1);
+        while read_all().0.len() < 2 {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let (records, _) = read_journal(&path, after_two, 10).unwrap();
+        assert_eq!(records.iter().map(|record| record.seq).collect::<Vec<_>>(), [3, 4]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Takes `budget` more bytes, then fails writes; truncation fails while `stuck`
+    struct FlakyFile {
+        bytes: std::cell::RefCell<Vec<u8>>,
+        budget: usize,
+        stuck: bool,
+    }
+
+    impl Write for FlakyFile {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let taken = buf.len().min(self.budget);
+            if taken == 0 {
+                return Err(io::Error::other("disk full"));
+            }
+            self.budget -= taken;
+            self.bytes.borrow_mut().extend_from_slice(&buf[..taken]);
+            Ok(taken)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl JournalFile for FlakyFile {
+        fn len(&self) -> io::Result<u64> {
+            Ok(self.bytes.borrow().len() as u64)
+        }
+
+        fn truncate(&self, len: u64) -> io::Result<()> {
+            if self.stuck {
+                return Err(io::Error::other("read-only"));
+            }
+            self.bytes.borrow_mut().truncate(len as usize);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn failed_writes_never_swallow_the_next_record() {
+        let line = |seq: u64| {
+            let record = AuditRecord {
+                seq,
+                recorded_at: Utc::now(),
+                level: "INFO".to_string(),
+                event: "login".to_string(),
+                message: String::new(),
+                fields: Map::new(),
+            };
+            let mut line = serde_json::to_vec(&record).unwrap();
+            line.push(b'\n');
+            line
+        };
+        let seqs = |bytes: &[u8]| {
+            let path = std::env::temp_dir().join(format!("audit-{}.ndjson", uuid::Uuid::new_v4()));
+            std::fs::write(&path, bytes).unwrap();
+            let (records, _) = read_journal(&path, Watermark::default(), 10).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            records.iter().map(|record| record.seq).collect::<Vec<_>>()
+        };
+
+        for stuck in [false, true] {
+            let mut file = FlakyFile { bytes: Default::default(), budget: line(1).len() + 10, stuck };
+            let mut torn = false;
+            append_line(&mut file, &line(1), &mut torn).unwrap();
+            assert!(append_line(&mut file, &line(2), &mut torn).is_err());
+            assert_eq!(torn, stuck);
+            file.budget = usize::MAX;
+            append_line(&mut file, &line(3), &mut torn).unwrap();
+            assert_eq!(seqs(&file.bytes.borrow()), [1, 3]);
+            assert_eq!(file.bytes.borrow().len(), line(1).len() + line(3).len() + if stuck { 11 } else { 0 });
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use serde::Serialize;
+use std::any::Any;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+static STARTED: AtomicU64 = AtomicU64::new(0);
+static COMPLETED: AtomicU64 = AtomicU64::new(0);
+static PANICKED: AtomicU64 = AtomicU64::new(0);
+static TIMED_OUT: AtomicU64 = AtomicU64::new(0);
+static BUSY_MICROS: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, thiserror::Error)]
+pub enum BlockingError {
+    #[error("Blocking task `{task}` panicked: {message}")]
+    Panicked { task: &'static str, message: String },
+    #[error("Blocking task `{task}` timed out after {after:?}")]
+    TimedOut { task: &'static str, after: Duration },
+    #[error("Blocking task `{task}` was cancelled")]
+    Cancelled { task: &'static str },
+    /// The CPU pool queue for the task's priority class is full
+    #[error("No capacity for blocking task `{task}`")]
+    Saturated { task: &'static str },
+}
+
+/// Process-wide counters over every `run_blocking` call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BlockingStats {
+    pub started: u64,
+    pub completed: u64,
+    pub panicked: u64,
+    pub timed_out: u64,
+    /// Time spent running closures that completed
+    pub busy_ms: u64,
+}
+
+pub fn blocking_stats() -> BlockingStats {
+    BlockingStats {
+        started: STARTED.load(Ordering::Relaxed),
+        completed: COMPLETED.load(Ordering::Relaxed),
+        panicked: PANICKED.load(Ordering::Relaxed),
+        timed_out: TIMED_OUT.load(Ordering::Relaxed),
+        busy_ms: BUSY_MICROS.load(Ordering::Relaxed) / 1000,
+    }
+}
+
+/// Run CPU-bound work on tokio's blocking pool so it never stalls the
+/// runtime's worker threads.
+///
+/// A panic in `work` becomes `BlockingError::Panicked` instead of taking the
+/// response down with it. Past `timeout` the caller gets
+/// `BlockingError::TimedOut`. The closure itself can't be interrupted and runs
+/// to completion in the background. `task` names the work in logs.
+pub async fn run_blocking<F, T>(task: &'static str, timeout: Duration, work: F) -> Result<T, BlockingError>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    STARTED.fetch_add(1, Ordering::Relaxed);
+    let handle = tokio::task::spawn_blocking(move || {
+        let started = Instant::now();
+        let output = work();
+        (output, started.elapsed())
+    });
+
+    match tokio::time::timeout(timeout, handle).await {
+        Ok(Ok((output, busy))) => {
+            COMPLETED.fetch_add(1, Ordering::Relaxed);
+            BUSY_MICROS.fetch_add(busy.as_micros() as u64, Ordering::Relaxed);
+            tracing::debug!(blocking_task = task, duration_ms = busy.as_millis() as u64, "Blocking task completed");
+            Ok(output)
+        }
+        Ok(Err(err)) if err.is_panic() => {
+            PANICKED.fetch_add(1, Ordering::Relaxed);
+            let message = panic_message(err.into_panic());
+            tracing::error!(blocking_task = task, panic = %message, "Blocking task panicked");
+            Err(BlockingError::Panicked { task, message })
+        }
+        Ok(Err(_)) => Err(BlockingError::Cancelled { task }),
+        Err(_) => {
+            TIMED_OUT.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!(blocking_task = task, timeout_ms = timeout.as_millis() as u64, "Blocking task timed out");
+            Err(BlockingError::TimedOut { task, after: timeout })
+        }
+    }
+}
+
+pub(crate) fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "non-string panic payload".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn results_panics_and_timeouts_are_reported() {
+        let before = blocking_stats();
+
+        assert_eq!(run_blocking("sum", Duration::from_secs(5), || 2 + 2).await.unwrap(), 4);
+
+        let err = run_blocking("boom", Duration::from_secs(5), || -> u32 { panic!("bad input") })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, BlockingError::Panicked { task: "boom", ref message } if message == "bad input"));
+
+        let err = run_blocking("slow", Duration::from_millis(10), || std::thread::sleep(Duration::from_millis(200)))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, BlockingError::TimedOut { task: "slow", .. }));
+
+        // Other tests may run blocking work concurrently
+        let after = blocking_stats();
+        assert!(after.started >= before.started + 3);
+        assert!(after.panicked > before.panicked);
+        assert!(after.timed_out > before.timed_out);
+    }
+}
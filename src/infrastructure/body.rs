@@ -0,0 +1,246 @@
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use bytes::BytesMut;
+use http_body_util::{BodyExt, LengthLimitError, Limited};
+use std::time::Duration;
+
+use crate::response::error_response;
+
+/// Upper bound on reading a whole body unless a reader sets its own
+const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, thiserror::Error)]
+pub enum BodyReadError {
+    #[error("Request body exceeds the {limit} byte limit")]
+    TooLarge { limit: usize },
+    #[error("Request body was not received within {}s", .0.as_secs())]
+    Timeout(Duration),
+    #[error("Unsupported charset `{0}`; only UTF-8 is accepted")]
+    UnsupportedCharset(String),
+    #[error("Request body is not valid UTF-8 (invalid byte at offset {offset})")]
+    InvalidEncoding { offset: usize },
+    #[error("Failed to read request body: {0}")]
+    Read(String),
+}
+
+impl BodyReadError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            BodyReadError::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            BodyReadError::Timeout(_) => StatusCode::REQUEST_TIMEOUT,
+            BodyReadError::UnsupportedCharset(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            BodyReadError::InvalidEncoding { .. } | BodyReadError::Read(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            BodyReadError::TooLarge { .. } => "PAYLOAD_TOO_LARGE",
+            BodyReadError::Timeout(_) => "REQUEST_TIMEOUT",
+            BodyReadError::UnsupportedCharset(_) => "UNSUPPORTED_CHARSET",
+            BodyReadError::InvalidEncoding { .. } => "INVALID_ENCODING",
+            BodyReadError::Read(_) => "BAD_REQUEST",
+        }
+    }
+}
+
+impl IntoResponse for BodyReadError {
+    fn into_response(self) -> Response {
+        error_response(self.status(), self.code(), self.to_string()).into_response()
+    }
+}
+
+/// Buffers bodies with a size limit and a deadline instead of ad-hoc
+/// `to_bytes` calls, so oversized, slow or mis-encoded bodies surface as a
+/// `BodyReadError` rather than a panic or an unbounded wait
+#[derive(Debug, Clone, Copy)]
+pub struct BodyReader {
+    max_bytes: usize,
+    timeout: Duration,
+}
+
+impl BodyReader {
+    pub const fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            timeout: DEFAULT_READ_TIMEOUT,
+        }
+    }
+
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Whether the body has a known size within the limit. Callers that must
+    /// not consume a body they can't buffer check this first.
+    pub fn fits(&self, body: &Body) -> bool {
+        body.size_hint()
+            .upper()
+            .is_some_and(|upper| upper <= self.max_bytes as u64)
+    }
+
+    pub async fn read(&self, body: Body) -> Result<Bytes, BodyReadError> {
+        if body.size_hint().lower() > self.max_bytes as u64 {
+            return Err(BodyReadError::TooLarge { limit: self.max_bytes });
+        }
+
+        let collected = tokio::time::timeout(self.timeout, Limited::new(body, self.max_bytes).collect())
+            .await
+            .map_err(|_| BodyReadError::Timeout(self.timeout))?;
+        collected.map(|collected| collected.to_bytes()).map_err(|err| self.read_error(err))
+    }
+
+    /// Like `read`, but copies the body into `buffer` and returns the filled
+    /// part, so a pooled buffer backs bodies that arrive in several frames
+    pub async fn read_into(&self, body: Body, buffer: &mut BytesMut) -> Result<Bytes, BodyReadError> {
+        if body.size_hint().lower() > self.max_bytes as u64 {
+            return Err(BodyReadError::TooLarge { limit: self.max_bytes });
+        }
+
+        let mut body = Limited::new(body, self.max_bytes);
+        let read = async {
+            while let Some(frame) = body.frame().await {
+                if let Ok(data) = frame?.into_data() {
+                    buffer.extend_from_slice(&data);
+                }
+            }
+            Ok(())
+        };
+        tokio::time::timeout(self.timeout, read)
+            .await
+            .map_err(|_| BodyReadError::Timeout(self.timeout))?
+            .map_err(|err| self.read_error(err))?;
+        Ok(buffer.split().freeze())
+    }
+
+    fn read_error(&self, err: axum::BoxError) -> BodyReadError {
+        if err.is::<LengthLimitError>() {
+            BodyReadError::TooLarge { limit: self.max_bytes }
+        } else {
+            BodyReadError::Read(err.to_string())
+        }
+    }
+
+    /// Read the body as text after checking the `Content-Type` charset
+    pub async fn read_text(&self, headers: &HeaderMap, body: Body) -> Result<String, BodyReadError> {
+        let bytes = self.read(body).await?;
+        decode_text(headers, &bytes).map(str::to_string)
+    }
+}
+
+/// Validate already-buffered bytes as text; a missing charset means UTF-8
+pub fn decode_text<'a>(headers: &HeaderMap, bytes: &'a [u8]) -> Result<&'a str, BodyReadError> {
+    if let Some(charset) = charset(headers) {
+        if !["utf-8", "utf8", "us-ascii"].iter().any(|accepted| charset.eq_ignore_ascii_case(accepted)) {
+            return Err(BodyReadError::UnsupportedCharset(charset));
+        }
+    }
+    std::str::from_utf8(bytes).map_err(|err| BodyReadError::InvalidEncoding { offset: err.valid_up_to() })
+}
+
+fn charset(headers: &HeaderMap) -> Option<String> {
+    let content_type = headers.get(header::CONTENT_TYPE)?.to_str().ok()?;
+    content_type.split(';').skip(1).find_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    /// A client that never sends its body
+    struct Stalled;
+
+    impl HttpBody for Stalled {
+        type Data = Bytes;
+        type Error = axum::Error;
+
+        fn poll_frame(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+        ) -> Poll<Option<Result<hyper::body::Frame<Bytes>, Self::Error>>> {
+            Poll::Pending
+        }
+    }
+
+    /// A body sent in several chunks without a known length
+    struct Frames(Vec<&'static str>);
+
+    impl HttpBody for Frames {
+        type Data = Bytes;
+        type Error = axum::Error;
+
+        fn poll_frame(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+        ) -> Poll<Option<Result<hyper::body::Frame<Bytes>, Self::Error>>> {
+            let next = (!self.0.is_empty()).then(|| self.0.remove(0));
+            Poll::Ready(next.map(|chunk| Ok(hyper::body::Frame::data(Bytes::from_static(chunk.as_bytes())))))
+        }
+    }
+
+    fn frames(chunks: &[&'static str]) -> Body {
+        Body::new(Frames(chunks.to_vec()))
+    }
+
+    fn content_type(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[tokio::test]
+    async fn bodies_over_the_limit_are_rejected() {
+        let reader = BodyReader::new(4);
+        assert_eq!(reader.read(Body::from("abcd")).await.unwrap(), "abcd");
+        assert!(!reader.fits(&Body::from("abcde")));
+        let err = reader.read(Body::from("abcde")).await.unwrap_err();
+        assert!(matches!(err, BodyReadError::TooLarge { limit: 4 }));
+        assert_eq!(err.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn read_into_fills_the_given_buffer() {
+        let reader = BodyReader::new(8);
+        let mut buffer = BytesMut::with_capacity(16);
+        assert_eq!(reader.read_into(frames(&["abc", "def"]), &mut buffer).await.unwrap(), "abcdef");
+        assert!(buffer.is_empty());
+
+        let err = reader.read_into(frames(&["abcde", "fghij"]), &mut buffer).await.unwrap_err();
+        assert!(matches!(err, BodyReadError::TooLarge { limit: 8 }));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stalled_bodies_time_out() {
+        let reader = BodyReader::new(1024).with_timeout(Duration::from_secs(5));
+        let err = reader.read(Body::new(Stalled)).await.unwrap_err();
+        assert!(matches!(err, BodyReadError::Timeout(_)));
+        assert_eq!(err.status(), StatusCode::REQUEST_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn text_must_be_utf8() {
+        let reader = BodyReader::new(1024);
+        let json = content_type("application/json; charset=\"UTF-8\"");
+        assert_eq!(reader.read_text(&json, Body::from("{\"a\":1}")).await.unwrap(), "{\"a\":1}");
+        assert_eq!(reader.read_text(&HeaderMap::new(), Body::from("plain")).await.unwrap(), "plain");
+
+        let latin1 = content_type("text/plain; charset=ISO-8859-1");
+        let err = reader.read_text(&latin1, Body::from("caf\u{e9}")).await.unwrap_err();
+        assert!(matches!(err, BodyReadError::UnsupportedCharset(charset) if charset == "ISO-8859-1"));
+
+        let err = reader.read_text(&json, Body::from(vec![b'o', b'k', 0xff])).await.unwrap_err();
+        assert!(matches!(err, BodyReadError::InvalidEncoding { offset: 2 }));
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Duration;
+use tokio::sync::RwLock;
+// tokio's clock so tests can drive expiry with paused time
+use tokio::time::Instant;
+
+/// Bounded in-process cache where every entry expires after its own TTL
+pub struct TtlCache<K, V> {
+    entries: RwLock<HashMap<K, (V, Instant)>>,
+    max_entries: usize,
+}
+
+impl<K, V> TtlCache<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+            max_entries,
+        }
+    }
+
+    /// Return the cached value if it has not expired yet
+    pub async fn get(&self, key: &K) -> Option<V> {
+        let entries = self.entries.read().await;
+        match entries.get(key) {
+            Some((value, expires_at)) if *expires_at > Instant::now() => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    /// The cached value even if it has expired, as long as it hasn't been
+    /// evicted; for serving stale data while the source is down
+    pub async fn get_stale(&self, key: &K) -> Option<V> {
+        self.entries.read().await.get(key).map(|(value, _)| value.clone())
+    }
+
+    /// Insert a value, evicting expired entries first when the cache is full
+    pub async fn insert(&self, key: K, value: V, ttl: Duration) {
+        let now = Instant::now();
+        let mut entries = self.entries.write().await;
+
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            entries.retain(|_, (_, expires_at)| *expires_at > now);
+            if entries.len() >= self.max_entries {
+                // Still full of live entries; skip caching rather than grow unbounded
+                return;
+            }
+        }
+
+        entries.insert(key, (value, now + ttl));
+    }
+
+    pub async fn remove(&self, key: &K) {
+        self.entries.write().await.remove(key);
+    }
+
+    /// Remove every entry whose key matches; returns how many were removed
+    pub async fn remove_matching(&self, mut matches: impl FnMut(&K) -> bool) -> usize {
+        let mut entries = self.entries.write().await;
+        let before = entries.len();
+        entries.retain(|key, _| !matches(key));
+        before - entries.len()
+    }
+
+    /// Drop every entry and release the map's allocation; returns how many were dropped
+    pub async fn clear(&self) -> usize {
+        std::mem::take(&mut *self.entries.write().await).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::advance;
+
+    #[tokio::test(start_paused = true)]
+    async fn entries_expire_exactly_at_their_ttl() {
+        let cache = TtlCache::new(10);
+        cache.insert("short", 1, Duration::from_secs(5)).await;
+        cache.insert("long", 2, Duration::from_secs(60)).await;
+
+        advance(Duration::from_millis(4_999)).await;
+        assert_eq!(cache.get(&"short").await, Some(1));
+
+        advance(Duration::from_millis(1)).await;
+        assert_eq!(cache.get(&"short").await, None);
+        assert_eq!(cache.get(&"long").await, Some(2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn full_cache_only_admits_new_keys_once_entries_expire() {
+        let cache = TtlCache::new(1);
+        cache.insert("a", 1, Duration::from_secs(10)).await;
+
+        cache.insert("b", 2, Duration::from_secs(10)).await;
+        assert_eq!(cache.get(&"b").await, None);
+
+        advance(Duration::from_secs(10)).await;
+        cache.insert("b", 2, Duration::from_secs(10)).await;
+        assert_eq!(cache.get(&"b").await, Some(2));
+        assert_eq!(cache.get(&"a").await, None);
+    }
+}
@@ -0,0 +1,107 @@
+use async_trait::async_trait;
+use serde_json::json;
+
+/// Surrogate keys shared by response headers and purge calls
+pub mod surrogate_keys {
+    use uuid::Uuid;
+
+    pub const USERS_LIST: &str = "users:list";
+
+    pub fn user(id: Uuid) -> String {
+        format!("user:{}", id)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CdnPurgeError {
+    #[error("Purge request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("Purge rejected with status {0}")]
+    Rejected(reqwest::StatusCode),
+}
+
+/// Invalidates edge-cached responses by surrogate key / cache tag
+#[async_trait]
+pub trait CdnPurgeClient: Send + Sync {
+    async fn purge(&self, keys: &[String]) -> Result<(), CdnPurgeError>;
+}
+
+/// Used when no CDN is configured
+pub struct NoopPurgeClient;
+
+#[async_trait]
+impl CdnPurgeClient for NoopPurgeClient {
+    async fn purge(&self, _keys: &[String]) -> Result<(), CdnPurgeError> {
+        Ok(())
+    }
+}
+
+/// Fastly purge by surrogate key
+pub struct FastlyPurgeClient {
+    http: reqwest::Client,
+    service_id: String,
+    api_token: String,
+}
+
+impl FastlyPurgeClient {
+    pub fn new(service_id: String, api_token: String) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            service_id,
+            api_token,
+        }
+    }
+}
+
+#[async_trait]
+impl CdnPurgeClient for FastlyPurgeClient {
+    async fn purge(&self, keys: &[String]) -> Result<(), CdnPurgeError> {
+        let response = self
+            .http
+            .post(format!("https://api.fastly.com/service/{}/purge", self.service_id))
+            .header("Fastly-Key", &self.api_token)
+            .header("Surrogate-Key", keys.join(" "))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(CdnPurgeError::Rejected(response.status()));
+        }
+        Ok(())
+    }
+}
+
+/// Cloudflare purge by cache tag
+pub struct CloudflarePurgeClient {
+    http: reqwest::Client,
+    zone_id: String,
+    api_token: String,
+}
+
+impl CloudflarePurgeClient {
+    pub fn new(zone_id: String, api_token: String) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            zone_id,
+            api_token,
+        }
+    }
+}
+
+#[async_trait]
+impl CdnPurgeClient for CloudflarePurgeClient {
+    async fn purge(&self, keys: &[String]) -> Result<(), CdnPurgeError> {
+        let response = self
+            .http
+            .post(format!("https://api.cloudflare.com/client/v4/zones/{}/purge_cache", self.zone_id))
+            .bearer_auth(&self.api_token)
+            .json(&json!({ "tags": keys }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(CdnPurgeError::Rejected(response.status()));
+        }
+        Ok(())
+    }
+}
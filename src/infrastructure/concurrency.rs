@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Caps the requests each client key (a user, an API key) has in flight at
+/// once, so one client can't occupy every worker and connection.
+pub struct ConcurrencyLimiter {
+    max_per_key: usize,
+    in_flight: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+/// A claimed slot; dropping it frees the slot
+pub struct ConcurrencyPermit {
+    key: String,
+    in_flight: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(max_per_key: usize) -> Self {
+        Self { max_per_key, in_flight: Arc::default() }
+    }
+
+    /// `None` when `key` already has `max_per_key` requests in flight
+    pub fn try_acquire(&self, key: &str) -> Option<ConcurrencyPermit> {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        let count = in_flight.entry(key.to_string()).or_insert(0);
+        if *count >= self.max_per_key {
+            return None;
+        }
+        *count += 1;
+        Some(ConcurrencyPermit { key: key.to_string(), in_flight: self.in_flight.clone() })
+    }
+
+    pub fn in_flight(&self, key: &str) -> usize {
+        self.in_flight.lock().unwrap().get(key).copied().unwrap_or(0)
+    }
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(count) = in_flight.get_mut(&self.key) {
+            *count -= 1;
+            // Idle keys are forgotten, so the map only holds active clients
+            if *count == 0 {
+                in_flight.remove(&self.key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slots_are_per_key_and_freed_on_drop() {
+        let limiter = ConcurrencyLimiter::new(2);
+        let first = limiter.try_acquire("alice").unwrap();
+        let _second = limiter.try_acquire("alice").unwrap();
+        assert!(limiter.try_acquire("alice").is_none());
+        assert!(limiter.try_acquire("bob").is_some());
+
+        drop(first);
+        assert_eq!(limiter.in_flight("alice"), 1);
+        assert!(limiter.try_acquire("alice").is_some());
+        assert_eq!(limiter.in_flight("bob"), 0);
+        assert!(limiter.in_flight.lock().unwrap().get("bob").is_none());
+    }
+}
@@ -0,0 +1,226 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use super::{bucket_percentile, latency_bucket, LatencyBucket, BUCKETS_MS};
+
+/// Counters of one message consumer, updated by the consumer as it works.
+/// Lag is the number of messages waiting: in-process queues count sends
+/// and receives, bus adapters set it from the broker's offsets.
+pub struct ConsumerStats {
+    transport: &'static str,
+    connected: AtomicBool,
+    lag: AtomicU64,
+    in_flight: AtomicU64,
+    processed: AtomicU64,
+    failed: AtomicU64,
+    retries: AtomicU64,
+    dead_lettered: AtomicU64,
+    dead_letter_depth: AtomicU64,
+    total_micros: AtomicU64,
+    buckets: [AtomicU64; BUCKETS_MS.len() + 1],
+    last_processed_at: Mutex<Option<DateTime<Utc>>>,
+}
+
+impl ConsumerStats {
+    /// Stats not registered with `ConsumerMetrics`, for consumers built without one
+    pub fn new(transport: &'static str) -> Self {
+        Self {
+            transport,
+            connected: AtomicBool::new(false),
+            lag: AtomicU64::new(0),
+            in_flight: AtomicU64::new(0),
+            processed: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+            retries: AtomicU64::new(0),
+            dead_lettered: AtomicU64::new(0),
+            dead_letter_depth: AtomicU64::new(0),
+            total_micros: AtomicU64::new(0),
+            buckets: Default::default(),
+            last_processed_at: Mutex::new(None),
+        }
+    }
+
+    /// The consumer is attached to its source and receiving; readiness fails while it is not
+    pub fn set_connected(&self, connected: bool) {
+        if self.connected.swap(connected, Ordering::Relaxed) != connected {
+            tracing::info!(transport = self.transport, connected, "Message consumer connectivity changed");
+        }
+    }
+
+    /// A message was put on an in-process queue
+    pub fn enqueued(&self) {
+        self.lag.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The consumer took a message off the queue and starts handling it
+    pub fn started(&self) {
+        // Never below zero, even for a receive recorded without its send
+        let _ = self.lag.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |lag| lag.checked_sub(1));
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Handling finished after `elapsed`; `ok` is false when the handler failed
+    pub fn finished(&self, elapsed: Duration, ok: bool) {
+        let _ = self.in_flight.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |in_flight| in_flight.checked_sub(1));
+        self.processed.fetch_add(1, Ordering::Relaxed);
+        if !ok {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        }
+        self.total_micros.fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+        self.buckets[latency_bucket(elapsed)].fetch_add(1, Ordering::Relaxed);
+        *self.last_processed_at.lock().unwrap() = Some(Utc::now());
+    }
+
+    /// Messages waiting at the source, for consumers whose broker reports lag
+    pub fn set_lag(&self, lag: u64) {
+        self.lag.store(lag, Ordering::Relaxed);
+    }
+
+    pub fn retried(&self) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A message was given up on and moved to the dead-letter queue
+    pub fn dead_lettered(&self) {
+        self.dead_lettered.fetch_add(1, Ordering::Relaxed);
+        self.dead_letter_depth.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Messages currently in the dead-letter queue, when the broker reports it
+    pub fn set_dead_letter_depth(&self, depth: u64) {
+        self.dead_letter_depth.store(depth, Ordering::Relaxed);
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    fn report(&self, name: &str) -> ConsumerReport {
+        let processed = self.processed.load(Ordering::Relaxed);
+        let counts: Vec<u64> = self.buckets.iter().map(|bucket| bucket.load(Ordering::Relaxed)).collect();
+        ConsumerReport {
+            name: name.to_string(),
+            transport: self.transport,
+            connected: self.is_connected(),
+            lag: self.lag.load(Ordering::Relaxed),
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+            processed,
+            failed: self.failed.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            dead_lettered: self.dead_lettered.load(Ordering::Relaxed),
+            dead_letter_depth: self.dead_letter_depth.load(Ordering::Relaxed),
+            mean_ms: if processed == 0 {
+                0.0
+            } else {
+                self.total_micros.load(Ordering::Relaxed) as f64 / processed as f64 / 1000.0
+            },
+            p50_ms: bucket_percentile(&counts, processed, 0.50),
+            p95_ms: bucket_percentile(&counts, processed, 0.95),
+            p99_ms: bucket_percentile(&counts, processed, 0.99),
+            buckets: counts
+                .iter()
+                .enumerate()
+                .map(|(bucket, count)| LatencyBucket { le_ms: BUCKETS_MS.get(bucket).copied(), count: *count })
+                .collect(),
+            last_processed_at: *self.last_processed_at.lock().unwrap(),
+        }
+    }
+}
+
+/// Lag, processing latency, retries and dead letters of one consumer
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsumerReport {
+    pub name: String,
+    /// `in_process`, or the bus the consumer reads from, e.g. `kafka`
+    pub transport: &'static str,
+    pub connected: bool,
+    /// Messages waiting to be handled
+    pub lag: u64,
+    pub in_flight: u64,
+    pub processed: u64,
+    pub failed: u64,
+    pub retries: u64,
+    pub dead_lettered: u64,
+    pub dead_letter_depth: u64,
+    /// Handling time, without the wait in the queue
+    pub mean_ms: f64,
+    /// Upper bounds of the buckets holding each percentile; `None` past the last bucket
+    pub p50_ms: Option<u64>,
+    pub p95_ms: Option<u64>,
+    pub p99_ms: Option<u64>,
+    pub buckets: Vec<LatencyBucket>,
+    pub last_processed_at: Option<DateTime<Utc>>,
+}
+
+/// Every message consumer of the process, by name. Consumers register once
+/// and update their `ConsumerStats`; the report backs
+/// `/api/admin/consumers` and the `message_consumers` readiness check.
+#[derive(Default)]
+pub struct ConsumerMetrics {
+    consumers: RwLock<BTreeMap<String, Arc<ConsumerStats>>>,
+}
+
+impl ConsumerMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The stats of consumer `name`, created on first use
+    pub fn consumer(&self, name: &str, transport: &'static str) -> Arc<ConsumerStats> {
+        self.consumers
+            .write()
+            .unwrap()
+            .entry(name.to_string())
+            .or_insert_with(|| Arc::new(ConsumerStats::new(transport)))
+            .clone()
+    }
+
+    pub fn report(&self) -> Vec<ConsumerReport> {
+        self.consumers.read().unwrap().iter().map(|(name, stats)| stats.report(name)).collect()
+    }
+
+    /// Names of the consumers not currently connected
+    pub fn disconnected(&self) -> Vec<String> {
+        self.consumers
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, stats)| !stats.is_connected())
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lag_follows_the_queue_and_handling_is_timed() {
+        let metrics = ConsumerMetrics::new();
+        let stats = metrics.consumer("webhooks", "in_process");
+        assert_eq!(metrics.disconnected(), vec!["webhooks".to_string()]);
+        stats.set_connected(true);
+
+        for _ in 0..3 {
+            stats.enqueued();
+        }
+        stats.started();
+        stats.finished(Duration::from_millis(3), true);
+        stats.started();
+        stats.finished(Duration::from_millis(40), false);
+        stats.retried();
+        stats.dead_lettered();
+
+        let report = &metrics.consumer("webhooks", "in_process").report("webhooks");
+        assert!(metrics.disconnected().is_empty());
+        assert_eq!((report.lag, report.in_flight, report.processed, report.failed), (1, 0, 2, 1));
+        assert_eq!((report.retries, report.dead_lettered, report.dead_letter_depth), (1, 1, 1));
+        assert_eq!((report.p50_ms, report.p99_ms), (Some(5), Some(50)));
+        assert!(report.last_processed_at.is_some());
+    }
+}
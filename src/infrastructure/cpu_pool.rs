@@ -0,0 +1,261 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+use super::blocking::{panic_message, BlockingError};
+
+/// Updates the counters before handing the result back, so a caller never
+/// sees stats older than its own task
+type Job = Box<dyn FnOnce(&Counters) + Send>;
+
+/// Scheduling class of CPU work
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    /// A request is waiting on it; always taken before batch work
+    Interactive,
+    /// Background work (imports, compression); runs when no interactive work is queued
+    Batch,
+}
+
+#[derive(Default)]
+struct Queues {
+    interactive: VecDeque<Job>,
+    batch: VecDeque<Job>,
+    shutdown: bool,
+}
+
+#[derive(Default)]
+struct Counters {
+    active: AtomicUsize,
+    completed: AtomicU64,
+    panicked: AtomicU64,
+    rejected_interactive: AtomicU64,
+    rejected_batch: AtomicU64,
+}
+
+struct Shared {
+    queues: Mutex<Queues>,
+    available: Condvar,
+    counters: Counters,
+}
+
+/// Queue depths and counters of a `CpuPool`
+#[derive(Debug, Clone, Serialize)]
+pub struct CpuPoolStats {
+    pub threads: usize,
+    pub active: usize,
+    pub interactive_queued: usize,
+    pub interactive_capacity: usize,
+    pub batch_queued: usize,
+    pub batch_capacity: usize,
+    pub completed: u64,
+    pub panicked: u64,
+    pub rejected_interactive: u64,
+    pub rejected_batch: u64,
+}
+
+/// Dedicated threads for CPU-heavy work such as hashing, compression and
+/// parsing, kept apart from tokio's runtime and blocking pool.
+///
+/// Each priority class has a bounded queue. Workers always drain interactive
+/// work first. A full queue rejects new work with `BlockingError::Saturated`,
+/// which handlers answer with 503, instead of letting latency grow without
+/// bound.
+pub struct CpuPool {
+    shared: Arc<Shared>,
+    threads: usize,
+    interactive_capacity: usize,
+    batch_capacity: usize,
+}
+
+impl CpuPool {
+    /// One pool, a thread per core, for every test in the binary instead of
+    /// a pool spawned per test
+    #[cfg(test)]
+    pub fn shared_for_tests() -> Arc<Self> {
+        static POOL: std::sync::OnceLock<Arc<CpuPool>> = std::sync::OnceLock::new();
+        Arc::clone(POOL.get_or_init(|| Arc::new(CpuPool::new(0, 256, 256))))
+    }
+
+    /// `threads == 0` uses one thread per available core
+    pub fn new(threads: usize, interactive_capacity: usize, batch_capacity: usize) -> Self {
+        let threads = match threads {
+            0 => std::thread::available_parallelism().map(usize::from).unwrap_or(1),
+            threads => threads,
+        };
+        let shared = Arc::new(Shared {
+            queues: Mutex::new(Queues::default()),
+            available: Condvar::new(),
+            counters: Counters::default(),
+        });
+        for index in 0..threads {
+            let shared = Arc::clone(&shared);
+            std::thread::Builder::new()
+                .name(format!("cpu-pool-{index}"))
+                .spawn(move || worker(&shared))
+                .expect("failed to spawn CPU pool thread");
+        }
+        Self { shared, threads, interactive_capacity, batch_capacity }
+    }
+
+    /// Queue `work` in its priority class and wait up to `timeout` for the
+    /// result. Past the timeout the work still runs; only the caller gives up.
+    pub async fn run<F, T>(&self, priority: Priority, task: &'static str, timeout: Duration, work: F) -> Result<T, BlockingError>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let job: Job = Box::new(move |counters| {
+            let started = Instant::now();
+            let output = catch_unwind(AssertUnwindSafe(work));
+            if output.is_err() {
+                counters.panicked.fetch_add(1, Ordering::Relaxed);
+            }
+            counters.completed.fetch_add(1, Ordering::Relaxed);
+            let _ = sender.send((output, started.elapsed()));
+        });
+
+        {
+            let mut queues = self.shared.queues.lock().unwrap();
+            let (queue, capacity, rejected) = match priority {
+                Priority::Interactive => (
+                    &mut queues.interactive,
+                    self.interactive_capacity,
+                    &self.shared.counters.rejected_interactive,
+                ),
+                Priority::Batch => (&mut queues.batch, self.batch_capacity, &self.shared.counters.rejected_batch),
+            };
+            if queue.len() >= capacity {
+                rejected.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!(cpu_task = task, priority = ?priority, queued = queue.len(), "CPU pool saturated");
+                return Err(BlockingError::Saturated { task });
+            }
+            queue.push_back(job);
+        }
+        self.shared.available.notify_one();
+
+        match tokio::time::timeout(timeout, receiver).await {
+            Ok(Ok((Ok(output), busy))) => {
+                tracing::debug!(cpu_task = task, duration_ms = busy.as_millis() as u64, "CPU task completed");
+                Ok(output)
+            }
+            Ok(Ok((Err(payload), _))) => {
+                let message = panic_message(payload);
+                tracing::error!(cpu_task = task, panic = %message, "CPU task panicked");
+                Err(BlockingError::Panicked { task, message })
+            }
+            Ok(Err(_)) => Err(BlockingError::Cancelled { task }),
+            Err(_) => {
+                tracing::warn!(cpu_task = task, timeout_ms = timeout.as_millis() as u64, "CPU task timed out");
+                Err(BlockingError::TimedOut { task, after: timeout })
+            }
+        }
+    }
+
+    pub fn stats(&self) -> CpuPoolStats {
+        let (interactive_queued, batch_queued) = {
+            let queues = self.shared.queues.lock().unwrap();
+            (queues.interactive.len(), queues.batch.len())
+        };
+        let counters = &self.shared.counters;
+        CpuPoolStats {
+            threads: self.threads,
+            active: counters.active.load(Ordering::Relaxed),
+            interactive_queued,
+            interactive_capacity: self.interactive_capacity,
+            batch_queued,
+            batch_capacity: self.batch_capacity,
+            completed: counters.completed.load(Ordering::Relaxed),
+            panicked: counters.panicked.load(Ordering::Relaxed),
+            rejected_interactive: counters.rejected_interactive.load(Ordering::Relaxed),
+            rejected_batch: counters.rejected_batch.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Drop for CpuPool {
+    /// Workers finish the queued work, then exit
+    fn drop(&mut self) {
+        self.shared.queues.lock().unwrap().shutdown = true;
+        self.shared.available.notify_all();
+    }
+}
+
+fn worker(shared: &Shared) {
+    loop {
+        let job = {
+            let mut queues = shared.queues.lock().unwrap();
+            loop {
+                if let Some(job) = queues.interactive.pop_front().or_else(|| queues.batch.pop_front()) {
+                    break job;
+                }
+                if queues.shutdown {
+                    return;
+                }
+                queues = shared.available.wait(queues).unwrap();
+            }
+        };
+
+        shared.counters.active.fetch_add(1, Ordering::Relaxed);
+        job(&shared.counters);
+        shared.counters.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[tokio::test]
+    async fn interactive_work_jumps_the_batch_queue_and_full_queues_reject() {
+        let pool = Arc::new(CpuPool::new(1, 1, 2));
+        let (release, gate) = mpsc::channel::<()>();
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        // Hold the only worker until everything else is queued
+        let blocker = {
+            let pool = Arc::clone(&pool);
+            tokio::spawn(async move {
+                pool.run(Priority::Batch, "blocker", Duration::from_secs(5), move || gate.recv().unwrap()).await
+            })
+        };
+        while pool.stats().active == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        let mut queued = Vec::new();
+        for (priority, name) in [(Priority::Batch, "batch-1"), (Priority::Batch, "batch-2"), (Priority::Interactive, "interactive")] {
+            let worker_pool = Arc::clone(&pool);
+            let order = Arc::clone(&order);
+            queued.push(tokio::spawn(async move {
+                worker_pool.run(priority, name, Duration::from_secs(5), move || order.lock().unwrap().push(name)).await
+            }));
+            while pool.stats().interactive_queued + pool.stats().batch_queued < queued.len() {
+                tokio::task::yield_now().await;
+            }
+        }
+
+        let rejected = pool.run(Priority::Batch, "batch-3", Duration::from_secs(5), || ()).await;
+        assert!(matches!(rejected, Err(BlockingError::Saturated { task: "batch-3" })));
+        let stats = pool.stats();
+        assert_eq!((stats.interactive_queued, stats.batch_queued, stats.rejected_batch), (1, 2, 1));
+
+        release.send(()).unwrap();
+        blocker.await.unwrap().unwrap();
+        for task in queued {
+            task.await.unwrap().unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), ["interactive", "batch-1", "batch-2"]);
+
+        let panicked = pool.run(Priority::Interactive, "boom", Duration::from_secs(5), || panic!("bad row")).await;
+        assert!(matches!(panicked, Err(BlockingError::Panicked { ref message, .. }) if message == "bad row"));
+        assert_eq!(pool.stats().panicked, 1);
+    }
+}
@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Identity of this running instance for blue/green and canary orchestration
+pub struct DeploymentInfo {
+    pub id: String,
+    pub color: String,
+    pub region: String,
+    pub started_at: DateTime<Utc>,
+    draining: AtomicBool,
+}
+
+impl DeploymentInfo {
+    pub fn new(id: String, color: String, region: String) -> Self {
+        Self {
+            id,
+            color,
+            region,
+            started_at: Utc::now(),
+            draining: AtomicBool::new(false),
+        }
+    }
+
+    /// A draining instance reports not-ready so traffic shifts away from it
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Relaxed)
+    }
+
+    pub fn set_draining(&self, draining: bool) {
+        self.draining.store(draining, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,92 @@
+use serde::Serialize;
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+
+/// Outcome of one branch of a fan-out read.
+///
+/// Aggregate endpoints start every branch with `branch`, await them together
+/// with `tokio::join!`, then `take` each result. A branch that times out or
+/// fails is listed as missing instead of failing the whole response:
+///
+/// ```ignore
+/// let (profile, sessions) = tokio::join!(
+///     branch(timeout, users.get_user_by_id(id)),
+///     branch(timeout, sessions.list_active(id)),
+/// );
+/// let mut missing = Vec::new();
+/// let sessions = sessions.take("sessions", &mut missing);
+/// ```
+#[derive(Debug)]
+pub enum Branch<T> {
+    Ready(T),
+    TimedOut,
+    Failed(String),
+}
+
+/// A part left out of a partial response, and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MissingPart {
+    pub source: &'static str,
+    /// `timeout` or `error`
+    pub reason: &'static str,
+}
+
+/// Run `call`, giving up after `timeout`. Dropping the future on timeout
+/// cancels the call.
+pub async fn branch<T, E: Display>(timeout: Duration, call: impl Future<Output = Result<T, E>>) -> Branch<T> {
+    match tokio::time::timeout(timeout, call).await {
+        Ok(Ok(value)) => Branch::Ready(value),
+        Ok(Err(err)) => Branch::Failed(err.to_string()),
+        Err(_) => Branch::TimedOut,
+    }
+}
+
+impl<T> Branch<T> {
+    /// The value, or `None` with the branch added to `missing` and logged
+    pub fn take(self, source: &'static str, missing: &mut Vec<MissingPart>) -> Option<T> {
+        let reason = match self {
+            Branch::Ready(value) => return Some(value),
+            Branch::TimedOut => {
+                tracing::warn!(source, "Fan-out branch timed out");
+                "timeout"
+            }
+            Branch::Failed(error) => {
+                tracing::warn!(source, error = %error, "Fan-out branch failed");
+                "error"
+            }
+        };
+        missing.push(MissingPart { source, reason });
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn slow_and_failing_branches_are_listed_as_missing() {
+        let timeout = Duration::from_millis(20);
+        let (fast, slow, failing) = tokio::join!(
+            branch(timeout, async { Ok::<_, String>(1) }),
+            branch(timeout, async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok::<_, String>(2)
+            }),
+            branch(timeout, async { Err::<u32, _>("store down".to_string()) }),
+        );
+
+        let mut missing = Vec::new();
+        assert_eq!(fast.take("fast", &mut missing), Some(1));
+        assert_eq!(slow.take("slow", &mut missing), None);
+        assert_eq!(failing.take("failing", &mut missing), None);
+        assert_eq!(
+            missing,
+            vec![
+                MissingPart { source: "slow", reason: "timeout" },
+                MissingPart { source: "failing", reason: "error" },
+            ]
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use async_trait::async_trait;
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::container::startup::StartupComponent;
+
+#[derive(Debug, thiserror::Error)]
+pub enum GeoIpError {
+    #[error("failed to open GeoIP database {path}: {source}")]
+    Open {
+        path: String,
+        source: MaxMindDBError,
+    },
+}
+
+/// Where a client address resolves to; fields are `None` when the databases
+/// have no answer (private ranges, unlisted networks)
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GeoLocation {
+    pub country: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub asn: Option<u32>,
+    pub as_org: Option<String>,
+}
+
+struct Databases {
+    city: Option<Reader<Vec<u8>>>,
+    asn: Option<Reader<Vec<u8>>>,
+}
+
+/// MaxMind GeoLite2/GeoIP2 lookups. Databases are opened by the startup graph;
+/// until then, or when no path is configured, every lookup returns `None`.
+pub struct GeoIp {
+    city_db_path: String,
+    asn_db_path: String,
+    databases: OnceLock<Databases>,
+}
+
+impl GeoIp {
+    /// Empty paths disable the corresponding lookup
+    pub fn new(city_db_path: String, asn_db_path: String) -> Self {
+        Self {
+            city_db_path,
+            asn_db_path,
+            databases: OnceLock::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.city_db_path.is_empty() || !self.asn_db_path.is_empty()
+    }
+
+    fn open(path: &str) -> Result<Option<Reader<Vec<u8>>>, GeoIpError> {
+        if path.is_empty() {
+            return Ok(None);
+        }
+        Reader::open_readfile(path)
+            .map(Some)
+            .map_err(|source| GeoIpError::Open { path: path.to_string(), source })
+    }
+
+    pub fn load(&self) -> Result<(), GeoIpError> {
+        let databases = Databases {
+            city: Self::open(&self.city_db_path)?,
+            asn: Self::open(&self.asn_db_path)?,
+        };
+        let _ = self.databases.set(databases);
+        Ok(())
+    }
+
+    pub fn lookup(&self, ip: IpAddr) -> Option<GeoLocation> {
+        let databases = self.databases.get()?;
+        let mut location = GeoLocation::default();
+
+        if let Some(city) = databases.city.as_ref().and_then(|reader| reader.lookup::<geoip2::City>(ip).ok()) {
+            location.country = city.country.and_then(|country| country.iso_code).map(str::to_string);
+            if let Some(coordinates) = city.location {
+                location.latitude = coordinates.latitude;
+                location.longitude = coordinates.longitude;
+            }
+        }
+        if let Some(asn) = databases.asn.as_ref().and_then(|reader| reader.lookup::<geoip2::Asn>(ip).ok()) {
+            location.asn = asn.autonomous_system_number;
+            location.as_org = asn.autonomous_system_organization.map(str::to_string);
+        }
+
+        (location != GeoLocation::default()).then_some(location)
+    }
+}
+
+#[async_trait]
+impl StartupComponent for GeoIp {
+    fn name(&self) -> &'static str {
+        "geoip"
+    }
+
+    async fn start(&self) -> Result<(), String> {
+        self.load().map_err(|err| err.to_string())
+    }
+}
+
+/// Logins closer together than this are never flagged; GeoIP coordinates
+/// are only accurate to a city or region
+const MIN_TRAVEL_KM: f64 = 100.0;
+/// Logins older than this are forgotten
+const TRAVEL_MEMORY: Duration = Duration::from_secs(24 * 60 * 60);
+const MAX_TRACKED_USERS: usize = 100_000;
+
+/// Two consecutive logins for the same user that would require travelling
+/// faster than the configured speed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImpossibleTravel {
+    pub user: String,
+    pub from_country: Option<String>,
+    pub to_country: Option<String>,
+    pub distance_km: f64,
+    pub elapsed_secs: u64,
+    pub speed_kmh: f64,
+}
+
+/// Remembers the last login location per user and raises a security event
+/// when the next one is physically implausible
+pub struct ImpossibleTravelDetector {
+    max_speed_kmh: f64,
+    last_login: Mutex<HashMap<String, (GeoLocation, Instant)>>,
+}
+
+impl ImpossibleTravelDetector {
+    pub fn new(max_speed_kmh: f64) -> Self {
+        Self {
+            max_speed_kmh,
+            last_login: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Call after a successful login; locations without coordinates are ignored
+    pub fn record_login(&self, user: &str, location: &GeoLocation, at: Instant) -> Option<ImpossibleTravel> {
+        let (Some(latitude), Some(longitude)) = (location.latitude, location.longitude) else {
+            return None;
+        };
+
+        let mut last_login = self.last_login.lock().unwrap();
+        if last_login.len() >= MAX_TRACKED_USERS {
+            last_login.retain(|_, (_, seen)| at.saturating_duration_since(*seen) < TRAVEL_MEMORY);
+        }
+        let previous = last_login.insert(user.to_string(), (location.clone(), at))?;
+
+        let (previous, seen) = previous;
+        let elapsed = at.saturating_duration_since(seen);
+        let (Some(previous_latitude), Some(previous_longitude)) = (previous.latitude, previous.longitude) else {
+            return None;
+        };
+        let distance_km = haversine_km(previous_latitude, previous_longitude, latitude, longitude);
+        if elapsed >= TRAVEL_MEMORY || distance_km < MIN_TRAVEL_KM {
+            return None;
+        }
+
+        // A zero interval is treated as one second so the speed stays finite
+        let speed_kmh = distance_km / (elapsed.as_secs_f64().max(1.0) / 3600.0);
+        if speed_kmh <= self.max_speed_kmh {
+            return None;
+        }
+
+        let travel = ImpossibleTravel {
+            user: user.to_string(),
+            from_country: previous.country,
+            to_country: location.country.clone(),
+            distance_km,
+            elapsed_secs: elapsed.as_secs(),
+            speed_kmh,
+        };
+        tracing::warn!(
+            target: "security",
+            security_event = "impossible_travel",
+            user = %travel.user,
+            from_country = travel.from_country.as_deref(),
+            to_country = travel.to_country.as_deref(),
+            distance_km = travel.distance_km.round(),
+            elapsed_secs = travel.elapsed_secs,
+            speed_kmh = travel.speed_kmh.round(),
+            "Impossible travel between logins"
+        );
+        Some(travel)
+    }
+}
+
+/// Great-circle distance between two coordinates in kilometres
+fn haversine_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    const EARTH_RADIUS_KM: f64 = 6371.0;
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(country: &str, latitude: f64, longitude: f64) -> GeoLocation {
+        GeoLocation {
+            country: Some(country.to_string()),
+            latitude: Some(latitude),
+            longitude: Some(longitude),
+            ..GeoLocation::default()
+        }
+    }
+
+    #[test]
+    fn logins_faster_than_an_airliner_are_flagged() {
+        let detector = ImpossibleTravelDetector::new(900.0);
+        let start = Instant::now();
+        let jakarta = at("ID", -6.2, 106.8);
+        let london = at("GB", 51.5, -0.1);
+
+        assert_eq!(detector.record_login("alice", &jakarta, start), None);
+        let travel = detector.record_login("alice", &london, start + Duration::from_secs(3600)).unwrap();
+        assert_eq!(travel.from_country.as_deref(), Some("ID"));
+        assert_eq!(travel.to_country.as_deref(), Some("GB"));
+        assert!((11_000.0..12_500.0).contains(&travel.distance_km));
+
+        // Sixteen hours later the same trip is plausible
+        let back = detector.record_login("alice", &jakarta, start + Duration::from_secs(17 * 3600));
+        assert_eq!(back, None);
+    }
+
+    #[test]
+    fn nearby_and_unlocated_logins_are_ignored() {
+        let detector = ImpossibleTravelDetector::new(900.0);
+        let start = Instant::now();
+
+        detector.record_login("bob", &at("ID", -6.2, 106.8), start);
+        // Bogor is ~45km away, under the accuracy floor even seconds later
+        assert!(detector.record_login("bob", &at("ID", -6.6, 106.8), start + Duration::from_secs(5)).is_none());
+        assert!(detector.record_login("bob", &GeoLocation::default(), start).is_none());
+    }
+
+    #[test]
+    fn lookups_are_empty_until_databases_load() {
+        let geoip = GeoIp::new(String::new(), String::new());
+        assert!(!geoip.is_enabled());
+        assert_eq!(geoip.lookup("8.8.8.8".parse().unwrap()), None);
+        geoip.load().unwrap();
+        assert_eq!(geoip.lookup("8.8.8.8".parse().unwrap()), None);
+    }
+}
@@ -0,0 +1,166 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
+
+use super::CdnPurgeClient;
+
+/// Which cache entries to drop. An entry matches when its key is listed, its
+/// key starts with one of the prefixes, or it carries one of the tags.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheSelector {
+    pub keys: Vec<String>,
+    pub prefixes: Vec<String>,
+    /// Surrogate keys, e.g. `user:<id>`; also purged at the CDN
+    pub tags: Vec<String>,
+}
+
+impl CacheSelector {
+    pub fn matches(&self, key: &str, tags: &[&str]) -> bool {
+        self.keys.iter().any(|wanted| wanted == key)
+            || self.prefixes.iter().any(|prefix| key.starts_with(prefix.as_str()))
+            || tags.iter().any(|tag| self.tags.iter().any(|wanted| wanted == tag))
+    }
+}
+
+/// An in-process cache an admin can invalidate by key, prefix or tag
+#[async_trait]
+pub trait InvalidatableCache: Send + Sync {
+    fn name(&self) -> &'static str;
+    /// Drop the matching entries; returns how many were dropped
+    async fn invalidate(&self, selector: &CacheSelector) -> usize;
+}
+
+/// Entries dropped from one cache
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheInvalidated {
+    pub cache: &'static str,
+    pub removed: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CdnPurgeStatus {
+    /// `CDN_PURGE_PROVIDER` is not set
+    NotConfigured,
+    /// Only tags are purged at the CDN, and none were given
+    NoTags,
+    Purged,
+    Failed,
+}
+
+/// What an invalidation dropped, locally and at the CDN
+#[derive(Debug, Clone, Serialize)]
+pub struct InvalidationReport {
+    pub caches: Vec<CacheInvalidated>,
+    pub cdn: CdnPurgeStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cdn_error: Option<String>,
+}
+
+/// Every invalidatable cache of the process, plus the CDN purge client when
+/// a CDN is configured
+#[derive(Default)]
+pub struct CacheInvalidator {
+    caches: RwLock<Vec<Arc<dyn InvalidatableCache>>>,
+    cdn: Option<Arc<dyn CdnPurgeClient>>,
+}
+
+impl CacheInvalidator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also purge tags at the CDN
+    pub fn with_cdn_purge_client(mut self, cdn: Arc<dyn CdnPurgeClient>) -> Self {
+        self.cdn = Some(cdn);
+        self
+    }
+
+    pub fn register(&self, cache: Arc<dyn InvalidatableCache>) {
+        self.caches.write().unwrap().push(cache);
+    }
+
+    /// Local caches first, then the CDN, so an edge refill can't be served
+    /// from a stale local entry. A failed purge is reported, not retried.
+    pub async fn invalidate(&self, selector: &CacheSelector) -> InvalidationReport {
+        let caches = self.caches.read().unwrap().clone();
+        let mut report = InvalidationReport { caches: Vec::with_capacity(caches.len()), cdn: CdnPurgeStatus::NoTags, cdn_error: None };
+        for cache in caches {
+            let removed = cache.invalidate(selector).await;
+            report.caches.push(CacheInvalidated { cache: cache.name(), removed });
+        }
+
+        match &self.cdn {
+            None => report.cdn = CdnPurgeStatus::NotConfigured,
+            Some(_) if selector.tags.is_empty() => {}
+            Some(cdn) => match cdn.purge(&selector.tags).await {
+                Ok(()) => report.cdn = CdnPurgeStatus::Purged,
+                Err(err) => {
+                    report.cdn = CdnPurgeStatus::Failed;
+                    report.cdn_error = Some(err.to_string());
+                }
+            },
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::{CdnPurgeError, TtlCache};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    struct Tagged(TtlCache<String, u32>);
+
+    #[async_trait]
+    impl InvalidatableCache for Tagged {
+        fn name(&self) -> &'static str {
+            "tagged"
+        }
+
+        async fn invalidate(&self, selector: &CacheSelector) -> usize {
+            // Entries under `team:` carry the `teams` tag
+            self.0
+                .remove_matching(|key| selector.matches(key, if key.starts_with("team:") { &["teams"] } else { &[] }))
+                .await
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingCdn(Mutex<Vec<String>>);
+
+    #[async_trait]
+    impl CdnPurgeClient for RecordingCdn {
+        async fn purge(&self, keys: &[String]) -> Result<(), CdnPurgeError> {
+            self.0.lock().unwrap().extend_from_slice(keys);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn drops_entries_by_key_prefix_or_tag_and_purges_tags_at_the_cdn() {
+        let cache = TtlCache::new(10);
+        for key in ["user:1", "user:2", "team:1", "team:2", "org:1"] {
+            cache.insert(key.to_string(), 1, Duration::from_secs(60)).await;
+        }
+        let cdn = Arc::new(RecordingCdn::default());
+        let invalidator = CacheInvalidator::new().with_cdn_purge_client(cdn.clone());
+        let tagged = Arc::new(Tagged(cache));
+        invalidator.register(tagged.clone());
+
+        let selector = CacheSelector { keys: vec!["org:1".into()], prefixes: vec!["user:".into()], tags: vec![] };
+        let report = invalidator.invalidate(&selector).await;
+        assert_eq!((report.caches[0].removed, report.cdn), (3, CdnPurgeStatus::NoTags));
+
+        let selector = CacheSelector { tags: vec!["teams".into()], ..CacheSelector::default() };
+        let report = invalidator.invalidate(&selector).await;
+        assert_eq!((report.caches[0].removed, report.cdn), (2, CdnPurgeStatus::Purged));
+        assert_eq!(*cdn.0.lock().unwrap(), vec!["teams"]);
+        assert_eq!(tagged.0.clear().await, 0);
+
+        let local_only = CacheInvalidator::new().invalidate(&selector).await;
+        assert_eq!(local_only.cdn, CdnPurgeStatus::NotConfigured);
+    }
+}
@@ -0,0 +1,99 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+/// Limits for free-form JSON a client stores with a resource, checked at the
+/// boundary before anything is saved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonBudget {
+    /// Serialized size
+    pub max_bytes: usize,
+    /// Levels of nested objects and arrays; `{"a": 1}` is one level
+    pub max_depth: usize,
+    /// Object keys at every level together
+    pub max_keys: usize,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BudgetError {
+    #[error("Must be at most {limit} bytes, got {size}")]
+    TooLarge { size: usize, limit: usize },
+    #[error("Must nest at most {limit} levels deep")]
+    TooDeep { limit: usize },
+    #[error("Must have at most {limit} keys")]
+    TooManyKeys { limit: usize },
+}
+
+impl JsonBudget {
+    pub const fn new(max_bytes: usize, max_depth: usize, max_keys: usize) -> Self {
+        Self { max_bytes, max_depth, max_keys }
+    }
+
+    /// Depth and keys are checked first, stopping at the first level or key
+    /// over the limit, so an oversized document is never fully walked
+    pub fn check(&self, value: &Value) -> Result<(), BudgetError> {
+        self.walk(value, 0, &mut 0)?;
+        self.check_size(value)
+    }
+
+    /// `check` for a top-level object, without wrapping it in a `Value`
+    pub fn check_object(&self, object: &Map<String, Value>) -> Result<(), BudgetError> {
+        self.walk_object(object, 0, &mut 0)?;
+        self.check_size(object)
+    }
+
+    fn check_size(&self, value: &impl Serialize) -> Result<(), BudgetError> {
+        let size = serde_json::to_vec(value).map_or(usize::MAX, |bytes| bytes.len());
+        if size > self.max_bytes {
+            return Err(BudgetError::TooLarge { size, limit: self.max_bytes });
+        }
+        Ok(())
+    }
+
+    fn walk(&self, value: &Value, depth: usize, keys: &mut usize) -> Result<(), BudgetError> {
+        match value {
+            Value::Object(object) => self.walk_object(object, depth, keys),
+            Value::Array(items) => {
+                self.enter(depth)?;
+                items.iter().try_for_each(|item| self.walk(item, depth + 1, keys))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn walk_object(&self, object: &Map<String, Value>, depth: usize, keys: &mut usize) -> Result<(), BudgetError> {
+        *keys += object.len();
+        if *keys > self.max_keys {
+            return Err(BudgetError::TooManyKeys { limit: self.max_keys });
+        }
+        self.enter(depth)?;
+        object.values().try_for_each(|value| self.walk(value, depth + 1, keys))
+    }
+
+    fn enter(&self, depth: usize) -> Result<(), BudgetError> {
+        if depth >= self.max_depth {
+            return Err(BudgetError::TooDeep { limit: self.max_depth });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn rejects_documents_over_any_limit() {
+        let budget = JsonBudget::new(64, 2, 4);
+        assert_eq!(budget.check(&json!({ "plan": "pro", "tags": ["a", "b"] })), Ok(()));
+        assert_eq!(budget.check(&json!({ "a": { "b": { "c": 1 } } })), Err(BudgetError::TooDeep { limit: 2 }));
+        assert_eq!(budget.check(&json!({ "a": [[1]] })), Err(BudgetError::TooDeep { limit: 2 }));
+        assert_eq!(
+            budget.check(&json!({ "a": 1, "b": 2, "c": { "d": 3, "e": 4 } })),
+            Err(BudgetError::TooManyKeys { limit: 4 })
+        );
+        let long = "x".repeat(64);
+        assert_eq!(budget.check(&json!({ "note": long })), Err(BudgetError::TooLarge { size: 75, limit: 64 }));
+    }
+}
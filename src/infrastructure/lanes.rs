@@ -0,0 +1,189 @@
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+
+/// Priority class of a route
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Lane {
+    /// Probes and admin endpoints; may use the whole capacity
+    Critical,
+    /// Regular API calls a client is waiting on
+    Interactive,
+    /// Heavy exports, imports and generated documents
+    Bulk,
+}
+
+impl Lane {
+    pub const ALL: [Lane; 3] = [Lane::Critical, Lane::Interactive, Lane::Bulk];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Critical => "critical",
+            Self::Interactive => "interactive",
+            Self::Bulk => "bulk",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct Counters {
+    in_flight: usize,
+    admitted: u64,
+    rejected: u64,
+}
+
+#[derive(Default)]
+struct Occupancy {
+    total: usize,
+    lanes: [Counters; 3],
+}
+
+/// Counters of one lane
+#[derive(Debug, Clone, Serialize)]
+pub struct LaneStats {
+    pub lane: Lane,
+    /// Requests in flight, across all lanes, above which this lane is shed
+    pub limit: usize,
+    pub in_flight: usize,
+    pub admitted: u64,
+    pub rejected: u64,
+}
+
+/// Shared capacity and the counters of every lane
+#[derive(Debug, Clone, Serialize)]
+pub struct LaneReport {
+    /// 0 when admission is disabled
+    pub capacity: usize,
+    pub in_flight: usize,
+    pub lanes: Vec<LaneStats>,
+}
+
+/// Weighted concurrency limit over one shared pool of request slots.
+///
+/// Each lane may only be admitted while the total in flight, over all lanes,
+/// is below its share of the capacity. Lower lanes are shed first as load
+/// grows, so a burst of bulk requests always leaves room for interactive
+/// traffic, and both leave room for probes and admin calls. Critical always
+/// gets the full capacity. A capacity of 0 admits everything but still counts.
+pub struct LaneLimiter {
+    capacity: usize,
+    limits: [usize; 3],
+    occupancy: Arc<Mutex<Occupancy>>,
+}
+
+/// A claimed slot; dropping it frees the slot
+pub struct LanePermit {
+    lane: Lane,
+    occupancy: Arc<Mutex<Occupancy>>,
+}
+
+/// The request's slot, in request extensions for handlers that park. A
+/// handler about to wait on something other than this server, like a long
+/// poll, releases it so idle waiters don't crowd out work.
+#[derive(Clone)]
+pub struct LaneSlot(Arc<Mutex<Option<LanePermit>>>);
+
+impl LaneSlot {
+    pub fn new(permit: LanePermit) -> Self {
+        Self(Arc::new(Mutex::new(Some(permit))))
+    }
+
+    /// Frees the slot now rather than when the response is produced
+    pub fn release(&self) {
+        self.0.lock().unwrap().take();
+    }
+}
+
+impl LaneLimiter {
+    /// Shares are percentages of `capacity`; each lane gets at least one slot
+    pub fn new(capacity: usize, interactive_share: u8, bulk_share: u8) -> Self {
+        let share = |percent: u8| (capacity * usize::from(percent.min(100)) / 100).max(1);
+        Self {
+            capacity,
+            limits: [capacity, share(interactive_share), share(bulk_share)],
+            occupancy: Arc::default(),
+        }
+    }
+
+    /// `None` when the lane's share of the capacity is in use
+    pub fn try_acquire(&self, lane: Lane) -> Option<LanePermit> {
+        let mut occupancy = self.occupancy.lock().unwrap();
+        if self.capacity > 0 && occupancy.total >= self.limits[lane.index()] {
+            occupancy.lanes[lane.index()].rejected += 1;
+            return None;
+        }
+        occupancy.total += 1;
+        let counters = &mut occupancy.lanes[lane.index()];
+        counters.in_flight += 1;
+        counters.admitted += 1;
+        Some(LanePermit { lane, occupancy: self.occupancy.clone() })
+    }
+
+    pub fn report(&self) -> LaneReport {
+        let occupancy = self.occupancy.lock().unwrap();
+        LaneReport {
+            capacity: self.capacity,
+            in_flight: occupancy.total,
+            lanes: Lane::ALL
+                .iter()
+                .map(|&lane| {
+                    let counters = occupancy.lanes[lane.index()];
+                    LaneStats {
+                        lane,
+                        limit: self.limits[lane.index()],
+                        in_flight: counters.in_flight,
+                        admitted: counters.admitted,
+                        rejected: counters.rejected,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Drop for LanePermit {
+    fn drop(&mut self) {
+        let mut occupancy = self.occupancy.lock().unwrap();
+        occupancy.total -= 1;
+        occupancy.lanes[self.lane.index()].in_flight -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lower_lanes_are_shed_first_and_slots_free_on_drop() {
+        let limiter = LaneLimiter::new(10, 80, 20);
+        let bulk: Vec<_> = (0..2).map(|_| limiter.try_acquire(Lane::Bulk).unwrap()).collect();
+        assert!(limiter.try_acquire(Lane::Bulk).is_none());
+
+        let interactive: Vec<_> = (0..6).map(|_| limiter.try_acquire(Lane::Interactive).unwrap()).collect();
+        assert!(limiter.try_acquire(Lane::Interactive).is_none());
+        assert!(limiter.try_acquire(Lane::Critical).is_some());
+
+        drop(bulk);
+        assert!(limiter.try_acquire(Lane::Bulk).is_none(), "interactive traffic still holds the bulk share");
+        let _more = limiter.try_acquire(Lane::Interactive).unwrap();
+
+        let report = limiter.report();
+        assert_eq!(report.in_flight, 7);
+        let bulk = &report.lanes[Lane::Bulk.index()];
+        assert_eq!((bulk.limit, bulk.in_flight, bulk.admitted, bulk.rejected), (2, 0, 2, 2));
+        let critical = &report.lanes[Lane::Critical.index()];
+        assert_eq!((critical.limit, critical.in_flight, critical.admitted), (10, 0, 1));
+        drop(interactive);
+    }
+
+    #[test]
+    fn zero_capacity_admits_everything() {
+        let limiter = LaneLimiter::new(0, 80, 20);
+        let permits: Vec<_> = (0..50).map(|_| limiter.try_acquire(Lane::Bulk).unwrap()).collect();
+        assert_eq!(limiter.report().lanes[Lane::Bulk.index()].in_flight, permits.len());
+    }
+}
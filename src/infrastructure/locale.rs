@@ -0,0 +1,259 @@
+use serde::Serialize;
+
+/// Number and currency conventions of one locale
+#[derive(Debug, PartialEq, Eq)]
+pub struct Locale {
+    /// BCP 47 tag, e.g. `de-DE`
+    pub tag: &'static str,
+    decimal: char,
+    group: char,
+    /// `1.234,56 €` rather than `€1,234.56`
+    symbol_after: bool,
+    /// Used when the client sends no `X-Currency`
+    currency: &'static str,
+    units: UnitSystem,
+}
+
+/// A currency and how many minor units make up one major unit
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct Currency {
+    /// ISO 4217 code
+    pub code: &'static str,
+    pub symbol: &'static str,
+    pub minor_units: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnitSystem {
+    Metric,
+    Imperial,
+}
+
+impl UnitSystem {
+    /// `metric` or `imperial`, case-insensitive
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "metric" => Some(Self::Metric),
+            "imperial" => Some(Self::Imperial),
+            _ => None,
+        }
+    }
+}
+
+const fn locale(
+    tag: &'static str,
+    decimal: char,
+    group: char,
+    symbol_after: bool,
+    currency: &'static str,
+    units: UnitSystem,
+) -> Locale {
+    Locale { tag, decimal, group, symbol_after, currency, units }
+}
+
+/// Locales the API formats for; anything else falls back to the default
+pub static LOCALES: &[Locale] = &[
+    locale("en-US", '.', ',', false, "USD", UnitSystem::Imperial),
+    locale("en-GB", '.', ',', false, "GBP", UnitSystem::Metric),
+    locale("de-DE", ',', '.', true, "EUR", UnitSystem::Metric),
+    locale("fr-FR", ',', '\u{202f}', true, "EUR", UnitSystem::Metric),
+    locale("es-ES", ',', '.', true, "EUR", UnitSystem::Metric),
+    locale("nl-NL", ',', '.', false, "EUR", UnitSystem::Metric),
+    locale("id-ID", ',', '.', false, "IDR", UnitSystem::Metric),
+    locale("ja-JP", '.', ',', false, "JPY", UnitSystem::Metric),
+];
+
+pub static CURRENCIES: &[Currency] = &[
+    Currency { code: "USD", symbol: "$", minor_units: 2 },
+    Currency { code: "EUR", symbol: "€", minor_units: 2 },
+    Currency { code: "GBP", symbol: "£", minor_units: 2 },
+    Currency { code: "IDR", symbol: "Rp", minor_units: 2 },
+    Currency { code: "JPY", symbol: "¥", minor_units: 0 },
+    Currency { code: "CHF", symbol: "CHF\u{a0}", minor_units: 2 },
+    Currency { code: "AUD", symbol: "A$", minor_units: 2 },
+    Currency { code: "CAD", symbol: "CA$", minor_units: 2 },
+    Currency { code: "SGD", symbol: "S$", minor_units: 2 },
+    Currency { code: "KWD", symbol: "KD", minor_units: 3 },
+];
+
+impl Locale {
+    /// Exact tag, case-insensitive, or the first locale of the same language
+    pub fn find(tag: &str) -> Option<&'static Locale> {
+        let tag = tag.trim();
+        let language = tag.split(['-', '_']).next().unwrap_or_default();
+        LOCALES
+            .iter()
+            .find(|locale| locale.tag.eq_ignore_ascii_case(&tag.replace('_', "-")))
+            .or_else(|| {
+                LOCALES.iter().find(|locale| {
+                    locale.tag.split('-').next().is_some_and(|own| own.eq_ignore_ascii_case(language))
+                })
+            })
+    }
+
+    /// Best supported locale of an `Accept-Language` header, by quality
+    pub fn negotiate(accept_language: &str) -> Option<&'static Locale> {
+        let mut ranges: Vec<(&str, f32)> = accept_language
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let tag = parts.next()?.trim();
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse().ok())?;
+                (!tag.is_empty() && tag != "*" && quality > 0.0).then_some((tag, quality))
+            })
+            .collect();
+        // Stable, so equal qualities keep the client's order
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranges.into_iter().find_map(|(tag, _)| Locale::find(tag))
+    }
+
+    pub fn currency(&self) -> &'static Currency {
+        Currency::find(self.currency).expect("every locale's currency is in CURRENCIES")
+    }
+
+    pub fn units(&self) -> UnitSystem {
+        self.units
+    }
+
+    /// `value` rounded to `decimals`, with this locale's separators
+    pub fn format_number(&self, value: f64, decimals: usize) -> String {
+        let formatted = format!("{:.*}", decimals, value.abs());
+        let (integer, fraction) = formatted.split_once('.').unwrap_or((&formatted, ""));
+        self.separate(value < 0.0, integer, fraction)
+    }
+
+    /// An amount in the currency's minor units, e.g. cents. Integer math, so
+    /// large amounts stay exact.
+    pub fn format_money(&self, minor: i64, currency: &Currency) -> String {
+        let scale = 10u64.pow(currency.minor_units);
+        let integer = (minor.unsigned_abs() / scale).to_string();
+        let fraction = match currency.minor_units {
+            0 => String::new(),
+            width => format!("{:0width$}", minor.unsigned_abs() % scale, width = width as usize),
+        };
+        let number = self.separate(minor < 0, &integer, &fraction);
+        if self.symbol_after {
+            format!("{number}\u{a0}{}", currency.symbol)
+        } else if let Some(unsigned) = number.strip_prefix('-') {
+            format!("-{}{unsigned}", currency.symbol)
+        } else {
+            format!("{}{number}", currency.symbol)
+        }
+    }
+
+    /// Join digit strings with this locale's group and decimal separators. A
+    /// value that rounds to zero gets no minus sign.
+    fn separate(&self, negative: bool, integer: &str, fraction: &str) -> String {
+        let mut out = String::new();
+        if negative && integer.bytes().chain(fraction.bytes()).any(|digit| digit != b'0') {
+            out.push('-');
+        }
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index).is_multiple_of(3) {
+                out.push(self.group);
+            }
+            out.push(digit);
+        }
+        if !fraction.is_empty() {
+            out.push(self.decimal);
+            out.push_str(fraction);
+        }
+        out
+    }
+}
+
+impl Currency {
+    /// By ISO 4217 code, case-insensitive
+    pub fn find(code: &str) -> Option<&'static Currency> {
+        CURRENCIES.iter().find(|currency| currency.code.eq_ignore_ascii_case(code.trim()))
+    }
+}
+
+/// Locale, currency and unit system a response should be formatted for,
+/// resolved per request by `request_context_middleware`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestContext {
+    pub locale: &'static Locale,
+    pub currency: &'static Currency,
+    pub units: UnitSystem,
+}
+
+impl Default for RequestContext {
+    /// `en-US`, `USD`, metric
+    fn default() -> Self {
+        Self { locale: &LOCALES[0], currency: &CURRENCIES[0], units: UnitSystem::Metric }
+    }
+}
+
+impl RequestContext {
+    /// From config values; unknown ones keep the `Default` part
+    pub fn from_settings(locale: &str, currency: &str, units: &str) -> Self {
+        let fallback = Self::default();
+        Self {
+            locale: Locale::find(locale).unwrap_or(fallback.locale),
+            currency: Currency::find(currency).unwrap_or(fallback.currency),
+            units: UnitSystem::parse(units).unwrap_or(fallback.units),
+        }
+    }
+
+    pub fn format_number(&self, value: f64, decimals: usize) -> String {
+        self.locale.format_number(value, decimals)
+    }
+
+    /// An amount in minor units of the request's currency
+    pub fn format_money(&self, minor: i64) -> String {
+        self.locale.format_money(minor, self.currency)
+    }
+
+    /// Kilometres or miles, one decimal
+    pub fn format_distance(&self, meters: f64) -> String {
+        match self.units {
+            UnitSystem::Metric => format!("{} km", self.format_number(meters / 1000.0, 1)),
+            UnitSystem::Imperial => format!("{} mi", self.format_number(meters / 1609.344, 1)),
+        }
+    }
+
+    /// Kilograms or pounds, one decimal
+    pub fn format_mass(&self, grams: f64) -> String {
+        match self.units {
+            UnitSystem::Metric => format!("{} kg", self.format_number(grams / 1000.0, 1)),
+            UnitSystem::Imperial => format!("{} lb", self.format_number(grams / 453.592_37, 1)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiates_locales_by_quality_and_language() {
+        assert_eq!(Locale::negotiate("fr-CA, de;q=0.8").unwrap().tag, "fr-FR");
+        assert_eq!(Locale::negotiate("pl;q=0.9, nl-NL;q=0.5, en;q=0.7").unwrap().tag, "en-US");
+        assert_eq!(Locale::negotiate("en-gb").unwrap().tag, "en-GB");
+        assert!(Locale::negotiate("pl, *;q=0.1").is_none());
+        assert!(Locale::negotiate("de;q=0").is_none());
+    }
+
+    #[test]
+    fn formats_numbers_money_and_units_per_locale() {
+        let us = Locale::find("en-US").unwrap();
+        let de = Locale::find("de-DE").unwrap();
+        let eur = Currency::find("eur").unwrap();
+        assert_eq!(us.format_number(1234567.891, 2), "1,234,567.89");
+        assert_eq!(de.format_number(-1234.5, 1), "-1.234,5");
+        assert_eq!(us.format_number(-0.001, 2), "0.00");
+        assert_eq!(us.format_money(-123456, us.currency()), "-$1,234.56");
+        assert_eq!(de.format_money(123456, eur), "1.234,56\u{a0}€");
+        assert_eq!(us.format_money(1500, Currency::find("JPY").unwrap()), "¥1,500");
+        assert_eq!(us.format_money(i64::MAX, us.currency()), "$92,233,720,368,547,758.07");
+
+        let context = RequestContext { locale: de, currency: eur, units: UnitSystem::Imperial };
+        assert_eq!(context.format_distance(16093.44), "10,0 mi");
+        assert_eq!(RequestContext::default().format_mass(2500.0), "2.5 kg");
+        assert_eq!(RequestContext::from_settings("xx", "EUR", "imperial").currency.code, "EUR");
+    }
+}
@@ -1,14 +1,22 @@
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-pub fn init_logger() {
+use super::{AuditJournalLayer, OpsAlertLayer};
+
+/// `filter` takes `EnvFilter` directives, a default level plus per-target
+/// levels such as `info,http_body=debug`. `alerts` forwards `alerts`-target
+/// events to the ops chat webhook when configured; `audit` keeps `audit`-target
+/// events in the audit journal
+pub fn init_logger(filter: &str, alerts: Option<OpsAlertLayer>, audit: Option<AuditJournalLayer>) {
     tracing_subscriber::registry()
         .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
+            tracing_subscriber::EnvFilter::try_new(filter)
                 .unwrap_or_else(|_| "info".into()),
         )
         .with(
             tracing_subscriber::fmt::layer()
                 .json()
         )
+        .with(alerts)
+        .with(audit)
         .init();
 }
\ No newline at end of file
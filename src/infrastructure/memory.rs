@@ -0,0 +1,253 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use crate::container::startup::StartupComponent;
+
+const MIB: u64 = 1024 * 1024;
+
+/// Something holding memory it can rebuild later, such as a cache
+#[async_trait]
+pub trait MemoryReclaimer: Send + Sync {
+    fn name(&self) -> &'static str;
+    /// Drop what can be dropped; returns the number of entries released
+    async fn shrink(&self) -> usize;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryPressure {
+    Normal,
+    /// Over the soft limit; reclaimers are shrunk on every sample
+    Soft,
+    /// Over the hard limit; the instance reports not ready
+    Hard,
+}
+
+/// Process memory as the kernel accounts it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ProcessMemory {
+    pub rss_bytes: u64,
+    pub peak_rss_bytes: u64,
+    pub virtual_bytes: u64,
+}
+
+impl ProcessMemory {
+    /// `None` where `/proc` is unavailable
+    pub fn sample() -> Option<Self> {
+        std::fs::read_to_string("/proc/self/status").ok().as_deref().and_then(Self::parse)
+    }
+
+    fn parse(status: &str) -> Option<Self> {
+        let field = |name: &str| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+                .and_then(|value| value.trim().strip_suffix("kB"))
+                .and_then(|kib| kib.trim().parse::<u64>().ok())
+                .map(|kib| kib * 1024)
+        };
+        Some(Self {
+            rss_bytes: field("VmRSS")?,
+            peak_rss_bytes: field("VmHWM").unwrap_or_default(),
+            virtual_bytes: field("VmSize").unwrap_or_default(),
+        })
+    }
+}
+
+/// Latest sample and limits, served by `GET /api/admin/memory`
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryReport {
+    pub pressure: MemoryPressure,
+    pub memory: Option<ProcessMemory>,
+    pub soft_limit_bytes: Option<u64>,
+    pub hard_limit_bytes: Option<u64>,
+    /// Reclaimer entries released since startup
+    pub reclaimed_entries: u64,
+    pub sampled_at: Option<DateTime<Utc>>,
+}
+
+/// Samples process RSS and acts before the OOM killer does.
+///
+/// Past the soft limit every registered reclaimer is shrunk on each sample.
+/// Past the hard limit the memory health probe fails, so readiness turns
+/// not-ready and the load balancer stops sending traffic until memory drops.
+/// A limit of 0 disables it.
+pub struct MemoryGuard {
+    soft_limit: Option<u64>,
+    hard_limit: Option<u64>,
+    interval: Duration,
+    reclaimers: RwLock<Vec<Arc<dyn MemoryReclaimer>>>,
+    report: Mutex<MemoryReport>,
+}
+
+impl MemoryGuard {
+    pub fn new(soft_limit_mb: u64, hard_limit_mb: u64, interval: Duration) -> Self {
+        let limit = |mb: u64| (mb > 0).then_some(mb * MIB);
+        let (soft_limit, hard_limit) = (limit(soft_limit_mb), limit(hard_limit_mb));
+        Self {
+            soft_limit,
+            hard_limit,
+            interval,
+            reclaimers: RwLock::new(Vec::new()),
+            report: Mutex::new(MemoryReport {
+                pressure: MemoryPressure::Normal,
+                memory: None,
+                soft_limit_bytes: soft_limit,
+                hard_limit_bytes: hard_limit,
+                reclaimed_entries: 0,
+                sampled_at: None,
+            }),
+        }
+    }
+
+    pub fn register_reclaimer(&self, reclaimer: Arc<dyn MemoryReclaimer>) {
+        self.reclaimers.write().unwrap().push(reclaimer);
+    }
+
+    pub fn report(&self) -> MemoryReport {
+        self.report.lock().unwrap().clone()
+    }
+
+    pub fn pressure(&self) -> MemoryPressure {
+        self.report.lock().unwrap().pressure
+    }
+
+    fn pressure_at(&self, rss_bytes: u64) -> MemoryPressure {
+        let over = |limit: Option<u64>| limit.is_some_and(|limit| rss_bytes >= limit);
+        if over(self.hard_limit) {
+            MemoryPressure::Hard
+        } else if over(self.soft_limit) {
+            MemoryPressure::Soft
+        } else {
+            MemoryPressure::Normal
+        }
+    }
+
+    /// Apply one sample: update the pressure and shrink reclaimers above the soft limit
+    pub async fn evaluate(&self, memory: Option<ProcessMemory>) -> MemoryPressure {
+        let pressure = memory.map_or(MemoryPressure::Normal, |memory| self.pressure_at(memory.rss_bytes));
+
+        let mut reclaimed = 0;
+        if pressure != MemoryPressure::Normal {
+            let reclaimers = self.reclaimers.read().unwrap().clone();
+            for reclaimer in reclaimers {
+                let released = reclaimer.shrink().await;
+                tracing::debug!(reclaimer = reclaimer.name(), released, "Shrunk under memory pressure");
+                reclaimed += released as u64;
+            }
+        }
+
+        let previous = {
+            let mut report = self.report.lock().unwrap();
+            let previous = report.pressure;
+            report.pressure = pressure;
+            report.memory = memory;
+            report.reclaimed_entries += reclaimed;
+            report.sampled_at = Some(Utc::now());
+            previous
+        };
+        if pressure != previous {
+            let rss_mb = memory.map_or(0, |memory| memory.rss_bytes / MIB);
+            match pressure {
+                MemoryPressure::Hard => {
+                    tracing::error!(target: "alerts", rss_mb, "Memory over hard limit; reporting not ready")
+                }
+                MemoryPressure::Soft => tracing::warn!(rss_mb, reclaimed, "Memory over soft limit; shrinking caches"),
+                MemoryPressure::Normal => tracing::info!(rss_mb, "Memory back under limits"),
+            }
+        }
+        pressure
+    }
+
+    pub fn spawn_sampler(self: &Arc<Self>) {
+        let guard = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(guard.interval);
+            loop {
+                ticker.tick().await;
+                guard.evaluate(ProcessMemory::sample()).await;
+            }
+        });
+    }
+}
+
+/// Starts sampling process memory once the server is about to take traffic
+pub struct MemorySampler {
+    guard: Arc<MemoryGuard>,
+}
+
+impl MemorySampler {
+    pub fn new(guard: Arc<MemoryGuard>) -> Self {
+        Self { guard }
+    }
+}
+
+#[async_trait]
+impl StartupComponent for MemorySampler {
+    fn name(&self) -> &'static str {
+        "memory_guard"
+    }
+
+    async fn start(&self) -> Result<(), String> {
+        self.guard.spawn_sampler();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Counting(AtomicUsize);
+
+    #[async_trait]
+    impl MemoryReclaimer for Counting {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+
+        async fn shrink(&self) -> usize {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            10
+        }
+    }
+
+    fn rss_mb(mb: u64) -> Option<ProcessMemory> {
+        Some(ProcessMemory { rss_bytes: mb * MIB, peak_rss_bytes: mb * MIB, virtual_bytes: 0 })
+    }
+
+    #[test]
+    fn parses_proc_status() {
+        let status = "Name:\tserver\nVmPeak:\t  900 kB\nVmSize:\t  800 kB\nVmHWM:\t  300 kB\nVmRSS:\t  200 kB\n";
+        assert_eq!(
+            ProcessMemory::parse(status),
+            Some(ProcessMemory { rss_bytes: 200 * 1024, peak_rss_bytes: 300 * 1024, virtual_bytes: 800 * 1024 })
+        );
+        assert_eq!(ProcessMemory::parse("Name:\tserver\n"), None);
+    }
+
+    #[tokio::test]
+    async fn soft_limit_shrinks_and_hard_limit_is_reported() {
+        let guard = MemoryGuard::new(100, 200, Duration::from_secs(1));
+        let reclaimer = Arc::new(Counting(AtomicUsize::new(0)));
+        guard.register_reclaimer(reclaimer.clone());
+
+        assert_eq!(guard.evaluate(rss_mb(50)).await, MemoryPressure::Normal);
+        assert_eq!(reclaimer.0.load(Ordering::Relaxed), 0);
+
+        assert_eq!(guard.evaluate(rss_mb(150)).await, MemoryPressure::Soft);
+        assert_eq!(guard.evaluate(rss_mb(250)).await, MemoryPressure::Hard);
+        assert_eq!(reclaimer.0.load(Ordering::Relaxed), 2);
+        assert_eq!(guard.report().reclaimed_entries, 20);
+        assert_eq!(guard.pressure(), MemoryPressure::Hard);
+
+        assert_eq!(guard.evaluate(rss_mb(50)).await, MemoryPressure::Normal);
+
+        let disabled = MemoryGuard::new(0, 0, Duration::from_secs(1));
+        assert_eq!(disabled.evaluate(rss_mb(10_000)).await, MemoryPressure::Normal);
+    }
+}
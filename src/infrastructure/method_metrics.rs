@@ -0,0 +1,192 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Upper bounds of the latency buckets, in milliseconds; slower calls land
+/// in a final overflow bucket
+pub(crate) const BUCKETS_MS: [u64; 11] = [1, 2, 5, 10, 25, 50, 100, 250, 500, 1_000, 2_500];
+
+/// Index into `BUCKETS_MS`, or of the overflow bucket
+pub(crate) fn latency_bucket(elapsed: Duration) -> usize {
+    let millis = elapsed.as_secs_f64() * 1000.0;
+    BUCKETS_MS.iter().position(|le| millis <= *le as f64).unwrap_or(BUCKETS_MS.len())
+}
+
+/// Upper bound of the bucket holding `quantile` of the `total` samples
+/// counted in `counts`; `None` past the last bucket
+pub(crate) fn bucket_percentile(counts: &[u64], total: u64, quantile: f64) -> Option<u64> {
+    let rank = (quantile * total as f64).ceil().max(1.0) as u64;
+    let mut seen = 0;
+    counts
+        .iter()
+        .position(|count| {
+            seen += count;
+            seen >= rank
+        })
+        .and_then(|bucket| BUCKETS_MS.get(bucket).copied())
+}
+
+/// Whether an error returned by a measured method counts against its error
+/// rate. Errors the caller caused, such as validation failures or missing
+/// records, are answers rather than faults.
+pub trait MethodOutcome {
+    fn is_fault(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Default)]
+struct MethodStats {
+    calls: AtomicU64,
+    faults: AtomicU64,
+    over_budget: AtomicU64,
+    total_micros: AtomicU64,
+    buckets: [AtomicU64; BUCKETS_MS.len() + 1],
+}
+
+/// Calls, faults and latency of one service method
+#[derive(Debug, Clone, Serialize)]
+pub struct MethodReport {
+    /// `service.method`
+    pub method: &'static str,
+    pub calls: u64,
+    pub faults: u64,
+    /// Faults per call, from 0 to 1
+    pub error_rate: f64,
+    /// Calls slower than the budget
+    pub over_budget: u64,
+    pub mean_ms: f64,
+    /// Upper bounds of the buckets holding each percentile; `None` past the last bucket
+    pub p50_ms: Option<u64>,
+    pub p95_ms: Option<u64>,
+    pub p99_ms: Option<u64>,
+    pub buckets: Vec<LatencyBucket>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyBucket {
+    /// `None` for the overflow bucket
+    pub le_ms: Option<u64>,
+    pub count: u64,
+}
+
+/// Per-method call counts, latency histograms and error rates for the
+/// domain layer, below the HTTP metrics. Services are measured by wrapping
+/// each method body in `measure`, usually from a decorator around the
+/// service trait so the implementation stays unaware of it.
+pub struct MethodMetrics {
+    /// Calls slower than this are counted and logged; zero disables
+    budget: Duration,
+    methods: RwLock<BTreeMap<&'static str, Arc<MethodStats>>>,
+}
+
+impl MethodMetrics {
+    pub fn new(budget: Duration) -> Self {
+        Self { budget, methods: RwLock::default() }
+    }
+
+    /// Run `call`, recording its latency and outcome under `method`
+    pub async fn measure<T, E: MethodOutcome>(
+        &self,
+        method: &'static str,
+        call: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        let started = Instant::now();
+        let result = call.await;
+        self.record(method, started.elapsed(), result.as_ref().err().is_some_and(MethodOutcome::is_fault));
+        result
+    }
+
+    fn record(&self, method: &'static str, elapsed: Duration, fault: bool) {
+        let stats = self.stats(method);
+        stats.calls.fetch_add(1, Ordering::Relaxed);
+        stats.total_micros.fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+        let millis = elapsed.as_secs_f64() * 1000.0;
+        stats.buckets[latency_bucket(elapsed)].fetch_add(1, Ordering::Relaxed);
+        if fault {
+            stats.faults.fetch_add(1, Ordering::Relaxed);
+        }
+        if !self.budget.is_zero() && elapsed > self.budget {
+            stats.over_budget.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!(method, elapsed_ms = millis, budget_ms = self.budget.as_millis() as u64, "Service method over budget");
+        }
+    }
+
+    fn stats(&self, method: &'static str) -> Arc<MethodStats> {
+        if let Some(stats) = self.methods.read().unwrap().get(method) {
+            return stats.clone();
+        }
+        self.methods.write().unwrap().entry(method).or_default().clone()
+    }
+
+    /// Every measured method, by name
+    pub fn report(&self) -> Vec<MethodReport> {
+        self.methods.read().unwrap().iter().map(|(method, stats)| stats.report(method)).collect()
+    }
+}
+
+impl MethodStats {
+    fn report(&self, method: &'static str) -> MethodReport {
+        let calls = self.calls.load(Ordering::Relaxed);
+        let faults = self.faults.load(Ordering::Relaxed);
+        let counts: Vec<u64> = self.buckets.iter().map(|bucket| bucket.load(Ordering::Relaxed)).collect();
+        let percentile = |quantile: f64| bucket_percentile(&counts, calls, quantile);
+        MethodReport {
+            method,
+            calls,
+            faults,
+            error_rate: if calls == 0 { 0.0 } else { faults as f64 / calls as f64 },
+            over_budget: self.over_budget.load(Ordering::Relaxed),
+            mean_ms: if calls == 0 { 0.0 } else { self.total_micros.load(Ordering::Relaxed) as f64 / calls as f64 / 1000.0 },
+            p50_ms: percentile(0.50),
+            p95_ms: percentile(0.95),
+            p99_ms: percentile(0.99),
+            buckets: counts
+                .iter()
+                .enumerate()
+                .map(|(bucket, count)| LatencyBucket { le_ms: BUCKETS_MS.get(bucket).copied(), count: *count })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    enum Error {
+        Invalid,
+        Down,
+    }
+
+    impl MethodOutcome for Error {
+        fn is_fault(&self) -> bool {
+            matches!(self, Error::Down)
+        }
+    }
+
+    #[tokio::test]
+    async fn calls_are_bucketed_and_only_faults_count_as_errors() {
+        let metrics = MethodMetrics::new(Duration::from_millis(1));
+        for _ in 0..8 {
+            metrics.measure("users.get", async { Ok::<_, Error>(()) }).await.unwrap();
+        }
+        assert!(metrics.measure("users.get", async { Err::<(), _>(Error::Invalid) }).await.is_err());
+        let slow = async {
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            Err::<(), _>(Error::Down)
+        };
+        assert!(metrics.measure("users.get", slow).await.is_err());
+
+        let report = &metrics.report()[0];
+        assert_eq!((report.method, report.calls, report.faults, report.over_budget), ("users.get", 10, 1, 1));
+        assert_eq!(report.error_rate, 0.1);
+        assert_eq!(report.p50_ms, Some(1));
+        assert!(report.p99_ms >= Some(50), "{report:?}");
+        assert_eq!(report.buckets.iter().map(|bucket| bucket.count).sum::<u64>(), 10);
+    }
+}
@@ -1,3 +1,80 @@
 pub mod logger;
+pub mod cache;
+pub mod invalidation;
+pub mod bloom;
+pub mod cdn;
+pub mod schema_registry;
+pub mod registry_serializer;
+pub mod sharding;
+pub mod deployment;
+pub mod region;
+pub mod sync;
+pub mod geoip;
+pub mod anomaly;
+pub mod body;
+pub mod alerting;
+pub mod audit_journal;
+pub mod audit_export;
+pub mod audit_archive;
+pub mod rate_limit;
+pub mod concurrency;
+pub mod lanes;
+pub mod blocking;
+pub mod cpu_pool;
+pub mod memory;
+pub mod object_pool;
+pub mod method_metrics;
+pub mod consumer_metrics;
+pub mod runtime;
+pub mod versioning;
+pub mod validation;
+pub mod json_budget;
+pub mod locale;
+pub mod money;
+pub mod sql_log;
+pub mod fan_out;
+pub mod plugins;
+#[cfg(feature = "wasm-plugins")]
+pub mod wasm_plugins;
+#[cfg(feature = "script-policies")]
+pub mod script_policies;
 
-pub use logger::*;
\ No newline at end of file
+pub use logger::*;
+pub use cache::*;
+pub use invalidation::*;
+pub use bloom::*;
+pub use cdn::*;
+pub use schema_registry::*;
+pub use registry_serializer::*;
+pub use sharding::*;
+pub use deployment::*;
+pub use region::*;
+pub use geoip::*;
+pub use anomaly::*;
+pub use body::*;
+pub use alerting::*;
+pub use audit_journal::*;
+pub use audit_export::*;
+pub use audit_archive::*;
+pub use rate_limit::*;
+pub use concurrency::*;
+pub use lanes::*;
+pub use blocking::*;
+pub use cpu_pool::*;
+pub use memory::*;
+pub use object_pool::*;
+pub use method_metrics::*;
+pub use consumer_metrics::*;
+pub use runtime::*;
+pub use versioning::*;
+pub use validation::*;
+pub use json_budget::*;
+pub use locale::*;
+pub use money::*;
+pub use sql_log::*;
+pub use fan_out::*;
+pub use plugins::*;
+#[cfg(feature = "wasm-plugins")]
+pub use wasm_plugins::*;
+#[cfg(feature = "script-policies")]
+pub use script_policies::*;
@@ -0,0 +1,270 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef};
+use sqlx::{Decode, Encode, Postgres, Type};
+use std::fmt;
+use thiserror::Error;
+use validator::ValidationError;
+
+use super::locale::{Currency, Locale};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MoneyError {
+    #[error("unknown currency: {0}")]
+    UnknownCurrency(String),
+    #[error("invalid amount: {0}")]
+    InvalidAmount(String),
+    #[error("cannot combine {0} with {1}")]
+    CurrencyMismatch(&'static str, &'static str),
+    #[error("amount out of range")]
+    Overflow,
+}
+
+/// How an amount is brought to its currency's minor units
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// Halves to the even neighbour (banker's rounding); no bias over many
+    /// roundings, so the default for computed amounts
+    HalfEven,
+    /// Halves away from zero, as on most receipts
+    HalfUp,
+    /// Toward zero, e.g. for amounts paid out
+    Down,
+    /// Away from zero, e.g. for amounts charged
+    Up,
+}
+
+impl Rounding {
+    fn strategy(self) -> RoundingStrategy {
+        match self {
+            Self::HalfEven => RoundingStrategy::MidpointNearestEven,
+            Self::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+            Self::Down => RoundingStrategy::ToZero,
+            Self::Up => RoundingStrategy::AwayFromZero,
+        }
+    }
+}
+
+/// An exact decimal amount in one currency. Use it for every financial field
+/// instead of `f64`.
+///
+/// Serialized as `{"amount": "12.50", "currency": "EUR"}`. The amount is a
+/// string so JSON clients can't lose precision, and numbers are rejected.
+/// Arithmetic is checked and never mixes currencies. Intermediate results
+/// (tax, discounts) keep their full precision until `round` brings them to the
+/// currency's minor units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Money {
+    amount: Decimal,
+    currency: &'static Currency,
+}
+
+impl Money {
+    pub fn new(amount: Decimal, currency: &'static Currency) -> Self {
+        Self { amount, currency }
+    }
+
+    /// `"12.50"` and an ISO 4217 code
+    pub fn parse(amount: &str, currency: &str) -> Result<Self, MoneyError> {
+        let currency = Currency::find(currency).ok_or_else(|| MoneyError::UnknownCurrency(currency.to_string()))?;
+        let amount = amount.trim().parse().map_err(|_| MoneyError::InvalidAmount(amount.to_string()))?;
+        Ok(Self::new(amount, currency))
+    }
+
+    /// From an integer count of minor units, e.g. cents
+    pub fn from_minor(minor: i64, currency: &'static Currency) -> Self {
+        Self::new(Decimal::new(minor, currency.minor_units), currency)
+    }
+
+    pub fn zero(currency: &'static Currency) -> Self {
+        Self::new(Decimal::ZERO, currency)
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    pub fn currency(&self) -> &'static Currency {
+        self.currency
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.amount.is_sign_negative() && !self.amount.is_zero()
+    }
+
+    /// Whether the amount has no more decimals than the currency's minor units
+    pub fn is_rounded(&self) -> bool {
+        self.amount.normalize().scale() <= self.currency.minor_units
+    }
+
+    /// To the currency's minor units
+    pub fn round(self, rounding: Rounding) -> Self {
+        let amount = self.amount.round_dp_with_strategy(self.currency.minor_units, rounding.strategy());
+        Self::new(amount, self.currency)
+    }
+
+    /// Rounded, as an integer count of minor units
+    pub fn to_minor(self, rounding: Rounding) -> Result<i64, MoneyError> {
+        let rounded = self.round(rounding).amount;
+        let minor = rounded.checked_mul(Decimal::from(10u64.pow(self.currency.minor_units))).ok_or(MoneyError::Overflow)?;
+        i64::try_from(minor).map_err(|_| MoneyError::Overflow)
+    }
+
+    pub fn checked_add(self, other: Money) -> Result<Self, MoneyError> {
+        self.same_currency(other)?;
+        let amount = self.amount.checked_add(other.amount).ok_or(MoneyError::Overflow)?;
+        Ok(Self::new(amount, self.currency))
+    }
+
+    pub fn checked_sub(self, other: Money) -> Result<Self, MoneyError> {
+        self.same_currency(other)?;
+        let amount = self.amount.checked_sub(other.amount).ok_or(MoneyError::Overflow)?;
+        Ok(Self::new(amount, self.currency))
+    }
+
+    /// By a rate such as a quantity, tax or discount; not rounded
+    pub fn checked_mul(self, factor: Decimal) -> Result<Self, MoneyError> {
+        let amount = self.amount.checked_mul(factor).ok_or(MoneyError::Overflow)?;
+        Ok(Self::new(amount, self.currency))
+    }
+
+    /// Rounded with `rounding` and formatted for `locale`
+    pub fn format(self, locale: &Locale, rounding: Rounding) -> Result<String, MoneyError> {
+        Ok(locale.format_money(self.to_minor(rounding)?, self.currency))
+    }
+
+    fn same_currency(self, other: Money) -> Result<(), MoneyError> {
+        if self.currency != other.currency {
+            return Err(MoneyError::CurrencyMismatch(self.currency.code, other.currency.code));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Money {
+    /// `12.50 EUR`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.amount, self.currency.code)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct MoneyJson {
+    #[serde(with = "rust_decimal::serde::str")]
+    amount: Decimal,
+    currency: String,
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MoneyJson { amount: self.amount, currency: self.currency.code.to_string() }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = MoneyJson::deserialize(deserializer)?;
+        let currency = Currency::find(&json.currency)
+            .ok_or_else(|| serde::de::Error::custom(MoneyError::UnknownCurrency(json.currency.clone())))?;
+        Ok(Money::new(json.amount, currency))
+    }
+}
+
+/// `validator` check: no more decimals than the currency has,
+/// e.g. `#[validate(custom = "crate::infrastructure::validate_money_precision")]`
+pub fn validate_money_precision(money: &Money) -> Result<(), ValidationError> {
+    if money.is_rounded() {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("money_precision");
+    error.message = Some(format!("At most {} decimals for {}", money.currency.minor_units, money.currency.code).into());
+    Err(error)
+}
+
+/// `validator` check: zero or more
+pub fn validate_money_non_negative(money: &Money) -> Result<(), ValidationError> {
+    if !money.is_negative() {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("money_non_negative");
+    error.message = Some("Must not be negative".into());
+    Err(error)
+}
+
+/// Postgres composite `money_amount (amount NUMERIC, currency TEXT)`, created
+/// by `migrations/002_create_products.sql`
+#[derive(sqlx::Type)]
+#[sqlx(type_name = "money_amount")]
+struct MoneyRecord {
+    amount: Decimal,
+    currency: String,
+}
+
+impl Type<Postgres> for Money {
+    fn type_info() -> PgTypeInfo {
+        MoneyRecord::type_info()
+    }
+}
+
+impl<'q> Encode<'q, Postgres> for Money {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        MoneyRecord { amount: self.amount, currency: self.currency.code.to_string() }.encode_by_ref(buf)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for Money {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let record = MoneyRecord::decode(value)?;
+        let currency = Currency::find(&record.currency).ok_or(MoneyError::UnknownCurrency(record.currency))?;
+        Ok(Money::new(record.amount, currency))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn eur(amount: &str) -> Money {
+        Money::parse(amount, "EUR").unwrap()
+    }
+
+    #[test]
+    fn arithmetic_is_exact_checked_and_rounds_by_policy() {
+        // 0.1 + 0.2 is exactly 0.3, unlike f64
+        assert_eq!(eur("0.1").checked_add(eur("0.2")).unwrap(), eur("0.3"));
+        let usd = Money::parse("1", "USD").unwrap();
+        assert_eq!(eur("1").checked_add(usd), Err(MoneyError::CurrencyMismatch("EUR", "USD")));
+
+        let tax = eur("2.345");
+        assert!(!tax.is_rounded());
+        assert_eq!(tax.round(Rounding::HalfEven), eur("2.34"));
+        assert_eq!(tax.round(Rounding::HalfUp), eur("2.35"));
+        assert_eq!(eur("-2.349").round(Rounding::Down), eur("-2.34"));
+        assert_eq!(eur("2.341").round(Rounding::Up), eur("2.35"));
+
+        let total = eur("19.99").checked_mul(Decimal::new(119, 2)).unwrap();
+        assert_eq!(total.to_minor(Rounding::HalfUp), Ok(2379));
+        assert_eq!(Money::from_minor(2379, total.currency()), eur("23.79"));
+        assert_eq!(Money::parse("1", "JPY").unwrap().checked_mul(Decimal::MAX).unwrap().to_minor(Rounding::HalfEven), Err(MoneyError::Overflow));
+        assert_eq!(Money::parse("1", "XXX"), Err(MoneyError::UnknownCurrency("XXX".to_string())));
+    }
+
+    #[test]
+    fn serializes_amounts_as_strings_and_validates_at_the_boundary() {
+        let price = eur("12.50");
+        assert_eq!(serde_json::to_value(price).unwrap(), json!({ "amount": "12.50", "currency": "EUR" }));
+        assert_eq!(serde_json::from_value::<Money>(json!({ "amount": "12.5", "currency": "eur" })).unwrap(), price);
+        assert!(serde_json::from_value::<Money>(json!({ "amount": 12.5, "currency": "EUR" })).is_err());
+        assert!(serde_json::from_value::<Money>(json!({ "amount": "12.5", "currency": "ZZZ" })).is_err());
+
+        assert!(validate_money_precision(&eur("12.500")).is_ok());
+        assert!(validate_money_precision(&eur("12.505")).is_err());
+        assert!(validate_money_non_negative(&eur("-0.01")).is_err());
+        assert!(validate_money_non_negative(&eur("-0")).is_ok());
+        let de = Locale::find("de-DE").unwrap();
+        assert_eq!(eur("1234.565").format(de, Rounding::HalfEven).unwrap(), "1.234,56\u{a0}€");
+    }
+}
@@ -0,0 +1,114 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Reuse counters of an `ObjectPool`
+#[derive(Debug, Clone, Serialize)]
+pub struct ObjectPoolStats {
+    pub name: &'static str,
+    /// Objects idle in the pool right now
+    pub pooled: usize,
+    pub max_pooled: usize,
+    /// Takes served from the pool
+    pub hits: u64,
+    /// Takes that had to create a new object
+    pub misses: u64,
+    /// Returned objects dropped because the pool was full or `recycle` refused them
+    pub discarded: u64,
+}
+
+/// A small pool of reusable objects such as byte buffers, so hot paths don't
+/// allocate a fresh one per request.
+///
+/// Objects are taken with `take` and handed back with `give`. `recycle`
+/// resets a returned object and can refuse it, for example a buffer that grew
+/// too large to keep around. `new` is `const`, so pools can be statics.
+pub struct ObjectPool<T> {
+    name: &'static str,
+    items: Mutex<Vec<T>>,
+    max_pooled: usize,
+    create: fn() -> T,
+    recycle: fn(&mut T) -> bool,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    discarded: AtomicU64,
+}
+
+impl<T> ObjectPool<T> {
+    pub const fn new(name: &'static str, max_pooled: usize, create: fn() -> T, recycle: fn(&mut T) -> bool) -> Self {
+        Self {
+            name,
+            items: Mutex::new(Vec::new()),
+            max_pooled,
+            create,
+            recycle,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            discarded: AtomicU64::new(0),
+        }
+    }
+
+    pub fn take(&self) -> T {
+        match self.items.lock().unwrap().pop() {
+            Some(item) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                item
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                (self.create)()
+            }
+        }
+    }
+
+    pub fn give(&self, mut item: T) {
+        if (self.recycle)(&mut item) {
+            let mut items = self.items.lock().unwrap();
+            if items.len() < self.max_pooled {
+                items.push(item);
+                return;
+            }
+        }
+        self.discarded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> ObjectPoolStats {
+        ObjectPoolStats {
+            name: self.name,
+            pooled: self.items.lock().unwrap().len(),
+            max_pooled: self.max_pooled,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            discarded: self.discarded.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keep_small(buffer: &mut Vec<u8>) -> bool {
+        buffer.clear();
+        buffer.capacity() <= 64
+    }
+
+    #[test]
+    fn objects_are_reused_up_to_the_pool_size() {
+        let pool: ObjectPool<Vec<u8>> = ObjectPool::new("test", 1, || Vec::with_capacity(16), keep_small);
+
+        let mut first = pool.take();
+        first.extend_from_slice(b"abc");
+        let second = pool.take();
+        pool.give(first);
+        pool.give(second);
+
+        let reused = pool.take();
+        assert!(reused.is_empty());
+        assert!(reused.capacity() >= 16);
+        pool.give(Vec::with_capacity(1024));
+
+        let stats = pool.stats();
+        assert_eq!((stats.hits, stats.misses, stats.discarded, stats.pooled), (1, 2, 2, 0));
+    }
+}
@@ -0,0 +1,370 @@
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method, Uri};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use super::{CpuPool, Priority};
+
+/// What a request plugin sees. Header names are lower-case; repeated headers
+/// are joined with `, `.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RequestView {
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    pub headers: BTreeMap<String, String>,
+}
+
+impl RequestView {
+    pub fn new(method: &Method, uri: &Uri, headers: &HeaderMap) -> Self {
+        Self {
+            method: method.to_string(),
+            path: uri.path().to_string(),
+            query: uri.query().map(str::to_string),
+            headers: header_map(headers),
+        }
+    }
+}
+
+fn header_map(headers: &HeaderMap) -> BTreeMap<String, String> {
+    let mut map = BTreeMap::new();
+    for (name, value) in headers {
+        let Ok(value) = value.to_str() else { continue };
+        map.entry(name.as_str().to_string())
+            .and_modify(|joined: &mut String| {
+                joined.push_str(", ");
+                joined.push_str(value);
+            })
+            .or_insert_with(|| value.to_string());
+    }
+    map
+}
+
+/// A plugin's answer. The default lets the request through unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PluginVerdict {
+    pub reject: Option<PluginRejection>,
+    /// Replaces any existing values
+    pub set_headers: BTreeMap<String, String>,
+    pub remove_headers: Vec<String>,
+}
+
+/// What a plugin sees of the response to a request it let through
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResponseView {
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+}
+
+/// Header changes a plugin makes to a response
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ResponseVerdict {
+    pub set_headers: BTreeMap<String, String>,
+    pub remove_headers: Vec<String>,
+}
+
+/// Error answered instead of running the request
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PluginRejection {
+    /// A 4xx or 5xx status; anything else is answered as 403
+    #[serde(default = "PluginRejection::default_status")]
+    pub status: u16,
+    pub code: String,
+    pub message: String,
+}
+
+impl PluginRejection {
+    fn default_status() -> u16 {
+        403
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PluginError {
+    #[error("Failed to load plugin {path}: {reason}")]
+    Load { path: String, reason: String },
+    #[error("Plugin ran out of fuel")]
+    OutOfFuel,
+    #[error("Plugin failed: {0}")]
+    Failed(String),
+    #[error("Plugin returned an invalid verdict: {0}")]
+    InvalidVerdict(String),
+}
+
+/// Inspects, and may rewrite or reject, each request before it is routed
+pub trait RequestPlugin: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn on_request(&self, request: &RequestView) -> Result<PluginVerdict, PluginError>;
+
+    /// Shape the response; by default it is left alone
+    fn on_response(&self, _request: &RequestView, _response: &ResponseView) -> Result<ResponseVerdict, PluginError> {
+        Ok(ResponseVerdict::default())
+    }
+}
+
+/// Why the chain stopped a request
+#[derive(Debug)]
+pub enum PluginRefusal {
+    Rejected { plugin: String, rejection: PluginRejection },
+    /// Only when the chain fails closed
+    Failed { plugin: String, error: PluginError },
+}
+
+/// Request plugins, run in registration order. Each one sees the headers as
+/// the plugins before it left them.
+#[derive(Default)]
+pub struct PluginChain {
+    plugins: RwLock<Vec<Arc<dyn RequestPlugin>>>,
+    /// Let requests through when a plugin fails rather than answering 500
+    fail_open: bool,
+    /// Where `run_on_pool` runs the chain, and how long it may take there
+    pool: Option<(Arc<CpuPool>, Duration)>,
+}
+
+impl PluginChain {
+    pub fn new(fail_open: bool) -> Self {
+        Self { plugins: RwLock::default(), fail_open, pool: None }
+    }
+
+    /// Run plugins on the CPU pool rather than the async worker threads.
+    /// `timeout` covers the queue wait and the whole chain.
+    pub fn with_cpu_pool(mut self, pool: Arc<CpuPool>, timeout: Duration) -> Self {
+        self.pool = Some((pool, timeout));
+        self
+    }
+
+    pub fn register(&self, plugin: Arc<dyn RequestPlugin>) {
+        self.plugins.write().unwrap().push(plugin);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.read().unwrap().is_empty()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.plugins.read().unwrap().iter().map(|plugin| plugin.name().to_string()).collect()
+    }
+
+    /// Run every plugin, applying their header changes to `headers`
+    pub fn run(&self, method: &Method, uri: &Uri, headers: &mut HeaderMap) -> Result<(), PluginRefusal> {
+        let plugins = self.plugins.read().unwrap().clone();
+        for plugin in plugins {
+            let view = RequestView::new(method, uri, headers);
+            let applied = plugin.on_request(&view).and_then(|verdict| apply(verdict, headers));
+            match applied {
+                Ok(None) => {}
+                Ok(Some(rejection)) => {
+                    return Err(PluginRefusal::Rejected { plugin: plugin.name().to_string(), rejection });
+                }
+                Err(error) if self.fail_open => {
+                    tracing::error!(plugin = plugin.name(), error = %error, "Request plugin failed; skipped (fail open)");
+                }
+                Err(error) => return Err(PluginRefusal::Failed { plugin: plugin.name().to_string(), error }),
+            }
+        }
+        Ok(())
+    }
+
+    /// Let every plugin shape the response, in the same order. The request
+    /// was served already, so a failing plugin is logged and skipped.
+    pub fn run_response(&self, request: &RequestView, status: u16, headers: &mut HeaderMap) {
+        let plugins = self.plugins.read().unwrap().clone();
+        for plugin in plugins {
+            let view = ResponseView { status, headers: header_map(headers) };
+            let applied = plugin
+                .on_response(request, &view)
+                .and_then(|verdict| apply_headers(&verdict.set_headers, &verdict.remove_headers, headers));
+            if let Err(error) = applied {
+                tracing::error!(plugin = plugin.name(), error = %error, "Response plugin failed; skipped");
+            }
+        }
+    }
+
+    /// `run` on the CPU pool, or inline without one. A chain that doesn't
+    /// finish in time counts as a failed plugin.
+    pub async fn run_on_pool(self: &Arc<Self>, method: &Method, uri: &Uri, headers: &mut HeaderMap) -> Result<(), PluginRefusal> {
+        let Some((pool, timeout)) = self.pool.clone() else {
+            return self.run(method, uri, headers);
+        };
+        let (chain, method, uri, mut scratch) = (self.clone(), method.clone(), uri.clone(), headers.clone());
+        let ran = pool
+            .run(Priority::Interactive, "request_plugins", timeout, move || {
+                chain.run(&method, &uri, &mut scratch).map(|()| scratch)
+            })
+            .await;
+        match ran {
+            Ok(Ok(rewritten)) => {
+                *headers = rewritten;
+                Ok(())
+            }
+            Ok(Err(refusal)) => Err(refusal),
+            Err(err) if self.fail_open => {
+                tracing::error!(error = %err, "Request plugins did not run; skipped (fail open)");
+                Ok(())
+            }
+            Err(err) => Err(PluginRefusal::Failed { plugin: "request_plugins".to_string(), error: PluginError::Failed(err.to_string()) }),
+        }
+    }
+
+    /// `run_response` on the CPU pool, or inline without one. Past the
+    /// timeout the response goes out with its headers unshaped.
+    pub async fn run_response_on_pool(self: &Arc<Self>, request: RequestView, status: u16, headers: &mut HeaderMap) {
+        let Some((pool, timeout)) = self.pool.clone() else {
+            return self.run_response(&request, status, headers);
+        };
+        let (chain, mut scratch) = (self.clone(), headers.clone());
+        let ran = pool
+            .run(Priority::Interactive, "response_plugins", timeout, move || {
+                chain.run_response(&request, status, &mut scratch);
+                scratch
+            })
+            .await;
+        match ran {
+            Ok(shaped) => *headers = shaped,
+            Err(err) => tracing::error!(error = %err, "Response plugins did not run; skipped"),
+        }
+    }
+}
+
+fn apply(verdict: PluginVerdict, headers: &mut HeaderMap) -> Result<Option<PluginRejection>, PluginError> {
+    if let Some(mut rejection) = verdict.reject {
+        if !(400..=599).contains(&rejection.status) {
+            rejection.status = 403;
+        }
+        return Ok(Some(rejection));
+    }
+    apply_headers(&verdict.set_headers, &verdict.remove_headers, headers)?;
+    Ok(None)
+}
+
+/// Changes are applied only when every name and value is valid
+fn apply_headers(
+    set_headers: &BTreeMap<String, String>,
+    remove_headers: &[String],
+    headers: &mut HeaderMap,
+) -> Result<(), PluginError> {
+    let invalid = |err: &dyn std::fmt::Display| PluginError::InvalidVerdict(err.to_string());
+    let mut set = Vec::with_capacity(set_headers.len());
+    for (name, value) in set_headers {
+        set.push((
+            HeaderName::try_from(name.as_str()).map_err(|err| invalid(&err))?,
+            HeaderValue::try_from(value.as_str()).map_err(|err| invalid(&err))?,
+        ));
+    }
+    let mut remove = Vec::with_capacity(remove_headers.len());
+    for name in remove_headers {
+        remove.push(HeaderName::try_from(name.as_str()).map_err(|err| invalid(&err))?);
+    }
+
+    for name in remove {
+        headers.remove(name);
+    }
+    for (name, value) in set {
+        headers.insert(name, value);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rejects requests without `x-tenant`, then tags the rest
+    struct TenantRequired;
+
+    impl RequestPlugin for TenantRequired {
+        fn name(&self) -> &str {
+            "tenant_required"
+        }
+
+        fn on_request(&self, request: &RequestView) -> Result<PluginVerdict, PluginError> {
+            if !request.headers.contains_key("x-tenant") {
+                let rejection = PluginRejection { status: 200, code: "TENANT_REQUIRED".into(), message: "No tenant".into() };
+                return Ok(PluginVerdict { reject: Some(rejection), ..PluginVerdict::default() });
+            }
+            let mut verdict = PluginVerdict { remove_headers: vec!["x-debug".into()], ..PluginVerdict::default() };
+            verdict.set_headers.insert("x-tenant-checked".into(), "1".into());
+            Ok(verdict)
+        }
+    }
+
+    /// Fails unless an earlier plugin tagged the request
+    struct NeedsTag;
+
+    impl RequestPlugin for NeedsTag {
+        fn name(&self) -> &str {
+            "needs_tag"
+        }
+
+        fn on_request(&self, request: &RequestView) -> Result<PluginVerdict, PluginError> {
+            match request.headers.get("x-tenant-checked") {
+                Some(_) => Ok(PluginVerdict::default()),
+                None => Err(PluginError::Failed("untagged".into())),
+            }
+        }
+    }
+
+    #[test]
+    fn plugins_run_in_order_on_the_headers_left_by_the_previous_one() {
+        let chain = PluginChain::new(false);
+        chain.register(Arc::new(TenantRequired));
+        chain.register(Arc::new(NeedsTag));
+        let (method, uri) = (Method::POST, Uri::from_static("/api/users?x=1"));
+
+        let mut headers = HeaderMap::new();
+        match chain.run(&method, &uri, &mut headers) {
+            Err(PluginRefusal::Rejected { plugin, rejection }) => {
+                assert_eq!(plugin, "tenant_required");
+                assert_eq!((rejection.status, rejection.code.as_str()), (403, "TENANT_REQUIRED"));
+            }
+            other => panic!("expected a rejection, got {other:?}"),
+        }
+
+        headers.insert("x-tenant", HeaderValue::from_static("acme"));
+        headers.insert("x-debug", HeaderValue::from_static("1"));
+        chain.run(&method, &uri, &mut headers).unwrap();
+        assert_eq!(headers["x-tenant-checked"], "1");
+        assert!(headers.get("x-debug").is_none());
+
+        // Alone, the second plugin fails; closed by default, skipped when open
+        let closed = PluginChain::new(false);
+        closed.register(Arc::new(NeedsTag));
+        assert!(matches!(closed.run(&method, &uri, &mut HeaderMap::new()), Err(PluginRefusal::Failed { .. })));
+        let open = PluginChain::new(true);
+        open.register(Arc::new(NeedsTag));
+        assert!(open.run(&method, &uri, &mut HeaderMap::new()).is_ok());
+    }
+
+    /// Records the thread it ran on
+    struct ThreadSpy(std::sync::Mutex<Option<String>>);
+
+    impl RequestPlugin for ThreadSpy {
+        fn name(&self) -> &str {
+            "thread_spy"
+        }
+
+        fn on_request(&self, _request: &RequestView) -> Result<PluginVerdict, PluginError> {
+            *self.0.lock().unwrap() = std::thread::current().name().map(str::to_string);
+            let mut verdict = PluginVerdict::default();
+            verdict.set_headers.insert("x-checked".into(), "1".into());
+            Ok(verdict)
+        }
+    }
+
+    #[tokio::test]
+    async fn plugins_run_on_the_cpu_pool_and_hand_their_headers_back() {
+        let pool = Arc::new(CpuPool::new(1, 4, 4));
+        let spy = Arc::new(ThreadSpy(std::sync::Mutex::new(None)));
+        let chain = Arc::new(PluginChain::new(false).with_cpu_pool(pool, Duration::from_secs(5)));
+        chain.register(spy.clone());
+
+        let mut headers = HeaderMap::new();
+        chain.run_on_pool(&Method::GET, &Uri::from_static("/api/users"), &mut headers).await.unwrap();
+        assert_eq!(headers["x-checked"], "1");
+        assert!(spy.0.lock().unwrap().as_deref().is_some_and(|name| name.starts_with("cpu-pool-")));
+    }
+}
@@ -0,0 +1,188 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+// tokio's clock so tests can drive windows with paused time
+use tokio::time::Instant;
+
+const WINDOW: Duration = Duration::from_secs(60);
+/// Client windows tracked at once before expired ones are swept
+const MAX_CLIENTS: usize = 100_000;
+/// Clients listed in a report, most limited first
+const REPORTED_CLIENTS: usize = 100;
+
+/// Window start and requests counted, keyed by bucket and client
+type Windows = HashMap<(&'static str, String), (Instant, u32)>;
+
+/// What happens to a request over its budget
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitMode {
+    /// Rejected with 429
+    #[default]
+    Enforce,
+    /// Served, with a warning header and a log line, so limits can be tuned
+    /// against real traffic before they are enforced
+    Shadow,
+}
+
+impl RateLimitMode {
+    /// `shadow`, or `enforce` for anything else
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "shadow" => Self::Shadow,
+            _ => Self::Enforce,
+        }
+    }
+}
+
+/// How often one client went over one bucket's budget
+#[derive(Debug, Clone, Serialize)]
+pub struct LimitedClient {
+    pub bucket: &'static str,
+    pub client: String,
+    pub limited: u64,
+    pub last_limited_at: DateTime<Utc>,
+}
+
+/// The limiter's mode and the clients that went over budget most often
+#[derive(Debug, Clone, Serialize)]
+pub struct RateLimitReport {
+    pub mode: RateLimitMode,
+    pub clients: Vec<LimitedClient>,
+}
+
+/// Fixed one-minute request budgets per named bucket and client.
+///
+/// Buckets without a limit, or with a limit of 0, are unlimited. Every request
+/// over budget is counted per bucket and client, in either mode, for
+/// `report`.
+#[derive(Default)]
+pub struct RateLimiter {
+    limits: HashMap<&'static str, u32>,
+    mode: RateLimitMode,
+    windows: Mutex<Windows>,
+    limited: Mutex<HashMap<(&'static str, String), LimitedClient>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limit(mut self, bucket: &'static str, per_minute: u32) -> Self {
+        self.limits.insert(bucket, per_minute);
+        self
+    }
+
+    pub fn with_mode(mut self, mode: RateLimitMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn mode(&self) -> RateLimitMode {
+        self.mode
+    }
+
+    /// Requests per minute allowed in `bucket`; `None` when unlimited
+    pub fn limit(&self, bucket: &str) -> Option<u32> {
+        self.limits.get(bucket).copied().filter(|limit| *limit > 0)
+    }
+
+    /// Clients that went over budget most often, across all buckets
+    pub fn report(&self) -> RateLimitReport {
+        let mut clients: Vec<_> = self.limited.lock().unwrap().values().cloned().collect();
+        clients.sort_by(|a, b| b.limited.cmp(&a.limited).then(b.last_limited_at.cmp(&a.last_limited_at)));
+        clients.truncate(REPORTED_CLIENTS);
+        RateLimitReport { mode: self.mode, clients }
+    }
+
+    fn record_limited(&self, bucket: &'static str, client: &str) {
+        let mut limited = self.limited.lock().unwrap();
+        let key = (bucket, client.to_string());
+        // Past the cap only clients already listed keep counting
+        if limited.len() >= MAX_CLIENTS && !limited.contains_key(&key) {
+            return;
+        }
+        let entry = limited.entry(key).or_insert_with(|| LimitedClient {
+            bucket,
+            client: client.to_string(),
+            limited: 0,
+            last_limited_at: Utc::now(),
+        });
+        entry.limited += 1;
+        entry.last_limited_at = Utc::now();
+    }
+
+    /// Count a request; `Err` carries how long until the client's window
+    /// resets. In shadow mode the caller serves the request anyway.
+    pub fn check(&self, bucket: &'static str, client: &str) -> Result<(), Duration> {
+        let Some(&limit) = self.limits.get(bucket).filter(|limit| **limit > 0) else {
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        if windows.len() >= MAX_CLIENTS {
+            windows.retain(|_, (started, _)| now.duration_since(*started) < WINDOW);
+        }
+
+        let (started, count) = windows
+            .entry((bucket, client.to_string()))
+            .or_insert((now, 0));
+        if now.duration_since(*started) >= WINDOW {
+            *started = now;
+            *count = 0;
+        }
+        if *count >= limit {
+            let retry_after = WINDOW - now.duration_since(*started);
+            drop(windows);
+            self.record_limited(bucket, client);
+            return Err(retry_after);
+        }
+        *count += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn budgets_are_per_bucket_and_client_and_reset_each_minute() {
+        let limiter = RateLimiter::new().with_limit("write", 2).with_limit("read", 0);
+
+        assert!(limiter.check("write", "10.0.0.1").is_ok());
+        assert!(limiter.check("write", "10.0.0.1").is_ok());
+        assert_eq!(limiter.check("write", "10.0.0.1"), Err(Duration::from_secs(60)));
+        assert!(limiter.check("write", "10.0.0.2").is_ok());
+        assert!((0..100).all(|_| limiter.check("read", "10.0.0.1").is_ok()));
+        assert!(limiter.check("unknown", "10.0.0.1").is_ok());
+
+        tokio::time::advance(Duration::from_secs(45)).await;
+        assert_eq!(limiter.check("write", "10.0.0.1"), Err(Duration::from_secs(15)));
+        tokio::time::advance(Duration::from_secs(15)).await;
+        assert!(limiter.check("write", "10.0.0.1").is_ok());
+    }
+
+    #[test]
+    fn over_budget_clients_are_reported_most_limited_first() {
+        let limiter = RateLimiter::new().with_limit("write", 1).with_mode(RateLimitMode::Shadow);
+        for _ in 0..4 {
+            let _ = limiter.check("write", "10.0.0.1");
+        }
+        for _ in 0..2 {
+            let _ = limiter.check("write", "10.0.0.2");
+        }
+
+        let report = limiter.report();
+        assert_eq!(report.mode, RateLimitMode::Shadow);
+        let counts: Vec<_> = report.clients.iter().map(|client| (client.client.as_str(), client.limited)).collect();
+        assert_eq!(counts, vec![("10.0.0.1", 3), ("10.0.0.2", 1)]);
+        assert_eq!(limiter.limit("write"), Some(1));
+        assert_eq!(limiter.limit("read"), None);
+        assert_eq!(RateLimitMode::parse("enforce"), RateLimitMode::Enforce);
+    }
+}
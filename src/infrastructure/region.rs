@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+
+/// What to do with a request pinned to another region
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionPinMode {
+    /// Answer 421 so the client retries against the right region
+    Reject,
+    /// Answer 307 to the pinned region's base URL, when it is known
+    Redirect,
+}
+
+impl RegionPinMode {
+    pub fn from_name(name: &str) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "redirect" => RegionPinMode::Redirect,
+            _ => RegionPinMode::Reject,
+        }
+    }
+}
+
+/// Outcome of checking a request's region pin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PinDecision {
+    /// Unpinned, or pinned to this region
+    Local,
+    /// Full URL of the same path and query in the pinned region
+    Redirect(String),
+    Misdirected { pinned: String },
+}
+
+/// The region this instance serves, and the base URLs of its peers for
+/// active-active deployments where a client may pin itself to one region
+#[derive(Debug, Clone)]
+pub struct RegionRouter {
+    pub region: String,
+    mode: RegionPinMode,
+    peers: BTreeMap<String, String>,
+}
+
+impl RegionRouter {
+    pub fn new(region: impl Into<String>, mode: RegionPinMode) -> Self {
+        Self { region: region.into(), mode, peers: BTreeMap::new() }
+    }
+
+    /// Peers as `name=base_url` pairs separated by commas, e.g.
+    /// `eu-west=https://eu.api.example.com,us-east=https://us.api.example.com`.
+    /// Malformed pairs are skipped with a warning.
+    pub fn with_peers(mut self, peers: &str) -> Self {
+        for pair in peers.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+            match pair.split_once('=') {
+                Some((name, url)) if !name.trim().is_empty() && url.trim().starts_with("http") => {
+                    self.peers.insert(name.trim().to_ascii_lowercase(), url.trim().trim_end_matches('/').to_string());
+                }
+                _ => tracing::warn!(peer = pair, "Ignoring malformed REGION_PEERS entry"),
+            }
+        }
+        self
+    }
+
+    pub fn peers(&self) -> impl Iterator<Item = &str> {
+        self.peers.keys().map(String::as_str)
+    }
+
+    /// Region names compare case-insensitively
+    pub fn decide(&self, pin: Option<&str>, path_and_query: &str) -> PinDecision {
+        let Some(pinned) = pin.map(str::trim).filter(|pin| !pin.is_empty()) else {
+            return PinDecision::Local;
+        };
+        if pinned.eq_ignore_ascii_case(&self.region) {
+            return PinDecision::Local;
+        }
+        let peer = self.peers.get(&pinned.to_ascii_lowercase());
+        match (self.mode, peer) {
+            (RegionPinMode::Redirect, Some(base)) => PinDecision::Redirect(format!("{base}{path_and_query}")),
+            _ => PinDecision::Misdirected { pinned: pinned.to_string() },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pins_to_other_regions_are_redirected_to_known_peers_or_rejected() {
+        let peers = "eu-west=https://eu.example.com/, bogus, us-east=https://us.example.com";
+        let router = RegionRouter::new("us-east", RegionPinMode::Redirect).with_peers(peers);
+        assert_eq!(router.peers().collect::<Vec<_>>(), ["eu-west", "us-east"]);
+
+        assert_eq!(router.decide(None, "/api/users"), PinDecision::Local);
+        assert_eq!(router.decide(Some("US-East"), "/api/users"), PinDecision::Local);
+        assert_eq!(
+            router.decide(Some("eu-west"), "/api/users?page=2"),
+            PinDecision::Redirect("https://eu.example.com/api/users?page=2".into())
+        );
+        assert_eq!(router.decide(Some("ap-south"), "/"), PinDecision::Misdirected { pinned: "ap-south".into() });
+
+        let rejecting = RegionRouter::new("us-east", RegionPinMode::Reject).with_peers(peers);
+        assert!(matches!(rejecting.decide(Some("eu-west"), "/"), PinDecision::Misdirected { .. }));
+    }
+}
@@ -0,0 +1,358 @@
+use async_trait::async_trait;
+use std::marker::PhantomData;
+use std::sync::{Arc, OnceLock};
+
+use super::{Compatibility, RegistrySchema, SchemaRegistry, SchemaRegistryError};
+use crate::container::startup::StartupComponent;
+
+/// First byte of the Confluent wire format, before the schema id
+const WIRE_MAGIC: u8 = 0;
+
+/// A payload with an Avro and a Protobuf encoding the schema registry can hold
+pub trait RegistryPayload: Send + Sync + 'static {
+    /// Avro record schema, as JSON
+    const AVRO_SCHEMA: &'static str;
+    /// proto3 file whose first message is the payload
+    const PROTOBUF_SCHEMA: &'static str;
+
+    /// Append the Avro binary encoding of the record in `AVRO_SCHEMA`
+    fn encode_avro(&self, out: &mut Vec<u8>);
+
+    /// Append the Protobuf encoding of the first message in `PROTOBUF_SCHEMA`
+    fn encode_protobuf(&self, out: &mut Vec<u8>);
+}
+
+/// Binary encodings with a schema the registry can hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadFormat {
+    Avro,
+    Protobuf,
+}
+
+impl PayloadFormat {
+    /// `avro` or `protobuf`; anything else means JSON only
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "avro" => Some(PayloadFormat::Avro),
+            "protobuf" => Some(PayloadFormat::Protobuf),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PayloadFormat::Avro => "avro",
+            PayloadFormat::Protobuf => "protobuf",
+        }
+    }
+
+    fn schema<T: RegistryPayload>(self) -> RegistrySchema {
+        match self {
+            PayloadFormat::Avro => RegistrySchema { schema_type: "AVRO", schema: T::AVRO_SCHEMA.to_string() },
+            PayloadFormat::Protobuf => RegistrySchema { schema_type: "PROTOBUF", schema: T::PROTOBUF_SCHEMA.to_string() },
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RegistrationError {
+    #[error("schema is incompatible with the latest version of `{subject}`: {}", reasons.join("; "))]
+    Incompatible { subject: String, reasons: Vec<String> },
+    #[error(transparent)]
+    Registry(#[from] SchemaRegistryError),
+}
+
+/// Writes payloads for consumers outside this service in an Avro or
+/// Protobuf format, framed as the Confluent wire format: a zero byte, the
+/// schema id (big endian), then the encoded payload. Until the schema is
+/// registered, or with no format at all, `encode` returns `None` and callers
+/// fall back to JSON.
+pub struct RegistrySerializer<T> {
+    format: Option<PayloadFormat>,
+    subject: String,
+    registry: Option<Arc<dyn SchemaRegistry>>,
+    schema_id: OnceLock<u32>,
+    payload: PhantomData<fn(&T)>,
+}
+
+impl<T: RegistryPayload> RegistrySerializer<T> {
+    /// JSON only
+    pub fn json() -> Self {
+        Self::new(None, "")
+    }
+
+    /// Registers the schema of `format` under `subject` once `register` is called
+    pub fn new(format: Option<PayloadFormat>, subject: &str) -> Self {
+        Self { format, subject: subject.to_string(), registry: None, schema_id: OnceLock::new(), payload: PhantomData }
+    }
+
+    /// `avro` or `protobuf`; anything else is JSON only
+    pub fn from_name(name: &str, subject: &str) -> Self {
+        Self::new(PayloadFormat::from_name(name), subject)
+    }
+
+    pub fn with_registry(mut self, registry: Arc<dyn SchemaRegistry>) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// The format payloads are written in right now: `json` until a schema id is known
+    pub fn format_name(&self) -> &'static str {
+        match (self.format, self.schema_id()) {
+            (Some(format), Some(_)) => format.name(),
+            _ => "json",
+        }
+    }
+
+    /// Whether payloads are meant to be written in a registry format, once registered
+    pub fn uses_registry(&self) -> bool {
+        self.format.is_some() && self.registry.is_some()
+    }
+
+    pub fn schema_id(&self) -> Option<u32> {
+        self.schema_id.get().copied()
+    }
+
+    pub fn encode(&self, payload: &T) -> Option<Vec<u8>> {
+        let (format, schema_id) = (self.format?, self.schema_id()?);
+        let mut out = vec![WIRE_MAGIC];
+        out.extend_from_slice(&schema_id.to_be_bytes());
+        match format {
+            PayloadFormat::Avro => payload.encode_avro(&mut out),
+            PayloadFormat::Protobuf => {
+                // The message indexes: `[0]`, the first message in the schema, written as a lone 0
+                out.push(0);
+                payload.encode_protobuf(&mut out);
+            }
+        }
+        Some(out)
+    }
+
+    /// Checks that the schema is a compatible evolution of the subject's
+    /// latest version, then registers it so payloads carry its id. Done once;
+    /// later calls return the id. `None` without a format or a registry.
+    pub async fn register(&self) -> Result<Option<u32>, RegistrationError> {
+        if let Some(id) = self.schema_id() {
+            return Ok(Some(id));
+        }
+        let (Some(format), Some(registry)) = (self.format, &self.registry) else {
+            return Ok(None);
+        };
+        let (subject, schema) = (self.subject.as_str(), format.schema::<T>());
+        if let Compatibility::Incompatible(reasons) = registry.check_compatibility(subject, &schema).await? {
+            return Err(RegistrationError::Incompatible { subject: subject.to_string(), reasons });
+        }
+        let id = registry.register(subject, &schema).await?;
+        tracing::info!(subject, schema_id = id, format = format.name(), "Schema registered");
+        Ok(Some(*self.schema_id.get_or_init(|| id)))
+    }
+}
+
+#[cfg(test)]
+impl<T: RegistryPayload> RegistrySerializer<T> {
+    /// As if registered under `schema_id`
+    pub(crate) fn with_schema_id(self, schema_id: u32) -> Self {
+        let _ = self.schema_id.set(schema_id);
+        self
+    }
+}
+
+/// Registers a serializer's schema at startup. An incompatible schema stops
+/// startup; an unreachable registry only leaves payloads in JSON until the
+/// next restart.
+pub struct SchemaRegistration<T> {
+    name: &'static str,
+    serializer: Arc<RegistrySerializer<T>>,
+}
+
+impl<T: RegistryPayload> SchemaRegistration<T> {
+    pub fn new(name: &'static str, serializer: Arc<RegistrySerializer<T>>) -> Self {
+        Self { name, serializer }
+    }
+}
+
+#[async_trait]
+impl<T: RegistryPayload> StartupComponent for SchemaRegistration<T> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    async fn start(&self) -> Result<(), String> {
+        match self.serializer.register().await {
+            Ok(_) => Ok(()),
+            Err(err @ RegistrationError::Incompatible { .. }) => Err(err.to_string()),
+            Err(RegistrationError::Registry(err)) => {
+                tracing::warn!(subject = %self.serializer.subject, error = %err, "Schema registry unavailable; payloads fall back to JSON");
+                Ok(())
+            }
+        }
+    }
+}
+
+pub fn avro_long(value: i64, out: &mut Vec<u8>) {
+    varint(((value << 1) ^ (value >> 63)) as u64, out);
+}
+
+pub fn avro_string(value: &str, out: &mut Vec<u8>) {
+    avro_long(value.len() as i64, out);
+    out.extend_from_slice(value.as_bytes());
+}
+
+/// A varint field (`uint64`, `int64`, ...); proto3 leaves out zeros
+pub fn protobuf_varint(field: u64, value: u64, out: &mut Vec<u8>) {
+    if value != 0 {
+        varint(field << 3, out);
+        varint(value, out);
+    }
+}
+
+/// A `string` field; proto3 leaves out empty strings
+pub fn protobuf_string(field: u64, value: &str, out: &mut Vec<u8>) {
+    if !value.is_empty() {
+        varint(field << 3 | 2, out);
+        varint(value.len() as u64, out);
+        out.extend_from_slice(value.as_bytes());
+    }
+}
+
+fn varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Reads an Avro-encoded `T`, as written by `RegistrySerializer`, back into
+/// JSON by walking `T::AVRO_SCHEMA` as parsed by the `avro-schema` crate, so
+/// field order and types come from the schema rather than from the encoder
+#[cfg(test)]
+pub(crate) fn decode_avro<T: RegistryPayload>(framed: &[u8]) -> serde_json::Value {
+    use avro_schema::schema::Schema;
+    use serde_json::{json, Value};
+
+    fn long(bytes: &mut &[u8]) -> i64 {
+        let (mut value, mut shift) = (0u64, 0);
+        loop {
+            let (byte, rest) = bytes.split_first().expect("truncated varint");
+            *bytes = rest;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return (value >> 1) as i64 ^ -((value & 1) as i64);
+            }
+            shift += 7;
+        }
+    }
+
+    fn read(schema: &Schema, bytes: &mut &[u8]) -> Value {
+        match schema {
+            Schema::Long(_) => json!(long(bytes)),
+            Schema::String(_) => {
+                let length = long(bytes) as usize;
+                let (text, rest) = bytes.split_at(length);
+                *bytes = rest;
+                json!(std::str::from_utf8(text).unwrap())
+            }
+            Schema::Array(items) => {
+                let mut values = Vec::new();
+                loop {
+                    match long(bytes) {
+                        0 => return Value::Array(values),
+                        count => (0..count).for_each(|_| values.push(read(items, bytes))),
+                    }
+                }
+            }
+            Schema::Record(record) => record.fields.iter().map(|field| (field.name.clone(), read(&field.schema, bytes))).collect(),
+            other => panic!("no reader for {other:?}"),
+        }
+    }
+
+    let schema: Schema = serde_json::from_str(T::AVRO_SCHEMA).expect("a valid Avro schema");
+    let (header, mut payload) = framed.split_at(5);
+    assert_eq!(header[0], WIRE_MAGIC);
+    let value = read(&schema, &mut payload);
+    assert!(payload.is_empty(), "{} bytes left over", payload.len());
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    struct Ping(u64);
+
+    impl RegistryPayload for Ping {
+        const AVRO_SCHEMA: &'static str = r#"{"type":"record","name":"Ping","fields":[{"name":"n","type":"long"}]}"#;
+        const PROTOBUF_SCHEMA: &'static str = "syntax = \"proto3\";\nmessage Ping { uint64 n = 1; }\n";
+
+        fn encode_avro(&self, out: &mut Vec<u8>) {
+            avro_long(self.0 as i64, out);
+        }
+
+        fn encode_protobuf(&self, out: &mut Vec<u8>) {
+            protobuf_varint(1, self.0, out);
+        }
+    }
+
+    /// Answers compatibility checks with `compatibility`, or fails every call when `None`
+    struct FakeRegistry {
+        compatibility: Option<Compatibility>,
+        registered: Mutex<Vec<RegistrySchema>>,
+    }
+
+    #[async_trait]
+    impl SchemaRegistry for FakeRegistry {
+        async fn check_compatibility(&self, _subject: &str, _schema: &RegistrySchema) -> Result<Compatibility, SchemaRegistryError> {
+            self.compatibility.clone().ok_or_else(|| SchemaRegistryError::Rejected {
+                status: reqwest::StatusCode::SERVICE_UNAVAILABLE,
+                message: String::new(),
+            })
+        }
+
+        async fn register(&self, _subject: &str, schema: &RegistrySchema) -> Result<u32, SchemaRegistryError> {
+            self.registered.lock().unwrap().push(schema.clone());
+            Ok(42)
+        }
+    }
+
+    async fn start(format: &str, compatibility: Option<Compatibility>) -> (Arc<RegistrySerializer<Ping>>, Arc<FakeRegistry>, Result<(), String>) {
+        let registry = Arc::new(FakeRegistry { compatibility, registered: Mutex::new(Vec::new()) });
+        let serializer = Arc::new(RegistrySerializer::from_name(format, "pings-value").with_registry(registry.clone()));
+        let started = SchemaRegistration::new("ping_schemas", serializer.clone()).start().await;
+        (serializer, registry, started)
+    }
+
+    #[test]
+    fn payloads_are_framed_with_the_schema_id() {
+        let avro = RegistrySerializer::from_name("avro", "pings-value");
+        assert_eq!(avro.encode(&Ping(300)), None);
+        let avro = avro.with_schema_id(42);
+        assert_eq!(avro.encode(&Ping(300)).unwrap(), [0, 0, 0, 0, 42, 0xd8, 0x04]);
+        let protobuf = RegistrySerializer::from_name("protobuf", "pings-value").with_schema_id(7);
+        assert_eq!(protobuf.encode(&Ping(300)).unwrap(), [0, 0, 0, 0, 7, 0, 0x08, 0xac, 0x02]);
+        assert_eq!(protobuf.encode(&Ping(0)).unwrap(), [0, 0, 0, 0, 7, 0]);
+        assert_eq!(RegistrySerializer::<Ping>::from_name("json", "pings-value").with_schema_id(1).encode(&Ping(1)), None);
+    }
+
+    #[tokio::test]
+    async fn startup_registers_compatible_schemas_and_refuses_breaking_ones() {
+        let (serializer, registry, started) = start("avro", Some(Compatibility::NewSubject)).await;
+        assert!(started.is_ok());
+        assert_eq!((serializer.schema_id(), serializer.format_name()), (Some(42), "avro"));
+        assert_eq!(registry.registered.lock().unwrap()[0].schema_type, "AVRO");
+        assert_eq!(serializer.register().await.unwrap(), Some(42));
+        assert_eq!(registry.registered.lock().unwrap().len(), 1);
+
+        let breaking = Compatibility::Incompatible(vec!["READER_FIELD_MISSING_DEFAULT_VALUE".to_string()]);
+        let (serializer, registry, started) = start("protobuf", Some(breaking)).await;
+        assert!(started.unwrap_err().contains("READER_FIELD_MISSING_DEFAULT_VALUE"));
+        assert!(registry.registered.lock().unwrap().is_empty());
+        assert_eq!(serializer.schema_id(), None);
+
+        let (serializer, _, started) = start("avro", None).await;
+        assert!(started.is_ok());
+        assert_eq!((serializer.schema_id(), serializer.format_name()), (None, "json"));
+        assert!(matches!(serializer.register().await, Err(RegistrationError::Registry(_))));
+    }
+}
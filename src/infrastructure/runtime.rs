@@ -0,0 +1,80 @@
+use std::io;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::config::Config;
+
+/// Tokio runtime tuning, built by hand in `main` instead of `#[tokio::main]`
+/// so thread counts can follow the instance size. A zero keeps tokio's
+/// default for that setting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeSettings {
+    /// Async worker threads; tokio defaults to one per core
+    pub worker_threads: usize,
+    /// Upper bound on `spawn_blocking` threads; tokio defaults to 512
+    pub max_blocking_threads: usize,
+    pub thread_name: String,
+    /// Scheduler ticks between polls of the I/O and timer drivers; tokio
+    /// defaults to 61. Lower favours I/O latency, higher favours throughput
+    /// of busy tasks.
+    pub event_interval: u32,
+}
+
+impl RuntimeSettings {
+    /// Under the supervisor, an unset worker count splits the cores between
+    /// the server processes
+    pub fn from_config(config: &Config) -> Self {
+        let worker_threads = match config.runtime_worker_threads {
+            0 if config.server_processes > 1 => {
+                let cores = std::thread::available_parallelism().map(usize::from).unwrap_or(1);
+                (cores / config.server_processes).max(1)
+            }
+            threads => threads,
+        };
+        Self {
+            worker_threads,
+            max_blocking_threads: config.runtime_max_blocking_threads,
+            thread_name: config.runtime_thread_name.clone(),
+            event_interval: config.runtime_event_interval,
+        }
+    }
+
+    pub fn build(&self) -> io::Result<Runtime> {
+        let mut builder = Builder::new_multi_thread();
+        builder.enable_all();
+        if self.worker_threads > 0 {
+            builder.worker_threads(self.worker_threads);
+        }
+        if self.max_blocking_threads > 0 {
+            builder.max_blocking_threads(self.max_blocking_threads);
+        }
+        if !self.thread_name.is_empty() {
+            builder.thread_name(self.thread_name.clone());
+        }
+        if self.event_interval > 0 {
+            builder.event_interval(self.event_interval);
+        }
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runtime_follows_the_settings() {
+        let settings = RuntimeSettings {
+            worker_threads: 2,
+            max_blocking_threads: 4,
+            thread_name: "test-worker".to_string(),
+            event_interval: 31,
+        };
+        let runtime = settings.build().unwrap();
+        assert_eq!(runtime.metrics().num_workers(), 2);
+
+        let name = runtime.block_on(async {
+            tokio::spawn(async { std::thread::current().name().map(str::to_string) }).await.unwrap()
+        });
+        assert_eq!(name.as_deref(), Some("test-worker"));
+    }
+}
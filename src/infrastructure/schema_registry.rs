@@ -0,0 +1,171 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+/// Content type of the Confluent Schema Registry API
+const REGISTRY_CONTENT_TYPE: &str = "application/vnd.schemaregistry.v1+json";
+
+/// Error code the registry answers with for a subject it has no versions of
+const SUBJECT_NOT_FOUND: u32 = 40401;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SchemaRegistryError {
+    #[error("Schema registry request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("Schema registry rejected the request with status {status}: {message}")]
+    Rejected { status: reqwest::StatusCode, message: String },
+    #[error("Schema registry URL `{0}` cannot take a path")]
+    InvalidUrl(String),
+}
+
+/// A schema as the registry stores it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistrySchema {
+    /// `AVRO` or `PROTOBUF`
+    pub schema_type: &'static str,
+    pub schema: String,
+}
+
+/// Whether a schema can replace the latest version of a subject
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Compatibility {
+    Compatible,
+    /// The subject has no versions yet, so anything goes
+    NewSubject,
+    /// Why not, as the registry explains it
+    Incompatible(Vec<String>),
+}
+
+/// Registers schemas and checks their evolution under the subject's
+/// compatibility level
+#[async_trait]
+pub trait SchemaRegistry: Send + Sync {
+    async fn check_compatibility(&self, subject: &str, schema: &RegistrySchema) -> Result<Compatibility, SchemaRegistryError>;
+
+    /// The schema's id, registering it as the subject's next version when it
+    /// is new. Registering a schema the subject already has returns its id.
+    async fn register(&self, subject: &str, schema: &RegistrySchema) -> Result<u32, SchemaRegistryError>;
+}
+
+/// Confluent Schema Registry, or anything speaking its REST API
+/// (Redpanda, Apicurio in compatibility mode, ...)
+pub struct ConfluentSchemaRegistry {
+    http: reqwest::Client,
+    base_url: String,
+    /// `user:password` for HTTP basic auth, e.g. a Confluent Cloud API key
+    credentials: Option<(String, String)>,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    error_code: u32,
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct CompatibilityBody {
+    is_compatible: bool,
+    #[serde(default)]
+    messages: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RegisteredBody {
+    id: u32,
+}
+
+impl ConfluentSchemaRegistry {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            credentials: None,
+        }
+    }
+
+    /// `user:password`; empty leaves requests unauthenticated
+    pub fn with_credentials(mut self, credentials: &str) -> Self {
+        self.credentials = credentials
+            .split_once(':')
+            .map(|(user, password)| (user.to_string(), password.to_string()));
+        self
+    }
+
+    /// `segments` appended to the base URL, each percent-encoded, so a
+    /// subject holding `/`, `?` or `%` stays one path segment
+    fn endpoint(&self, segments: &[&str]) -> Result<reqwest::Url, SchemaRegistryError> {
+        let invalid = || SchemaRegistryError::InvalidUrl(self.base_url.clone());
+        let mut url = reqwest::Url::parse(&self.base_url).map_err(|_| invalid())?;
+        url.path_segments_mut().map_err(|_| invalid())?.pop_if_empty().extend(segments);
+        Ok(url)
+    }
+
+    async fn post(&self, url: reqwest::Url, schema: &RegistrySchema) -> Result<reqwest::Response, SchemaRegistryError> {
+        let mut request = self
+            .http
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, REGISTRY_CONTENT_TYPE)
+            .json(&json!({ "schemaType": schema.schema_type, "schema": schema.schema }));
+        if let Some((user, password)) = &self.credentials {
+            request = request.basic_auth(user, Some(password));
+        }
+        Ok(request.send().await?)
+    }
+}
+
+async fn rejected(response: reqwest::Response) -> SchemaRegistryError {
+    let status = response.status();
+    let message = match response.json::<ErrorBody>().await {
+        Ok(body) => body.message,
+        Err(_) => String::new(),
+    };
+    SchemaRegistryError::Rejected { status, message }
+}
+
+#[async_trait]
+impl SchemaRegistry for ConfluentSchemaRegistry {
+    async fn check_compatibility(&self, subject: &str, schema: &RegistrySchema) -> Result<Compatibility, SchemaRegistryError> {
+        let mut url = self.endpoint(&["compatibility", "subjects", subject, "versions", "latest"])?;
+        url.set_query(Some("verbose=true"));
+        let response = self.post(url, schema).await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            let status = response.status();
+            return match response.json::<ErrorBody>().await {
+                Ok(body) if body.error_code == SUBJECT_NOT_FOUND => Ok(Compatibility::NewSubject),
+                Ok(body) => Err(SchemaRegistryError::Rejected { status, message: body.message }),
+                Err(_) => Err(SchemaRegistryError::Rejected { status, message: String::new() }),
+            };
+        }
+        if !response.status().is_success() {
+            return Err(rejected(response).await);
+        }
+        let body: CompatibilityBody = response.json().await?;
+        Ok(if body.is_compatible {
+            Compatibility::Compatible
+        } else {
+            Compatibility::Incompatible(body.messages)
+        })
+    }
+
+    async fn register(&self, subject: &str, schema: &RegistrySchema) -> Result<u32, SchemaRegistryError> {
+        let response = self.post(self.endpoint(&["subjects", subject, "versions"])?, schema).await?;
+        if !response.status().is_success() {
+            return Err(rejected(response).await);
+        }
+        Ok(response.json::<RegisteredBody>().await?.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subjects_are_encoded_as_a_single_path_segment() {
+        let registry = ConfluentSchemaRegistry::new("https://registry.example.com/ccompat/");
+        let url = registry.endpoint(&["subjects", "events/v1?x=%", "versions"]).unwrap();
+        assert_eq!(url.as_str(), "https://registry.example.com/ccompat/subjects/events%2Fv1%3Fx=%25/versions");
+        assert!(matches!(ConfluentSchemaRegistry::new("not a url").endpoint(&["subjects"]), Err(SchemaRegistryError::InvalidUrl(_))));
+    }
+}
@@ -0,0 +1,383 @@
+use async_trait::async_trait;
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, Scope, AST};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use super::{run_blocking, PluginChain, PluginError, PluginVerdict, RequestPlugin, RequestView, ResponseVerdict, ResponseView};
+use crate::container::startup::StartupComponent;
+
+thread_local! {
+    /// When the script running on this thread must stop
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// What one call of a script may use
+#[derive(Debug, Clone, Copy)]
+pub struct ScriptLimits {
+    pub max_operations: u64,
+    pub timeout: Duration,
+}
+
+struct Script {
+    name: String,
+    source: String,
+    ast: AST,
+    on_request: bool,
+    on_response: bool,
+}
+
+/// Request admission and response rules written in Rhai, one `.rhai` file
+/// per policy in `SCRIPTS_DIR`, run in file name order. A script defines
+/// either hook, or both, and returns a map shaped like `PluginVerdict` or
+/// `ResponseVerdict`, or nothing to leave the request alone:
+///
+/// ```rhai
+/// fn on_request(req) {
+///     if req.method != "GET" && req.headers["x-tenant"] == () {
+///         return #{ reject: #{ status: 400, code: "TENANT_REQUIRED", message: "Send X-Tenant" } };
+///     }
+/// }
+///
+/// fn on_response(req, res) {
+///     #{ remove_headers: ["x-powered-by"] }
+/// }
+/// ```
+///
+/// Scripts can't import modules or `eval`, and are stopped after
+/// `max_operations` or `timeout`, whichever comes first.
+pub struct ScriptPolicies {
+    dir: PathBuf,
+    engine: Engine,
+    timeout: Duration,
+    scripts: RwLock<Arc<Vec<Arc<Script>>>>,
+    /// Sources that failed to compile, by script, so each is logged once
+    rejected: Mutex<HashMap<String, String>>,
+}
+
+impl ScriptPolicies {
+    pub fn new(dir: impl Into<PathBuf>, limits: ScriptLimits) -> Self {
+        let mut engine = Engine::new();
+        engine.set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new());
+        engine.disable_symbol("eval");
+        engine.set_max_operations(limits.max_operations);
+        engine.set_max_call_levels(32);
+        engine.set_max_expr_depths(64, 32);
+        engine.set_max_string_size(64 * 1024);
+        engine.set_max_array_size(10_000);
+        engine.set_max_map_size(10_000);
+        engine.on_progress(|operations| {
+            // The clock is read every 1024 operations
+            let late = operations % 1024 == 0
+                && DEADLINE.with(Cell::get).is_some_and(|deadline| Instant::now() >= deadline);
+            late.then(|| Dynamic::from("timeout"))
+        });
+        engine.on_print(|text| tracing::info!(target: "scripts", "{text}"));
+        engine.on_debug(|text, source, _| tracing::debug!(target: "scripts", script = source, "{text}"));
+        Self { dir: dir.into(), engine, timeout: limits.timeout, scripts: RwLock::default(), rejected: Mutex::default() }
+    }
+
+    /// Compile every script; fails on the first that doesn't compile
+    pub fn load(&self) -> Result<usize, PluginError> {
+        let mut scripts = Vec::new();
+        for (path, name, source) in self.read_dir()? {
+            scripts.push(Arc::new(self.compile(&path, name, source)?));
+        }
+        let loaded = scripts.len();
+        *self.scripts.write().unwrap() = Arc::new(scripts);
+        Ok(loaded)
+    }
+
+    /// Pick up added, changed and removed scripts. A script that no longer
+    /// compiles keeps running its previous version. Returns whether anything
+    /// changed.
+    pub fn reload(&self) -> Result<bool, PluginError> {
+        let current = self.scripts();
+        let mut changed = false;
+        let mut scripts = Vec::new();
+        let mut rejected = self.rejected.lock().unwrap();
+        for (path, name, source) in self.read_dir()? {
+            let previous = current.iter().find(|script| script.name == name);
+            if previous.is_some_and(|script| script.source == source) || rejected.get(&name) == Some(&source) {
+                scripts.extend(previous.cloned());
+                continue;
+            }
+            match self.compile(&path, name.clone(), source.clone()) {
+                Ok(script) => {
+                    tracing::info!(script = %script.name, "Script policy reloaded");
+                    rejected.remove(&name);
+                    scripts.push(Arc::new(script));
+                    changed = true;
+                }
+                Err(err) => {
+                    tracing::error!(error = %err, "Script policy not reloaded; keeping the previous version");
+                    rejected.insert(name, source);
+                    scripts.extend(previous.cloned());
+                }
+            }
+        }
+        changed |= scripts.len() != current.len();
+        *self.scripts.write().unwrap() = Arc::new(scripts);
+        Ok(changed)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.scripts().iter().map(|script| script.name.clone()).collect()
+    }
+
+    fn scripts(&self) -> Arc<Vec<Arc<Script>>> {
+        self.scripts.read().unwrap().clone()
+    }
+
+    /// `.rhai` files by name, with their stem and source
+    fn read_dir(&self) -> Result<Vec<(PathBuf, String, String)>, PluginError> {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(&self.dir)
+            .map_err(|err| load_error(&self.dir, err))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "rhai"))
+            .collect();
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| {
+                let source = std::fs::read_to_string(&path).map_err(|err| load_error(&path, err))?;
+                let name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+                Ok((path, name, source))
+            })
+            .collect()
+    }
+
+    fn compile(&self, path: &Path, name: String, source: String) -> Result<Script, PluginError> {
+        let mut ast = self.engine.compile(&source).map_err(|err| load_error(path, err))?;
+        ast.set_source(name.as_str());
+        let defines = |hook: &str| ast.iter_functions().any(|function| function.name == hook);
+        let (on_request, on_response) = (defines("on_request"), defines("on_response"));
+        if !on_request && !on_response {
+            return Err(load_error(path, "defines neither `on_request` nor `on_response`"));
+        }
+        Ok(Script { name, source, ast, on_request, on_response })
+    }
+
+    fn call(&self, script: &Script, hook: &str, args: impl rhai::FuncArgs) -> Result<Dynamic, PluginError> {
+        DEADLINE.with(|deadline| deadline.set(Some(Instant::now() + self.timeout)));
+        let result = self.engine.call_fn_with_options::<Dynamic>(
+            CallFnOptions::new().eval_ast(false),
+            &mut Scope::new(),
+            &script.ast,
+            hook,
+            args,
+        );
+        DEADLINE.with(|deadline| deadline.set(None));
+        result.map_err(|err| {
+            let reason = match *err {
+                EvalAltResult::ErrorTooManyOperations(_) => "ran over its operation limit".to_string(),
+                EvalAltResult::ErrorTerminated(..) => format!("ran over its {:?} time limit", self.timeout),
+                err => err.to_string(),
+            };
+            PluginError::Failed(format!("{} `{hook}` {reason}", script.name))
+        })
+    }
+}
+
+impl RequestPlugin for ScriptPolicies {
+    fn name(&self) -> &str {
+        "scripts"
+    }
+
+    /// Scripts see the headers as the scripts before them left them; the
+    /// first rejection wins
+    fn on_request(&self, request: &RequestView) -> Result<PluginVerdict, PluginError> {
+        let mut view = request.clone();
+        let mut combined = PluginVerdict::default();
+        for script in self.scripts().iter().filter(|script| script.on_request) {
+            let arg = to_dynamic(&view)?;
+            let verdict: PluginVerdict = from_dynamic(self.call(script, "on_request", (arg,))?)?;
+            if verdict.reject.is_some() {
+                tracing::info!(script = %script.name, path = %request.path, "Request rejected by script policy");
+                return Ok(verdict);
+            }
+            for name in verdict.remove_headers {
+                view.headers.remove(&name.to_ascii_lowercase());
+                combined.set_headers.remove(&name);
+                combined.remove_headers.push(name);
+            }
+            for (name, value) in verdict.set_headers {
+                view.headers.insert(name.to_ascii_lowercase(), value.clone());
+                combined.set_headers.insert(name, value);
+            }
+        }
+        Ok(combined)
+    }
+
+    fn on_response(&self, request: &RequestView, response: &ResponseView) -> Result<ResponseVerdict, PluginError> {
+        let mut view = response.clone();
+        let mut combined = ResponseVerdict::default();
+        for script in self.scripts().iter().filter(|script| script.on_response) {
+            let args = (to_dynamic(request)?, to_dynamic(&view)?);
+            let verdict: ResponseVerdict = from_dynamic(self.call(script, "on_response", args)?)?;
+            for name in verdict.remove_headers {
+                view.headers.remove(&name.to_ascii_lowercase());
+                combined.set_headers.remove(&name);
+                combined.remove_headers.push(name);
+            }
+            for (name, value) in verdict.set_headers {
+                view.headers.insert(name.to_ascii_lowercase(), value.clone());
+                combined.set_headers.insert(name, value);
+            }
+        }
+        Ok(combined)
+    }
+}
+
+fn to_dynamic(value: &impl serde::Serialize) -> Result<Dynamic, PluginError> {
+    rhai::serde::to_dynamic(value).map_err(|err| PluginError::Failed(err.to_string()))
+}
+
+/// `()` is the default verdict
+fn from_dynamic<T: Default + serde::de::DeserializeOwned>(value: Dynamic) -> Result<T, PluginError> {
+    if value.is_unit() {
+        return Ok(T::default());
+    }
+    rhai::serde::from_dynamic(&value).map_err(|err| PluginError::InvalidVerdict(err.to_string()))
+}
+
+fn load_error(path: impl AsRef<Path>, reason: impl std::fmt::Display) -> PluginError {
+    PluginError::Load { path: path.as_ref().display().to_string(), reason: reason.to_string() }
+}
+
+/// Compiles the scripts at startup, so one that doesn't compile keeps the
+/// server from starting, then rereads the directory every `reload_interval`
+pub struct ScriptPolicyLoader {
+    policies: Arc<ScriptPolicies>,
+    chain: Arc<PluginChain>,
+    /// Zero disables hot reloading
+    reload_interval: Duration,
+}
+
+impl ScriptPolicyLoader {
+    pub fn new(policies: Arc<ScriptPolicies>, chain: Arc<PluginChain>, reload_interval: Duration) -> Self {
+        Self { policies, chain, reload_interval }
+    }
+}
+
+#[async_trait]
+impl StartupComponent for ScriptPolicyLoader {
+    fn name(&self) -> &'static str {
+        "script_policies"
+    }
+
+    async fn start(&self) -> Result<(), String> {
+        let loaded = self.policies.load().map_err(|err| err.to_string())?;
+        self.chain.register(self.policies.clone());
+        tracing::info!(dir = %self.policies.dir.display(), scripts = ?self.policies.names(), loaded, "Script policies loaded");
+
+        if !self.reload_interval.is_zero() {
+            let (policies, interval) = (self.policies.clone(), self.reload_interval);
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(interval);
+                ticker.tick().await;
+                loop {
+                    ticker.tick().await;
+                    let policies = policies.clone();
+                    // Reading and compiling the scripts blocks; keep it off the async workers
+                    let reloaded = run_blocking("script_reload", interval, move || policies.reload().map_err(|err| err.to_string())).await;
+                    if let Err(err) = reloaded.map_err(|err| err.to_string()).and_then(|reloaded| reloaded) {
+                        tracing::error!(error = %err, "Script policies not reloaded");
+                    }
+                }
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::{CpuPool, PluginRefusal};
+    use axum::http::{HeaderMap, Method};
+
+    const LIMITS: ScriptLimits = ScriptLimits { max_operations: 50_000, timeout: Duration::from_millis(200) };
+
+    fn request(headers: &[(&'static str, &'static str)]) -> RequestView {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.insert(*name, value.parse().unwrap());
+        }
+        RequestView::new(&Method::POST, &"/api/users".parse().unwrap(), &map)
+    }
+
+    #[test]
+    fn scripts_admit_shape_and_hot_reload_within_their_limits() {
+        let dir = std::env::temp_dir().join(format!("script-policies-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, source: &str| std::fs::write(dir.join(name), source).unwrap();
+        write(
+            "10_tenant.rhai",
+            r#"fn on_request(req) {
+                if req.headers["x-tenant"] == () {
+                    return #{ reject: #{ status: 400, code: "TENANT_REQUIRED", message: "Send X-Tenant" } };
+                }
+                #{ set_headers: #{ "x-tenant-tier": "gold" } }
+            }"#,
+        );
+        write(
+            "20_tier.rhai",
+            r#"fn on_request(req) { if req.headers["x-tenant-tier"] != "gold" { throw "tier not set"; } }
+               fn on_response(req, res) { #{ set_headers: #{ "x-policy": `${res.status}` }, remove_headers: ["server"] } }"#,
+        );
+        let policies = ScriptPolicies::new(&dir, LIMITS);
+        assert_eq!(policies.load().unwrap(), 2);
+
+        let rejected = policies.on_request(&request(&[])).unwrap().reject.unwrap();
+        assert_eq!((rejected.status, rejected.code.as_str()), (400, "TENANT_REQUIRED"));
+        let admitted = policies.on_request(&request(&[("x-tenant", "acme")])).unwrap();
+        assert_eq!(admitted.set_headers["x-tenant-tier"], "gold");
+        let response = ResponseView { status: 201, headers: Default::default() };
+        let shaped = policies.on_response(&request(&[]), &response).unwrap();
+        assert_eq!((shaped.set_headers["x-policy"].as_str(), shaped.remove_headers.as_slice()), ("201", &["server".to_string()][..]));
+
+        // A broken edit keeps the old version; a fixed one replaces it
+        write("10_tenant.rhai", "fn on_request(req) { #{ ");
+        assert!(!policies.reload().unwrap());
+        assert!(policies.on_request(&request(&[])).unwrap().reject.is_some());
+        write("10_tenant.rhai", r#"fn on_request(req) { loop { } }"#);
+        assert!(policies.reload().unwrap());
+        let err = policies.on_request(&request(&[])).unwrap_err();
+        assert!(err.to_string().contains("operation limit"), "{err}");
+
+        write("10_tenant.rhai", r#"fn on_request(req) { import "secrets" as s; }"#);
+        policies.reload().unwrap();
+        assert!(policies.on_request(&request(&[])).is_err());
+        std::fs::remove_file(dir.join("10_tenant.rhai")).unwrap();
+        assert!(policies.reload().unwrap());
+        assert_eq!(policies.names(), ["20_tier"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn runaway_scripts_are_cut_off_on_the_cpu_pool() {
+        let dir = std::env::temp_dir().join(format!("script-policies-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("spin.rhai"), "fn on_request(req) { loop { } }").unwrap();
+        let limits = ScriptLimits { max_operations: 0, timeout: Duration::from_millis(50) };
+        let policies = Arc::new(ScriptPolicies::new(&dir, limits));
+        policies.load().unwrap();
+        let chain = Arc::new(PluginChain::new(false).with_cpu_pool(Arc::new(CpuPool::new(1, 4, 4)), Duration::from_secs(5)));
+        chain.register(policies);
+
+        let started = std::time::Instant::now();
+        let refused = chain.run_on_pool(&Method::GET, &"/api/users".parse().unwrap(), &mut HeaderMap::new()).await;
+        match refused {
+            Err(PluginRefusal::Failed { plugin, error }) => {
+                assert_eq!(plugin, "scripts");
+                assert!(error.to_string().contains("time limit"), "{error}");
+            }
+            other => panic!("expected the script to fail, got {other:?}"),
+        }
+        assert!(started.elapsed() < Duration::from_secs(2));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,373 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use crate::infrastructure::sync::{AtomicU64, Ordering, RwLock};
+
+/// Picks one of a set of backend nodes for each key with rendezvous (highest
+/// random weight) hashing: every node scores the key and the highest score
+/// wins. The hash is stable across processes and builds, so every instance
+/// maps a key to the same node. When a node is added or removed, only the
+/// keys it wins or owned move; the rest keep their node.
+pub struct ShardRing {
+    name: String,
+    nodes: RwLock<Vec<Arc<Node>>>,
+    rebalances: AtomicU64,
+    last_rebalance: RwLock<Option<Rebalance>>,
+}
+
+struct Node {
+    name: String,
+    seed: u64,
+    picks: AtomicU64,
+}
+
+/// How the last change to the node set moved keys
+#[derive(Debug, Clone, Serialize)]
+pub struct Rebalance {
+    pub at: DateTime<Utc>,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Expected share of keys that changed node, from 0 to 1
+    pub moved_fraction: f64,
+}
+
+/// Per-node counters of a `ShardRing`
+#[derive(Debug, Clone, Serialize)]
+pub struct ShardNodeStats {
+    pub node: String,
+    /// Keys routed to this node since it joined
+    pub picks: u64,
+    /// This node's part of all picks, from 0 to 1
+    pub share: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShardRingStats {
+    pub name: String,
+    pub nodes: Vec<ShardNodeStats>,
+    pub rebalances: u64,
+    pub last_rebalance: Option<Rebalance>,
+}
+
+impl ShardRing {
+    pub fn new(name: impl Into<String>, nodes: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let ring = Self {
+            name: name.into(),
+            nodes: RwLock::default(),
+            rebalances: AtomicU64::new(0),
+            last_rebalance: RwLock::default(),
+        };
+        *ring.nodes.write().unwrap() = dedup(nodes).into_iter().map(Node::new).map(Arc::new).collect();
+        ring
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn nodes(&self) -> Vec<String> {
+        self.nodes.read().unwrap().iter().map(|node| node.name.clone()).collect()
+    }
+
+    /// The node owning `key`, or `None` when the ring is empty
+    pub fn pick(&self, key: &[u8]) -> Option<String> {
+        let nodes = self.nodes.read().unwrap();
+        let hash = fnv1a(key);
+        let node = nodes.iter().max_by_key(|node| mix(hash ^ node.seed))?;
+        node.picks.fetch_add(1, Ordering::Relaxed);
+        Some(node.name.clone())
+    }
+
+    /// The `count` nodes with the highest scores for `key`, best first, for
+    /// writing replicas; not counted in the pick stats
+    pub fn pick_n(&self, key: &[u8], count: usize) -> Vec<String> {
+        let hash = fnv1a(key);
+        let mut scored: Vec<(u64, String)> =
+            self.nodes.read().unwrap().iter().map(|node| (mix(hash ^ node.seed), node.name.clone())).collect();
+        scored.sort_unstable_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored.into_iter().take(count).map(|(_, name)| name).collect()
+    }
+
+    /// Replace the node set. Nodes that stay keep their counters. Returns
+    /// the rebalance, or `None` when the set didn't change.
+    pub fn set_nodes(&self, nodes: impl IntoIterator<Item = impl Into<String>>) -> Option<Rebalance> {
+        let wanted = dedup(nodes);
+        let mut current = self.nodes.write().unwrap();
+        let before: BTreeSet<String> = current.iter().map(|node| node.name.clone()).collect();
+        let after: BTreeSet<String> = wanted.iter().cloned().collect();
+        if before == after {
+            return None;
+        }
+
+        // A key keeps its node only when the best scorer over both sets
+        // is in both, so the expected share moved is 1 - |A ∩ B| / |A ∪ B|
+        let kept = before.intersection(&after).count() as f64;
+        let union = before.union(&after).count() as f64;
+        let rebalance = Rebalance {
+            at: Utc::now(),
+            added: after.difference(&before).cloned().collect(),
+            removed: before.difference(&after).cloned().collect(),
+            moved_fraction: 1.0 - kept / union,
+        };
+
+        let mut existing: HashMap<String, Arc<Node>> =
+            current.drain(..).map(|node| (node.name.clone(), node)).collect();
+        *current = wanted
+            .into_iter()
+            .map(|name| existing.remove(&name).unwrap_or_else(|| Arc::new(Node::new(name))))
+            .collect();
+        // Counted before the nodes are released, so `stats` never reports
+        // the new set without its rebalance
+        self.rebalances.fetch_add(1, Ordering::Relaxed);
+        *self.last_rebalance.write().unwrap() = Some(rebalance.clone());
+        drop(current);
+
+        tracing::info!(
+            ring = %self.name,
+            added = ?rebalance.added,
+            removed = ?rebalance.removed,
+            moved_fraction = rebalance.moved_fraction,
+            "Shard ring rebalanced"
+        );
+        Some(rebalance)
+    }
+
+    pub fn stats(&self) -> ShardRingStats {
+        let nodes = self.nodes.read().unwrap();
+        let picks: Vec<u64> = nodes.iter().map(|node| node.picks.load(Ordering::Relaxed)).collect();
+        let total = picks.iter().sum::<u64>().max(1) as f64;
+        ShardRingStats {
+            name: self.name.clone(),
+            nodes: nodes
+                .iter()
+                .zip(picks)
+                .map(|(node, picks)| ShardNodeStats { node: node.name.clone(), picks, share: picks as f64 / total })
+                .collect(),
+            rebalances: self.rebalances.load(Ordering::Relaxed),
+            last_rebalance: self.last_rebalance.read().unwrap().clone(),
+        }
+    }
+}
+
+impl Node {
+    fn new(name: String) -> Self {
+        Self { seed: mix(fnv1a(name.as_bytes())), name, picks: AtomicU64::new(0) }
+    }
+}
+
+/// Trimmed, non-empty and in first-seen order
+fn dedup(nodes: impl IntoIterator<Item = impl Into<String>>) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    nodes
+        .into_iter()
+        .map(|node| node.into().trim().to_string())
+        .filter(|node| !node.is_empty() && seen.insert(node.clone()))
+        .collect()
+}
+
+/// 64-bit FNV-1a; fixed, unlike `std`'s `DefaultHasher`
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// SplitMix64 finalizer, so scores of similar inputs spread out
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// One backend per node of a `ShardRing`, e.g. a cache client per cache
+/// server, with each key served by the backend of its node
+pub struct Sharded<T> {
+    ring: Arc<ShardRing>,
+    backends: RwLock<HashMap<String, Arc<T>>>,
+}
+
+impl<T> Sharded<T> {
+    pub fn new(name: impl Into<String>, backends: impl IntoIterator<Item = (String, T)>) -> Self {
+        let backends: HashMap<String, Arc<T>> =
+            backends.into_iter().map(|(node, backend)| (node, Arc::new(backend))).collect();
+        let ring = Arc::new(ShardRing::new(name, backends.keys().cloned().collect::<Vec<_>>()));
+        Self { ring, backends: RwLock::new(backends) }
+    }
+
+    /// For `ShardRegistry::register`
+    pub fn ring(&self) -> Arc<ShardRing> {
+        self.ring.clone()
+    }
+
+    /// The backend owning `key`
+    pub fn get(&self, key: &[u8]) -> Option<Arc<T>> {
+        // Pick under the backends lock, so a concurrent `set_backends` can't
+        // remove the picked node before its backend is looked up
+        let backends = self.backends.read().unwrap();
+        backends.get(&self.ring.pick(key)?).cloned()
+    }
+
+    /// Every backend, for operations that span all keys
+    pub fn backends(&self) -> Vec<Arc<T>> {
+        self.backends.read().unwrap().values().cloned().collect()
+    }
+
+    /// Swap in a new set of backends; backends of nodes that stay are kept
+    pub fn set_backends(&self, backends: impl IntoIterator<Item = (String, T)>) -> Option<Rebalance> {
+        let mut current = self.backends.write().unwrap();
+        let mut next = HashMap::new();
+        for (node, backend) in backends {
+            let backend = current.remove(&node).unwrap_or_else(|| Arc::new(backend));
+            next.insert(node, backend);
+        }
+        let rebalance = self.ring.set_nodes(next.keys().cloned().collect::<Vec<_>>());
+        *current = next;
+        rebalance
+    }
+}
+
+/// Outbound HTTP client for a sharded service: each request goes to the base
+/// URL of the node owning its key
+pub struct ShardedHttpClient {
+    http: reqwest::Client,
+    ring: Arc<ShardRing>,
+}
+
+impl ShardedHttpClient {
+    /// `base_urls` are node names too, e.g. `http://cache-1:8080`
+    pub fn new(name: impl Into<String>, base_urls: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let base_urls = base_urls.into_iter().map(|url| url.into().trim_end_matches('/').to_string());
+        Self { http: reqwest::Client::new(), ring: Arc::new(ShardRing::new(name, base_urls)) }
+    }
+
+    pub fn ring(&self) -> Arc<ShardRing> {
+        self.ring.clone()
+    }
+
+    /// A request for `path` on the node owning `key`; `None` without nodes
+    pub fn request(&self, method: reqwest::Method, key: &str, path: &str) -> Option<reqwest::RequestBuilder> {
+        let base = self.ring.pick(key.as_bytes())?;
+        Some(self.http.request(method, format!("{base}{path}")))
+    }
+}
+
+/// Shard rings reported at `GET /api/admin/shards`
+#[derive(Default)]
+pub struct ShardRegistry {
+    rings: RwLock<Vec<Arc<ShardRing>>>,
+}
+
+impl ShardRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&self, ring: Arc<ShardRing>) {
+        self.rings.write().unwrap().push(ring);
+    }
+
+    pub fn stats(&self) -> Vec<ShardRingStats> {
+        self.rings.read().unwrap().iter().map(|ring| ring.stats()).collect()
+    }
+}
+
+#[cfg(all(test, not(loom_model)))]
+mod tests {
+    use super::*;
+
+    fn owners(ring: &ShardRing) -> Vec<String> {
+        (0..10_000).map(|key: u32| ring.pick(&key.to_be_bytes()).unwrap()).collect()
+    }
+
+    #[test]
+    fn keys_spread_evenly_and_only_the_expected_share_moves() {
+        let ring = ShardRing::new("cache", ["a", "b", "c", "b", " "]);
+        assert_eq!(ring.nodes(), ["a", "b", "c"]);
+        let before = owners(&ring);
+        for stats in ring.stats().nodes {
+            assert!((0.30..0.37).contains(&stats.share), "{stats:?}");
+        }
+        assert_eq!(ring.pick_n(b"user:1", 2)[0], ring.pick(b"user:1").unwrap());
+
+        // Adding a fourth node moves about a quarter of the keys, all to it
+        let rebalance = ring.set_nodes(["a", "b", "c", "d"]).unwrap();
+        assert_eq!((rebalance.added.as_slice(), rebalance.moved_fraction), (&["d".to_string()][..], 0.25));
+        let after = owners(&ring);
+        let moved: Vec<_> = before.iter().zip(&after).filter(|(old, new)| old != new).collect();
+        assert!(moved.iter().all(|(_, new)| *new == "d"));
+        assert!((2_200..2_800).contains(&moved.len()), "{}", moved.len());
+        assert!(ring.set_nodes(["d", "c", "b", "a"]).is_none());
+        assert_eq!(ring.stats().rebalances, 1);
+    }
+
+    #[tokio::test]
+    async fn sharded_backends_follow_the_ring() {
+        use crate::infrastructure::TtlCache;
+        use std::time::Duration;
+
+        let caches = Sharded::new("sessions", ["a", "b"].map(|node| (node.to_string(), TtlCache::new(100))));
+        caches.get(b"k").unwrap().insert("k", 1, Duration::from_secs(60)).await;
+        assert_eq!(caches.get(b"k").unwrap().get(&"k").await, Some(1));
+
+        let owner = caches.ring().pick(b"k").unwrap();
+        caches.set_backends([(owner, TtlCache::new(100))]).unwrap();
+        assert_eq!(caches.get(b"k").unwrap().get(&"k").await, Some(1));
+        assert!(ShardedHttpClient::new("empty", Vec::<String>::new()).request(reqwest::Method::GET, "k", "/").is_none());
+    }
+}
+
+/// Run with `RUSTFLAGS="--cfg loom_model" cargo test --release sharding::loom_tests`
+#[cfg(all(test, loom_model))]
+mod loom_tests {
+    use super::*;
+    use crate::infrastructure::sync::model;
+    use loom::thread;
+
+    #[test]
+    fn lookups_never_miss_while_backends_are_swapped() {
+        model(|| {
+            let sharded = Arc::new(Sharded::new("cache", ["a", "b"].map(|node| (node.to_string(), node))));
+            let swap = {
+                let sharded = sharded.clone();
+                thread::spawn(move || {
+                    sharded.set_backends([("b".to_string(), "b"), ("c".to_string(), "c")]);
+                })
+            };
+            for key in [b"k1", b"k2"] {
+                let backend = sharded.get(key).expect("a non-empty ring always has a backend");
+                assert!(["a", "b", "c"].contains(&*backend));
+            }
+            swap.join().unwrap();
+            assert_eq!(sharded.ring().stats().rebalances, 1);
+        });
+    }
+
+    #[test]
+    fn the_registry_reports_whole_rings_while_they_change() {
+        model(|| {
+            let registry = Arc::new(ShardRegistry::new());
+            let users = Arc::new(ShardRing::new("users", ["a", "b"]));
+            registry.register(users.clone());
+
+            let rebalance = {
+                let users = users.clone();
+                thread::spawn(move || {
+                    users.set_nodes(["a", "c"]);
+                })
+            };
+            let register = {
+                let registry = registry.clone();
+                thread::spawn(move || registry.register(Arc::new(ShardRing::new("sessions", ["x"]))))
+            };
+            let stats = registry.stats();
+            assert!((1..=2).contains(&stats.len()));
+            let nodes: Vec<&str> = stats[0].nodes.iter().map(|node| node.node.as_str()).collect();
+            assert!(nodes == ["a", "b"] || nodes == ["a", "c"], "{nodes:?}");
+            assert_eq!(stats[0].rebalances == 1, nodes == ["a", "c"]);
+
+            rebalance.join().unwrap();
+            register.join().unwrap();
+            assert_eq!(registry.stats().len(), 2);
+        });
+    }
+}
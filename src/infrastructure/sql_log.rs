@@ -0,0 +1,210 @@
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Debug logging of every SQL query, with a warning and an optional
+/// `EXPLAIN` for the slow ones.
+///
+/// Repositories run each query through `observe` with the statement text.
+/// Statements are expected to bind their values as `$1`, `$2`, ...; any
+/// literal left in the text is redacted before it is logged. Plans are
+/// captured from the statement as written, since the redacted text is not
+/// valid SQL.
+pub struct QueryLog {
+    /// Queries slower than this are logged at warn; zero disables
+    slow_threshold: Duration,
+    /// How many of the slowest statements get a plan captured; zero disables
+    explain_top: usize,
+    pool: Option<PgPool>,
+    slow: Mutex<HashMap<String, SlowQuery>>,
+}
+
+/// A statement that went over the threshold at least once
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowQuery {
+    /// Redacted statement
+    pub statement: String,
+    pub occurrences: u64,
+    pub max_ms: f64,
+    /// `EXPLAIN (GENERIC_PLAN)` output, once captured
+    pub plan: Option<String>,
+    /// The statement as the repository wrote it, which is what gets explained
+    #[serde(skip)]
+    sql: &'static str,
+    #[serde(skip)]
+    explain_started: bool,
+}
+
+impl QueryLog {
+    pub fn new(slow_threshold: Duration) -> Self {
+        Self { slow_threshold, explain_top: 0, pool: None, slow: Mutex::default() }
+    }
+
+    /// Capture a plan for the `top` slowest statements, once each, by
+    /// running `EXPLAIN (GENERIC_PLAN)` on `pool`. Needs Postgres 16 or
+    /// newer, which plans statements with unbound parameters.
+    pub fn with_explain(mut self, pool: PgPool, top: usize) -> Self {
+        self.pool = Some(pool);
+        self.explain_top = top;
+        self
+    }
+
+    /// Run `query`, logging its duration and the rows `rows` counts in its result
+    pub async fn observe<T>(
+        self: &Arc<Self>,
+        statement: &'static str,
+        rows: impl FnOnce(&T) -> u64,
+        query: impl Future<Output = Result<T, sqlx::Error>>,
+    ) -> Result<T, sqlx::Error> {
+        let started = Instant::now();
+        let result = query.await;
+        let elapsed = started.elapsed();
+        let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+
+        match &result {
+            Ok(value) => tracing::debug!(target: "sql", elapsed_ms, rows = rows(value), statement, "Query"),
+            Err(err) => tracing::debug!(target: "sql", elapsed_ms, error = %err, statement, "Query failed"),
+        }
+        if !self.slow_threshold.is_zero() && elapsed > self.slow_threshold {
+            tracing::warn!(
+                target: "sql",
+                elapsed_ms,
+                threshold_ms = self.slow_threshold.as_millis() as u64,
+                statement = %redact(statement),
+                "Slow query"
+            );
+            self.record_slow(statement, elapsed_ms);
+        }
+        result
+    }
+
+    fn record_slow(self: &Arc<Self>, sql: &'static str, elapsed_ms: f64) {
+        let statement = redact(sql);
+        let mut slow = self.slow.lock().unwrap();
+        let entry = slow.entry(statement.clone()).or_insert_with(|| SlowQuery {
+            statement: statement.clone(),
+            occurrences: 0,
+            max_ms: 0.0,
+            plan: None,
+            sql,
+            explain_started: false,
+        });
+        entry.occurrences += 1;
+        entry.max_ms = entry.max_ms.max(elapsed_ms);
+
+        let Some(pool) = self.pool.clone() else { return };
+        let worst = worst(&slow, self.explain_top);
+        let Some(entry) = slow.get_mut(&statement).filter(|entry| !entry.explain_started && worst.contains(&statement))
+        else {
+            return;
+        };
+        entry.explain_started = true;
+        let sql = entry.sql;
+        let log = self.clone();
+        tokio::spawn(async move {
+            // The plan of a generic statement shows its placeholders, never bound values
+            match explain(&pool, sql).await {
+                Ok(plan) => {
+                    tracing::warn!(target: "sql", statement = %statement, plan = %plan, "Slow query plan");
+                    if let Some(entry) = log.slow.lock().unwrap().get_mut(&statement) {
+                        entry.plan = Some(plan);
+                    }
+                }
+                Err(err) => tracing::debug!(target: "sql", statement = %statement, error = %err, "Slow query not explained"),
+            }
+        });
+    }
+
+    /// Statements that went over the threshold, slowest first
+    pub fn slow_queries(&self) -> Vec<SlowQuery> {
+        let mut queries: Vec<SlowQuery> = self.slow.lock().unwrap().values().cloned().collect();
+        queries.sort_by(|a, b| b.max_ms.total_cmp(&a.max_ms));
+        queries
+    }
+}
+
+/// The `top` statements by worst duration
+fn worst(slow: &HashMap<String, SlowQuery>, top: usize) -> Vec<String> {
+    let mut by_duration: Vec<&SlowQuery> = slow.values().collect();
+    by_duration.sort_by(|a, b| b.max_ms.total_cmp(&a.max_ms));
+    by_duration.into_iter().take(top).map(|query| query.statement.clone()).collect()
+}
+
+async fn explain(pool: &PgPool, statement: &str) -> Result<String, sqlx::Error> {
+    let rows = sqlx::query(&format!("EXPLAIN (GENERIC_PLAN) {statement}")).fetch_all(pool).await?;
+    let lines: Result<Vec<String>, _> = rows.iter().map(|row| row.try_get::<String, _>(0)).collect();
+    Ok(lines?.join("\n"))
+}
+
+/// Replace quoted strings and numbers outside identifiers with `?`, and
+/// collapse whitespace; bind placeholders like `$1` are kept
+pub fn redact(statement: &str) -> String {
+    let mut redacted = String::with_capacity(statement.len());
+    let mut chars = statement.chars().peekable();
+    // Whether the previous character continues an identifier or placeholder
+    let mut in_word = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                // '' inside a literal is an escaped quote
+                while let Some(c) = chars.next() {
+                    if c == '\'' && chars.next_if_eq(&'\'').is_none() {
+                        break;
+                    }
+                }
+                redacted.push('?');
+                in_word = false;
+            }
+            '0'..='9' if !in_word => {
+                while chars.next_if(|c| c.is_ascii_digit() || *c == '.').is_some() {}
+                redacted.push('?');
+            }
+            c if c.is_whitespace() => {
+                if !redacted.ends_with(' ') {
+                    redacted.push(' ');
+                }
+                in_word = false;
+            }
+            c => {
+                redacted.push(c);
+                in_word = c.is_alphanumeric() || c == '_' || c == '$';
+            }
+        }
+    }
+    redacted.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn slow_queries_are_recorded_with_literals_redacted() {
+        assert_eq!(
+            redact("SELECT id\n  FROM users WHERE email = 'o''hara@x.io' AND age > 21 AND t2.id = $1 LIMIT 10"),
+            "SELECT id FROM users WHERE email = ? AND age > ? AND t2.id = $1 LIMIT ?"
+        );
+
+        let log = Arc::new(QueryLog::new(Duration::from_millis(5)));
+        let fast = log.observe("SELECT 1", |rows: &Vec<u8>| rows.len() as u64, async { Ok(vec![1]) });
+        assert_eq!(fast.await.unwrap(), vec![1]);
+        assert!(log.slow_queries().is_empty());
+
+        let slow = async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok(3u64)
+        };
+        log.observe("DELETE FROM sessions WHERE expires_at < now() - interval '30 days'", |rows| *rows, slow)
+            .await
+            .unwrap();
+        let queries = log.slow_queries();
+        assert_eq!(queries[0].statement, "DELETE FROM sessions WHERE expires_at < now() - interval ?");
+        assert_eq!(queries[0].occurrences, 1);
+        assert!(queries[0].max_ms >= 20.0 && queries[0].plan.is_none());
+        // Explained as written; the redacted text is not SQL
+        assert_eq!(queries[0].sql, "DELETE FROM sessions WHERE expires_at < now() - interval '30 days'");
+    }
+}
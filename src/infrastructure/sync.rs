@@ -0,0 +1,26 @@
+//! Synchronization primitives for lock-based code that is model-checked with
+//! loom. Normal builds use `std::sync`; `RUSTFLAGS="--cfg loom_model"` swaps in
+//! loom's instrumented versions so every interleaving can be explored.
+
+#[cfg(loom_model)]
+pub use loom::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+#[cfg(loom_model)]
+pub use loom::sync::{Mutex, RwLock};
+
+#[cfg(not(loom_model))]
+pub use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+#[cfg(not(loom_model))]
+pub use std::sync::{Mutex, RwLock};
+
+/// Explore a model with at most `LOOM_MAX_PREEMPTIONS` preemptions per
+/// execution, 3 when unset. Unbounded, the models with three threads run for
+/// hours; a bound of 3 finds the known races in seconds.
+#[cfg(all(test, loom_model))]
+pub fn model<F>(f: F)
+where
+    F: Fn() + Sync + Send + 'static,
+{
+    let mut builder = loom::model::Builder::new();
+    builder.preemption_bound.get_or_insert(3);
+    builder.check(f);
+}
@@ -0,0 +1,197 @@
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use validator::{ValidationErrors, ValidationErrorsKind};
+
+/// Shown for a failed rule that has no `message`
+const DEFAULT_MESSAGE: &str = "Invalid value";
+/// Code of failures added by hand with `FieldErrors::field`
+const DEFAULT_CODE: &str = "invalid";
+
+/// One failed rule: the `validator` code (`email`, `length`, ...) and its message
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FieldFailure {
+    code: String,
+    message: String,
+}
+
+/// `validator` failures flattened per field, for error responses and logs.
+///
+/// Nested structs and lists get dotted paths such as `address.city` or
+/// `items[2].sku`. Fields are kept sorted so output is stable.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldErrors {
+    fields: BTreeMap<String, Vec<FieldFailure>>,
+}
+
+impl FieldErrors {
+    /// One failure on one field, for checks `validator` can't express
+    pub fn field(field: impl Into<String>, message: impl Into<String>) -> Self {
+        let failure = FieldFailure { code: DEFAULT_CODE.to_string(), message: message.into() };
+        Self { fields: BTreeMap::from([(field.into(), vec![failure])]) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Messages per field path
+    pub fn fields(&self) -> BTreeMap<&str, Vec<&str>> {
+        self.fields
+            .iter()
+            .map(|(field, failures)| (field.as_str(), failures.iter().map(|failure| failure.message.as_str()).collect()))
+            .collect()
+    }
+
+    /// One `field: message` line per failed rule
+    pub fn messages(&self) -> Vec<String> {
+        self.fields
+            .iter()
+            .flat_map(|(field, failures)| failures.iter().map(move |failure| format!("{field}: {}", failure.message)))
+            .collect()
+    }
+
+    /// `details` of a `VALIDATION_ERROR` response: the human-readable list
+    /// and the per-field map
+    pub fn to_details(&self) -> HashMap<String, Value> {
+        HashMap::from([
+            ("validation_errors".to_string(), json!(self.messages())),
+            ("fields".to_string(), json!(self.fields())),
+        ])
+    }
+
+    /// The `errors` extension member of an RFC 9457 problem: one entry per
+    /// failed rule, with a JSON Pointer to the field in the request body
+    pub fn to_problem_errors(&self) -> Vec<Value> {
+        self.fields
+            .iter()
+            .flat_map(|(field, failures)| {
+                failures.iter().map(move |failure| {
+                    json!({
+                        "pointer": json_pointer(field),
+                        "field": field,
+                        "code": failure.code,
+                        "detail": failure.message,
+                    })
+                })
+            })
+            .collect()
+    }
+
+    fn collect(&mut self, prefix: &str, errors: &ValidationErrors) {
+        for (field, kind) in errors.errors() {
+            let path = if prefix.is_empty() { field.to_string() } else { format!("{prefix}.{field}") };
+            match kind {
+                ValidationErrorsKind::Field(failures) => {
+                    let recorded = self.fields.entry(path).or_default();
+                    recorded.extend(failures.iter().map(|failure| FieldFailure {
+                        code: failure.code.to_string(),
+                        message: failure.message.as_deref().unwrap_or(DEFAULT_MESSAGE).to_string(),
+                    }));
+                }
+                ValidationErrorsKind::Struct(nested) => self.collect(&path, nested),
+                ValidationErrorsKind::List(items) => {
+                    for (index, nested) in items {
+                        self.collect(&format!("{path}[{index}]"), nested);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `items[2].sku` as the URI fragment `#/items/2/sku`, escaped per RFC 6901
+fn json_pointer(path: &str) -> String {
+    let mut pointer = String::from("#");
+    for segment in path.split('.') {
+        let (name, indexes) = segment.split_once('[').map_or((segment, ""), |(name, rest)| (name, rest));
+        pointer.push('/');
+        pointer.push_str(&name.replace('~', "~0").replace('/', "~1"));
+        for index in indexes.split('[').map(|index| index.trim_end_matches(']')).filter(|index| !index.is_empty()) {
+            pointer.push('/');
+            pointer.push_str(index);
+        }
+    }
+    pointer
+}
+
+impl From<&ValidationErrors> for FieldErrors {
+    fn from(errors: &ValidationErrors) -> Self {
+        let mut flattened = Self::default();
+        flattened.collect("", errors);
+        flattened
+    }
+}
+
+impl From<ValidationErrors> for FieldErrors {
+    fn from(errors: ValidationErrors) -> Self {
+        Self::from(&errors)
+    }
+}
+
+impl fmt::Display for FieldErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.messages().join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use validator::Validate;
+
+    #[derive(Validate)]
+    struct Address {
+        #[validate(length(min = 2, message = "City is too short"))]
+        city: String,
+    }
+
+    #[derive(Validate)]
+    struct Signup {
+        #[validate(email(message = "Invalid email format"))]
+        email: String,
+        #[validate(length(min = 6))]
+        password: String,
+        #[validate]
+        address: Address,
+    }
+
+    #[test]
+    fn flattens_nested_errors_into_messages_and_a_field_map() {
+        let signup = Signup {
+            email: "nope".to_string(),
+            password: "abc".to_string(),
+            address: Address { city: "X".to_string() },
+        };
+        let errors = FieldErrors::from(signup.validate().unwrap_err());
+
+        assert_eq!(
+            errors.messages(),
+            vec!["address.city: City is too short", "email: Invalid email format", "password: Invalid value"]
+        );
+        assert_eq!(errors.to_string(), errors.messages().join(", "));
+        let details = errors.to_details();
+        assert_eq!(details["fields"]["email"], json!(["Invalid email format"]));
+        assert_eq!(details["validation_errors"].as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn problem_errors_point_at_each_failed_rule() {
+        let signup = Signup {
+            email: "nope".to_string(),
+            password: "abcdef".to_string(),
+            address: Address { city: "X".to_string() },
+        };
+        let errors = FieldErrors::from(signup.validate().unwrap_err());
+        assert_eq!(
+            errors.to_problem_errors(),
+            [
+                json!({ "pointer": "#/address/city", "field": "address.city", "code": "length", "detail": "City is too short" }),
+                json!({ "pointer": "#/email", "field": "email", "code": "email", "detail": "Invalid email format" }),
+            ]
+        );
+        assert_eq!(json_pointer("items[2].sku"), "#/items/2/sku");
+        assert_eq!(json_pointer("metadata.a/b"), "#/metadata/a~1b");
+        assert_eq!(FieldErrors::field("include", "Unknown include").to_problem_errors()[0]["code"], "invalid");
+    }
+}
@@ -0,0 +1,135 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+/// Field holding the schema version in a persisted record. Records without
+/// it were written before versioning and count as version 1.
+pub const VERSION_FIELD: &str = "_v";
+
+/// Rewrites a record of one version into the shape of the next
+pub type Upcaster = fn(Map<String, Value>) -> Result<Map<String, Value>, String>;
+
+/// An entity persisted as JSON (redb values, exports, snapshots, backups)
+/// whose shape can change between releases.
+///
+/// Bump `VERSION` whenever the serialized form changes and append an
+/// upcaster from the previous version, so records written by every earlier
+/// release still load. Upcasters work on the raw JSON object, not on an old
+/// struct, so old shapes need no Rust type of their own.
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// Version this build writes
+    const VERSION: u32;
+    /// `UPCASTERS[n - 1]` turns a version `n` record into version `n + 1`
+    const UPCASTERS: &'static [Upcaster];
+}
+
+#[derive(Debug, Error)]
+pub enum VersionError {
+    #[error("record is not a JSON object")]
+    NotAnObject,
+    #[error("record has an invalid version tag: {0}")]
+    InvalidVersion(Value),
+    #[error("record version {found} is newer than the supported version {supported}")]
+    TooNew { found: u32, supported: u32 },
+    #[error("no upcaster from version {0}")]
+    MissingUpcaster(u32),
+    #[error("upcasting from version {from} failed: {reason}")]
+    Upcast { from: u32, reason: String },
+    #[error("invalid record: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Serialize `entity` with its version tag
+pub fn to_versioned_value<T: Versioned>(entity: &T) -> Result<Value, VersionError> {
+    let Value::Object(mut record) = serde_json::to_value(entity)? else {
+        return Err(VersionError::NotAnObject);
+    };
+    record.insert(VERSION_FIELD.to_string(), Value::from(T::VERSION));
+    Ok(Value::Object(record))
+}
+
+pub fn to_versioned_vec<T: Versioned>(entity: &T) -> Result<Vec<u8>, VersionError> {
+    Ok(serde_json::to_vec(&to_versioned_value(entity)?)?)
+}
+
+/// Deserialize a record of any version up to `T::VERSION`, running it
+/// through the upcasters it is behind on
+pub fn from_versioned_value<T: Versioned>(value: Value) -> Result<T, VersionError> {
+    let Value::Object(mut record) = value else {
+        return Err(VersionError::NotAnObject);
+    };
+    let mut version = match record.remove(VERSION_FIELD) {
+        None => 1,
+        Some(tag) => match tag.as_u64().and_then(|tag| u32::try_from(tag).ok()) {
+            Some(version) if version >= 1 => version,
+            _ => return Err(VersionError::InvalidVersion(tag)),
+        },
+    };
+    if version > T::VERSION {
+        return Err(VersionError::TooNew { found: version, supported: T::VERSION });
+    }
+    while version < T::VERSION {
+        let upcast = T::UPCASTERS.get(version as usize - 1).ok_or(VersionError::MissingUpcaster(version))?;
+        record = upcast(record).map_err(|reason| VersionError::Upcast { from: version, reason })?;
+        version += 1;
+    }
+    Ok(serde_json::from_value(Value::Object(record))?)
+}
+
+pub fn from_versioned_slice<T: Versioned>(bytes: &[u8]) -> Result<T, VersionError> {
+    from_versioned_value(serde_json::from_slice(bytes)?)
+}
+
+pub fn from_versioned_str<T: Versioned>(text: &str) -> Result<T, VersionError> {
+    from_versioned_slice(text.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    /// v1 had `name`; v2 renamed it to `display_name`; v3 added `locale`
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Profile {
+        display_name: String,
+        locale: String,
+    }
+
+    fn rename_name(mut record: Map<String, Value>) -> Result<Map<String, Value>, String> {
+        let name = record.remove("name").ok_or("missing `name`")?;
+        record.insert("display_name".to_string(), name);
+        Ok(record)
+    }
+
+    fn default_locale(mut record: Map<String, Value>) -> Result<Map<String, Value>, String> {
+        record.entry("locale").or_insert_with(|| Value::from("en"));
+        Ok(record)
+    }
+
+    impl Versioned for Profile {
+        const VERSION: u32 = 3;
+        const UPCASTERS: &'static [Upcaster] = &[rename_name, default_locale];
+    }
+
+    #[test]
+    fn old_records_are_upcast_and_new_ones_rejected() {
+        let expected = Profile { display_name: "Ada".to_string(), locale: "en".to_string() };
+        assert_eq!(from_versioned_str::<Profile>(r#"{"name":"Ada"}"#).unwrap(), expected);
+        assert_eq!(from_versioned_str::<Profile>(r#"{"_v":2,"display_name":"Ada"}"#).unwrap(), expected);
+
+        let current = to_versioned_vec(&expected).unwrap();
+        assert_eq!(from_versioned_slice::<Profile>(&current).unwrap(), expected);
+        assert!(String::from_utf8(current).unwrap().contains(r#""_v":3"#));
+
+        assert!(matches!(
+            from_versioned_str::<Profile>(r#"{"_v":4,"display_name":"Ada","locale":"en"}"#),
+            Err(VersionError::TooNew { found: 4, supported: 3 })
+        ));
+        assert!(matches!(
+            from_versioned_str::<Profile>(r#"{"_v":1,"display_name":"Ada"}"#),
+            Err(VersionError::Upcast { from: 1, .. })
+        ));
+    }
+}
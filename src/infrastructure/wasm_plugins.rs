@@ -0,0 +1,213 @@
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use wasmtime::{Engine, Instance, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+
+use super::{PluginChain, PluginError, PluginVerdict, RequestPlugin, RequestView};
+use crate::container::startup::StartupComponent;
+
+/// Verdicts larger than this are refused rather than copied out
+const MAX_VERDICT_BYTES: usize = 64 * 1024;
+
+/// What one call of a plugin may use
+#[derive(Debug, Clone, Copy)]
+pub struct WasmLimits {
+    /// Roughly one unit per WebAssembly instruction
+    pub fuel: u64,
+    pub memory_bytes: usize,
+}
+
+/// A request plugin compiled to WebAssembly (`.wasm`, or `.wat` text).
+///
+/// The module gets no imports, so it can't reach the host, and must export:
+/// - `memory`
+/// - `alloc(len: i32) -> i32`, returning where the host may write `len` bytes
+/// - `on_request(ptr: i32, len: i32) -> i64`, given the `RequestView` as
+///   JSON; returns 0 to let the request through unchanged, or the location
+///   of a `PluginVerdict` in JSON as `ptr << 32 | len`
+///
+/// Every call runs in a fresh instance under the fuel and memory limits, so
+/// no state carries over between requests.
+pub struct WasmPlugin {
+    name: String,
+    engine: Engine,
+    module: Module,
+    limits: WasmLimits,
+}
+
+impl WasmPlugin {
+    /// An engine that meters fuel, shared by the plugins of a chain
+    pub fn engine() -> Engine {
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        Engine::new(&config).expect("fuel metering is supported on every target")
+    }
+
+    pub fn from_file(engine: &Engine, path: &Path, limits: WasmLimits) -> Result<Self, PluginError> {
+        let name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        let bytes = std::fs::read(path).map_err(|err| load_error(path, err))?;
+        Self::from_bytes(engine, name, &bytes, limits).map_err(|err| match err {
+            PluginError::Load { reason, .. } => load_error(path, reason),
+            err => err,
+        })
+    }
+
+    pub fn from_bytes(engine: &Engine, name: String, bytes: &[u8], limits: WasmLimits) -> Result<Self, PluginError> {
+        let module = Module::new(engine, bytes).map_err(|err| load_error(&name, err))?;
+        if let Some(import) = module.imports().next() {
+            let reason = format!("imports `{}::{}`; plugins run without host functions", import.module(), import.name());
+            return Err(load_error(&name, reason));
+        }
+        for export in ["memory", "alloc", "on_request"] {
+            if module.get_export(export).is_none() {
+                return Err(load_error(&name, format!("does not export `{export}`")));
+            }
+        }
+        Ok(Self { name, engine: engine.clone(), module, limits })
+    }
+
+    fn call(&self, input: &[u8]) -> wasmtime::Result<Option<Vec<u8>>> {
+        let limits = StoreLimitsBuilder::new().memory_size(self.limits.memory_bytes).instances(1).build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits: &mut StoreLimits| limits);
+        store.set_fuel(self.limits.fuel)?;
+
+        let instance = Instance::new(&mut store, &self.module, &[])?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| wasmtime::Error::msg("`memory` is not a memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        let on_request = instance.get_typed_func::<(i32, i32), i64>(&mut store, "on_request")?;
+
+        let len = i32::try_from(input.len())?;
+        let ptr = alloc.call(&mut store, len)?;
+        memory.write(&mut store, ptr as u32 as usize, input)?;
+        let packed = on_request.call(&mut store, (ptr, len))? as u64;
+        if packed == 0 {
+            return Ok(None);
+        }
+
+        let (ptr, len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        if len > MAX_VERDICT_BYTES {
+            return Err(wasmtime::Error::msg(format!("verdict of {len} bytes is over {MAX_VERDICT_BYTES}")));
+        }
+        let mut output = vec![0; len];
+        memory.read(&store, ptr, &mut output)?;
+        Ok(Some(output))
+    }
+}
+
+impl RequestPlugin for WasmPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn on_request(&self, request: &RequestView) -> Result<PluginVerdict, PluginError> {
+        let input = serde_json::to_vec(request).map_err(|err| PluginError::Failed(err.to_string()))?;
+        match self.call(&input) {
+            Ok(None) => Ok(PluginVerdict::default()),
+            Ok(Some(output)) => {
+                serde_json::from_slice(&output).map_err(|err| PluginError::InvalidVerdict(err.to_string()))
+            }
+            Err(err) if err.downcast_ref::<Trap>() == Some(&Trap::OutOfFuel) => Err(PluginError::OutOfFuel),
+            Err(err) => Err(PluginError::Failed(format!("{err:#}"))),
+        }
+    }
+}
+
+fn load_error(path: impl AsRef<Path>, reason: impl std::fmt::Display) -> PluginError {
+    PluginError::Load { path: path.as_ref().display().to_string(), reason: reason.to_string() }
+}
+
+/// Compiles the plugins in `PLUGINS_DIR` into the chain at startup, in file
+/// name order, so a plugin that doesn't load keeps the server from starting
+pub struct WasmPluginLoader {
+    dir: PathBuf,
+    limits: WasmLimits,
+    chain: Arc<PluginChain>,
+}
+
+impl WasmPluginLoader {
+    pub fn new(dir: impl Into<PathBuf>, limits: WasmLimits, chain: Arc<PluginChain>) -> Self {
+        Self { dir: dir.into(), limits, chain }
+    }
+
+    fn load(&self) -> Result<usize, PluginError> {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(&self.dir)
+            .map_err(|err| load_error(&self.dir, err))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| matches!(path.extension().and_then(|extension| extension.to_str()), Some("wasm" | "wat")))
+            .collect();
+        paths.sort();
+
+        let engine = WasmPlugin::engine();
+        for path in &paths {
+            self.chain.register(Arc::new(WasmPlugin::from_file(&engine, path, self.limits)?));
+        }
+        Ok(paths.len())
+    }
+}
+
+#[async_trait]
+impl StartupComponent for WasmPluginLoader {
+    fn name(&self) -> &'static str {
+        "wasm_plugins"
+    }
+
+    async fn start(&self) -> Result<(), String> {
+        let loaded = self.load().map_err(|err| err.to_string())?;
+        tracing::info!(dir = %self.dir.display(), plugins = ?self.chain.names(), loaded, "Request plugins loaded");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: WasmLimits = WasmLimits { fuel: 100_000, memory_bytes: 1 << 20 };
+
+    /// Answers every request with the verdict stored at offset 0
+    fn constant_verdict(verdict: &str) -> String {
+        format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 0) "{}")
+                (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+                (func (export "on_request") (param i32 i32) (result i64) (i64.const {})))"#,
+            verdict.replace('"', "\\\""),
+            verdict.len()
+        )
+    }
+
+    #[test]
+    fn verdicts_come_back_from_the_guest_and_limits_hold() {
+        let engine = WasmPlugin::engine();
+        let request = RequestView::new(&axum::http::Method::GET, &"/api/users".parse().unwrap(), &Default::default());
+
+        let wat = constant_verdict(r#"{"set_headers":{"x-plugin":"seen"}}"#);
+        let plugin = WasmPlugin::from_bytes(&engine, "tagger".into(), wat.as_bytes(), LIMITS).unwrap();
+        let verdict = plugin.on_request(&request).unwrap();
+        assert_eq!(verdict.set_headers["x-plugin"], "seen");
+
+        let spin = r#"(module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) (i32.const 0))
+            (func (export "on_request") (param i32 i32) (result i64) (loop (br 0)) (i64.const 0)))"#;
+        let plugin = WasmPlugin::from_bytes(&engine, "spin".into(), spin.as_bytes(), LIMITS).unwrap();
+        assert!(matches!(plugin.on_request(&request), Err(PluginError::OutOfFuel)));
+
+        let grow = r#"(module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) (i32.const 0))
+            (func (export "on_request") (param i32 i32) (result i64)
+                (if (i32.eq (memory.grow (i32.const 64)) (i32.const -1)) (then unreachable))
+                (i64.const 0)))"#;
+        let plugin = WasmPlugin::from_bytes(&engine, "grow".into(), grow.as_bytes(), LIMITS).unwrap();
+        assert!(matches!(plugin.on_request(&request), Err(PluginError::Failed(_))));
+
+        let host = r#"(module (import "env" "now" (func)) (memory (export "memory") 1))"#;
+        let err = WasmPlugin::from_bytes(&engine, "host".into(), host.as_bytes(), LIMITS).err().unwrap();
+        assert!(err.to_string().contains("imports `env::now`"), "{err}");
+    }
+}
@@ -0,0 +1,18 @@
+// The OpenAPI spec is one large `json!` literal
+#![recursion_limit = "512"]
+
+pub mod config;
+mod error;
+pub mod middleware;
+pub mod response;
+pub mod pagination;
+mod domain;
+pub mod infrastructure;
+pub mod delivery;
+pub mod container;
+pub mod codegen;
+pub mod smoke;
+pub mod loadtest;
+pub mod supervisor;
+pub mod transfer;
+pub mod backup;
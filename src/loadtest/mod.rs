@@ -0,0 +1,103 @@
+pub mod k6;
+pub mod report;
+pub mod vegeta;
+
+use serde_json::Value;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::codegen::{ApiDescription, GeneratedFile, Operation};
+use crate::config::Config;
+use report::Budgets;
+
+/// `loadtest generate [out_dir] [base_url]` writes k6 and Vegeta scripts from the route table
+pub fn write_scripts(spec: &Value, budgets: &Budgets, out_dir: &Path, base_url: &str) -> io::Result<()> {
+    for file in generate_scripts(spec, budgets, base_url) {
+        let path = out_dir.join(file.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, file.contents)?;
+        println!("generated {}", path.display());
+    }
+    Ok(())
+}
+
+pub fn generate_scripts(spec: &Value, budgets: &Budgets, base_url: &str) -> Vec<GeneratedFile> {
+    let api = ApiDescription::from_spec(spec);
+    let operations: Vec<&Operation> = api.operations.iter().filter(|op| is_load_tested(op)).collect();
+    vec![
+        GeneratedFile { path: "k6.js", contents: k6::generate(spec, &operations, budgets) },
+        GeneratedFile { path: "vegeta-targets.txt", contents: vegeta::generate(&operations, base_url) },
+    ]
+}
+
+/// `loadtest check <results.json>` compares k6 or Vegeta results against the configured budgets
+pub fn check_results_file(path: &Path, config: &Config) -> io::Result<bool> {
+    let contents = fs::read(path)?;
+    let results: Value = serde_json::from_slice(&contents)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let report = report::check_results(&results, &Budgets::from_config(config))
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    println!("{}", serde_json::to_string_pretty(&report).map_err(io::Error::other)?);
+    Ok(report.passed)
+}
+
+/// Admin operations and placeholder-style mutations (PUT/DELETE) are left out so
+/// a load run can't drain the instance or destroy the fixtures it reads
+fn is_load_tested(operation: &Operation) -> bool {
+    !operation.requires_auth && matches!(operation.method.as_str(), "GET" | "POST")
+}
+
+/// Collection path a `{param}` path belongs to, e.g. /api/users/{id} -> /api/users
+fn collection_path(path: &str) -> Option<&str> {
+    path.rsplit_once('/')
+        .filter(|(_, last)| last.starts_with('{'))
+        .map(|(parent, _)| parent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delivery::openapi_spec;
+
+    fn budgets() -> Budgets {
+        Budgets { p95_ms: 200.0, p99_ms: 500.0, max_error_rate: 0.01 }
+    }
+
+    #[test]
+    fn generated_scripts_cover_public_read_and_create_routes() {
+        let files = generate_scripts(&openapi_spec(), &budgets(), "http://localhost:3000");
+        let k6 = &files[0].contents;
+        let vegeta = &files[1].contents;
+
+        for operation_id in ["healthCheck", "listUsers", "createUser", "getProduct"] {
+            assert!(k6.contains(&format!("name: \"{}\"", operation_id)), "{} missing from k6 script", operation_id);
+        }
+        assert!(!k6.contains("startDraining"));
+        assert!(!k6.contains("deleteUser"));
+        assert!(!k6.contains("getUser\""));
+        assert!(k6.contains("\"http_req_duration{name:listUsers}\": [\"p(95)<200\", \"p(99)<500\"]"));
+
+        assert!(vegeta.contains("GET http://localhost:3000/api/users\n"));
+        assert!(!vegeta.contains("{id}"));
+    }
+
+    #[test]
+    fn generated_k6_script_is_valid_javascript() {
+        // Syntax check only; skipped where node isn't installed
+        if std::process::Command::new("node").arg("--version").output().is_err() {
+            return;
+        }
+        let dir = std::env::temp_dir().join(format!("loadtest-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("k6.mjs");
+        let files = generate_scripts(&openapi_spec(), &budgets(), "http://localhost:3000");
+        fs::write(&script, &files[0].contents).unwrap();
+
+        let status = std::process::Command::new("node").arg("--check").arg(&script).status().unwrap();
+        fs::remove_dir_all(&dir).ok();
+        assert!(status.success());
+    }
+}
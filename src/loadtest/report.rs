@@ -0,0 +1,181 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::Config;
+
+/// Latency and error budgets a load run must stay within
+#[derive(Debug, Clone, Serialize)]
+pub struct Budgets {
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_error_rate: f64,
+}
+
+impl Budgets {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            p95_ms: config.latency_budget_p95_ms,
+            p99_ms: config.latency_budget_p99_ms,
+            max_error_rate: config.latency_budget_max_error_rate,
+        }
+    }
+}
+
+/// Latency percentiles for one metric (overall, or one operation for k6)
+#[derive(Debug, Serialize)]
+pub struct MetricResult {
+    pub name: String,
+    pub p95_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BudgetReport {
+    pub tool: &'static str,
+    pub budgets: Budgets,
+    pub error_rate: Option<f64>,
+    pub metrics: Vec<MetricResult>,
+    pub regressions: Vec<String>,
+    pub passed: bool,
+}
+
+/// Compare a k6 summary (`--summary-export` or `handleSummary` JSON) or a
+/// `vegeta report -type=json` document against the budgets
+pub fn check_results(results: &Value, budgets: &Budgets) -> Result<BudgetReport, String> {
+    let (tool, error_rate, metrics) = if results.get("metrics").is_some() {
+        parse_k6(results)
+    } else if results.get("latencies").is_some() {
+        parse_vegeta(results)
+    } else {
+        return Err("unrecognised results file; expected k6 summary or vegeta JSON report".to_string());
+    };
+
+    let mut regressions = Vec::new();
+    if let Some(rate) = error_rate {
+        if rate > budgets.max_error_rate {
+            regressions.push(format!("error rate {:.4} exceeds budget {}", rate, budgets.max_error_rate));
+        }
+    }
+    for metric in &metrics {
+        if let Some(p95) = metric.p95_ms.filter(|p95| *p95 > budgets.p95_ms) {
+            regressions.push(format!("{} p95 {:.2}ms exceeds budget {}ms", metric.name, p95, budgets.p95_ms));
+        }
+        if let Some(p99) = metric.p99_ms.filter(|p99| *p99 > budgets.p99_ms) {
+            regressions.push(format!("{} p99 {:.2}ms exceeds budget {}ms", metric.name, p99, budgets.p99_ms));
+        }
+    }
+
+    Ok(BudgetReport {
+        tool,
+        budgets: budgets.clone(),
+        error_rate,
+        passed: regressions.is_empty(),
+        metrics,
+        regressions,
+    })
+}
+
+fn parse_k6(results: &Value) -> (&'static str, Option<f64>, Vec<MetricResult>) {
+    // `--summary-export` puts stats on the metric itself, `handleSummary` nests them under `values`
+    let stats = |metric: &Value| -> Value { metric.get("values").unwrap_or(metric).clone() };
+
+    let error_rate = results["metrics"]
+        .get("http_req_failed")
+        .map(stats)
+        .and_then(|values| values.get("rate").or(values.get("value")).and_then(Value::as_f64));
+
+    let mut metrics: Vec<MetricResult> = results["metrics"]
+        .as_object()
+        .map(|metrics| {
+            metrics
+                .iter()
+                .filter(|(name, _)| name.starts_with("http_req_duration"))
+                .map(|(name, metric)| {
+                    let values = stats(metric);
+                    MetricResult {
+                        name: name.clone(),
+                        p95_ms: values["p(95)"].as_f64(),
+                        p99_ms: values["p(99)"].as_f64(),
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    metrics.sort_by(|a, b| a.name.cmp(&b.name));
+
+    ("k6", error_rate, metrics)
+}
+
+fn parse_vegeta(results: &Value) -> (&'static str, Option<f64>, Vec<MetricResult>) {
+    // Vegeta reports latencies in nanoseconds and success as a ratio
+    let millis = |value: &Value| value.as_f64().map(|nanos| nanos / 1_000_000.0);
+    let metric = MetricResult {
+        name: "latencies".to_string(),
+        p95_ms: millis(&results["latencies"]["95th"]),
+        p99_ms: millis(&results["latencies"]["99th"]),
+    };
+    let error_rate = results["success"].as_f64().map(|success| 1.0 - success);
+
+    ("vegeta", error_rate, vec![metric])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn budgets() -> Budgets {
+        Budgets { p95_ms: 200.0, p99_ms: 500.0, max_error_rate: 0.01 }
+    }
+
+    #[test]
+    fn k6_summary_within_budget_passes() {
+        let summary = json!({
+            "metrics": {
+                "http_req_duration": { "avg": 12.0, "p(95)": 40.0, "p(99)": 90.0, "max": 120.0 },
+                "http_req_duration{name:listUsers}": { "avg": 15.0, "p(95)": 55.0, "p(99)": 110.0, "max": 130.0 },
+                "http_req_failed": { "passes": 0, "fails": 1000, "value": 0.0 },
+            }
+        });
+
+        let report = check_results(&summary, &budgets()).unwrap();
+        assert!(report.passed);
+        assert_eq!(report.tool, "k6");
+        assert_eq!(report.metrics.len(), 2);
+    }
+
+    #[test]
+    fn k6_per_operation_regression_is_flagged() {
+        let summary = json!({
+            "metrics": {
+                "http_req_duration": { "values": { "p(95)": 150.0, "p(99)": 300.0 } },
+                "http_req_duration{name:createUser}": { "values": { "p(95)": 250.0, "p(99)": 700.0 } },
+                "http_req_failed": { "values": { "rate": 0.05 } },
+            }
+        });
+
+        let report = check_results(&summary, &budgets()).unwrap();
+        assert!(!report.passed);
+        assert_eq!(report.regressions.len(), 3);
+        assert!(report.regressions.iter().any(|r| r.starts_with("http_req_duration{name:createUser} p95")));
+        assert!(report.regressions.iter().any(|r| r.starts_with("error rate")));
+    }
+
+    #[test]
+    fn vegeta_report_uses_nanosecond_latencies() {
+        let report = json!({
+            "latencies": { "95th": 180_000_000u64, "99th": 600_000_000u64 },
+            "success": 1.0,
+        });
+
+        let report = check_results(&report, &budgets()).unwrap();
+        assert_eq!(report.tool, "vegeta");
+        assert_eq!(report.metrics[0].p95_ms, Some(180.0));
+        assert_eq!(report.regressions, vec!["latencies p99 600.00ms exceeds budget 500ms".to_string()]);
+    }
+
+    #[test]
+    fn unknown_results_are_rejected() {
+        assert!(check_results(&json!({ "foo": 1 }), &budgets()).is_err());
+    }
+}
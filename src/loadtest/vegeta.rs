@@ -0,0 +1,21 @@
+use std::fmt::Write;
+
+use crate::codegen::Operation;
+
+/// Vegeta targets file (`vegeta attack -targets=vegeta-targets.txt`).
+///
+/// Vegeta replays static requests, so only operations without path parameters
+/// or request bodies are included; use the k6 script for the write paths.
+pub fn generate(operations: &[&Operation], base_url: &str) -> String {
+    let base_url = base_url.trim_end_matches('/');
+    let mut out = String::new();
+    for operation in operations {
+        if !operation.path_params.is_empty() || operation.body.is_some() {
+            continue;
+        }
+        writeln!(out, "# {}", operation.operation_id).unwrap();
+        writeln!(out, "{} {}{}", operation.method, base_url, operation.path).unwrap();
+        out.push('\n');
+    }
+    out
+}
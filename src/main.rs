@@ -1,59 +1,326 @@
-mod config;
-mod error;
-mod middleware;
-mod response;
-mod domain;
-mod infrastructure;
-mod delivery;
-mod container;
-
-use config::Config;
+use rust_boilerplate::{backup, codegen, delivery, infrastructure, loadtest, middleware, response, smoke, supervisor, transfer};
+use rust_boilerplate::container::{self, AppContainer};
+use rust_boilerplate::container::boot::{BootReport, Listener};
+use rust_boilerplate::config::Config;
 use std::io;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-#[tokio::main]
-async fn main() -> io::Result<()> {
-    // Initialize tracing using infrastructure logger
-    infrastructure::init_logger();
+fn main() -> io::Result<()> {
+    let started = Instant::now();
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Configuration error: {err}");
+            std::process::exit(2);
+        }
+    };
+    // Built by hand so thread counts can follow the instance size
+    let runtime = if supervisor::supervises(&config) {
+        // The supervisor only waits on its server processes
+        tokio::runtime::Builder::new_current_thread().enable_all().build()?
+    } else {
+        infrastructure::RuntimeSettings::from_config(&config).build()?
+    };
+    runtime.block_on(run(config, started))
+}
 
-    // Load configuration
-    let config = Config::from_env();
-    tracing::info!("Starting server at {}:{}", config.server_host, config.server_port);
+async fn run(config: Config, started: Instant) -> io::Result<()> {
+    // Initialize tracing using infrastructure logger, forwarding ops alerts when a chat webhook is configured
+    let alerter = (!config.ops_alert_webhook_url.is_empty()).then(|| {
+        infrastructure::OpsAlerter::spawn(
+            config.ops_alert_webhook_url.clone(),
+            infrastructure::AlertFormat::from_name(&config.ops_alert_format),
+            format!("{} ({}, {})", config.deployment_id, config.deployment_color, config.region),
+            config.ops_alert_max_per_minute,
+            Duration::from_secs(config.ops_alert_dedup_secs),
+        )
+    });
+    // Keep audit events for the warehouse export when a journal is configured
+    let audit_journal = match container::audit_journal_path(&config, supervisor::worker_index()) {
+        Some(path) => Some(infrastructure::AuditJournal::open(&path)?),
+        None => None,
+    };
+    infrastructure::init_logger(
+        &config.log_level,
+        alerter.clone().map(infrastructure::OpsAlertLayer::new),
+        audit_journal.clone().map(infrastructure::AuditJournalLayer::new),
+    );
+    infrastructure::install_panic_alert_hook();
+
+    // `generate-clients [out_dir]` writes client SDKs from the OpenAPI spec and exits
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("generate-clients") {
+        let out_dir = args.get(2).map(String::as_str).unwrap_or("clients");
+        return codegen::write_clients(&delivery::openapi_spec(), std::path::Path::new(out_dir));
+    }
+
+    // `smoke [base_url]` runs post-deploy checks against a running instance and prints a JSON report
+    if args.get(1).map(String::as_str) == Some("smoke") {
+        let report = smoke::run(smoke::SmokeOptions::from_args(args.get(2).map(String::as_str))).await;
+        println!("{}", serde_json::to_string_pretty(&report).map_err(io::Error::other)?);
+        if !report.passed {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // `loadtest generate [out_dir] [base_url]` writes k6/Vegeta scripts; `loadtest check <results.json>` gates on latency budgets
+    if args.get(1).map(String::as_str) == Some("loadtest") {
+        match args.get(2).map(String::as_str) {
+            Some("generate") => {
+                let out_dir = args.get(3).map(String::as_str).unwrap_or("loadtest");
+                let base_url = args.get(4).map(String::as_str).unwrap_or("http://localhost:3000");
+                let budgets = loadtest::report::Budgets::from_config(&config);
+                return loadtest::write_scripts(&delivery::openapi_spec(), &budgets, std::path::Path::new(out_dir), base_url);
+            }
+            Some("check") => {
+                let Some(results) = args.get(3) else {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, "usage: loadtest check <results.json>"));
+                };
+                if !loadtest::check_results_file(std::path::Path::new(results), &config)? {
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "usage: loadtest generate [out_dir] [base_url] | loadtest check <results.json>",
+                ));
+            }
+        }
+    }
+
+    // `users export <file>` / `users import <file>` move users between stores as JSON lines
+    if args.get(1).map(String::as_str) == Some("users") {
+        let usage = || io::Error::new(io::ErrorKind::InvalidInput, "usage: users export <file> | users import <file>");
+        let path = std::path::Path::new(args.get(3).ok_or_else(usage)?);
+        let summary = match args.get(2).map(String::as_str) {
+            Some("export") => serde_json::json!({ "exported": transfer::export_users(&config, path).await? }),
+            Some("import") => serde_json::to_value(transfer::import_users(&config, path).await?).map_err(io::Error::other)?,
+            _ => return Err(usage()),
+        };
+        println!("{summary}");
+        return Ok(());
+    }
+
+    // `migrate-data --from <backend> --to <backend>` copies users between stores directly
+    if args.get(1).map(String::as_str) == Some("migrate-data") {
+        let usage = || io::Error::new(io::ErrorKind::InvalidInput, "usage: migrate-data --from <backend> --to <backend>");
+        let flag = |name: &str| {
+            let position = args.iter().position(|arg| arg == name).ok_or_else(usage)?;
+            transfer::Backend::parse(args.get(position + 1).ok_or_else(usage)?, &config)
+        };
+        let summary = transfer::migrate_data(&flag("--from")?, &flag("--to")?).await?;
+        println!("{}", serde_json::to_string(&summary).map_err(io::Error::other)?);
+        if !summary.verified {
+            return Err(io::Error::other("destination checksum does not match the source"));
+        }
+        return Ok(());
+    }
+
+    // `backup` writes an encrypted archive to BACKUP_DIR; `restore <file>` loads one
+    if args.get(1).map(String::as_str) == Some("backup") {
+        let summary = backup::backup_users(&config).await?;
+        println!("{}", serde_json::to_string(&summary).map_err(io::Error::other)?);
+        return Ok(());
+    }
+    if args.get(1).map(String::as_str) == Some("restore") {
+        let usage = || io::Error::new(io::ErrorKind::InvalidInput, "usage: restore <file>");
+        let summary = backup::restore_users(&config, std::path::Path::new(args.get(2).ok_or_else(usage)?)).await?;
+        println!("{}", serde_json::to_string(&summary).map_err(io::Error::other)?);
+        return Ok(());
+    }
+
+    // `audit export` ships the audit journal past its watermark; `audit replay <seq>` ships it again from `seq`.
+    // Under SERVER_PROCESSES each server process has its own journal, picked with `--worker <n>`.
+    if args.get(1).map(String::as_str) == Some("audit") {
+        let usage = || io::Error::new(io::ErrorKind::InvalidInput, "usage: audit export [--worker <n>] | audit replay <seq> [--worker <n>]");
+        let (mut positional, mut worker) = (Vec::new(), None);
+        let mut rest = args.iter().skip(2);
+        while let Some(arg) = rest.next() {
+            match arg.as_str() {
+                "--worker" => worker = Some(rest.next().and_then(|n| n.parse::<usize>().ok()).ok_or_else(usage)?),
+                arg => positional.push(arg),
+            }
+        }
+        match (worker, config.server_processes) {
+            (None, processes) if processes > 1 => {
+                let message = format!("SERVER_PROCESSES is {processes}; pick a journal with --worker 0..{}", processes - 1);
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+            }
+            (Some(worker), processes) if processes <= 1 || worker >= processes => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "--worker must be below SERVER_PROCESSES"));
+            }
+            _ => {}
+        }
+        let exporter = container::audit_exporter(&config, worker)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "set AUDIT_JOURNAL_PATH and AUDIT_EXPORT_SINK"))?;
+        let summary = match positional.as_slice() {
+            ["export"] => exporter.export_pending().await,
+            ["replay", seq] => exporter.replay(seq.parse().map_err(|_| usage())?).await,
+            _ => return Err(usage()),
+        };
+        println!("{}", serde_json::to_string(&summary.map_err(io::Error::other)?).map_err(io::Error::other)?);
+        return Ok(());
+    }
+
+    // Warn about insecure defaults, or refuse them under a production profile.
+    // Server processes under the supervisor were already checked by it.
+    if supervisor::worker_index().is_none() {
+        if let Err(err) = container::lint::enforce(&config) {
+            tracing::error!(error = %err, "Configuration error");
+            std::process::exit(2);
+        }
+    }
+
+    // SERVER_PROCESSES > 1 runs that many copies of this server sharing the port
+    if supervisor::supervises(&config) {
+        return supervisor::run(supervisor::SupervisorOptions::from_config(&config)).await;
+    }
+
+    response::set_profile(response::SerializationProfile::from_names(
+        &config.response_field_case,
+        &config.response_envelope,
+        &config.response_null_fields,
+        &config.response_error_format,
+    ));
+
+    // Build the container and initialize its components in dependency order
+    let mut container = AppContainer::new(&config);
+    // Archiving rewrites the journal, so it goes through the writer opened above
+    if let Some(archiver) = audit_journal.and_then(|journal| container::audit_archiver(&config, journal)) {
+        container.startup.add(Arc::new(archiver));
+    }
+    let components = match container.startup.start_all().await {
+        Ok(components) => components,
+        Err(err) => {
+            // Covers failed migrations and, on every restart, crash loops
+            tracing::error!(target: infrastructure::ALERT_TARGET, alert = "startup_failed", error = %err, "Startup failed");
+            if let Some(alerter) = &alerter {
+                // The process exits next; give the alert a moment to go out
+                let _ = tokio::time::timeout(Duration::from_secs(5), alerter.flush()).await;
+            }
+            return Err(io::Error::other(err));
+        }
+    };
 
     // Create router with clean architecture layers
-    let app = delivery::create_routes()
+    let mut app = delivery::create_app(&container);
+    if config.request_dedup_window_ms > 0 {
+        // Collapse double-submits before they reach the handlers
+        let dedup = middleware::RequestDeduplicator::new(Duration::from_millis(config.request_dedup_window_ms));
+        app = app.layer(axum::middleware::from_fn_with_state(Arc::new(dedup), middleware::dedup_middleware));
+    }
+    if config.anomaly_window_secs > 0 {
+        // Per-route baselines; inside the router so the matched route is known
+        app = app.layer(axum::middleware::from_fn_with_state(container.anomalies.clone(), middleware::anomaly_middleware));
+    }
+    if config.ops_alert_5xx_threshold > 0 {
+        // Alert when server errors pile up within a minute
+        let bursts = infrastructure::ErrorBurstDetector::new(config.ops_alert_5xx_threshold, Duration::from_secs(60));
+        app = app.layer(axum::middleware::from_fn_with_state(Arc::new(bursts), middleware::server_error_alert_middleware));
+    }
+    if config.user_max_concurrent_requests > 0 {
+        // Inside auth, so the principal is known
+        let limiter = infrastructure::ConcurrencyLimiter::new(config.user_max_concurrent_requests);
+        app = app.layer(axum::middleware::from_fn_with_state(Arc::new(limiter), middleware::user_concurrency_middleware));
+    }
+    // Inside auth, so the principal is known
+    app = app.layer(axum::middleware::from_fn_with_state(container.presence.clone(), middleware::presence_middleware));
+    // Locale, currency and units handlers format with, behind the `RequestContext` extractor
+    let defaults = infrastructure::RequestContext::from_settings(
+        &config.default_locale,
+        &config.default_currency,
+        &config.default_units,
+    );
+    app = app.layer(axum::middleware::from_fn_with_state(Arc::new(defaults), middleware::request_context_middleware));
+    // Inside auth, so cookie-authenticated writes are known
+    app = app.layer(axum::middleware::from_fn_with_state(container.csrf.clone(), middleware::csrf_middleware));
+    // Tag every log line of authenticated requests with the user and tenant
+    app = app.layer(axum::middleware::from_fn(middleware::principal_log_context_middleware));
+    // Resolve session tokens to the principal behind `AuthUser` / `MaybeAuthUser`
+    app = app.layer(axum::middleware::from_fn_with_state(container.sessions.clone(), middleware::auth_middleware));
+    // Resolve impersonation tokens and enforce their policy before any handler runs
+    app = app.layer(axum::middleware::from_fn_with_state(container.impersonation.clone(), middleware::impersonation_middleware));
+    if !container.plugins.is_empty() {
+        // Outside auth, so plugins see and may rewrite the credentials
+        app = app.layer(axum::middleware::from_fn_with_state(container.plugins.clone(), middleware::plugin_middleware));
+    }
+    // Believed for forwarding headers, and exempt from the per-IP connection limit
+    let trusted_proxies = Arc::new(middleware::TrustedProxies::parse(&config.trusted_proxies));
+    let app = app
+        // Send requests pinned to another region there before any work is done
+        .layer(axum::middleware::from_fn_with_state(container.region.clone(), middleware::region_middleware))
+        // Assign canary variant inside the request span and record it there
+        .layer(axum::middleware::from_fn_with_state(config.canary_percentage, middleware::canary_middleware))
         // Apply logging middleware layers
         .layer(axum::middleware::from_fn(middleware::security_logging_middleware))
         .layer(axum::middleware::from_fn(middleware::error_logging_middleware))
-        .layer(axum::middleware::from_fn(middleware::request_logging_middleware))
-        // Add HTTP tracing layer for distributed tracing
+        .layer(axum::middleware::from_fn_with_state(
+            Arc::new(middleware::AccessLogSampling::new(
+                config.access_log_sample_rate,
+                Duration::from_millis(config.access_log_slow_ms),
+            )),
+            middleware::request_logging_middleware,
+        ))
+        // Parse the User-Agent and resolve the client location once, before anything logs them
+        .layer(axum::middleware::from_fn_with_state(
+            middleware::ClientInfoDetail::from_name(&config.client_info_detail),
+            middleware::client_info_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(container.geoip.clone(), middleware::geoip_middleware))
+        // The client address, from the peer or behind `TRUSTED_PROXIES` their forwarding headers
+        .layer(axum::middleware::from_fn_with_state(
+            trusted_proxies.clone(),
+            middleware::client_ip_middleware,
+        ))
+        // The one root span per request; inner layers record their fields on it
         .layer(tower_http::trace::TraceLayer::new_for_http()
-            .make_span_with(|request: &axum::http::Request<_>| {
-                let correlation_id = middleware::extract_or_generate_correlation_id(request.headers());
-                tracing::info_span!(
-                    "http_request",
-                    correlation_id = %correlation_id,
-                    method = %request.method(),
-                    uri = %request.uri(),
-                )
-            })
-        );
+            .make_span_with(middleware::RequestSpan::new(&config.deployment_id, &config.deployment_color, &config.region)))
+        // Give errors from any layer or extractor the standard envelope
+        .layer(axum::middleware::from_fn(middleware::error_envelope_middleware))
+        // Resolve the correlation id once, before the span is created
+        .layer(axum::middleware::from_fn(middleware::correlation_id_middleware))
+        // Move WebSocket `?access_token=` into `Authorization` before anything logs the URI
+        .layer(axum::middleware::from_fn(middleware::websocket_token_middleware));
 
     // Start server
-    let listener = tokio::net::TcpListener::bind(format!("{}:{}", config.server_host, config.server_port))
-        .await?;
-
-    tracing::info!("Server listening on {}:{}", config.server_host, config.server_port);
-    tracing::info!("Available endpoints:");
-    tracing::info!("  GET  /api/health     - Health check");
-    tracing::info!("  GET  /api/ready      - Readiness check");
-    tracing::info!("  GET  /api/live       - Liveness check");
-    tracing::info!("  GET  /api/users      - List users (with pagination)");
-    tracing::info!("  POST /api/users      - Create user");
-    tracing::info!("  GET  /api/users/:id  - Get user by ID");
-    tracing::info!("  PUT  /api/users/:id  - Update user (placeholder)");
-    tracing::info!("  DELETE /api/users/:id - Delete user (placeholder)");
-
-    axum::serve(listener, app).await?;
+    let addr = format!("{}:{}", config.server_host, config.server_port);
+    let worker = supervisor::worker_index();
+    let listener = if worker.is_some() {
+        supervisor::exit_when_orphaned();
+        supervisor::bind_shared(&addr).await?
+    } else {
+        tokio::net::TcpListener::bind(&addr).await?
+    };
+
+    // One structured startup record instead of scattered log lines
+    let report = BootReport::new(&config, components)
+        .with_worker(worker)
+        .with_listener(Listener { protocol: "http", address: listener.local_addr()?, shared: worker.is_some() })
+        .with_routes(container.routes.routes())
+        .finish(started);
+    report.log();
+    let _ = container.boot_report.set(report);
+
+    let limits = delivery::ConnectionLimits {
+        header_read_timeout: std::time::Duration::from_secs(config.header_read_timeout_secs),
+        max_headers: config.max_headers,
+        max_header_bytes: config.max_header_bytes,
+        max_connections_per_ip: config.max_connections_per_ip,
+        trusted_proxies,
+    };
+    let events = container.events.clone();
+    tokio::select! {
+        served = delivery::serve(listener, app, limits) => served?,
+        _ = supervisor::shutdown_signal() => {
+            // Close event streams with resume tokens before the process goes away
+            tracing::info!(streams = events.connections(), "Shutting down");
+            events.shutdown(Duration::from_millis(config.events_shutdown_grace_ms)).await;
+        }
+    }
 
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,87 @@
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Which requests `request_logging_middleware` logs.
+///
+/// Client and server errors and slow requests are always logged in full.
+/// Of the rest, a random `rate` share is; the others leave no access log
+/// lines at all. Every access log event carries `log_sample` with the reason
+/// it was kept and `sample_rate`, so counts can be scaled back up.
+#[derive(Debug, Clone)]
+pub struct AccessLogSampling {
+    rate: f64,
+    slow: Duration,
+}
+
+/// Why a request's access log lines were written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogSample {
+    /// Picked by the sample rate, before its outcome was known
+    Sampled,
+    /// Answered with a 4xx or 5xx status
+    Error,
+    /// Took at least the slow threshold
+    Slow,
+}
+
+impl LogSample {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogSample::Sampled => "sampled",
+            LogSample::Error => "error",
+            LogSample::Slow => "slow",
+        }
+    }
+}
+
+impl AccessLogSampling {
+    pub fn new(rate: f64, slow: Duration) -> Self {
+        Self { rate: rate.clamp(0.0, 1.0), slow }
+    }
+
+    /// Log every request
+    pub fn all() -> Self {
+        Self::new(1.0, Duration::ZERO)
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Whether to log a request in full from the start
+    pub fn sample(&self) -> bool {
+        self.rate >= 1.0 || (Uuid::new_v4().as_u128() % 1_000_000) < (self.rate * 1_000_000.0) as u128
+    }
+
+    /// Why a finished request is logged, if it is
+    pub fn keep(&self, sampled: bool, status_code: u16, duration: Duration) -> Option<LogSample> {
+        if status_code >= 400 {
+            Some(LogSample::Error)
+        } else if !self.slow.is_zero() && duration >= self.slow {
+            Some(LogSample::Slow)
+        } else {
+            sampled.then_some(LogSample::Sampled)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn problems_are_kept_whatever_the_rate() {
+        let sampling = AccessLogSampling::new(0.0, Duration::from_millis(500));
+        assert!(!(0..1000).any(|_| sampling.sample()));
+        assert_eq!(sampling.keep(false, 200, Duration::from_millis(10)), None);
+        assert_eq!(sampling.keep(false, 404, Duration::from_millis(10)), Some(LogSample::Error));
+        assert_eq!(sampling.keep(false, 503, Duration::from_millis(900)), Some(LogSample::Error));
+        assert_eq!(sampling.keep(false, 204, Duration::from_millis(500)), Some(LogSample::Slow));
+        assert_eq!(sampling.keep(true, 200, Duration::from_millis(10)), Some(LogSample::Sampled));
+
+        let all = AccessLogSampling::all();
+        assert!((0..1000).all(|_| all.sample()));
+        let kept = (0..10_000).filter(|_| AccessLogSampling::new(0.1, Duration::ZERO).sample()).count();
+        assert!((700..1300).contains(&kept), "{kept}");
+    }
+}
@@ -0,0 +1,36 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+use crate::response::unauthorized_response;
+
+/// Guards admin routes with a static bearer token (ADMIN_API_TOKEN).
+/// An empty token disables admin access entirely.
+pub async fn require_admin_token(
+    State(token): State<Arc<str>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if admin_token_matches(request.headers(), &token) {
+        next.run(request).await
+    } else {
+        unauthorized_response("Admin token required").into_response()
+    }
+}
+
+pub(crate) fn admin_token_matches(headers: &HeaderMap, token: &str) -> bool {
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    matches!(provided, Some(provided) if !token.is_empty() && constant_time_eq(provided.as_bytes(), token.as_bytes()))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
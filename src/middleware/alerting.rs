@@ -0,0 +1,36 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::infrastructure::{ErrorBurstDetector, ALERT_TARGET};
+
+/// Raises an ops alert when server errors reach the burst threshold within a window
+pub async fn server_error_alert_middleware(
+    State(detector): State<Arc<ErrorBurstDetector>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().clone();
+    let uri = request.uri().clone();
+    let response = next.run(request).await;
+
+    if response.status().is_server_error() {
+        if let Some(count) = detector.record(Instant::now()) {
+            tracing::error!(
+                target: ALERT_TARGET,
+                alert = "repeated_5xx",
+                count = count,
+                window_secs = detector.window().as_secs(),
+                last_method = %method,
+                last_path = uri.path(),
+                last_status = response.status().as_u16(),
+                "Repeated server errors"
+            );
+        }
+    }
+    response
+}
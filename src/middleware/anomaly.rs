@@ -0,0 +1,53 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::infrastructure::AnomalyDetector;
+
+/// Anomaly detection middleware.
+///
+/// Feeds each finished request into the per-route baselines, keyed by method
+/// and matched route so path parameters don't create a route per id.
+pub async fn anomaly_middleware(
+    State(detector): State<Arc<AnomalyDetector>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = match request.extensions().get::<MatchedPath>() {
+        Some(path) => format!("{} {}", request.method(), path.as_str()),
+        None => "unmatched".to_string(),
+    };
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    detector.record(&route, response.status().is_server_error(), started.elapsed());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Router};
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn requests_are_recorded_under_their_route_template() {
+        let detector = Arc::new(AnomalyDetector::new(Duration::from_secs(60)));
+        let app = Router::new()
+            .nest("/api", Router::new().route("/users/:id", get(|| async { "ok" })))
+            .layer(axum::middleware::from_fn_with_state(detector.clone(), anomaly_middleware));
+
+        for id in ["1", "2"] {
+            let request = Request::get(format!("/api/users/{}", id)).body(Body::empty()).unwrap();
+            app.clone().oneshot(request).await.unwrap();
+        }
+        app.oneshot(Request::get("/nope").body(Body::empty()).unwrap()).await.unwrap();
+
+        assert_eq!(detector.routes(), vec!["GET /api/users/:id".to_string(), "unmatched".to_string()]);
+    }
+}
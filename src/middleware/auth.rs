@@ -0,0 +1,190 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+use super::cookie;
+use crate::domain::session::entities::{hash_token, Principal, SessionKind};
+use crate::domain::session::feature::SESSION_COOKIE;
+use crate::domain::session::repository::SessionStore;
+use crate::response::internal_error_response;
+
+/// Marks requests whose session came from the `session` cookie rather than a
+/// bearer token; browsers attach it cross-site, so `csrf_middleware` checks
+/// their unsafe methods
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CookieSession;
+
+/// Auth middleware.
+///
+/// Resolves a bearer session token, or failing that the `session` cookie, to a
+/// `Principal` request extension, which handlers read through the `AuthUser`
+/// and `MaybeAuthUser` extractors.
+/// Requests without a token, or with one that isn't an active session (the
+/// admin token, an expired session, a refresh token), pass through without a
+/// principal; routes that need one reject them in the extractor. Impersonation
+/// tokens resolve too, as bearer tokens only, after `impersonation_middleware`
+/// has applied its policy.
+pub async fn auth_middleware(
+    State(sessions): State<Arc<dyn SessionStore>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let headers = request.headers();
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let (token_hash, from_cookie) = match bearer {
+        Some(token) => (hash_token(token), false),
+        None => match cookie(headers, SESSION_COOKIE) {
+            Some(token) => (hash_token(token), true),
+            None => return next.run(request).await,
+        },
+    };
+
+    match sessions.find_by_token_hash(&token_hash).await {
+        Ok(Some(session)) if session.kind == SessionKind::Session || (!from_cookie && session.kind != SessionKind::RefreshToken) => {
+            request.extensions_mut().insert(Principal::from_session(&session));
+            if from_cookie {
+                request.extensions_mut().insert(CookieSession);
+            }
+        }
+        Ok(_) => {}
+        Err(_) => return internal_error_response("Failed to verify session token").into_response(),
+    }
+    next.run(request).await
+}
+
+/// Query parameter WebSocket clients pass their token in
+pub const ACCESS_TOKEN_PARAM: &str = "access_token";
+
+/// Outermost layer for WebSocket handshakes, which browsers cannot add an
+/// `Authorization` header to: moves `?access_token=` into that header and
+/// drops it from the URI before anything logs it. Other requests must send
+/// the header.
+pub async fn websocket_token_middleware(mut request: Request, next: Next) -> Response {
+    let upgrade = request
+        .headers()
+        .get(header::UPGRADE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("websocket"));
+    if upgrade {
+        if let Some((uri, token)) = take_query_param(request.uri(), ACCESS_TOKEN_PARAM) {
+            *request.uri_mut() = uri;
+            let headers = request.headers_mut();
+            if !headers.contains_key(header::AUTHORIZATION) {
+                if let Ok(value) = HeaderValue::from_str(&format!("Bearer {token}")) {
+                    headers.insert(header::AUTHORIZATION, value);
+                }
+            }
+        }
+    }
+    next.run(request).await
+}
+
+/// `uri` without the first `name` query parameter, and that parameter's raw value
+fn take_query_param(uri: &Uri, name: &str) -> Option<(Uri, String)> {
+    let query = uri.query()?;
+    let mut value = None;
+    let rest: Vec<&str> = query
+        .split('&')
+        .filter(|pair| match pair.split_once('=') {
+            Some((key, token)) if key == name && value.is_none() => {
+                value = Some(token.to_string());
+                false
+            }
+            _ => true,
+        })
+        .collect();
+    let value = value?;
+    let path_and_query = if rest.is_empty() { uri.path().to_string() } else { format!("{}?{}", uri.path(), rest.join("&")) };
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(path_and_query.parse().ok()?);
+    Some((Uri::from_parts(parts).ok()?, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delivery::{fake_principal, AuthUser, MaybeAuthUser, WithPrincipal};
+    use crate::domain::session::entities::Session;
+    use crate::domain::session::repository::InMemorySessionStore;
+    use axum::{body::Body, http::StatusCode, routing::get, Router};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn body(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn session_tokens_resolve_to_principals_for_the_extractors() {
+        let sessions: Arc<dyn SessionStore> = Arc::new(InMemorySessionStore::new());
+        let mut session = Session::new(Uuid::new_v4(), SessionKind::Session, chrono::Duration::hours(1));
+        session.roles = vec!["editor".to_string()];
+        let token = session.issue_token("ses_");
+        sessions.save(session.clone()).await.unwrap();
+
+        let app = Router::new()
+            .route("/me", get(|AuthUser(principal): AuthUser| async move { principal.user_id.to_string() }))
+            .route(
+                "/maybe",
+                get(|MaybeAuthUser(principal): MaybeAuthUser| async move {
+                    principal.map_or("anonymous".to_string(), |principal| principal.roles.join(","))
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(sessions, auth_middleware));
+        let get = |path: &str, token: Option<&str>| {
+            let mut request = Request::get(path);
+            if let Some(token) = token {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        let response = app.clone().oneshot(get("/me", Some(&token))).await.unwrap();
+        assert_eq!(body(response).await, session.user_id.to_string());
+        let response = app.clone().oneshot(get("/maybe", Some(&token))).await.unwrap();
+        assert_eq!(body(response).await, "editor");
+
+        let response = app.clone().oneshot(get("/me", Some("unknown"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app.clone().oneshot(get("/maybe", None)).await.unwrap();
+        assert_eq!(body(response).await, "anonymous");
+
+        // Tests can skip token issuing and inject a principal directly
+        let principal = fake_principal(Uuid::new_v4(), &["admin"]);
+        let request = Request::get("/maybe").with_principal(principal).body(Body::empty()).unwrap();
+        assert_eq!(body(app.oneshot(request).await.unwrap()).await, "admin");
+    }
+
+    #[tokio::test]
+    async fn websocket_handshakes_may_pass_the_token_in_the_query() {
+        let app = Router::new()
+            .route(
+                "/events",
+                get(|request: Request| async move {
+                    let authorization = request.headers().get(header::AUTHORIZATION).map(|value| value.to_str().unwrap().to_string());
+                    format!("{} {}", request.uri(), authorization.unwrap_or_default())
+                }),
+            )
+            .layer(axum::middleware::from_fn(websocket_token_middleware));
+        let request = |upgrade: bool| {
+            let mut request = Request::get("/events?topics=presence&access_token=ses_abc");
+            if upgrade {
+                request = request.header(header::UPGRADE, "websocket");
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        let response = app.clone().oneshot(request(true)).await.unwrap();
+        assert_eq!(body(response).await, "/events?topics=presence Bearer ses_abc");
+        let response = app.oneshot(request(false)).await.unwrap();
+        assert_eq!(body(response).await, "/events?topics=presence&access_token=ses_abc ");
+    }
+}
@@ -0,0 +1,141 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use uuid::Uuid;
+
+use super::cookie;
+
+const CANARY_HEADER: &str = "x-canary";
+const CANARY_COOKIE: &str = "canary";
+
+/// Code path a request was assigned to, stored in request extensions so
+/// handlers can branch with `Extension<CanaryVariant>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanaryVariant {
+    Stable,
+    Canary,
+}
+
+impl CanaryVariant {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CanaryVariant::Stable => "stable",
+            CanaryVariant::Canary => "canary",
+        }
+    }
+}
+
+/// Canary assignment middleware.
+///
+/// An explicit `X-Canary` header or `canary` cookie wins; otherwise the request
+/// is assigned to the canary with probability `percentage` and the choice is
+/// pinned with a cookie so the client stays on the same variant.
+pub async fn canary_middleware(
+    State(percentage): State<u8>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let explicit = explicit_variant(request.headers());
+    let variant = explicit.unwrap_or_else(|| assign_variant(percentage));
+
+    request.extensions_mut().insert(variant);
+    tracing::Span::current().record("canary_variant", variant.as_str());
+    tracing::debug!(
+        canary_variant = variant.as_str(),
+        assigned = explicit.is_none(),
+        "Canary variant selected"
+    );
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert("x-canary-variant", HeaderValue::from_static(variant.as_str()));
+    if explicit.is_none() {
+        let cookie = format!("{}={}; Path=/; HttpOnly; SameSite=Lax", CANARY_COOKIE, variant.as_str());
+        if let Ok(value) = HeaderValue::from_str(&cookie) {
+            headers.append(header::SET_COOKIE, value);
+        }
+    }
+    response
+}
+
+fn explicit_variant(headers: &HeaderMap) -> Option<CanaryVariant> {
+    if let Some(value) = headers.get(CANARY_HEADER).and_then(|value| value.to_str().ok()) {
+        return Some(parse_variant(value));
+    }
+
+    cookie(headers, CANARY_COOKIE).map(parse_variant)
+}
+
+fn parse_variant(value: &str) -> CanaryVariant {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "canary" => CanaryVariant::Canary,
+        _ => CanaryVariant::Stable,
+    }
+}
+
+fn assign_variant(percentage: u8) -> CanaryVariant {
+    if (Uuid::new_v4().as_u128() % 100) < percentage.min(100) as u128 {
+        CanaryVariant::Canary
+    } else {
+        CanaryVariant::Stable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Extension, Router};
+    use tower::ServiceExt;
+
+    fn app(percentage: u8) -> Router {
+        Router::new()
+            .route("/", get(|Extension(variant): Extension<CanaryVariant>| async move { variant.as_str() }))
+            .layer(axum::middleware::from_fn_with_state(percentage, canary_middleware))
+    }
+
+    async fn variant(app: &Router, request: Request) -> (String, Option<String>) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let header = response.headers()["x-canary-variant"].to_str().unwrap().to_string();
+        let cookie = response.headers().get(header::SET_COOKIE).map(|value| value.to_str().unwrap().to_string());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, header.as_bytes(), "handlers see the variant the response reports");
+        (header, cookie)
+    }
+
+    fn plain() -> Request {
+        Request::get("/").body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn header_and_cookie_override_the_assignment() {
+        let never = app(0);
+        let header = Request::get("/").header(CANARY_HEADER, "true").body(Body::empty()).unwrap();
+        assert_eq!(variant(&never, header).await, ("canary".to_string(), None));
+        let cookie = Request::get("/").header(header::COOKIE, "theme=dark; canary=canary").body(Body::empty()).unwrap();
+        assert_eq!(variant(&never, cookie).await, ("canary".to_string(), None));
+
+        let always = app(100);
+        let header = Request::get("/").header(CANARY_HEADER, "0").body(Body::empty()).unwrap();
+        assert_eq!(variant(&always, header).await.0, "stable");
+    }
+
+    #[tokio::test]
+    async fn assignments_follow_the_percentage_and_are_pinned_with_a_cookie() {
+        let (stable, cookie) = variant(&app(0), plain()).await;
+        assert_eq!((stable.as_str(), cookie.as_deref()), ("stable", Some("canary=stable; Path=/; HttpOnly; SameSite=Lax")));
+        assert_eq!(variant(&app(100), plain()).await.0, "canary");
+
+        let split = app(30);
+        let mut canaries = 0;
+        for _ in 0..2000 {
+            if variant(&split, plain()).await.0 == "canary" {
+                canaries += 1;
+            }
+        }
+        // 600 expected; the band is about six standard deviations either side
+        assert!((480..=720).contains(&canaries), "{canaries} of 2000 on the canary");
+    }
+}
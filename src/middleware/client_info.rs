@@ -0,0 +1,179 @@
+use axum::{
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use serde::Serialize;
+use woothee::parser::Parser;
+
+const UNKNOWN: &str = woothee::woothee::VALUE_UNKNOWN;
+/// Name the parser gives agents that look automated but match no known crawler
+const UNIDENTIFIED_CRAWLER: &str = "misc crawler";
+
+/// How much of the User-Agent is kept; versions add fingerprinting entropy,
+/// so they are only parsed when explicitly asked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientInfoDetail {
+    Off,
+    Coarse,
+    Full,
+}
+
+impl ClientInfoDetail {
+    pub fn from_name(name: &str) -> Self {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "off" | "none" | "false" => ClientInfoDetail::Off,
+            "full" => ClientInfoDetail::Full,
+            _ => ClientInfoDetail::Coarse,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceCategory {
+    Desktop,
+    Smartphone,
+    MobilePhone,
+    Appliance,
+    Crawler,
+    Other,
+}
+
+impl DeviceCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeviceCategory::Desktop => "desktop",
+            DeviceCategory::Smartphone => "smartphone",
+            DeviceCategory::MobilePhone => "mobile_phone",
+            DeviceCategory::Appliance => "appliance",
+            DeviceCategory::Crawler => "crawler",
+            DeviceCategory::Other => "other",
+        }
+    }
+}
+
+/// Parsed User-Agent, stored in request extensions by `client_info_middleware`
+/// so handlers can use `Extension<ClientInfo>`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClientInfo {
+    pub browser: String,
+    pub browser_version: Option<String>,
+    pub os: String,
+    pub os_version: Option<String>,
+    pub device: DeviceCategory,
+    pub is_bot: bool,
+}
+
+impl ClientInfo {
+    pub fn parse(user_agent: &str, detail: ClientInfoDetail) -> Self {
+        let parsed = Parser::new().parse(user_agent);
+        let field = |value: Option<&str>| {
+            value
+                .filter(|value| !value.is_empty() && *value != UNKNOWN)
+                .map(str::to_string)
+        };
+        let version = |value: Option<&str>| match detail {
+            ClientInfoDetail::Full => field(value),
+            _ => None,
+        };
+
+        let device = match parsed.as_ref().map(|parsed| parsed.category) {
+            Some("pc") => DeviceCategory::Desktop,
+            Some("smartphone") => DeviceCategory::Smartphone,
+            Some("mobilephone") => DeviceCategory::MobilePhone,
+            Some("appliance") => DeviceCategory::Appliance,
+            Some("crawler") => DeviceCategory::Crawler,
+            _ => DeviceCategory::Other,
+        };
+
+        Self {
+            browser: field(parsed.as_ref().map(|parsed| parsed.name)).unwrap_or_else(|| "unknown".to_string()),
+            browser_version: version(parsed.as_ref().map(|parsed| parsed.version)),
+            os: field(parsed.as_ref().map(|parsed| parsed.os)).unwrap_or_else(|| "unknown".to_string()),
+            os_version: version(parsed.as_ref().map(|parsed| parsed.os_version.as_ref())),
+            device,
+            is_bot: device == DeviceCategory::Crawler,
+        }
+    }
+
+    /// A crawler the parser recognised by name (Googlebot, Bingbot, ...), as
+    /// opposed to an agent that merely calls itself a bot
+    pub fn is_known_crawler(&self) -> bool {
+        self.is_bot && self.browser != UNIDENTIFIED_CRAWLER && self.browser != "unknown"
+    }
+}
+
+/// Client info middleware.
+///
+/// Parses the User-Agent once per request and stores the result in request
+/// extensions for logging, security checks and handlers. When the detail
+/// level is `Off` nothing is parsed and no client fields are logged. Each
+/// finished request emits one `client_analytics` event.
+pub async fn client_info_middleware(
+    State(detail): State<ClientInfoDetail>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if detail == ClientInfoDetail::Off {
+        return next.run(request).await;
+    }
+
+    let user_agent = request
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let client = ClientInfo::parse(user_agent, detail);
+    request.extensions_mut().insert(client.clone());
+
+    let method = request.method().clone();
+    let response = next.run(request).await;
+    tracing::info!(
+        target: "client_analytics",
+        method = %method,
+        status_code = response.status().as_u16(),
+        browser = %client.browser,
+        browser_version = client.browser_version.as_deref(),
+        os = %client.os,
+        os_version = client.os_version.as_deref(),
+        device = client.device.as_str(),
+        is_bot = client.is_bot,
+        "Client request"
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHROME_ON_WINDOWS: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+    const GOOGLEBOT: &str = "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
+
+    #[test]
+    fn coarse_detail_drops_versions() {
+        let full = ClientInfo::parse(CHROME_ON_WINDOWS, ClientInfoDetail::Full);
+        assert_eq!(full.browser, "Chrome");
+        assert_eq!(full.browser_version.as_deref(), Some("120.0.0.0"));
+        assert_eq!(full.device, DeviceCategory::Desktop);
+        assert!(!full.is_bot);
+
+        let coarse = ClientInfo::parse(CHROME_ON_WINDOWS, ClientInfoDetail::Coarse);
+        assert_eq!(coarse.browser, "Chrome");
+        assert_eq!(coarse.browser_version, None);
+        assert_eq!(coarse.os_version, None);
+    }
+
+    #[test]
+    fn crawlers_are_told_apart_from_self_declared_bots() {
+        let googlebot = ClientInfo::parse(GOOGLEBOT, ClientInfoDetail::Coarse);
+        assert!(googlebot.is_bot);
+        assert!(googlebot.is_known_crawler());
+
+        let unknown = ClientInfo::parse("my-scraper-bot/0.1", ClientInfoDetail::Coarse);
+        assert!(unknown.is_bot);
+        assert!(!unknown.is_known_crawler());
+    }
+}
@@ -0,0 +1,176 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::Response,
+};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+/// The address a request came from, resolved once by `client_ip_middleware`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+/// Addresses and CIDR ranges of the load balancers and proxies in front of
+/// the server; only their forwarding headers are believed
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies {
+    ranges: Vec<(IpAddr, u8)>,
+}
+
+impl TrustedProxies {
+    /// Comma-separated, e.g. `10.0.0.0/8,192.168.1.7`; entries that don't
+    /// parse are skipped with a warning
+    pub fn parse(spec: &str) -> Self {
+        let ranges = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .filter_map(|entry| {
+                let range = parse_range(entry);
+                if range.is_none() {
+                    tracing::warn!(entry, "Ignoring unparsable TRUSTED_PROXIES entry");
+                }
+                range
+            })
+            .collect();
+        Self { ranges }
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = canonical(ip);
+        self.ranges.iter().any(|(network, prefix)| match (network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => masked(u32::from(*network).into(), *prefix, 32) == masked(u32::from(ip).into(), *prefix, 32),
+            (IpAddr::V6(network), IpAddr::V6(ip)) => masked(u128::from(*network), *prefix, 128) == masked(u128::from(ip), *prefix, 128),
+            _ => false,
+        })
+    }
+
+    /// The client behind `peer`: `peer` itself unless it is a trusted proxy,
+    /// in which case `X-Forwarded-For` is walked from the right past further
+    /// trusted hops. A hop that doesn't parse ends the walk at the last
+    /// trusted address, since only the client writes anything left of it.
+    /// `X-Real-IP` is used only when a trusted peer sends no
+    /// `X-Forwarded-For` at all.
+    pub fn resolve(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.contains(peer) {
+            return peer;
+        }
+        let forwarded: Vec<Option<IpAddr>> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .map(|value| value.to_str().ok())
+            .flat_map(|value| value.unwrap_or_default().split(','))
+            .map(|hop| hop.trim().parse::<IpAddr>().ok())
+            .collect();
+        if forwarded.is_empty() {
+            return headers
+                .get("x-real-ip")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse().ok())
+                .unwrap_or(peer);
+        }
+        let mut client = peer;
+        for hop in forwarded.into_iter().rev() {
+            let Some(hop) = hop else { break };
+            client = hop;
+            if !self.contains(hop) {
+                break;
+            }
+        }
+        client
+    }
+}
+
+fn parse_range(entry: &str) -> Option<(IpAddr, u8)> {
+    let (address, prefix) = match entry.split_once('/') {
+        Some((address, prefix)) => (address.parse::<IpAddr>().ok()?, Some(prefix.parse::<u8>().ok()?)),
+        None => (entry.parse::<IpAddr>().ok()?, None),
+    };
+    let address = canonical(address);
+    let bits = if address.is_ipv4() { 32 } else { 128 };
+    let prefix = prefix.unwrap_or(bits);
+    (prefix <= bits).then_some((address, prefix))
+}
+
+/// IPv4-mapped IPv6 addresses, as dual-stack listeners report them, as IPv4
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+        IpAddr::V4(_) => ip,
+    }
+}
+
+fn masked(bits: u128, prefix: u8, width: u8) -> u128 {
+    if prefix == 0 {
+        0
+    } else {
+        bits >> (width - prefix)
+    }
+}
+
+/// Resolves the client address from the peer and, behind trusted proxies,
+/// their forwarding headers, and stores it as the `ClientIp` extension for
+/// rate limiting, GeoIP and logs. Requests without a peer address (only
+/// in tests) get none.
+pub async fn client_ip_middleware(
+    State(proxies): State<Arc<TrustedProxies>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let peer = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| addr.ip());
+    if let Some(peer) = peer {
+        let client = proxies.resolve(peer, request.headers());
+        request.extensions_mut().insert(ClientIp(client));
+    }
+    next.run(request).await
+}
+
+/// The resolved client address, or the peer address when the middleware
+/// did not run
+pub(crate) fn client_addr(request: &Request) -> Option<IpAddr> {
+    request
+        .extensions()
+        .get::<ClientIp>()
+        .map(|ClientIp(ip)| *ip)
+        .or_else(|| request.extensions().get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| addr.ip()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(forwarded: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", forwarded.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn forwarding_headers_are_only_believed_from_trusted_proxies() {
+        let proxies = TrustedProxies::parse("10.0.0.0/8, 192.168.1.7, nonsense");
+        let (client, spoofed): (IpAddr, IpAddr) = ("203.0.113.9".parse().unwrap(), "198.51.100.1".parse().unwrap());
+
+        // A direct client cannot pick its own address
+        assert_eq!(proxies.resolve(client, &headers("198.51.100.1")), client);
+        // Behind the proxy, the rightmost untrusted hop is the client, whatever it prepended
+        assert_eq!(proxies.resolve("10.1.2.3".parse().unwrap(), &headers("198.51.100.1, 203.0.113.9, 192.168.1.7")), client);
+        assert_ne!(proxies.resolve("10.1.2.3".parse().unwrap(), &headers("198.51.100.1, 203.0.113.9")), spoofed);
+        assert_eq!(proxies.resolve("::ffff:10.0.0.1".parse().unwrap(), &HeaderMap::new()), "::ffff:10.0.0.1".parse::<IpAddr>().unwrap());
+        assert!(proxies.contains("::ffff:192.168.1.7".parse().unwrap()));
+        assert!(!TrustedProxies::parse("").contains("10.0.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn junk_forwarded_hops_never_fall_back_to_x_real_ip() {
+        let proxies = TrustedProxies::parse("10.0.0.0/8");
+        let proxy: IpAddr = "10.1.2.3".parse().unwrap();
+        let mut forged = headers("junk");
+        forged.insert("x-real-ip", "198.51.100.1".parse().unwrap());
+
+        assert_eq!(proxies.resolve(proxy, &forged), proxy);
+        // Hops left of the junk are the client's own; the walk stops at the last trusted one
+        assert_eq!(proxies.resolve(proxy, &headers("198.51.100.1, junk, 10.9.9.9")), "10.9.9.9".parse::<IpAddr>().unwrap());
+        assert_eq!(proxies.resolve(proxy, &headers("junk, 203.0.113.9")), "203.0.113.9".parse::<IpAddr>().unwrap());
+    }
+}
@@ -0,0 +1,39 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+use crate::domain::session::entities::Principal;
+use crate::infrastructure::ConcurrencyLimiter;
+use crate::response::error_response;
+
+/// Per-user concurrency middleware.
+///
+/// Caps the requests an authenticated user has in flight; the excess gets 429
+/// with `Retry-After: 1`. Runs inside `auth_middleware`, which attaches the
+/// `Principal`. Anonymous requests are left to the per-IP rate limits.
+pub async fn user_concurrency_middleware(
+    State(limiter): State<Arc<ConcurrencyLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(user_id) = request.extensions().get::<Principal>().map(|principal| principal.user_id) else {
+        return next.run(request).await;
+    };
+
+    let Some(_permit) = limiter.try_acquire(&user_id.to_string()) else {
+        tracing::warn!(user_id = %user_id, "Too many concurrent requests for user");
+        let mut response = error_response(
+            StatusCode::TOO_MANY_REQUESTS,
+            "TOO_MANY_CONCURRENT_REQUESTS",
+            "Too many requests in flight for this user",
+        )
+        .into_response();
+        response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from_static("1"));
+        return response;
+    };
+    next.run(request).await
+}
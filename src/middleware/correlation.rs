@@ -0,0 +1,140 @@
+use axum::{
+    extract::Request,
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+use tower_http::trace::MakeSpan;
+use tracing::{field::Empty, Span};
+
+use super::extract_or_generate_correlation_id;
+
+/// Echoed on every response
+pub const CORRELATION_HEADER: &str = "x-correlation-id";
+
+/// Correlation id of the current request, resolved once by
+/// `correlation_id_middleware` and read from request extensions everywhere else
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorrelationId(pub Arc<str>);
+
+impl CorrelationId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Outermost layer: takes the caller's id or generates one, stores it in
+/// request extensions and echoes it on the response.
+pub async fn correlation_id_middleware(mut request: Request, next: Next) -> Response {
+    let correlation_id = CorrelationId(extract_or_generate_correlation_id(request.headers()).into());
+    request.extensions_mut().insert(correlation_id.clone());
+
+    let mut response = next.run(request).await;
+    if let Ok(value) = HeaderValue::from_str(correlation_id.as_str()) {
+        response.headers_mut().insert(CORRELATION_HEADER, value);
+    }
+    response
+}
+
+/// The id stored by `correlation_id_middleware`; requests that bypassed it
+/// (tests, routers served without the full stack) fall back to their headers
+pub fn correlation_id<B>(request: &axum::http::Request<B>) -> String {
+    match request.extensions().get::<CorrelationId>() {
+        Some(correlation_id) => correlation_id.as_str().to_string(),
+        None => extract_or_generate_correlation_id(request.headers()),
+    }
+}
+
+/// Builds the single root `http_request` span for `TraceLayer`.
+///
+/// Fields known later are declared empty here and filled in by the layers
+/// that learn them, with `Span::current().record(..)`.
+#[derive(Clone)]
+pub struct RequestSpan {
+    deployment_id: Arc<str>,
+    deployment_color: Arc<str>,
+    region: Arc<str>,
+}
+
+impl RequestSpan {
+    pub fn new(deployment_id: &str, deployment_color: &str, region: &str) -> Self {
+        Self {
+            deployment_id: deployment_id.into(),
+            deployment_color: deployment_color.into(),
+            region: region.into(),
+        }
+    }
+}
+
+impl<B> MakeSpan<B> for RequestSpan {
+    fn make_span(&mut self, request: &axum::http::Request<B>) -> Span {
+        tracing::info_span!(
+            "http_request",
+            correlation_id = %correlation_id(request),
+            method = %request.method(),
+            uri = %request.uri(),
+            version = ?request.version(),
+            deployment_id = %self.deployment_id,
+            deployment_color = %self.deployment_color,
+            region = %self.region,
+            canary_variant = Empty,
+            user_id = Empty,
+            tenant_id = Empty,
+            impersonated_by = Empty,
+            action_id = Empty,
+            attempt = Empty,
+            status_code = Empty,
+            duration_ms = Empty,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::{request_logging_middleware, AccessLogSampling};
+    use axum::{body::Body, routing::get, Extension, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+    use tower_http::trace::TraceLayer;
+    use tracing::span::{Attributes, Id};
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::Layer;
+
+    #[derive(Clone, Default)]
+    struct SpanCounter(Arc<AtomicUsize>);
+
+    impl<S: tracing::Subscriber> Layer<S> for SpanCounter {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _: &Id, _: Context<'_, S>) {
+            if attrs.metadata().name() == "http_request" {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    fn app() -> Router {
+        Router::new()
+            .route("/", get(|Extension(id): Extension<CorrelationId>| async move { id.as_str().to_string() }))
+            .layer(axum::middleware::from_fn_with_state(Arc::new(AccessLogSampling::all()), request_logging_middleware))
+            .layer(TraceLayer::new_for_http().make_span_with(RequestSpan::new("local", "blue", "local")))
+            .layer(axum::middleware::from_fn(correlation_id_middleware))
+    }
+
+    #[tokio::test]
+    async fn one_root_span_and_one_correlation_id_per_request() {
+        let spans = SpanCounter::default();
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(spans.clone()));
+
+        let response = app().oneshot(Request::get("/").body(Body::empty()).unwrap()).await.unwrap();
+        let echoed = response.headers()[CORRELATION_HEADER].to_str().unwrap().to_string();
+        let body = axum::body::to_bytes(response.into_body(), 1024).await.unwrap();
+        assert_eq!(body, echoed.as_bytes());
+        assert_eq!(spans.0.load(Ordering::SeqCst), 1);
+
+        let request = Request::get("/").header("x-request-id", "req-1").body(Body::empty()).unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        assert_eq!(response.headers()[CORRELATION_HEADER], "req-1");
+        assert_eq!(spans.0.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,94 @@
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+use super::{cookie, CookieSession};
+use crate::domain::session::entities::Principal;
+use crate::domain::session::feature::{CsrfTokens, CSRF_COOKIE, CSRF_HEADER};
+use crate::response::error_response;
+
+/// CSRF middleware.
+///
+/// Unsafe methods on requests authenticated by the session cookie must repeat
+/// the `csrf_token` cookie in the `X-CSRF-Token` header, as issued by
+/// `GET /api/auth/csrf` for that session. Other requests pass through: bearer
+/// tokens are never sent by the browser on its own, and safe methods must not
+/// change state. Refusals are 403 with `CSRF_TOKEN_MISSING` or
+/// `CSRF_TOKEN_INVALID`. Runs inside `auth_middleware`.
+pub async fn csrf_middleware(State(tokens): State<Arc<CsrfTokens>>, request: Request, next: Next) -> Response {
+    if request.method().is_safe() || request.extensions().get::<CookieSession>().is_none() {
+        return next.run(request).await;
+    }
+    let Some(principal) = request.extensions().get::<Principal>() else {
+        return next.run(request).await;
+    };
+
+    let headers = request.headers();
+    let header = headers.get(CSRF_HEADER).and_then(|value| value.to_str().ok());
+    if let Err(err) = tokens.verify(principal.session_id, cookie(headers, CSRF_COOKIE), header) {
+        tracing::warn!(
+            session_id = %principal.session_id,
+            method = %request.method(),
+            path = %request.uri().path(),
+            code = err.code(),
+            "Cookie-authenticated request refused by CSRF check"
+        );
+        return error_response(StatusCode::FORBIDDEN, err.code(), err.to_string()).into_response();
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::session::entities::{Session, SessionKind};
+    use crate::domain::session::feature::SESSION_COOKIE;
+    use crate::domain::session::repository::{InMemorySessionStore, SessionStore};
+    use crate::middleware::auth_middleware;
+    use axum::{body::Body, http::header, routing::post, Router};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn unsafe_cookie_requests_need_the_token_and_bearer_requests_do_not() {
+        let sessions: Arc<dyn SessionStore> = Arc::new(InMemorySessionStore::new());
+        let mut session = Session::new(Uuid::new_v4(), SessionKind::Session, chrono::Duration::hours(1));
+        let token = session.issue_token("ses_");
+        sessions.save(session.clone()).await.unwrap();
+        let tokens = Arc::new(CsrfTokens::new("secret"));
+        let csrf = tokens.issue(session.id);
+
+        let app = Router::new()
+            .route("/things", post(|| async { "created" }))
+            .layer(axum::middleware::from_fn_with_state(tokens, csrf_middleware))
+            .layer(axum::middleware::from_fn_with_state(sessions, auth_middleware));
+        let send = |cookies: String, csrf_header: Option<&str>| {
+            let mut request = Request::post("/things").header(header::COOKIE, cookies);
+            if let Some(value) = csrf_header {
+                request = request.header(CSRF_HEADER, value);
+            }
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+        let code = |response: Response| async move {
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()["error"]["code"].clone()
+        };
+
+        let response = send(format!("{SESSION_COOKIE}={token}"), None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(code(response).await, "CSRF_TOKEN_MISSING");
+        let response = send(format!("{SESSION_COOKIE}={token}; {CSRF_COOKIE}={csrf}"), Some("nonce.00")).await.unwrap();
+        assert_eq!(code(response).await, "CSRF_TOKEN_INVALID");
+        let response = send(format!("{SESSION_COOKIE}={token}; {CSRF_COOKIE}={csrf}"), Some(&csrf)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // The same session as a bearer token is not exposed to cross-site requests
+        let request = Request::post("/things").header(header::AUTHORIZATION, format!("Bearer {token}"));
+        let response = app.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,562 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::domain::session::entities::Principal;
+use crate::infrastructure::sync::Mutex;
+use crate::infrastructure::BodyReader;
+use crate::response::error_response;
+
+/// Bodies above this size are never buffered for deduplication
+const BODY_READER: BodyReader = BodyReader::new(1024 * 1024);
+/// Entries tracked at once; beyond this requests pass through untouched
+const MAX_ENTRIES: usize = 10_000;
+/// Client-chosen key naming one logical request across retries
+pub const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+
+/// One logical action and which attempt at it a request is. Every duplicate
+/// or retry of a request shares its id; audit events of the request carry it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditAction {
+    pub id: Uuid,
+    /// 1 for the request that started the action
+    pub attempt: u32,
+}
+
+/// What made two requests the same action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeySource {
+    IdempotencyKey,
+    BodyHash,
+}
+
+impl KeySource {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::IdempotencyKey => "idempotency_key",
+            Self::BodyHash => "body_hash",
+        }
+    }
+}
+
+/// A finished response kept for replay within the window
+#[derive(Clone)]
+struct RecordedResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+enum Progress {
+    // `None` until the first request finishes; a dropped sender means it never will
+    InFlight(watch::Receiver<Option<RecordedResponse>>),
+    Done(RecordedResponse),
+    /// Finished without a response to replay (server error, large body), so
+    /// a retry runs the handler again, as the next attempt of the action
+    Unrecorded,
+}
+
+struct Entry {
+    action_id: Uuid,
+    attempts: u32,
+    /// Hash of the first request's body; a keyed repeat must send the same
+    body_hash: u64,
+    progress: Progress,
+    /// When `progress` last changed
+    at: Instant,
+}
+
+/// Collapses identical requests (same method, URI, caller and body or
+/// `Idempotency-Key`) that arrive within `window` of each other onto a single
+/// handler invocation, and links them as attempts of one audited action.
+pub struct RequestDeduplicator {
+    window: Duration,
+    entries: Mutex<HashMap<u64, Entry>>,
+}
+
+impl RequestDeduplicator {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+enum Claim {
+    Leader(watch::Sender<Option<RecordedResponse>>, AuditAction),
+    Follower(watch::Receiver<Option<RecordedResponse>>, AuditAction),
+    Replay(RecordedResponse, AuditAction),
+    /// The key was used before with a different body
+    Mismatch(AuditAction),
+    PassThrough,
+}
+
+/// Request deduplication middleware.
+///
+/// Only unsafe methods are considered, and only from callers with a session,
+/// a bearer token or an `Idempotency-Key`; anonymous requests without a key
+/// could belong to anyone behind the same address. A duplicate of an in-flight request
+/// waits for and shares its response; a duplicate of a finished one gets the
+/// recorded response replayed. Replays carry `X-Deduplicated: true`. Server
+/// errors are not recorded so a retry reaches the handler again. Reusing an
+/// `Idempotency-Key` with a different body is answered with 422
+/// `IDEMPOTENCY_KEY_REUSED`.
+///
+/// Each request's `AuditAction` is recorded on the request span and added as
+/// an extension. Every attempt after the first also writes a
+/// `duplicate_request` event to the `audit` target. The audit log then shows
+/// one action with its attempts rather than unrelated repeats.
+pub async fn dedup_middleware(
+    State(dedup): State<Arc<RequestDeduplicator>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS)
+        || !BODY_READER.fits(request.body())
+    {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let body = match BODY_READER.read(body).await {
+        Ok(body) => body,
+        Err(err) => return err.into_response(),
+    };
+    let Some((key, source)) = request_key(&parts, &body) else {
+        return next.run(Request::from_parts(parts, Body::from(body))).await;
+    };
+    let body_hash = hash_body(&body);
+    let mut request = Request::from_parts(parts, Body::from(body));
+    let audit = |action: AuditAction, outcome: &str, request: &Request| {
+        record_action(action, source, outcome, request);
+    };
+
+    match dedup.claim(key, body_hash) {
+        Claim::PassThrough => next.run(request).await,
+        Claim::Mismatch(action) => {
+            tracing::warn!(action_id = %action.id, method = %request.method(), path = request.uri().path(), "Idempotency-Key reused with a different body");
+            error_response(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "IDEMPOTENCY_KEY_REUSED",
+                "This Idempotency-Key was already used with a different request body",
+            )
+            .into_response()
+        }
+        Claim::Replay(recorded, action) => {
+            audit(action, "replayed", &request);
+            replay(recorded)
+        }
+        Claim::Follower(mut receiver, action) => {
+            // Wait for the leader; if it went away without a response, run ourselves
+            let recorded = receiver.wait_for(Option::is_some).await.ok().and_then(|recorded| recorded.clone());
+            match recorded {
+                Some(recorded) => {
+                    audit(action, "shared", &request);
+                    replay(recorded)
+                }
+                None => {
+                    audit(action, "retried", &request);
+                    request.extensions_mut().insert(action);
+                    next.run(request).await
+                }
+            }
+        }
+        Claim::Leader(sender, action) => {
+            audit(action, "retried", &request);
+            request.extensions_mut().insert(action);
+            let response = next.run(request).await;
+            if !BODY_READER.fits(response.body()) {
+                // Streaming or large responses are passed on, not recorded
+                dedup.unrecorded(key);
+                return response;
+            }
+            let (parts, body) = response.into_parts();
+            let body = match BODY_READER.read(body).await {
+                Ok(body) => body,
+                Err(_) => {
+                    dedup.unrecorded(key);
+                    return Response::from_parts(parts, Body::empty());
+                }
+            };
+
+            let recorded = RecordedResponse {
+                status: parts.status,
+                headers: parts.headers.clone(),
+                body: body.clone(),
+            };
+            if recorded.status.is_server_error() {
+                dedup.unrecorded(key);
+            } else {
+                dedup.complete(key, recorded.clone());
+            }
+            // Followers already waiting get the response either way
+            let _ = sender.send(Some(recorded));
+            Response::from_parts(parts, Body::from(body))
+        }
+    }
+}
+
+/// On the request span for every attempt; as an audit event for repeats
+fn record_action(action: AuditAction, source: KeySource, outcome: &str, request: &Request) {
+    let span = tracing::Span::current();
+    span.record("action_id", tracing::field::display(action.id));
+    span.record("attempt", action.attempt);
+    if action.attempt > 1 {
+        tracing::info!(
+            target: "audit",
+            audit_event = "duplicate_request",
+            action_id = %action.id,
+            attempt = action.attempt,
+            matched_by = source.as_str(),
+            outcome,
+            method = %request.method(),
+            path = request.uri().path(),
+            "Request repeats an earlier action"
+        );
+    }
+}
+
+impl RequestDeduplicator {
+    fn claim(&self, key: u64, body_hash: u64) -> Claim {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+
+        if let Some(entry) = entries.get_mut(&key).filter(|entry| self.is_live(entry, now)) {
+            if entry.body_hash != body_hash {
+                return Claim::Mismatch(AuditAction { id: entry.action_id, attempt: entry.attempts });
+            }
+            entry.attempts += 1;
+            let action = AuditAction { id: entry.action_id, attempt: entry.attempts };
+            return match &entry.progress {
+                Progress::Done(recorded) => Claim::Replay(recorded.clone(), action),
+                Progress::InFlight(receiver) => Claim::Follower(receiver.clone(), action),
+                Progress::Unrecorded => {
+                    let (sender, receiver) = watch::channel(None);
+                    entry.progress = Progress::InFlight(receiver);
+                    entry.at = now;
+                    Claim::Leader(sender, action)
+                }
+            };
+        }
+
+        if entries.len() >= MAX_ENTRIES {
+            entries.retain(|_, entry| self.is_live(entry, now));
+            if entries.len() >= MAX_ENTRIES {
+                return Claim::PassThrough;
+            }
+        }
+
+        let (sender, receiver) = watch::channel(None);
+        let action = AuditAction { id: Uuid::new_v4(), attempt: 1 };
+        entries.insert(
+            key,
+            Entry { action_id: action.id, attempts: 1, body_hash, progress: Progress::InFlight(receiver), at: now },
+        );
+        Claim::Leader(sender, action)
+    }
+
+    fn is_live(&self, entry: &Entry, now: Instant) -> bool {
+        match &entry.progress {
+            Progress::InFlight(receiver) => receiver.has_changed().is_ok(),
+            Progress::Done(_) | Progress::Unrecorded => now.duration_since(entry.at) < self.window,
+        }
+    }
+
+    fn complete(&self, key: u64, recorded: RecordedResponse) {
+        self.set_progress(key, Progress::Done(recorded));
+    }
+
+    fn unrecorded(&self, key: u64) {
+        self.set_progress(key, Progress::Unrecorded);
+    }
+
+    fn set_progress(&self, key: u64, progress: Progress) {
+        if let Some(entry) = self.entries.lock().unwrap().get_mut(&key) {
+            entry.progress = progress;
+            entry.at = Instant::now();
+        }
+    }
+}
+
+/// Method, URI, caller, and the `Idempotency-Key` when there is one or else
+/// the body. The caller is the session the auth middleware resolved (from
+/// the bearer token or the `session` cookie), else the `Authorization`
+/// header, plus the peer address, so distinct users behind one NAT are never
+/// merged. `None` for anonymous requests without a key, which are not
+/// deduplicated.
+fn request_key(parts: &axum::http::request::Parts, body: &Bytes) -> Option<(u64, KeySource)> {
+    let session = parts.extensions.get::<Principal>().map(|principal| principal.session_id);
+    let authorization = parts.headers.get(header::AUTHORIZATION).map(HeaderValue::as_bytes);
+    if session.is_none() && authorization.is_none() && !parts.headers.contains_key(IDEMPOTENCY_KEY) {
+        return None;
+    }
+    let mut hasher = DefaultHasher::new();
+    parts.method.hash(&mut hasher);
+    parts.uri.hash(&mut hasher);
+    parts
+        .extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+        .hash(&mut hasher);
+    session.hash(&mut hasher);
+    authorization.hash(&mut hasher);
+    let source = match parts.headers.get(IDEMPOTENCY_KEY) {
+        Some(key) => {
+            key.as_bytes().hash(&mut hasher);
+            KeySource::IdempotencyKey
+        }
+        None => {
+            body.hash(&mut hasher);
+            KeySource::BodyHash
+        }
+    };
+    source.as_str().hash(&mut hasher);
+    Some((hasher.finish(), source))
+}
+
+fn hash_body(body: &Bytes) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn replay(recorded: RecordedResponse) -> Response {
+    let mut response = Response::new(Body::from(recorded.body));
+    *response.status_mut() = recorded.status;
+    *response.headers_mut() = recorded.headers;
+    response
+        .headers_mut()
+        .insert("x-deduplicated", HeaderValue::from_static("true"));
+    response
+}
+
+#[cfg(all(test, not(loom_model)))]
+mod tests {
+    use super::*;
+    use axum::{body::to_bytes, routing::post, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    fn app(window: Duration, calls: Arc<AtomicUsize>) -> Router {
+        Router::new()
+            .route(
+                "/users",
+                post(move |body: Bytes| {
+                    let calls = calls.clone();
+                    async move {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        body
+                    }
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                Arc::new(RequestDeduplicator::new(window)),
+                dedup_middleware,
+            ))
+    }
+
+    fn submit(body: &'static str) -> Request {
+        Request::post("/users").header(header::AUTHORIZATION, "Bearer token").body(Body::from(body)).unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn concurrent_duplicates_share_one_handler_call() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(Duration::from_secs(2), calls.clone());
+
+        let (first, second) = tokio::join!(app.clone().oneshot(submit("a")), app.clone().oneshot(submit("a")));
+        let (first, second) = (first.unwrap(), second.unwrap());
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(first.headers().get("x-deduplicated").is_none());
+        assert_eq!(second.headers()["x-deduplicated"], "true");
+        assert_eq!(to_bytes(second.into_body(), usize::MAX).await.unwrap(), "a");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn recent_duplicate_is_replayed_until_window_expires() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(Duration::from_secs(2), calls.clone());
+
+        app.clone().oneshot(submit("a")).await.unwrap();
+        let replayed = app.clone().oneshot(submit("a")).await.unwrap();
+        assert_eq!(replayed.headers()["x-deduplicated"], "true");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // A different body is a different request
+        app.clone().oneshot(submit("b")).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        tokio::time::advance(Duration::from_secs(2)).await;
+        app.clone().oneshot(submit("a")).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn sessions_sharing_an_address_are_never_replayed_each_other() {
+        use crate::delivery::{fake_principal, WithPrincipal};
+        use std::net::{Ipv4Addr, SocketAddr};
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(Duration::from_secs(2), calls.clone());
+        let peer = ConnectInfo(SocketAddr::from((Ipv4Addr::new(203, 0, 113, 7), 443)));
+        let as_user = |principal: &Principal| {
+            Request::post("/users")
+                .header(header::COOKIE, "session=ses_x")
+                .extension(peer)
+                .with_principal(principal.clone())
+                .body(Body::from("a"))
+                .unwrap()
+        };
+        let (alice, bob) = (fake_principal(Uuid::new_v4(), &[]), fake_principal(Uuid::new_v4(), &[]));
+
+        app.clone().oneshot(as_user(&alice)).await.unwrap();
+        let response = app.clone().oneshot(as_user(&bob)).await.unwrap();
+        assert!(response.headers().get("x-deduplicated").is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        let replayed = app.clone().oneshot(as_user(&alice)).await.unwrap();
+        assert_eq!(replayed.headers()["x-deduplicated"], "true");
+
+        // Anonymous requests without a key always reach the handler
+        let anonymous = || Request::post("/users").extension(peer).body(Body::from("a")).unwrap();
+        app.clone().oneshot(anonymous()).await.unwrap();
+        app.clone().oneshot(anonymous()).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_and_keyed_repeats_are_attempts_of_one_action() {
+        let statuses = Arc::new(Mutex::new(vec![StatusCode::OK, StatusCode::BAD_GATEWAY]));
+        let actions = Arc::new(Mutex::new(Vec::new()));
+        let (recorded, pending) = (actions.clone(), statuses.clone());
+        let app = Router::new()
+            .route(
+                "/orders",
+                post(move |axum::Extension(action): axum::Extension<AuditAction>| {
+                    recorded.lock().unwrap().push(action);
+                    let status = pending.lock().unwrap().pop().unwrap();
+                    async move { status }
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                Arc::new(RequestDeduplicator::new(Duration::from_secs(2))),
+                dedup_middleware,
+            ));
+        let order = |key: &str, body: &'static str| {
+            Request::post("/orders").header(IDEMPOTENCY_KEY, key).body(Body::from(body)).unwrap()
+        };
+
+        // The 502 is not replayed; the retry runs again as attempt 2
+        assert_eq!(app.clone().oneshot(order("k1", "a")).await.unwrap().status(), StatusCode::BAD_GATEWAY);
+        assert_eq!(app.clone().oneshot(order("k1", "a")).await.unwrap().status(), StatusCode::OK);
+        // Same key and body: the same action, so replayed
+        let replayed = app.clone().oneshot(order("k1", "a")).await.unwrap();
+        assert_eq!(replayed.headers()["x-deduplicated"], "true");
+
+        let actions = actions.lock().unwrap();
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[0].id, actions[1].id);
+        assert_eq!((actions[0].attempt, actions[1].attempt), (1, 2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_key_reused_with_another_body_is_refused() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(Duration::from_secs(2), calls.clone());
+        let keyed = |body: &'static str| Request::post("/users").header(IDEMPOTENCY_KEY, "k1").body(Body::from(body)).unwrap();
+
+        app.clone().oneshot(keyed("a")).await.unwrap();
+        let refused = app.clone().oneshot(keyed("b")).await.unwrap();
+        assert_eq!(refused.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = serde_json::from_slice(&to_bytes(refused.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(body["error"]["code"], "IDEMPOTENCY_KEY_REUSED");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // The original request is still replayed, and the key is free once the window passes
+        let replayed = app.clone().oneshot(keyed("a")).await.unwrap();
+        assert_eq!(to_bytes(replayed.into_body(), usize::MAX).await.unwrap(), "a");
+        tokio::time::advance(Duration::from_secs(2)).await;
+        assert_eq!(app.clone().oneshot(keyed("b")).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
+
+/// Run with `RUSTFLAGS="--cfg loom_model" cargo test --release dedup::loom_tests`
+#[cfg(all(test, loom_model))]
+mod loom_tests {
+    use super::*;
+    use crate::infrastructure::sync::model;
+    use loom::thread;
+
+    fn recorded() -> RecordedResponse {
+        RecordedResponse { status: StatusCode::CREATED, headers: HeaderMap::new(), body: Bytes::from_static(b"done") }
+    }
+
+    fn action(claim: &Claim) -> Option<AuditAction> {
+        match claim {
+            Claim::Leader(_, action) | Claim::Follower(_, action) | Claim::Replay(_, action) => Some(*action),
+            Claim::Mismatch(_) | Claim::PassThrough => None,
+        }
+    }
+
+    #[test]
+    fn concurrent_duplicates_elect_a_single_leader() {
+        model(|| {
+            let dedup = Arc::new(RequestDeduplicator::new(Duration::from_secs(60)));
+            let other = {
+                let dedup = dedup.clone();
+                thread::spawn(move || dedup.claim(1, 7))
+            };
+            let (mine, theirs) = (dedup.claim(1, 7), other.join().unwrap());
+
+            let leaders = [&mine, &theirs].into_iter().filter(|claim| matches!(claim, Claim::Leader(..))).count();
+            assert_eq!(leaders, 1);
+            let (mine, theirs) = (action(&mine).unwrap(), action(&theirs).unwrap());
+            assert_eq!(mine.id, theirs.id);
+            assert_eq!(mine.attempt + theirs.attempt, 3);
+        });
+    }
+
+    #[test]
+    fn a_finished_request_is_followed_or_replayed_never_rerun() {
+        model(|| {
+            let dedup = Arc::new(RequestDeduplicator::new(Duration::from_secs(60)));
+            let Claim::Leader(sender, first) = dedup.claim(1, 7) else { panic!("the first claim leads") };
+            let leader = {
+                let dedup = dedup.clone();
+                thread::spawn(move || {
+                    dedup.complete(1, recorded());
+                    let _ = sender.send(Some(recorded()));
+                })
+            };
+            let retry = dedup.claim(1, 7);
+            leader.join().unwrap();
+
+            match retry {
+                Claim::Replay(response, action) => assert_eq!((response.body, action.id), (recorded().body, first.id)),
+                // Waits for the response the leader sends either way
+                Claim::Follower(receiver, action) => {
+                    assert_eq!(action.id, first.id);
+                    assert_eq!(receiver.borrow().as_ref().map(|response| response.status), Some(StatusCode::CREATED));
+                }
+                _ => panic!("a retry of a finished request ran the handler again"),
+            }
+            assert!(matches!(dedup.claim(1, 8), Claim::Mismatch(action) if action.id == first.id));
+        });
+    }
+}
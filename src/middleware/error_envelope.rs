@@ -0,0 +1,170 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use std::collections::HashMap;
+
+use super::correlation_id;
+use crate::infrastructure::BodyReader;
+use crate::response::{error_response_with_details, profile, ErrorFormat, PROBLEM_CONTENT_TYPE};
+
+/// Plain-text error bodies up to this size become the envelope message
+const MESSAGE_READER: BodyReader = BodyReader::new(1024);
+/// Problem documents larger than this are passed on without `status` and `instance`
+const PROBLEM_READER: BodyReader = BodyReader::new(64 * 1024);
+
+/// Wraps error responses that aren't JSON in the standard `ApiResponse`
+/// envelope.
+///
+/// Extractor rejections, 404/405 from the router and errors from third-party
+/// layers answer with plain text or an empty body. Those become an
+/// `ApiResponse` error whose `details.correlation_id` matches the request. A
+/// short text body is kept as the message, and headers such as `Allow` and
+/// `Retry-After` are preserved. JSON errors pass through untouched.
+///
+/// With `RESPONSE_ERROR_FORMAT=problem`, error bodies are already problem
+/// documents; this adds the `status` and `instance` members only the
+/// response knows, and the `application/problem+json` media type.
+pub async fn error_envelope_middleware(request: Request, next: Next) -> Response {
+    let correlation_id = correlation_id(&request);
+    let instance = request.uri().path().to_string();
+    let response = next.run(request).await;
+
+    let status = response.status();
+    if !(status.is_client_error() || status.is_server_error()) {
+        return response;
+    }
+    let response = if is_json(response.headers()) { response } else { envelope(response, correlation_id).await };
+    match profile().errors {
+        ErrorFormat::Problem => finish_problem(response, &instance).await,
+        ErrorFormat::Envelope => response,
+    }
+}
+
+async fn envelope(response: Response, correlation_id: String) -> Response {
+    let status = response.status();
+    let (mut parts, body) = response.into_parts();
+    let message = match MESSAGE_READER.read(body).await {
+        Ok(bytes) if !bytes.is_empty() => String::from_utf8_lossy(&bytes).trim().to_string(),
+        _ => status.canonical_reason().unwrap_or("Error").to_string(),
+    };
+    let details = HashMap::from([("correlation_id".to_string(), json!(correlation_id))]);
+    let envelope = error_response_with_details(status, error_code(status), message, details).into_response();
+
+    parts.headers.remove(header::CONTENT_TYPE);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let (envelope_parts, body) = envelope.into_parts();
+    parts.headers.extend(envelope_parts.headers);
+    Response::from_parts(parts, body)
+}
+
+async fn finish_problem(response: Response, instance: &str) -> Response {
+    if !PROBLEM_READER.fits(response.body()) {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = PROBLEM_READER.read(body).await else {
+        return Response::from_parts(parts, Body::empty());
+    };
+    let mut problem = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(problem) if problem.get("type").is_some_and(serde_json::Value::is_string) => problem,
+        // Some other JSON error; leave it as it is
+        _ => return Response::from_parts(parts, Body::from(bytes)),
+    };
+    problem["status"] = json!(parts.status.as_u16());
+    if problem.get("instance").is_none() {
+        problem["instance"] = json!(instance);
+    }
+    let body = serde_json::to_vec(&problem).unwrap_or_default();
+    parts.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_CONTENT_TYPE));
+    parts.headers.insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+    Response::from_parts(parts, Body::from(body))
+}
+
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json") || value.contains("+json"))
+}
+
+/// Same codes our own handlers use, otherwise the status reason in upper snake case
+fn error_code(status: StatusCode) -> String {
+    match status {
+        StatusCode::INTERNAL_SERVER_ERROR => "INTERNAL_ERROR".to_string(),
+        StatusCode::TOO_MANY_REQUESTS => "RATE_LIMITED".to_string(),
+        StatusCode::GATEWAY_TIMEOUT => "TIMEOUT".to_string(),
+        _ => status
+            .canonical_reason()
+            .unwrap_or("ERROR")
+            .to_uppercase()
+            .replace([' ', '-'], "_"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::correlation_id_middleware;
+    use crate::response::not_found_response;
+    use axum::{body::Body, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/text", get(|| async { (StatusCode::PAYLOAD_TOO_LARGE, "Body exceeds 1MB") }))
+            .route("/json", get(|| async { not_found_response("User") }))
+            .layer(axum::middleware::from_fn(error_envelope_middleware))
+            .layer(axum::middleware::from_fn(correlation_id_middleware))
+    }
+
+    async fn send(method: &str, path: &str) -> (StatusCode, HeaderMap, serde_json::Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .header("x-request-id", "req-1")
+            .body(Body::empty())
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let body = axum::body::to_bytes(body, 4096).await.unwrap();
+        (parts.status, parts.headers, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn plain_errors_get_the_envelope_with_correlation_id() {
+        let (status, _, body) = send("GET", "/text").await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body["error"]["code"], "PAYLOAD_TOO_LARGE");
+        assert_eq!(body["error"]["message"], "Body exceeds 1MB");
+        assert_eq!(body["error"]["details"]["correlation_id"], "req-1");
+
+        let (status, headers, body) = send("POST", "/text").await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(headers[header::ALLOW], "GET,HEAD");
+        assert_eq!(body["error"]["code"], "METHOD_NOT_ALLOWED");
+        assert_eq!(body["error"]["message"], "Method Not Allowed");
+
+        let (_, _, body) = send("GET", "/json").await;
+        assert_eq!(body["error"]["code"], "NOT_FOUND");
+        assert!(body["error"]["details"].is_null());
+    }
+
+    #[tokio::test]
+    async fn problem_documents_get_their_status_instance_and_media_type() {
+        let problem = json!({ "type": "/problems/not-found", "title": "Not found", "detail": "User not found", "code": "NOT_FOUND" });
+        let response = (StatusCode::NOT_FOUND, axum::Json(problem)).into_response();
+        let (parts, body) = finish_problem(response, "/api/users/1").await.into_parts();
+        let body: serde_json::Value = serde_json::from_slice(&axum::body::to_bytes(body, 4096).await.unwrap()).unwrap();
+        assert_eq!(parts.headers[header::CONTENT_TYPE], PROBLEM_CONTENT_TYPE);
+        assert_eq!((body["status"].as_u64(), body["instance"].as_str()), (Some(404), Some("/api/users/1")));
+
+        let other = (StatusCode::CONFLICT, axum::Json(json!({ "message": "taken" }))).into_response();
+        let (parts, _) = finish_problem(other, "/api/users").await.into_parts();
+        assert_eq!(parts.headers[header::CONTENT_TYPE], "application/json");
+    }
+}
@@ -0,0 +1,26 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+
+use super::client_ip::client_addr;
+
+use crate::infrastructure::GeoIp;
+
+/// GeoIP enrichment middleware.
+///
+/// Resolves the client address to country and ASN and stores the
+/// `GeoLocation` in request extensions for logging, security checks and
+/// handlers. Addresses the databases know nothing about get no extension.
+pub async fn geoip_middleware(
+    State(geoip): State<Arc<GeoIp>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if let Some(location) = client_addr(&request).and_then(|ip| geoip.lookup(ip)) {
+        request.extensions_mut().insert(location);
+    }
+    next.run(request).await
+}
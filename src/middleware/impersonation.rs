@@ -0,0 +1,79 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+use crate::domain::session::feature::{ImpersonationService, IMPERSONATION_TOKEN_PREFIX};
+use crate::response::{error_response, internal_error_response, unauthorized_response};
+
+/// Impersonation middleware.
+///
+/// Requests carrying an impersonation bearer token are resolved to the
+/// impersonated user and checked against the impersonation policy. Allowed
+/// requests get an `Impersonation` extension, an `audit` log entry and
+/// `X-Impersonated-User` / `X-Impersonated-By` response headers. Other
+/// requests pass through untouched.
+pub async fn impersonation_middleware(
+    State(service): State<Arc<ImpersonationService>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .filter(|token| token.starts_with(IMPERSONATION_TOKEN_PREFIX))
+        .map(str::to_string);
+    let Some(token) = token else {
+        return next.run(request).await;
+    };
+
+    let impersonation = match service.authenticate(&token).await {
+        Ok(Some(impersonation)) => impersonation,
+        Ok(None) => return unauthorized_response("Impersonation token is invalid or has expired").into_response(),
+        Err(_) => return internal_error_response("Failed to verify impersonation token").into_response(),
+    };
+
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    if let Err(reason) = service.policy().check(&method, &path) {
+        tracing::warn!(
+            target: "audit",
+            audit_event = "impersonation_denied",
+            user_id = %impersonation.user_id,
+            session_id = %impersonation.session_id,
+            actor = %impersonation.actor,
+            method = %method,
+            path = %path,
+            reason = reason,
+            "Impersonated request denied by policy"
+        );
+        return error_response(StatusCode::FORBIDDEN, "IMPERSONATION_FORBIDDEN", reason).into_response();
+    }
+
+    tracing::info!(
+        target: "audit",
+        audit_event = "impersonated_request",
+        user_id = %impersonation.user_id,
+        session_id = %impersonation.session_id,
+        actor = %impersonation.actor,
+        method = %method,
+        path = %path,
+        "Impersonated request"
+    );
+    request.extensions_mut().insert(impersonation.clone());
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(&impersonation.user_id.to_string()) {
+        headers.insert("x-impersonated-user", value);
+    }
+    if let Ok(value) = HeaderValue::from_str(&impersonation.actor) {
+        headers.insert("x-impersonated-by", value);
+    }
+    response
+}
@@ -0,0 +1,85 @@
+use axum::{extract::Request, middleware::Next, response::Response};
+
+use crate::domain::session::entities::Principal;
+
+/// Principal log context middleware.
+///
+/// Runs inside `auth_middleware` and records who the request is for on the
+/// root `http_request` span: `user_id`, `tenant_id` when the session has a
+/// tenant, and `impersonated_by` for impersonation tokens. Every event logged
+/// later in the request carries them, so logs can be filtered by principal
+/// without handlers adding the fields. Anonymous requests leave them empty.
+pub async fn principal_log_context_middleware(request: Request, next: Next) -> Response {
+    if let Some(principal) = request.extensions().get::<Principal>() {
+        let span = tracing::Span::current();
+        span.record("user_id", tracing::field::display(principal.user_id));
+        if let Some(tenant) = &principal.tenant {
+            span.record("tenant_id", tenant.as_str());
+        }
+        if let Some(actor) = &principal.claims.impersonated_by {
+            span.record("impersonated_by", actor.as_str());
+        }
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delivery::{fake_principal, WithPrincipal};
+    use crate::middleware::RequestSpan;
+    use axum::{body::Body, routing::get, Router};
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+    use tower_http::trace::TraceLayer;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Id, Record};
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::Layer;
+    use uuid::Uuid;
+
+    /// Every value recorded on a span after it was created, as `name=value`
+    #[derive(Clone, Default)]
+    struct Recorded(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for Recorded {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.lock().unwrap().push(format!("{}={value}", field.name()));
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.lock().unwrap().push(format!("{}={value:?}", field.name()));
+        }
+    }
+
+    impl<S: tracing::Subscriber> Layer<S> for Recorded {
+        fn on_record(&self, _: &Id, values: &Record<'_>, _: Context<'_, S>) {
+            values.record(&mut self.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn principal_fields_are_recorded_on_the_request_span() {
+        let recorded = Recorded::default();
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(recorded.clone()));
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn(principal_log_context_middleware))
+            .layer(TraceLayer::new_for_http().make_span_with(RequestSpan::new("local", "blue", "local")));
+
+        let user_id = Uuid::new_v4();
+        let mut principal = fake_principal(user_id, &[]);
+        principal.tenant = Some("acme".to_string());
+        principal.claims.impersonated_by = Some("support@example.com".to_string());
+        let request = Request::get("/").with_principal(principal).body(Body::empty()).unwrap();
+        app.clone().oneshot(request).await.unwrap();
+        assert_eq!(
+            *recorded.0.lock().unwrap(),
+            [format!("user_id={user_id}"), "tenant_id=acme".to_string(), "impersonated_by=support@example.com".to_string()]
+        );
+
+        recorded.0.lock().unwrap().clear();
+        app.oneshot(Request::get("/").body(Body::empty()).unwrap()).await.unwrap();
+        assert!(recorded.0.lock().unwrap().is_empty());
+    }
+}
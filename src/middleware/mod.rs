@@ -1,52 +1,117 @@
+pub mod access_log;
+pub mod canary;
+pub mod region;
+pub mod admin;
+pub mod dedup;
+pub mod client_info;
+pub mod geoip;
+pub mod client_ip;
+pub mod anomaly;
+pub mod impersonation;
+pub mod auth;
+pub mod log_context;
+pub mod csrf;
+pub mod plugins;
+pub mod concurrency;
+pub mod presence;
+pub mod request_context;
+pub mod alerting;
+pub mod policy;
+pub mod ownership;
+pub mod correlation;
+pub mod error_envelope;
+
+pub use access_log::*;
+pub use canary::*;
+pub use region::*;
+pub use admin::*;
+pub use dedup::*;
+pub use client_info::*;
+pub use geoip::*;
+pub use client_ip::*;
+pub use anomaly::*;
+pub use impersonation::*;
+pub use auth::*;
+pub use log_context::*;
+pub use csrf::*;
+pub use plugins::*;
+pub use concurrency::*;
+pub use presence::*;
+pub use request_context::*;
+pub use alerting::*;
+pub use policy::*;
+pub use ownership::*;
+pub use correlation::*;
+pub use error_envelope::*;
+
 use axum::{
-    extract::Request,
-    http::{HeaderMap, StatusCode},
+    body::Body,
+    extract::{Request, State},
+    http::{HeaderMap, Method, StatusCode, Uri},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
+use bytes::BytesMut;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tracing::{info, warn, error, debug, Instrument};
+use tracing::{info, warn, error, debug};
 use uuid::Uuid;
-use log;
 
-/// Request logging middleware with correlation IDs and performance metrics
+use crate::infrastructure::{decode_text, BodyReader, GeoLocation, ObjectPool};
+use crate::response::{new_buffer, recycle_buffer, take_buffer};
+
+/// Tracing target of request and response bodies; set it to `debug` in
+/// `LOG_LEVEL` (e.g. `info,http_body=debug`) to log them
+pub const BODY_LOG_TARGET: &str = "http_body";
+/// Larger bodies are logged by size only
+const BODY_LOG_LIMIT: usize = 10_000;
+const BODY_LOG_READER: BodyReader = BodyReader::new(BODY_LOG_LIMIT);
+/// Buffers that logged request bodies are read into before being passed on
+pub static BODY_CAPTURE_BUFFERS: ObjectPool<BytesMut> =
+    ObjectPool::new("body_capture_buffers", 16, new_buffer, recycle_buffer);
+
+/// Request logging middleware with performance metrics.
+///
+/// Runs inside the root `http_request` span from `RequestSpan` and records
+/// the response status and duration on it. Which requests are logged
+/// follows `AccessLogSampling`; the incoming request line of one that wasn't
+/// sampled is held back until it turns out to be an error or slow.
 pub async fn request_logging_middleware(
+    State(sampling): State<Arc<AccessLogSampling>>,
     request: Request,
     next: Next,
 ) -> Response {
     let start_time = Instant::now();
-    let method = request.method().clone();
-    let uri = request.uri().clone();
-    let version = request.version();
-
-    // Generate correlation ID if not present
-    let correlation_id = extract_or_generate_correlation_id(request.headers());
-
-    // Create span for this request
-    let span = tracing::info_span!(
-        "http_request",
-        correlation_id = %correlation_id,
-        method = %method,
-        uri = %uri,
-        version = ?version,
-    );
+    let correlation_id = correlation_id(&request);
 
     // Log request details
-    log_request_details(&request, &correlation_id);
+    let sampled = sampling.sample();
+    let incoming = IncomingRequest::new(&request);
+    if sampled {
+        incoming.log(&correlation_id, LogSample::Sampled, sampling.rate());
+    }
+    let request = match log_request_body_if_debug(request, &correlation_id).await {
+        Ok(request) => request,
+        Err(response) => return response,
+    };
 
-    // Process request with span context
-    async {
-        let response = next.run(request).await;
+    let response = next.run(request).await;
 
-        let duration = start_time.elapsed();
-        let status = response.status();
-        let status_code = status.as_u16();
+    let duration = start_time.elapsed();
+    let status_code = response.status().as_u16();
+    let span = tracing::Span::current();
+    span.record("status_code", status_code);
+    span.record("duration_ms", duration.as_millis() as u64);
 
-        // Log response details
-        log_response_details(&response, &correlation_id, duration, status_code);
+    // Log response details
+    if let Some(reason) = sampling.keep(sampled, status_code, duration) {
+        if !sampled {
+            incoming.log(&correlation_id, reason, sampling.rate());
+        }
+        log_response_details(&response, &correlation_id, duration, status_code, reason, sampling.rate());
+    }
 
-        response
-    }.instrument(span).await
+    response
 }
 
 /// Enhanced error logging middleware
@@ -54,7 +119,7 @@ pub async fn error_logging_middleware(
     request: Request,
     next: Next,
 ) -> Response {
-    let correlation_id = extract_or_generate_correlation_id(request.headers());
+    let correlation_id = correlation_id(&request);
     let method = request.method().clone();
     let uri = request.uri().clone();
 
@@ -90,10 +155,13 @@ pub async fn security_logging_middleware(
     let headers = request.headers().clone();
     let uri = request.uri().clone();
     let method = request.method().clone();
-    let correlation_id = extract_or_generate_correlation_id(&headers);
+    let correlation_id = correlation_id(&request);
+    let client = request.extensions().get::<ClientInfo>().cloned();
+    let location = request.extensions().get::<GeoLocation>().cloned();
+    let client_ip = client_addr(&request);
 
     // Log suspicious patterns
-    detect_suspicious_activity(&headers, &uri, &method, &correlation_id);
+    detect_suspicious_activity(&headers, client.as_ref(), location.as_ref(), &uri, &method, &correlation_id);
 
     let response = next.run(request).await;
 
@@ -103,8 +171,11 @@ pub async fn security_logging_middleware(
             correlation_id = correlation_id,
             method = %method,
             uri = %uri,
-            user_agent = get_header_value(&headers, "user-agent"),
-            ip_address = get_client_ip(&headers),
+            client_browser = client.as_ref().map(|client| client.browser.as_str()),
+            client_device = client.as_ref().map(|client| client.device.as_str()),
+            ip_address = client_ip.map(tracing::field::display),
+            client_country = location.as_ref().and_then(|location| location.country.as_deref()),
+            client_asn = location.as_ref().and_then(|location| location.asn),
             "Authentication failed"
         );
     }
@@ -135,39 +206,60 @@ pub fn extract_or_generate_correlation_id(headers: &HeaderMap) -> String {
     Uuid::new_v4().to_string()
 }
 
-/// Log request details with structured logging
-fn log_request_details(request: &Request, correlation_id: &str) {
-    let method = request.method();
-    let uri = request.uri();
-    let user_agent = get_header_value(request.headers(), "user-agent");
-    let content_type = get_header_value(request.headers(), "content-type");
-    let content_length = get_header_value(request.headers(), "content-length");
-
-    // Log basic request info
-    info!(
-        correlation_id = correlation_id,
-        method = %method,
-        uri = %uri,
-        user_agent = user_agent,
-        content_type = content_type,
-        content_length = content_length,
-        "Incoming request"
-    );
-
-    // Log request body for POST/PUT requests (in debug mode only)
-    if (method == axum::http::Method::POST || method == axum::http::Method::PUT)
-        && log::log_enabled!(log::Level::Debug) {
-
-        debug!(correlation_id = correlation_id, "Request body will be logged by individual handlers");
+/// What the incoming request line logs, kept until the sampling decision
+/// is final
+struct IncomingRequest {
+    method: Method,
+    uri: Uri,
+    client: Option<ClientInfo>,
+    location: Option<GeoLocation>,
+    content_type: Option<String>,
+    content_length: Option<String>,
+}
+
+impl IncomingRequest {
+    fn new(request: &Request) -> Self {
+        Self {
+            method: request.method().clone(),
+            uri: request.uri().clone(),
+            client: request.extensions().get::<ClientInfo>().cloned(),
+            location: request.extensions().get::<GeoLocation>().cloned(),
+            content_type: get_header_value(request.headers(), "content-type"),
+            content_length: get_header_value(request.headers(), "content-length"),
+        }
     }
 
-    // Log query parameters
-    if let Some(query) = uri.query() {
-        debug!(
+    /// Log request details with structured logging
+    fn log(&self, correlation_id: &str, reason: LogSample, sample_rate: f64) {
+        let client = self.client.as_ref();
+        let location = self.location.as_ref();
+
+        // Log basic request info
+        info!(
             correlation_id = correlation_id,
-            query = query,
-            "Request query parameters"
+            method = %self.method,
+            uri = %self.uri,
+            client_browser = client.map(|client| client.browser.as_str()),
+            client_os = client.map(|client| client.os.as_str()),
+            client_device = client.map(|client| client.device.as_str()),
+            client_bot = client.map(|client| client.is_bot),
+            client_country = location.and_then(|location| location.country.as_deref()),
+            client_asn = location.and_then(|location| location.asn),
+            content_type = self.content_type,
+            content_length = self.content_length,
+            log_sample = reason.as_str(),
+            sample_rate = sample_rate,
+            "Incoming request"
         );
+
+        // Log query parameters
+        if let Some(query) = self.uri.query() {
+            debug!(
+                correlation_id = correlation_id,
+                query = query,
+                "Request query parameters"
+            );
+        }
     }
 }
 
@@ -177,6 +269,8 @@ fn log_response_details(
     correlation_id: &str,
     duration: Duration,
     status_code: u16,
+    reason: LogSample,
+    sample_rate: f64,
 ) {
     let content_type = get_header_value(response.headers(), "content-type");
     let content_length = get_header_value(response.headers(), "content-length");
@@ -188,6 +282,8 @@ fn log_response_details(
                 correlation_id = correlation_id,
                 status_code = status_code,
                 duration_ms = duration.as_millis(),
+                log_sample = reason.as_str(),
+                sample_rate = sample_rate,
                 content_type = content_type,
                 content_length = content_length,
                 "Request completed successfully"
@@ -198,6 +294,8 @@ fn log_response_details(
                 correlation_id = correlation_id,
                 status_code = status_code,
                 duration_ms = duration.as_millis(),
+                log_sample = reason.as_str(),
+                sample_rate = sample_rate,
                 content_type = content_type,
                 "Request redirected"
             );
@@ -207,6 +305,8 @@ fn log_response_details(
                 correlation_id = correlation_id,
                 status_code = status_code,
                 duration_ms = duration.as_millis(),
+                log_sample = reason.as_str(),
+                sample_rate = sample_rate,
                 content_type = content_type,
                 "Client error occurred"
             );
@@ -216,6 +316,8 @@ fn log_response_details(
                 correlation_id = correlation_id,
                 status_code = status_code,
                 duration_ms = duration.as_millis(),
+                log_sample = reason.as_str(),
+                sample_rate = sample_rate,
                 content_type = content_type,
                 "Server error occurred"
             );
@@ -225,6 +327,8 @@ fn log_response_details(
                 correlation_id = correlation_id,
                 status_code = status_code,
                 duration_ms = duration.as_millis(),
+                log_sample = reason.as_str(),
+                sample_rate = sample_rate,
                 "Unknown status code"
             );
         }
@@ -268,50 +372,58 @@ fn get_header_value(headers: &HeaderMap, header_name: &str) -> Option<String> {
         .map(|s| s.to_string())
 }
 
+/// Value of the named cookie in the `Cookie` headers
+pub fn cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(axum::http::header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(cookie, _)| *cookie == name)
+        .map(|(_, value)| value)
+}
+
 /// Detect suspicious request patterns
 fn detect_suspicious_activity(
     headers: &HeaderMap,
+    client: Option<&ClientInfo>,
+    location: Option<&GeoLocation>,
     uri: &axum::http::Uri,
     method: &axum::http::Method,
     correlation_id: &str,
 ) {
-    // Check for suspicious user agents
+    // Check for suspicious user agents; crawlers the parser recognises are expected traffic
     if let Some(user_agent) = get_header_value(headers, "user-agent") {
-        let suspicious_agents = [
-            "sqlmap", "nikto", "nmap", "masscan", "zap", "burp",
-            "scanner", "crawler", "bot", "spider"
-        ];
-
-        for agent in suspicious_agents {
-            if user_agent.to_lowercase().contains(&agent.to_string()) {
-                warn!(
-                    correlation_id = correlation_id,
-                    user_agent = user_agent,
-                    suspicious_pattern = agent,
-                    "Suspicious user agent detected"
-                );
+        let known_crawler = client.is_some_and(ClientInfo::is_known_crawler);
+        for agent in suspicious_user_agent_patterns(&user_agent) {
+            if known_crawler && GENERIC_BOT_PATTERNS.contains(&agent) {
+                continue;
             }
+            warn!(
+                correlation_id = correlation_id,
+                suspicious_pattern = agent,
+                client_browser = client.map(|client| client.browser.as_str()),
+                client_device = client.map(|client| client.device.as_str()),
+                client_country = location.and_then(|location| location.country.as_deref()),
+                client_asn = location.and_then(|location| location.asn),
+                "Suspicious user agent detected"
+            );
         }
     }
 
     // Check for suspicious URL patterns
     let uri_str = uri.to_string();
-    let suspicious_patterns = [
-        "..", "%2e%2e", "/etc/passwd", "/proc/self",
-        "<script", "javascript:", "eval(", "alert(",
-        "union select", "drop table", "insert into"
-    ];
-
-    for pattern in suspicious_patterns {
-        if uri_str.to_lowercase().contains(&pattern.to_string()) {
-            warn!(
-                correlation_id = correlation_id,
-                uri = uri_str,
-                suspicious_pattern = pattern,
-                method = %method,
-                "Suspicious URL pattern detected"
-            );
-        }
+    for pattern in suspicious_uri_patterns(&uri_str) {
+        warn!(
+            correlation_id = correlation_id,
+            uri = uri_str,
+            suspicious_pattern = pattern,
+            method = %method,
+            client_country = location.and_then(|location| location.country.as_deref()),
+            client_asn = location.and_then(|location| location.asn),
+            "Suspicious URL pattern detected"
+        );
     }
 
     // Check for large header sizes
@@ -329,68 +441,189 @@ fn detect_suspicious_activity(
     }
 }
 
-/// Attempt to get client IP from headers
-fn get_client_ip(headers: &HeaderMap) -> Option<String> {
-    const IP_HEADERS: [&str; 5] = [
-        "x-forwarded-for",
-        "x-real-ip",
-        "cf-connecting-ip",
-        "x-client-ip",
-        "x-forwarded"
+/// Patterns that also match legitimate crawlers
+const GENERIC_BOT_PATTERNS: [&str; 3] = ["crawler", "bot", "spider"];
+
+/// Scanner patterns found in a User-Agent header
+pub fn suspicious_user_agent_patterns(user_agent: &str) -> Vec<&'static str> {
+    const SUSPICIOUS_AGENTS: [&str; 10] = [
+        "sqlmap", "nikto", "nmap", "masscan", "zap", "burp",
+        "scanner", "crawler", "bot", "spider"
     ];
 
-    for header_name in IP_HEADERS {
-        if let Some(ip_value) = headers.get(header_name) {
-            if let Ok(ip_str) = ip_value.to_str() {
-                // Take the first IP if multiple are present
-                let ip = ip_str.split(',').next()?.trim();
-                return Some(ip.to_string());
-            }
-        }
-    }
+    let user_agent = user_agent.to_lowercase();
+    SUSPICIOUS_AGENTS
+        .into_iter()
+        .filter(|agent| user_agent.contains(agent))
+        .collect()
+}
+
+/// Path traversal, XSS and SQL injection patterns found in a request URI
+pub fn suspicious_uri_patterns(uri: &str) -> Vec<&'static str> {
+    const SUSPICIOUS_PATTERNS: [&str; 11] = [
+        "..", "%2e%2e", "/etc/passwd", "/proc/self",
+        "<script", "javascript:", "eval(", "alert(",
+        "union select", "drop table", "insert into"
+    ];
 
-    None
+    let uri = uri.to_lowercase();
+    SUSPICIOUS_PATTERNS
+        .into_iter()
+        .filter(|pattern| uri.contains(pattern))
+        .collect()
 }
 
-/// Request body logging for debugging (to be used in individual handlers)
-pub fn log_request_body(correlation_id: &str, endpoint: &str, body: &str) {
-    // Only log if debug level is enabled and body is not too large
-    if log::log_enabled!(log::Level::Debug) && body.len() < 10000 {
-        debug!(
-            correlation_id = correlation_id,
-            endpoint = endpoint,
-            body_size = body.len(),
-            body = body,
-            "Request body details"
-        );
-    } else if log::log_enabled!(log::Level::Info) {
-        info!(
+/// Buffer and log POST/PUT bodies when body logging is on, then hand the
+/// request on unchanged. Bodies of unknown or excessive size are not
+/// buffered; a body that can't be read is answered with the structured read
+/// error.
+async fn log_request_body_if_debug(request: Request, correlation_id: &str) -> Result<Request, Response> {
+    let method = request.method();
+    if !(method == axum::http::Method::POST || method == axum::http::Method::PUT) || !body_logging_enabled() {
+        return Ok(request);
+    }
+    if !BODY_LOG_READER.fits(request.body()) {
+        debug!(target: BODY_LOG_TARGET, correlation_id = correlation_id, "Request body too large or streamed; not logged");
+        return Ok(request);
+    }
+
+    let (parts, body) = request.into_parts();
+    let mut buffer = take_buffer(&BODY_CAPTURE_BUFFERS);
+    let read = BODY_LOG_READER.read_into(body, &mut buffer).await;
+    BODY_CAPTURE_BUFFERS.give(buffer);
+    let bytes = read.map_err(IntoResponse::into_response)?;
+    match decode_text(&parts.headers, &bytes) {
+        Ok(text) => log_request_body(correlation_id, parts.uri.path(), text),
+        Err(err) => debug!(
+            target: BODY_LOG_TARGET,
             correlation_id = correlation_id,
-            endpoint = endpoint,
-            body_size = body.len(),
-            "Request body received (too large for debug logging)"
-        );
+            body_size = bytes.len(),
+            error = %err,
+            "Request body not logged"
+        ),
     }
+    Ok(Request::from_parts(parts, Body::from(bytes)))
+}
+
+/// Whether the `http_body` target logs at debug; check before buffering a body
+pub fn body_logging_enabled() -> bool {
+    tracing::enabled!(target: BODY_LOG_TARGET, tracing::Level::DEBUG)
+}
+
+/// Request body logging for debugging; called by `request_logging_middleware`
+pub fn log_request_body(correlation_id: &str, endpoint: &str, body: &str) {
+    log_body("request", correlation_id, endpoint, body);
 }
 
 /// Response body logging for debugging (to be used in individual handlers)
 pub fn log_response_body(correlation_id: &str, endpoint: &str, body: &str) {
-    // Only log if debug level is enabled and body is not too large
-    if log::log_enabled!(log::Level::Debug) && body.len() < 10000 {
+    log_body("response", correlation_id, endpoint, body);
+}
+
+fn log_body(direction: &'static str, correlation_id: &str, endpoint: &str, body: &str) {
+    if body.len() <= BODY_LOG_LIMIT {
+        let body = redact_body(body);
+        let body = body.as_str();
         debug!(
+            target: BODY_LOG_TARGET,
+            direction,
             correlation_id = correlation_id,
             endpoint = endpoint,
             body_size = body.len(),
             body = body,
-            "Response body details"
+            "Body details"
         );
-    } else if log::log_enabled!(log::Level::Info) {
-        info!(
+    } else {
+        debug!(
+            target: BODY_LOG_TARGET,
+            direction,
             correlation_id = correlation_id,
             endpoint = endpoint,
             body_size = body.len(),
-            "Response body sent (too large for debug logging)"
+            "Body too large to log"
         );
     }
 }
 
+
+/// Fields whose values never reach the body log: any key naming a password,
+/// token or secret, and the OAuth proofs sent to the token endpoint
+fn is_sensitive(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    ["password", "token", "secret"].iter().any(|word| key.contains(word)) || key == "code_verifier" || key == "device_code"
+}
+
+/// The body with sensitive values replaced by `[redacted]`, for JSON and
+/// form-encoded bodies; anything else is logged as is
+fn redact_body(body: &str) -> String {
+    fn redact_json(value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(fields) => {
+                for (key, value) in fields.iter_mut() {
+                    if is_sensitive(key) {
+                        *value = serde_json::Value::from(crate::config::REDACTED);
+                    } else {
+                        redact_json(value);
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(redact_json),
+            _ => {}
+        }
+    }
+
+    if let Ok(mut value) = serde_json::from_str::<serde_json::Value>(body) {
+        redact_json(&mut value);
+        return value.to_string();
+    }
+    let is_form = !body.is_empty() && body.split('&').all(|pair| pair.contains('=') && !pair.contains(char::is_whitespace));
+    if !is_form {
+        return body.to_string();
+    }
+    body.split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((key, _)) if is_sensitive(key) => format!("{key}={}", crate::config::REDACTED),
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn secrets_never_reach_the_body_log() {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt().with_max_level(tracing::Level::DEBUG).with_writer(move || writer.clone()).finish();
+        tracing::subscriber::with_default(subscriber, || {
+            log_request_body("c1", "/api/users", r#"{"email":"ada@example.com","password":"hunter2-secret","profile":{"api_token":"tok_live_1"}}"#);
+            log_request_body("c2", "/api/oidc/token", "grant_type=authorization_code&code_verifier=verif1er&client_secret=sh%68&client_id=cli");
+            log_response_body("c3", "/api/auth/login", r#"{"data":{"token":"ses_abc123","session_id":"s1"}}"#);
+        });
+
+        let log = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        for secret in ["hunter2-secret", "tok_live_1", "verif1er", "sh%68", "ses_abc123"] {
+            assert!(!log.contains(secret), "{secret} was logged: {log}");
+        }
+        assert!(log.contains("ada@example.com") && log.contains("client_id=cli") && log.contains("[redacted]"));
+    }
+}
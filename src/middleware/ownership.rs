@@ -0,0 +1,200 @@
+use async_trait::async_trait;
+use axum::{
+    extract::{FromRequestParts, RawPathParams, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::domain::session::entities::Principal;
+use crate::response::{error_response, internal_error_response, not_found_response, unauthorized_response};
+
+/// Kinds of resources whose routes only their owner (or the admin token) may
+/// call; named in `RoutePolicy::owned`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceKind {
+    User,
+}
+
+impl ResourceKind {
+    /// As shown in `not found` messages
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::User => "User",
+        }
+    }
+}
+
+/// Looks up who owns a resource, by the `:id` segment of its route
+#[async_trait]
+pub trait OwnedResource: Send + Sync {
+    /// `None` when there is no such resource; the handler then answers 404
+    async fn owner_of(&self, id: &str) -> Result<Option<Uuid>, OwnershipError>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OwnershipError {
+    #[error("Owner lookup failed: {0}")]
+    Lookup(String),
+}
+
+/// What a signed-in user gets for someone else's resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disclosure {
+    /// 404, as if the resource did not exist
+    NotFound,
+    /// 403, which tells the caller the resource exists
+    Forbidden,
+}
+
+impl Disclosure {
+    /// `forbidden`; anything else hides the resource
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "forbidden" => Self::Forbidden,
+            _ => Self::NotFound,
+        }
+    }
+}
+
+/// The owner lookups of each resource kind, consulted by the policy
+/// middleware of owned routes
+pub struct Ownership {
+    resources: HashMap<ResourceKind, Arc<dyn OwnedResource>>,
+    disclosure: Disclosure,
+}
+
+impl Default for Ownership {
+    fn default() -> Self {
+        Self::new(Disclosure::NotFound)
+    }
+}
+
+impl Ownership {
+    pub fn new(disclosure: Disclosure) -> Self {
+        Self { resources: HashMap::new(), disclosure }
+    }
+
+    pub fn with_resource(mut self, kind: ResourceKind, resource: Arc<dyn OwnedResource>) -> Self {
+        self.resources.insert(kind, resource);
+        self
+    }
+
+    pub fn disclosure(&self) -> Disclosure {
+        self.disclosure
+    }
+
+    /// Whether `user_id` owns resource `id`; `None` when there is no such resource
+    pub async fn is_owner(&self, kind: ResourceKind, user_id: Uuid, id: &str) -> Result<Option<bool>, OwnershipError> {
+        let Some(resource) = self.resources.get(&kind) else {
+            return Err(OwnershipError::Lookup(format!("no owner lookup registered for {}", kind.name())));
+        };
+        Ok(resource.owner_of(id).await?.map(|owner| owner == user_id))
+    }
+
+    /// `Err` is the response for a caller who may not touch the resource:
+    /// 401 without a session, 404 or 403 (per the disclosure) for someone
+    /// else's. Missing resources pass, so the handler answers as usual.
+    pub async fn check(&self, kind: ResourceKind, request: Request) -> Result<Request, Response> {
+        let Some(user_id) = request.extensions().get::<Principal>().map(|principal| principal.user_id) else {
+            return Err(unauthorized_response("Authentication required").into_response());
+        };
+        if !self.resources.contains_key(&kind) {
+            tracing::error!(resource = kind.name(), "No owner lookup registered for owned route");
+            return Err(internal_error_response("Failed to check resource owner").into_response());
+        }
+
+        let (mut parts, body) = request.into_parts();
+        let id = RawPathParams::from_request_parts(&mut parts, &())
+            .await
+            .ok()
+            .and_then(|params| params.iter().find(|(name, _)| *name == "id").map(|(_, value)| value.to_string()));
+        let request = Request::from_parts(parts, body);
+        let Some(id) = id else {
+            tracing::error!(resource = kind.name(), "Owned route has no `:id` segment");
+            return Err(internal_error_response("Failed to check resource owner").into_response());
+        };
+
+        match self.is_owner(kind, user_id, &id).await {
+            Ok(Some(false)) => {
+                tracing::warn!(target: "security", resource = kind.name(), id = %id, user_id = %user_id, "Access to another user's resource denied");
+                Err(match self.disclosure {
+                    Disclosure::NotFound => not_found_response(kind.name()).into_response(),
+                    Disclosure::Forbidden => {
+                        error_response(StatusCode::FORBIDDEN, "FORBIDDEN", format!("{} belongs to another user", kind.name()))
+                            .into_response()
+                    }
+                })
+            }
+            Ok(_) => Ok(request),
+            Err(err) => {
+                tracing::error!(resource = kind.name(), error = %err, "Owner lookup failed");
+                Err(internal_error_response("Failed to check resource owner").into_response())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delivery::{fake_principal, WithPrincipal};
+    use crate::infrastructure::{LaneLimiter, RateLimiter};
+    use crate::middleware::{route_policy_middleware, PolicyState, RoutePolicy};
+    use axum::{body::Body, http::header, routing::get, Router};
+    use tower::ServiceExt;
+
+    /// Every resource but `missing` belongs to the given user
+    struct OwnedByOne(Uuid);
+
+    #[async_trait]
+    impl OwnedResource for OwnedByOne {
+        async fn owner_of(&self, id: &str) -> Result<Option<Uuid>, OwnershipError> {
+            Ok((id != "missing").then_some(self.0))
+        }
+    }
+
+    fn app(owner: Uuid, disclosure: Disclosure) -> Router {
+        let state = Arc::new(PolicyState {
+            policy: RoutePolicy::public().owned(ResourceKind::User),
+            admin_token: Arc::from("secret"),
+            limiter: Arc::new(RateLimiter::new()),
+            lanes: Arc::new(LaneLimiter::new(0, 100, 100)),
+            ownership: Arc::new(
+                Ownership::new(disclosure).with_resource(ResourceKind::User, Arc::new(OwnedByOne(owner))),
+            ),
+        });
+        Router::new().route(
+            "/users/:id",
+            get(|| async { "ok" }).layer(axum::middleware::from_fn_with_state(state, route_policy_middleware)),
+        )
+    }
+
+    #[tokio::test]
+    async fn only_the_owner_or_the_admin_token_reach_owned_routes() {
+        let owner = Uuid::new_v4();
+        let app = app(owner, Disclosure::NotFound);
+        let as_user = |user_id: Uuid, path: &str| {
+            Request::get(path).with_principal(fake_principal(user_id, &[])).body(Body::empty()).unwrap()
+        };
+
+        let response = app.clone().oneshot(as_user(owner, "/users/1")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.clone().oneshot(as_user(Uuid::new_v4(), "/users/1")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = app.clone().oneshot(as_user(Uuid::new_v4(), "/users/missing")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let anonymous = Request::get("/users/1").body(Body::empty()).unwrap();
+        assert_eq!(app.clone().oneshot(anonymous).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+        let admin =
+            Request::get("/users/1").header(header::AUTHORIZATION, "Bearer secret").body(Body::empty()).unwrap();
+        assert_eq!(app.oneshot(admin).await.unwrap().status(), StatusCode::OK);
+
+        let forbidden = self::app(owner, Disclosure::Forbidden);
+        let response = forbidden.oneshot(as_user(Uuid::new_v4(), "/users/1")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
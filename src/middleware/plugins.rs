@@ -0,0 +1,40 @@
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+use crate::infrastructure::{PluginChain, PluginRefusal, RequestView};
+use crate::response::error_response;
+
+/// Request plugin middleware.
+///
+/// Runs the plugin chain over the method, path and headers before routing.
+/// Header changes are applied to the request. A rejection is answered with
+/// the plugin's status, code and message; a plugin that fails is answered
+/// with 500 `PLUGIN_FAILED` unless the chain fails open. Requests let through
+/// get their response headers shaped by the same chain. Plugins run on the
+/// CPU pool, so a slow one doesn't stall the async workers.
+pub async fn plugin_middleware(State(chain): State<Arc<PluginChain>>, mut request: Request, next: Next) -> Response {
+    let (method, uri) = (request.method().clone(), request.uri().clone());
+    match chain.run_on_pool(&method, &uri, request.headers_mut()).await {
+        Ok(()) => {
+            let view = RequestView::new(&method, &uri, request.headers());
+            let mut response = next.run(request).await;
+            let status = response.status().as_u16();
+            chain.run_response_on_pool(view, status, response.headers_mut()).await;
+            response
+        }
+        Err(PluginRefusal::Rejected { plugin, rejection }) => {
+            tracing::warn!(plugin = %plugin, code = %rejection.code, method = %method, path = %uri.path(), "Request rejected by plugin");
+            let status = StatusCode::from_u16(rejection.status).unwrap_or(StatusCode::FORBIDDEN);
+            error_response(status, rejection.code, rejection.message).into_response()
+        }
+        Err(PluginRefusal::Failed { plugin, error }) => {
+            tracing::error!(plugin = %plugin, error = %error, method = %method, path = %uri.path(), "Request plugin failed");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "PLUGIN_FAILED", "Request could not be checked").into_response()
+        }
+    }
+}
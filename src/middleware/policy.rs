@@ -0,0 +1,419 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::admin::admin_token_matches;
+use super::client_ip::client_addr;
+use super::ownership::{Ownership, ResourceKind};
+use crate::domain::session::entities::{Principal, Scope};
+use crate::infrastructure::{Lane, LaneLimiter, LaneSlot, RateLimitMode, RateLimiter};
+use crate::response::{error_response, unauthorized_response};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Set in shadow mode on responses that enforcement would have rejected
+pub const RATE_LIMIT_WARNING: &str = "x-ratelimit-warning";
+
+/// Who may call a route
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Auth {
+    Public,
+    /// Bearer `ADMIN_API_TOKEN`, or a session with the `admin` role
+    Admin,
+    /// A session of the user who owns the resource at `:id`, or the admin token
+    Owner(ResourceKind),
+}
+
+/// Per-client budget a route draws from; limits per bucket come from config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitBucket {
+    Unlimited,
+    Read,
+    Write,
+    Webhook,
+}
+
+impl RateLimitBucket {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Unlimited => "unlimited",
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::Webhook => "webhook",
+        }
+    }
+}
+
+/// `Cache-Control` sent with successful responses that don't set their own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cacheability {
+    NoStore,
+    /// Shared caches and the CDN may keep the response
+    Public { max_age_secs: u32 },
+    Private { max_age_secs: u32 },
+}
+
+impl Cacheability {
+    pub fn header_value(self) -> String {
+        match self {
+            Self::NoStore => "no-store".to_string(),
+            Self::Public { max_age_secs } => format!("public, max-age={max_age_secs}"),
+            Self::Private { max_age_secs } => format!("private, max-age={max_age_secs}"),
+        }
+    }
+}
+
+/// Server-side caching of a route's successful GET responses: answered from
+/// memory for `fresh_secs`, then for up to `stale_secs` more while a single
+/// request refreshes the entry in the background
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StaleWhileRevalidate {
+    pub fresh_secs: u32,
+    pub stale_secs: u32,
+}
+
+/// Auth, scope, rate limit, timeout, cacheability and priority lane of one route
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoutePolicy {
+    pub auth: Auth,
+    /// Required of scoped tokens; anonymous callers and the admin token are
+    /// not asked for it
+    pub scope: Option<Scope>,
+    pub rate_limit: RateLimitBucket,
+    pub timeout: Duration,
+    pub cache: Cacheability,
+    pub lane: Lane,
+    /// Only for routes whose answer depends on nothing but the URL and the
+    /// negotiated headers, once the caller is allowed in
+    pub revalidate: Option<StaleWhileRevalidate>,
+}
+
+impl RoutePolicy {
+    /// Public, read bucket, 10s timeout, not cacheable, interactive lane
+    pub const fn public() -> Self {
+        Self {
+            auth: Auth::Public,
+            scope: None,
+            rate_limit: RateLimitBucket::Read,
+            timeout: DEFAULT_TIMEOUT,
+            cache: Cacheability::NoStore,
+            lane: Lane::Interactive,
+            revalidate: None,
+        }
+    }
+
+    pub const fn admin(mut self) -> Self {
+        self.auth = Auth::Admin;
+        self
+    }
+
+    pub const fn owned(mut self, resource: ResourceKind) -> Self {
+        self.auth = Auth::Owner(resource);
+        self
+    }
+
+    pub const fn scope(mut self, scope: Scope) -> Self {
+        self.scope = Some(scope);
+        self
+    }
+
+    pub const fn limit(mut self, bucket: RateLimitBucket) -> Self {
+        self.rate_limit = bucket;
+        self
+    }
+
+    pub const fn timeout_secs(mut self, secs: u64) -> Self {
+        self.timeout = Duration::from_secs(secs);
+        self
+    }
+
+    pub const fn cache(mut self, cache: Cacheability) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    pub const fn lane(mut self, lane: Lane) -> Self {
+        self.lane = lane;
+        self
+    }
+
+    pub const fn stale_while_revalidate(mut self, fresh_secs: u32, stale_secs: u32) -> Self {
+        self.revalidate = Some(StaleWhileRevalidate { fresh_secs, stale_secs });
+        self
+    }
+}
+
+/// What the policy middleware of one route needs
+pub struct PolicyState {
+    pub policy: RoutePolicy,
+    pub admin_token: Arc<str>,
+    pub limiter: Arc<RateLimiter>,
+    pub lanes: Arc<LaneLimiter>,
+    pub ownership: Arc<Ownership>,
+}
+
+/// Enforces a route's policy: rate limit (per `ClientIp`), then auth (and ownership), then the
+/// caller's token scopes, then a slot in the route's lane, then the handler under its timeout. A full lane is answered
+/// with 503 and `Retry-After: 1`. In shadow mode an over-budget request is
+/// served with an `X-RateLimit-Warning` header instead of 429. Successful
+/// responses get the route's `Cache-Control` unless the handler set one.
+pub async fn route_policy_middleware(
+    State(state): State<Arc<PolicyState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let policy = state.policy;
+
+    // Budget first, so guessing the admin token is limited too. Every served
+    // request has a peer address; there is no shared bucket for those without.
+    let client = client_addr(&request).map(|ip| ip.to_string());
+    let bucket = policy.rate_limit.name();
+    let mut rate_limit_warning = None;
+    if let Some(Err(retry_after)) = client.as_deref().map(|client| state.limiter.check(bucket, client)) {
+        let retry_after_secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+        if state.limiter.mode() == RateLimitMode::Enforce {
+            let mut response =
+                error_response(StatusCode::TOO_MANY_REQUESTS, "RATE_LIMITED", "Too many requests").into_response();
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+            return response;
+        }
+        let limit = state.limiter.limit(bucket).unwrap_or_default();
+        tracing::warn!(bucket, client = client.as_deref(), limit, "Rate limit exceeded (shadow mode, request served)");
+        rate_limit_warning = HeaderValue::from_str(&format!(
+            "bucket={bucket}; limit={limit}; window=60; retry-after={retry_after_secs}"
+        ))
+        .ok();
+    }
+
+    let mut request = match policy.auth {
+        Auth::Public => request,
+        Auth::Admin if admin_token_matches(request.headers(), &state.admin_token) => request,
+        Auth::Admin if request.extensions().get::<Principal>().is_some_and(Principal::is_admin) => request,
+        Auth::Admin => return unauthorized_response("Admin token required").into_response(),
+        Auth::Owner(_) if admin_token_matches(request.headers(), &state.admin_token) => request,
+        Auth::Owner(resource) => match state.ownership.check(resource, request).await {
+            Ok(request) => request,
+            Err(response) => return response,
+        },
+    };
+
+    if let Some(scope) = policy.scope {
+        let denied = request.extensions().get::<Principal>().is_some_and(|principal| !principal.allows(scope));
+        if denied && !admin_token_matches(request.headers(), &state.admin_token) {
+            return insufficient_scope(scope);
+        }
+    }
+
+    // Held until the response is produced, including on timeout, unless the handler releases it to park
+    let Some(permit) = state.lanes.try_acquire(policy.lane) else {
+        tracing::warn!(lane = policy.lane.name(), "Lane saturated, shedding request");
+        let mut response =
+            error_response(StatusCode::SERVICE_UNAVAILABLE, "SATURATED", "Server is busy, please retry shortly")
+                .into_response();
+        response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from_static("1"));
+        return response;
+    };
+    let slot = LaneSlot::new(permit);
+    request.extensions_mut().insert(slot.clone());
+
+    let mut response = match tokio::time::timeout(policy.timeout, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => error_response(StatusCode::GATEWAY_TIMEOUT, "TIMEOUT", "Request timed out").into_response(),
+    };
+    if let Some(warning) = rate_limit_warning {
+        response.headers_mut().insert(RATE_LIMIT_WARNING, warning);
+    }
+
+    if response.status().is_success() && !response.headers().contains_key(header::CACHE_CONTROL) {
+        if let Ok(value) = HeaderValue::from_str(&policy.cache.header_value()) {
+            response.headers_mut().insert(header::CACHE_CONTROL, value);
+        }
+    }
+    response
+}
+
+/// 403 with the RFC 6750 challenge naming the missing scope
+fn insufficient_scope(scope: Scope) -> Response {
+    let mut response = error_response(
+        StatusCode::FORBIDDEN,
+        "INSUFFICIENT_SCOPE",
+        format!("This token lacks the {} scope", scope.name()),
+    )
+    .into_response();
+    let challenge = format!("Bearer error=\"insufficient_scope\", scope=\"{}\"", scope.name());
+    if let Ok(value) = HeaderValue::from_str(&challenge) {
+        response.headers_mut().insert(header::WWW_AUTHENTICATE, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn app(policy: RoutePolicy, limiter: RateLimiter) -> Router {
+        lane_app(policy, limiter, Arc::new(LaneLimiter::new(0, 100, 100)))
+    }
+
+    fn lane_app(policy: RoutePolicy, limiter: RateLimiter, lanes: Arc<LaneLimiter>) -> Router {
+        let state = Arc::new(PolicyState {
+            policy,
+            admin_token: Arc::from("secret"),
+            limiter: Arc::new(limiter),
+            lanes,
+            ownership: Arc::new(Ownership::default()),
+        });
+        Router::new()
+            .route("/fast", get(|| async { "ok" }))
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(30)).await;
+                    "late"
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(state, route_policy_middleware))
+    }
+
+    fn get_request(path: &str, token: Option<&str>) -> Request {
+        let peer = std::net::SocketAddr::from(([203, 0, 113, 9], 40000));
+        let mut request = Request::get(path).extension(axum::extract::ConnectInfo(peer));
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+        request.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn rate_limit_applies_before_admin_auth() {
+        let policy = RoutePolicy::public().admin().limit(RateLimitBucket::Write);
+        let app = app(policy, RateLimiter::new().with_limit("write", 2));
+
+        let response = app.clone().oneshot(get_request("/fast", Some("secret"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.clone().oneshot(get_request("/fast", Some("wrong"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app.clone().oneshot(get_request("/fast", Some("secret"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "60");
+    }
+
+    #[tokio::test]
+    async fn admin_routes_take_the_token_or_an_admin_session() {
+        use crate::delivery::fake_principal;
+
+        let signed_in = |principal: Principal| {
+            let mut request = get_request("/fast", None);
+            request.extensions_mut().insert(principal);
+            request
+        };
+        let app = app(RoutePolicy::public().admin(), RateLimiter::new());
+        let admin = fake_principal(uuid::Uuid::new_v4(), &["admin"]);
+        let mut impersonated = admin.clone();
+        impersonated.claims.impersonated_by = Some("support@example.com".to_string());
+
+        let response = app.clone().oneshot(signed_in(admin)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.clone().oneshot(signed_in(fake_principal(uuid::Uuid::new_v4(), &[]))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app.oneshot(signed_in(impersonated)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn scoped_tokens_need_the_route_scope() {
+        use crate::delivery::fake_principal;
+
+        let signed_in = |principal: Principal| {
+            let mut request = get_request("/fast", None);
+            request.extensions_mut().insert(principal);
+            request
+        };
+        let app = app(RoutePolicy::public().scope(Scope::UsersWrite), RateLimiter::new());
+        let mut scoped = fake_principal(uuid::Uuid::new_v4(), &[]);
+        scoped.scopes = Some(vec!["users:read".to_string()]);
+
+        let response = app.clone().oneshot(signed_in(scoped.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(response.headers()[header::WWW_AUTHENTICATE], "Bearer error=\"insufficient_scope\", scope=\"users:write\"");
+
+        scoped.scopes = Some(vec!["users:*".to_string()]);
+        let response = app.clone().oneshot(signed_in(scoped)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let unscoped = fake_principal(uuid::Uuid::new_v4(), &[]);
+        let response = app.clone().oneshot(signed_in(unscoped)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.oneshot(get_request("/fast", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn clients_are_limited_by_address_not_by_forwarding_headers() {
+        let app = app(RoutePolicy::public(), RateLimiter::new().with_limit("read", 1));
+        let spoofing = |forwarded: &str| {
+            let mut request = get_request("/fast", None);
+            request.headers_mut().insert("x-forwarded-for", forwarded.parse().unwrap());
+            request
+        };
+
+        assert_eq!(app.clone().oneshot(spoofing("198.51.100.1")).await.unwrap().status(), StatusCode::OK);
+        let rotated = app.clone().oneshot(spoofing("198.51.100.2")).await.unwrap();
+        assert_eq!(rotated.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // Another direct client has its own budget
+        let mut other = Request::get("/fast").body(Body::empty()).unwrap();
+        other.extensions_mut().insert(axum::extract::ConnectInfo(std::net::SocketAddr::from(([203, 0, 113, 10], 40000))));
+        assert_eq!(app.oneshot(other).await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slow_handlers_time_out_and_successes_get_cache_control() {
+        let policy = RoutePolicy::public().timeout_secs(5).cache(Cacheability::Public { max_age_secs: 60 });
+        let app = app(policy, RateLimiter::new());
+
+        let response = app.clone().oneshot(get_request("/fast", None)).await.unwrap();
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "public, max-age=60");
+
+        let response = app.clone().oneshot(get_request("/slow", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert!(response.headers().get(header::CACHE_CONTROL).is_none());
+    }
+
+    #[tokio::test]
+    async fn full_lanes_shed_requests_with_503() {
+        let lanes = Arc::new(LaneLimiter::new(4, 75, 25));
+        let app = lane_app(RoutePolicy::public().lane(Lane::Bulk), RateLimiter::new(), lanes.clone());
+        let busy = lanes.try_acquire(Lane::Interactive).unwrap();
+
+        let response = app.clone().oneshot(get_request("/fast", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+
+        drop(busy);
+        let response = app.oneshot(get_request("/fast", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bulk = &lanes.report().lanes[2];
+        assert_eq!((bulk.in_flight, bulk.admitted, bulk.rejected), (0, 1, 1));
+    }
+
+    #[tokio::test]
+    async fn shadow_mode_serves_over_budget_requests_with_a_warning() {
+        let limiter = RateLimiter::new().with_limit("read", 1).with_mode(RateLimitMode::Shadow);
+        let app = app(RoutePolicy::public(), limiter);
+
+        let response = app.clone().oneshot(get_request("/fast", None)).await.unwrap();
+        assert!(response.headers().get(RATE_LIMIT_WARNING).is_none());
+
+        let response = app.oneshot(get_request("/fast", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let warning = response.headers()[RATE_LIMIT_WARNING].to_str().unwrap();
+        assert!(warning.starts_with("bucket=read; limit=1; window=60; retry-after="), "{warning}");
+    }
+}
@@ -0,0 +1,26 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+
+use crate::domain::session::entities::{Principal, SessionKind};
+use crate::domain::user::feature::PresenceTracker;
+
+/// Marks the signed-in user as online on every request.
+///
+/// Runs inside `auth_middleware`, which attaches the `Principal`. Requests
+/// made while impersonating a user don't count as that user being present.
+pub async fn presence_middleware(
+    State(presence): State<Arc<PresenceTracker>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Some(principal) = request.extensions().get::<Principal>() {
+        if principal.claims.kind != SessionKind::Impersonation {
+            presence.touch(principal.user_id).await;
+        }
+    }
+    next.run(request).await
+}
@@ -0,0 +1,57 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+use crate::infrastructure::{PinDecision, RegionRouter};
+use crate::response::error_response;
+
+/// Sent by clients that must be served by one region
+pub const REGION_PIN_HEADER: &str = "x-region-pin";
+/// Region that answered, on every response
+pub const REGION_HEADER: &str = "x-region";
+
+/// Region pinning middleware.
+///
+/// Requests without `X-Region-Pin`, or pinned to this region, pass through.
+/// Requests pinned elsewhere are redirected with 307 to that region's peer
+/// URL when redirects are on and the peer is known, and answered with 421
+/// `REGION_MISMATCH` otherwise. Every response names the region in
+/// `X-Region`.
+pub async fn region_middleware(State(router): State<Arc<RegionRouter>>, request: Request, next: Next) -> Response {
+    let pin = request.headers().get(REGION_PIN_HEADER).and_then(|value| value.to_str().ok());
+    let path_and_query = request.uri().path_and_query().map_or("/", |path| path.as_str());
+
+    let mut response = match router.decide(pin, path_and_query) {
+        PinDecision::Local => next.run(request).await,
+        PinDecision::Redirect(location) => {
+            tracing::info!(region = %router.region, location = %location, "Request redirected to its pinned region");
+            let mut response = error_response(
+                StatusCode::TEMPORARY_REDIRECT,
+                "REGION_REDIRECT",
+                "Request is pinned to another region",
+            )
+            .into_response();
+            if let Ok(value) = HeaderValue::from_str(&location) {
+                response.headers_mut().insert(header::LOCATION, value);
+            }
+            response
+        }
+        PinDecision::Misdirected { pinned } => {
+            tracing::warn!(region = %router.region, pinned = %pinned, "Request pinned to another region");
+            error_response(
+                StatusCode::MISDIRECTED_REQUEST,
+                "REGION_MISMATCH",
+                format!("This is region {}, not {pinned}", router.region),
+            )
+            .into_response()
+        }
+    };
+    if let Ok(value) = HeaderValue::from_str(&router.region) {
+        response.headers_mut().insert(REGION_HEADER, value);
+    }
+    response
+}
@@ -0,0 +1,83 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+
+use crate::infrastructure::{Currency, Locale, RequestContext, UnitSystem};
+
+/// ISO 4217 code of the currency amounts should be shown in
+pub const CURRENCY_HEADER: &str = "x-currency";
+/// `metric` or `imperial`
+pub const UNITS_HEADER: &str = "x-units";
+
+/// Request context middleware.
+///
+/// Stores the `RequestContext` handlers format with in the request
+/// extensions. `Accept-Language` picks the locale, which also implies a
+/// currency and unit system; `X-Currency` and `X-Units` override those.
+/// Unsupported or missing values fall back to the configured defaults.
+pub async fn request_context_middleware(
+    State(defaults): State<Arc<RequestContext>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let context = resolve(request.headers(), &defaults);
+    request.extensions_mut().insert(context);
+    next.run(request).await
+}
+
+fn resolve(headers: &HeaderMap, defaults: &RequestContext) -> RequestContext {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    let locale = header(header::ACCEPT_LANGUAGE.as_str()).and_then(Locale::negotiate);
+    RequestContext {
+        locale: locale.unwrap_or(defaults.locale),
+        currency: header(CURRENCY_HEADER)
+            .and_then(Currency::find)
+            .or(locale.map(Locale::currency))
+            .unwrap_or(defaults.currency),
+        units: header(UNITS_HEADER)
+            .and_then(UnitSystem::parse)
+            .or(locale.map(Locale::units))
+            .unwrap_or(defaults.units),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Router};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn headers_override_what_the_locale_implies() {
+        let defaults = Arc::new(RequestContext::from_settings("en-GB", "GBP", "metric"));
+        let app = Router::new()
+            .route(
+                "/price",
+                get(|context: RequestContext| async move {
+                    format!("{} {}", context.format_money(123456), context.format_distance(1000.0))
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(defaults, request_context_middleware));
+        let price = |headers: &[(&str, &str)]| {
+            let mut request = Request::get("/price");
+            for (name, value) in headers {
+                request = request.header(*name, *value);
+            }
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                String::from_utf8(bytes.to_vec()).unwrap()
+            }
+        };
+
+        assert_eq!(price(&[]).await, "£1,234.56 1.0 km");
+        assert_eq!(price(&[("accept-language", "de-DE,en;q=0.5")]).await, "1.234,56\u{a0}€ 1,0 km");
+        assert_eq!(price(&[("accept-language", "en-US"), ("x-units", "metric")]).await, "$1,234.56 1.0 km");
+        assert_eq!(price(&[("accept-language", "de"), ("x-currency", "usd")]).await, "1.234,56\u{a0}$ 1,0 km");
+    }
+}
@@ -0,0 +1,185 @@
+//! Page/limit arithmetic shared by services, handlers and repositories,
+//! links between the pages of a list, plus opaque cursors for keyset
+//! pagination.
+
+use axum::http::Uri;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use thiserror::Error;
+
+pub const DEFAULT_PAGE: u32 = 1;
+pub const DEFAULT_LIMIT: u32 = 10;
+/// Largest page a client can ask for
+pub const MAX_LIMIT: u32 = 100;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PaginationError {
+    #[error("invalid pagination cursor")]
+    InvalidCursor,
+}
+
+/// A 1-based page of `limit` items. Both are at least 1, so offset and
+/// page-count math never underflows or divides by zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageRequest {
+    pub page: u32,
+    pub limit: u32,
+}
+
+impl PageRequest {
+    /// Raises a zero `page` or `limit` to 1. Internal callers such as export
+    /// loops use this to pick page sizes above `MAX_LIMIT`.
+    pub fn new(page: u32, limit: u32) -> Self {
+        Self { page: page.max(1), limit: limit.max(1) }
+    }
+
+    /// From client-supplied query parameters: defaults for missing values and
+    /// `limit` capped at `MAX_LIMIT`
+    pub fn from_query(page: Option<u32>, limit: Option<u32>) -> Self {
+        Self::new(page.unwrap_or(DEFAULT_PAGE), limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT))
+    }
+
+    /// Items before this page. Computed in `usize`, so large pages don't
+    /// overflow `u32`.
+    pub fn offset(&self) -> usize {
+        (self.page.saturating_sub(1) as usize).saturating_mul(self.limit as usize)
+    }
+
+    /// This page's slice of `items`, in iteration order
+    pub fn window<I: Iterator>(&self, items: I) -> std::iter::Take<std::iter::Skip<I>> {
+        items.skip(self.offset()).take(self.limit as usize)
+    }
+
+    /// Whether a page that returned `len` items was the last one
+    pub fn is_last(&self, len: usize) -> bool {
+        len < self.limit as usize
+    }
+
+    pub fn next(&self) -> Self {
+        Self { page: self.page.saturating_add(1), limit: self.limit }
+    }
+}
+
+/// Pages needed to hold `total` items, `limit` per page
+pub fn total_pages(total: u64, limit: u32) -> u32 {
+    u32::try_from(total.div_ceil(u64::from(limit.max(1)))).unwrap_or(u32::MAX)
+}
+
+/// URLs of the pages around the current one, for `meta.links` and the
+/// `Link` header of bare lists
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PageLinks {
+    pub first: String,
+    /// `None` on the first page
+    pub prev: Option<String>,
+    /// `None` on the last page
+    pub next: Option<String>,
+    pub last: String,
+}
+
+impl PageLinks {
+    /// Links for `page` of `total_pages` (an empty list still has a first and
+    /// last page). Each is the request URL with only `page` changed, so
+    /// filters, sorting and `limit` carry over; `origin` (e.g.
+    /// `https://api.example.com`) makes them absolute.
+    pub fn new(origin: Option<&str>, uri: &Uri, page: u32, total_pages: u32) -> Self {
+        let last = total_pages.max(1);
+        let url = |page| page_url(origin, uri, page);
+        Self {
+            first: url(1),
+            prev: (page > 1).then(|| url(page - 1)),
+            next: (page < last).then(|| url(page + 1)),
+            last: url(last),
+        }
+    }
+
+    /// RFC 8288 `first`, `prev`, `next` and `last` links
+    pub fn to_header(&self) -> String {
+        [(Some(&self.first), "first"), (self.prev.as_ref(), "prev"), (self.next.as_ref(), "next"), (Some(&self.last), "last")]
+            .into_iter()
+            .filter_map(|(url, rel)| url.map(|url| format!("<{url}>; rel=\"{rel}\"")))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// `uri` with its `page` parameter set, other parameters kept in order
+fn page_url(origin: Option<&str>, uri: &Uri, page: u32) -> String {
+    let mut query: Vec<&str> = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty() && *pair != "page" && !pair.starts_with("page="))
+        .collect();
+    let page = format!("page={page}");
+    query.push(&page);
+    format!("{}{}?{}", origin.unwrap_or_default(), uri.path(), query.join("&"))
+}
+
+/// Encode the sort key of the last item on a page as an opaque, URL-safe
+/// cursor for the next request
+pub fn encode_cursor<T: Serialize>(key: &T) -> String {
+    hex::encode(serde_json::to_vec(key).expect("cursor keys serialize to JSON"))
+}
+
+/// Decode a cursor produced by `encode_cursor` with the same key type
+pub fn decode_cursor<T: DeserializeOwned>(cursor: &str) -> Result<T, PaginationError> {
+    let bytes = hex::decode(cursor).map_err(|_| PaginationError::InvalidCursor)?;
+    serde_json::from_slice(&bytes).map_err(|_| PaginationError::InvalidCursor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+    use uuid::Uuid;
+
+    #[test]
+    fn query_values_are_defaulted_and_clamped() {
+        assert_eq!(PageRequest::from_query(None, None), PageRequest { page: 1, limit: 10 });
+        assert_eq!(PageRequest::from_query(Some(0), Some(0)), PageRequest { page: 1, limit: 1 });
+        assert_eq!(PageRequest::from_query(Some(3), Some(500)), PageRequest { page: 3, limit: 100 });
+        assert_eq!(PageRequest::new(2, 500), PageRequest { page: 2, limit: 500 });
+    }
+
+    #[test]
+    fn offsets_windows_and_page_counts() {
+        let request = PageRequest::new(3, 4);
+        assert_eq!(request.offset(), 8);
+        assert_eq!(request.window(0..10).collect::<Vec<_>>(), vec![8, 9]);
+        assert!(request.is_last(2));
+        assert!(!PageRequest::new(1, 4).is_last(4));
+        assert_eq!(request.next(), PageRequest::new(4, 4));
+        assert_eq!(PageRequest::new(u32::MAX, u32::MAX).offset(), (u32::MAX as usize - 1) * u32::MAX as usize);
+
+        assert_eq!(total_pages(0, 10), 0);
+        assert_eq!(total_pages(25, 10), 3);
+        assert_eq!(total_pages(30, 10), 3);
+        assert_eq!(total_pages(5, 0), 5);
+        assert_eq!(total_pages(u64::MAX, 1), u32::MAX);
+    }
+
+    #[test]
+    fn page_links_keep_the_query_and_stop_at_the_ends() {
+        let uri: Uri = "/api/users?metadata.plan=pro&page=2&sort=email".parse().unwrap();
+        let links = PageLinks::new(Some("https://api.example.com"), &uri, 2, 3);
+        assert_eq!(links.first, "https://api.example.com/api/users?metadata.plan=pro&sort=email&page=1");
+        assert_eq!(links.prev.as_deref(), Some("https://api.example.com/api/users?metadata.plan=pro&sort=email&page=1"));
+        assert_eq!(links.next.as_deref(), Some("https://api.example.com/api/users?metadata.plan=pro&sort=email&page=3"));
+
+        let empty = PageLinks::new(None, &"/api/users".parse().unwrap(), 1, 0);
+        assert_eq!((empty.prev, empty.next), (None, None));
+        assert_eq!(empty.last, "/api/users?page=1");
+    }
+
+    #[test]
+    fn cursors_round_trip_and_reject_tampering() {
+        let key: (DateTime<Utc>, Uuid) = (Utc::now(), Uuid::new_v4());
+        let cursor = encode_cursor(&key);
+        assert!(cursor.bytes().all(|byte| byte.is_ascii_hexdigit()));
+        assert_eq!(decode_cursor::<(DateTime<Utc>, Uuid)>(&cursor).unwrap(), key);
+
+        assert_eq!(decode_cursor::<(DateTime<Utc>, Uuid)>("zz"), Err(PaginationError::InvalidCursor));
+        assert_eq!(decode_cursor::<(DateTime<Utc>, Uuid)>(&cursor[2..]), Err(PaginationError::InvalidCursor));
+    }
+}
@@ -0,0 +1,122 @@
+use axum::{
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+use super::{pooled_json_response, with_surrogate_keys, ApiResponse, Meta, ResponseSuccess};
+
+/// A success envelope put together step by step, for handlers that need more
+/// than `pooled_success_response` gives them:
+///
+/// ```ignore
+/// respond(user).status(StatusCode::CREATED).location(&url).into_response()
+/// ```
+///
+/// Rendered through the active `SerializationProfile` like the helpers.
+/// Header values that aren't valid are skipped rather than failing the
+/// response.
+#[must_use]
+pub struct ResponseBuilder<T> {
+    data: T,
+    status: StatusCode,
+    meta: Option<Meta>,
+    headers: HeaderMap,
+    links: Vec<String>,
+    surrogate_keys: Vec<String>,
+}
+
+/// Start a `200 OK` response carrying `data`
+pub fn respond<T: Serialize>(data: T) -> ResponseBuilder<T> {
+    ResponseBuilder {
+        data,
+        status: StatusCode::OK,
+        meta: None,
+        headers: HeaderMap::new(),
+        links: Vec::new(),
+        surrogate_keys: Vec::new(),
+    }
+}
+
+impl<T: Serialize> ResponseBuilder<T> {
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn meta(mut self, meta: Meta) -> Self {
+        self.meta = Some(meta);
+        self
+    }
+
+    /// Replaces any earlier value of `name`
+    pub fn header(mut self, name: HeaderName, value: &str) -> Self {
+        if let Ok(value) = HeaderValue::from_str(value) {
+            self.headers.insert(name, value);
+        }
+        self
+    }
+
+    /// Where the created or moved resource lives
+    pub fn location(self, url: &str) -> Self {
+        self.header(header::LOCATION, url)
+    }
+
+    /// Adds `<url>; rel="rel"` to the `Link` header
+    pub fn link(mut self, url: &str, rel: &str) -> Self {
+        self.links.push(format!("<{url}>; rel=\"{rel}\""));
+        self
+    }
+
+    /// Tags the response for CDN purging, as `with_surrogate_keys` does
+    pub fn surrogate_keys(mut self, keys: &[String]) -> Self {
+        self.surrogate_keys.extend_from_slice(keys);
+        self
+    }
+}
+
+impl<T: Serialize> IntoResponse for ResponseBuilder<T> {
+    fn into_response(self) -> Response {
+        let body = match self.meta {
+            Some(meta) => ApiResponse::success_with_meta(self.data, meta),
+            None => ApiResponse::success(self.data),
+        };
+        let mut response = pooled_json_response(self.status, &body);
+        response.headers_mut().extend(self.headers);
+        if !self.links.is_empty() {
+            if let Ok(value) = HeaderValue::from_str(&self.links.join(", ")) {
+                response.headers_mut().insert(header::LINK, value);
+            }
+        }
+        if self.surrogate_keys.is_empty() {
+            response
+        } else {
+            with_surrogate_keys(response, &self.surrogate_keys)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn builder_sets_status_headers_and_meta() {
+        let response = respond(serde_json::json!({ "id": 7 }))
+            .status(StatusCode::CREATED)
+            .location("/api/users/7")
+            .link("/api/users?page=2", "next")
+            .header(HeaderName::from_static("x-note"), "bad\nvalue")
+            .meta(Meta::new(1, 10, 11))
+            .into_response();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()[header::LOCATION], "/api/users/7");
+        assert_eq!(response.headers()[header::LINK], "</api/users?page=2>; rel=\"next\"");
+        assert!(!response.headers().contains_key("x-note"));
+        let body: serde_json::Value =
+            serde_json::from_slice(&axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(body["data"]["id"], 7);
+        assert_eq!(body["meta"]["total_pages"], 2);
+    }
+}
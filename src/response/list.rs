@@ -0,0 +1,153 @@
+use axum::{
+    http::{header, HeaderName, HeaderValue, StatusCode, Uri},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+use crate::pagination::PageLinks;
+
+use super::{pooled_json_response, pooled_success_response, pooled_success_response_with_meta, profile, FieldCase, Meta};
+
+/// Request header asking for the bare items, like `?envelope=false`
+pub const X_ENVELOPE: HeaderName = HeaderName::from_static("x-envelope");
+/// Number of items across all pages of a bare list
+pub const X_TOTAL_COUNT: HeaderName = HeaderName::from_static("x-total-count");
+
+/// How a list endpoint answers: the standard envelope, or only the items for
+/// spreadsheets and scripts. Bare lists carry their pagination in
+/// `X-Total-Count` and `Link` headers instead of `meta`. Errors keep the
+/// envelope either way. Enveloped lists get the same links in `meta.links`.
+#[derive(Debug, Clone)]
+pub struct ListEnvelope {
+    bare: bool,
+    /// Path and query of the request; page links only change its `page`
+    uri: Uri,
+    /// Scheme and host the request was made to, so page links are absolute
+    origin: Option<String>,
+}
+
+impl ListEnvelope {
+    /// Bare when the query has `envelope=false` (or `0`, `no`, `none`), or the
+    /// `X-Envelope` header does
+    pub fn from_request(uri: &Uri, header: Option<&HeaderValue>) -> Self {
+        let off = |value: &str| matches!(value.trim().to_ascii_lowercase().as_str(), "false" | "0" | "no" | "none");
+        let in_query = uri
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .filter_map(|pair| pair.strip_prefix("envelope="))
+            .any(off);
+        let in_header = header.and_then(|value| value.to_str().ok()).is_some_and(off);
+        Self { bare: in_query || in_header, uri: uri.clone(), origin: None }
+    }
+
+    /// Make page links absolute, e.g. `https://api.example.com`
+    pub fn with_origin(mut self, origin: impl Into<String>) -> Self {
+        self.origin = Some(origin.into());
+        self
+    }
+
+    pub fn is_bare(&self) -> bool {
+        self.bare
+    }
+
+    /// `data` in the standard envelope, or just the `items` it holds. Without
+    /// `meta` the list is unpaged and `X-Total-Count` is its length.
+    pub fn respond<T: Serialize, I: Serialize>(
+        &self,
+        data: T,
+        items: impl FnOnce(&T) -> &[I],
+        meta: Option<Meta>,
+    ) -> Response {
+        let mut response = match (self.bare, meta) {
+            (true, meta) => self.bare_response(items(&data), meta.as_ref()),
+            (false, Some(mut meta)) => {
+                if meta.links.is_none() {
+                    meta.links = self.links(&meta);
+                }
+                pooled_success_response_with_meta(data, meta)
+            }
+            (false, None) => pooled_success_response(data),
+        };
+        // A shared cache must not answer the header variant with the other one
+        response.headers_mut().append(header::VARY, HeaderValue::from_static("x-envelope"));
+        response
+    }
+
+    fn bare_response<I: Serialize>(&self, items: &[I], meta: Option<&Meta>) -> Response {
+        let profile = profile();
+        let mut response = match profile.field_case {
+            FieldCase::Snake => pooled_json_response(StatusCode::OK, &items),
+            FieldCase::Camel => match profile.render_data(&items) {
+                Ok(body) => pooled_json_response(StatusCode::OK, &body),
+                Err(err) => {
+                    tracing::error!(error = %err, "Failed to serialize JSON response");
+                    return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                }
+            },
+        };
+
+        let total = meta.and_then(|meta| meta.total).unwrap_or(items.len() as u64);
+        let headers = response.headers_mut();
+        headers.insert(X_TOTAL_COUNT, HeaderValue::from(total));
+        if let Some(links) = meta.and_then(|meta| self.links(meta)) {
+            if let Ok(value) = HeaderValue::from_str(&links.to_header()) {
+                headers.insert(header::LINK, value);
+            }
+        }
+        response
+    }
+
+    /// Links between the pages of a paged list, from `meta`
+    fn links(&self, meta: &Meta) -> Option<PageLinks> {
+        Some(PageLinks::new(self.origin.as_deref(), &self.uri, meta.page?, meta.total_pages?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Listed {
+        users: Vec<serde_json::Value>,
+        total: u64,
+    }
+
+    async fn body(response: Response) -> serde_json::Value {
+        serde_json::from_slice(&axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn bare_lists_move_pagination_into_headers() {
+        let uri: Uri = "/api/users?limit=2&page=2&metadata.plan=pro&envelope=false".parse().unwrap();
+        let envelope = ListEnvelope::from_request(&uri, None);
+        assert!(envelope.is_bare());
+        let listed = Listed { users: vec![serde_json::json!({ "id": 3 }), serde_json::json!({ "id": 4 })], total: 5 };
+
+        let response = envelope.respond(listed, |listed| &listed.users, Some(Meta::new(2, 2, 5)));
+        assert_eq!(response.headers()[X_TOTAL_COUNT], "5");
+        assert_eq!(
+            response.headers()[header::LINK],
+            "</api/users?limit=2&metadata.plan=pro&envelope=false&page=1>; rel=\"first\", \
+             </api/users?limit=2&metadata.plan=pro&envelope=false&page=1>; rel=\"prev\", \
+             </api/users?limit=2&metadata.plan=pro&envelope=false&page=3>; rel=\"next\", \
+             </api/users?limit=2&metadata.plan=pro&envelope=false&page=3>; rel=\"last\""
+        );
+        assert_eq!(body(response).await, serde_json::json!([{ "id": 3 }, { "id": 4 }]));
+    }
+
+    #[tokio::test]
+    async fn envelope_is_kept_unless_switched_off() {
+        let uri: Uri = "/api/admin/routes?envelope=true".parse().unwrap();
+        let header = HeaderValue::from_static("FALSE");
+        assert!(ListEnvelope::from_request(&uri, Some(&header)).is_bare());
+
+        let envelope = ListEnvelope::from_request(&uri, None);
+        let listed = Listed { users: vec![serde_json::json!({ "id": 1 })], total: 1 };
+        let response = envelope.respond(listed, |listed| &listed.users, None);
+        assert!(response.headers().get(X_TOTAL_COUNT).is_none());
+        assert_eq!(response.headers()[header::VARY], "x-envelope");
+        assert_eq!(body(response).await["data"]["total"], 1);
+    }
+}
@@ -1,14 +1,21 @@
 use axum::{
-    http::StatusCode,
+    http::{HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde::Serialize;
-use serde_json::json;
 use std::collections::HashMap;
+use crate::pagination::PageLinks;
 
-/// Standard API Response wrapper
-#[derive(Debug, Serialize)]
+pub mod builder;
+pub mod list;
+pub mod pooled;
+pub mod problem;
+pub mod profile;
+
+/// Standard API Response wrapper; its wire layout follows the active
+/// `SerializationProfile`
+#[derive(Debug)]
 pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
@@ -19,22 +26,38 @@ pub struct ApiResponse<T> {
 /// Metadata for paginated responses
 #[derive(Debug, Serialize)]
 pub struct Meta {
+    #[serde(skip_serializing_if = "omit_none")]
     pub page: Option<u32>,
+    #[serde(skip_serializing_if = "omit_none")]
     pub limit: Option<u32>,
+    #[serde(skip_serializing_if = "omit_none")]
     pub total: Option<u64>,
+    #[serde(skip_serializing_if = "omit_none")]
     pub total_pages: Option<u32>,
+    #[serde(skip_serializing_if = "omit_none")]
+    pub total_exact: Option<bool>,
+    /// Filled in by `ListEnvelope` from the request URL
+    #[serde(skip_serializing_if = "omit_none")]
+    pub links: Option<PageLinks>,
 }
 
 impl Meta {
     pub fn new(page: u32, limit: u32, total: u64) -> Self {
-        let total_pages = ((total as f64) / (limit as f64)).ceil() as u32;
         Self {
             page: Some(page),
             limit: Some(limit),
             total: Some(total),
-            total_pages: Some(total_pages),
+            total_pages: Some(crate::pagination::total_pages(total, limit)),
+            total_exact: None,
+            links: None,
         }
     }
+
+    /// Mark whether `total` was counted for this request or served from a cached count
+    pub fn with_total_exact(mut self, exact: bool) -> Self {
+        self.total_exact = Some(exact);
+        self
+    }
 }
 
 /// Standard error structure
@@ -42,7 +65,12 @@ impl Meta {
 pub struct ApiError {
     pub code: String,
     pub message: String,
+    #[serde(skip_serializing_if = "omit_none")]
     pub details: Option<HashMap<String, serde_json::Value>>,
+    /// Failed input rules; problem documents list them as `errors`, the
+    /// envelope keeps them in `details`
+    #[serde(skip)]
+    pub violations: Option<crate::infrastructure::FieldErrors>,
 }
 
 impl ApiError {
@@ -51,6 +79,7 @@ impl ApiError {
             code: code.into(),
             message: message.into(),
             details: None,
+            violations: None,
         }
     }
 
@@ -63,8 +92,14 @@ impl ApiError {
             code: code.into(),
             message: message.into(),
             details: Some(details),
+            violations: None,
         }
     }
+
+    pub fn with_violations(mut self, violations: &crate::infrastructure::FieldErrors) -> Self {
+        self.violations = Some(violations.clone());
+        self
+    }
 }
 
 /// Trait for creating successful responses
@@ -112,6 +147,7 @@ impl ResponseError for ApiResponse<()> {
 /// Helper functions for creating responses
 pub mod helpers {
     use super::*;
+    use crate::infrastructure::FieldErrors;
     use crate::response::ApiResponse;
 
     pub fn success_response<T: Serialize>(data: T) -> Json<ApiResponse<T>> {
@@ -122,6 +158,16 @@ pub mod helpers {
         Json(ApiResponse::success_with_meta(data, meta))
     }
 
+    /// `201 Created` carrying `data`, pointing at the new resource when its
+    /// URL could be built; chain more on the returned builder as needed
+    pub fn created_response<T: Serialize>(data: T, location: Option<String>) -> ResponseBuilder<T> {
+        let response = respond(data).status(StatusCode::CREATED);
+        match location {
+            Some(url) => response.location(&url),
+            None => response,
+        }
+    }
+
     pub fn error_response(
         status: StatusCode,
         code: impl Into<String>,
@@ -144,19 +190,14 @@ pub mod helpers {
     }
 
     pub fn validation_error_response(
-        validation_errors: Vec<String>,
+        validation_errors: &FieldErrors,
     ) -> (StatusCode, Json<ApiResponse<()>>) {
-        let mut details = HashMap::new();
-        details.insert(
-            "validation_errors".to_string(),
-            json!(validation_errors).into(),
-        );
-
         let error = ApiError::with_details(
             "VALIDATION_ERROR",
             "Request validation failed",
-            details,
-        );
+            validation_errors.to_details(),
+        )
+        .with_violations(validation_errors);
         let response = ApiResponse::error(error);
         (StatusCode::BAD_REQUEST, Json(response))
     }
@@ -176,6 +217,18 @@ pub mod helpers {
     pub fn unauthorized_response(message: &str) -> (StatusCode, Json<ApiResponse<()>>) {
         error_response(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", message)
     }
+
+    /// Tag a response for CDN purging (Surrogate-Key for Fastly, Cache-Tag for Cloudflare)
+    pub fn with_surrogate_keys(mut response: Response, keys: &[String]) -> Response {
+        let headers = response.headers_mut();
+        if let Ok(value) = HeaderValue::from_str(&keys.join(" ")) {
+            headers.insert("surrogate-key", value);
+        }
+        if let Ok(value) = HeaderValue::from_str(&keys.join(",")) {
+            headers.insert("cache-tag", value);
+        }
+        response
+    }
 }
 
 /// Implementation of IntoResponse for ApiResponse
@@ -200,4 +253,9 @@ impl<T: Serialize> IntoResponse for ApiResponse<T> {
 }
 
 // Re-exports
-pub use helpers::*;
\ No newline at end of file
+pub use builder::*;
+pub use helpers::*;
+pub use list::*;
+pub use pooled::*;
+pub use problem::*;
+pub use profile::*;
\ No newline at end of file
@@ -0,0 +1,131 @@
+use axum::{
+    body::Body,
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use bytes::{BufMut, BytesMut};
+use serde::Serialize;
+
+use super::{ApiResponse, Meta, ResponseSuccess};
+use crate::infrastructure::ObjectPool;
+
+const INITIAL_BUFFER_CAPACITY: usize = 4 * 1024;
+const MAX_POOLED_CAPACITY: usize = 1024 * 1024;
+
+/// Serialization buffers of the JSON fast path
+pub static RESPONSE_BUFFERS: ObjectPool<BytesMut> =
+    ObjectPool::new("response_buffers", 64, new_buffer, recycle_buffer);
+
+pub(crate) fn new_buffer() -> BytesMut {
+    BytesMut::with_capacity(INITIAL_BUFFER_CAPACITY)
+}
+
+/// Keep spare capacity for the next response, but don't hoard huge buffers
+pub(crate) fn recycle_buffer(buffer: &mut BytesMut) -> bool {
+    buffer.clear();
+    buffer.capacity() <= MAX_POOLED_CAPACITY
+}
+
+/// A pooled buffer with room for a typical body. `split` leaves a pooled
+/// buffer holding only the tail of its allocation; `reserve` reclaims the
+/// whole allocation once the bytes split off earlier have been dropped.
+pub(crate) fn take_buffer(pool: &ObjectPool<BytesMut>) -> BytesMut {
+    let mut buffer = pool.take();
+    buffer.reserve(INITIAL_BUFFER_CAPACITY);
+    buffer
+}
+
+/// Serialize `value` straight into a pooled buffer and hand the bytes to the
+/// body without an intermediate `Vec`/`String`. Content-Length is set up front.
+pub fn pooled_json_response<T: Serialize>(status: StatusCode, value: &T) -> Response {
+    let mut buffer = take_buffer(&RESPONSE_BUFFERS);
+
+    if let Err(err) = serde_json::to_writer((&mut buffer).writer(), value) {
+        tracing::error!(error = %err, "Failed to serialize JSON response");
+        RESPONSE_BUFFERS.give(buffer);
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    let body = buffer.split().freeze();
+    RESPONSE_BUFFERS.give(buffer);
+
+    let content_length = HeaderValue::from(body.len());
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert(header::CONTENT_LENGTH, content_length);
+    response
+}
+
+/// Fast-path equivalent of `success_response`
+pub fn pooled_success_response<T: Serialize>(data: T) -> Response {
+    pooled_json_response(StatusCode::OK, &ApiResponse::success(data))
+}
+
+/// Fast-path equivalent of `success_response_with_meta`
+pub fn pooled_success_response_with_meta<T: Serialize>(data: T, meta: Meta) -> Response {
+    pooled_json_response(StatusCode::OK, &ApiResponse::success_with_meta(data, meta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Json;
+    use std::time::Instant;
+
+    fn sample_payload() -> Vec<serde_json::Value> {
+        (0..100)
+            .map(|i| serde_json::json!({ "id": i, "email": format!("user{}@example.com", i) }))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn pooled_response_matches_json_extractor_output() {
+        let payload = sample_payload();
+
+        let pooled = pooled_success_response(&payload);
+        assert_eq!(
+            pooled.headers()[header::CONTENT_TYPE],
+            HeaderValue::from_static("application/json")
+        );
+        let content_length: usize = pooled.headers()[header::CONTENT_LENGTH]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        let pooled_body = axum::body::to_bytes(pooled.into_body(), usize::MAX).await.unwrap();
+
+        let standard = Json(ApiResponse::success(&payload)).into_response();
+        let standard_body = axum::body::to_bytes(standard.into_body(), usize::MAX).await.unwrap();
+
+        assert_eq!(pooled_body, standard_body);
+        assert_eq!(content_length, pooled_body.len());
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture bench_`
+    #[test]
+    #[ignore]
+    fn bench_pooled_vs_json_response() {
+        const ITERATIONS: u32 = 20_000;
+        let payload = sample_payload();
+
+        let started = Instant::now();
+        for _ in 0..ITERATIONS {
+            std::hint::black_box(Json(ApiResponse::success(&payload)).into_response());
+        }
+        let json_elapsed = started.elapsed();
+
+        let started = Instant::now();
+        for _ in 0..ITERATIONS {
+            std::hint::black_box(pooled_success_response(&payload));
+        }
+        let pooled_elapsed = started.elapsed();
+
+        println!(
+            "Json<>: {:?}/response, pooled: {:?}/response",
+            json_elapsed / ITERATIONS,
+            pooled_elapsed / ITERATIONS
+        );
+    }
+}
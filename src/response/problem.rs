@@ -0,0 +1,50 @@
+use serde_json::{json, Map, Value};
+
+use super::ApiError;
+
+/// Media type of RFC 9457 problem documents
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// Problem `type`s are this followed by the error code in kebab case, e.g.
+/// `/problems/validation-error`. Relative, so clients resolve it against the
+/// request URL.
+pub const PROBLEM_TYPE_PREFIX: &str = "/problems/";
+
+/// Members a problem document defines itself; `details` entries never replace them
+const RESERVED_MEMBERS: &[&str] = &["type", "title", "status", "detail", "instance", "code", "errors"];
+
+/// The `type` of problems with this error code
+pub fn problem_type(code: &str) -> String {
+    format!("{PROBLEM_TYPE_PREFIX}{}", code.to_ascii_lowercase().replace('_', "-"))
+}
+
+/// The same for every occurrence of a code: `USER_ALREADY_EXISTS` is
+/// "User already exists"
+fn problem_title(code: &str) -> String {
+    let words = code.to_ascii_lowercase().replace('_', " ");
+    let mut chars = words.chars();
+    chars.next().map(|first| first.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+}
+
+/// `error` as an RFC 9457 problem document. The error code stays as the
+/// `code` extension member, failed input rules become `errors`, and other
+/// `details` entries are extension members of their own. `status` and
+/// `instance` are added by `error_envelope_middleware`, which knows them.
+pub fn problem_document(error: &ApiError) -> Value {
+    let mut problem = Map::new();
+    problem.insert("type".to_string(), json!(problem_type(&error.code)));
+    problem.insert("title".to_string(), json!(problem_title(&error.code)));
+    problem.insert("detail".to_string(), json!(error.message));
+    problem.insert("code".to_string(), json!(error.code));
+    if let Some(violations) = &error.violations {
+        problem.insert("errors".to_string(), Value::Array(violations.to_problem_errors()));
+    }
+    for (key, value) in error.details.iter().flatten() {
+        // The envelope's forms of the violations, already listed in `errors`
+        let restated = error.violations.is_some() && matches!(key.as_str(), "fields" | "validation_errors");
+        if !restated && !RESERVED_MEMBERS.contains(&key.as_str()) {
+            problem.insert(key.clone(), value.clone());
+        }
+    }
+    Value::Object(problem)
+}
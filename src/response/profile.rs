@@ -0,0 +1,335 @@
+use serde::ser::{Error as _, SerializeStruct};
+use serde::{Serialize, Serializer};
+use serde_json::{json, Map, Value};
+use std::sync::OnceLock;
+
+use super::{problem_document, ApiResponse};
+
+static PROFILE: OnceLock<SerializationProfile> = OnceLock::new();
+
+/// Key naming of the envelope and of every payload object in it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FieldCase {
+    /// Keys as the Rust types name them
+    #[default]
+    Snake,
+    Camel,
+}
+
+/// Top-level layout of every response body
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnvelopeLayout {
+    /// `{"success", "data", "error", "meta"}`
+    #[default]
+    Standard,
+    /// `{"status": "ok"|"error", "result", "errors": [..], "meta"}`
+    StatusResult,
+}
+
+/// Whether empty envelope, `meta` and `error` fields are written as `null`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NullFields {
+    /// Every field is always present, so clients can rely on a fixed schema
+    #[default]
+    Stable,
+    /// `null` fields are omitted; payload data is written as is
+    Compact,
+}
+
+/// How error responses are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorFormat {
+    /// In the envelope of `EnvelopeLayout`, like every other response
+    #[default]
+    Envelope,
+    /// RFC 9457 problem documents (`application/problem+json`); successful
+    /// responses keep the envelope
+    Problem,
+}
+
+/// How `ApiResponse` is written on the wire, chosen once at startup from
+/// `RESPONSE_FIELD_CASE`, `RESPONSE_ENVELOPE`, `RESPONSE_NULL_FIELDS` and
+/// `RESPONSE_ERROR_FORMAT`.
+///
+/// Snake case in the standard envelope serializes straight from the types.
+/// Other profiles go through a `serde_json::Value`, which costs an extra
+/// allocation per response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SerializationProfile {
+    pub field_case: FieldCase,
+    pub envelope: EnvelopeLayout,
+    pub null_fields: NullFields,
+    pub errors: ErrorFormat,
+}
+
+impl SerializationProfile {
+    /// Unknown names fall back to the defaults
+    pub fn from_names(field_case: &str, envelope: &str, null_fields: &str, errors: &str) -> Self {
+        Self {
+            field_case: match field_case.to_ascii_lowercase().as_str() {
+                "camel" | "camelcase" => FieldCase::Camel,
+                _ => FieldCase::Snake,
+            },
+            envelope: match envelope.to_ascii_lowercase().as_str() {
+                "status_result" | "status-result" => EnvelopeLayout::StatusResult,
+                _ => EnvelopeLayout::Standard,
+            },
+            null_fields: match null_fields.to_ascii_lowercase().as_str() {
+                "compact" => NullFields::Compact,
+                _ => NullFields::Stable,
+            },
+            errors: match errors.to_ascii_lowercase().as_str() {
+                "problem" | "problem+json" | "rfc9457" => ErrorFormat::Problem,
+                _ => ErrorFormat::Envelope,
+            },
+        }
+    }
+
+    fn serializes_directly<T>(&self, response: &ApiResponse<T>) -> bool {
+        self.field_case == FieldCase::Snake && self.envelope == EnvelopeLayout::Standard && !self.renders_problem(response)
+    }
+
+    fn renders_problem<T>(&self, response: &ApiResponse<T>) -> bool {
+        self.errors == ErrorFormat::Problem && !response.success
+    }
+
+    /// The response body under this profile
+    pub fn render<T: Serialize>(&self, response: &ApiResponse<T>) -> Result<Value, serde_json::Error> {
+        if let (true, Some(error)) = (self.renders_problem(response), &response.error) {
+            let problem = problem_document(error);
+            return Ok(match self.field_case {
+                FieldCase::Snake => problem,
+                FieldCase::Camel => camel_case_keys(problem),
+            });
+        }
+        let data = serde_json::to_value(&response.data)?;
+        let error = serde_json::to_value(&response.error)?;
+        let meta = serde_json::to_value(&response.meta)?;
+        let mut body = match self.envelope {
+            EnvelopeLayout::Standard => json!({
+                "success": response.success,
+                "data": data,
+                "error": error,
+                "meta": meta,
+            }),
+            EnvelopeLayout::StatusResult => json!({
+                "status": if response.success { "ok" } else { "error" },
+                "result": data,
+                "errors": if error.is_null() { json!([]) } else { json!([error]) },
+                "meta": meta,
+            }),
+        };
+        if self.null_fields == NullFields::Compact {
+            drop_nulls(&mut body);
+            for nested in ["meta", "error"] {
+                if let Some(nested) = body.get_mut(nested) {
+                    drop_nulls(nested);
+                }
+            }
+            if let Some(errors) = body.get_mut("errors").and_then(Value::as_array_mut) {
+                errors.iter_mut().for_each(drop_nulls);
+            }
+        }
+        Ok(match self.field_case {
+            FieldCase::Snake => body,
+            FieldCase::Camel => camel_case_keys(body),
+        })
+    }
+
+    /// A payload on its own, without the envelope, keyed in this profile's case
+    pub fn render_data<T: Serialize>(&self, data: &T) -> Result<Value, serde_json::Error> {
+        let data = serde_json::to_value(data)?;
+        Ok(match self.field_case {
+            FieldCase::Snake => data,
+            FieldCase::Camel => camel_case_keys(data),
+        })
+    }
+}
+
+/// Install the process-wide profile; only the first call has an effect
+pub fn set_profile(profile: SerializationProfile) -> bool {
+    PROFILE.set(profile).is_ok()
+}
+
+pub fn profile() -> SerializationProfile {
+    PROFILE.get().copied().unwrap_or_default()
+}
+
+impl<T: Serialize> Serialize for ApiResponse<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let profile = profile();
+        if !profile.serializes_directly(self) {
+            return profile.render(self).map_err(S::Error::custom)?.serialize(serializer);
+        }
+
+        let mut state = serializer.serialize_struct("ApiResponse", 4)?;
+        state.serialize_field("success", &self.success)?;
+        serialize_option(&mut state, "data", &self.data)?;
+        serialize_option(&mut state, "error", &self.error)?;
+        serialize_option(&mut state, "meta", &self.meta)?;
+        state.end()
+    }
+}
+
+fn serialize_option<S: SerializeStruct, T: Serialize>(
+    state: &mut S,
+    key: &'static str,
+    value: &Option<T>,
+) -> Result<(), S::Error> {
+    if omit_none(value) {
+        state.skip_field(key)
+    } else {
+        state.serialize_field(key, value)
+    }
+}
+
+/// `skip_serializing_if` for optional envelope fields: `None` is omitted
+/// under `NullFields::Compact`
+pub fn omit_none<T>(value: &Option<T>) -> bool {
+    value.is_none() && profile().null_fields == NullFields::Compact
+}
+
+fn drop_nulls(value: &mut Value) {
+    if let Value::Object(object) = value {
+        object.retain(|_, value| !value.is_null());
+    }
+}
+
+/// Maps keyed by data rather than by the schema, such as user metadata and
+/// the per-field map of validation and conflict errors (`details.fields`),
+/// whose keys name request fields: their own key is converted, the keys
+/// inside them are left as they are
+const FREE_FORM_KEYS: &[&str] = &["metadata", "fields"];
+
+fn camel_case_keys(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = if FREE_FORM_KEYS.contains(&key.as_str()) { value } else { camel_case_keys(value) };
+                    (camel_case(&key), value)
+                })
+                .collect::<Map<_, _>>(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(camel_case_keys).collect()),
+        other => other,
+    }
+}
+
+fn camel_case(key: &str) -> String {
+    let mut parts = key.split('_').filter(|part| !part.is_empty());
+    let mut out = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            out.extend(first.to_uppercase());
+            out.push_str(chars.as_str());
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::{ApiError, Meta, ResponseError, ResponseSuccess};
+
+    #[test]
+    fn default_profile_keeps_the_standard_snake_case_body() {
+        let response = ApiResponse::success_with_meta(json!({ "created_at": 1 }), Meta::new(1, 10, 25));
+        let direct = serde_json::to_value(&response).unwrap();
+        assert_eq!(direct, SerializationProfile::default().render(&response).unwrap());
+        assert_eq!(direct["meta"]["total_pages"], 3);
+        assert_eq!(direct["data"]["created_at"], 1);
+    }
+
+    #[test]
+    fn camel_case_status_result_profile() {
+        let profile = SerializationProfile::from_names("camel", "status_result", "stable", "envelope");
+
+        let success = ApiResponse::success_with_meta(
+            json!([{ "created_at": 1, "metadata": { "billing_plan": "pro", "ui": { "dark_mode": true } } }]),
+            Meta::new(1, 10, 25),
+        );
+        assert_eq!(
+            profile.render(&success).unwrap(),
+            json!({
+                "status": "ok",
+                "result": [{ "createdAt": 1, "metadata": { "billing_plan": "pro", "ui": { "dark_mode": true } } }],
+                "errors": [],
+                "meta": { "page": 1, "limit": 10, "total": 25, "totalPages": 3, "totalExact": null, "links": null },
+            })
+        );
+
+        let failure = ApiResponse::error(ApiError::new("NOT_FOUND", "User not found"));
+        let body = profile.render(&failure).unwrap();
+        assert_eq!(body["status"], "error");
+        assert_eq!(body["errors"][0]["code"], "NOT_FOUND");
+        assert!(body["result"].is_null());
+
+        // Clients match these keys against the fields they sent
+        let violations = crate::infrastructure::FieldErrors::field("display_name", "Too long");
+        let invalid = ApiResponse::error(ApiError::with_details("VALIDATION_ERROR", "Request validation failed", violations.to_details()));
+        let details = &profile.render(&invalid).unwrap()["errors"][0]["details"];
+        assert_eq!(details["fields"]["display_name"], json!(["Too long"]));
+        assert_eq!(details["validationErrors"], json!(["display_name: Too long"]));
+    }
+
+    #[test]
+    fn compact_profile_omits_nulls_and_stable_keeps_every_field() {
+        let compact = SerializationProfile::from_names("snake", "standard", "compact", "envelope");
+        let stable = SerializationProfile::default();
+
+        let success = ApiResponse::success(json!({ "nickname": null }));
+        assert_eq!(compact.render(&success).unwrap(), json!({ "success": true, "data": { "nickname": null } }));
+        assert_eq!(
+            stable.render(&success).unwrap(),
+            json!({ "success": true, "data": { "nickname": null }, "error": null, "meta": null })
+        );
+
+        let listed = ApiResponse::success_with_meta(json!([]), Meta::new(1, 10, 0));
+        assert_eq!(
+            compact.render(&listed).unwrap()["meta"],
+            json!({ "page": 1, "limit": 10, "total": 0, "total_pages": 0 })
+        );
+
+        let failure = ApiResponse::error(ApiError::new("NOT_FOUND", "User not found"));
+        assert_eq!(
+            compact.render(&failure).unwrap(),
+            json!({ "success": false, "error": { "code": "NOT_FOUND", "message": "User not found" } })
+        );
+        let status_result = SerializationProfile::from_names("snake", "status_result", "compact", "envelope");
+        assert_eq!(
+            status_result.render(&failure).unwrap(),
+            json!({ "status": "error", "errors": [{ "code": "NOT_FOUND", "message": "User not found" }] })
+        );
+    }
+
+    #[test]
+    fn problem_profile_writes_errors_as_problem_documents() {
+        let problem = SerializationProfile::from_names("camel", "standard", "stable", "problem");
+
+        let success = ApiResponse::success(json!({ "created_at": 1 }));
+        assert_eq!(problem.render(&success).unwrap()["data"]["createdAt"], 1);
+
+        let violations = crate::infrastructure::FieldErrors::field("email", "Invalid email format");
+        let mut details = violations.to_details();
+        details.insert("correlation_id".to_string(), json!("req-1"));
+        let failure = ApiResponse::error(
+            ApiError::with_details("VALIDATION_ERROR", "Request validation failed", details).with_violations(&violations),
+        );
+        assert_eq!(
+            problem.render(&failure).unwrap(),
+            json!({
+                "type": "/problems/validation-error",
+                "title": "Validation error",
+                "detail": "Request validation failed",
+                "code": "VALIDATION_ERROR",
+                "errors": [{ "pointer": "#/email", "field": "email", "code": "invalid", "detail": "Invalid email format" }],
+                "correlationId": "req-1",
+            })
+        );
+        assert!(!problem.serializes_directly(&failure));
+    }
+}
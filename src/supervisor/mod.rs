@@ -0,0 +1,190 @@
+use std::collections::VecDeque;
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpSocket};
+use tokio::process::{Child, Command};
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+use crate::config::Config;
+use crate::infrastructure::ALERT_TARGET;
+
+/// Set on every process the supervisor starts, holding its index
+pub const WORKER_INDEX_ENV: &str = "SUPERVISOR_WORKER_INDEX";
+
+const RESTART_DELAY: Duration = Duration::from_millis(500);
+const ORPHAN_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Index of this server process when it runs under the supervisor
+pub fn worker_index() -> Option<usize> {
+    std::env::var(WORKER_INDEX_ENV).ok()?.parse().ok()
+}
+
+/// Whether this process should supervise workers instead of serving itself
+pub fn supervises(config: &Config) -> bool {
+    config.server_processes > 1 && worker_index().is_none()
+}
+
+pub struct SupervisorOptions {
+    pub processes: usize,
+    /// More crashes than this within a minute stop the supervisor
+    pub max_restarts_per_minute: usize,
+}
+
+impl SupervisorOptions {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            processes: config.server_processes,
+            max_restarts_per_minute: config.supervisor_max_restarts_per_minute,
+        }
+    }
+}
+
+/// Crash restarts allowed within a sliding window
+struct RestartBudget {
+    window: Duration,
+    max: usize,
+    restarts: VecDeque<Instant>,
+}
+
+impl RestartBudget {
+    fn new(window: Duration, max: usize) -> Self {
+        Self { window, max, restarts: VecDeque::new() }
+    }
+
+    fn allow(&mut self, now: Instant) -> bool {
+        while self.restarts.front().is_some_and(|at| now.duration_since(*at) >= self.window) {
+            self.restarts.pop_front();
+        }
+        if self.restarts.len() >= self.max {
+            return false;
+        }
+        self.restarts.push_back(now);
+        true
+    }
+}
+
+/// Run `options.processes` copies of this binary, with the same arguments,
+/// sharing the server port through SO_REUSEPORT, and restart any that exit.
+///
+/// Workers are killed when the supervisor gets SIGINT or SIGTERM. A worker
+/// whose supervisor dies exits on its own (see `exit_when_orphaned`). Each
+/// worker keeps its own in-process state: rate limits, caches and admin
+/// endpoints are per process.
+pub async fn run(options: SupervisorOptions) -> io::Result<()> {
+    let exe = std::env::current_exe()?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let spawn = |index: usize| -> io::Result<Child> {
+        Command::new(&exe).args(&args).env(WORKER_INDEX_ENV, index.to_string()).kill_on_drop(true).spawn()
+    };
+
+    let mut workers = JoinSet::new();
+    for index in 0..options.processes {
+        let mut child = spawn(index)?;
+        workers.spawn(async move { (index, child.wait().await) });
+    }
+    tracing::info!(processes = options.processes, "Supervisor started server processes");
+
+    let mut budget = RestartBudget::new(Duration::from_secs(60), options.max_restarts_per_minute);
+    loop {
+        tokio::select! {
+            Some(exited) = workers.join_next() => {
+                let Ok((index, status)) = exited else { continue };
+                tracing::warn!(worker = index, status = ?status, "Server process exited");
+                if !budget.allow(Instant::now()) {
+                    tracing::error!(
+                        target: ALERT_TARGET,
+                        alert = "supervisor_crash_loop",
+                        worker = index,
+                        "Server processes keep exiting; supervisor giving up"
+                    );
+                    // Dropping the set kills the remaining workers
+                    return Err(io::Error::other("server processes exceeded the restart budget"));
+                }
+                tokio::time::sleep(RESTART_DELAY).await;
+                let mut child = spawn(index)?;
+                workers.spawn(async move { (index, child.wait().await) });
+                tracing::info!(worker = index, "Server process restarted");
+            }
+            _ = shutdown_signal() => {
+                tracing::info!("Supervisor shutting down server processes");
+                workers.shutdown().await;
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// SIGTERM or Ctrl-C
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate = signal(SignalKind::terminate()).expect("failed to install SIGTERM handler");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Bind `addr` with SO_REUSEPORT so every worker accepts on the same port and
+/// the kernel spreads connections across them
+pub async fn bind_shared(addr: &str) -> io::Result<TcpListener> {
+    let addr: SocketAddr = tokio::net::lookup_host(addr)
+        .await?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("cannot resolve {addr}")))?;
+    let socket = if addr.is_ipv4() { TcpSocket::new_v4()? } else { TcpSocket::new_v6()? };
+    socket.set_reuseaddr(true)?;
+    #[cfg(unix)]
+    socket.set_reuseport(true)?;
+    socket.bind(addr)?;
+    socket.listen(1024)
+}
+
+/// Exit once the supervisor is gone, so a killed supervisor doesn't leave
+/// workers holding the port
+pub fn exit_when_orphaned() {
+    #[cfg(unix)]
+    {
+        let supervisor = std::os::unix::process::parent_id();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(ORPHAN_CHECK_INTERVAL);
+            loop {
+                ticker.tick().await;
+                if std::os::unix::process::parent_id() != supervisor {
+                    tracing::warn!("Supervisor exited; stopping server process");
+                    std::process::exit(1);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restart_budget_slides_over_the_window() {
+        let mut budget = RestartBudget::new(Duration::from_secs(60), 2);
+        let start = Instant::now();
+        assert!(budget.allow(start));
+        assert!(budget.allow(start + Duration::from_secs(10)));
+        assert!(!budget.allow(start + Duration::from_secs(20)));
+        assert!(budget.allow(start + Duration::from_secs(61)));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn workers_can_bind_the_same_port() {
+        let first = bind_shared("127.0.0.1:0").await.unwrap();
+        let addr = first.local_addr().unwrap();
+        let second = bind_shared(&addr.to_string()).await.unwrap();
+        assert_eq!(second.local_addr().unwrap(), addr);
+    }
+}
@@ -0,0 +1,288 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::container::user_repository;
+use crate::domain::user::entities::User;
+use crate::domain::user::repository::{InMemoryUserRepository, RedbUserRepository, UserRepository};
+use crate::infrastructure::{from_versioned_str, to_versioned_value};
+use crate::pagination::PageRequest;
+
+const PAGE_SIZE: u32 = 500;
+
+/// Outcome of `users import`
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// New users and users updated by id
+    pub imported: u64,
+    /// Users whose email already belongs to a different id
+    pub conflicts: u64,
+}
+
+/// Outcome of `migrate-data`
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct MigrationSummary {
+    pub from: String,
+    pub to: String,
+    /// Users in the source when the migration started
+    pub source_users: u64,
+    /// Users written to the destination
+    pub migrated: u64,
+    /// Users whose email already belongs to a different id in the destination
+    pub conflicts: u64,
+    /// XOR of the SHA-256 of each migrated user as read from the source.
+    /// Independent of order, so the same users give the same checksum in
+    /// every store.
+    pub checksum: String,
+    /// Whether reading the migrated users back from the destination produced
+    /// the same checksum
+    pub verified: bool,
+}
+
+/// A user store named by `migrate-data --from/--to`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Backend {
+    /// `redb` (the file at `REDB_PATH`) or `redb:<path>`
+    Redb(PathBuf),
+    /// `snapshot:<file>`: a JSON-lines export, held in an in-memory store
+    /// while migrating
+    Snapshot(PathBuf),
+}
+
+impl Backend {
+    pub fn parse(spec: &str, config: &Config) -> io::Result<Self> {
+        let (kind, path) = match spec.split_once(':') {
+            Some((kind, path)) => (kind, Some(path)),
+            None => (spec, None),
+        };
+        match (kind, path) {
+            ("redb", None) => Ok(Self::Redb(PathBuf::from(&config.redb_path))),
+            ("redb", Some(path)) => Ok(Self::Redb(PathBuf::from(path))),
+            ("snapshot", Some(path)) => Ok(Self::Snapshot(PathBuf::from(path))),
+            ("snapshot", None) => Err(invalid_input("the snapshot backend needs a file: snapshot:<file>")),
+            _ => Err(invalid_input(&format!("unknown backend `{spec}`; expected redb[:<path>] or snapshot:<file>"))),
+        }
+    }
+
+    /// A snapshot that doesn't exist yet starts out empty, so it can be a
+    /// destination
+    async fn open(&self) -> io::Result<Arc<dyn UserRepository>> {
+        match self {
+            Self::Redb(path) => Ok(Arc::new(RedbUserRepository::new(path))),
+            Self::Snapshot(path) => {
+                let repository = InMemoryUserRepository::new();
+                if path.exists() {
+                    import(&repository, BufReader::new(std::fs::File::open(path)?)).await?;
+                }
+                Ok(Arc::new(repository))
+            }
+        }
+    }
+
+    /// Write a snapshot back to its file; redb commits as it goes
+    async fn persist(&self, repository: &dyn UserRepository) -> io::Result<()> {
+        let Self::Snapshot(path) = self else { return Ok(()) };
+        // Write beside the snapshot and rename, so a failed write keeps the old file
+        let partial = path.with_extension("partial");
+        let mut out = BufWriter::new(std::fs::File::create(&partial)?);
+        export(repository, &mut out).await?;
+        out.into_inner().map_err(io::IntoInnerError::into_error)?.sync_all()?;
+        std::fs::rename(partial, path)
+    }
+}
+
+impl std::fmt::Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Redb(path) => write!(f, "redb:{}", path.display()),
+            Self::Snapshot(path) => write!(f, "snapshot:{}", path.display()),
+        }
+    }
+}
+
+fn invalid_input(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.to_string())
+}
+
+/// `users export <file>`: write every user of the configured repository
+/// (`USER_REPOSITORY`) as JSON lines, password hashes included. Returns the
+/// number of users written.
+pub async fn export_users(config: &Config, path: &Path) -> io::Result<u64> {
+    let repository = user_repository(config);
+    let mut out = BufWriter::new(std::fs::File::create(path)?);
+    let written = export(repository.as_ref(), &mut out).await?;
+    out.flush()?;
+    Ok(written)
+}
+
+/// `users import <file>`: save each JSON line of an export into the
+/// configured repository. Importing the same file twice is harmless.
+pub async fn import_users(config: &Config, path: &Path) -> io::Result<ImportSummary> {
+    let repository = user_repository(config);
+    import(repository.as_ref(), BufReader::new(std::fs::File::open(path)?)).await
+}
+
+/// `migrate-data --from <backend> --to <backend>`: copy every user from one
+/// store into another, then read them back from the destination and compare
+/// checksums. Users are matched by id as in `users import`, so rerunning an
+/// interrupted migration is safe.
+pub async fn migrate_data(from: &Backend, to: &Backend) -> io::Result<MigrationSummary> {
+    if from == to {
+        return Err(invalid_input("source and destination are the same store"));
+    }
+    let source = from.open().await?;
+    let destination = to.open().await?;
+    let summary = migrate(source.as_ref(), destination.as_ref()).await?;
+    to.persist(destination.as_ref()).await?;
+    Ok(MigrationSummary { from: from.to_string(), to: to.to_string(), ..summary })
+}
+
+async fn migrate(source: &dyn UserRepository, destination: &dyn UserRepository) -> io::Result<MigrationSummary> {
+    let source_users = source.count().await.map_err(io::Error::other)?;
+    let mut checksum = [0u8; 32];
+    let mut migrated_ids: Vec<Uuid> = Vec::new();
+    let mut conflicts = 0;
+
+    let mut request = PageRequest::new(1, PAGE_SIZE);
+    loop {
+        let users = source.list(request.page, request.limit).await.map_err(io::Error::other)?;
+        for user in &users {
+            let owner = destination.find_by_email(&user.email).await.map_err(io::Error::other)?;
+            if owner.is_some_and(|owner| owner.id != user.id) {
+                tracing::warn!(user_id = %user.id, "Email already belongs to another user; not migrated");
+                conflicts += 1;
+                continue;
+            }
+            fold_digest(&mut checksum, user)?;
+            destination.save(user.clone()).await.map_err(io::Error::other)?;
+            migrated_ids.push(user.id);
+        }
+        tracing::info!(migrated = migrated_ids.len(), conflicts, total = source_users, "Migrating users");
+        if request.is_last(users.len()) {
+            break;
+        }
+        request = request.next();
+    }
+
+    let mut readback = [0u8; 32];
+    for id in &migrated_ids {
+        match destination.find_by_id(*id).await.map_err(io::Error::other)? {
+            Some(user) => fold_digest(&mut readback, &user)?,
+            None => {
+                tracing::error!(user_id = %id, "Migrated user missing from the destination");
+                break;
+            }
+        }
+    }
+    let verified = readback == checksum;
+
+    Ok(MigrationSummary {
+        source_users,
+        migrated: migrated_ids.len() as u64,
+        conflicts,
+        checksum: hex::encode(checksum),
+        verified,
+        ..MigrationSummary::default()
+    })
+}
+
+fn fold_digest(checksum: &mut [u8; 32], user: &User) -> io::Result<()> {
+    let digest = Sha256::digest(serde_json::to_vec(user)?);
+    checksum.iter_mut().zip(digest).for_each(|(byte, digest)| *byte ^= digest);
+    Ok(())
+}
+
+pub(crate) async fn export(repository: &dyn UserRepository, out: &mut impl Write) -> io::Result<u64> {
+    let mut written = 0;
+    let mut request = PageRequest::new(1, PAGE_SIZE);
+    loop {
+        let users = repository.list(request.page, request.limit).await.map_err(io::Error::other)?;
+        for user in &users {
+            let record = to_versioned_value(user.as_ref()).map_err(io::Error::other)?;
+            serde_json::to_writer(&mut *out, &record)?;
+            out.write_all(b"\n")?;
+        }
+        written += users.len() as u64;
+        if request.is_last(users.len()) {
+            break;
+        }
+        request = request.next();
+    }
+    Ok(written)
+}
+
+pub(crate) async fn import(repository: &dyn UserRepository, input: impl BufRead) -> io::Result<ImportSummary> {
+    let mut summary = ImportSummary::default();
+    for (index, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let user: User = from_versioned_str(&line)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {err}", index + 1)))?;
+
+        let owner = repository.find_by_email(&user.email).await.map_err(io::Error::other)?;
+        if owner.is_some_and(|owner| owner.id != user.id) {
+            tracing::warn!(user_id = %user.id, "Email already belongs to another user; not imported");
+            summary.conflicts += 1;
+            continue;
+        }
+        repository.save(Arc::new(user)).await.map_err(io::Error::other)?;
+        summary.imported += 1;
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::user::repository::InMemoryUserRepository;
+
+    #[tokio::test]
+    async fn export_then_import_copies_users_and_reports_conflicts() {
+        let users: Vec<User> = (0..3)
+            .map(|i| User::new(format!("user{i}@example.com"), "hash".to_string()))
+            .collect();
+        let source = InMemoryUserRepository::new_with_users(users);
+        let mut exported = Vec::new();
+        assert_eq!(export(&source, &mut exported).await.unwrap(), 3);
+
+        let target = InMemoryUserRepository::new_with_users(vec![User::new(
+            "user0@example.com".to_string(),
+            "other".to_string(),
+        )]);
+        let summary = import(&target, exported.as_slice()).await.unwrap();
+        assert_eq!(summary, ImportSummary { imported: 2, conflicts: 1 });
+        assert_eq!(import(&target, exported.as_slice()).await.unwrap().imported, 2);
+        assert_eq!(target.count().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn migration_copies_users_and_verifies_the_destination() {
+        let users: Vec<User> = (0..3)
+            .map(|i| User::new(format!("user{i}@example.com"), "hash".to_string()))
+            .collect();
+        let source = InMemoryUserRepository::new_with_users(users);
+        let destination = InMemoryUserRepository::new_with_users(vec![User::new(
+            "user1@example.com".to_string(),
+            "other".to_string(),
+        )]);
+
+        let summary = migrate(&source, &destination).await.unwrap();
+        assert_eq!((summary.source_users, summary.migrated, summary.conflicts), (3, 2, 1));
+        assert!(summary.verified);
+        assert_eq!(summary.checksum.len(), 64);
+        assert_eq!(destination.count().await.unwrap(), 3);
+
+        let config = Config::from_env();
+        assert_eq!(
+            Backend::parse("snapshot:users.jsonl", &config).unwrap(),
+            Backend::Snapshot(PathBuf::from("users.jsonl"))
+        );
+        assert!(Backend::parse("postgres", &config).is_err());
+    }
+}